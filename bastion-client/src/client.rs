@@ -0,0 +1,1022 @@
+use reqwest::{Method, RequestBuilder, Response, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::error::{ClientError, Result};
+use crate::events::EventStream;
+use crate::types::*;
+
+/// Client for one bastion-agent
+///
+/// Every call authenticates with the bearer token given at construction; the
+/// token needs the scope listed on the route in the agent's OpenAPI document.
+/// Account routes (identity, messaging, posts, ...) go to the agent's active
+/// account unless an account is selected with [`BastionClient::account`].
+#[derive(Clone)]
+pub struct BastionClient {
+    http: reqwest::Client,
+    base_url: Url,
+    token: String,
+    account: Option<String>,
+}
+
+impl BastionClient {
+    /// Client for the agent at `base_url` (e.g. `http://127.0.0.1:8745`)
+    pub fn new(base_url: &str, token: impl Into<String>) -> Result<Self> {
+        Self::with_http_client(reqwest::Client::new(), base_url, token)
+    }
+
+    /// Like [`BastionClient::new`], reusing a configured `reqwest::Client`
+    pub fn with_http_client(
+        http: reqwest::Client,
+        base_url: &str,
+        token: impl Into<String>,
+    ) -> Result<Self> {
+        let base_url = Url::parse(base_url).map_err(|e| ClientError::InvalidUrl(e.to_string()))?;
+        if base_url.cannot_be_a_base() {
+            return Err(ClientError::InvalidUrl(base_url.to_string()));
+        }
+        Ok(Self {
+            http,
+            base_url,
+            token: token.into(),
+            account: None,
+        })
+    }
+
+    /// A client whose account routes go to `/api/accounts/{account_id}/...`
+    pub fn account(&self, account_id: impl Into<String>) -> Self {
+        Self {
+            account: Some(account_id.into()),
+            ..self.clone()
+        }
+    }
+
+    /// URL of `segments` below the base URL; each segment is percent-encoded.
+    /// Account routes are moved below the selected account, if any.
+    fn url(&self, segments: &[&str]) -> Url {
+        let mut url = self.base_url.clone();
+        {
+            let mut path = url.path_segments_mut().expect("checked in constructor");
+            path.pop_if_empty();
+            match (&self.account, segments) {
+                (Some(account), ["api", rest @ ..]) if !is_agent_route(rest) => {
+                    path.extend(["api", "accounts", account.as_str()])
+                        .extend(rest);
+                }
+                _ => {
+                    path.extend(segments);
+                }
+            }
+        }
+        url
+    }
+
+    fn request(&self, method: Method, segments: &[&str]) -> RequestBuilder {
+        self.http
+            .request(method, self.url(segments))
+            .bearer_auth(&self.token)
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let response = check(request.send().await?).await?;
+        let bytes = response.bytes().await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<T> {
+        self.send(self.request(Method::GET, segments)).await
+    }
+
+    async fn get_query<T: DeserializeOwned, Q: Serialize>(
+        &self,
+        segments: &[&str],
+        query: &Q,
+    ) -> Result<T> {
+        self.send(self.request(Method::GET, segments).query(query))
+            .await
+    }
+
+    async fn post<T: DeserializeOwned, B: Serialize>(
+        &self,
+        segments: &[&str],
+        body: &B,
+    ) -> Result<T> {
+        self.send(self.request(Method::POST, segments).json(body))
+            .await
+    }
+
+    async fn post_empty<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<T> {
+        self.send(self.request(Method::POST, segments)).await
+    }
+
+    async fn put<T: DeserializeOwned, B: Serialize>(
+        &self,
+        segments: &[&str],
+        body: &B,
+    ) -> Result<T> {
+        self.send(self.request(Method::PUT, segments).json(body))
+            .await
+    }
+
+    async fn delete<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<T> {
+        self.send(self.request(Method::DELETE, segments)).await
+    }
+
+    // ============================================================
+    // Accounts
+    // ============================================================
+
+    /// `GET /api/accounts`
+    pub async fn list_accounts(&self) -> Result<Vec<AccountInfo>> {
+        self.get(&["api", "accounts"]).await
+    }
+
+    /// `POST /api/accounts` — the new account is unlocked and becomes the
+    /// active one
+    pub async fn create_account(
+        &self,
+        request: &CreateIdentityRequest,
+    ) -> Result<CreateAccountResponse> {
+        self.post(&["api", "accounts"], request).await
+    }
+
+    /// `DELETE /api/accounts/:accountId`
+    pub async fn remove_account(&self, account_id: &str, delete_data: bool) -> Result<()> {
+        self.send(
+            self.request(Method::DELETE, &["api", "accounts", account_id])
+                .query(&[("deleteData", delete_data)]),
+        )
+        .await
+    }
+
+    // ============================================================
+    // Identity
+    // ============================================================
+
+    /// `GET /api/identity` — `None` until an identity is created
+    pub async fn get_identity(&self) -> Result<Option<IdentityInfo>> {
+        self.get(&["api", "identity"]).await
+    }
+
+    /// `GET /api/identity/status`
+    pub async fn get_identity_status(&self) -> Result<IdentityStatus> {
+        self.get(&["api", "identity", "status"]).await
+    }
+
+    /// `POST /api/identity/unlock`
+    pub async fn unlock_identity(&self, passphrase: &str) -> Result<IdentityInfo> {
+        self.post(
+            &["api", "identity", "unlock"],
+            &json!({ "passphrase": passphrase }),
+        )
+        .await
+    }
+
+    /// `POST /api/identity/lock`
+    pub async fn lock_identity(&self) -> Result<()> {
+        self.post_empty(&["api", "identity", "lock"]).await
+    }
+
+    /// `PUT /api/identity/display-name`
+    pub async fn update_display_name(&self, display_name: &str) -> Result<()> {
+        self.put(
+            &["api", "identity", "display-name"],
+            &json!({ "displayName": display_name }),
+        )
+        .await
+    }
+
+    /// `PUT /api/identity/bio`
+    pub async fn update_bio(&self, bio: Option<&str>) -> Result<()> {
+        self.put(&["api", "identity", "bio"], &json!({ "bio": bio }))
+            .await
+    }
+
+    /// `GET /api/identity/avatar`
+    pub async fn get_avatar(&self) -> Result<Avatar> {
+        self.get_avatar_at(&["api", "identity", "avatar"]).await
+    }
+
+    /// `PUT /api/identity/avatar` — returns the image's content hash
+    pub async fn set_avatar(&self, mime_type: &str, bytes: Vec<u8>) -> Result<String> {
+        let request = self
+            .request(Method::PUT, &["api", "identity", "avatar"])
+            .header(reqwest::header::CONTENT_TYPE, mime_type)
+            .body(bytes);
+        let response: SetAvatarResponse = self.send(request).await?;
+        Ok(response.avatar_hash)
+    }
+
+    /// `DELETE /api/identity/avatar`
+    pub async fn clear_avatar(&self) -> Result<()> {
+        self.delete(&["api", "identity", "avatar"]).await
+    }
+
+    async fn get_avatar_at(&self, segments: &[&str]) -> Result<Avatar> {
+        let response = check(self.request(Method::GET, segments).send().await?).await?;
+        let mime_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        let data = response.bytes().await?.to_vec();
+        Ok(Avatar { mime_type, data })
+    }
+
+    // ============================================================
+    // Network
+    // ============================================================
+
+    /// `POST /api/network/start`
+    pub async fn start_network(&self) -> Result<()> {
+        self.post_empty(&["api", "network", "start"]).await
+    }
+
+    /// `POST /api/network/stop`
+    pub async fn stop_network(&self) -> Result<()> {
+        self.post_empty(&["api", "network", "stop"]).await
+    }
+
+    /// `POST /api/network/restart`
+    pub async fn restart_network(&self) -> Result<()> {
+        self.post_empty(&["api", "network", "restart"]).await
+    }
+
+    /// `GET /api/network/status`
+    pub async fn get_network_status(&self) -> Result<NetworkStatus> {
+        self.get(&["api", "network", "status"]).await
+    }
+
+    /// `GET /api/network/peers`
+    pub async fn get_connected_peers(&self) -> Result<Vec<PeerInfo>> {
+        self.get(&["api", "network", "peers"]).await
+    }
+
+    /// `GET /api/network/presence`
+    pub async fn get_presence(&self) -> Result<Vec<PeerPresence>> {
+        self.get(&["api", "network", "presence"]).await
+    }
+
+    /// `PUT /api/network/presence`
+    pub async fn set_presence(
+        &self,
+        status: PresenceStatus,
+        status_text: Option<&str>,
+    ) -> Result<()> {
+        self.put(
+            &["api", "network", "presence"],
+            &json!({ "status": status, "statusText": status_text }),
+        )
+        .await
+    }
+
+    /// `POST /api/network/connect`
+    pub async fn connect_to_peer(&self, multiaddr: &str) -> Result<()> {
+        self.post(
+            &["api", "network", "connect"],
+            &json!({ "multiaddr": multiaddr }),
+        )
+        .await
+    }
+
+    /// `POST /api/network/relay`
+    pub async fn add_relay_server(&self, multiaddr: &str) -> Result<()> {
+        self.post(
+            &["api", "network", "relay"],
+            &json!({ "multiaddr": multiaddr }),
+        )
+        .await
+    }
+
+    /// `POST /api/network/relays/public`
+    pub async fn connect_to_public_relays(&self) -> Result<()> {
+        self.post_empty(&["api", "network", "relays", "public"])
+            .await
+    }
+
+    /// `GET /api/network/addresses`
+    pub async fn get_listening_addresses(&self) -> Result<Vec<String>> {
+        self.get(&["api", "network", "addresses"]).await
+    }
+
+    /// `GET /api/network/shareable`
+    pub async fn get_shareable_addresses(&self) -> Result<Vec<String>> {
+        self.get(&["api", "network", "shareable"]).await
+    }
+
+    /// `GET /api/network/contact-string`
+    pub async fn get_shareable_contact_string(&self) -> Result<String> {
+        self.get(&["api", "network", "contact-string"]).await
+    }
+
+    // ============================================================
+    // Messaging
+    // ============================================================
+
+    /// `POST /api/messages/send`
+    pub async fn send_message(&self, request: &SendMessageRequest) -> Result<SendMessageResult> {
+        self.post(&["api", "messages", "send"], request).await
+    }
+
+    /// `GET /api/messages/:peerId`
+    pub async fn get_messages(
+        &self,
+        peer_id: &str,
+        query: &MessagesQuery,
+    ) -> Result<Vec<MessageInfo>> {
+        self.get_query(&["api", "messages", peer_id], query).await
+    }
+
+    /// `GET /api/conversations/:peerId/messages` — pages back from the newest
+    /// message; pass `next_cursor` to get the page before
+    pub async fn get_message_page(
+        &self,
+        peer_id: &str,
+        query: &MessagePageQuery,
+    ) -> Result<MessagePage> {
+        self.get_query(&["api", "conversations", peer_id, "messages"], query)
+            .await
+    }
+
+    /// `GET /api/conversations/:peerId/export` — the whole conversation with
+    /// the signature status of every message
+    pub async fn export_conversation(
+        &self,
+        peer_id: &str,
+        format: ExportFormat,
+    ) -> Result<ConversationExport> {
+        let segments = ["api", "conversations", peer_id, "export"];
+        let request = self
+            .request(Method::GET, &segments)
+            .query(&[("format", format)]);
+        let response = check(request.send().await?).await?;
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let mime_type = header(reqwest::header::CONTENT_TYPE).unwrap_or_default();
+        let file_name = header(reqwest::header::CONTENT_DISPOSITION).and_then(|v| {
+            v.split_once("filename=")
+                .map(|(_, name)| name.trim_matches('"').to_string())
+        });
+        let content = response.text().await?;
+        Ok(ConversationExport {
+            file_name,
+            mime_type,
+            content,
+        })
+    }
+
+    /// `GET /api/conversations`
+    pub async fn get_conversations(&self) -> Result<Vec<ConversationInfo>> {
+        self.get(&["api", "conversations"]).await
+    }
+
+    /// `POST /api/conversations/:peerId/read` — returns how many messages were marked
+    pub async fn mark_conversation_read(&self, peer_id: &str) -> Result<i64> {
+        self.post_empty(&["api", "conversations", peer_id, "read"])
+            .await
+    }
+
+    /// `POST /api/conversations/:peerId/typing`
+    pub async fn set_typing(&self, peer_id: &str, typing: bool) -> Result<()> {
+        self.post(
+            &["api", "conversations", peer_id, "typing"],
+            &json!({ "typing": typing }),
+        )
+        .await
+    }
+
+    /// `GET /api/conversations/:peerId/retention`
+    pub async fn get_conversation_retention(&self, peer_id: &str) -> Result<RetentionSetting> {
+        self.get(&["api", "conversations", peer_id, "retention"])
+            .await
+    }
+
+    /// `PUT /api/conversations/:peerId/retention` — `None` keeps messages forever
+    pub async fn set_conversation_retention(
+        &self,
+        peer_id: &str,
+        retention_secs: Option<u64>,
+    ) -> Result<()> {
+        self.put(
+            &["api", "conversations", peer_id, "retention"],
+            &RetentionSetting { retention_secs },
+        )
+        .await
+    }
+
+    /// `GET /api/messages/unread`
+    pub async fn get_total_unread_count(&self) -> Result<i64> {
+        self.get(&["api", "messages", "unread"]).await
+    }
+
+    /// `PUT /api/messages/:peerId/:messageId`
+    pub async fn edit_message(&self, peer_id: &str, message_id: &str, content: &str) -> Result<()> {
+        self.put(
+            &["api", "messages", peer_id, message_id],
+            &json!({ "content": content }),
+        )
+        .await
+    }
+
+    /// `DELETE /api/messages/:peerId/:messageId` — deletes for everyone
+    pub async fn delete_message(&self, peer_id: &str, message_id: &str) -> Result<()> {
+        self.delete(&["api", "messages", peer_id, message_id]).await
+    }
+
+    /// `PUT /api/messages/:peerId/:messageId/reaction`
+    pub async fn react_to_message(
+        &self,
+        peer_id: &str,
+        message_id: &str,
+        reaction: &str,
+    ) -> Result<()> {
+        self.put(
+            &["api", "messages", peer_id, message_id, "reaction"],
+            &json!({ "reaction": reaction }),
+        )
+        .await
+    }
+
+    /// `DELETE /api/messages/:peerId/:messageId/reaction`
+    pub async fn remove_reaction(&self, peer_id: &str, message_id: &str) -> Result<()> {
+        self.delete(&["api", "messages", peer_id, message_id, "reaction"])
+            .await
+    }
+
+    /// `POST /api/messages/:peerId/attachments`
+    pub async fn send_attachment(
+        &self,
+        peer_id: &str,
+        file_name: &str,
+        mime_type: &str,
+        bytes: Vec<u8>,
+    ) -> Result<SendMessageResult> {
+        let request = self
+            .request(Method::POST, &["api", "messages", peer_id, "attachments"])
+            .query(&[("fileName", file_name)])
+            .header(reqwest::header::CONTENT_TYPE, mime_type)
+            .body(bytes);
+        self.send(request).await
+    }
+
+    /// `GET /api/messages/:peerId/:messageId/attachment` — 404 until the file
+    /// has been fetched from the sender
+    pub async fn get_attachment(&self, peer_id: &str, message_id: &str) -> Result<Attachment> {
+        let segments = ["api", "messages", peer_id, message_id, "attachment"];
+        let response = check(self.request(Method::GET, &segments).send().await?).await?;
+        let mime_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        let data = response.bytes().await?.to_vec();
+        Ok(Attachment { mime_type, data })
+    }
+
+    // ============================================================
+    // Contacts
+    // ============================================================
+
+    /// `GET /api/contacts`
+    pub async fn get_active_contacts(&self) -> Result<Vec<ContactInfo>> {
+        self.get(&["api", "contacts"]).await
+    }
+
+    /// `POST /api/contacts` — returns the contact's row id
+    pub async fn add_contact(&self, request: &AddContactRequest) -> Result<i64> {
+        self.post(&["api", "contacts"], request).await
+    }
+
+    /// `POST /api/contacts/from-string` — returns the contact's peer id
+    pub async fn add_contact_from_string(&self, contact_string: &str) -> Result<String> {
+        self.post(
+            &["api", "contacts", "from-string"],
+            &json!({ "contactString": contact_string }),
+        )
+        .await
+    }
+
+    /// `DELETE /api/contacts/:peerId`
+    pub async fn remove_contact(&self, peer_id: &str) -> Result<bool> {
+        self.delete(&["api", "contacts", peer_id]).await
+    }
+
+    /// `POST /api/contacts/:peerId/block`
+    pub async fn block_contact(&self, peer_id: &str) -> Result<bool> {
+        self.post_empty(&["api", "contacts", peer_id, "block"])
+            .await
+    }
+
+    /// `GET /api/contacts/:peerId/avatar` — once fetched from the contact
+    pub async fn get_contact_avatar(&self, peer_id: &str) -> Result<Avatar> {
+        self.get_avatar_at(&["api", "contacts", peer_id, "avatar"])
+            .await
+    }
+
+    /// `GET /api/contacts/:peerId/safety-number`
+    pub async fn get_safety_number(&self, peer_id: &str) -> Result<SafetyNumber> {
+        self.get(&["api", "contacts", peer_id, "safety-number"])
+            .await
+    }
+
+    /// `POST /api/contacts/:peerId/verify` — marks the contact verified after
+    /// the numbers were compared out of band
+    pub async fn mark_contact_verified(&self, peer_id: &str) -> Result<bool> {
+        self.post_empty(&["api", "contacts", peer_id, "verify"])
+            .await
+    }
+
+    /// `POST /api/contacts/:peerId/verify` — false if `scanned` (the contact's
+    /// QR payload) doesn't match our safety number
+    pub async fn verify_contact_safety_number(&self, peer_id: &str, scanned: &str) -> Result<bool> {
+        self.post(
+            &["api", "contacts", peer_id, "verify"],
+            &json!({ "scanned": scanned }),
+        )
+        .await
+    }
+
+    /// `DELETE /api/contacts/:peerId/verify`
+    pub async fn clear_contact_verification(&self, peer_id: &str) -> Result<()> {
+        self.delete(&["api", "contacts", peer_id, "verify"]).await
+    }
+
+    // ============================================================
+    // Permissions
+    // ============================================================
+
+    /// `POST /api/permissions/grant`
+    pub async fn grant_permission(&self, request: &GrantPermissionRequest) -> Result<GrantResult> {
+        self.post(&["api", "permissions", "grant"], request).await
+    }
+
+    /// `POST /api/permissions/grant-all`
+    pub async fn grant_all_permissions(&self, peer_id: &str) -> Result<Vec<GrantResult>> {
+        self.post(
+            &["api", "permissions", "grant-all"],
+            &json!({ "peerId": peer_id }),
+        )
+        .await
+    }
+
+    /// `DELETE /api/permissions/:grantId`
+    pub async fn revoke_permission(&self, grant_id: &str) -> Result<bool> {
+        self.delete(&["api", "permissions", grant_id]).await
+    }
+
+    /// `GET /api/permissions/chat-peers`
+    pub async fn get_chat_peers(&self) -> Result<Vec<String>> {
+        self.get(&["api", "permissions", "chat-peers"]).await
+    }
+
+    // ============================================================
+    // Communities and boards
+    // ============================================================
+
+    /// `GET /api/communities`
+    pub async fn get_communities(&self) -> Result<Vec<CommunityInfo>> {
+        self.get(&["api", "communities"]).await
+    }
+
+    /// `POST /api/communities/join`
+    pub async fn join_community(&self, relay_address: &str) -> Result<()> {
+        self.post(
+            &["api", "communities", "join"],
+            &json!({ "relayAddress": relay_address }),
+        )
+        .await
+    }
+
+    /// `DELETE /api/communities/:relayPeerId`
+    pub async fn leave_community(&self, relay_peer_id: &str) -> Result<()> {
+        self.delete(&["api", "communities", relay_peer_id]).await
+    }
+
+    /// `GET /api/boards/:relayPeerId`
+    pub async fn get_boards(&self, relay_peer_id: &str) -> Result<Vec<BoardInfo>> {
+        self.get(&["api", "boards", relay_peer_id]).await
+    }
+
+    /// `GET /api/boards/:relayPeerId/:boardId/posts`
+    pub async fn get_board_posts(
+        &self,
+        relay_peer_id: &str,
+        board_id: &str,
+        query: &BoardPostsQuery,
+    ) -> Result<Vec<BoardPostInfo>> {
+        self.get_query(&["api", "boards", relay_peer_id, board_id, "posts"], query)
+            .await
+    }
+
+    /// `GET /api/boards/:relayPeerId/:boardId/threads`
+    pub async fn get_board_threads(
+        &self,
+        relay_peer_id: &str,
+        board_id: &str,
+        query: &BoardPostsQuery,
+    ) -> Result<Vec<BoardPostInfo>> {
+        self.get_query(
+            &["api", "boards", relay_peer_id, board_id, "threads"],
+            query,
+        )
+        .await
+    }
+
+    /// `GET /api/boards/:relayPeerId/threads/:postId`
+    pub async fn get_board_thread(
+        &self,
+        relay_peer_id: &str,
+        root_post_id: &str,
+    ) -> Result<Vec<BoardPostInfo>> {
+        self.get(&["api", "boards", relay_peer_id, "threads", root_post_id])
+            .await
+    }
+
+    /// `POST /api/boards/:relayPeerId/threads/:postId/sync`
+    pub async fn sync_board_thread(&self, relay_peer_id: &str, root_post_id: &str) -> Result<()> {
+        self.post_empty(&[
+            "api",
+            "boards",
+            relay_peer_id,
+            "threads",
+            root_post_id,
+            "sync",
+        ])
+        .await
+    }
+
+    /// `POST /api/boards/:relayPeerId/:boardId/posts`
+    pub async fn submit_board_post(
+        &self,
+        relay_peer_id: &str,
+        board_id: &str,
+        request: &SubmitBoardPostRequest,
+    ) -> Result<()> {
+        self.post(
+            &["api", "boards", relay_peer_id, board_id, "posts"],
+            request,
+        )
+        .await
+    }
+
+    /// `DELETE /api/boards/posts/:postId`
+    pub async fn delete_board_post(&self, relay_peer_id: &str, post_id: &str) -> Result<()> {
+        let request = self
+            .request(Method::DELETE, &["api", "boards", "posts", post_id])
+            .json(&json!({ "relayPeerId": relay_peer_id }));
+        self.send(request).await
+    }
+
+    /// `POST /api/boards/:relayPeerId/:boardId/sync`
+    pub async fn sync_board(&self, relay_peer_id: &str, board_id: &str) -> Result<()> {
+        self.post_empty(&["api", "boards", relay_peer_id, board_id, "sync"])
+            .await
+    }
+
+    /// `POST /api/boards/:relayPeerId/subscribe`
+    pub async fn subscribe_boards(&self, relay_peer_id: &str, board_ids: &[String]) -> Result<()> {
+        self.post(
+            &["api", "boards", relay_peer_id, "subscribe"],
+            &json!({ "boardIds": board_ids }),
+        )
+        .await
+    }
+
+    /// `POST /api/boards/:relayPeerId/unsubscribe` — all boards if `board_ids` is empty
+    pub async fn unsubscribe_boards(
+        &self,
+        relay_peer_id: &str,
+        board_ids: &[String],
+    ) -> Result<()> {
+        self.post(
+            &["api", "boards", relay_peer_id, "unsubscribe"],
+            &json!({ "boardIds": board_ids }),
+        )
+        .await
+    }
+
+    // ============================================================
+    // Posts, media and likes
+    // ============================================================
+
+    /// `GET /api/posts` — the local identity's posts
+    pub async fn get_my_posts(&self, query: &PostsQuery) -> Result<Vec<PostInfo>> {
+        self.get_query(&["api", "posts"], query).await
+    }
+
+    /// `POST /api/posts`
+    pub async fn create_post(&self, request: &CreatePostRequest) -> Result<CreatePostResult> {
+        self.post(&["api", "posts"], request).await
+    }
+
+    /// `GET /api/posts/:postId`
+    pub async fn get_post(&self, post_id: &str) -> Result<PostInfo> {
+        self.get(&["api", "posts", post_id]).await
+    }
+
+    /// `PUT /api/posts/:postId`
+    pub async fn update_post(&self, post_id: &str, content_text: Option<&str>) -> Result<()> {
+        self.put(
+            &["api", "posts", post_id],
+            &json!({ "contentText": content_text }),
+        )
+        .await
+    }
+
+    /// `DELETE /api/posts/:postId`
+    pub async fn delete_post(&self, post_id: &str) -> Result<()> {
+        self.delete(&["api", "posts", post_id]).await
+    }
+
+    /// `GET /api/posts/:postId/media`
+    pub async fn get_post_media(&self, post_id: &str) -> Result<Vec<PostMediaInfo>> {
+        self.get(&["api", "posts", post_id, "media"]).await
+    }
+
+    /// `POST /api/posts/:postId/media` — attach `bytes` to a post
+    pub async fn upload_post_media(
+        &self,
+        post_id: &str,
+        upload: &MediaUpload,
+        bytes: Vec<u8>,
+    ) -> Result<PostMediaInfo> {
+        let request = self
+            .request(Method::POST, &["api", "posts", post_id, "media"])
+            .query(upload)
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .body(bytes);
+        self.send(request).await
+    }
+
+    /// `GET /api/media/:mediaHash` — raw bytes of stored media
+    pub async fn get_media(&self, media_hash: &str) -> Result<Vec<u8>> {
+        let response = self
+            .request(Method::GET, &["api", "media", media_hash])
+            .send()
+            .await?;
+        Ok(check(response).await?.bytes().await?.to_vec())
+    }
+
+    /// `GET /api/posts/:postId/likes`
+    pub async fn get_post_likes(&self, post_id: &str) -> Result<LikeSummaryInfo> {
+        self.get(&["api", "posts", post_id, "likes"]).await
+    }
+
+    /// `POST /api/posts/:postId/likes`
+    pub async fn like_post(&self, post_id: &str) -> Result<LikeSummaryInfo> {
+        self.post_empty(&["api", "posts", post_id, "likes"]).await
+    }
+
+    /// `DELETE /api/posts/:postId/likes`
+    pub async fn unlike_post(&self, post_id: &str) -> Result<LikeSummaryInfo> {
+        self.delete(&["api", "posts", post_id, "likes"]).await
+    }
+
+    /// `POST /api/posts/likes/batch`
+    pub async fn get_posts_likes_batch(&self, post_ids: &[String]) -> Result<Vec<LikeSummaryInfo>> {
+        self.post(
+            &["api", "posts", "likes", "batch"],
+            &json!({ "postIds": post_ids }),
+        )
+        .await
+    }
+
+    /// `GET /api/posts/liked` — ids of posts the local identity has liked
+    pub async fn get_my_liked_posts(&self) -> Result<Vec<String>> {
+        self.get(&["api", "posts", "liked"]).await
+    }
+
+    // ============================================================
+    // Feed and walls
+    // ============================================================
+
+    /// `GET /api/feed` — follow `next_cursor` for older pages
+    pub async fn get_feed(&self, query: &FeedQuery) -> Result<FeedPage> {
+        self.get_query(&["api", "feed"], query).await
+    }
+
+    /// `GET /api/wall/:peerId`
+    pub async fn get_wall(&self, peer_id: &str, query: &WallQuery) -> Result<Vec<FeedItemInfo>> {
+        self.get_query(&["api", "wall", peer_id], query).await
+    }
+
+    /// `POST /api/sync/feed` — pull recent posts from connected peers
+    pub async fn sync_feed(&self, limit: Option<u32>) -> Result<()> {
+        self.post(&["api", "sync", "feed"], &json!({ "limit": limit }))
+            .await
+    }
+
+    // ============================================================
+    // Search
+    // ============================================================
+
+    /// `GET /api/search` — ranked hits across messages, posts, board posts
+    /// and contacts
+    pub async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchHitInfo>> {
+        self.get_query(&["api", "search"], query).await
+    }
+
+    // ============================================================
+    // Scheduled posts and messages
+    // ============================================================
+
+    /// `GET /api/scheduled` — soonest first; only pending items unless
+    /// `include_finished`
+    pub async fn get_scheduled_items(
+        &self,
+        include_finished: bool,
+    ) -> Result<Vec<ScheduledItemInfo>> {
+        self.get_query(
+            &["api", "scheduled"],
+            &[("includeFinished", include_finished)],
+        )
+        .await
+    }
+
+    /// `POST /api/scheduled/posts` — the post is signed when it is published
+    pub async fn schedule_post(&self, request: &SchedulePostRequest) -> Result<ScheduledItemInfo> {
+        self.post(&["api", "scheduled", "posts"], request).await
+    }
+
+    /// `POST /api/scheduled/messages` — the message is signed when it is sent
+    pub async fn schedule_message(
+        &self,
+        request: &ScheduleMessageRequest,
+    ) -> Result<ScheduledItemInfo> {
+        self.post(&["api", "scheduled", "messages"], request).await
+    }
+
+    /// `DELETE /api/scheduled/:scheduleId` — cancel a pending item
+    pub async fn cancel_scheduled_item(&self, schedule_id: &str) -> Result<()> {
+        self.delete(&["api", "scheduled", schedule_id]).await
+    }
+
+    // ============================================================
+    // Relay authentication
+    // ============================================================
+
+    /// `POST /api/auth/verify-agent` — solve a relay's Isnad CAPTCHA. The
+    /// agent keeps the token fresh from then on.
+    pub async fn verify_agent(
+        &self,
+        auth_url: &str,
+        relay_peer_id: Option<&str>,
+    ) -> Result<AuthenticateResponse> {
+        self.post(
+            &["api", "auth", "verify-agent"],
+            &json!({ "authUrl": auth_url, "relayPeerId": relay_peer_id }),
+        )
+        .await
+    }
+
+    /// `GET /api/auth/relays`
+    pub async fn list_relay_tokens(&self) -> Result<Vec<RelayTokenInfo>> {
+        self.get(&["api", "auth", "relays"]).await
+    }
+
+    /// `DELETE /api/auth/relays`
+    pub async fn forget_relay(&self, auth_url: &str) -> Result<()> {
+        self.send(
+            self.request(Method::DELETE, &["api", "auth", "relays"])
+                .query(&[("authUrl", auth_url)]),
+        )
+        .await
+    }
+
+    // ============================================================
+    // API tokens
+    // ============================================================
+
+    /// `GET /api/tokens`
+    pub async fn list_tokens(&self) -> Result<Vec<TokenInfo>> {
+        self.get(&["api", "tokens"]).await
+    }
+
+    /// `POST /api/tokens` — the secret is only returned here
+    pub async fn create_token(&self, name: &str, scopes: &[Scope]) -> Result<ApiToken> {
+        self.post(
+            &["api", "tokens"],
+            &json!({ "name": name, "scopes": scopes }),
+        )
+        .await
+    }
+
+    /// `DELETE /api/tokens/:name`
+    pub async fn revoke_token(&self, name: &str) -> Result<()> {
+        self.delete(&["api", "tokens", name]).await
+    }
+
+    // ============================================================
+    // Webhooks
+    // ============================================================
+
+    /// `GET /api/webhooks`
+    pub async fn list_webhooks(&self) -> Result<Vec<WebhookInfo>> {
+        self.get(&["api", "webhooks"]).await
+    }
+
+    /// `POST /api/webhooks` — the signing secret is only returned here
+    pub async fn create_webhook(&self, request: &CreateWebhookRequest) -> Result<CreatedWebhook> {
+        self.post(&["api", "webhooks"], request).await
+    }
+
+    /// `PUT /api/webhooks/:webhookId` — enable or disable
+    pub async fn set_webhook_enabled(&self, webhook_id: &str, enabled: bool) -> Result<()> {
+        self.put(
+            &["api", "webhooks", webhook_id],
+            &json!({ "enabled": enabled }),
+        )
+        .await
+    }
+
+    /// `DELETE /api/webhooks/:webhookId`
+    pub async fn delete_webhook(&self, webhook_id: &str) -> Result<()> {
+        self.delete(&["api", "webhooks", webhook_id]).await
+    }
+
+    /// `GET /api/webhooks/:webhookId/deliveries`
+    pub async fn list_deliveries(
+        &self,
+        webhook_id: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<DeliveryInfo>> {
+        self.get_query(
+            &["api", "webhooks", webhook_id, "deliveries"],
+            &[("limit", limit)],
+        )
+        .await
+    }
+
+    /// `POST /api/webhooks/:webhookId/deliveries/:deliveryId/retry`
+    pub async fn retry_delivery(&self, webhook_id: &str, delivery_id: i64) -> Result<()> {
+        let delivery_id = delivery_id.to_string();
+        self.post_empty(&[
+            "api",
+            "webhooks",
+            webhook_id,
+            "deliveries",
+            &delivery_id,
+            "retry",
+        ])
+        .await
+    }
+
+    // ============================================================
+    // Events and schema
+    // ============================================================
+
+    /// `GET /api/events` — network events as they happen. Pass the id of the
+    /// last event seen to replay what was missed since.
+    pub async fn events(&self, last_event_id: Option<i64>) -> Result<EventStream> {
+        let mut request = self
+            .request(Method::GET, &["api", "events"])
+            .header(reqwest::header::ACCEPT, "text/event-stream");
+        if let Some(id) = last_event_id {
+            request = request.header("Last-Event-ID", id.to_string());
+        }
+        let response = check(request.send().await?).await?;
+        Ok(EventStream::new(response))
+    }
+
+    /// `GET /api/openapi.json` — the agent's OpenAPI document
+    pub async fn openapi(&self) -> Result<serde_json::Value> {
+        self.get(&["api", "openapi.json"]).await
+    }
+}
+
+/// Routes served by the agent itself rather than by an account
+fn is_agent_route(rest: &[&str]) -> bool {
+    matches!(
+        rest.first(),
+        Some(&"accounts" | &"tokens" | &"openapi.json")
+    )
+}
+
+/// Turn error statuses into `ClientError::Api`
+async fn check(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().await?;
+    Err(match serde_json::from_str::<ErrorResponse>(&body) {
+        Ok(error) => ClientError::Api {
+            status: status.as_u16(),
+            error,
+        },
+        Err(_) => ClientError::UnexpectedResponse {
+            status: status.as_u16(),
+            body,
+        },
+    })
+}
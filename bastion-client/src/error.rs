@@ -0,0 +1,58 @@
+use harbor_lib::error::{ErrorCode, ErrorResponse};
+use thiserror::Error;
+
+/// Errors returned by [`crate::BastionClient`]
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The request never got a response (connection, TLS, timeout, ...)
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The agent answered with an error status
+    #[error("API error ({status}): {}", describe(.error))]
+    Api { status: u16, error: ErrorResponse },
+
+    /// The agent answered with an error status and a body that is not an `ErrorResponse`
+    #[error("Unexpected response ({status}): {body}")]
+    UnexpectedResponse { status: u16, body: String },
+
+    /// The response body did not match the expected type
+    #[error("Failed to decode response: {0}")]
+    Decode(#[from] serde_json::Error),
+
+    /// The event stream broke or carried malformed data
+    #[error("Event stream error: {0}")]
+    EventStream(String),
+
+    /// The base URL cannot carry API paths
+    #[error("Invalid base URL: {0}")]
+    InvalidUrl(String),
+}
+
+impl ClientError {
+    /// HTTP status of an API error, if this is one
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ClientError::Api { status, .. } | ClientError::UnexpectedResponse { status, .. } => {
+                Some(*status)
+            }
+            ClientError::Http(e) => e.status().map(|s| s.as_u16()),
+            _ => None,
+        }
+    }
+
+    /// Machine-readable code of an API error, if the agent sent one
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            ClientError::Api { error, .. } => Some(error.code),
+            _ => None,
+        }
+    }
+}
+
+/// The specific failure when the agent sent one, else the generic message
+fn describe(error: &ErrorResponse) -> &str {
+    error.details.as_deref().unwrap_or(&error.message)
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
@@ -0,0 +1,105 @@
+//! Typed view of the agent's `/api/events` Server-Sent Events stream
+
+use eventsource_stream::Eventsource;
+use futures::stream::{BoxStream, Stream, StreamExt};
+use harbor_lib::p2p::NetworkEvent;
+use serde::Deserialize;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::error::{ClientError, Result};
+
+/// One item of the event stream
+#[derive(Debug, Clone)]
+pub enum AgentEvent {
+    /// A network event with its journal id (pass it to
+    /// [`crate::BastionClient::events`] to resume after it)
+    Network { id: i64, event: NetworkEvent },
+    /// Events after `missed_after` were evicted from the agent's journal
+    /// before they could be replayed; the stream resumes at `resumed_at`
+    Gap { missed_after: i64, resumed_at: i64 },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GapPayload {
+    missed_after: i64,
+    resumed_at: i64,
+}
+
+/// Stream of [`AgentEvent`]s. Ends when the agent closes the connection.
+pub struct EventStream {
+    inner: BoxStream<'static, Result<AgentEvent>>,
+    last_event_id: Option<i64>,
+}
+
+impl EventStream {
+    pub(crate) fn new(response: reqwest::Response) -> Self {
+        let inner = response
+            .bytes_stream()
+            .eventsource()
+            .filter_map(|item| async move {
+                match item {
+                    Ok(event) => parse(event),
+                    Err(e) => Some(Err(ClientError::EventStream(e.to_string()))),
+                }
+            })
+            .boxed();
+        Self {
+            inner,
+            last_event_id: None,
+        }
+    }
+
+    /// Id of the last network event yielded, for resuming after a disconnect
+    pub fn last_event_id(&self) -> Option<i64> {
+        self.last_event_id
+    }
+}
+
+fn parse(event: eventsource_stream::Event) -> Option<Result<AgentEvent>> {
+    match event.event.as_str() {
+        "gap" => Some(
+            serde_json::from_str::<GapPayload>(&event.data)
+                .map(|gap| AgentEvent::Gap {
+                    missed_after: gap.missed_after,
+                    resumed_at: gap.resumed_at,
+                })
+                .map_err(ClientError::from),
+        ),
+        // Unnamed events carry network events
+        "" | "message" => {
+            if event.data.is_empty() {
+                return None;
+            }
+            let id = match event.id.parse::<i64>() {
+                Ok(id) => id,
+                Err(_) => {
+                    return Some(Err(ClientError::EventStream(format!(
+                        "event without a numeric id: {:?}",
+                        event.id
+                    ))))
+                }
+            };
+            Some(
+                serde_json::from_str::<NetworkEvent>(&event.data)
+                    .map(|event| AgentEvent::Network { id, event })
+                    .map_err(ClientError::from),
+            )
+        }
+        // Event kinds added by newer agents
+        _ => None,
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<AgentEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let polled = self.inner.poll_next_unpin(cx);
+        if let Poll::Ready(Some(Ok(AgentEvent::Network { id, .. }))) = &polled {
+            self.last_event_id = Some(*id);
+        }
+        polled
+    }
+}
@@ -0,0 +1,32 @@
+//! Typed Rust client for the bastion-agent HTTP API
+//!
+//! ```no_run
+//! use bastion_client::{AgentEvent, BastionClient};
+//! use futures::StreamExt;
+//!
+//! # async fn run() -> bastion_client::Result<()> {
+//! let client = BastionClient::new("http://127.0.0.1:8745", "bst_...")?;
+//! let status = client.get_identity_status().await?;
+//! println!("unlocked: {}", status.is_unlocked);
+//!
+//! let mut events = client.events(None).await?;
+//! while let Some(event) = events.next().await {
+//!     if let AgentEvent::Network { id, event } = event? {
+//!         println!("#{id}: {event:?}");
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Each method documents the route it calls; the agent serves the matching
+//! OpenAPI document at `/api/openapi.json`.
+
+mod client;
+mod error;
+mod events;
+pub mod types;
+
+pub use client::BastionClient;
+pub use error::{ClientError, Result};
+pub use events::{AgentEvent, EventStream};
@@ -0,0 +1,662 @@
+//! Request and response bodies of the bastion-agent API
+//!
+//! These mirror the agent's handler types field for field (camelCase on the
+//! wire); the contract tests run them against a real agent. Types the agent
+//! takes straight from `harbor_lib` (identity info, peers, presence, network
+//! stats, safety numbers and events) are re-exported instead of copied.
+
+use serde::{Deserialize, Serialize};
+
+pub use harbor_lib::error::{ErrorCode, ErrorResponse};
+pub use harbor_lib::models::IdentityInfo;
+pub use harbor_lib::p2p::{
+    ConnectionStatus, NatStatus, NetworkEvent, NetworkStats, PeerInfo, PeerPresence, PresenceStatus,
+};
+pub use harbor_lib::services::SafetyNumber;
+
+// ============================================================
+// Accounts
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    /// Account id, as passed to [`crate::BastionClient::account`]
+    pub id: String,
+    pub peer_id: String,
+    pub display_name: String,
+    pub bio: Option<String>,
+    pub avatar_hash: Option<String>,
+    pub created_at: i64,
+    pub last_accessed_at: Option<i64>,
+    /// Served by requests that don't select an account
+    pub is_active: bool,
+    pub is_unlocked: bool,
+    pub network_running: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAccountResponse {
+    pub account: AccountInfo,
+    pub identity: IdentityInfo,
+}
+
+// ============================================================
+// Identity
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentityStatus {
+    pub has_identity: bool,
+    pub is_unlocked: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateIdentityRequest {
+    pub display_name: String,
+    pub passphrase: String,
+    pub bio: Option<String>,
+    pub passphrase_hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetAvatarResponse {
+    pub avatar_hash: String,
+}
+
+/// An avatar image as served by the agent
+#[derive(Debug, Clone)]
+pub struct Avatar {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+// ============================================================
+// Network
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStatus {
+    pub running: bool,
+    #[serde(default)]
+    pub stats: Option<NetworkStats>,
+}
+
+// ============================================================
+// Messaging
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendMessageRequest {
+    pub peer_id: String,
+    pub content: String,
+    pub content_type: Option<String>,
+    pub reply_to: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendMessageResult {
+    pub message_id: String,
+    pub conversation_id: String,
+    pub sent_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageInfo {
+    pub message_id: String,
+    pub conversation_id: String,
+    pub sender_peer_id: String,
+    pub recipient_peer_id: String,
+    pub content: String,
+    pub content_type: String,
+    pub reply_to_message_id: Option<String>,
+    #[serde(default)]
+    pub lamport_clock: i64,
+    pub sent_at: i64,
+    pub delivered_at: Option<i64>,
+    pub read_at: Option<i64>,
+    pub status: String,
+    pub is_outgoing: bool,
+    pub edited_at: Option<i64>,
+    pub deleted_at: Option<i64>,
+    pub reactions: Vec<ReactionInfo>,
+    pub attachment: Option<AttachmentMeta>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReactionInfo {
+    pub peer_id: String,
+    pub reaction: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentMeta {
+    pub file_name: String,
+    pub mime_type: String,
+    pub size: u64,
+    /// Whether the file has been fetched from the sender
+    pub downloaded: bool,
+}
+
+/// An attachment file as served by the agent
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationInfo {
+    pub conversation_id: String,
+    pub peer_id: String,
+    pub last_message_at: i64,
+    pub unread_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagePage {
+    /// Oldest first
+    pub messages: Vec<MessageInfo>,
+    /// Pass as `cursor` to fetch the page before this one; absent on the first page
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagePageQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+/// Format of a conversation export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Json,
+    Markdown,
+    Mbox,
+}
+
+/// A conversation export as served by the agent
+#[derive(Debug, Clone)]
+pub struct ConversationExport {
+    /// File name suggested by the agent
+    pub file_name: Option<String>,
+    pub mime_type: String,
+    pub content: String,
+}
+
+/// How long a conversation's messages are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionSetting {
+    /// Seconds messages are kept: 3600, 86400, 604800 or 2592000; `None`
+    /// keeps them forever
+    pub retention_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagesQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<i64>,
+}
+
+// ============================================================
+// Contacts
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactInfo {
+    pub id: i64,
+    pub peer_id: String,
+    pub display_name: String,
+    pub avatar_hash: Option<String>,
+    pub bio: Option<String>,
+    pub is_blocked: bool,
+    pub trust_level: i32,
+    pub last_seen_at: Option<i64>,
+    pub added_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddContactRequest {
+    pub peer_id: String,
+    pub public_key: Vec<u8>,
+    pub x25519_public: Vec<u8>,
+    pub display_name: String,
+    pub avatar_hash: Option<String>,
+    pub bio: Option<String>,
+}
+
+// ============================================================
+// Permissions
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrantPermissionRequest {
+    pub peer_id: String,
+    /// `chat`, `wall_read` or `call`
+    pub capability: String,
+    pub expires_in_seconds: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrantResult {
+    pub grant_id: String,
+    pub capability: String,
+    pub subject_peer_id: String,
+    pub issued_at: i64,
+    pub expires_at: Option<i64>,
+}
+
+// ============================================================
+// Communities and boards
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommunityInfo {
+    pub relay_peer_id: String,
+    pub relay_address: String,
+    pub community_name: Option<String>,
+    pub joined_at: i64,
+    pub last_sync_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardInfo {
+    pub board_id: String,
+    pub relay_peer_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardPostInfo {
+    pub post_id: String,
+    pub board_id: String,
+    pub relay_peer_id: String,
+    pub author_peer_id: String,
+    pub author_display_name: Option<String>,
+    pub content_type: String,
+    pub content_text: Option<String>,
+    pub lamport_clock: i64,
+    pub created_at: i64,
+    pub reply_to_post_id: Option<String>,
+    pub thread_root_post_id: Option<String>,
+    pub reply_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitBoardPostRequest {
+    pub content_text: String,
+    pub reply_to_post_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardPostsQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<i64>,
+}
+
+// ============================================================
+// Posts, media and likes
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostInfo {
+    pub post_id: String,
+    pub author_peer_id: String,
+    pub content_type: String,
+    pub content_text: Option<String>,
+    pub visibility: String,
+    pub lamport_clock: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub deleted_at: Option<i64>,
+    pub is_local: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostMediaInfo {
+    pub id: i64,
+    pub post_id: String,
+    pub media_hash: String,
+    pub media_type: String,
+    pub mime_type: String,
+    pub file_name: String,
+    pub file_size: i64,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub duration_seconds: Option<i32>,
+    pub sort_order: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LikeSummaryInfo {
+    pub post_id: String,
+    pub total_likes: i64,
+    pub user_has_liked: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePostRequest {
+    pub content_type: Option<String>,
+    pub content_text: Option<String>,
+    /// `public` or `contacts` (the default)
+    pub visibility: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePostResult {
+    pub post_id: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostsQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before_timestamp: Option<i64>,
+}
+
+/// Metadata sent alongside uploaded media bytes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaUpload {
+    pub file_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<i32>,
+}
+
+// ============================================================
+// Feed and walls
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedItemInfo {
+    pub post_id: String,
+    pub author_peer_id: String,
+    pub author_display_name: Option<String>,
+    pub content_type: String,
+    pub content_text: Option<String>,
+    pub visibility: String,
+    pub lamport_clock: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub is_local: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedPage {
+    pub items: Vec<FeedItemInfo>,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WallQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before_timestamp: Option<i64>,
+}
+
+// ============================================================
+// Search
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHitInfo {
+    /// message, wall_post, feed_post, board_post or contact
+    pub kind: String,
+    /// Message, post or board post ID, or the contact's peer ID
+    pub item_id: String,
+    pub peer_id: String,
+    #[serde(default)]
+    pub relay_peer_id: Option<String>,
+    #[serde(default)]
+    pub board_id: Option<String>,
+    pub created_at: i64,
+    /// Matched terms are wrapped in `<mark>` tags
+    pub snippet: String,
+    /// BM25 rank; lower is better
+    pub rank: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchQuery {
+    pub q: String,
+    /// Comma-separated kinds to search; all if absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kinds: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+}
+
+// ============================================================
+// Scheduled posts and messages
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledItemInfo {
+    pub schedule_id: String,
+    /// `post` or `message`
+    pub kind: String,
+    #[serde(default)]
+    pub recipient_peer_id: Option<String>,
+    pub content_type: String,
+    /// Cleared once the item is published or canceled
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub visibility: Option<String>,
+    #[serde(default)]
+    pub reply_to_message_id: Option<String>,
+    /// Unix seconds
+    pub publish_at: i64,
+    /// `pending`, `publishing`, `published`, `failed` or `canceled`
+    pub status: String,
+    pub created_at: i64,
+    #[serde(default)]
+    pub published_at: Option<i64>,
+    /// ID of the post or message once published
+    #[serde(default)]
+    pub published_id: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulePostRequest {
+    pub content_type: Option<String>,
+    pub content_text: Option<String>,
+    /// `public` or `contacts` (the default)
+    pub visibility: Option<String>,
+    /// Unix seconds; within a year from now
+    pub publish_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleMessageRequest {
+    pub peer_id: String,
+    pub content: String,
+    pub content_type: Option<String>,
+    pub reply_to: Option<String>,
+    /// Unix seconds; within a year from now
+    pub publish_at: i64,
+}
+
+// ============================================================
+// Relay authentication
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticateResponse {
+    pub token: String,
+    pub expires_in_seconds: i64,
+    pub peer_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayTokenInfo {
+    pub auth_url: String,
+    pub relay_peer_id: Option<String>,
+    /// A token has been issued and has not expired
+    pub verified: bool,
+    pub expires_at: Option<i64>,
+    pub updated_at: i64,
+    pub last_error: Option<String>,
+}
+
+// ============================================================
+// API tokens
+// ============================================================
+
+/// What an API token may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    Read,
+    Send,
+    Admin,
+}
+
+/// A token as listed (the secret is never returned after creation)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: i64,
+}
+
+/// A newly created token, including its secret
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiToken {
+    pub name: String,
+    pub token: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: i64,
+}
+
+// ============================================================
+// Webhooks
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookInfo {
+    pub webhook_id: String,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub enabled: bool,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub webhook: WebhookInfo,
+    /// HMAC signing secret; only returned on creation
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    /// Event types to deliver (e.g. "message_received"); empty means all
+    pub event_types: Vec<String>,
+    /// Signing secret; generated by the agent when absent
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryInfo {
+    pub delivery_id: i64,
+    pub event_id: i64,
+    pub event_type: String,
+    pub status: String,
+    pub attempts: u32,
+    pub next_attempt_at: i64,
+    pub last_status_code: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+}
@@ -0,0 +1,764 @@
+//! Contract tests: the client against an in-process bastion-agent
+//!
+//! Each test serves the real API router on an ephemeral port, backed by a
+//! fresh node in a temporary directory. The P2P network is never started.
+
+use bastion_agent::access::ApiTokens;
+use bastion_agent::accounts::Accounts;
+use bastion_agent::api;
+use bastion_agent::captcha_solver::ChallengeSolver;
+use bastion_agent::event_journal::EventJournal;
+use bastion_agent::state::AgentState;
+use bastion_client::types::*;
+use bastion_client::{AgentEvent, BastionClient, ClientError, EventStream};
+use futures::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+const PASSPHRASE: &str = "correct horse battery staple";
+
+struct TestAgent {
+    _dir: TempDir,
+    url: String,
+    admin_token: String,
+    state: Arc<AgentState>,
+}
+
+impl TestAgent {
+    async fn start() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let api_tokens = Arc::new(ApiTokens::load_or_init(dir.path()).unwrap());
+        let admin_token = api_tokens.list()[0].token.clone();
+        let accounts = Accounts::open(
+            dir.path().to_path_buf(),
+            api_tokens.clone(),
+            Arc::new(ChallengeSolver::default()),
+        )
+        .unwrap();
+        let state = Arc::new(AgentState {
+            api_tokens,
+            accounts,
+        });
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let router = api::router(state.clone());
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+
+        Self {
+            _dir: dir,
+            url,
+            admin_token,
+            state,
+        }
+    }
+
+    fn client(&self) -> BastionClient {
+        BastionClient::new(&self.url, self.admin_token.clone()).unwrap()
+    }
+
+    /// A client scoped to a new account named `display_name`
+    async fn create_account(&self, display_name: &str) -> (BastionClient, IdentityInfo) {
+        let client = self.client();
+        let created = client
+            .create_account(&CreateIdentityRequest {
+                display_name: display_name.to_string(),
+                passphrase: PASSPHRASE.to_string(),
+                bio: None,
+                passphrase_hint: None,
+            })
+            .await
+            .unwrap();
+        (client.account(created.account.id), created.identity)
+    }
+
+    async fn client_with_identity(&self) -> (BastionClient, IdentityInfo) {
+        self.create_account("Agent Smith").await
+    }
+
+    async fn journal(&self, account_id: &str) -> Arc<EventJournal> {
+        let account = self.state.accounts.get(account_id).await.unwrap();
+        account.state.journal.clone()
+    }
+}
+
+fn api_status(err: ClientError) -> (u16, ErrorCode) {
+    match err {
+        ClientError::Api { status, error } => (status, error.code),
+        other => panic!("expected an API error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_openapi_document_matches_router() {
+    let agent = TestAgent::start().await;
+
+    // Served without a token
+    let doc: serde_json::Value = reqwest::get(format!("{}/api/openapi.json", agent.url))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(doc, agent.client().openapi().await.unwrap());
+
+    let paths = doc["paths"].as_object().unwrap();
+    assert!(paths.len() > 50, "only {} paths documented", paths.len());
+
+    // Every documented operation is routed and guarded
+    let http = reqwest::Client::new();
+    for (path, item) in paths {
+        let concrete = path
+            .split('/')
+            .map(|segment| match segment {
+                "{deliveryId}" => "1",
+                s if s.starts_with('{') => "x",
+                s => s,
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let methods = item
+            .as_object()
+            .unwrap()
+            .keys()
+            .filter(|key| ["get", "post", "put", "delete"].contains(&key.as_str()));
+        for method in methods {
+            let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+            let status = http
+                .request(method.clone(), format!("{}{}", agent.url, concrete))
+                .send()
+                .await
+                .unwrap()
+                .status();
+            assert_eq!(
+                status, 401,
+                "{} {} is not routed as documented",
+                method, path
+            );
+
+            let operation = &item[method.as_str().to_lowercase()];
+            assert!(
+                operation["security"][0]["bearer"][0].is_string(),
+                "{} {} documents no scope",
+                method,
+                path
+            );
+        }
+    }
+
+    let schemas = doc["components"]["schemas"].as_object().unwrap();
+    for name in [
+        "NetworkEvent",
+        "ErrorResponse",
+        "PostInfo",
+        "FeedPageResponse",
+    ] {
+        assert!(schemas.contains_key(name), "schema {} missing", name);
+    }
+}
+
+#[tokio::test]
+async fn test_identity_lifecycle() {
+    let agent = TestAgent::start().await;
+    assert!(agent.client().list_accounts().await.unwrap().is_empty());
+    let err = agent.client().get_identity_status().await.unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::IdentityNotFound));
+
+    let (client, created) = agent.client_with_identity().await;
+    assert_eq!(created.display_name, "Agent Smith");
+    assert!(client.get_identity_status().await.unwrap().is_unlocked);
+
+    client.update_display_name("Agent Jones").await.unwrap();
+    client.update_bio(Some("Mesh resident")).await.unwrap();
+    let identity = client.get_identity().await.unwrap().unwrap();
+    assert_eq!(identity.peer_id, created.peer_id);
+    assert_eq!(identity.display_name, "Agent Jones");
+    assert_eq!(identity.bio.as_deref(), Some("Mesh resident"));
+
+    client.lock_identity().await.unwrap();
+    assert!(!client.get_identity_status().await.unwrap().is_unlocked);
+
+    let err = client
+        .create_post(&CreatePostRequest {
+            content_text: Some("while locked".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (403, ErrorCode::IdentityLocked));
+
+    let err = client
+        .unlock_identity("wrong passphrase")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::IdentityInvalidPassphrase));
+    assert_eq!(api_status(err), (401, ErrorCode::IdentityInvalidPassphrase));
+
+    let unlocked = client.unlock_identity(PASSPHRASE).await.unwrap();
+    assert_eq!(unlocked.peer_id, created.peer_id);
+
+    // The registry follows profile edits
+    let accounts = agent.client().list_accounts().await.unwrap();
+    assert_eq!(accounts[0].display_name, "Agent Jones");
+    assert_eq!(accounts[0].bio.as_deref(), Some("Mesh resident"));
+}
+
+#[tokio::test]
+async fn test_accounts_are_isolated() {
+    let agent = TestAgent::start().await;
+    let (alice, alice_identity) = agent.create_account("Alice").await;
+    let (bob, bob_identity) = agent.create_account("Bob").await;
+    assert_ne!(alice_identity.peer_id, bob_identity.peer_id);
+
+    let accounts = agent.client().list_accounts().await.unwrap();
+    assert_eq!(accounts.len(), 2);
+    let active: Vec<_> = accounts.iter().filter(|a| a.is_active).collect();
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].peer_id, bob_identity.peer_id);
+    assert!(accounts.iter().all(|a| a.is_unlocked && !a.network_running));
+
+    alice
+        .create_post(&CreatePostRequest {
+            content_text: Some("alice only".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        alice
+            .get_my_posts(&PostsQuery::default())
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(bob
+        .get_my_posts(&PostsQuery::default())
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Locking one account leaves the other unlocked
+    alice.lock_identity().await.unwrap();
+    assert!(bob.get_identity_status().await.unwrap().is_unlocked);
+
+    // Unprefixed routes are served by the active account
+    let identity = agent.client().get_identity().await.unwrap().unwrap();
+    assert_eq!(identity.peer_id, bob_identity.peer_id);
+
+    let err = agent
+        .client()
+        .account("no-such-account")
+        .get_identity()
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+
+    agent
+        .client()
+        .remove_account(&alice_identity.peer_id, true)
+        .await
+        .unwrap();
+    let accounts = agent.client().list_accounts().await.unwrap();
+    assert_eq!(accounts.len(), 1);
+    let err = alice.get_identity().await.unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+}
+
+#[tokio::test]
+async fn test_posts_media_likes_and_feed() {
+    let agent = TestAgent::start().await;
+    let (client, identity) = agent.client_with_identity().await;
+
+    let created = client
+        .create_post(&CreatePostRequest {
+            content_text: Some("hello mesh".to_string()),
+            visibility: Some("public".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let post = client.get_post(&created.post_id).await.unwrap();
+    assert_eq!(post.content_text.as_deref(), Some("hello mesh"));
+    assert_eq!(post.visibility, "public");
+    assert_eq!(post.author_peer_id, identity.peer_id);
+
+    client
+        .update_post(&created.post_id, Some("hello again"))
+        .await
+        .unwrap();
+    let mine = client.get_my_posts(&PostsQuery::default()).await.unwrap();
+    assert_eq!(mine.len(), 1);
+    assert_eq!(mine[0].content_text.as_deref(), Some("hello again"));
+
+    // Search follows the edit
+    let hits = client
+        .search(&SearchQuery {
+            q: "agai".to_string(),
+            kinds: Some("wall_post,board_post".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].kind, "wall_post");
+    assert_eq!(hits[0].item_id, created.post_id);
+    assert!(hits[0].snippet.contains("<mark>again</mark>"));
+    assert!(client
+        .search(&SearchQuery {
+            q: "mesh".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(client
+        .search(&SearchQuery {
+            q: "hello".to_string(),
+            kinds: Some("tweets".to_string()),
+            ..Default::default()
+        })
+        .await
+        .is_err());
+
+    // Media round trip
+    let bytes = b"not really a png".to_vec();
+    let media = client
+        .upload_post_media(
+            &created.post_id,
+            &MediaUpload {
+                file_name: "pic.png".to_string(),
+                mime_type: Some("image/png".to_string()),
+                ..Default::default()
+            },
+            bytes.clone(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(media.media_type, "image");
+    assert_eq!(media.file_size, bytes.len() as i64);
+    assert_eq!(client.get_media(&media.media_hash).await.unwrap(), bytes);
+    assert_eq!(
+        client.get_post_media(&created.post_id).await.unwrap().len(),
+        1
+    );
+
+    // Likes
+    let summary = client.like_post(&created.post_id).await.unwrap();
+    assert_eq!(summary.total_likes, 1);
+    assert!(summary.user_has_liked);
+    assert_eq!(
+        client.get_my_liked_posts().await.unwrap(),
+        vec![created.post_id.clone()]
+    );
+    let batch = client
+        .get_posts_likes_batch(&[created.post_id.clone()])
+        .await
+        .unwrap();
+    assert_eq!(batch.len(), 1);
+    let summary = client.unlike_post(&created.post_id).await.unwrap();
+    assert_eq!(summary.total_likes, 0);
+
+    // Feed and wall
+    let page = client.get_feed(&FeedQuery::default()).await.unwrap();
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].post_id, created.post_id);
+    assert!(page.next_cursor.is_none());
+    let wall = client
+        .get_wall(&identity.peer_id, &WallQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(wall.len(), 1);
+
+    client.delete_post(&created.post_id).await.unwrap();
+    let page = client.get_feed(&FeedQuery::default()).await.unwrap();
+    assert!(page.items.is_empty());
+}
+
+#[tokio::test]
+async fn test_local_state_endpoints() {
+    let agent = TestAgent::start().await;
+    let (client, _) = agent.client_with_identity().await;
+
+    let status = client.get_network_status().await.unwrap();
+    assert!(!status.running);
+    assert!(status.stats.is_none());
+
+    assert!(client.get_active_contacts().await.unwrap().is_empty());
+    assert!(client.get_conversations().await.unwrap().is_empty());
+    assert_eq!(client.get_total_unread_count().await.unwrap(), 0);
+    assert!(client.get_chat_peers().await.unwrap().is_empty());
+    assert!(client.get_communities().await.unwrap().is_empty());
+
+    // Network-backed calls fail cleanly while the network is stopped
+    assert!(client.get_connected_peers().await.is_err());
+    assert!(client.get_presence().await.is_err());
+    assert!(client
+        .set_presence(PresenceStatus::Away, Some("lunch"))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_contact_verification() {
+    let agent = TestAgent::start().await;
+    let (client, _) = agent.client_with_identity().await;
+
+    let peer_id = "12D3KooWGzh6cKRtmqYcXGMkwfM4ETpDPuSuvkBhYLMoYdLrgkHf";
+    client
+        .add_contact(&AddContactRequest {
+            peer_id: peer_id.to_string(),
+            public_key: vec![1; 32],
+            x25519_public: vec![2; 32],
+            display_name: "Bob".to_string(),
+            avatar_hash: None,
+            bio: None,
+        })
+        .await
+        .unwrap();
+
+    let number = client.get_safety_number(peer_id).await.unwrap();
+    assert_eq!(number.digits.split(' ').count(), 12);
+    assert!(!client
+        .verify_contact_safety_number(peer_id, "HARBORSN1:000")
+        .await
+        .unwrap());
+    assert!(client
+        .verify_contact_safety_number(peer_id, &number.qr_payload)
+        .await
+        .unwrap());
+    let contacts = client.get_active_contacts().await.unwrap();
+    assert_eq!(contacts[0].trust_level, 1);
+
+    client.clear_contact_verification(peer_id).await.unwrap();
+    let contacts = client.get_active_contacts().await.unwrap();
+    assert_eq!(contacts[0].trust_level, 0);
+    assert!(client.mark_contact_verified(peer_id).await.unwrap());
+
+    let err = client.get_safety_number("nobody").await.unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+}
+
+#[tokio::test]
+async fn test_avatar_upload() {
+    let agent = TestAgent::start().await;
+    let (client, _) = agent.client_with_identity().await;
+
+    let err = client.get_avatar().await.unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+    let err = client
+        .set_avatar("text/plain", b"not an image".to_vec())
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (400, ErrorCode::ValidationError));
+
+    let hash = client
+        .set_avatar("image/png", b"png bytes".to_vec())
+        .await
+        .unwrap();
+    assert_eq!(hash.len(), 64);
+    let identity = client.get_identity().await.unwrap().unwrap();
+    assert_eq!(identity.avatar_hash.as_deref(), Some(hash.as_str()));
+    let avatar = client.get_avatar().await.unwrap();
+    assert_eq!(avatar.mime_type, "image/png");
+    assert_eq!(avatar.data, b"png bytes");
+
+    client.clear_avatar().await.unwrap();
+    let identity = client.get_identity().await.unwrap().unwrap();
+    assert_eq!(identity.avatar_hash, None);
+    let err = client.get_contact_avatar("nobody").await.unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+}
+
+#[tokio::test]
+async fn test_message_events_need_a_message() {
+    let agent = TestAgent::start().await;
+    let (client, _) = agent.client_with_identity().await;
+    let peer_id = "12D3KooWNobody";
+
+    let err = client
+        .edit_message(peer_id, "missing", "edited")
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+    let err = client.delete_message(peer_id, "missing").await.unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+    let err = client
+        .react_to_message(peer_id, "missing", "+1")
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+    let err = client.get_attachment(peer_id, "missing").await.unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+
+    let err = client
+        .send_attachment(peer_id, "empty.txt", "text/plain", Vec::new())
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (400, ErrorCode::ValidationError));
+}
+
+#[tokio::test]
+async fn test_conversation_paging_and_export_need_a_contact() {
+    let agent = TestAgent::start().await;
+    let (client, _) = agent.client_with_identity().await;
+    let peer_id = "12D3KooWNobody";
+
+    let err = client
+        .get_message_page(
+            peer_id,
+            &MessagePageQuery {
+                cursor: Some("not-a-cursor".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (400, ErrorCode::ValidationError));
+    let err = client
+        .get_message_page(peer_id, &MessagePageQuery::default())
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+    let err = client
+        .export_conversation(peer_id, ExportFormat::Mbox)
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+}
+
+#[tokio::test]
+async fn test_conversation_retention_needs_a_chat_permission() {
+    let agent = TestAgent::start().await;
+    let (client, _) = agent.client_with_identity().await;
+    let peer_id = "12D3KooWNobody";
+
+    let setting = client.get_conversation_retention(peer_id).await.unwrap();
+    assert_eq!(setting.retention_secs, None);
+
+    let err = client
+        .set_conversation_retention(peer_id, Some(90))
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (400, ErrorCode::ValidationError));
+    let err = client
+        .set_conversation_retention(peer_id, Some(86_400))
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (403, ErrorCode::PermissionDenied));
+}
+
+#[tokio::test]
+async fn test_scheduled_items_can_be_listed_and_canceled() {
+    let agent = TestAgent::start().await;
+    let (client, _) = agent.client_with_identity().await;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    let err = client
+        .schedule_post(&SchedulePostRequest {
+            content_text: Some("Too late".to_string()),
+            publish_at: now - 60,
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (400, ErrorCode::ValidationError));
+
+    let err = client
+        .schedule_message(&ScheduleMessageRequest {
+            peer_id: "12D3KooWNobody".to_string(),
+            content: "Hello later".to_string(),
+            content_type: None,
+            reply_to: None,
+            publish_at: now + 3600,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (403, ErrorCode::PermissionDenied));
+
+    let scheduled = client
+        .schedule_post(&SchedulePostRequest {
+            content_text: Some("Announcement".to_string()),
+            visibility: Some("public".to_string()),
+            publish_at: now + 3600,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(scheduled.kind, "post");
+    assert_eq!(scheduled.status, "pending");
+
+    let pending = client.get_scheduled_items(false).await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].content.as_deref(), Some("Announcement"));
+
+    client
+        .cancel_scheduled_item(&scheduled.schedule_id)
+        .await
+        .unwrap();
+    assert!(client.get_scheduled_items(false).await.unwrap().is_empty());
+    let all = client.get_scheduled_items(true).await.unwrap();
+    assert_eq!(all[0].status, "canceled");
+    assert_eq!(all[0].content, None);
+
+    let err = client
+        .cancel_scheduled_item(&scheduled.schedule_id)
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (400, ErrorCode::ValidationError));
+    let err = client.cancel_scheduled_item("missing").await.unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+}
+
+#[tokio::test]
+async fn test_token_scopes() {
+    let agent = TestAgent::start().await;
+    let admin = agent.client();
+
+    let token = admin.create_token("reader", &[Scope::Read]).await.unwrap();
+    assert!(token.token.starts_with("bst_"));
+    let names: Vec<_> = admin
+        .list_tokens()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    assert!(names.contains(&"reader".to_string()));
+
+    let reader = BastionClient::new(&agent.url, token.token).unwrap();
+    reader.get_identity_status().await.unwrap();
+    let err = reader.list_tokens().await.unwrap_err();
+    assert_eq!(api_status(err), (403, ErrorCode::PermissionDenied));
+
+    let stranger = BastionClient::new(&agent.url, "bst_nope").unwrap();
+    let err = stranger.get_identity_status().await.unwrap_err();
+    assert_eq!(api_status(err), (401, ErrorCode::Unauthorized));
+
+    admin.revoke_token("reader").await.unwrap();
+    let err = reader.get_identity_status().await.unwrap_err();
+    assert_eq!(api_status(err).0, 401);
+}
+
+#[tokio::test]
+async fn test_webhook_management() {
+    let agent = TestAgent::start().await;
+    let (client, _) = agent.client_with_identity().await;
+
+    let created = client
+        .create_webhook(&CreateWebhookRequest {
+            url: "http://127.0.0.1:9/hook".to_string(),
+            event_types: vec!["message_received".to_string()],
+            secret: None,
+        })
+        .await
+        .unwrap();
+    assert!(created.secret.starts_with("whsec_"));
+    assert!(created.webhook.enabled);
+
+    let id = created.webhook.webhook_id;
+    client.set_webhook_enabled(&id, false).await.unwrap();
+    let listed = client.list_webhooks().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert!(!listed[0].enabled);
+
+    assert!(client.list_deliveries(&id, None).await.unwrap().is_empty());
+    let err = client.retry_delivery(&id, 42).await.unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+
+    client.delete_webhook(&id).await.unwrap();
+    assert!(client.list_webhooks().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_relay_tokens_are_tracked() {
+    let agent = TestAgent::start().await;
+    let (client, _) = agent.client_with_identity().await;
+    assert!(client.list_relay_tokens().await.unwrap().is_empty());
+
+    // Nothing listens there: the attempt fails but the relay is remembered
+    // so it is retried
+    let auth_url = "http://127.0.0.1:9";
+    let err = client
+        .verify_agent(auth_url, Some("12D3KooWRelay"))
+        .await
+        .unwrap_err();
+    assert_eq!(api_status(err), (502, ErrorCode::NetworkError));
+
+    let relays = client.list_relay_tokens().await.unwrap();
+    assert_eq!(relays.len(), 1);
+    assert_eq!(relays[0].auth_url, auth_url);
+    assert_eq!(relays[0].relay_peer_id.as_deref(), Some("12D3KooWRelay"));
+    assert!(!relays[0].verified);
+    assert!(relays[0].last_error.is_some());
+
+    client.forget_relay(auth_url).await.unwrap();
+    assert!(client.list_relay_tokens().await.unwrap().is_empty());
+    let err = client.forget_relay(auth_url).await.unwrap_err();
+    assert_eq!(api_status(err), (404, ErrorCode::NotFound));
+}
+
+async fn next_event(events: &mut EventStream) -> AgentEvent {
+    tokio::time::timeout(Duration::from_secs(5), events.next())
+        .await
+        .expect("timed out waiting for an event")
+        .expect("stream ended")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_event_stream_is_typed_and_resumable() {
+    let agent = TestAgent::start().await;
+    let (client, identity) = agent.client_with_identity().await;
+    let journal = agent.journal(&identity.peer_id).await;
+
+    let record = |event: NetworkEvent| {
+        let payload = serde_json::to_value(&event).unwrap();
+        let event_type = payload["type"].as_str().unwrap().to_string();
+        journal.record(&event_type, payload);
+    };
+    let mut events = client.events(None).await.unwrap();
+    record(NetworkEvent::PeerConnected {
+        peer_id: "peer-a".to_string(),
+    });
+    record(NetworkEvent::PeerDisconnected {
+        peer_id: "peer-a".to_string(),
+    });
+
+    let first_id = match next_event(&mut events).await {
+        AgentEvent::Network {
+            id,
+            event: NetworkEvent::PeerConnected { peer_id },
+        } => {
+            assert_eq!(peer_id, "peer-a");
+            id
+        }
+        other => panic!("unexpected event {:?}", other),
+    };
+    match next_event(&mut events).await {
+        AgentEvent::Network {
+            event: NetworkEvent::PeerDisconnected { .. },
+            ..
+        } => {}
+        other => panic!("unexpected event {:?}", other),
+    }
+    assert!(events.last_event_id() > Some(first_id));
+    drop(events);
+
+    // Reconnecting after the first event replays the second
+    let mut resumed = client.events(Some(first_id)).await.unwrap();
+    match next_event(&mut resumed).await {
+        AgentEvent::Network {
+            event: NetworkEvent::PeerDisconnected { peer_id },
+            ..
+        } => assert_eq!(peer_id, "peer-a"),
+        other => panic!("unexpected event {:?}", other),
+    }
+}
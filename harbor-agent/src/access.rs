@@ -0,0 +1,424 @@
+//! Bearer-token access control for the HTTP API
+//!
+//! Tokens live in `api_tokens.json` in the data directory. On first run an
+//! admin token holding every scope is generated and written there (mode 0600
+//! on Unix); operators read it from the file and pass it as
+//! `Authorization: Bearer <token>`. Further tokens with narrower scopes can be
+//! minted through `/api/tokens`.
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use harbor_lib::error::AppError;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+
+/// File holding the API tokens, relative to the data directory
+pub const TOKENS_FILE: &str = "api_tokens.json";
+
+/// Name of the token generated on first run
+pub const DEFAULT_TOKEN_NAME: &str = "default";
+
+/// Prefix of generated tokens, so they are recognisable in configs and logs
+const TOKEN_PREFIX: &str = "bst_";
+
+/// What a token may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// Read identity, contacts, messages, boards and events
+    Read,
+    /// Send messages and board posts, mark conversations read, sync boards
+    Send,
+    /// Identity unlock, network control, contacts, permissions and token management
+    Admin,
+}
+
+/// A stored API token
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiToken {
+    pub name: String,
+    pub token: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: i64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct TokensFile {
+    tokens: Vec<ApiToken>,
+}
+
+/// The agent's API tokens, persisted in the data directory
+pub struct ApiTokens {
+    path: PathBuf,
+    tokens: RwLock<Vec<ApiToken>>,
+}
+
+impl ApiTokens {
+    /// Load tokens from `data_dir`, generating an admin token on first run
+    pub fn load_or_init(data_dir: &Path) -> anyhow::Result<Self> {
+        let path = data_dir.join(TOKENS_FILE);
+        let file: TokensFile = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            TokensFile::default()
+        };
+
+        let store = Self {
+            path,
+            tokens: RwLock::new(file.tokens),
+        };
+
+        if store.tokens.read().unwrap().is_empty() {
+            store.create(
+                DEFAULT_TOKEN_NAME,
+                vec![Scope::Read, Scope::Send, Scope::Admin],
+            )?;
+            info!("Generated admin API token in {:?}", store.path);
+        }
+
+        Ok(store)
+    }
+
+    /// Mint a new token. Names must be unique.
+    pub fn create(&self, name: &str, scopes: Vec<Scope>) -> Result<ApiToken, AppError> {
+        if name.is_empty() {
+            return Err(AppError::Validation("Token name is required".to_string()));
+        }
+        if scopes.is_empty() {
+            return Err(AppError::Validation(
+                "At least one scope is required".to_string(),
+            ));
+        }
+
+        let mut tokens = self.tokens.write().unwrap();
+        if tokens.iter().any(|t| t.name == name) {
+            return Err(AppError::AlreadyExists(format!(
+                "Token '{}' already exists",
+                name
+            )));
+        }
+
+        let token = ApiToken {
+            name: name.to_string(),
+            token: random_secret(TOKEN_PREFIX),
+            scopes,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        tokens.push(token.clone());
+        self.save(&tokens)?;
+        Ok(token)
+    }
+
+    /// Revoke a token by name. Returns whether it existed.
+    pub fn revoke(&self, name: &str) -> Result<bool, AppError> {
+        let mut tokens = self.tokens.write().unwrap();
+        let before = tokens.len();
+        tokens.retain(|t| t.name != name);
+        if tokens.len() == before {
+            return Ok(false);
+        }
+        self.save(&tokens)?;
+        Ok(true)
+    }
+
+    /// All tokens (secrets included; callers redact as needed)
+    pub fn list(&self) -> Vec<ApiToken> {
+        self.tokens.read().unwrap().clone()
+    }
+
+    /// Scopes granted to a presented bearer token
+    pub fn scopes_for(&self, presented: &str) -> Option<Vec<Scope>> {
+        self.tokens
+            .read()
+            .unwrap()
+            .iter()
+            .find(|t| constant_time_eq(t.token.as_bytes(), presented.as_bytes()))
+            .map(|t| t.scopes.clone())
+    }
+
+    fn save(&self, tokens: &[ApiToken]) -> Result<(), AppError> {
+        let json = serde_json::to_string_pretty(&TokensFile {
+            tokens: tokens.to_vec(),
+        })
+        .map_err(|e| AppError::Serialization(e.to_string()))?;
+
+        let tmp = self.path.with_extension("json.tmp");
+        write_private(&tmp, json.as_bytes())
+            .and_then(|_| std::fs::rename(&tmp, &self.path))
+            .map_err(|e| AppError::Internal(format!("Failed to write API tokens: {}", e)))
+    }
+}
+
+/// Write a file readable only by the owner
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(contents)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    std::fs::write(path, contents)
+}
+
+/// 32 random bytes, hex-encoded behind a recognisable prefix
+pub fn random_secret(prefix: &str) -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    format!("{}{}", prefix, hex::encode(bytes))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Extract the presented token. The SSE endpoints also accept
+/// `?access_token=` because browser `EventSource` cannot set headers.
+fn presented_token(req: &Request) -> Option<String> {
+    if let Some(value) = req
+        .headers()
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+    {
+        return value.strip_prefix("Bearer ").map(|t| t.trim().to_string());
+    }
+
+    if is_event_stream(req.uri().path()) {
+        return req.uri().query().and_then(|query| {
+            query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == "access_token")
+                .map(|(_, value)| value.to_string())
+        });
+    }
+
+    None
+}
+
+/// `/api/events`, or an account's `/api/accounts/:accountId/events`
+fn is_event_stream(path: &str) -> bool {
+    path == "/api/events"
+        || path
+            .strip_prefix("/api/accounts/")
+            .and_then(|rest| rest.split_once('/'))
+            .is_some_and(|(_, rest)| rest == "events")
+}
+
+/// Scopes of the request's token, or the error to answer with
+pub fn authenticate(tokens: &ApiTokens, req: &Request) -> Result<Vec<Scope>, ApiError> {
+    let token = presented_token(req).ok_or_else(|| {
+        ApiError(AppError::Unauthorized("Missing bearer token".to_string()))
+    })?;
+    tokens
+        .scopes_for(&token)
+        .ok_or_else(|| ApiError(AppError::Unauthorized("Invalid API token".to_string())))
+}
+
+/// Middleware: reject requests whose token lacks `scope`
+pub async fn require_scope(
+    State(tokens): State<Arc<ApiTokens>>,
+    scope: Scope,
+    req: Request,
+    next: Next,
+) -> Response {
+    match authenticate(&tokens, &req) {
+        Err(e) => e.into_response(),
+        Ok(scopes) if !scopes.contains(&scope) => ApiError(AppError::PermissionDenied(format!(
+            "Token lacks the '{}' scope",
+            scope.as_str()
+        )))
+        .into_response(),
+        Ok(_) => next.run(req).await,
+    }
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Send => "send",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{header, StatusCode};
+    use axum::routing::get;
+    use axum::Router;
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    fn tokens() -> (TempDir, Arc<ApiTokens>) {
+        let dir = tempfile::tempdir().unwrap();
+        let tokens = Arc::new(ApiTokens::load_or_init(dir.path()).unwrap());
+        (dir, tokens)
+    }
+
+    fn request(uri: &str, authorization: Option<&str>) -> Request {
+        let mut builder = axum::http::Request::builder().uri(uri);
+        if let Some(value) = authorization {
+            builder = builder.header(header::AUTHORIZATION, value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    fn bearer(token: &str) -> String {
+        format!("Bearer {}", token)
+    }
+
+    /// Scopes of a request, or `None` if it is rejected as unauthorized
+    fn scopes(tokens: &ApiTokens, req: &Request) -> Option<Vec<Scope>> {
+        match authenticate(tokens, req) {
+            Ok(scopes) => Some(scopes),
+            Err(e) => {
+                assert!(matches!(e.0, AppError::Unauthorized(_)));
+                None
+            }
+        }
+    }
+
+    /// A router whose only route requires `scope`
+    fn guarded(tokens: &Arc<ApiTokens>, scope: Scope) -> Router {
+        Router::new()
+            .route("/api/test", get(|| async { "ok" }))
+            .route_layer(axum::middleware::from_fn_with_state(
+                tokens.clone(),
+                move |tokens: State<Arc<ApiTokens>>, req: Request, next: Next| {
+                    require_scope(tokens, scope, req, next)
+                },
+            ))
+    }
+
+    async fn status(router: &Router, authorization: Option<&str>) -> StatusCode {
+        router
+            .clone()
+            .oneshot(request("/api/test", authorization))
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[test]
+    fn test_first_run_token_holds_every_scope() {
+        let (dir, tokens) = tokens();
+        let admin = tokens.list().remove(0);
+        assert_eq!(admin.name, DEFAULT_TOKEN_NAME);
+        assert_eq!(admin.scopes, [Scope::Read, Scope::Send, Scope::Admin]);
+        assert!(admin.token.starts_with(TOKEN_PREFIX));
+
+        // Reloading keeps the token rather than generating another
+        let reloaded = ApiTokens::load_or_init(dir.path()).unwrap();
+        assert_eq!(reloaded.list().len(), 1);
+        assert_eq!(reloaded.list()[0].token, admin.token);
+    }
+
+    #[test]
+    fn test_token_scopes() {
+        let (_dir, tokens) = tokens();
+        let reader = tokens.create("reader", vec![Scope::Read]).unwrap();
+        let req = request("/api/contacts", Some(&bearer(&reader.token)));
+        assert_eq!(scopes(&tokens, &req), Some(vec![Scope::Read]));
+
+        assert!(tokens.create("reader", vec![Scope::Send]).is_err());
+        assert!(tokens.create("empty", Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_missing_and_malformed_tokens_are_rejected() {
+        let (_dir, tokens) = tokens();
+        let token = tokens.list().remove(0).token;
+
+        let rejected = [
+            None,
+            Some(token.clone()),
+            Some(format!("Basic {}", token)),
+            Some("Bearer ".to_string()),
+            Some(bearer("bst_not-a-token")),
+            Some(bearer(&token[..token.len() - 1])),
+            Some(bearer(&format!("{}0", token))),
+        ];
+        for authorization in rejected {
+            let req = request("/api/contacts", authorization.as_deref());
+            assert_eq!(scopes(&tokens, &req), None, "{:?}", authorization);
+        }
+    }
+
+    #[test]
+    fn test_revoked_tokens_are_rejected() {
+        let (_dir, tokens) = tokens();
+        let sender = tokens.create("sender", vec![Scope::Send]).unwrap();
+        assert!(tokens.revoke("sender").unwrap());
+        assert!(!tokens.revoke("sender").unwrap());
+
+        let req = request("/api/messages", Some(&bearer(&sender.token)));
+        assert_eq!(scopes(&tokens, &req), None);
+    }
+
+    #[test]
+    fn test_query_tokens_only_for_event_streams() {
+        let (_dir, tokens) = tokens();
+        let token = tokens.list().remove(0).token;
+
+        for uri in ["/api/events", "/api/accounts/abc/events"] {
+            let req = request(&format!("{}?access_token={}", uri, token), None);
+            assert!(scopes(&tokens, &req).is_some(), "{}", uri);
+        }
+        for uri in ["/api/contacts", "/api/accounts/abc/events/extra"] {
+            let req = request(&format!("{}?access_token={}", uri, token), None);
+            assert_eq!(scopes(&tokens, &req), None, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_require_scope_allows_and_denies() {
+        let (_dir, tokens) = tokens();
+        let admin = bearer(&tokens.list().remove(0).token);
+        let reader = bearer(&tokens.create("reader", vec![Scope::Read]).unwrap().token);
+        let sender = bearer(&tokens.create("sender", vec![Scope::Send]).unwrap().token);
+
+        let send_route = guarded(&tokens, Scope::Send);
+        assert_eq!(status(&send_route, Some(&sender)).await, StatusCode::OK);
+        assert_eq!(status(&send_route, Some(&admin)).await, StatusCode::OK);
+        assert_eq!(
+            status(&send_route, Some(&reader)).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(status(&send_route, None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status(&send_route, Some("Bearer nope")).await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        let admin_route = guarded(&tokens, Scope::Admin);
+        assert_eq!(status(&admin_route, Some(&admin)).await, StatusCode::OK);
+        assert_eq!(
+            status(&admin_route, Some(&sender)).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! Identities hosted by this agent
+//!
+//! Every account is a separate identity with its own Harbor database, agent
+//! database (event journal and webhooks), media store and P2P network. Accounts
+//! are listed in `accounts.json` through harbor_lib's `AccountsService` and each
+//! lives in `profile-<peerId>/` under the data directory. The API serves them
+//! at `/api/accounts/:accountId/...`; unprefixed routes go to the active
+//! account (the most recently created one).
+
+use axum::Router;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use harbor_lib::error::AppError;
+use harbor_lib::models::{CreateIdentityRequest, IdentityInfo};
+use harbor_lib::node::HarborNode;
+use harbor_lib::services::accounts_service::AccountInfo;
+use harbor_lib::services::AccountsService;
+
+use crate::access::ApiTokens;
+use crate::agent_db::AgentDatabase;
+use crate::api;
+use crate::captcha_solver::ChallengeSolver;
+use crate::event_journal::EventJournal;
+use crate::relay_auth::RelayAuth;
+use crate::state::AppState;
+use crate::webhooks;
+
+/// Harbor database of an account, relative to its directory
+pub const DB_FILE: &str = "bastion.db";
+
+/// Agent database (journal and webhooks) of an account
+const AGENT_DB_FILE: &str = "agent.db";
+
+/// Files of the single-identity layout moved into the account's directory
+const LEGACY_FILES: &[&str] = &[
+    DB_FILE,
+    "bastion.db-wal",
+    "bastion.db-shm",
+    AGENT_DB_FILE,
+    "agent.db-wal",
+    "agent.db-shm",
+    "media",
+];
+
+/// A loaded account: its services, API routes and background tasks
+pub struct Account {
+    pub id: String,
+    pub state: Arc<AppState>,
+    /// The account's API, with paths relative to `/api`
+    pub router: Router,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Account {
+    /// Open the account stored in `dir`
+    fn open(
+        id: &str,
+        dir: &Path,
+        data_dir: &Path,
+        api_tokens: &Arc<ApiTokens>,
+        solver: &Arc<ChallengeSolver>,
+    ) -> Result<Self, AppError> {
+        let node = Arc::new(
+            HarborNode::builder(dir.join(DB_FILE))
+                .accounts_dir(data_dir.to_path_buf())
+                .event_capacity(1024)
+                .build()?,
+        );
+
+        let journal = Arc::new(EventJournal::new(AgentDatabase::open(
+            &dir.join(AGENT_DB_FILE),
+        )?));
+        let relay_auth = Arc::new(RelayAuth::new(
+            journal.db().clone(),
+            node.identity_service.clone(),
+            solver.clone(),
+        ));
+        let tasks = vec![
+            tokio::spawn(journal.clone().run(node.clone())),
+            tokio::spawn(webhooks::run_dispatcher(journal.clone())),
+            tokio::spawn(relay_auth.clone().run(node.clone())),
+        ];
+
+        let state = Arc::new(AppState::new(
+            node,
+            api_tokens.clone(),
+            dir.join("media"),
+            journal,
+            relay_auth,
+        ));
+
+        Ok(Self {
+            id: id.to_string(),
+            router: api::account_router(state.clone()),
+            state,
+            tasks,
+        })
+    }
+
+    /// Stop the network and background tasks and lock the identity
+    async fn shut_down(&self) {
+        if let Err(e) = self.state.node.stop_network().await {
+            warn!("Failed to stop network of account {}: {}", self.id, e);
+        }
+        for task in &self.tasks {
+            task.abort();
+        }
+        self.state.identity_service.lock();
+    }
+}
+
+/// All accounts hosted by the agent
+pub struct Accounts {
+    data_dir: PathBuf,
+    api_tokens: Arc<ApiTokens>,
+    solver: Arc<ChallengeSolver>,
+    registry: AccountsService,
+    loaded: RwLock<HashMap<String, Arc<Account>>>,
+}
+
+impl Accounts {
+    /// Load every registered account, first moving a single-identity data
+    /// directory into its own account
+    pub fn open(
+        data_dir: PathBuf,
+        api_tokens: Arc<ApiTokens>,
+        solver: Arc<ChallengeSolver>,
+    ) -> Result<Self, AppError> {
+        let registry = AccountsService::new(data_dir.clone());
+        migrate_legacy_layout(&data_dir, &registry)?;
+
+        let mut loaded = HashMap::new();
+        for account in registry.list_accounts()? {
+            let dir = registry.get_account_data_path(&account.id)?;
+            if !dir.join(DB_FILE).exists() {
+                warn!("Skipping account {}: no database in {:?}", account.id, dir);
+                continue;
+            }
+            let opened = Account::open(&account.id, &dir, &data_dir, &api_tokens, &solver)?;
+            info!("Loaded account {} ({})", account.display_name, account.id);
+            loaded.insert(account.id, Arc::new(opened));
+        }
+
+        Ok(Self {
+            data_dir,
+            api_tokens,
+            solver,
+            registry,
+            loaded: RwLock::new(loaded),
+        })
+    }
+
+    /// A loaded account
+    pub async fn get(&self, account_id: &str) -> Result<Arc<Account>, AppError> {
+        self.loaded
+            .read()
+            .await
+            .get(account_id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(format!("Account {} not found", account_id)))
+    }
+
+    /// The account served by unprefixed routes
+    pub async fn active(&self) -> Result<Arc<Account>, AppError> {
+        let active = self
+            .registry
+            .get_active_account()?
+            .ok_or(AppError::IdentityNotFound)?;
+        self.get(&active.id).await
+    }
+
+    /// Every loaded account
+    pub async fn all(&self) -> Vec<Arc<Account>> {
+        self.loaded.read().await.values().cloned().collect()
+    }
+
+    /// Registry entries of the loaded accounts, most recently used first
+    pub async fn list(&self) -> Result<Vec<AccountInfo>, AppError> {
+        let loaded = self.loaded.read().await;
+        Ok(self
+            .registry
+            .list_accounts()?
+            .into_iter()
+            .filter(|account| loaded.contains_key(&account.id))
+            .collect())
+    }
+
+    /// Id of the active account, if any
+    pub fn active_id(&self) -> Result<Option<String>, AppError> {
+        Ok(self
+            .registry
+            .get_active_account()?
+            .map(|account| account.id))
+    }
+
+    /// Create an identity in a new account and load it, unlocked. The new
+    /// account becomes the active one.
+    pub async fn create(
+        &self,
+        request: CreateIdentityRequest,
+    ) -> Result<(AccountInfo, IdentityInfo), AppError> {
+        // The directory is named after the peer id, which only exists once the
+        // identity has been created, so build it in a staging directory first
+        let staging = self
+            .data_dir
+            .join(format!(".new-account-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&staging)?;
+
+        let created = create_identity_in(&staging, &self.data_dir, request.clone());
+        let identity = match created {
+            Ok(identity) => identity,
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&staging);
+                return Err(e);
+            }
+        };
+
+        let account = match self.registry.register_account(
+            identity.peer_id.clone(),
+            request.display_name,
+            request.bio,
+            identity.avatar_hash.clone(),
+        ) {
+            Ok(account) => account,
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&staging);
+                return Err(e);
+            }
+        };
+
+        let dir = self.registry.get_account_data_path(&account.id)?;
+        std::fs::rename(&staging, &dir)?;
+
+        let opened = Account::open(
+            &account.id,
+            &dir,
+            &self.data_dir,
+            &self.api_tokens,
+            &self.solver,
+        )?;
+        let identity = opened.state.identity_service.unlock(&request.passphrase)?;
+        self.loaded
+            .write()
+            .await
+            .insert(account.id.clone(), Arc::new(opened));
+
+        info!("Created account {} ({})", account.display_name, account.id);
+        Ok((account, identity))
+    }
+
+    /// Shut an account down and unregister it, optionally deleting its data
+    pub async fn remove(&self, account_id: &str, delete_data: bool) -> Result<(), AppError> {
+        let account = self.get(account_id).await?;
+        account.shut_down().await;
+        self.loaded.write().await.remove(account_id);
+        self.registry.remove_account(account_id, delete_data)
+    }
+}
+
+/// Create an identity in a fresh database in `dir`
+fn create_identity_in(
+    dir: &Path,
+    data_dir: &Path,
+    request: CreateIdentityRequest,
+) -> Result<IdentityInfo, AppError> {
+    let node = HarborNode::builder(dir.join(DB_FILE))
+        .accounts_dir(data_dir.to_path_buf())
+        .build()?;
+    node.identity_service.create_identity(request)
+}
+
+/// Move the pre multi-account layout (`bastion.db`, `agent.db` and `media/`
+/// directly in the data directory) into the identity's account directory
+fn migrate_legacy_layout(data_dir: &Path, registry: &AccountsService) -> Result<(), AppError> {
+    let legacy_db = data_dir.join(DB_FILE);
+    if !legacy_db.exists() {
+        return Ok(());
+    }
+
+    let identity = HarborNode::builder(legacy_db.clone())
+        .accounts_dir(data_dir.to_path_buf())
+        .build()?
+        .identity_service
+        .get_identity_info()?;
+    let Some(identity) = identity else {
+        warn!(
+            "{:?} holds no identity and is not served; create accounts via POST /api/accounts",
+            legacy_db
+        );
+        return Ok(());
+    };
+
+    if registry.get_account(&identity.peer_id)?.is_none() {
+        registry.register_account(
+            identity.peer_id.clone(),
+            identity.display_name.clone(),
+            identity.bio.clone(),
+            identity.avatar_hash.clone(),
+        )?;
+    }
+
+    let dir = registry.get_account_data_path(&identity.peer_id)?;
+    if dir.join(DB_FILE).exists() {
+        warn!(
+            "Not migrating {:?}: account {} already has a database",
+            legacy_db, identity.peer_id
+        );
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(&dir)?;
+    for name in LEGACY_FILES {
+        let from = data_dir.join(name);
+        if from.exists() {
+            std::fs::rename(&from, dir.join(name))?;
+        }
+    }
+    info!("Moved identity {} into {:?}", identity.peer_id, dir);
+    Ok(())
+}
@@ -0,0 +1,559 @@
+//! bastion-agent SQLite store for the event journal, webhook deliveries and
+//! relay auth tokens
+//!
+//! Kept separate from the Harbor database: these tables belong to the agent's
+//! HTTP frontend, not to the identity it hosts.
+
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS event_journal (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_type TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS webhooks (
+    webhook_id TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    event_types TEXT NOT NULL DEFAULT '[]',
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    delivery_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    webhook_id TEXT NOT NULL,
+    event_id INTEGER NOT NULL,
+    event_type TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at INTEGER NOT NULL,
+    last_status_code INTEGER,
+    last_error TEXT,
+    created_at INTEGER NOT NULL,
+    completed_at INTEGER,
+    FOREIGN KEY (webhook_id) REFERENCES webhooks(webhook_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due
+    ON webhook_deliveries(status, next_attempt_at);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook
+    ON webhook_deliveries(webhook_id, delivery_id DESC);
+"#;
+
+/// Schema migrations applied after `SCHEMA`, tracked by `PRAGMA user_version`.
+/// Append new entries; never edit one that has shipped.
+const MIGRATIONS: &[&str] = &[
+    // 1: Isnad tokens per relay auth endpoint
+    r#"
+CREATE TABLE IF NOT EXISTS relay_tokens (
+    auth_url TEXT PRIMARY KEY,
+    relay_peer_id TEXT,
+    token TEXT,
+    expires_at INTEGER,
+    updated_at INTEGER NOT NULL,
+    last_error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_relay_tokens_peer ON relay_tokens(relay_peer_id);
+"#,
+];
+
+/// A journaled network event
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub created_at: i64,
+}
+
+/// A registered webhook
+#[derive(Debug, Clone)]
+pub struct WebhookRow {
+    pub webhook_id: String,
+    pub url: String,
+    pub secret: String,
+    /// Event types delivered; empty means all
+    pub event_types: Vec<String>,
+    pub enabled: bool,
+    pub created_at: i64,
+}
+
+impl WebhookRow {
+    pub fn wants(&self, event_type: &str) -> bool {
+        self.enabled
+            && (self.event_types.is_empty() || self.event_types.iter().any(|t| t == event_type))
+    }
+}
+
+/// A queued, delivered or failed webhook delivery
+#[derive(Debug, Clone)]
+pub struct DeliveryRow {
+    pub delivery_id: i64,
+    pub webhook_id: String,
+    pub event_id: i64,
+    pub event_type: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: u32,
+    pub next_attempt_at: i64,
+    pub last_status_code: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+}
+
+/// A relay the agent authenticates with, and its latest Isnad token
+#[derive(Debug, Clone)]
+pub struct RelayTokenRow {
+    pub auth_url: String,
+    pub relay_peer_id: Option<String>,
+    /// None until the first successful verification
+    pub token: Option<String>,
+    pub expires_at: Option<i64>,
+    pub updated_at: i64,
+    /// Error of the latest attempt, cleared on success
+    pub last_error: Option<String>,
+}
+
+/// Agent database
+#[derive(Clone)]
+pub struct AgentDatabase {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl AgentDatabase {
+    /// Open or create the database at the given path
+    pub fn open(path: &Path) -> SqliteResult<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        conn.execute_batch(SCHEMA)?;
+        Self::run_migrations(&conn)?;
+
+        info!("Agent database initialized at {:?}", path);
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    fn run_migrations(conn: &Connection) -> SqliteResult<()> {
+        let version: usize =
+            conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))? as usize;
+
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let target = i + 1;
+            conn.execute_batch(&format!(
+                "BEGIN;\n{}\nPRAGMA user_version = {};\nCOMMIT;",
+                migration, target
+            ))?;
+            info!("Applied agent database migration {}", target);
+        }
+        Ok(())
+    }
+
+    // ========== Event Journal ==========
+
+    /// Append an event, trimming the journal to `capacity` entries. Returns its id.
+    pub fn append_event(
+        &self,
+        event_type: &str,
+        payload: &serde_json::Value,
+        capacity: usize,
+    ) -> SqliteResult<JournalEntry> {
+        let conn = self.conn.lock().unwrap();
+        let created_at = chrono::Utc::now().timestamp();
+        conn.execute(
+            "INSERT INTO event_journal (event_type, payload, created_at) VALUES (?, ?, ?)",
+            params![event_type, payload.to_string(), created_at],
+        )?;
+        let id = conn.last_insert_rowid();
+        conn.execute(
+            "DELETE FROM event_journal WHERE id <= ?",
+            params![id - capacity as i64],
+        )?;
+
+        Ok(JournalEntry {
+            id,
+            event_type: event_type.to_string(),
+            payload: payload.clone(),
+            created_at,
+        })
+    }
+
+    /// Journaled events with id greater than `after_id`, oldest first
+    pub fn events_after(&self, after_id: i64, limit: usize) -> SqliteResult<Vec<JournalEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, event_type, payload, created_at FROM event_journal
+             WHERE id > ? ORDER BY id ASC LIMIT ?",
+        )?;
+        let mut rows = stmt.query(params![after_id, limit as i64])?;
+        let mut entries = Vec::new();
+        while let Some(row) = rows.next()? {
+            let payload: String = row.get(2)?;
+            entries.push(JournalEntry {
+                id: row.get(0)?,
+                event_type: row.get(1)?,
+                payload: serde_json::from_str(&payload).unwrap_or(serde_json::Value::Null),
+                created_at: row.get(3)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Oldest and newest journal ids, if any events are journaled
+    pub fn journal_bounds(&self) -> SqliteResult<Option<(i64, i64)>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT MIN(id), MAX(id) FROM event_journal",
+            [],
+            |row| Ok((row.get::<_, Option<i64>>(0)?, row.get::<_, Option<i64>>(1)?)),
+        )
+        .map(|(min, max)| min.zip(max))
+    }
+
+    // ========== Webhooks ==========
+
+    pub fn insert_webhook(&self, webhook: &WebhookRow) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO webhooks (webhook_id, url, secret, event_types, enabled, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                webhook.webhook_id,
+                webhook.url,
+                webhook.secret,
+                serde_json::to_string(&webhook.event_types).unwrap_or_else(|_| "[]".into()),
+                webhook.enabled as i32,
+                webhook.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_webhooks(&self) -> SqliteResult<Vec<WebhookRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT webhook_id, url, secret, event_types, enabled, created_at
+             FROM webhooks ORDER BY created_at ASC",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut webhooks = Vec::new();
+        while let Some(row) = rows.next()? {
+            webhooks.push(Self::row_to_webhook(row)?);
+        }
+        Ok(webhooks)
+    }
+
+    pub fn get_webhook(&self, webhook_id: &str) -> SqliteResult<Option<WebhookRow>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT webhook_id, url, secret, event_types, enabled, created_at
+             FROM webhooks WHERE webhook_id = ?",
+            params![webhook_id],
+            Self::row_to_webhook,
+        )
+        .optional()
+    }
+
+    /// Delete a webhook and its deliveries. Returns whether it existed.
+    pub fn delete_webhook(&self, webhook_id: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute(
+            "DELETE FROM webhooks WHERE webhook_id = ?",
+            params![webhook_id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    pub fn set_webhook_enabled(&self, webhook_id: &str, enabled: bool) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute(
+            "UPDATE webhooks SET enabled = ? WHERE webhook_id = ?",
+            params![enabled as i32, webhook_id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    fn row_to_webhook(row: &rusqlite::Row<'_>) -> SqliteResult<WebhookRow> {
+        let event_types: String = row.get(3)?;
+        Ok(WebhookRow {
+            webhook_id: row.get(0)?,
+            url: row.get(1)?,
+            secret: row.get(2)?,
+            event_types: serde_json::from_str(&event_types).unwrap_or_default(),
+            enabled: row.get::<_, i32>(4)? != 0,
+            created_at: row.get(5)?,
+        })
+    }
+
+    // ========== Deliveries ==========
+
+    /// Queue a journaled event for every enabled webhook that wants it.
+    /// Returns the number of deliveries queued.
+    pub fn enqueue_deliveries(&self, entry: &JournalEntry) -> SqliteResult<usize> {
+        let webhooks = self.list_webhooks()?;
+        let conn = self.conn.lock().unwrap();
+        let payload = entry.payload.to_string();
+        let now = chrono::Utc::now().timestamp();
+
+        let mut queued = 0;
+        for webhook in webhooks.iter().filter(|w| w.wants(&entry.event_type)) {
+            conn.execute(
+                "INSERT INTO webhook_deliveries
+                    (webhook_id, event_id, event_type, payload, next_attempt_at, created_at)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+                params![
+                    webhook.webhook_id,
+                    entry.id,
+                    entry.event_type,
+                    payload,
+                    now,
+                    now
+                ],
+            )?;
+            queued += 1;
+        }
+        Ok(queued)
+    }
+
+    /// Pending deliveries whose next attempt is due (disabled webhooks wait)
+    pub fn due_deliveries(&self, now: i64, limit: usize) -> SqliteResult<Vec<DeliveryRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE status = 'pending' AND next_attempt_at <= ?
+               AND webhook_id IN (SELECT webhook_id FROM webhooks WHERE enabled = 1)
+             ORDER BY next_attempt_at ASC, delivery_id ASC LIMIT ?",
+            DELIVERY_SELECT
+        ))?;
+        let mut rows = stmt.query(params![now, limit as i64])?;
+        let mut deliveries = Vec::new();
+        while let Some(row) = rows.next()? {
+            deliveries.push(Self::row_to_delivery(row)?);
+        }
+        Ok(deliveries)
+    }
+
+    /// Most recent deliveries for a webhook
+    pub fn recent_deliveries(
+        &self,
+        webhook_id: &str,
+        limit: usize,
+    ) -> SqliteResult<Vec<DeliveryRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE webhook_id = ? ORDER BY delivery_id DESC LIMIT ?",
+            DELIVERY_SELECT
+        ))?;
+        let mut rows = stmt.query(params![webhook_id, limit as i64])?;
+        let mut deliveries = Vec::new();
+        while let Some(row) = rows.next()? {
+            deliveries.push(Self::row_to_delivery(row)?);
+        }
+        Ok(deliveries)
+    }
+
+    pub fn mark_delivered(&self, delivery_id: i64, status_code: u16) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE webhook_deliveries
+             SET status = 'delivered', attempts = attempts + 1, last_status_code = ?,
+                 last_error = NULL, completed_at = ?
+             WHERE delivery_id = ?",
+            params![status_code, chrono::Utc::now().timestamp(), delivery_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record a failed attempt. With `next_attempt_at` the delivery stays
+    /// pending; without it the delivery is given up on.
+    pub fn mark_attempt_failed(
+        &self,
+        delivery_id: i64,
+        status_code: Option<u16>,
+        error: &str,
+        next_attempt_at: Option<i64>,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        match next_attempt_at {
+            Some(next) => conn.execute(
+                "UPDATE webhook_deliveries
+                 SET attempts = attempts + 1, last_status_code = ?, last_error = ?,
+                     next_attempt_at = ?
+                 WHERE delivery_id = ?",
+                params![status_code, error, next, delivery_id],
+            )?,
+            None => conn.execute(
+                "UPDATE webhook_deliveries
+                 SET status = 'failed', attempts = attempts + 1, last_status_code = ?,
+                     last_error = ?, completed_at = ?
+                 WHERE delivery_id = ?",
+                params![
+                    status_code,
+                    error,
+                    chrono::Utc::now().timestamp(),
+                    delivery_id
+                ],
+            )?,
+        };
+        Ok(())
+    }
+
+    /// Put a delivery back in the queue for an immediate attempt
+    pub fn requeue_delivery(&self, webhook_id: &str, delivery_id: i64) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute(
+            "UPDATE webhook_deliveries
+             SET status = 'pending', next_attempt_at = ?, completed_at = NULL
+             WHERE delivery_id = ? AND webhook_id = ?",
+            params![chrono::Utc::now().timestamp(), delivery_id, webhook_id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Drop completed deliveries older than the cutoff. Returns rows removed.
+    pub fn purge_deliveries(&self, completed_before: i64) -> SqliteResult<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM webhook_deliveries WHERE status != 'pending' AND completed_at < ?",
+            params![completed_before],
+        )
+    }
+
+    // ========== Relay Tokens ==========
+
+    /// Store a freshly issued token, clearing any previous error
+    pub fn save_relay_token(
+        &self,
+        auth_url: &str,
+        relay_peer_id: Option<&str>,
+        token: &str,
+        expires_at: i64,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO relay_tokens (auth_url, relay_peer_id, token, expires_at, updated_at, last_error)
+             VALUES (?, ?, ?, ?, ?, NULL)
+             ON CONFLICT(auth_url) DO UPDATE SET
+                relay_peer_id = COALESCE(excluded.relay_peer_id, relay_tokens.relay_peer_id),
+                token = excluded.token,
+                expires_at = excluded.expires_at,
+                updated_at = excluded.updated_at,
+                last_error = NULL",
+            params![
+                auth_url,
+                relay_peer_id,
+                token,
+                expires_at,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Record a failed verification, keeping any earlier token
+    pub fn save_relay_auth_error(
+        &self,
+        auth_url: &str,
+        relay_peer_id: Option<&str>,
+        error: &str,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO relay_tokens (auth_url, relay_peer_id, updated_at, last_error)
+             VALUES (?, ?, ?, ?)
+             ON CONFLICT(auth_url) DO UPDATE SET
+                relay_peer_id = COALESCE(excluded.relay_peer_id, relay_tokens.relay_peer_id),
+                updated_at = excluded.updated_at,
+                last_error = excluded.last_error",
+            params![
+                auth_url,
+                relay_peer_id,
+                chrono::Utc::now().timestamp(),
+                error
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_relay_tokens(&self) -> SqliteResult<Vec<RelayTokenRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!("{} ORDER BY auth_url ASC", RELAY_TOKEN_SELECT))?;
+        let mut rows = stmt.query([])?;
+        let mut tokens = Vec::new();
+        while let Some(row) = rows.next()? {
+            tokens.push(Self::row_to_relay_token(row)?);
+        }
+        Ok(tokens)
+    }
+
+    /// The relay entry for a relay peer, if its auth URL is known
+    pub fn relay_token_for_peer(&self, relay_peer_id: &str) -> SqliteResult<Option<RelayTokenRow>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            &format!(
+                "{} WHERE relay_peer_id = ? ORDER BY updated_at DESC LIMIT 1",
+                RELAY_TOKEN_SELECT
+            ),
+            params![relay_peer_id],
+            Self::row_to_relay_token,
+        )
+        .optional()
+    }
+
+    /// Stop tracking a relay. Returns whether it was tracked.
+    pub fn delete_relay_token(&self, auth_url: &str) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute(
+            "DELETE FROM relay_tokens WHERE auth_url = ?",
+            params![auth_url],
+        )?;
+        Ok(changed > 0)
+    }
+
+    fn row_to_relay_token(row: &rusqlite::Row<'_>) -> SqliteResult<RelayTokenRow> {
+        Ok(RelayTokenRow {
+            auth_url: row.get(0)?,
+            relay_peer_id: row.get(1)?,
+            token: row.get(2)?,
+            expires_at: row.get(3)?,
+            updated_at: row.get(4)?,
+            last_error: row.get(5)?,
+        })
+    }
+
+    fn row_to_delivery(row: &rusqlite::Row<'_>) -> SqliteResult<DeliveryRow> {
+        Ok(DeliveryRow {
+            delivery_id: row.get(0)?,
+            webhook_id: row.get(1)?,
+            event_id: row.get(2)?,
+            event_type: row.get(3)?,
+            payload: row.get(4)?,
+            status: row.get(5)?,
+            attempts: row.get(6)?,
+            next_attempt_at: row.get(7)?,
+            last_status_code: row.get(8)?,
+            last_error: row.get(9)?,
+            created_at: row.get(10)?,
+            completed_at: row.get(11)?,
+        })
+    }
+}
+
+/// Column list shared by delivery queries; indices match `row_to_delivery`
+const DELIVERY_SELECT: &str = "SELECT delivery_id, webhook_id, event_id, event_type, payload, status,
+        attempts, next_attempt_at, last_status_code, last_error, created_at, completed_at
+ FROM webhook_deliveries";
+
+/// Column list shared by relay token queries; indices match `row_to_relay_token`
+const RELAY_TOKEN_SELECT: &str =
+    "SELECT auth_url, relay_peer_id, token, expires_at, updated_at, last_error FROM relay_tokens";
@@ -0,0 +1,188 @@
+use axum::extract::{Path, Query, Request, State};
+use axum::http::Uri;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tower::ServiceExt;
+use utoipa::{IntoParams, ToSchema};
+
+use harbor_lib::error::AppError;
+use harbor_lib::models::{CreateIdentityRequest, IdentityInfo};
+use harbor_lib::services::accounts_service::AccountInfo as RegistryEntry;
+
+use crate::access;
+use crate::accounts::Account;
+use crate::error::ApiError;
+use crate::state::AgentState;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    /// Account id, used in `/api/accounts/{accountId}/...` (the peer id)
+    pub id: String,
+    pub peer_id: String,
+    pub display_name: String,
+    pub bio: Option<String>,
+    pub avatar_hash: Option<String>,
+    pub created_at: i64,
+    pub last_accessed_at: Option<i64>,
+    /// Served by the unprefixed `/api/...` routes
+    pub is_active: bool,
+    pub is_unlocked: bool,
+    pub network_running: bool,
+}
+
+impl AccountInfo {
+    async fn new(entry: RegistryEntry, account: &Account, active_id: Option<&str>) -> Self {
+        Self {
+            is_active: active_id == Some(entry.id.as_str()),
+            is_unlocked: account.state.identity_service.is_unlocked(),
+            network_running: account.state.network.is_running().await,
+            id: entry.id,
+            peer_id: entry.peer_id,
+            display_name: entry.display_name,
+            bio: entry.bio,
+            avatar_hash: entry.avatar_hash,
+            created_at: entry.created_at,
+            last_accessed_at: entry.last_accessed_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAccountResponse {
+    pub account: AccountInfo,
+    pub identity: IdentityInfo,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct RemoveAccountQuery {
+    /// Also delete the account's databases and media
+    pub delete_data: Option<bool>,
+}
+
+/// GET /api/accounts
+#[utoipa::path(
+    get,
+    path = "/api/accounts",
+    tag = "accounts",
+    responses((status = 200, body = Vec<AccountInfo>)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn list_accounts(
+    State(state): State<Arc<AgentState>>,
+) -> Result<Json<Vec<AccountInfo>>, ApiError> {
+    let active_id = state.accounts.active_id()?;
+    let mut accounts = Vec::new();
+    for entry in state.accounts.list().await? {
+        let account = state.accounts.get(&entry.id).await?;
+        accounts.push(AccountInfo::new(entry, &account, active_id.as_deref()).await);
+    }
+    Ok(Json(accounts))
+}
+
+/// POST /api/accounts — create an identity in a new account (left unlocked)
+#[utoipa::path(
+    post,
+    path = "/api/accounts",
+    tag = "accounts",
+    request_body = CreateIdentityRequest,
+    responses((status = 200, body = CreateAccountResponse)),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn create_account(
+    State(state): State<Arc<AgentState>>,
+    Json(request): Json<CreateIdentityRequest>,
+) -> Result<Json<CreateAccountResponse>, ApiError> {
+    let (entry, identity) = state.accounts.create(request).await?;
+    let account = state.accounts.get(&entry.id).await?;
+    let active_id = state.accounts.active_id()?;
+    Ok(Json(CreateAccountResponse {
+        account: AccountInfo::new(entry, &account, active_id.as_deref()).await,
+        identity,
+    }))
+}
+
+/// DELETE /api/accounts/:accountId
+#[utoipa::path(
+    delete,
+    path = "/api/accounts/{accountId}",
+    tag = "accounts",
+    params(("accountId" = String, Path), RemoveAccountQuery),
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn remove_account(
+    State(state): State<Arc<AgentState>>,
+    Path(account_id): Path<String>,
+    Query(query): Query<RemoveAccountQuery>,
+) -> Result<Json<()>, ApiError> {
+    state
+        .accounts
+        .remove(&account_id, query.delete_data.unwrap_or(false))
+        .await?;
+    Ok(Json(()))
+}
+
+/// `/api/accounts/:accountId/<rest>` — served by the account's router as `/api/<rest>`
+pub async fn forward_to_account(
+    State(state): State<Arc<AgentState>>,
+    mut req: Request,
+) -> Response {
+    // Reject bad tokens before revealing whether the account exists
+    if let Err(e) = access::authenticate(&state.api_tokens, &req) {
+        return e.into_response();
+    }
+
+    let Some((account_id, path)) = split_account_path(req.uri()) else {
+        return ApiError(AppError::NotFound("Not found".to_string())).into_response();
+    };
+    let account = match state.accounts.get(&account_id).await {
+        Ok(account) => account,
+        Err(e) => return ApiError(e).into_response(),
+    };
+
+    match path.parse() {
+        Ok(uri) => *req.uri_mut() = uri,
+        Err(_) => {
+            return ApiError(AppError::Validation("Invalid request path".to_string()))
+                .into_response()
+        }
+    }
+    serve(&account, req).await
+}
+
+/// Unprefixed `/api/...` routes, served by the active account
+pub async fn forward_to_active(State(state): State<Arc<AgentState>>, req: Request) -> Response {
+    if let Err(e) = access::authenticate(&state.api_tokens, &req) {
+        return e.into_response();
+    }
+
+    match state.accounts.active().await {
+        Ok(account) => serve(&account, req).await,
+        Err(e) => ApiError(e).into_response(),
+    }
+}
+
+async fn serve(account: &Account, req: Request) -> Response {
+    match account.router.clone().oneshot(req).await {
+        Ok(response) => response,
+        Err(infallible) => match infallible {},
+    }
+}
+
+/// Account id and the account-relative path (with query) of
+/// `/api/accounts/:accountId/<rest>`, from the raw, still percent-encoded URI
+fn split_account_path(uri: &Uri) -> Option<(String, String)> {
+    let rest = uri.path().strip_prefix("/api/accounts/")?;
+    let (account_id, rest) = rest.split_once('/')?;
+    let path = match uri.query() {
+        Some(query) => format!("/api/{}?{}", rest, query),
+        None => format!("/api/{}", rest),
+    };
+    Some((account_id.to_string(), path))
+}
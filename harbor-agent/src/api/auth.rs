@@ -1,22 +1,26 @@
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::Json;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
 
 use harbor_lib::error::AppError;
 
-use crate::captcha_solver;
+use crate::agent_db::RelayTokenRow;
 use crate::error::ApiError;
 use crate::state::AppState;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthenticateRequest {
     /// The relay's HTTP auth URL (e.g. "http://52.200.206.197:4002")
     pub auth_url: String,
+    /// The relay's peer id, so `VerificationRequired` errors from it trigger
+    /// re-authentication
+    pub relay_peer_id: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthenticateResponse {
     pub token: String,
@@ -24,20 +28,59 @@ pub struct AuthenticateResponse {
     pub peer_id: String,
 }
 
-/// POST /api/auth/verify-agent - Authenticate with a relay using Isnad CAPTCHA
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayTokenInfo {
+    pub auth_url: String,
+    pub relay_peer_id: Option<String>,
+    /// Whether a token has been issued and has not expired
+    pub verified: bool,
+    pub expires_at: Option<i64>,
+    pub updated_at: i64,
+    /// Error of the latest attempt, if it failed
+    pub last_error: Option<String>,
+}
+
+impl From<RelayTokenRow> for RelayTokenInfo {
+    fn from(row: RelayTokenRow) -> Self {
+        let now = chrono::Utc::now().timestamp();
+        Self {
+            verified: row.token.is_some() && row.expires_at.is_some_and(|at| at > now),
+            auth_url: row.auth_url,
+            relay_peer_id: row.relay_peer_id,
+            expires_at: row.expires_at,
+            updated_at: row.updated_at,
+            last_error: row.last_error,
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ForgetRelayQuery {
+    /// Auth URL of the relay to stop renewing
+    pub auth_url: String,
+}
+
+/// POST /api/auth/verify-agent - Authenticate with a relay using Isnad CAPTCHA.
+/// The token is stored and renewed before it expires.
+#[utoipa::path(
+    post,
+    path = "/api/auth/verify-agent",
+    tag = "auth",
+    request_body = AuthenticateRequest,
+    responses((status = 200, body = AuthenticateResponse)),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn verify_agent(
     State(state): State<Arc<AppState>>,
     Json(req): Json<AuthenticateRequest>,
 ) -> Result<Json<AuthenticateResponse>, ApiError> {
-    // Need identity to get peer_id
-    let identity = state
-        .identity_service
-        .get_identity_info()?
-        .ok_or_else(|| AppError::NotFound("Identity not found. Create one first.".to_string()))?;
-
-    let result = captcha_solver::authenticate_with_relay(&req.auth_url, &identity.peer_id)
-        .await
-        .map_err(|e| AppError::Network(format!("CAPTCHA auth failed: {}", e)))?;
+    let result = state
+        .relay_auth
+        .authenticate(&req.auth_url, req.relay_peer_id.as_deref())
+        .await?;
 
     Ok(Json(AuthenticateResponse {
         token: result.token,
@@ -45,3 +88,40 @@ pub async fn verify_agent(
         peer_id: result.peer_id,
     }))
 }
+
+/// GET /api/auth/relays - Relays whose tokens are kept fresh
+#[utoipa::path(
+    get,
+    path = "/api/auth/relays",
+    tag = "auth",
+    responses((status = 200, body = Vec<RelayTokenInfo>)),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn list_relay_tokens(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<RelayTokenInfo>>, ApiError> {
+    let relays = state.relay_auth.list()?;
+    Ok(Json(relays.into_iter().map(Into::into).collect()))
+}
+
+/// DELETE /api/auth/relays - Stop renewing a relay's token
+#[utoipa::path(
+    delete,
+    path = "/api/auth/relays",
+    tag = "auth",
+    params(ForgetRelayQuery),
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn forget_relay(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ForgetRelayQuery>,
+) -> Result<Json<()>, ApiError> {
+    if !state
+        .relay_auth
+        .forget(query.auth_url.trim_end_matches('/'))?
+    {
+        return Err(AppError::NotFound(format!("Relay {} is not tracked", query.auth_url)).into());
+    }
+    Ok(Json(()))
+}
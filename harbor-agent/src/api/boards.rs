@@ -2,13 +2,14 @@ use axum::extract::{Path, Query, State};
 use axum::Json;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
 
 use harbor_lib::error::AppError;
 
 use crate::error::ApiError;
 use crate::state::AppState;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CommunityInfo {
     pub relay_peer_id: String,
@@ -18,7 +19,7 @@ pub struct CommunityInfo {
     pub last_sync_at: Option<i64>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BoardInfo {
     pub board_id: String,
@@ -28,7 +29,7 @@ pub struct BoardInfo {
     pub is_default: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BoardPostInfo {
     pub post_id: String,
@@ -40,28 +41,61 @@ pub struct BoardPostInfo {
     pub content_text: Option<String>,
     pub lamport_clock: i64,
     pub created_at: i64,
+    pub reply_to_post_id: Option<String>,
+    pub thread_root_post_id: Option<String>,
+    pub reply_count: i64,
 }
 
-#[derive(Deserialize)]
+impl From<harbor_lib::db::BoardPost> for BoardPostInfo {
+    fn from(p: harbor_lib::db::BoardPost) -> Self {
+        Self {
+            post_id: p.post_id,
+            board_id: p.board_id,
+            relay_peer_id: p.relay_peer_id,
+            author_peer_id: p.author_peer_id,
+            author_display_name: p.author_display_name,
+            content_type: p.content_type,
+            content_text: p.content_text,
+            lamport_clock: p.lamport_clock,
+            created_at: p.created_at,
+            reply_to_post_id: p.reply_to_post_id,
+            thread_root_post_id: p.thread_root_post_id,
+            reply_count: p.reply_count,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct JoinCommunityRequest {
     pub relay_address: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SubmitPostRequest {
     pub content_text: String,
+    /// Post this as a reply to an existing post
+    #[serde(default)]
+    pub reply_to_post_id: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
 pub struct BoardPostsQuery {
     pub limit: Option<i64>,
     pub before: Option<i64>,
 }
 
 /// GET /api/communities
+#[utoipa::path(
+    get,
+    path = "/api/communities",
+    tag = "boards",
+    responses((status = 200, body = Vec<CommunityInfo>)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_communities(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<CommunityInfo>>, ApiError> {
@@ -81,6 +115,14 @@ pub async fn get_communities(
 }
 
 /// POST /api/communities/join
+#[utoipa::path(
+    post,
+    path = "/api/communities/join",
+    tag = "boards",
+    request_body = JoinCommunityRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn join_community(
     State(state): State<Arc<AppState>>,
     Json(req): Json<JoinCommunityRequest>,
@@ -90,7 +132,7 @@ pub async fn join_community(
     let addr: libp2p::Multiaddr = req
         .relay_address
         .parse()
-        .map_err(|e| AppError::Network(format!("Invalid address: {}", e)))?;
+        .map_err(|e| AppError::Validation(format!("Invalid address: {}", e)))?;
 
     let relay_peer_id = addr
         .iter()
@@ -102,7 +144,7 @@ pub async fn join_community(
             }
         })
         .ok_or_else(|| {
-            AppError::Network("Address must contain peer ID (/p2p/...)".to_string())
+            AppError::Validation("Address must contain peer ID (/p2p/...)".to_string())
         })?;
 
     // Dial the relay first
@@ -117,6 +159,14 @@ pub async fn join_community(
 }
 
 /// DELETE /api/communities/:relayPeerId
+#[utoipa::path(
+    delete,
+    path = "/api/communities/{relayPeerId}",
+    tag = "boards",
+    params(("relayPeerId" = String, Path)),
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn leave_community(
     State(state): State<Arc<AppState>>,
     Path(relay_peer_id): Path<String>,
@@ -126,6 +176,14 @@ pub async fn leave_community(
 }
 
 /// GET /api/boards/:relayPeerId
+#[utoipa::path(
+    get,
+    path = "/api/boards/{relayPeerId}",
+    tag = "boards",
+    params(("relayPeerId" = String, Path)),
+    responses((status = 200, body = Vec<BoardInfo>)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_boards(
     State(state): State<Arc<AppState>>,
     Path(relay_peer_id): Path<String>,
@@ -146,6 +204,18 @@ pub async fn get_boards(
 }
 
 /// GET /api/boards/:relayPeerId/:boardId/posts
+#[utoipa::path(
+    get,
+    path = "/api/boards/{relayPeerId}/{boardId}/posts",
+    tag = "boards",
+    params(
+        ("relayPeerId" = String, Path),
+        ("boardId" = String, Path),
+        BoardPostsQuery,
+    ),
+    responses((status = 200, body = Vec<BoardPostInfo>)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_board_posts(
     State(state): State<Arc<AppState>>,
     Path((relay_peer_id, board_id)): Path<(String, String)>,
@@ -156,25 +226,94 @@ pub async fn get_board_posts(
         state
             .board_service
             .get_board_posts(&relay_peer_id, &board_id, limit, query.before)?;
-    Ok(Json(
-        posts
-            .into_iter()
-            .map(|p| BoardPostInfo {
-                post_id: p.post_id,
-                board_id: p.board_id,
-                relay_peer_id: p.relay_peer_id,
-                author_peer_id: p.author_peer_id,
-                author_display_name: p.author_display_name,
-                content_type: p.content_type,
-                content_text: p.content_text,
-                lamport_clock: p.lamport_clock,
-                created_at: p.created_at,
-            })
-            .collect(),
-    ))
+    Ok(Json(posts.into_iter().map(BoardPostInfo::from).collect()))
+}
+
+/// GET /api/boards/:relayPeerId/:boardId/threads
+#[utoipa::path(
+    get,
+    path = "/api/boards/{relayPeerId}/{boardId}/threads",
+    tag = "boards",
+    params(
+        ("relayPeerId" = String, Path),
+        ("boardId" = String, Path),
+        BoardPostsQuery,
+    ),
+    responses((status = 200, body = Vec<BoardPostInfo>)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_board_threads(
+    State(state): State<Arc<AppState>>,
+    Path((relay_peer_id, board_id)): Path<(String, String)>,
+    Query(query): Query<BoardPostsQuery>,
+) -> Result<Json<Vec<BoardPostInfo>>, ApiError> {
+    let limit = query.limit.unwrap_or(50);
+    let posts =
+        state
+            .board_service
+            .get_board_threads(&relay_peer_id, &board_id, limit, query.before)?;
+    Ok(Json(posts.into_iter().map(BoardPostInfo::from).collect()))
+}
+
+/// GET /api/boards/:relayPeerId/threads/:postId
+#[utoipa::path(
+    get,
+    path = "/api/boards/{relayPeerId}/threads/{postId}",
+    tag = "boards",
+    params(
+        ("relayPeerId" = String, Path),
+        ("postId" = String, Path),
+    ),
+    responses((status = 200, body = Vec<BoardPostInfo>)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_board_thread(
+    State(state): State<Arc<AppState>>,
+    Path((relay_peer_id, root_post_id)): Path<(String, String)>,
+) -> Result<Json<Vec<BoardPostInfo>>, ApiError> {
+    let posts = state.board_service.get_thread(&relay_peer_id, &root_post_id)?;
+    Ok(Json(posts.into_iter().map(BoardPostInfo::from).collect()))
+}
+
+/// POST /api/boards/:relayPeerId/threads/:postId/sync
+#[utoipa::path(
+    post,
+    path = "/api/boards/{relayPeerId}/threads/{postId}/sync",
+    tag = "boards",
+    params(
+        ("relayPeerId" = String, Path),
+        ("postId" = String, Path),
+    ),
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn sync_board_thread(
+    State(state): State<Arc<AppState>>,
+    Path((relay_peer_id, root_post_id)): Path<(String, String)>,
+) -> Result<Json<()>, ApiError> {
+    let handle = state.network.get_handle().await?;
+
+    let peer_id: libp2p::PeerId = relay_peer_id
+        .parse()
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
+
+    handle.get_board_thread(peer_id, root_post_id).await?;
+    Ok(Json(()))
 }
 
 /// POST /api/boards/:relayPeerId/:boardId/posts
+#[utoipa::path(
+    post,
+    path = "/api/boards/{relayPeerId}/{boardId}/posts",
+    tag = "boards",
+    params(
+        ("relayPeerId" = String, Path),
+        ("boardId" = String, Path),
+    ),
+    request_body = SubmitPostRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
 pub async fn submit_board_post(
     State(state): State<Arc<AppState>>,
     Path((relay_peer_id, board_id)): Path<(String, String)>,
@@ -184,16 +323,25 @@ pub async fn submit_board_post(
 
     let peer_id: libp2p::PeerId = relay_peer_id
         .parse()
-        .map_err(|e| AppError::Network(format!("Invalid peer ID: {}", e)))?;
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
 
     handle
-        .submit_board_post(peer_id, board_id, req.content_text)
+        .submit_board_post(peer_id, board_id, req.content_text, req.reply_to_post_id)
         .await?;
 
     Ok(Json(()))
 }
 
 /// DELETE /api/boards/posts/:postId
+#[utoipa::path(
+    delete,
+    path = "/api/boards/posts/{postId}",
+    tag = "boards",
+    params(("postId" = String, Path)),
+    request_body = DeleteBoardPostRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
 pub async fn delete_board_post(
     State(state): State<Arc<AppState>>,
     Path(post_id): Path<String>,
@@ -204,19 +352,30 @@ pub async fn delete_board_post(
     let peer_id: libp2p::PeerId = body
         .relay_peer_id
         .parse()
-        .map_err(|e| AppError::Network(format!("Invalid peer ID: {}", e)))?;
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
 
     handle.delete_board_post(peer_id, post_id).await?;
     Ok(Json(()))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct DeleteBoardPostRequest {
     pub relay_peer_id: String,
 }
 
 /// POST /api/boards/:relayPeerId/:boardId/sync
+#[utoipa::path(
+    post,
+    path = "/api/boards/{relayPeerId}/{boardId}/sync",
+    tag = "boards",
+    params(
+        ("relayPeerId" = String, Path),
+        ("boardId" = String, Path),
+    ),
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
 pub async fn sync_board(
     State(state): State<Arc<AppState>>,
     Path((relay_peer_id, board_id)): Path<(String, String)>,
@@ -225,8 +384,68 @@ pub async fn sync_board(
 
     let peer_id: libp2p::PeerId = relay_peer_id
         .parse()
-        .map_err(|e| AppError::Network(format!("Invalid peer ID: {}", e)))?;
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
 
     handle.get_board_posts(peer_id, board_id, None, 50).await?;
     Ok(Json(()))
 }
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardSubscriptionRequest {
+    #[serde(default)]
+    pub board_ids: Vec<String>,
+}
+
+/// POST /api/boards/:relayPeerId/subscribe
+///
+/// New posts on these boards arrive as `board_posts_received` events on
+/// `/api/events` instead of having to poll `/sync`.
+#[utoipa::path(
+    post,
+    path = "/api/boards/{relayPeerId}/subscribe",
+    tag = "boards",
+    params(("relayPeerId" = String, Path)),
+    request_body = BoardSubscriptionRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn subscribe_boards(
+    State(state): State<Arc<AppState>>,
+    Path(relay_peer_id): Path<String>,
+    Json(req): Json<BoardSubscriptionRequest>,
+) -> Result<Json<()>, ApiError> {
+    let handle = state.network.get_handle().await?;
+
+    let peer_id: libp2p::PeerId = relay_peer_id
+        .parse()
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
+
+    handle.subscribe_boards(peer_id, req.board_ids).await?;
+    Ok(Json(()))
+}
+
+/// POST /api/boards/:relayPeerId/unsubscribe (all boards if `boardIds` is empty)
+#[utoipa::path(
+    post,
+    path = "/api/boards/{relayPeerId}/unsubscribe",
+    tag = "boards",
+    params(("relayPeerId" = String, Path)),
+    request_body = BoardSubscriptionRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn unsubscribe_boards(
+    State(state): State<Arc<AppState>>,
+    Path(relay_peer_id): Path<String>,
+    Json(req): Json<BoardSubscriptionRequest>,
+) -> Result<Json<()>, ApiError> {
+    let handle = state.network.get_handle().await?;
+
+    let peer_id: libp2p::PeerId = relay_peer_id
+        .parse()
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
+
+    handle.unsubscribe_boards(peer_id, req.board_ids).await?;
+    Ok(Json(()))
+}
@@ -1,16 +1,21 @@
 use axum::extract::{Path, State};
+use axum::response::IntoResponse;
 use axum::Json;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use utoipa::ToSchema;
 use tracing::info;
 
 use harbor_lib::db::Capability;
 use harbor_lib::error::AppError;
+use harbor_lib::models::ContactBundle;
+use harbor_lib::services::SafetyNumber;
 
+use crate::api::identity::avatar_response;
 use crate::error::ApiError;
 use crate::state::AppState;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ContactInfo {
     pub id: i64,
@@ -24,7 +29,7 @@ pub struct ContactInfo {
     pub added_at: i64,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AddContactRequest {
     pub peer_id: String,
@@ -35,13 +40,28 @@ pub struct AddContactRequest {
     pub bio: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AddContactFromStringRequest {
     pub contact_string: String,
 }
 
+#[derive(Deserialize, ToSchema, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyContactRequest {
+    /// QR payload scanned from the contact's device. Without it the contact
+    /// is marked verified as-is, after comparing the numbers by other means.
+    pub scanned: Option<String>,
+}
+
 /// GET /api/contacts
+#[utoipa::path(
+    get,
+    path = "/api/contacts",
+    tag = "contacts",
+    responses((status = 200, body = Vec<ContactInfo>)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_active_contacts(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<ContactInfo>>, ApiError> {
@@ -65,6 +85,14 @@ pub async fn get_active_contacts(
 }
 
 /// POST /api/contacts
+#[utoipa::path(
+    post,
+    path = "/api/contacts",
+    tag = "contacts",
+    request_body = AddContactRequest,
+    responses((status = 200, body = i64)),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn add_contact(
     State(state): State<Arc<AppState>>,
     Json(req): Json<AddContactRequest>,
@@ -81,59 +109,22 @@ pub async fn add_contact(
 }
 
 /// POST /api/contacts/from-string
+#[utoipa::path(
+    post,
+    path = "/api/contacts/from-string",
+    tag = "contacts",
+    request_body = AddContactFromStringRequest,
+    responses((status = 200, body = String)),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn add_contact_from_string(
     State(state): State<Arc<AppState>>,
     Json(req): Json<AddContactFromStringRequest>,
 ) -> Result<Json<String>, ApiError> {
-    use base64::Engine;
-
-    let encoded = req
-        .contact_string
-        .strip_prefix("harbor://")
-        .ok_or_else(|| AppError::Validation("Invalid contact string format".to_string()))?;
-
-    let json_bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
-        .decode(encoded)
-        .map_err(|e| AppError::Validation(format!("Invalid contact encoding: {}", e)))?;
-
-    #[derive(serde::Deserialize)]
-    #[serde(rename_all = "camelCase")]
-    struct ContactBundle {
-        multiaddr: String,
-        display_name: String,
-        public_key: String,
-        x25519_public: String,
-        bio: Option<String>,
-        avatar_hash: Option<String>,
-    }
-
-    let bundle: ContactBundle = serde_json::from_slice(&json_bytes)
-        .map_err(|e| AppError::Validation(format!("Invalid contact data: {}", e)))?;
-
-    // The public key in contact strings is double-base64-encoded:
-    // base64(base64(32 raw bytes)). Decode both layers.
-    let pk_once = base64::engine::general_purpose::STANDARD
-        .decode(&bundle.public_key)
-        .map_err(|e| AppError::Validation(format!("Invalid public key: {}", e)))?;
-    let public_key = base64::engine::general_purpose::STANDARD
-        .decode(&pk_once)
-        .unwrap_or(pk_once);
-
-    // The x25519 key in contact strings is double-base64-encoded:
-    // base64(base64(32 raw bytes)). Decode both layers.
-    let x25519_once = base64::engine::general_purpose::STANDARD
-        .decode(&bundle.x25519_public)
-        .map_err(|e| AppError::Validation(format!("Invalid x25519 key: {}", e)))?;
-    let x25519_public = base64::engine::general_purpose::STANDARD
-        .decode(&x25519_once)
-        .unwrap_or(x25519_once);
-
-    let peer_id = bundle
-        .multiaddr
-        .split("/p2p/")
-        .last()
-        .ok_or_else(|| AppError::Validation("No peer ID in multiaddr".to_string()))?
-        .to_string();
+    let bundle = ContactBundle::parse(&req.contact_string)?;
+    let public_key = bundle.public_key_bytes()?;
+    let x25519_public = bundle.x25519_public_bytes()?;
+    let peer_id = bundle.peer_id()?;
 
     state.contacts_service.add_contact(
         &peer_id,
@@ -170,6 +161,14 @@ pub async fn add_contact_from_string(
 }
 
 /// DELETE /api/contacts/:peerId
+#[utoipa::path(
+    delete,
+    path = "/api/contacts/{peerId}",
+    tag = "contacts",
+    params(("peerId" = String, Path)),
+    responses((status = 200, body = bool)),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn remove_contact(
     State(state): State<Arc<AppState>>,
     Path(peer_id): Path<String>,
@@ -179,6 +178,14 @@ pub async fn remove_contact(
 }
 
 /// POST /api/contacts/:peerId/block
+#[utoipa::path(
+    post,
+    path = "/api/contacts/{peerId}/block",
+    tag = "contacts",
+    params(("peerId" = String, Path)),
+    responses((status = 200, body = bool)),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn block_contact(
     State(state): State<Arc<AppState>>,
     Path(peer_id): Path<String>,
@@ -186,3 +193,91 @@ pub async fn block_contact(
     let blocked = state.contacts_service.block_contact(&peer_id)?;
     Ok(Json(blocked))
 }
+
+/// GET /api/contacts/:peerId/avatar — raw bytes of a contact's avatar image,
+/// once it has been fetched from them
+#[utoipa::path(
+    get,
+    path = "/api/contacts/{peerId}/avatar",
+    tag = "contacts",
+    params(("peerId" = String, Path)),
+    responses(
+        (status = 200, content_type = "image/*", body = Vec<u8>),
+        (status = 404, description = "No avatar stored"),
+    ),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_contact_avatar(
+    State(state): State<Arc<AppState>>,
+    Path(peer_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let blob = state
+        .contacts_service
+        .get_avatar(&peer_id)?
+        .ok_or_else(|| AppError::NotFound(format!("No avatar stored for {}", peer_id)))?;
+    Ok(avatar_response(blob))
+}
+
+/// GET /api/contacts/:peerId/safety-number
+#[utoipa::path(
+    get,
+    path = "/api/contacts/{peerId}/safety-number",
+    tag = "contacts",
+    params(("peerId" = String, Path)),
+    responses((status = 200, body = SafetyNumber)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_safety_number(
+    State(state): State<Arc<AppState>>,
+    Path(peer_id): Path<String>,
+) -> Result<Json<SafetyNumber>, ApiError> {
+    let number = state.contacts_service.get_safety_number(&peer_id)?;
+    Ok(Json(number))
+}
+
+/// POST /api/contacts/:peerId/verify
+///
+/// Returns false if a scanned payload doesn't match our safety number.
+#[utoipa::path(
+    post,
+    path = "/api/contacts/{peerId}/verify",
+    tag = "contacts",
+    params(("peerId" = String, Path)),
+    request_body = Option<VerifyContactRequest>,
+    responses((status = 200, body = bool)),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn verify_contact(
+    State(state): State<Arc<AppState>>,
+    Path(peer_id): Path<String>,
+    body: Option<Json<VerifyContactRequest>>,
+) -> Result<Json<bool>, ApiError> {
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    let verified = match req.scanned {
+        Some(scanned) => state
+            .contacts_service
+            .verify_scanned_safety_number(&peer_id, &scanned)?,
+        None => {
+            state.contacts_service.mark_verified(&peer_id)?;
+            true
+        }
+    };
+    Ok(Json(verified))
+}
+
+/// DELETE /api/contacts/:peerId/verify
+#[utoipa::path(
+    delete,
+    path = "/api/contacts/{peerId}/verify",
+    tag = "contacts",
+    params(("peerId" = String, Path)),
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn clear_contact_verification(
+    State(state): State<Arc<AppState>>,
+    Path(peer_id): Path<String>,
+) -> Result<Json<()>, ApiError> {
+    state.contacts_service.clear_verification(&peer_id)?;
+    Ok(Json(()))
+}
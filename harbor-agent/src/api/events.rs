@@ -1,24 +1,139 @@
 use axum::extract::State;
+use axum::http::HeaderMap;
 use axum::response::sse::{Event, KeepAlive, Sse};
-use futures::stream::Stream;
+use futures::stream::{self, Stream};
+use std::collections::VecDeque;
 use std::convert::Infallible;
 use std::sync::Arc;
-use tokio_stream::wrappers::BroadcastStream;
-use tokio_stream::StreamExt;
+use tokio::sync::broadcast;
+use tracing::warn;
 
+use harbor_lib::p2p::NetworkEvent;
+
+use crate::agent_db::JournalEntry;
+use crate::event_journal::EventJournal;
 use crate::state::AppState;
 
-/// GET /api/events — Server-Sent Events stream of network events
+/// Journal entries read per replay batch
+const REPLAY_BATCH: usize = 500;
+
+struct StreamState {
+    journal: Arc<EventJournal>,
+    live: broadcast::Receiver<JournalEntry>,
+    /// Id of the last event sent to the client
+    last_id: i64,
+    backlog: VecDeque<JournalEntry>,
+    /// The backlog was a full batch, so the journal may hold more
+    backlog_truncated: bool,
+    /// Sent first when the client asked to resume from an evicted event
+    gap: Option<Event>,
+}
+
+impl StreamState {
+    fn refill(&mut self) {
+        match self.journal.db().events_after(self.last_id, REPLAY_BATCH) {
+            Ok(entries) => {
+                self.backlog_truncated = entries.len() == REPLAY_BATCH;
+                self.backlog = entries.into();
+            }
+            Err(e) => {
+                warn!("Failed to read event journal: {}", e);
+                self.backlog_truncated = false;
+            }
+        }
+    }
+}
+
+fn to_sse(entry: &JournalEntry) -> Event {
+    Event::default()
+        .id(entry.id.to_string())
+        .json_data(&entry.payload)
+        .unwrap_or_else(|_| Event::default().id(entry.id.to_string()).data("{}"))
+}
+
+/// GET /api/events — Server-Sent Events stream of network events.
+///
+/// Every event carries its journal id. Reconnecting with `Last-Event-ID`
+/// replays what was missed; if those events have already been evicted from
+/// the journal a `gap` event is sent first. Consumers that fall behind the
+/// live stream are caught up from the journal instead of losing events.
+#[utoipa::path(
+    get,
+    path = "/api/events",
+    tag = "events",
+    params(
+        ("Last-Event-ID" = Option<i64>, Header, description = "Resume after this event id"),
+        ("access_token" = Option<String>, Query, description = "Bearer token, for clients that cannot set headers"),
+    ),
+    responses((
+        status = 200,
+        description = "`data:` of each event is a JSON-encoded NetworkEvent",
+        content_type = "text/event-stream",
+        body = NetworkEvent,
+    )),
+    security(("bearer" = ["read"]))
+)]
 pub async fn event_stream(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
-    let rx = state.event_tx.subscribe();
+    let journal = state.journal.clone();
+    // Subscribe before reading the journal so nothing falls between the two
+    let live = journal.subscribe();
+
+    let bounds = journal.db().journal_bounds().unwrap_or_else(|e| {
+        warn!("Failed to read event journal bounds: {}", e);
+        None
+    });
+    let resume_from = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<i64>().ok());
+
+    let mut st = StreamState {
+        journal,
+        live,
+        last_id: bounds.map(|(_, newest)| newest).unwrap_or(0),
+        backlog: VecDeque::new(),
+        backlog_truncated: false,
+        gap: None,
+    };
+
+    if let Some(last_seen) = resume_from {
+        st.last_id = last_seen;
+        if let Some((oldest, _)) = bounds {
+            if oldest > last_seen + 1 {
+                let gap = serde_json::json!({ "missedAfter": last_seen, "resumedAt": oldest });
+                st.gap = Event::default().event("gap").json_data(gap).ok();
+            }
+        }
+        st.refill();
+    }
 
-    let stream = BroadcastStream::new(rx).filter_map(|result| match result {
-        Ok(value) => Some(Ok(Event::default()
-            .json_data(value)
-            .unwrap_or_else(|_| Event::default().data("{}")))),
-        Err(_) => None,
+    let stream = stream::unfold(st, |mut st| async move {
+        if let Some(gap) = st.gap.take() {
+            return Some((Ok(gap), st));
+        }
+        loop {
+            if let Some(entry) = st.backlog.pop_front() {
+                st.last_id = entry.id;
+                return Some((Ok(to_sse(&entry)), st));
+            }
+            if st.backlog_truncated {
+                st.refill();
+                continue;
+            }
+            match st.live.recv().await {
+                Ok(entry) if entry.id <= st.last_id => continue,
+                Ok(entry) => {
+                    st.last_id = entry.id;
+                    return Some((Ok(to_sse(&entry)), st));
+                }
+                // Fell behind the live buffer: catch up from the journal
+                Err(broadcast::error::RecvError::Lagged(_)) => st.refill(),
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
     });
 
     Sse::new(stream).keep_alive(KeepAlive::default())
@@ -0,0 +1,140 @@
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use harbor_lib::services::{FeedCursor, FeedItem};
+
+use crate::error::ApiError;
+use crate::state::AppState;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedItemInfo {
+    pub post_id: String,
+    pub author_peer_id: String,
+    pub author_display_name: Option<String>,
+    pub content_type: String,
+    pub content_text: Option<String>,
+    pub visibility: String,
+    pub lamport_clock: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub is_local: bool,
+}
+
+impl From<FeedItem> for FeedItemInfo {
+    fn from(item: FeedItem) -> Self {
+        Self {
+            post_id: item.post.post_id,
+            author_peer_id: item.post.author_peer_id,
+            author_display_name: item.author_display_name,
+            content_type: item.post.content_type,
+            content_text: item.post.content_text,
+            visibility: item.post.visibility.as_str().to_string(),
+            lamport_clock: item.post.lamport_clock,
+            created_at: item.post.created_at,
+            updated_at: item.post.updated_at,
+            is_local: item.post.is_local,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedPageResponse {
+    pub items: Vec<FeedItemInfo>,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct FeedQuery {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct WallQuery {
+    pub limit: Option<i64>,
+    pub before_timestamp: Option<i64>,
+}
+
+#[derive(Deserialize, Default, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncFeedRequest {
+    pub limit: Option<u32>,
+}
+
+/// GET /api/feed?limit=&cursor=
+#[utoipa::path(
+    get,
+    path = "/api/feed",
+    tag = "feed",
+    params(FeedQuery),
+    responses((status = 200, body = FeedPageResponse)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_feed(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FeedQuery>,
+) -> Result<Json<FeedPageResponse>, ApiError> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let cursor = query.cursor.as_deref().map(FeedCursor::parse).transpose()?;
+
+    let page = state.feed_service.get_feed_page(limit, cursor.as_ref())?;
+    Ok(Json(FeedPageResponse {
+        items: page.items.into_iter().map(FeedItemInfo::from).collect(),
+        next_cursor: page.next_cursor.map(|c| c.to_string()),
+    }))
+}
+
+/// GET /api/wall/:peerId
+#[utoipa::path(
+    get,
+    path = "/api/wall/{peerId}",
+    tag = "feed",
+    params(
+        ("peerId" = String, Path),
+        WallQuery,
+    ),
+    responses((status = 200, body = Vec<FeedItemInfo>)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_wall(
+    State(state): State<Arc<AppState>>,
+    Path(peer_id): Path<String>,
+    Query(query): Query<WallQuery>,
+) -> Result<Json<Vec<FeedItemInfo>>, ApiError> {
+    let items = state.feed_service.get_wall(
+        &peer_id,
+        query.limit.unwrap_or(50),
+        query.before_timestamp,
+    )?;
+    Ok(Json(items.into_iter().map(FeedItemInfo::from).collect()))
+}
+
+/// POST /api/sync/feed — pull recent posts from connected peers
+#[utoipa::path(
+    post,
+    path = "/api/sync/feed",
+    tag = "feed",
+    request_body = Option<SyncFeedRequest>,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn sync_feed(
+    State(state): State<Arc<AppState>>,
+    body: Option<Json<SyncFeedRequest>>,
+) -> Result<Json<()>, ApiError> {
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    let handle = state.network.get_handle().await?;
+    handle.sync_feed(req.limit.unwrap_or(50)).await?;
+    Ok(Json(()))
+}
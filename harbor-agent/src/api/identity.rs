@@ -1,39 +1,58 @@
+use axum::body::Bytes;
 use axum::extract::State;
+use axum::http::{header, HeaderMap};
+use axum::response::IntoResponse;
 use axum::Json;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use utoipa::ToSchema;
 
-use harbor_lib::models::{CreateIdentityRequest, IdentityInfo};
+use harbor_lib::db::Blob;
+use harbor_lib::error::AppError;
+use harbor_lib::models::IdentityInfo;
 
 use crate::error::ApiError;
 use crate::state::AppState;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct IdentityStatusResponse {
     pub has_identity: bool,
     pub is_unlocked: bool,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UnlockRequest {
     pub passphrase: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateDisplayNameRequest {
     pub display_name: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateBioRequest {
     pub bio: Option<String>,
 }
 
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SetAvatarResponse {
+    pub avatar_hash: String,
+}
+
 /// GET /api/identity
+#[utoipa::path(
+    get,
+    path = "/api/identity",
+    tag = "identity",
+    responses((status = 200, body = Option<IdentityInfo>)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_identity(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Option<IdentityInfo>>, ApiError> {
@@ -42,6 +61,13 @@ pub async fn get_identity(
 }
 
 /// GET /api/identity/status
+#[utoipa::path(
+    get,
+    path = "/api/identity/status",
+    tag = "identity",
+    responses((status = 200, body = IdentityStatusResponse)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_identity_status(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<IdentityStatusResponse>, ApiError> {
@@ -53,28 +79,15 @@ pub async fn get_identity_status(
     }))
 }
 
-/// POST /api/identity
-pub async fn create_identity(
-    State(state): State<Arc<AppState>>,
-    Json(request): Json<CreateIdentityRequest>,
-) -> Result<Json<IdentityInfo>, ApiError> {
-    let display_name = request.display_name.clone();
-    let bio = request.bio.clone();
-
-    let identity = state.identity_service.create_identity(request)?;
-
-    // Register in accounts registry
-    let _ = state.accounts_service.register_account(
-        identity.peer_id.clone(),
-        display_name,
-        bio,
-        identity.avatar_hash.clone(),
-    );
-
-    Ok(Json(identity))
-}
-
 /// POST /api/identity/unlock
+#[utoipa::path(
+    post,
+    path = "/api/identity/unlock",
+    tag = "identity",
+    request_body = UnlockRequest,
+    responses((status = 200, body = IdentityInfo)),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn unlock_identity(
     State(state): State<Arc<AppState>>,
     Json(req): Json<UnlockRequest>,
@@ -84,6 +97,13 @@ pub async fn unlock_identity(
 }
 
 /// POST /api/identity/lock
+#[utoipa::path(
+    post,
+    path = "/api/identity/lock",
+    tag = "identity",
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn lock_identity(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<()>, ApiError> {
@@ -92,19 +112,115 @@ pub async fn lock_identity(
 }
 
 /// PUT /api/identity/display-name
+#[utoipa::path(
+    put,
+    path = "/api/identity/display-name",
+    tag = "identity",
+    request_body = UpdateDisplayNameRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn update_display_name(
     State(state): State<Arc<AppState>>,
     Json(req): Json<UpdateDisplayNameRequest>,
 ) -> Result<Json<()>, ApiError> {
     state.identity_service.update_display_name(&req.display_name)?;
+    let peer_id = state.identity_service.get_peer_id()?;
+    state
+        .accounts_service
+        .update_account(&peer_id, Some(req.display_name), None, None)?;
     Ok(Json(()))
 }
 
 /// PUT /api/identity/bio
+#[utoipa::path(
+    put,
+    path = "/api/identity/bio",
+    tag = "identity",
+    request_body = UpdateBioRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn update_bio(
     State(state): State<Arc<AppState>>,
     Json(req): Json<UpdateBioRequest>,
 ) -> Result<Json<()>, ApiError> {
     state.identity_service.update_bio(req.bio.as_deref())?;
+    let peer_id = state.identity_service.get_peer_id()?;
+    state
+        .accounts_service
+        .update_account(&peer_id, None, Some(req.bio), None)?;
     Ok(Json(()))
 }
+
+/// GET /api/identity/avatar — raw bytes of our avatar image
+#[utoipa::path(
+    get,
+    path = "/api/identity/avatar",
+    tag = "identity",
+    responses(
+        (status = 200, content_type = "image/*", body = Vec<u8>),
+        (status = 404, description = "No avatar set"),
+    ),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_avatar(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let blob = state
+        .identity_service
+        .get_avatar()?
+        .ok_or_else(|| AppError::NotFound("No avatar set".to_string()))?;
+    Ok(avatar_response(blob))
+}
+
+/// PUT /api/identity/avatar — raw image bytes in the body, type in Content-Type.
+/// Contacts are sent the new profile and fetch the image by its hash.
+#[utoipa::path(
+    put,
+    path = "/api/identity/avatar",
+    tag = "identity",
+    request_body(content = Vec<u8>, content_type = "image/*"),
+    responses((status = 200, body = SetAvatarResponse)),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn set_avatar(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<SetAvatarResponse>, ApiError> {
+    let mime_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let avatar_hash = state.identity_service.set_avatar(&body, mime_type)?;
+    let peer_id = state.identity_service.get_peer_id()?;
+    state
+        .accounts_service
+        .update_account(&peer_id, None, None, Some(Some(avatar_hash.clone())))?;
+    Ok(Json(SetAvatarResponse { avatar_hash }))
+}
+
+/// DELETE /api/identity/avatar
+#[utoipa::path(
+    delete,
+    path = "/api/identity/avatar",
+    tag = "identity",
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn clear_avatar(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<()>, ApiError> {
+    state.identity_service.clear_avatar()?;
+    let peer_id = state.identity_service.get_peer_id()?;
+    state
+        .accounts_service
+        .update_account(&peer_id, None, None, Some(None))?;
+    Ok(Json(()))
+}
+
+/// Raw image response for a stored avatar
+pub(crate) fn avatar_response(blob: Blob) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, blob.mime_type)], blob.data)
+}
@@ -1,19 +1,23 @@
+use axum::body::Bytes;
 use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap};
+use axum::response::IntoResponse;
 use axum::Json;
 use libp2p::PeerId;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
 use tracing::info;
 
 use harbor_lib::error::AppError;
-use harbor_lib::p2p::protocols::messaging::{DirectMessage, MessagingCodec, MessagingMessage};
-use harbor_lib::services::{DecryptedMessage, OutgoingMessage};
+use harbor_lib::p2p::protocols::messaging::{MessagingCodec, MessagingMessage};
+use harbor_lib::services::{AttachmentInfo, DecryptedMessage, ExportFormat, MessageCursor};
 
 use crate::error::ApiError;
 use crate::state::AppState;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SendMessageRequest {
     pub peer_id: String,
@@ -22,7 +26,7 @@ pub struct SendMessageRequest {
     pub reply_to: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SendMessageResult {
     pub message_id: String,
@@ -30,7 +34,7 @@ pub struct SendMessageResult {
     pub sent_at: i64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MessageInfo {
     pub message_id: String,
@@ -40,11 +44,44 @@ pub struct MessageInfo {
     pub content: String,
     pub content_type: String,
     pub reply_to_message_id: Option<String>,
+    pub lamport_clock: i64,
     pub sent_at: i64,
     pub delivered_at: Option<i64>,
     pub read_at: Option<i64>,
     pub status: String,
     pub is_outgoing: bool,
+    pub edited_at: Option<i64>,
+    pub deleted_at: Option<i64>,
+    pub reactions: Vec<ReactionInfo>,
+    pub attachment: Option<AttachmentMeta>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReactionInfo {
+    pub peer_id: String,
+    pub reaction: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentMeta {
+    pub file_name: String,
+    pub mime_type: String,
+    pub size: u64,
+    /// Whether the file has been fetched from the sender
+    pub downloaded: bool,
+}
+
+impl From<AttachmentInfo> for AttachmentMeta {
+    fn from(info: AttachmentInfo) -> Self {
+        Self {
+            file_name: info.file_name,
+            mime_type: info.mime_type,
+            size: info.size,
+            downloaded: info.downloaded,
+        }
+    }
 }
 
 impl From<DecryptedMessage> for MessageInfo {
@@ -57,16 +94,25 @@ impl From<DecryptedMessage> for MessageInfo {
             content: msg.content,
             content_type: msg.content_type,
             reply_to_message_id: msg.reply_to_message_id,
+            lamport_clock: msg.lamport_clock,
             sent_at: msg.sent_at,
             delivered_at: msg.delivered_at,
             read_at: msg.read_at,
             status: msg.status,
             is_outgoing: msg.is_outgoing,
+            edited_at: msg.edited_at,
+            deleted_at: msg.deleted_at,
+            reactions: msg
+                .reactions
+                .into_iter()
+                .map(|(peer_id, reaction)| ReactionInfo { peer_id, reaction })
+                .collect(),
+            attachment: msg.attachment.map(AttachmentMeta::from),
         }
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ConversationInfo {
     pub conversation_id: String,
@@ -75,30 +121,85 @@ pub struct ConversationInfo {
     pub unread_count: i64,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 #[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
 pub struct MessagesQuery {
     pub limit: Option<i64>,
     pub before: Option<i64>,
 }
 
-fn outgoing_to_direct_message(outgoing: &OutgoingMessage) -> DirectMessage {
-    DirectMessage {
-        message_id: outgoing.message_id.clone(),
-        conversation_id: outgoing.conversation_id.clone(),
-        sender_peer_id: outgoing.sender_peer_id.clone(),
-        recipient_peer_id: outgoing.recipient_peer_id.clone(),
-        content_encrypted: outgoing.content_encrypted.clone(),
-        content_type: outgoing.content_type.clone(),
-        reply_to: outgoing.reply_to.clone(),
-        nonce_counter: outgoing.nonce_counter,
-        lamport_clock: outgoing.lamport_clock,
-        timestamp: outgoing.timestamp,
-        signature: outgoing.signature.clone(),
-    }
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagePageResponse {
+    /// Oldest first
+    pub messages: Vec<MessageInfo>,
+    /// Pass as `cursor` to fetch the page before this one; absent on the first page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct MessagePageQuery {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// json (default), markdown or mbox
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EditMessageRequest {
+    pub content: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReactionRequest {
+    pub reaction: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct AttachmentQuery {
+    pub file_name: String,
+}
+
+async fn send_to_peer(
+    state: &AppState,
+    peer_id: &str,
+    message: &MessagingMessage,
+) -> Result<(), AppError> {
+    let payload = MessagingCodec::encode(message)
+        .map_err(|e| AppError::Internal(format!("Failed to encode message: {}", e)))?;
+
+    let libp2p_peer_id = PeerId::from_str(peer_id)
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
+
+    let handle = state.network.get_handle().await?;
+    handle
+        .send_message(libp2p_peer_id, "message".to_string(), payload)
+        .await
 }
 
 /// POST /api/messages/send
+#[utoipa::path(
+    post,
+    path = "/api/messages/send",
+    tag = "messaging",
+    request_body = SendMessageRequest,
+    responses((status = 200, body = SendMessageResult)),
+    security(("bearer" = ["send"]))
+)]
 pub async fn send_message(
     State(state): State<Arc<AppState>>,
     Json(body): Json<SendMessageRequest>,
@@ -112,18 +213,9 @@ pub async fn send_message(
         body.reply_to.as_deref(),
     )?;
 
-    let direct_msg = outgoing_to_direct_message(&outgoing);
+    let direct_msg = outgoing.to_direct_message();
     let msg_wrapper = MessagingMessage::Message(direct_msg);
-    let payload = MessagingCodec::encode(&msg_wrapper)
-        .map_err(|e| AppError::Internal(format!("Failed to encode message: {}", e)))?;
-
-    let libp2p_peer_id = PeerId::from_str(&body.peer_id)
-        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
-
-    let handle = state.network.get_handle().await?;
-    handle
-        .send_message(libp2p_peer_id, "message".to_string(), payload)
-        .await?;
+    send_to_peer(&state, &body.peer_id, &msg_wrapper).await?;
 
     info!(
         "Message {} sent to peer {}",
@@ -138,6 +230,17 @@ pub async fn send_message(
 }
 
 /// GET /api/messages/:peerId
+#[utoipa::path(
+    get,
+    path = "/api/messages/{peerId}",
+    tag = "messaging",
+    params(
+        ("peerId" = String, Path),
+        MessagesQuery,
+    ),
+    responses((status = 200, body = Vec<MessageInfo>)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_messages(
     State(state): State<Arc<AppState>>,
     Path(peer_id): Path<String>,
@@ -151,7 +254,86 @@ pub async fn get_messages(
     Ok(Json(messages.into_iter().map(MessageInfo::from).collect()))
 }
 
+/// GET /api/conversations/:peerId/messages?limit=&cursor= — newest page
+/// first, each page oldest message first
+#[utoipa::path(
+    get,
+    path = "/api/conversations/{peerId}/messages",
+    tag = "messaging",
+    params(
+        ("peerId" = String, Path),
+        MessagePageQuery,
+    ),
+    responses((status = 200, body = MessagePageResponse)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_message_page(
+    State(state): State<Arc<AppState>>,
+    Path(peer_id): Path<String>,
+    Query(query): Query<MessagePageQuery>,
+) -> Result<Json<MessagePageResponse>, ApiError> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(MessageCursor::parse)
+        .transpose()?;
+
+    let page = state
+        .messaging_service
+        .get_conversation_page(&peer_id, limit, cursor.as_ref())?;
+    Ok(Json(MessagePageResponse {
+        messages: page.messages.into_iter().map(MessageInfo::from).collect(),
+        next_cursor: page.next_cursor.map(|c| c.to_string()),
+    }))
+}
+
+/// GET /api/conversations/:peerId/export?format= — the whole conversation as
+/// a download, with the signature status of every message
+#[utoipa::path(
+    get,
+    path = "/api/conversations/{peerId}/export",
+    tag = "messaging",
+    params(
+        ("peerId" = String, Path),
+        ExportQuery,
+    ),
+    responses((
+        status = 200,
+        description = "The conversation in the requested format",
+        content(
+            (String = "application/json"),
+            (String = "text/markdown"),
+            (String = "application/mbox"),
+        )
+    )),
+    security(("bearer" = ["read"]))
+)]
+pub async fn export_conversation(
+    State(state): State<Arc<AppState>>,
+    Path(peer_id): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let format = ExportFormat::parse(query.format.as_deref().unwrap_or("json"))?;
+    let export = state.messaging_service.export_conversation(&peer_id, format)?;
+    let disposition = format!("attachment; filename=\"{}\"", export.file_name);
+    Ok((
+        [
+            (header::CONTENT_TYPE, export.mime_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        export.content,
+    ))
+}
+
 /// GET /api/conversations
+#[utoipa::path(
+    get,
+    path = "/api/conversations",
+    tag = "messaging",
+    responses((status = 200, body = Vec<ConversationInfo>)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_conversations(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<ConversationInfo>>, ApiError> {
@@ -170,6 +352,14 @@ pub async fn get_conversations(
 }
 
 /// POST /api/conversations/:peerId/read
+#[utoipa::path(
+    post,
+    path = "/api/conversations/{peerId}/read",
+    tag = "messaging",
+    params(("peerId" = String, Path)),
+    responses((status = 200, body = i64)),
+    security(("bearer" = ["send"]))
+)]
 pub async fn mark_conversation_read(
     State(state): State<Arc<AppState>>,
     Path(peer_id): Path<String>,
@@ -178,7 +368,93 @@ pub async fn mark_conversation_read(
     Ok(Json(count))
 }
 
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TypingRequest {
+    /// Repeat `true` every few seconds while typing continues
+    pub typing: bool,
+}
+
+/// POST /api/conversations/:peerId/typing — tell a contact we started or stopped typing
+#[utoipa::path(
+    post,
+    path = "/api/conversations/{peerId}/typing",
+    tag = "messaging",
+    params(("peerId" = String, Path)),
+    request_body = TypingRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn set_typing(
+    State(state): State<Arc<AppState>>,
+    Path(peer_id): Path<String>,
+    Json(body): Json<TypingRequest>,
+) -> Result<Json<()>, ApiError> {
+    let peer_id = PeerId::from_str(&peer_id)
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
+    let handle = state.network.get_handle().await?;
+    handle.set_typing(peer_id, body.typing).await?;
+    Ok(Json(()))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionSetting {
+    /// Seconds messages are kept: 3600, 86400, 604800 or 2592000; null keeps
+    /// them forever
+    pub retention_secs: Option<u64>,
+}
+
+/// GET /api/conversations/:peerId/retention — how long the conversation's messages are kept
+#[utoipa::path(
+    get,
+    path = "/api/conversations/{peerId}/retention",
+    tag = "messaging",
+    params(("peerId" = String, Path)),
+    responses((status = 200, body = RetentionSetting)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_retention(
+    State(state): State<Arc<AppState>>,
+    Path(peer_id): Path<String>,
+) -> Result<Json<RetentionSetting>, ApiError> {
+    let retention_secs = state.messaging_service.get_retention(&peer_id)?;
+    Ok(Json(RetentionSetting { retention_secs }))
+}
+
+/// PUT /api/conversations/:peerId/retention — set how long both peers keep the
+/// conversation's messages. Sent again when the peer next connects if it is offline.
+#[utoipa::path(
+    put,
+    path = "/api/conversations/{peerId}/retention",
+    tag = "messaging",
+    params(("peerId" = String, Path)),
+    request_body = RetentionSetting,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn set_retention(
+    State(state): State<Arc<AppState>>,
+    Path(peer_id): Path<String>,
+    Json(body): Json<RetentionSetting>,
+) -> Result<Json<()>, ApiError> {
+    let update = state
+        .messaging_service
+        .set_retention(&peer_id, body.retention_secs)?;
+    if let Err(e) = send_to_peer(&state, &peer_id, &MessagingMessage::Retention(update)).await {
+        tracing::warn!("Failed to send retention update to {}: {}", peer_id, e);
+    }
+    Ok(Json(()))
+}
+
 /// GET /api/messages/unread
+#[utoipa::path(
+    get,
+    path = "/api/messages/unread",
+    tag = "messaging",
+    responses((status = 200, body = i64)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_total_unread_count(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<i64>, ApiError> {
@@ -186,3 +462,163 @@ pub async fn get_total_unread_count(
     let total: i64 = conversations.iter().map(|c| c.unread_count).sum();
     Ok(Json(total))
 }
+
+/// PUT /api/messages/:peerId/:messageId — edit one of our messages
+#[utoipa::path(
+    put,
+    path = "/api/messages/{peerId}/{messageId}",
+    tag = "messaging",
+    params(("peerId" = String, Path), ("messageId" = String, Path)),
+    request_body = EditMessageRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn edit_message(
+    State(state): State<Arc<AppState>>,
+    Path((peer_id, message_id)): Path<(String, String)>,
+    Json(body): Json<EditMessageRequest>,
+) -> Result<Json<()>, ApiError> {
+    let event = state
+        .messaging_service
+        .edit_message(&peer_id, &message_id, &body.content)?;
+    send_to_peer(&state, &peer_id, &MessagingMessage::Event(event)).await?;
+    Ok(Json(()))
+}
+
+/// DELETE /api/messages/:peerId/:messageId — delete one of our messages for everyone
+#[utoipa::path(
+    delete,
+    path = "/api/messages/{peerId}/{messageId}",
+    tag = "messaging",
+    params(("peerId" = String, Path), ("messageId" = String, Path)),
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn delete_message(
+    State(state): State<Arc<AppState>>,
+    Path((peer_id, message_id)): Path<(String, String)>,
+) -> Result<Json<()>, ApiError> {
+    let event = state
+        .messaging_service
+        .delete_message(&peer_id, &message_id)?;
+    send_to_peer(&state, &peer_id, &MessagingMessage::Event(event)).await?;
+    Ok(Json(()))
+}
+
+/// PUT /api/messages/:peerId/:messageId/reaction
+#[utoipa::path(
+    put,
+    path = "/api/messages/{peerId}/{messageId}/reaction",
+    tag = "messaging",
+    params(("peerId" = String, Path), ("messageId" = String, Path)),
+    request_body = ReactionRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn react_to_message(
+    State(state): State<Arc<AppState>>,
+    Path((peer_id, message_id)): Path<(String, String)>,
+    Json(body): Json<ReactionRequest>,
+) -> Result<Json<()>, ApiError> {
+    let event =
+        state
+            .messaging_service
+            .react_to_message(&peer_id, &message_id, Some(&body.reaction))?;
+    send_to_peer(&state, &peer_id, &MessagingMessage::Event(event)).await?;
+    Ok(Json(()))
+}
+
+/// DELETE /api/messages/:peerId/:messageId/reaction — withdraw our reaction
+#[utoipa::path(
+    delete,
+    path = "/api/messages/{peerId}/{messageId}/reaction",
+    tag = "messaging",
+    params(("peerId" = String, Path), ("messageId" = String, Path)),
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn remove_reaction(
+    State(state): State<Arc<AppState>>,
+    Path((peer_id, message_id)): Path<(String, String)>,
+) -> Result<Json<()>, ApiError> {
+    let event = state
+        .messaging_service
+        .react_to_message(&peer_id, &message_id, None)?;
+    send_to_peer(&state, &peer_id, &MessagingMessage::Event(event)).await?;
+    Ok(Json(()))
+}
+
+/// POST /api/messages/:peerId/attachments — raw file bytes in the body, type in
+/// Content-Type. The peer fetches the encrypted file after the message arrives.
+#[utoipa::path(
+    post,
+    path = "/api/messages/{peerId}/attachments",
+    tag = "messaging",
+    params(("peerId" = String, Path), AttachmentQuery),
+    request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+    responses((status = 200, body = SendMessageResult)),
+    security(("bearer" = ["send"]))
+)]
+pub async fn send_attachment(
+    State(state): State<Arc<AppState>>,
+    Path(peer_id): Path<String>,
+    Query(query): Query<AttachmentQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<SendMessageResult>, ApiError> {
+    let mime_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/octet-stream");
+    let outgoing =
+        state
+            .messaging_service
+            .send_attachment(&peer_id, &query.file_name, mime_type, &body)?;
+
+    let direct_msg = outgoing.to_direct_message();
+    send_to_peer(&state, &peer_id, &MessagingMessage::Message(direct_msg)).await?;
+
+    info!(
+        "Attachment {} sent to peer {}",
+        outgoing.message_id, peer_id
+    );
+
+    Ok(Json(SendMessageResult {
+        message_id: outgoing.message_id,
+        conversation_id: outgoing.conversation_id,
+        sent_at: outgoing.timestamp,
+    }))
+}
+
+/// GET /api/messages/:peerId/:messageId/attachment — raw bytes of an attachment
+#[utoipa::path(
+    get,
+    path = "/api/messages/{peerId}/{messageId}/attachment",
+    tag = "messaging",
+    params(("peerId" = String, Path), ("messageId" = String, Path)),
+    responses(
+        (status = 200, content_type = "application/octet-stream", body = Vec<u8>),
+        (status = 404, description = "No attachment, or not fetched yet"),
+    ),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_attachment(
+    State(state): State<Arc<AppState>>,
+    Path((peer_id, message_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    let attachment = state
+        .messaging_service
+        .get_attachment(&peer_id, &message_id)?
+        .ok_or_else(|| AppError::NotFound("Attachment not fetched yet".to_string()))?;
+    let disposition = format!(
+        "attachment; filename=\"{}\"",
+        attachment.file_name.replace('"', "")
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, attachment.mime_type),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        attachment.data,
+    ))
+}
@@ -1,42 +1,73 @@
+pub mod accounts;
 pub mod auth;
 pub mod boards;
 pub mod contacts;
 pub mod events;
+pub mod feed;
 pub mod identity;
 pub mod messaging;
 pub mod network;
+pub mod openapi;
 pub mod permissions;
+pub mod posts;
+pub mod scheduled;
+pub mod search;
+pub mod tokens;
+pub mod webhooks;
 
-use axum::routing::{delete, get, post, put};
+use axum::extract::{DefaultBodyLimit, Request, State};
+use axum::middleware::{self, Next};
+use axum::routing::{any, delete, get, post, put};
 use axum::Router;
+use harbor_lib::services::{MAX_ATTACHMENT_BYTES, MAX_AVATAR_BYTES};
 use std::sync::Arc;
 
-use crate::state::AppState;
+use crate::access::{self, ApiTokens, Scope};
+use crate::state::{AgentState, AppState};
+
+/// Build the agent's API router. Every route except `/api/openapi.json`
+/// requires a bearer token carrying the route's scope: reads need `read`,
+/// outgoing actions need `send`, and account, identity, network, contact,
+/// permission and token management need `admin`.
+///
+/// Account routes are served at `/api/accounts/:accountId/...` and, without
+/// the prefix, for the active account.
+pub fn router(state: Arc<AgentState>) -> Router {
+    let read = Router::new().route("/api/accounts", get(accounts::list_accounts));
+
+    let admin = Router::new()
+        // Accounts
+        .route("/api/accounts", post(accounts::create_account))
+        .route("/api/accounts/:accountId", delete(accounts::remove_account))
+        // API tokens
+        .route("/api/tokens", get(tokens::list_tokens))
+        .route("/api/tokens", post(tokens::create_token))
+        .route("/api/tokens/:name", delete(tokens::revoke_token));
 
-pub fn router() -> Router<Arc<AppState>> {
     Router::new()
+        .route("/api/openapi.json", get(openapi::openapi_json))
+        .merge(scoped(read, &state.api_tokens, Scope::Read))
+        .merge(scoped(admin, &state.api_tokens, Scope::Admin))
+        .route(
+            "/api/accounts/:accountId/*rest",
+            any(accounts::forward_to_account),
+        )
+        .fallback(accounts::forward_to_active)
+        .with_state(state)
+}
+
+/// Routes of one account, as served by [`router`] below
+/// `/api/accounts/:accountId`
+pub fn account_router(state: Arc<AppState>) -> Router {
+    let read = Router::new()
         // Identity
         .route("/api/identity", get(identity::get_identity))
-        .route("/api/identity", post(identity::create_identity))
         .route("/api/identity/status", get(identity::get_identity_status))
-        .route("/api/identity/unlock", post(identity::unlock_identity))
-        .route("/api/identity/lock", post(identity::lock_identity))
-        .route(
-            "/api/identity/display-name",
-            put(identity::update_display_name),
-        )
-        .route("/api/identity/bio", put(identity::update_bio))
+        .route("/api/identity/avatar", get(identity::get_avatar))
         // Network
-        .route("/api/network/start", post(network::start_network))
-        .route("/api/network/stop", post(network::stop_network))
         .route("/api/network/status", get(network::get_network_status))
         .route("/api/network/peers", get(network::get_connected_peers))
-        .route("/api/network/connect", post(network::connect_to_peer))
-        .route("/api/network/relay", post(network::add_relay_server))
-        .route(
-            "/api/network/relays/public",
-            post(network::connect_to_public_relays),
-        )
+        .route("/api/network/presence", get(network::get_presence))
         .route(
             "/api/network/addresses",
             get(network::get_listening_addresses),
@@ -50,16 +81,173 @@ pub fn router() -> Router<Arc<AppState>> {
             get(network::get_shareable_contact_string),
         )
         // Messaging
-        .route("/api/messages/send", post(messaging::send_message))
         .route("/api/messages/unread", get(messaging::get_total_unread_count))
         .route("/api/messages/:peerId", get(messaging::get_messages))
+        .route(
+            "/api/messages/:peerId/:messageId/attachment",
+            get(messaging::get_attachment),
+        )
         .route("/api/conversations", get(messaging::get_conversations))
+        .route(
+            "/api/conversations/:peerId/messages",
+            get(messaging::get_message_page),
+        )
+        .route(
+            "/api/conversations/:peerId/export",
+            get(messaging::export_conversation),
+        )
+        .route(
+            "/api/conversations/:peerId/retention",
+            get(messaging::get_retention),
+        )
+        // Contacts
+        .route("/api/contacts", get(contacts::get_active_contacts))
+        .route(
+            "/api/contacts/:peerId/safety-number",
+            get(contacts::get_safety_number),
+        )
+        .route("/api/contacts/:peerId/avatar", get(contacts::get_contact_avatar))
+        // Permissions
+        .route(
+            "/api/permissions/chat-peers",
+            get(permissions::get_chat_peers),
+        )
+        // Boards / Communities
+        .route("/api/communities", get(boards::get_communities))
+        .route("/api/boards/:relayPeerId", get(boards::get_boards))
+        .route(
+            "/api/boards/:relayPeerId/:boardId/posts",
+            get(boards::get_board_posts),
+        )
+        .route(
+            "/api/boards/:relayPeerId/:boardId/threads",
+            get(boards::get_board_threads),
+        )
+        .route(
+            "/api/boards/:relayPeerId/threads/:postId",
+            get(boards::get_board_thread),
+        )
+        // Posts, likes and media
+        .route("/api/posts", get(posts::get_my_posts))
+        .route("/api/posts/liked", get(posts::get_my_liked_posts))
+        .route(
+            "/api/posts/likes/batch",
+            post(posts::get_posts_likes_batch),
+        )
+        .route("/api/posts/:postId", get(posts::get_post))
+        .route("/api/posts/:postId/media", get(posts::get_post_media))
+        .route("/api/posts/:postId/likes", get(posts::get_post_likes))
+        .route("/api/media/:mediaHash", get(posts::get_media))
+        // Feed / walls
+        .route("/api/feed", get(feed::get_feed))
+        .route("/api/wall/:peerId", get(feed::get_wall))
+        // Search
+        .route("/api/search", get(search::search))
+        // Scheduled posts and messages
+        .route("/api/scheduled", get(scheduled::get_scheduled_items))
+        // Events (SSE)
+        .route("/api/events", get(events::event_stream));
+
+    let send = Router::new()
+        // Messaging
+        .route("/api/messages/send", post(messaging::send_message))
         .route(
             "/api/conversations/:peerId/read",
             post(messaging::mark_conversation_read),
         )
+        .route(
+            "/api/conversations/:peerId/typing",
+            post(messaging::set_typing),
+        )
+        .route(
+            "/api/conversations/:peerId/retention",
+            put(messaging::set_retention),
+        )
+        .route(
+            "/api/messages/:peerId/:messageId",
+            put(messaging::edit_message).delete(messaging::delete_message),
+        )
+        .route(
+            "/api/messages/:peerId/:messageId/reaction",
+            put(messaging::react_to_message).delete(messaging::remove_reaction),
+        )
+        .route(
+            "/api/messages/:peerId/attachments",
+            post(messaging::send_attachment).layer(DefaultBodyLimit::max(MAX_ATTACHMENT_BYTES)),
+        )
+        // Boards
+        .route(
+            "/api/boards/:relayPeerId/:boardId/posts",
+            post(boards::submit_board_post),
+        )
+        .route(
+            "/api/boards/posts/:postId",
+            delete(boards::delete_board_post),
+        )
+        .route(
+            "/api/boards/:relayPeerId/threads/:postId/sync",
+            post(boards::sync_board_thread),
+        )
+        .route(
+            "/api/boards/:relayPeerId/subscribe",
+            post(boards::subscribe_boards),
+        )
+        .route(
+            "/api/boards/:relayPeerId/unsubscribe",
+            post(boards::unsubscribe_boards),
+        )
+        .route(
+            "/api/boards/:relayPeerId/:boardId/sync",
+            post(boards::sync_board),
+        )
+        // Posts, likes and media
+        .route("/api/posts", post(posts::create_post))
+        .route("/api/posts/:postId", put(posts::update_post))
+        .route("/api/posts/:postId", delete(posts::delete_post))
+        .route(
+            "/api/posts/:postId/media",
+            post(posts::upload_post_media).layer(DefaultBodyLimit::max(posts::MAX_MEDIA_BYTES)),
+        )
+        .route("/api/posts/:postId/likes", post(posts::like_post))
+        .route("/api/posts/:postId/likes", delete(posts::unlike_post))
+        // Scheduled posts and messages
+        .route("/api/scheduled/posts", post(scheduled::schedule_post))
+        .route("/api/scheduled/messages", post(scheduled::schedule_message))
+        .route(
+            "/api/scheduled/:scheduleId",
+            delete(scheduled::cancel_scheduled_item),
+        )
+        // Presence
+        .route("/api/network/presence", put(network::set_presence))
+        // Feed sync
+        .route("/api/sync/feed", post(feed::sync_feed));
+
+    let admin = Router::new()
+        // Identity
+        .route("/api/identity/unlock", post(identity::unlock_identity))
+        .route("/api/identity/lock", post(identity::lock_identity))
+        .route(
+            "/api/identity/display-name",
+            put(identity::update_display_name),
+        )
+        .route("/api/identity/bio", put(identity::update_bio))
+        .route(
+            "/api/identity/avatar",
+            put(identity::set_avatar)
+                .delete(identity::clear_avatar)
+                .layer(DefaultBodyLimit::max(MAX_AVATAR_BYTES)),
+        )
+        // Network
+        .route("/api/network/start", post(network::start_network))
+        .route("/api/network/stop", post(network::stop_network))
+        .route("/api/network/restart", post(network::restart_network))
+        .route("/api/network/connect", post(network::connect_to_peer))
+        .route("/api/network/relay", post(network::add_relay_server))
+        .route(
+            "/api/network/relays/public",
+            post(network::connect_to_public_relays),
+        )
         // Contacts
-        .route("/api/contacts", get(contacts::get_active_contacts))
         .route("/api/contacts", post(contacts::add_contact))
         .route(
             "/api/contacts/from-string",
@@ -70,6 +258,10 @@ pub fn router() -> Router<Arc<AppState>> {
             "/api/contacts/:peerId/block",
             post(contacts::block_contact),
         )
+        .route(
+            "/api/contacts/:peerId/verify",
+            post(contacts::verify_contact).delete(contacts::clear_contact_verification),
+        )
         // Permissions
         .route("/api/permissions/grant", post(permissions::grant_permission))
         .route(
@@ -80,36 +272,47 @@ pub fn router() -> Router<Arc<AppState>> {
             "/api/permissions/:grantId",
             delete(permissions::revoke_permission),
         )
-        .route(
-            "/api/permissions/chat-peers",
-            get(permissions::get_chat_peers),
-        )
-        // Boards / Communities
-        .route("/api/communities", get(boards::get_communities))
+        // Communities
         .route("/api/communities/join", post(boards::join_community))
         .route(
             "/api/communities/:relayPeerId",
             delete(boards::leave_community),
         )
-        .route("/api/boards/:relayPeerId", get(boards::get_boards))
-        .route(
-            "/api/boards/:relayPeerId/{boardId}/posts",
-            get(boards::get_board_posts),
-        )
-        .route(
-            "/api/boards/:relayPeerId/{boardId}/posts",
-            post(boards::submit_board_post),
-        )
+        // Auth (Isnad CAPTCHA)
+        .route("/api/auth/verify-agent", post(auth::verify_agent))
+        .route("/api/auth/relays", get(auth::list_relay_tokens))
+        .route("/api/auth/relays", delete(auth::forget_relay))
+        // Webhooks
+        .route("/api/webhooks", get(webhooks::list_webhooks))
+        .route("/api/webhooks", post(webhooks::create_webhook))
+        .route("/api/webhooks/:webhookId", put(webhooks::update_webhook))
+        .route("/api/webhooks/:webhookId", delete(webhooks::delete_webhook))
         .route(
-            "/api/boards/posts/:postId",
-            delete(boards::delete_board_post),
+            "/api/webhooks/:webhookId/deliveries",
+            get(webhooks::list_deliveries),
         )
         .route(
-            "/api/boards/:relayPeerId/{boardId}/sync",
-            post(boards::sync_board),
-        )
-        // Auth (Isnad CAPTCHA)
-        .route("/api/auth/verify-agent", post(auth::verify_agent))
-        // Events (SSE)
-        .route("/api/events", get(events::event_stream))
+            "/api/webhooks/:webhookId/deliveries/:deliveryId/retry",
+            post(webhooks::retry_delivery),
+        );
+
+    Router::new()
+        .merge(scoped(read, &state.api_tokens, Scope::Read))
+        .merge(scoped(send, &state.api_tokens, Scope::Send))
+        .merge(scoped(admin, &state.api_tokens, Scope::Admin))
+        .with_state(state)
+}
+
+/// Guard every route in `routes` with `scope`
+fn scoped<S: Clone + Send + Sync + 'static>(
+    routes: Router<S>,
+    tokens: &Arc<ApiTokens>,
+    scope: Scope,
+) -> Router<S> {
+    routes.route_layer(middleware::from_fn_with_state(
+        tokens.clone(),
+        move |tokens: State<Arc<ApiTokens>>, req: Request, next: Next| {
+            access::require_scope(tokens, scope, req, next)
+        },
+    ))
 }
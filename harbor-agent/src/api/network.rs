@@ -2,15 +2,16 @@ use axum::extract::State;
 use axum::Json;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tracing::info;
+use utoipa::ToSchema;
 
 use harbor_lib::error::AppError;
-use harbor_lib::p2p::{NetworkConfig, NetworkHandle, NetworkService, NetworkStats, PeerInfo};
+use harbor_lib::models::ContactBundle;
+use harbor_lib::p2p::{NetworkStats, PeerInfo, PeerPresence, PresenceStatus};
 
 use crate::error::ApiError;
 use crate::state::AppState;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct NetworkStatusResponse {
     pub running: bool,
@@ -18,110 +19,78 @@ pub struct NetworkStatusResponse {
     pub stats: Option<NetworkStats>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ConnectRequest {
     pub multiaddr: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct RelayRequest {
     pub multiaddr: String,
 }
 
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SetPresenceRequest {
+    pub status: PresenceStatus,
+    pub status_text: Option<String>,
+}
+
 /// POST /api/network/start
+#[utoipa::path(
+    post,
+    path = "/api/network/start",
+    tag = "network",
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn start_network(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<()>, ApiError> {
-    // Check if identity is unlocked
-    if !state.identity_service.is_unlocked() {
-        return Err(AppError::PermissionDenied(
-            "Identity must be unlocked to start network".to_string(),
-        )
-        .into());
-    }
-
-    // Check if network is already running
-    if state.network.is_running().await {
-        return Ok(Json(()));
-    }
-
-    // Get the unlocked keys to create a libp2p keypair
-    let unlocked_keys = state.identity_service.get_unlocked_keys()?;
-    let ed25519_bytes = unlocked_keys.ed25519_signing.to_bytes();
-
-    // Convert to libp2p keypair
-    let keypair = harbor_lib::p2p::swarm::ed25519_to_libp2p_keypair(&ed25519_bytes)?;
-    let network_peer_id = libp2p::PeerId::from(keypair.public());
-
-    // Verify peer ID matches stored identity
-    if let Ok(Some(identity_info)) = state.identity_service.get_identity_info() {
-        info!(
-            "PEER ID CHECK - Stored: {} vs Network: {}",
-            identity_info.peer_id, network_peer_id
-        );
-        if identity_info.peer_id != network_peer_id.to_string() {
-            tracing::error!("PEER ID MISMATCH! Stored peer ID does not match network peer ID.");
-        }
-    }
-
-    // Create network service
-    let config = NetworkConfig::default();
-    let identity_arc = state.identity_service.clone();
-    let (mut service, handle, mut event_rx) =
-        NetworkService::new(config, identity_arc, keypair)?;
-
-    // Inject services
-    service.set_messaging_service(state.messaging_service.clone());
-    service.set_contacts_service(state.contacts_service.clone());
-    service.set_permissions_service(state.permissions_service.clone());
-    service.set_posts_service(state.posts_service.clone());
-    service.set_content_sync_service(state.content_sync_service.clone());
-
-    // Store the handle
-    state.network.set_handle(handle).await;
-
-    // Spawn the network service
-    tokio::spawn(async move {
-        info!("Network service starting in background task");
-        service.run().await;
-        info!("Network service stopped");
-    });
-
-    // Spawn event forwarding to SSE broadcast channel
-    let event_tx = state.event_tx.clone();
-    tokio::spawn(async move {
-        while let Some(event) = event_rx.recv().await {
-            info!("Network event: {:?}", event);
-            if let Ok(value) = serde_json::to_value(&event) {
-                let _ = event_tx.send(value);
-            }
-        }
-    });
-
-    info!("Network started successfully");
+    state.node.start_network().await?;
     Ok(Json(()))
 }
 
 /// POST /api/network/stop
+#[utoipa::path(
+    post,
+    path = "/api/network/stop",
+    tag = "network",
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn stop_network(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<()>, ApiError> {
-    let maybe_handle: Option<NetworkHandle> = {
-        let mut guard = state.network.handle.write().await;
-        guard.take()
-    };
-
-    if let Some(handle) = maybe_handle {
-        handle.shutdown().await?;
-        info!("Network stopped");
-    }
+    state.node.stop_network().await?;
+    Ok(Json(()))
+}
 
+/// POST /api/network/restart
+#[utoipa::path(
+    post,
+    path = "/api/network/restart",
+    tag = "network",
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn restart_network(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<()>, ApiError> {
+    state.node.restart_network().await?;
     Ok(Json(()))
 }
 
 /// GET /api/network/status
+#[utoipa::path(
+    get,
+    path = "/api/network/status",
+    tag = "network",
+    responses((status = 200, body = NetworkStatusResponse)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_network_status(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<NetworkStatusResponse>, ApiError> {
@@ -136,6 +105,13 @@ pub async fn get_network_status(
 }
 
 /// GET /api/network/peers
+#[utoipa::path(
+    get,
+    path = "/api/network/peers",
+    tag = "network",
+    responses((status = 200, body = Vec<PeerInfo>)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_connected_peers(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<PeerInfo>>, ApiError> {
@@ -144,7 +120,49 @@ pub async fn get_connected_peers(
     Ok(Json(peers))
 }
 
+/// GET /api/network/presence — presence of connected peers that share it with us
+#[utoipa::path(
+    get,
+    path = "/api/network/presence",
+    tag = "network",
+    responses((status = 200, body = Vec<PeerPresence>)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_presence(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<PeerPresence>>, ApiError> {
+    let handle = state.network.get_handle().await?;
+    let presence = handle.get_presence().await?;
+    Ok(Json(presence))
+}
+
+/// PUT /api/network/presence — set our presence and share it with peers that may read our wall
+#[utoipa::path(
+    put,
+    path = "/api/network/presence",
+    tag = "network",
+    request_body = SetPresenceRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn set_presence(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SetPresenceRequest>,
+) -> Result<Json<()>, ApiError> {
+    let handle = state.network.get_handle().await?;
+    handle.set_presence(req.status, req.status_text).await?;
+    Ok(Json(()))
+}
+
 /// POST /api/network/connect
+#[utoipa::path(
+    post,
+    path = "/api/network/connect",
+    tag = "network",
+    request_body = ConnectRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn connect_to_peer(
     State(state): State<Arc<AppState>>,
     Json(req): Json<ConnectRequest>,
@@ -161,6 +179,14 @@ pub async fn connect_to_peer(
 }
 
 /// POST /api/network/relay
+#[utoipa::path(
+    post,
+    path = "/api/network/relay",
+    tag = "network",
+    request_body = RelayRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn add_relay_server(
     State(state): State<Arc<AppState>>,
     Json(req): Json<RelayRequest>,
@@ -177,6 +203,13 @@ pub async fn add_relay_server(
 }
 
 /// POST /api/network/relays/public
+#[utoipa::path(
+    post,
+    path = "/api/network/relays/public",
+    tag = "network",
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn connect_to_public_relays(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<()>, ApiError> {
@@ -186,6 +219,13 @@ pub async fn connect_to_public_relays(
 }
 
 /// GET /api/network/addresses
+#[utoipa::path(
+    get,
+    path = "/api/network/addresses",
+    tag = "network",
+    responses((status = 200, body = Vec<String>)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_listening_addresses(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<String>>, ApiError> {
@@ -195,6 +235,13 @@ pub async fn get_listening_addresses(
 }
 
 /// GET /api/network/shareable
+#[utoipa::path(
+    get,
+    path = "/api/network/shareable",
+    tag = "network",
+    responses((status = 200, body = Vec<String>)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_shareable_addresses(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<String>>, ApiError> {
@@ -229,64 +276,29 @@ pub async fn get_shareable_addresses(
 }
 
 /// GET /api/network/contact-string
+#[utoipa::path(
+    get,
+    path = "/api/network/contact-string",
+    tag = "network",
+    responses((status = 200, body = String)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_shareable_contact_string(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<String>, ApiError> {
-    use base64::Engine;
-
     let handle = state.network.get_handle().await?;
     let stats = handle.get_stats().await?;
 
-    let identity = state
-        .identity_service
-        .get_identity()?
-        .ok_or_else(|| AppError::NotFound("Identity not found".to_string()))?;
-
     let keys = state
         .identity_service
         .get_identity_info()?
         .ok_or_else(|| AppError::NotFound("Identity keys not found".to_string()))?;
 
-    let multiaddr = if !stats.relay_addresses.is_empty() {
-        stats.relay_addresses[0].clone()
-    } else if !stats.external_addresses.is_empty() {
-        let addr = &stats.external_addresses[0];
-        if addr.contains("/p2p/") {
-            addr.clone()
-        } else {
-            format!("{}/p2p/{}", addr, identity.peer_id)
-        }
-    } else {
-        return Err(AppError::Network(
+    let multiaddr = stats.shareable_address(&keys.peer_id).ok_or_else(|| {
+        AppError::Network(
             "No shareable address available. Please connect to a relay first.".to_string(),
         )
-        .into());
-    };
-
-    #[derive(serde::Serialize)]
-    #[serde(rename_all = "camelCase")]
-    struct ContactBundle {
-        multiaddr: String,
-        display_name: String,
-        public_key: String,
-        x25519_public: String,
-        bio: Option<String>,
-        avatar_hash: Option<String>,
-    }
-
-    let bundle = ContactBundle {
-        multiaddr,
-        display_name: identity.display_name,
-        public_key: base64::engine::general_purpose::STANDARD.encode(&keys.public_key),
-        x25519_public: base64::engine::general_purpose::STANDARD.encode(&keys.x25519_public),
-        bio: identity.bio,
-        avatar_hash: identity.avatar_hash,
-    };
-
-    let json = serde_json::to_string(&bundle)
-        .map_err(|e| AppError::Serialization(format!("Failed to serialize contact: {}", e)))?;
-
-    let encoded = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json.as_bytes());
+    })?;
 
-    Ok(Json(format!("harbor://{}", encoded)))
+    Ok(Json(ContactBundle::new(&keys, multiaddr).to_contact_string()?))
 }
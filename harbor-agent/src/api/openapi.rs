@@ -0,0 +1,323 @@
+//! OpenAPI 3 description of the HTTP API
+//!
+//! Paths come from the `#[utoipa::path]` annotations on the handlers and
+//! schemas from the request/response types, so the document tracks the code.
+//! Handlers of account routes are annotated with their account-relative path
+//! and documented under `/api/accounts/{accountId}`. Served unauthenticated at
+//! `/api/openapi.json`.
+
+use axum::Json;
+use utoipa::openapi::path::{ParameterBuilder, ParameterIn};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{
+    ContentBuilder, ObjectBuilder, Ref, RefOr, Required, Response, ResponseBuilder, SchemaType,
+};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+use harbor_lib::error::{ErrorCode, ErrorResponse};
+use harbor_lib::models::{CreateIdentityRequest, IdentityInfo};
+use harbor_lib::p2p::{
+    ConnectionStatus, NatStatus, NetworkEvent, NetworkStats, PeerInfo, PeerPresence, PresenceStatus,
+};
+use harbor_lib::services::SafetyNumber;
+
+use super::{
+    accounts, auth, boards, contacts, events, feed, identity, messaging, network, permissions,
+    posts, scheduled, search, tokens, webhooks,
+};
+use crate::access::{ApiToken, Scope};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "bastion-agent",
+        description = "Headless HTTP API for autonomous agents on the Harbor P2P mesh. \
+            Every route except this document requires `Authorization: Bearer <token>` \
+            with the scope listed on the operation."
+    ),
+    paths(
+        accounts::list_accounts,
+        accounts::create_account,
+        accounts::remove_account,
+        auth::verify_agent,
+        auth::list_relay_tokens,
+        auth::forget_relay,
+        boards::get_communities,
+        boards::join_community,
+        boards::leave_community,
+        boards::get_boards,
+        boards::get_board_posts,
+        boards::get_board_threads,
+        boards::get_board_thread,
+        boards::sync_board_thread,
+        boards::submit_board_post,
+        boards::delete_board_post,
+        boards::sync_board,
+        boards::subscribe_boards,
+        boards::unsubscribe_boards,
+        contacts::get_active_contacts,
+        contacts::add_contact,
+        contacts::add_contact_from_string,
+        contacts::remove_contact,
+        contacts::block_contact,
+        contacts::get_contact_avatar,
+        contacts::get_safety_number,
+        contacts::verify_contact,
+        contacts::clear_contact_verification,
+        events::event_stream,
+        feed::get_feed,
+        feed::get_wall,
+        feed::sync_feed,
+        identity::get_identity,
+        identity::get_identity_status,
+        identity::unlock_identity,
+        identity::lock_identity,
+        identity::update_display_name,
+        identity::update_bio,
+        identity::get_avatar,
+        identity::set_avatar,
+        identity::clear_avatar,
+        messaging::send_message,
+        messaging::get_messages,
+        messaging::get_message_page,
+        messaging::export_conversation,
+        messaging::get_conversations,
+        messaging::mark_conversation_read,
+        messaging::set_typing,
+        messaging::get_retention,
+        messaging::set_retention,
+        messaging::get_total_unread_count,
+        messaging::edit_message,
+        messaging::delete_message,
+        messaging::react_to_message,
+        messaging::remove_reaction,
+        messaging::send_attachment,
+        messaging::get_attachment,
+        network::start_network,
+        network::stop_network,
+        network::restart_network,
+        network::get_network_status,
+        network::get_connected_peers,
+        network::get_presence,
+        network::set_presence,
+        network::connect_to_peer,
+        network::add_relay_server,
+        network::connect_to_public_relays,
+        network::get_listening_addresses,
+        network::get_shareable_addresses,
+        network::get_shareable_contact_string,
+        permissions::grant_permission,
+        permissions::grant_all_permissions,
+        permissions::revoke_permission,
+        permissions::get_chat_peers,
+        posts::get_my_posts,
+        posts::create_post,
+        posts::get_post,
+        posts::update_post,
+        posts::delete_post,
+        posts::get_post_media,
+        posts::upload_post_media,
+        posts::get_media,
+        posts::get_post_likes,
+        posts::like_post,
+        posts::unlike_post,
+        posts::get_posts_likes_batch,
+        posts::get_my_liked_posts,
+        scheduled::get_scheduled_items,
+        scheduled::schedule_post,
+        scheduled::schedule_message,
+        scheduled::cancel_scheduled_item,
+        search::search,
+        tokens::list_tokens,
+        tokens::create_token,
+        tokens::revoke_token,
+        webhooks::list_webhooks,
+        webhooks::create_webhook,
+        webhooks::update_webhook,
+        webhooks::delete_webhook,
+        webhooks::list_deliveries,
+        webhooks::retry_delivery,
+    ),
+    components(schemas(
+        // harbor_lib
+        ErrorCode,
+        ErrorResponse,
+        IdentityInfo,
+        CreateIdentityRequest,
+        PeerInfo,
+        NetworkStats,
+        NatStatus,
+        ConnectionStatus,
+        NetworkEvent,
+        PresenceStatus,
+        PeerPresence,
+        // Access
+        Scope,
+        ApiToken,
+        // Accounts
+        accounts::AccountInfo,
+        accounts::CreateAccountResponse,
+        // Auth
+        auth::AuthenticateRequest,
+        auth::AuthenticateResponse,
+        auth::RelayTokenInfo,
+        // Boards
+        boards::CommunityInfo,
+        boards::BoardInfo,
+        boards::BoardPostInfo,
+        boards::JoinCommunityRequest,
+        boards::SubmitPostRequest,
+        boards::DeleteBoardPostRequest,
+        boards::BoardSubscriptionRequest,
+        // Contacts
+        contacts::ContactInfo,
+        contacts::AddContactRequest,
+        contacts::AddContactFromStringRequest,
+        contacts::VerifyContactRequest,
+        SafetyNumber,
+        // Feed
+        feed::FeedItemInfo,
+        feed::FeedPageResponse,
+        feed::SyncFeedRequest,
+        // Identity
+        identity::IdentityStatusResponse,
+        identity::UnlockRequest,
+        identity::UpdateDisplayNameRequest,
+        identity::UpdateBioRequest,
+        identity::SetAvatarResponse,
+        // Messaging
+        messaging::SendMessageRequest,
+        messaging::SendMessageResult,
+        messaging::MessageInfo,
+        messaging::ReactionInfo,
+        messaging::AttachmentMeta,
+        messaging::EditMessageRequest,
+        messaging::ReactionRequest,
+        messaging::TypingRequest,
+        messaging::RetentionSetting,
+        messaging::ConversationInfo,
+        messaging::MessagePageResponse,
+        // Network
+        network::NetworkStatusResponse,
+        network::ConnectRequest,
+        network::RelayRequest,
+        network::SetPresenceRequest,
+        // Permissions
+        permissions::GrantPermissionRequest,
+        permissions::GrantAllRequest,
+        permissions::GrantResult,
+        // Posts
+        posts::PostInfo,
+        posts::PostMediaInfo,
+        posts::LikeSummaryInfo,
+        posts::CreatePostRequest,
+        posts::CreatePostResult,
+        posts::UpdatePostRequest,
+        posts::LikesBatchRequest,
+        // Scheduled
+        scheduled::ScheduledItemInfo,
+        scheduled::SchedulePostRequest,
+        scheduled::ScheduleMessageRequest,
+        // Search
+        search::SearchHitInfo,
+        // Tokens
+        tokens::TokenInfo,
+        tokens::CreateTokenRequest,
+        // Webhooks
+        webhooks::WebhookInfo,
+        webhooks::CreateWebhookResponse,
+        webhooks::DeliveryInfo,
+        webhooks::CreateWebhookRequest,
+        webhooks::UpdateWebhookRequest,
+    )),
+    modifiers(&ApiConventions),
+    tags(
+        (name = "accounts", description = "Identities hosted by the agent"),
+        (name = "auth", description = "Relay authentication"),
+        (name = "boards", description = "Relay communities and boards"),
+        (name = "contacts", description = "Contacts"),
+        (name = "events", description = "Network event stream"),
+        (name = "feed", description = "Feed and walls"),
+        (name = "identity", description = "Local identity"),
+        (name = "messaging", description = "Direct messages"),
+        (name = "network", description = "P2P network lifecycle"),
+        (name = "permissions", description = "Capability grants"),
+        (name = "posts", description = "Posts, media and likes"),
+        (name = "scheduled", description = "Scheduled posts and messages"),
+        (name = "search", description = "Full-text search"),
+        (name = "tokens", description = "API tokens"),
+        (name = "webhooks", description = "Webhook subscriptions"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Adds what every operation shares: the bearer scheme and the error body.
+/// Also moves account routes below `/api/accounts/{accountId}`.
+struct ApiConventions;
+
+impl Modify for ApiConventions {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+
+        let error: RefOr<Response> = ResponseBuilder::new()
+            .description("Error")
+            .content(
+                "application/json",
+                ContentBuilder::new()
+                    .schema(Ref::from_schema_name(ErrorResponse::schema().0))
+                    .build(),
+            )
+            .build()
+            .into();
+
+        let account_id = ParameterBuilder::new()
+            .name("accountId")
+            .parameter_in(ParameterIn::Path)
+            .required(Required::True)
+            .description(Some("Account id (the identity's peer id)"))
+            .schema(Some(ObjectBuilder::new().schema_type(SchemaType::String)))
+            .build();
+
+        let paths = std::mem::take(&mut openapi.paths.paths);
+        for (path, mut item) in paths {
+            let path = match account_relative(&path) {
+                Some(rest) => {
+                    item.parameters
+                        .get_or_insert_with(Vec::new)
+                        .insert(0, account_id.clone());
+                    format!("/api/accounts/{{accountId}}{}", rest)
+                }
+                None => path,
+            };
+            openapi.paths.paths.insert(path, item);
+        }
+
+        for item in openapi.paths.paths.values_mut() {
+            for operation in item.operations.values_mut() {
+                operation
+                    .responses
+                    .responses
+                    .entry("default".to_string())
+                    .or_insert_with(|| error.clone());
+            }
+        }
+    }
+}
+
+/// The part after `/api` of a path served per account; `None` for agent routes
+fn account_relative(path: &str) -> Option<&str> {
+    if path.starts_with("/api/accounts") || path.starts_with("/api/tokens") {
+        return None;
+    }
+    path.strip_prefix("/api")
+}
+
+/// GET /api/openapi.json
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
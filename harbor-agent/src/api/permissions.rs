@@ -2,6 +2,7 @@ use axum::extract::{Path, State};
 use axum::Json;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use utoipa::ToSchema;
 
 use harbor_lib::db::Capability;
 use harbor_lib::error::AppError;
@@ -9,7 +10,7 @@ use harbor_lib::error::AppError;
 use crate::error::ApiError;
 use crate::state::AppState;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GrantPermissionRequest {
     pub peer_id: String,
@@ -17,13 +18,13 @@ pub struct GrantPermissionRequest {
     pub expires_in_seconds: Option<i64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GrantAllRequest {
     pub peer_id: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GrantResult {
     pub grant_id: String,
@@ -39,6 +40,14 @@ fn capability_from_str(s: &str) -> Result<Capability, ApiError> {
 }
 
 /// POST /api/permissions/grant
+#[utoipa::path(
+    post,
+    path = "/api/permissions/grant",
+    tag = "permissions",
+    request_body = GrantPermissionRequest,
+    responses((status = 200, body = GrantResult)),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn grant_permission(
     State(state): State<Arc<AppState>>,
     Json(req): Json<GrantPermissionRequest>,
@@ -60,6 +69,14 @@ pub async fn grant_permission(
 }
 
 /// POST /api/permissions/grant-all
+#[utoipa::path(
+    post,
+    path = "/api/permissions/grant-all",
+    tag = "permissions",
+    request_body = GrantAllRequest,
+    responses((status = 200, body = Vec<GrantResult>)),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn grant_all_permissions(
     State(state): State<Arc<AppState>>,
     Json(req): Json<GrantAllRequest>,
@@ -84,6 +101,14 @@ pub async fn grant_all_permissions(
 }
 
 /// DELETE /api/permissions/:grantId
+#[utoipa::path(
+    delete,
+    path = "/api/permissions/{grantId}",
+    tag = "permissions",
+    params(("grantId" = String, Path)),
+    responses((status = 200, body = bool)),
+    security(("bearer" = ["admin"]))
+)]
 pub async fn revoke_permission(
     State(state): State<Arc<AppState>>,
     Path(grant_id): Path<String>,
@@ -93,6 +118,13 @@ pub async fn revoke_permission(
 }
 
 /// GET /api/permissions/chat-peers
+#[utoipa::path(
+    get,
+    path = "/api/permissions/chat-peers",
+    tag = "permissions",
+    responses((status = 200, body = Vec<String>)),
+    security(("bearer" = ["read"]))
+)]
 pub async fn get_chat_peers(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<String>>, ApiError> {
@@ -0,0 +1,448 @@
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use harbor_lib::db::repositories::LikeSummary;
+use harbor_lib::db::{Post, PostMedia, PostVisibility};
+use harbor_lib::error::AppError;
+
+use crate::error::ApiError;
+use crate::state::AppState;
+
+/// Largest media upload accepted (bytes)
+pub const MAX_MEDIA_BYTES: usize = 25 * 1024 * 1024;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostInfo {
+    pub post_id: String,
+    pub author_peer_id: String,
+    pub content_type: String,
+    pub content_text: Option<String>,
+    pub visibility: String,
+    pub lamport_clock: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub deleted_at: Option<i64>,
+    pub is_local: bool,
+}
+
+impl From<Post> for PostInfo {
+    fn from(post: Post) -> Self {
+        Self {
+            post_id: post.post_id,
+            author_peer_id: post.author_peer_id,
+            content_type: post.content_type,
+            content_text: post.content_text,
+            visibility: post.visibility.as_str().to_string(),
+            lamport_clock: post.lamport_clock,
+            created_at: post.created_at,
+            updated_at: post.updated_at,
+            deleted_at: post.deleted_at,
+            is_local: post.is_local,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostMediaInfo {
+    pub id: i64,
+    pub post_id: String,
+    pub media_hash: String,
+    pub media_type: String,
+    pub mime_type: String,
+    pub file_name: String,
+    pub file_size: i64,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub duration_seconds: Option<i32>,
+    pub sort_order: i32,
+}
+
+impl From<PostMedia> for PostMediaInfo {
+    fn from(media: PostMedia) -> Self {
+        Self {
+            id: media.id,
+            post_id: media.post_id,
+            media_hash: media.media_hash,
+            media_type: media.media_type,
+            mime_type: media.mime_type,
+            file_name: media.file_name,
+            file_size: media.file_size,
+            width: media.width,
+            height: media.height,
+            duration_seconds: media.duration_seconds,
+            sort_order: media.sort_order,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LikeSummaryInfo {
+    pub post_id: String,
+    pub total_likes: i64,
+    pub user_has_liked: bool,
+}
+
+impl From<LikeSummary> for LikeSummaryInfo {
+    fn from(summary: LikeSummary) -> Self {
+        Self {
+            post_id: summary.post_id,
+            total_likes: summary.total_likes,
+            user_has_liked: summary.user_has_liked,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePostRequest {
+    pub content_type: Option<String>,
+    pub content_text: Option<String>,
+    pub visibility: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePostResult {
+    pub post_id: String,
+    pub created_at: i64,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePostRequest {
+    pub content_text: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct PostsQuery {
+    pub limit: Option<i64>,
+    pub before_timestamp: Option<i64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct MediaUploadQuery {
+    pub file_name: String,
+    pub mime_type: Option<String>,
+    pub media_type: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub duration_seconds: Option<i32>,
+    pub sort_order: Option<i32>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LikesBatchRequest {
+    pub post_ids: Vec<String>,
+}
+
+/// GET /api/posts — the local identity's posts
+#[utoipa::path(
+    get,
+    path = "/api/posts",
+    tag = "posts",
+    params(PostsQuery),
+    responses((status = 200, body = Vec<PostInfo>)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_my_posts(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PostsQuery>,
+) -> Result<Json<Vec<PostInfo>>, ApiError> {
+    let posts = state
+        .posts_service
+        .get_my_posts(query.limit.unwrap_or(50), query.before_timestamp)?;
+    Ok(Json(posts.into_iter().map(PostInfo::from).collect()))
+}
+
+/// POST /api/posts
+#[utoipa::path(
+    post,
+    path = "/api/posts",
+    tag = "posts",
+    request_body = CreatePostRequest,
+    responses((status = 200, body = CreatePostResult)),
+    security(("bearer" = ["send"]))
+)]
+pub async fn create_post(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreatePostRequest>,
+) -> Result<Json<CreatePostResult>, ApiError> {
+    let visibility = match req.visibility.as_deref() {
+        Some("public") => PostVisibility::Public,
+        _ => PostVisibility::Contacts,
+    };
+    let content_type = req.content_type.unwrap_or_else(|| "text".to_string());
+
+    let outgoing = state.posts_service.create_post(
+        &content_type,
+        req.content_text.as_deref(),
+        visibility,
+    )?;
+
+    Ok(Json(CreatePostResult {
+        post_id: outgoing.post_id,
+        created_at: outgoing.created_at,
+    }))
+}
+
+/// GET /api/posts/:postId
+#[utoipa::path(
+    get,
+    path = "/api/posts/{postId}",
+    tag = "posts",
+    params(("postId" = String, Path)),
+    responses((status = 200, body = PostInfo)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_post(
+    State(state): State<Arc<AppState>>,
+    Path(post_id): Path<String>,
+) -> Result<Json<PostInfo>, ApiError> {
+    let post = state
+        .posts_service
+        .get_post(&post_id)?
+        .ok_or_else(|| AppError::NotFound(format!("Post {} not found", post_id)))?;
+    Ok(Json(PostInfo::from(post)))
+}
+
+/// PUT /api/posts/:postId
+#[utoipa::path(
+    put,
+    path = "/api/posts/{postId}",
+    tag = "posts",
+    params(("postId" = String, Path)),
+    request_body = UpdatePostRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn update_post(
+    State(state): State<Arc<AppState>>,
+    Path(post_id): Path<String>,
+    Json(req): Json<UpdatePostRequest>,
+) -> Result<Json<()>, ApiError> {
+    state
+        .posts_service
+        .update_post(&post_id, req.content_text.as_deref())?;
+    Ok(Json(()))
+}
+
+/// DELETE /api/posts/:postId
+#[utoipa::path(
+    delete,
+    path = "/api/posts/{postId}",
+    tag = "posts",
+    params(("postId" = String, Path)),
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn delete_post(
+    State(state): State<Arc<AppState>>,
+    Path(post_id): Path<String>,
+) -> Result<Json<()>, ApiError> {
+    state.posts_service.delete_post(&post_id)?;
+    Ok(Json(()))
+}
+
+/// GET /api/posts/:postId/media
+#[utoipa::path(
+    get,
+    path = "/api/posts/{postId}/media",
+    tag = "posts",
+    params(("postId" = String, Path)),
+    responses((status = 200, body = Vec<PostMediaInfo>)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_post_media(
+    State(state): State<Arc<AppState>>,
+    Path(post_id): Path<String>,
+) -> Result<Json<Vec<PostMediaInfo>>, ApiError> {
+    let media = state.posts_service.get_post_media(&post_id)?;
+    Ok(Json(media.into_iter().map(PostMediaInfo::from).collect()))
+}
+
+/// POST /api/posts/:postId/media — raw file bytes in the body, metadata in the query.
+/// The file is stored content-addressed under its SHA-256 hash.
+#[utoipa::path(
+    post,
+    path = "/api/posts/{postId}/media",
+    tag = "posts",
+    params(
+        ("postId" = String, Path),
+        MediaUploadQuery,
+    ),
+    request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+    responses((status = 200, body = PostMediaInfo)),
+    security(("bearer" = ["send"]))
+)]
+pub async fn upload_post_media(
+    State(state): State<Arc<AppState>>,
+    Path(post_id): Path<String>,
+    Query(query): Query<MediaUploadQuery>,
+    body: Bytes,
+) -> Result<Json<PostMediaInfo>, ApiError> {
+    if body.is_empty() {
+        return Err(AppError::Validation("Media body is empty".to_string()).into());
+    }
+
+    let mime_type = query
+        .mime_type
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    let media_type = query.media_type.unwrap_or_else(|| {
+        match mime_type.split('/').next() {
+            Some("image") => "image",
+            Some("video") => "video",
+            Some("audio") => "audio",
+            _ => "file",
+        }
+        .to_string()
+    });
+
+    let media_hash = hex::encode(Sha256::digest(&body));
+    let path = state.media_dir.join(&media_hash);
+    if !path.exists() {
+        std::fs::create_dir_all(&state.media_dir)
+            .and_then(|_| std::fs::write(&path, &body))
+            .map_err(|e| AppError::Internal(format!("Failed to store media: {}", e)))?;
+    }
+
+    let sort_order = query.sort_order.unwrap_or(0);
+    state.posts_service.add_media_to_post(
+        &post_id,
+        &media_hash,
+        &media_type,
+        &mime_type,
+        &query.file_name,
+        body.len() as i64,
+        query.width,
+        query.height,
+        query.duration_seconds,
+        sort_order,
+    )?;
+
+    let media = state
+        .posts_service
+        .get_post_media(&post_id)?
+        .into_iter()
+        .find(|m| m.media_hash == media_hash && m.sort_order == sort_order)
+        .ok_or_else(|| AppError::Internal("Stored media not found".to_string()))?;
+    Ok(Json(PostMediaInfo::from(media)))
+}
+
+/// GET /api/media/:mediaHash — raw bytes of stored media
+#[utoipa::path(
+    get,
+    path = "/api/media/{mediaHash}",
+    tag = "posts",
+    params(("mediaHash" = String, Path)),
+    responses((status = 200, content_type = "application/octet-stream", body = Vec<u8>)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_media(
+    State(state): State<Arc<AppState>>,
+    Path(media_hash): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    if media_hash.len() != 64 || !media_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::Validation("Invalid media hash".to_string()).into());
+    }
+
+    let bytes = std::fs::read(state.media_dir.join(&media_hash))
+        .map_err(|_| AppError::NotFound(format!("Media {} not found", media_hash)))?;
+    Ok(([(header::CONTENT_TYPE, "application/octet-stream")], bytes))
+}
+
+/// GET /api/posts/:postId/likes
+#[utoipa::path(
+    get,
+    path = "/api/posts/{postId}/likes",
+    tag = "posts",
+    params(("postId" = String, Path)),
+    responses((status = 200, body = LikeSummaryInfo)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_post_likes(
+    State(state): State<Arc<AppState>>,
+    Path(post_id): Path<String>,
+) -> Result<Json<LikeSummaryInfo>, ApiError> {
+    let summary = state.posts_service.get_like_summary(&post_id)?;
+    Ok(Json(LikeSummaryInfo::from(summary)))
+}
+
+/// POST /api/posts/:postId/likes
+#[utoipa::path(
+    post,
+    path = "/api/posts/{postId}/likes",
+    tag = "posts",
+    params(("postId" = String, Path)),
+    responses((status = 200, body = LikeSummaryInfo)),
+    security(("bearer" = ["send"]))
+)]
+pub async fn like_post(
+    State(state): State<Arc<AppState>>,
+    Path(post_id): Path<String>,
+) -> Result<Json<LikeSummaryInfo>, ApiError> {
+    let summary = state.posts_service.like_post(&post_id)?;
+    Ok(Json(LikeSummaryInfo::from(summary)))
+}
+
+/// DELETE /api/posts/:postId/likes
+#[utoipa::path(
+    delete,
+    path = "/api/posts/{postId}/likes",
+    tag = "posts",
+    params(("postId" = String, Path)),
+    responses((status = 200, body = LikeSummaryInfo)),
+    security(("bearer" = ["send"]))
+)]
+pub async fn unlike_post(
+    State(state): State<Arc<AppState>>,
+    Path(post_id): Path<String>,
+) -> Result<Json<LikeSummaryInfo>, ApiError> {
+    let summary = state.posts_service.unlike_post(&post_id)?;
+    Ok(Json(LikeSummaryInfo::from(summary)))
+}
+
+/// POST /api/posts/likes/batch
+#[utoipa::path(
+    post,
+    path = "/api/posts/likes/batch",
+    tag = "posts",
+    request_body = LikesBatchRequest,
+    responses((status = 200, body = Vec<LikeSummaryInfo>)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_posts_likes_batch(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<LikesBatchRequest>,
+) -> Result<Json<Vec<LikeSummaryInfo>>, ApiError> {
+    let summaries = state.posts_service.get_like_summaries(&req.post_ids)?;
+    Ok(Json(summaries.into_iter().map(LikeSummaryInfo::from).collect()))
+}
+
+/// GET /api/posts/liked
+#[utoipa::path(
+    get,
+    path = "/api/posts/liked",
+    tag = "posts",
+    responses((status = 200, body = Vec<String>)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_my_liked_posts(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<String>>, ApiError> {
+    Ok(Json(state.posts_service.get_my_liked_posts()?))
+}
@@ -0,0 +1,185 @@
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use harbor_lib::db::PostVisibility;
+use harbor_lib::services::ScheduledEntry;
+
+use crate::error::ApiError;
+use crate::state::AppState;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledItemInfo {
+    pub schedule_id: String,
+    /// post or message
+    pub kind: String,
+    /// Messages only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient_peer_id: Option<String>,
+    pub content_type: String,
+    /// Cleared once the item is published or canceled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Posts only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_to_message_id: Option<String>,
+    /// Unix seconds
+    pub publish_at: i64,
+    /// pending, publishing, published, failed or canceled
+    pub status: String,
+    pub created_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<i64>,
+    /// ID of the post or message once published
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<ScheduledEntry> for ScheduledItemInfo {
+    fn from(entry: ScheduledEntry) -> Self {
+        Self {
+            schedule_id: entry.schedule_id,
+            kind: entry.kind.as_str().to_string(),
+            recipient_peer_id: entry.recipient_peer_id,
+            content_type: entry.content_type,
+            content: entry.content,
+            visibility: entry.visibility,
+            reply_to_message_id: entry.reply_to_message_id,
+            publish_at: entry.publish_at,
+            status: entry.status.as_str().to_string(),
+            created_at: entry.created_at,
+            published_at: entry.published_at,
+            published_id: entry.published_id,
+            error: entry.error,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulePostRequest {
+    pub content_type: Option<String>,
+    pub content_text: Option<String>,
+    pub visibility: Option<String>,
+    /// Unix seconds; within a year from now
+    pub publish_at: i64,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleMessageRequest {
+    pub peer_id: String,
+    pub content: String,
+    pub content_type: Option<String>,
+    pub reply_to: Option<String>,
+    /// Unix seconds; within a year from now
+    pub publish_at: i64,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ScheduledQuery {
+    /// Also list published, failed and canceled items
+    pub include_finished: Option<bool>,
+}
+
+/// GET /api/scheduled?includeFinished= — scheduled posts and messages, soonest first
+#[utoipa::path(
+    get,
+    path = "/api/scheduled",
+    tag = "scheduled",
+    params(ScheduledQuery),
+    responses((status = 200, body = Vec<ScheduledItemInfo>)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn get_scheduled_items(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ScheduledQuery>,
+) -> Result<Json<Vec<ScheduledItemInfo>>, ApiError> {
+    let entries = state
+        .scheduler_service
+        .list(query.include_finished.unwrap_or(false))?;
+    Ok(Json(
+        entries.into_iter().map(ScheduledItemInfo::from).collect(),
+    ))
+}
+
+/// POST /api/scheduled/posts — publish a post at `publishAt`. The post is
+/// signed when it is published.
+#[utoipa::path(
+    post,
+    path = "/api/scheduled/posts",
+    tag = "scheduled",
+    request_body = SchedulePostRequest,
+    responses((status = 200, body = ScheduledItemInfo)),
+    security(("bearer" = ["send"]))
+)]
+pub async fn schedule_post(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SchedulePostRequest>,
+) -> Result<Json<ScheduledItemInfo>, ApiError> {
+    let visibility = match req.visibility.as_deref() {
+        Some("public") => PostVisibility::Public,
+        _ => PostVisibility::Contacts,
+    };
+    let content_type = req.content_type.unwrap_or_else(|| "text".to_string());
+
+    let entry = state.scheduler_service.schedule_post(
+        &content_type,
+        req.content_text.as_deref(),
+        visibility,
+        req.publish_at,
+    )?;
+    Ok(Json(entry.into()))
+}
+
+/// POST /api/scheduled/messages — send a message at `publishAt`. The message
+/// is encrypted and signed when it is sent.
+#[utoipa::path(
+    post,
+    path = "/api/scheduled/messages",
+    tag = "scheduled",
+    request_body = ScheduleMessageRequest,
+    responses((status = 200, body = ScheduledItemInfo)),
+    security(("bearer" = ["send"]))
+)]
+pub async fn schedule_message(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ScheduleMessageRequest>,
+) -> Result<Json<ScheduledItemInfo>, ApiError> {
+    let content_type = req.content_type.unwrap_or_else(|| "text".to_string());
+
+    let entry = state.scheduler_service.schedule_message(
+        &req.peer_id,
+        &req.content,
+        &content_type,
+        req.reply_to.as_deref(),
+        req.publish_at,
+    )?;
+    Ok(Json(entry.into()))
+}
+
+/// DELETE /api/scheduled/:scheduleId — cancel a pending item
+#[utoipa::path(
+    delete,
+    path = "/api/scheduled/{scheduleId}",
+    tag = "scheduled",
+    params(("scheduleId" = String, Path)),
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["send"]))
+)]
+pub async fn cancel_scheduled_item(
+    State(state): State<Arc<AppState>>,
+    Path(schedule_id): Path<String>,
+) -> Result<Json<()>, ApiError> {
+    state.scheduler_service.cancel(&schedule_id)?;
+    Ok(Json(()))
+}
@@ -0,0 +1,100 @@
+use axum::extract::{Query, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use harbor_lib::db::{SearchFilters, SearchHit, SearchKind};
+use harbor_lib::error::AppError;
+
+use crate::error::ApiError;
+use crate::state::AppState;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHitInfo {
+    /// message, wall_post, feed_post, board_post or contact
+    pub kind: String,
+    /// Message, post or board post ID, or the contact's peer ID
+    pub item_id: String,
+    /// Author of the post, other party of the message, or the contact
+    pub peer_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relay_peer_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub board_id: Option<String>,
+    pub created_at: i64,
+    /// Matched terms are wrapped in `<mark>` tags; everything else is plain text
+    pub snippet: String,
+    /// BM25 rank; lower is better
+    pub rank: f64,
+}
+
+impl From<SearchHit> for SearchHitInfo {
+    fn from(hit: SearchHit) -> Self {
+        Self {
+            kind: hit.kind.as_str().to_string(),
+            item_id: hit.item_id,
+            peer_id: hit.peer_id,
+            relay_peer_id: hit.relay_peer_id,
+            board_id: hit.board_id,
+            created_at: hit.created_at,
+            snippet: hit.snippet,
+            rank: hit.rank,
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Words to find; each matches as a prefix
+    pub q: String,
+    /// Comma-separated kinds to search; all if absent
+    pub kinds: Option<String>,
+    pub peer_id: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// GET /api/search?q=&kinds=&peerId=&since=&until=&limit= — ranked hits
+/// across messages, posts, board posts and contacts. Messages are only
+/// searched while the identity is unlocked.
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    tag = "search",
+    params(SearchQuery),
+    responses((status = 200, body = Vec<SearchHitInfo>)),
+    security(("bearer" = ["read"]))
+)]
+pub async fn search(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchHitInfo>>, ApiError> {
+    let kinds = query
+        .kinds
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|kind| !kind.is_empty())
+        .map(|kind| {
+            SearchKind::from_str(kind)
+                .ok_or_else(|| AppError::Validation(format!("Unknown search kind: {}", kind)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let filters = SearchFilters {
+        kinds,
+        peer_id: query.peer_id,
+        since: query.since,
+        until: query.until,
+    };
+
+    let hits = state
+        .search_service
+        .search(&query.q, &filters, query.limit.unwrap_or(50))?;
+    Ok(Json(hits.into_iter().map(SearchHitInfo::from).collect()))
+}
@@ -0,0 +1,105 @@
+use axum::extract::{Path, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+use harbor_lib::error::AppError;
+
+use crate::access::{ApiToken, Scope};
+use crate::error::ApiError;
+use crate::state::AgentState;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: i64,
+}
+
+impl From<ApiToken> for TokenInfo {
+    fn from(token: ApiToken) -> Self {
+        Self {
+            name: token.name,
+            scopes: token.scopes,
+            created_at: token.created_at,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTokenRequest {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+}
+
+/// GET /api/tokens (secrets are not returned)
+#[utoipa::path(
+    get,
+    path = "/api/tokens",
+    tag = "tokens",
+    responses((status = 200, body = Vec<TokenInfo>)),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn list_tokens(
+    State(state): State<Arc<AgentState>>,
+) -> Result<Json<Vec<TokenInfo>>, ApiError> {
+    let tokens = state
+        .api_tokens
+        .list()
+        .into_iter()
+        .map(TokenInfo::from)
+        .collect();
+    Ok(Json(tokens))
+}
+
+/// POST /api/tokens (the secret is only returned here)
+#[utoipa::path(
+    post,
+    path = "/api/tokens",
+    tag = "tokens",
+    request_body = CreateTokenRequest,
+    responses((status = 200, body = ApiToken)),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn create_token(
+    State(state): State<Arc<AgentState>>,
+    Json(req): Json<CreateTokenRequest>,
+) -> Result<Json<ApiToken>, ApiError> {
+    let token = state.api_tokens.create(&req.name, req.scopes)?;
+    Ok(Json(token))
+}
+
+/// DELETE /api/tokens/:name
+#[utoipa::path(
+    delete,
+    path = "/api/tokens/{name}",
+    tag = "tokens",
+    params(("name" = String, Path)),
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn revoke_token(
+    State(state): State<Arc<AgentState>>,
+    Path(name): Path<String>,
+) -> Result<Json<()>, ApiError> {
+    let remaining_admins = state
+        .api_tokens
+        .list()
+        .iter()
+        .filter(|t| t.name != name && t.scopes.contains(&Scope::Admin))
+        .count();
+    if remaining_admins == 0 {
+        return Err(AppError::Validation(
+            "Cannot revoke the last admin token".to_string(),
+        )
+        .into());
+    }
+
+    if !state.api_tokens.revoke(&name)? {
+        return Err(AppError::NotFound(format!("Token '{}' not found", name)).into());
+    }
+    Ok(Json(()))
+}
@@ -0,0 +1,268 @@
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use harbor_lib::error::AppError;
+
+use crate::access::random_secret;
+use crate::agent_db::{DeliveryRow, WebhookRow};
+use crate::error::ApiError;
+use crate::state::AppState;
+
+/// Prefix of generated webhook secrets
+const SECRET_PREFIX: &str = "whsec_";
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookInfo {
+    pub webhook_id: String,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub enabled: bool,
+    pub created_at: i64,
+}
+
+impl From<WebhookRow> for WebhookInfo {
+    fn from(row: WebhookRow) -> Self {
+        Self {
+            webhook_id: row.webhook_id,
+            url: row.url,
+            event_types: row.event_types,
+            enabled: row.enabled,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWebhookResponse {
+    #[serde(flatten)]
+    pub webhook: WebhookInfo,
+    /// HMAC signing secret; only returned on creation
+    pub secret: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryInfo {
+    pub delivery_id: i64,
+    pub event_id: i64,
+    pub event_type: String,
+    pub status: String,
+    pub attempts: u32,
+    pub next_attempt_at: i64,
+    pub last_status_code: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+}
+
+impl From<DeliveryRow> for DeliveryInfo {
+    fn from(row: DeliveryRow) -> Self {
+        Self {
+            delivery_id: row.delivery_id,
+            event_id: row.event_id,
+            event_type: row.event_type,
+            status: row.status,
+            attempts: row.attempts,
+            next_attempt_at: row.next_attempt_at,
+            last_status_code: row.last_status_code,
+            last_error: row.last_error,
+            created_at: row.created_at,
+            completed_at: row.completed_at,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    /// Event types to deliver (e.g. "message_received"); empty or absent means all
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    /// Signing secret; generated when absent
+    pub secret: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateWebhookRequest {
+    pub enabled: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct DeliveriesQuery {
+    pub limit: Option<usize>,
+}
+
+fn db_error(e: rusqlite::Error) -> ApiError {
+    AppError::DatabaseString(e.to_string()).into()
+}
+
+/// GET /api/webhooks
+#[utoipa::path(
+    get,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    responses((status = 200, body = Vec<WebhookInfo>)),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn list_webhooks(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<WebhookInfo>>, ApiError> {
+    let webhooks = state.journal.db().list_webhooks().map_err(db_error)?;
+    Ok(Json(webhooks.into_iter().map(WebhookInfo::from).collect()))
+}
+
+/// POST /api/webhooks
+#[utoipa::path(
+    post,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    request_body = CreateWebhookRequest,
+    responses((status = 200, body = CreateWebhookResponse)),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn create_webhook(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateWebhookRequest>,
+) -> Result<Json<CreateWebhookResponse>, ApiError> {
+    if !(req.url.starts_with("http://") || req.url.starts_with("https://")) {
+        return Err(AppError::Validation("Webhook URL must be http(s)".to_string()).into());
+    }
+    if req.secret.as_deref().is_some_and(|s| s.len() < 16) {
+        return Err(AppError::Validation(
+            "Webhook secret must be at least 16 characters".to_string(),
+        )
+        .into());
+    }
+
+    let row = WebhookRow {
+        webhook_id: uuid::Uuid::new_v4().to_string(),
+        url: req.url,
+        secret: req.secret.unwrap_or_else(|| random_secret(SECRET_PREFIX)),
+        event_types: req.event_types,
+        enabled: true,
+        created_at: chrono::Utc::now().timestamp(),
+    };
+    state.journal.db().insert_webhook(&row).map_err(db_error)?;
+
+    let secret = row.secret.clone();
+    Ok(Json(CreateWebhookResponse {
+        webhook: WebhookInfo::from(row),
+        secret,
+    }))
+}
+
+/// PUT /api/webhooks/:webhookId — enable or disable
+#[utoipa::path(
+    put,
+    path = "/api/webhooks/{webhookId}",
+    tag = "webhooks",
+    params(("webhookId" = String, Path)),
+    request_body = UpdateWebhookRequest,
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn update_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(webhook_id): Path<String>,
+    Json(req): Json<UpdateWebhookRequest>,
+) -> Result<Json<()>, ApiError> {
+    let db = state.journal.db();
+    if !db
+        .set_webhook_enabled(&webhook_id, req.enabled)
+        .map_err(db_error)?
+    {
+        return Err(AppError::NotFound(format!("Webhook {} not found", webhook_id)).into());
+    }
+    if req.enabled {
+        state.journal.deliveries_queued.notify_one();
+    }
+    Ok(Json(()))
+}
+
+/// DELETE /api/webhooks/:webhookId
+#[utoipa::path(
+    delete,
+    path = "/api/webhooks/{webhookId}",
+    tag = "webhooks",
+    params(("webhookId" = String, Path)),
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn delete_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(webhook_id): Path<String>,
+) -> Result<Json<()>, ApiError> {
+    if !state
+        .journal
+        .db()
+        .delete_webhook(&webhook_id)
+        .map_err(db_error)?
+    {
+        return Err(AppError::NotFound(format!("Webhook {} not found", webhook_id)).into());
+    }
+    Ok(Json(()))
+}
+
+/// GET /api/webhooks/:webhookId/deliveries
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{webhookId}/deliveries",
+    tag = "webhooks",
+    params(
+        ("webhookId" = String, Path),
+        DeliveriesQuery,
+    ),
+    responses((status = 200, body = Vec<DeliveryInfo>)),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn list_deliveries(
+    State(state): State<Arc<AppState>>,
+    Path(webhook_id): Path<String>,
+    Query(query): Query<DeliveriesQuery>,
+) -> Result<Json<Vec<DeliveryInfo>>, ApiError> {
+    let limit = query.limit.unwrap_or(50).min(500);
+    let deliveries = state
+        .journal
+        .db()
+        .recent_deliveries(&webhook_id, limit)
+        .map_err(db_error)?;
+    Ok(Json(deliveries.into_iter().map(DeliveryInfo::from).collect()))
+}
+
+/// POST /api/webhooks/:webhookId/deliveries/:deliveryId/retry
+#[utoipa::path(
+    post,
+    path = "/api/webhooks/{webhookId}/deliveries/{deliveryId}/retry",
+    tag = "webhooks",
+    params(
+        ("webhookId" = String, Path),
+        ("deliveryId" = i64, Path),
+    ),
+    responses((status = 200, description = "Done")),
+    security(("bearer" = ["admin"]))
+)]
+pub async fn retry_delivery(
+    State(state): State<Arc<AppState>>,
+    Path((webhook_id, delivery_id)): Path<(String, i64)>,
+) -> Result<Json<()>, ApiError> {
+    if !state
+        .journal
+        .db()
+        .requeue_delivery(&webhook_id, delivery_id)
+        .map_err(db_error)?
+    {
+        return Err(AppError::NotFound(format!("Delivery {} not found", delivery_id)).into());
+    }
+    state.journal.deliveries_queued.notify_one();
+    Ok(Json(()))
+}
+
@@ -0,0 +1,101 @@
+//! Fallback solver that hands tasks to another program
+//!
+//! The task is sent as `{"task": <CaptchaTask>}`, and the reply must be the
+//! `TaskAnswer` JSON. Commands get the request on stdin and answer on stdout;
+//! endpoints (e.g. a local model server) get it as a `POST` body.
+
+use async_trait::async_trait;
+use isnad::{CaptchaTask, TaskAnswer};
+use serde::Serialize;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::warn;
+
+use super::CaptchaSolver;
+
+/// Upper bound per task; challenges expire a minute after they are issued
+const EXTERNAL_TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Serialize)]
+struct ExternalRequest<'a> {
+    task: &'a CaptchaTask,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExternalSolver {
+    /// Shell command, run with `sh -c`
+    Command(String),
+    /// HTTP URL
+    Endpoint(String),
+}
+
+#[async_trait]
+impl CaptchaSolver for ExternalSolver {
+    async fn solve(&self, task: &CaptchaTask) -> Option<TaskAnswer> {
+        let body = match serde_json::to_vec(&ExternalRequest { task }) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to encode CAPTCHA task: {}", e);
+                return None;
+            }
+        };
+        let result = match self {
+            ExternalSolver::Command(command) => run_command(command, body).await,
+            ExternalSolver::Endpoint(url) => post_endpoint(url, body).await,
+        };
+        result
+            .map_err(|e| warn!("External CAPTCHA solver failed: {}", e))
+            .ok()
+    }
+}
+
+async fn run_command(command: &str, body: Vec<u8>) -> Result<TaskAnswer, String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start `{}`: {}", command, e))?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin
+        .write_all(&body)
+        .await
+        .map_err(|e| format!("Failed to write task: {}", e))?;
+    drop(stdin);
+
+    let output = tokio::time::timeout(EXTERNAL_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("`{}` timed out", command))?
+        .map_err(|e| format!("Failed to run `{}`: {}", command, e))?;
+    if !output.status.success() {
+        return Err(format!("`{}` exited with {}", command, output.status));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("Invalid answer: {}", e))
+}
+
+async fn post_endpoint(url: &str, body: Vec<u8>) -> Result<TaskAnswer, String> {
+    let client = reqwest::Client::builder()
+        .timeout(EXTERNAL_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("Request to {} failed: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} answered {}", url, response.status()));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid answer: {}", e))
+}
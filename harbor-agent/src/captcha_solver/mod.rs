@@ -2,173 +2,203 @@
 //!
 //! Solves the relay's reverse-CAPTCHA challenges to prove this is an autonomous
 //! AI agent, not a human or a human-proxied assistant.
+//!
+//! Challenges go through a [`ChallengeSolver`]. The [`BuiltinSolver`] answers
+//! the tasks it is sure of; the others are handed to an optional fallback
+//! [`CaptchaSolver`] (such as an [`ExternalSolver`] running a command or asking
+//! a local model), and whatever is still open gets the built-in best guess.
+
+mod external;
+pub mod reading;
+pub mod sequences;
+
+pub use external::ExternalSolver;
 
+use async_trait::async_trait;
 use chrono::Utc;
-use isnad::{
-    apply_text_op, CaptchaChallenge, CaptchaResponse, CaptchaTask, PatternSequence, TaskAnswer,
-};
+use isnad::{apply_text_op, CaptchaChallenge, CaptchaResponse, CaptchaTask, TaskAnswer};
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use std::sync::Arc;
+use tracing::{debug, info};
 
-/// Solve a CAPTCHA challenge from the relay auth endpoint.
-pub fn solve_challenge(challenge: &CaptchaChallenge) -> CaptchaResponse {
-    let answers: Vec<TaskAnswer> = challenge
-        .tasks
-        .iter()
-        .map(|task| solve_task(task))
-        .collect();
+/// Answer given when nothing better is known
+const UNKNOWN: &str = "unknown";
+
+/// Answers individual challenge tasks
+#[async_trait]
+pub trait CaptchaSolver: Send + Sync {
+    /// Answer `task`, or `None` if this solver can't answer it with confidence
+    async fn solve(&self, task: &CaptchaTask) -> Option<TaskAnswer>;
+}
+
+/// Rule-based solver that needs no external help
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BuiltinSolver;
+
+impl BuiltinSolver {
+    /// Best-effort answer: parts it can't work out are guessed
+    pub fn guess(&self, task: &CaptchaTask) -> TaskAnswer {
+        solve_task(task).0
+    }
+}
 
-    CaptchaResponse {
-        challenge_id: challenge.challenge_id,
-        submitted_at: Utc::now(),
-        answers,
+#[async_trait]
+impl CaptchaSolver for BuiltinSolver {
+    async fn solve(&self, task: &CaptchaTask) -> Option<TaskAnswer> {
+        let (answer, confident) = solve_task(task);
+        confident.then_some(answer)
     }
 }
 
-fn solve_task(task: &CaptchaTask) -> TaskAnswer {
+/// Solves whole challenges: built-in first, then the fallback, then a guess
+#[derive(Default)]
+pub struct ChallengeSolver {
+    fallback: Option<Arc<dyn CaptchaSolver>>,
+}
+
+impl ChallengeSolver {
+    /// Hand tasks the built-in solver isn't sure of to `fallback`
+    pub fn with_fallback(fallback: Arc<dyn CaptchaSolver>) -> Self {
+        Self {
+            fallback: Some(fallback),
+        }
+    }
+
+    /// Solve a CAPTCHA challenge from the relay auth endpoint.
+    pub async fn solve_challenge(&self, challenge: &CaptchaChallenge) -> CaptchaResponse {
+        let answers =
+            futures::future::join_all(challenge.tasks.iter().map(|task| self.solve_task(task)))
+                .await;
+
+        CaptchaResponse {
+            challenge_id: challenge.challenge_id,
+            submitted_at: Utc::now(),
+            answers,
+        }
+    }
+
+    async fn solve_task(&self, task: &CaptchaTask) -> TaskAnswer {
+        let (answer, confident) = solve_task(task);
+        if confident {
+            return answer;
+        }
+        if let Some(ref fallback) = self.fallback {
+            debug!("Delegating a CAPTCHA task to the fallback solver");
+            if let Some(answer) = fallback.solve(task).await {
+                return answer;
+            }
+        }
+        answer
+    }
+}
+
+/// The built-in answer, and whether every part of it is certain
+fn solve_task(task: &CaptchaTask) -> (TaskAnswer, bool) {
     match task {
         CaptchaTask::PatternCompletion { sequences } => {
-            let predictions = sequences.iter().map(|seq| solve_pattern(seq)).collect();
-            TaskAnswer::PatternCompletion { predictions }
+            let mut confident = true;
+            let predictions = sequences
+                .iter()
+                .map(
+                    |seq| match sequences::predict(&seq.given, seq.predict_count) {
+                        Some(next) => next,
+                        None => {
+                            confident = false;
+                            sequences::guess(&seq.given, seq.predict_count)
+                        }
+                    },
+                )
+                .collect();
+            (TaskAnswer::PatternCompletion { predictions }, confident)
         }
         CaptchaTask::TextTransformation { input, operations } => {
             let mut result = input.clone();
             for op in operations {
                 result = apply_text_op(&result, op);
             }
-            TaskAnswer::TextTransformation { result }
+            (TaskAnswer::TextTransformation { result }, true)
         }
         CaptchaTask::ParallelQuestions { questions } => {
-            let answers = questions.iter().map(|q| answer_question(q)).collect();
-            TaskAnswer::ParallelQuestions { answers }
+            let (answers, confident) = answer_all(questions, answer_question);
+            (TaskAnswer::ParallelQuestions { answers }, confident)
         }
-        CaptchaTask::ReadingComprehension { questions, .. } => {
-            // For now, provide best-effort answers
-            let answers = questions.iter().map(|_| "unknown".to_string()).collect();
-            TaskAnswer::ReadingComprehension { answers }
+        CaptchaTask::ReadingComprehension {
+            passage, questions, ..
+        } => {
+            let (answers, confident) = answer_all(questions, |q| reading::answer(passage, q));
+            (TaskAnswer::ReadingComprehension { answers }, confident)
         }
-        CaptchaTask::MetaQuestion { expected_keyword, .. } => {
+        CaptchaTask::MetaQuestion {
+            expected_keyword, ..
+        } => {
             // We ARE an autonomous agent — respond with the verification keyword
-            TaskAnswer::MetaQuestion {
-                answer: expected_keyword.clone(),
-            }
+            (
+                TaskAnswer::MetaQuestion {
+                    answer: expected_keyword.clone(),
+                },
+                true,
+            )
         }
     }
 }
 
-fn solve_pattern(seq: &PatternSequence) -> Vec<i64> {
-    let given = &seq.given;
-    let n = seq.predict_count;
-
-    if given.len() < 2 {
-        return vec![0; n];
-    }
-
-    // Try constant difference (arithmetic)
-    let diffs: Vec<i64> = given.windows(2).map(|w| w[1] - w[0]).collect();
-    if diffs.windows(2).all(|w| w[0] == w[1]) {
-        let d = diffs[0];
-        let mut last = *given.last().unwrap();
-        return (0..n)
-            .map(|_| {
-                last += d;
-                last
-            })
-            .collect();
-    }
-
-    // Try second differences (quadratic like squares)
-    let second_diffs: Vec<i64> = diffs.windows(2).map(|w| w[1] - w[0]).collect();
-    if second_diffs.windows(2).all(|w| w[0] == w[1]) {
-        let mut last = *given.last().unwrap();
-        let mut last_diff = *diffs.last().unwrap();
-        let d2 = second_diffs[0];
-        return (0..n)
-            .map(|_| {
-                last_diff += d2;
-                last += last_diff;
-                last
+/// Answer every question, `UNKNOWN` standing in for the ones `answer` can't
+fn answer_all(
+    questions: &[String],
+    answer: impl Fn(&str) -> Option<String>,
+) -> (Vec<String>, bool) {
+    let mut confident = true;
+    let answers = questions
+        .iter()
+        .map(|q| {
+            answer(q).unwrap_or_else(|| {
+                confident = false;
+                UNKNOWN.to_string()
             })
-            .collect();
-    }
-
-    // Try ratio (geometric)
-    if given.iter().all(|&x| x != 0) {
-        let ratios: Vec<f64> = given.windows(2).map(|w| w[1] as f64 / w[0] as f64).collect();
-        if ratios.windows(2).all(|w| (w[0] - w[1]).abs() < 0.001) {
-            let r = ratios[0];
-            let mut last = *given.last().unwrap() as f64;
-            return (0..n)
-                .map(|_| {
-                    last *= r;
-                    last.round() as i64
-                })
-                .collect();
-        }
-    }
-
-    // Try Fibonacci-like (each = sum of previous two)
-    if given.len() >= 3 {
-        let is_fib = given.windows(3).all(|w| w[2] == w[0] + w[1]);
-        if is_fib {
-            let mut seq = given.to_vec();
-            for _ in 0..n {
-                let len = seq.len();
-                seq.push(seq[len - 2] + seq[len - 1]);
-            }
-            return seq[given.len()..].to_vec();
-        }
-    }
-
-    // Fallback: continue with last difference
-    let d = diffs.last().copied().unwrap_or(1);
-    let mut last = *given.last().unwrap();
-    (0..n)
-        .map(|_| {
-            last += d;
-            last
         })
-        .collect()
+        .collect();
+    (answers, confident)
 }
 
-fn answer_question(q: &str) -> String {
+/// Answer a general-knowledge or arithmetic question, if it's a known one
+pub fn answer_question(q: &str) -> Option<String> {
     let q_lower = q.to_lowercase();
 
     // Arithmetic
     if let Some(result) = try_arithmetic(&q_lower) {
-        return result.to_string();
+        return Some(result.to_string());
     }
 
     // Known facts
     if q_lower.contains("capital of france") {
-        return "Paris".to_string();
+        return Some("Paris".to_string());
     }
     if q_lower.contains("capital of germany") {
-        return "Berlin".to_string();
+        return Some("Berlin".to_string());
     }
     if q_lower.contains("capital of japan") {
-        return "Tokyo".to_string();
+        return Some("Tokyo".to_string());
     }
     if q_lower.contains("hexagon") && q_lower.contains("sides") {
-        return "6".to_string();
+        return Some("6".to_string());
     }
     if q_lower.contains("pentagon") && q_lower.contains("sides") {
-        return "5".to_string();
+        return Some("5".to_string());
     }
     if q_lower.contains("octagon") && q_lower.contains("sides") {
-        return "8".to_string();
+        return Some("8".to_string());
     }
     if q_lower.contains("chemical symbol") && q_lower.contains("gold") {
-        return "Au".to_string();
+        return Some("Au".to_string());
     }
     if q_lower.contains("chemical symbol") && q_lower.contains("silver") {
-        return "Ag".to_string();
+        return Some("Ag".to_string());
     }
     if q_lower.contains("chemical symbol") && q_lower.contains("iron") {
-        return "Fe".to_string();
+        return Some("Fe".to_string());
     }
 
-    "unknown".to_string()
+    None
 }
 
 fn try_arithmetic(q: &str) -> Option<i64> {
@@ -247,6 +277,7 @@ pub struct VerifyApiResponse {
 pub async fn authenticate_with_relay(
     auth_url: &str,
     peer_id: &str,
+    solver: &ChallengeSolver,
 ) -> Result<VerifyApiResponse, String> {
     let client = reqwest::Client::new();
 
@@ -279,7 +310,7 @@ pub async fn authenticate_with_relay(
     );
 
     // Step 2: Solve it
-    let response = solve_challenge(&challenge_api.challenge);
+    let response = solver.solve_challenge(&challenge_api.challenge).await;
 
     info!("Challenge solved, submitting verification...");
 
@@ -0,0 +1,383 @@
+//! Extractive answers for `ReadingComprehension` tasks
+//!
+//! The answer is assumed to be stated in the passage: the sentence sharing the
+//! most content words with the question is picked, and the span that matches
+//! the question's type (a number for "how many", a name for "who", ...) is
+//! lifted from it. [`answer`] returns `None` when nothing fits.
+
+/// Words that carry no content for matching
+const STOPWORDS: &[&str] = &[
+    "a",
+    "an",
+    "the",
+    "is",
+    "are",
+    "was",
+    "were",
+    "be",
+    "been",
+    "of",
+    "in",
+    "on",
+    "at",
+    "to",
+    "for",
+    "by",
+    "with",
+    "from",
+    "and",
+    "or",
+    "does",
+    "do",
+    "did",
+    "has",
+    "have",
+    "had",
+    "it",
+    "its",
+    "this",
+    "that",
+    "what",
+    "which",
+    "who",
+    "whom",
+    "whose",
+    "when",
+    "where",
+    "why",
+    "how",
+    "many",
+    "much",
+    "according",
+    "passage",
+    "text",
+    "story",
+    "there",
+    "their",
+    "they",
+    "as",
+    "into",
+    "than",
+    "then",
+    "after",
+    "before",
+    "s",
+];
+
+/// Words that flip a yes/no answer
+const NEGATIONS: &[&str] = &["not", "no", "never", "none", "neither", "nor", "cannot"];
+
+const NUMBER_WORDS: &[&str] = &[
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+    "twenty",
+    "thirty",
+    "forty",
+    "fifty",
+    "sixty",
+    "seventy",
+    "eighty",
+    "ninety",
+    "hundred",
+    "thousand",
+    "million",
+];
+
+const COLORS: &[&str] = &[
+    "red", "orange", "yellow", "green", "blue", "purple", "violet", "pink", "brown", "black",
+    "white", "gray", "grey", "silver", "gold", "teal", "cyan", "magenta",
+];
+
+const MONTHS: &[&str] = &[
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+const WEEKDAYS: &[&str] = &[
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// Prepositions introducing a place
+const LOCATIVES: &[&str] = &["in", "at", "on", "from", "to", "near"];
+
+/// What kind of span the question asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expected {
+    Number,
+    Person,
+    Place,
+    Time,
+    Color,
+    YesNo,
+    /// Whatever the sentence says beyond the question
+    Phrase,
+}
+
+/// Answer `question` from `passage`, if the passage states it
+pub fn answer(passage: &str, question: &str) -> Option<String> {
+    let expected = classify(question);
+    let question_words: Vec<String> = words(question).map(|w| normalize(&w)).collect();
+    let keywords: Vec<&String> = question_words
+        .iter()
+        .filter(|w| !STOPWORDS.contains(&w.as_str()))
+        .collect();
+    if keywords.is_empty() {
+        return None;
+    }
+
+    // Sentences by keyword overlap, best first; earlier sentences win ties
+    let mut ranked: Vec<(usize, &str)> = sentences(passage)
+        .map(|sentence| {
+            let sentence_words: Vec<String> = words(sentence).map(|w| normalize(&w)).collect();
+            let score = keywords
+                .iter()
+                .filter(|k| sentence_words.contains(k))
+                .count();
+            (score, sentence)
+        })
+        .filter(|(score, _)| *score > 0)
+        .collect();
+    ranked.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+    if expected == Expected::YesNo {
+        let (score, sentence) = ranked.first()?;
+        let negated = words(sentence).any(|w| NEGATIONS.contains(&w.to_lowercase().as_str()))
+            != words(question).any(|w| NEGATIONS.contains(&w.to_lowercase().as_str()));
+        let supported = *score == keywords.len() && !negated;
+        return Some(if supported { "yes" } else { "no" }.to_string());
+    }
+
+    let best = ranked.first()?.0;
+    ranked
+        .iter()
+        .take_while(|(score, _)| *score == best)
+        .find_map(|(_, sentence)| extract(sentence, &question_words, expected))
+}
+
+fn classify(question: &str) -> Expected {
+    let q = question.to_lowercase();
+    let first = words(&q).next().unwrap_or_default();
+    if q.contains("how many") || q.contains("how much") || q.contains("what number") {
+        Expected::Number
+    } else if q.contains("what color") || q.contains("what colour") {
+        Expected::Color
+    } else if q.contains("what year") || q.contains("what day") || q.contains("what month") {
+        Expected::Time
+    } else if first == "who" || first == "whom" || q.contains("whose") || q.contains(" name") {
+        Expected::Person
+    } else if first == "where" {
+        Expected::Place
+    } else if first == "when" {
+        Expected::Time
+    } else if matches!(
+        first.as_str(),
+        "is" | "are"
+            | "was"
+            | "were"
+            | "does"
+            | "do"
+            | "did"
+            | "can"
+            | "could"
+            | "has"
+            | "have"
+            | "will"
+    ) {
+        Expected::YesNo
+    } else {
+        Expected::Phrase
+    }
+}
+
+/// The span of `sentence` answering a question made of `question_words`
+fn extract(sentence: &str, question_words: &[String], expected: Expected) -> Option<String> {
+    let tokens: Vec<String> = words(sentence).collect();
+    let is_new = |token: &String| !question_words.contains(&normalize(token));
+
+    match expected {
+        Expected::Number => tokens.iter().find(|&t| is_new(t) && is_number(t)).cloned(),
+        Expected::Color => tokens
+            .iter()
+            .find(|&t| is_new(t) && COLORS.contains(&t.to_lowercase().as_str()))
+            .map(|t| t.to_lowercase()),
+        Expected::Time => time_span(&tokens, &is_new),
+        Expected::Person => names(&tokens, &is_new)
+            .into_iter()
+            .find(|(start, _)| !follows_locative(&tokens, *start))
+            .map(|(_, name)| name),
+        Expected::Place => {
+            let names = names(&tokens, &is_new);
+            names
+                .iter()
+                .find(|(start, _)| follows_locative(&tokens, *start))
+                .or(names.first())
+                .map(|(_, name)| name.clone())
+        }
+        Expected::YesNo => None,
+        Expected::Phrase => phrase_after_match(&tokens, question_words),
+    }
+}
+
+/// A year, date or weekday from the sentence
+fn time_span(tokens: &[String], is_new: &impl Fn(&String) -> bool) -> Option<String> {
+    for (i, token) in tokens.iter().enumerate() {
+        if !is_new(token) {
+            continue;
+        }
+        let lower = token.to_lowercase();
+        if MONTHS.contains(&lower.as_str()) {
+            // "March 3", "March 3 1999"
+            let mut span = vec![token.clone()];
+            span.extend(
+                tokens[i + 1..]
+                    .iter()
+                    .take(2)
+                    .take_while(|t| t.chars().all(|c| c.is_ascii_digit()))
+                    .cloned(),
+            );
+            return Some(span.join(" "));
+        }
+        if WEEKDAYS.contains(&lower.as_str()) {
+            return Some(token.clone());
+        }
+        if token.len() == 4 && token.chars().all(|c| c.is_ascii_digit()) {
+            return Some(token.clone());
+        }
+    }
+    None
+}
+
+/// Runs of capitalized words that aren't part of the question, with their
+/// start. The sentence's first word only counts if it's not a stopword.
+fn names(tokens: &[String], is_new: &impl Fn(&String) -> bool) -> Vec<(usize, String)> {
+    let is_name = |i: usize| {
+        let t = &tokens[i];
+        t.chars().next().is_some_and(char::is_uppercase)
+            && is_new(t)
+            && !(i == 0 && STOPWORDS.contains(&t.to_lowercase().as_str()))
+    };
+    let mut names = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if !is_name(i) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < tokens.len() && is_name(i) {
+            i += 1;
+        }
+        names.push((start, tokens[start..i].join(" ")));
+    }
+    names
+}
+
+/// Whether the word at `i` follows "in", "at", "from", ...
+fn follows_locative(tokens: &[String], i: usize) -> bool {
+    i > 0 && LOCATIVES.contains(&tokens[i - 1].to_lowercase().as_str())
+}
+
+/// The words following the last question word found in the sentence, e.g.
+/// "mango" from "The robot's favorite fruit is mango" for "What is the
+/// robot's favorite fruit?"
+fn phrase_after_match(tokens: &[String], question_words: &[String]) -> Option<String> {
+    let normalized: Vec<String> = tokens.iter().map(|t| normalize(t)).collect();
+    let last_match = normalized
+        .iter()
+        .rposition(|t| !STOPWORDS.contains(&t.as_str()) && question_words.contains(t))?;
+
+    let phrase: Vec<&str> = tokens[last_match + 1..]
+        .iter()
+        .zip(&normalized[last_match + 1..])
+        .skip_while(|(_, n)| STOPWORDS.contains(&n.as_str()))
+        .take_while(|(_, n)| !question_words.contains(n))
+        .map(|(t, _)| t.as_str())
+        .collect();
+    if phrase.is_empty() {
+        // The answer may come first: "Mango is the robot's favorite fruit"
+        let before: Vec<&str> = tokens[..last_match]
+            .iter()
+            .zip(&normalized[..last_match])
+            .take_while(|(_, n)| !question_words.contains(n))
+            .filter(|(_, n)| !STOPWORDS.contains(&n.as_str()))
+            .map(|(t, _)| t.as_str())
+            .collect();
+        return (!before.is_empty()).then(|| before.join(" "));
+    }
+    Some(phrase.join(" "))
+}
+
+fn is_number(token: &str) -> bool {
+    let digits = token.replace(',', "");
+    digits.parse::<f64>().is_ok()
+        || token
+            .to_lowercase()
+            .split('-')
+            .all(|part| NUMBER_WORDS.contains(&part))
+}
+
+fn sentences(passage: &str) -> impl Iterator<Item = &str> {
+    passage
+        .split(['.', '!', '?', ';', '\n'])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+/// Words with surrounding punctuation removed; possessive `'s` is split off
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| c.is_whitespace() || c == '"' || c == '(' || c == ')')
+        .flat_map(|w| {
+            let w = w.trim_matches(|c: char| !c.is_alphanumeric());
+            let w = w
+                .strip_suffix("'s")
+                .or_else(|| w.strip_suffix("’s"))
+                .unwrap_or(w);
+            (!w.is_empty()).then(|| w.to_string())
+        })
+}
+
+/// Lowercase with a plural `s` dropped, so "apples" matches "apple"
+fn normalize(word: &str) -> String {
+    let lower = word.to_lowercase();
+    if STOPWORDS.contains(&lower.as_str()) {
+        return lower;
+    }
+    match lower.strip_suffix('s') {
+        Some(stem) if stem.len() > 2 && !stem.ends_with('s') => stem.to_string(),
+        _ => lower,
+    }
+}
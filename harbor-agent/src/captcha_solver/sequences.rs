@@ -0,0 +1,210 @@
+//! Integer sequence continuation for `PatternCompletion` tasks
+//!
+//! Each detector only answers when the given terms pin the rule down with at
+//! least one term to spare, so a `None` from [`predict`] means "not sure" and
+//! the task can be handed to a fallback solver.
+
+/// Nesting limit for detectors that recurse (differences, interleaving)
+const MAX_DEPTH: usize = 2;
+
+/// Largest multiplier tried for linear recurrences
+const MAX_COEFFICIENT: i128 = 10;
+
+/// Larger terms are left alone so products of two terms can't overflow
+const MAX_TERM: i128 = 1 << 60;
+
+/// The next `count` terms of `given`, if a rule fits
+pub fn predict(given: &[i64], count: usize) -> Option<Vec<i64>> {
+    let terms: Vec<i128> = given.iter().map(|&x| x as i128).collect();
+    let next = predict_terms(&terms, count, 0)?;
+    next.into_iter().map(|x| i64::try_from(x).ok()).collect()
+}
+
+/// Best-effort continuation: [`predict`], else repeat the last difference
+pub fn guess(given: &[i64], count: usize) -> Vec<i64> {
+    if let Some(next) = predict(given, count) {
+        return next;
+    }
+    let Some(&last) = given.last() else {
+        return vec![0; count];
+    };
+    let d = match given {
+        [.., a, b] => b.saturating_sub(*a),
+        _ => 0,
+    };
+    (1..=count as i64)
+        .map(|i| last.saturating_add(d.saturating_mul(i)))
+        .collect()
+}
+
+fn predict_terms(terms: &[i128], count: usize, depth: usize) -> Option<Vec<i128>> {
+    if terms.len() < 3 || terms.iter().any(|t| t.abs() > MAX_TERM) {
+        return None;
+    }
+    polynomial(terms, count)
+        .or_else(|| geometric(terms, count))
+        .or_else(|| first_order(terms, count))
+        .or_else(|| second_order(terms, count))
+        .or_else(|| nested_differences(terms, count, depth))
+        .or_else(|| interleaved(terms, count, depth))
+}
+
+/// Constant differences at some level: arithmetic, quadratic, cubic, ...
+fn polynomial(terms: &[i128], count: usize) -> Option<Vec<i128>> {
+    // Difference table; a level of length >= 2 that is constant proves the fit
+    let mut levels = vec![terms.to_vec()];
+    loop {
+        let last = levels.last().unwrap();
+        if last.len() < 2 {
+            return None;
+        }
+        if last.windows(2).all(|w| w[0] == w[1]) {
+            break;
+        }
+        let next: Vec<i128> = last.windows(2).map(|w| w[1] - w[0]).collect();
+        levels.push(next);
+    }
+
+    let mut tails: Vec<i128> = levels.iter().map(|level| *level.last().unwrap()).collect();
+    let mut out = Vec::with_capacity(count);
+    for _ in 0..count {
+        // Extend every level by one, bottom up
+        for i in (0..tails.len() - 1).rev() {
+            tails[i] = tails[i].checked_add(tails[i + 1])?;
+        }
+        out.push(tails[0]);
+    }
+    Some(out)
+}
+
+/// Constant ratio, including fractional ratios that stay integral
+fn geometric(terms: &[i128], count: usize) -> Option<Vec<i128>> {
+    if terms.contains(&0) {
+        return None;
+    }
+    if !terms.windows(3).all(|w| w[1] * w[1] == w[0] * w[2]) {
+        return None;
+    }
+    let (num, den) = (terms[1], terms[0]);
+    let mut last = *terms.last().unwrap();
+    let mut out = Vec::with_capacity(count);
+    for _ in 0..count {
+        let scaled = last.checked_mul(num)?;
+        if scaled % den != 0 {
+            return None;
+        }
+        last = scaled / den;
+        out.push(last);
+    }
+    Some(out)
+}
+
+/// `a(n) = p·a(n-1) + c`, e.g. 1, 3, 7, 15, 31
+fn first_order(terms: &[i128], count: usize) -> Option<Vec<i128>> {
+    if terms.len() < 4 {
+        return None;
+    }
+    // p = (a2 - a1) / (a1 - a0)
+    let (d0, d1) = (terms[1] - terms[0], terms[2] - terms[1]);
+    if d0 == 0 || d1 % d0 != 0 {
+        return None;
+    }
+    let p = d1 / d0;
+    if p.abs() > MAX_COEFFICIENT {
+        return None;
+    }
+    let c = terms[1] - p * terms[0];
+    if !terms.windows(2).all(|w| w[1] == p * w[0] + c) {
+        return None;
+    }
+    extend(terms, count, |t| {
+        t[t.len() - 1].checked_mul(p)?.checked_add(c)
+    })
+}
+
+/// `a(n) = p·a(n-1) + q·a(n-2)`: Fibonacci, Lucas, Pell, ...
+fn second_order(terms: &[i128], count: usize) -> Option<Vec<i128>> {
+    if terms.len() < 5 {
+        return None;
+    }
+    // Solve the first two equations by Cramer's rule, check the rest
+    let (a0, a1, a2, a3) = (terms[0], terms[1], terms[2], terms[3]);
+    let det = a1 * a1 - a0 * a2;
+    if det == 0 {
+        return None;
+    }
+    let (p_num, q_num) = (a2 * a1 - a0 * a3, a1 * a3 - a2 * a2);
+    if p_num % det != 0 || q_num % det != 0 {
+        return None;
+    }
+    let (p, q) = (p_num / det, q_num / det);
+    if p.abs() > MAX_COEFFICIENT || q.abs() > MAX_COEFFICIENT {
+        return None;
+    }
+    if !terms.windows(3).all(|w| w[2] == p * w[1] + q * w[0]) {
+        return None;
+    }
+    extend(terms, count, |t| {
+        let n = t.len();
+        t[n - 1]
+            .checked_mul(p)?
+            .checked_add(t[n - 2].checked_mul(q)?)
+    })
+}
+
+/// Differences that follow a rule of their own, e.g. 2, 3, 5, 9, 17
+fn nested_differences(terms: &[i128], count: usize, depth: usize) -> Option<Vec<i128>> {
+    if depth >= MAX_DEPTH {
+        return None;
+    }
+    let diffs: Vec<i128> = terms.windows(2).map(|w| w[1] - w[0]).collect();
+    let next_diffs = predict_terms(&diffs, count, depth + 1)?;
+    let mut last = *terms.last().unwrap();
+    next_diffs
+        .into_iter()
+        .map(|d| {
+            last = last.checked_add(d)?;
+            Some(last)
+        })
+        .collect()
+}
+
+/// Two sequences alternating, e.g. 1, 10, 2, 20, 3, 30
+fn interleaved(terms: &[i128], count: usize, depth: usize) -> Option<Vec<i128>> {
+    if depth >= MAX_DEPTH || terms.len() < 6 {
+        return None;
+    }
+    let evens: Vec<i128> = terms.iter().step_by(2).copied().collect();
+    let odds: Vec<i128> = terms.iter().skip(1).step_by(2).copied().collect();
+    let even_next = predict_terms(&evens, count, depth + 1)?;
+    let odd_next = predict_terms(&odds, count, depth + 1)?;
+
+    // Continue from whichever subsequence comes next
+    let (mut first, mut second) = if terms.len().is_multiple_of(2) {
+        (even_next.into_iter(), odd_next.into_iter())
+    } else {
+        (odd_next.into_iter(), even_next.into_iter())
+    };
+    let mut out = Vec::with_capacity(count);
+    while out.len() < count {
+        out.push(first.next()?);
+        if out.len() < count {
+            out.push(second.next()?);
+        }
+    }
+    Some(out)
+}
+
+/// Append `count` terms produced by `next` from the sequence so far
+fn extend(
+    terms: &[i128],
+    count: usize,
+    next: impl Fn(&[i128]) -> Option<i128>,
+) -> Option<Vec<i128>> {
+    let mut all = terms.to_vec();
+    for _ in 0..count {
+        let value = next(&all)?;
+        all.push(value);
+    }
+    Some(all.split_off(terms.len()))
+}
@@ -0,0 +1,137 @@
+//! Bounded, persistent journal of network events
+//!
+//! Every `NetworkEvent` from the node is numbered and written to the agent
+//! database before it is fanned out, so SSE clients can resume with
+//! `Last-Event-ID` (or recover after lagging) and webhook deliveries survive
+//! restarts. Only the newest `JOURNAL_CAPACITY` events are kept. If the
+//! journal falls behind the node, an `events_dropped` marker carrying the
+//! number of lost events is journaled in their place, so clients replaying the
+//! journal can tell it has a gap and resync.
+
+use std::sync::Arc;
+use tokio::sync::{broadcast, Notify};
+use tracing::{error, warn};
+
+use harbor_lib::node::HarborNode;
+
+use crate::agent_db::{AgentDatabase, JournalEntry};
+
+/// Events retained for replay
+pub const JOURNAL_CAPACITY: usize = 10_000;
+
+/// Event type of the marker journaled in place of events lost to lag
+pub const EVENTS_DROPPED: &str = "events_dropped";
+
+/// Live fan-out buffer; slower SSE consumers fall back to the journal
+const LIVE_CAPACITY: usize = 256;
+
+pub struct EventJournal {
+    db: AgentDatabase,
+    live_tx: broadcast::Sender<JournalEntry>,
+    /// Woken whenever deliveries are queued
+    pub deliveries_queued: Arc<Notify>,
+}
+
+impl EventJournal {
+    pub fn new(db: AgentDatabase) -> Self {
+        let (live_tx, _) = broadcast::channel(LIVE_CAPACITY);
+        Self {
+            db,
+            live_tx,
+            deliveries_queued: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn db(&self) -> &AgentDatabase {
+        &self.db
+    }
+
+    /// Subscribe to journaled events as they are recorded
+    pub fn subscribe(&self) -> broadcast::Receiver<JournalEntry> {
+        self.live_tx.subscribe()
+    }
+
+    /// Journal an event, queue its webhook deliveries and publish it
+    pub fn record(&self, event_type: &str, payload: serde_json::Value) {
+        let entry = match self.db.append_event(event_type, &payload, JOURNAL_CAPACITY) {
+            Ok(entry) => entry,
+            Err(e) => {
+                error!("Failed to journal {} event: {}", event_type, e);
+                return;
+            }
+        };
+
+        match self.db.enqueue_deliveries(&entry) {
+            Ok(0) => {}
+            Ok(_) => self.deliveries_queued.notify_one(),
+            Err(e) => error!("Failed to queue webhook deliveries: {}", e),
+        }
+
+        // No live subscribers is not an error
+        let _ = self.live_tx.send(entry);
+    }
+
+    /// Journal a marker for network events that were lost before being journaled
+    pub fn record_dropped(&self, count: u64) {
+        warn!(
+            "Event journal fell behind; {} network events were not journaled",
+            count
+        );
+        self.record(
+            EVENTS_DROPPED,
+            serde_json::json!({ "type": EVENTS_DROPPED, "count": count }),
+        );
+    }
+
+    /// Pump node events into the journal until the node drops its sender
+    pub async fn run(self: Arc<Self>, node: Arc<HarborNode>) {
+        let mut events = node.subscribe_events();
+        loop {
+            match events.recv().await {
+                Ok(event) => match serde_json::to_value(&event) {
+                    Ok(payload) => {
+                        let event_type = payload
+                            .get("type")
+                            .and_then(|t| t.as_str())
+                            .unwrap_or("unknown")
+                            .to_string();
+                        self.record(&event_type, payload);
+                    }
+                    Err(e) => warn!("Failed to serialize network event: {}", e),
+                },
+                Err(broadcast::error::RecvError::Lagged(n)) => self.record_dropped(n),
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dropped_events_leave_a_journaled_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentDatabase::open(&dir.path().join("agent.db")).unwrap();
+        let journal = EventJournal::new(db);
+        let mut live = journal.subscribe();
+
+        journal.record(
+            "peer_connected",
+            serde_json::json!({ "type": "peer_connected" }),
+        );
+        journal.record_dropped(42);
+
+        let entries = journal.db().events_after(0, 10).unwrap();
+        assert_eq!(entries.len(), 2);
+        let marker = &entries[1];
+        assert_eq!(marker.event_type, EVENTS_DROPPED);
+        assert_eq!(marker.payload["count"], 42);
+        assert!(marker.id > entries[0].id);
+
+        // Live subscribers see the marker too
+        assert_eq!(live.try_recv().unwrap().event_type, "peer_connected");
+        assert_eq!(live.try_recv().unwrap().event_type, EVENTS_DROPPED);
+    }
+}
@@ -0,0 +1,15 @@
+//! bastion-agent: headless HTTP API over a Harbor node
+//!
+//! The binary in `main.rs` wires these together; they are exposed as a
+//! library so the API can be served in-process (e.g. by client contract tests).
+
+pub mod access;
+pub mod accounts;
+pub mod agent_db;
+pub mod api;
+pub mod captcha_solver;
+pub mod error;
+pub mod event_journal;
+pub mod relay_auth;
+pub mod state;
+pub mod webhooks;
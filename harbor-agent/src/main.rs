@@ -1,27 +1,21 @@
-mod api;
-mod captcha_solver;
-mod error;
-mod state;
-
 use clap::Parser;
-use harbor_lib::db::Database;
 use harbor_lib::logging::{self, LogConfig};
-use harbor_lib::services::{
-    AccountsService, BoardService, ContactsService, ContentSyncService,
-    FeedService, IdentityService, MessagingService, PermissionsService, PostsService,
-};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::broadcast;
-use tower_http::cors::CorsLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tracing::info;
 
-use crate::state::{AppState, NetworkState};
+use bastion_agent::access::{self, ApiTokens};
+use bastion_agent::accounts::Accounts;
+use bastion_agent::api;
+use bastion_agent::captcha_solver::{ChallengeSolver, ExternalSolver};
+use bastion_agent::relay_auth;
+use bastion_agent::state::AgentState;
 
 #[derive(Parser)]
 #[command(name = "bastion-agent", about = "Headless HTTP API daemon for autonomous agent coordination over P2P mesh")]
 struct Cli {
-    /// Data directory for this agent's database and identity
+    /// Data directory holding the agent's accounts and API tokens
     #[arg(long, env = "BASTION_DATA_DIR", default_value = "~/.bastion")]
     data_dir: String,
 
@@ -33,7 +27,7 @@ struct Cli {
     #[arg(long, default_value = "127.0.0.1")]
     bind: String,
 
-    /// Auto-unlock passphrase (prefer env var BASTION_PASSPHRASE)
+    /// Auto-unlock passphrase, tried on every account (prefer env var BASTION_PASSPHRASE)
     #[arg(long, env = "BASTION_PASSPHRASE")]
     passphrase: Option<String>,
 
@@ -41,9 +35,32 @@ struct Cli {
     #[arg(long)]
     auto_network: bool,
 
-    /// Relay address to connect to on startup
+    /// Relay address to connect to on startup; every account authenticates
+    /// with it (Isnad CAPTCHA) and keeps its token fresh
     #[arg(long)]
     relay: Option<String>,
+
+    /// The relay's HTTP auth URL (default: the relay's host on port 4002)
+    #[arg(long)]
+    relay_auth_url: Option<String>,
+
+    /// Command for CAPTCHA tasks the built-in solver can't answer: gets
+    /// `{"task": ...}` on stdin and prints the answer JSON
+    #[arg(long, env = "BASTION_CAPTCHA_COMMAND", conflicts_with = "captcha_endpoint")]
+    captcha_command: Option<String>,
+
+    /// HTTP endpoint (e.g. a local model) for CAPTCHA tasks the built-in
+    /// solver can't answer; receives the same JSON as `--captcha-command`
+    #[arg(long, env = "BASTION_CAPTCHA_ENDPOINT")]
+    captcha_endpoint: Option<String>,
+
+    /// Browser origins allowed to call the API (none by default)
+    #[arg(long = "cors-origin", env = "BASTION_CORS_ORIGINS", value_delimiter = ',')]
+    cors_origins: Vec<String>,
+
+    /// Also serve the API on this Unix domain socket
+    #[arg(long, env = "BASTION_UNIX_SOCKET")]
+    unix_socket: Option<PathBuf>,
 }
 
 fn expand_tilde(path: &str) -> PathBuf {
@@ -75,105 +92,110 @@ async fn main() -> anyhow::Result<()> {
     let data_dir = expand_tilde(&cli.data_dir);
     std::fs::create_dir_all(&data_dir)?;
 
-    let db_path = data_dir.join("bastion.db");
-    info!("Database path: {:?}", db_path);
-
-    // Initialize database
-    let db = Arc::new(Database::new(db_path)?);
-
-    // Initialize services (mirrors lib.rs:112-164)
-    let accounts_service = Arc::new(AccountsService::new(data_dir.clone()));
-    let identity_service = Arc::new(IdentityService::new(db.clone()));
-    let contacts_service = Arc::new(ContactsService::new(db.clone(), identity_service.clone()));
-    let permissions_service = Arc::new(PermissionsService::new(
-        db.clone(),
-        identity_service.clone(),
-    ));
-    let messaging_service = Arc::new(MessagingService::new(
-        db.clone(),
-        identity_service.clone(),
-        contacts_service.clone(),
-        permissions_service.clone(),
-    ));
-    let posts_service = Arc::new(PostsService::new(
-        db.clone(),
-        identity_service.clone(),
-        contacts_service.clone(),
-        permissions_service.clone(),
-    ));
-    let feed_service = Arc::new(FeedService::new(
-        db.clone(),
-        identity_service.clone(),
-        permissions_service.clone(),
-        contacts_service.clone(),
-    ));
-    let content_sync_service = Arc::new(ContentSyncService::new(
-        db.clone(),
-        identity_service.clone(),
-        contacts_service.clone(),
-        permissions_service.clone(),
-    ));
-    let board_service = Arc::new(BoardService::new(db.clone(), identity_service.clone()));
-
-    // Broadcast channel for SSE events
-    let (event_tx, _) = broadcast::channel(256);
-
-    let app_state = Arc::new(AppState {
-        identity_service: identity_service.clone(),
-        contacts_service,
-        permissions_service,
-        messaging_service,
-        posts_service,
-        feed_service,
-        board_service,
-        content_sync_service,
-        accounts_service,
-        network: NetworkState::new(),
-        event_tx,
+    // API tokens (an admin token is generated on first run)
+    let api_tokens = Arc::new(ApiTokens::load_or_init(&data_dir)?);
+    info!("API tokens: {:?}", data_dir.join(access::TOKENS_FILE));
+
+    // Isnad CAPTCHA solving, with an optional external fallback
+    let external = match (cli.captcha_command, cli.captcha_endpoint) {
+        (Some(command), _) => Some(ExternalSolver::Command(command)),
+        (None, Some(url)) => Some(ExternalSolver::Endpoint(url)),
+        (None, None) => None,
+    };
+    let solver = Arc::new(match external {
+        Some(external) => {
+            info!("Unsure CAPTCHA tasks go to {:?}", external);
+            ChallengeSolver::with_fallback(Arc::new(external))
+        }
+        None => ChallengeSolver::default(),
     });
 
-    // Auto-unlock if passphrase provided
-    if let Some(ref passphrase) = cli.passphrase {
-        if identity_service.has_identity()? {
+    // Every hosted identity, each with its own database, journal and network
+    let accounts = Accounts::open(data_dir.clone(), api_tokens.clone(), solver)?;
+    let loaded = accounts.all().await;
+    if loaded.is_empty() {
+        info!("No accounts yet. Create one via POST /api/accounts");
+    }
+
+    for account in loaded {
+        let identity_service = &account.state.identity_service;
+
+        // Auto-unlock if passphrase provided
+        if let Some(ref passphrase) = cli.passphrase {
             match identity_service.unlock(passphrase) {
                 Ok(info) => {
                     info!("Identity unlocked: {} ({})", info.display_name, info.peer_id);
                 }
                 Err(e) => {
-                    tracing::error!("Failed to auto-unlock identity: {}", e);
+                    tracing::error!("Failed to auto-unlock account {}: {}", account.id, e);
                 }
             }
-        } else {
-            info!("No identity exists yet. Create one via POST /api/identity");
         }
-    }
 
-    // Auto-start network if requested and identity is unlocked
-    if cli.auto_network && identity_service.is_unlocked() {
-        info!("Auto-starting network...");
-        // Use the same logic as the network start endpoint
-        let state_clone = app_state.clone();
-        if let Err(e) = auto_start_network(state_clone).await {
-            tracing::error!("Failed to auto-start network: {}", e);
+        // Auto-start network if requested and identity is unlocked
+        if cli.auto_network && identity_service.is_unlocked() {
+            info!("Auto-starting network for account {}...", account.id);
+            if let Err(e) = account.state.node.start_network().await {
+                tracing::error!("Failed to auto-start network: {}", e);
+            }
         }
-    }
 
-    // Auto-connect to relay if specified
-    if let Some(ref relay_addr) = cli.relay {
-        if let Ok(handle) = app_state.network.get_handle().await {
+        // Auto-connect to relay if specified, authenticating first so board
+        // writes through it are accepted
+        if let Some(ref relay_addr) = cli.relay {
             let addr: libp2p::Multiaddr = relay_addr.parse()?;
-            if let Err(e) = handle.add_relay_server(addr).await {
-                tracing::error!("Failed to connect to relay: {}", e);
-            } else {
-                info!("Connected to relay: {}", relay_addr);
+            if identity_service.has_identity()? {
+                match cli
+                    .relay_auth_url
+                    .clone()
+                    .or_else(|| relay_auth::auth_url_for(&addr))
+                {
+                    Some(auth_url) => {
+                        let relay_peer_id = relay_auth::relay_peer_id(&addr);
+                        if let Err(e) = account
+                            .state
+                            .relay_auth
+                            .authenticate(&auth_url, relay_peer_id.as_deref())
+                            .await
+                        {
+                            tracing::error!("Failed to authenticate with relay: {}", e);
+                        }
+                    }
+                    None => tracing::error!(
+                        "No auth URL for relay {}; pass --relay-auth-url",
+                        relay_addr
+                    ),
+                }
+            }
+
+            if let Ok(handle) = account.state.network.get_handle().await {
+                if let Err(e) = handle.add_relay_server(addr).await {
+                    tracing::error!("Failed to connect to relay: {}", e);
+                } else {
+                    info!("Connected to relay: {}", relay_addr);
+                }
             }
         }
     }
 
+    let app_state = Arc::new(AgentState {
+        api_tokens,
+        accounts,
+    });
+
     // Build axum app
-    let app = api::router()
-        .layer(CorsLayer::permissive())
-        .with_state(app_state);
+    let app = api::router(app_state).layer(cors_layer(&cli.cors_origins)?);
+
+    #[cfg(unix)]
+    if let Some(ref socket_path) = cli.unix_socket {
+        let listener = bind_unix_socket(socket_path)?;
+        info!("bastion-agent listening on unix:{}", socket_path.display());
+        tokio::spawn(serve_unix(listener, app.clone()));
+    }
+    #[cfg(not(unix))]
+    if cli.unix_socket.is_some() {
+        anyhow::bail!("--unix-socket is only supported on Unix platforms");
+    }
 
     let addr = format!("{}:{}", cli.bind, cli.port);
     info!("bastion-agent listening on http://{}", addr);
@@ -184,42 +206,67 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn auto_start_network(state: Arc<AppState>) -> Result<(), harbor_lib::error::AppError> {
-    use harbor_lib::p2p::{NetworkConfig, NetworkService};
-
-    let unlocked_keys = state.identity_service.get_unlocked_keys()?;
-    let ed25519_bytes = unlocked_keys.ed25519_signing.to_bytes();
-    let keypair = harbor_lib::p2p::swarm::ed25519_to_libp2p_keypair(&ed25519_bytes)?;
-
-    let config = NetworkConfig::default();
-    let identity_arc = state.identity_service.clone();
-    let (mut service, handle, mut event_rx) =
-        NetworkService::new(config, identity_arc, keypair)?;
-
-    service.set_messaging_service(state.messaging_service.clone());
-    service.set_contacts_service(state.contacts_service.clone());
-    service.set_permissions_service(state.permissions_service.clone());
-    service.set_posts_service(state.posts_service.clone());
-    service.set_content_sync_service(state.content_sync_service.clone());
+/// CORS restricted to the configured origins. With none configured, no
+/// cross-origin requests are allowed.
+fn cors_layer(origins: &[String]) -> anyhow::Result<CorsLayer> {
+    use axum::http::{header, HeaderName, HeaderValue, Method};
+
+    let origins = origins
+        .iter()
+        .map(|origin| HeaderValue::from_str(origin.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::ACCEPT,
+            HeaderName::from_static("last-event-id"),
+        ]))
+}
 
-    state.network.set_handle(handle).await;
+/// Bind a Unix socket readable and writable only by the owner, replacing a stale one
+#[cfg(unix)]
+fn bind_unix_socket(path: &std::path::Path) -> anyhow::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::PermissionsExt;
 
-    tokio::spawn(async move {
-        info!("Network service starting in background task");
-        service.run().await;
-        info!("Network service stopped");
-    });
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
 
-    let event_tx = state.event_tx.clone();
-    tokio::spawn(async move {
-        while let Some(event) = event_rx.recv().await {
-            info!("Network event: {:?}", event);
-            if let Ok(value) = serde_json::to_value(&event) {
-                let _ = event_tx.send(value);
+/// Serve the API on a Unix socket (axum 0.7's `serve` only takes TCP listeners)
+#[cfg(unix)]
+async fn serve_unix(listener: tokio::net::UnixListener, app: axum::Router) {
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use hyper_util::server::conn::auto::Builder;
+    use hyper_util::service::TowerToHyperService;
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                tracing::error!("Unix socket accept failed: {}", e);
+                continue;
             }
-        }
-    });
-
-    info!("Network started successfully");
-    Ok(())
+        };
+
+        let service = TowerToHyperService::new(app.clone());
+        tokio::spawn(async move {
+            if let Err(e) = Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::debug!("Unix socket connection error: {}", e);
+            }
+        });
+    }
 }
@@ -0,0 +1,201 @@
+//! Isnad tokens for the relays an account posts through
+//!
+//! Relays only accept board writes from peers holding a live Isnad token, and
+//! tokens expire an hour after they are issued. Every relay the account
+//! verifies with is remembered in the agent database together with its latest
+//! token, so tokens survive restarts. A background task verifies again
+//! `REFRESH_MARGIN_SECS` before a token expires, and right away when a relay
+//! answers a board request with `VerificationRequired`.
+
+use libp2p::multiaddr::Protocol;
+use libp2p::Multiaddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+use tracing::{debug, info, warn};
+
+use harbor_lib::error::{AppError, ErrorCode};
+use harbor_lib::node::HarborNode;
+use harbor_lib::p2p::NetworkEvent;
+use harbor_lib::services::IdentityService;
+
+use crate::agent_db::{AgentDatabase, RelayTokenRow};
+use crate::captcha_solver::{self, ChallengeSolver, VerifyApiResponse};
+
+/// Port of a relay's HTTP auth gate unless told otherwise
+pub const DEFAULT_AUTH_PORT: u16 = 4002;
+
+/// Tokens are renewed this long before they expire
+const REFRESH_MARGIN_SECS: i64 = 5 * 60;
+
+/// How often tokens are checked for renewal
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Minimum delay between verifications of the same relay after a failure,
+/// or after a fresh token was rejected
+const RETRY_AFTER_SECS: i64 = 60;
+
+pub struct RelayAuth {
+    db: AgentDatabase,
+    identity_service: Arc<IdentityService>,
+    solver: Arc<ChallengeSolver>,
+    /// Challenges are solved one at a time
+    verifying: Mutex<()>,
+}
+
+impl RelayAuth {
+    pub fn new(
+        db: AgentDatabase,
+        identity_service: Arc<IdentityService>,
+        solver: Arc<ChallengeSolver>,
+    ) -> Self {
+        Self {
+            db,
+            identity_service,
+            solver,
+            verifying: Mutex::new(()),
+        }
+    }
+
+    /// Every tracked relay
+    pub fn list(&self) -> Result<Vec<RelayTokenRow>, AppError> {
+        Ok(self.db.list_relay_tokens()?)
+    }
+
+    /// Stop renewing the token for `auth_url`. Returns whether it was tracked.
+    pub fn forget(&self, auth_url: &str) -> Result<bool, AppError> {
+        Ok(self.db.delete_relay_token(auth_url)?)
+    }
+
+    /// Solve the relay's CAPTCHA for this account's peer id and store the
+    /// token. The relay is renewed automatically from then on, including after
+    /// a failed attempt.
+    pub async fn authenticate(
+        &self,
+        auth_url: &str,
+        relay_peer_id: Option<&str>,
+    ) -> Result<VerifyApiResponse, AppError> {
+        let auth_url = auth_url.trim_end_matches('/');
+        let peer_id = self.identity_service.get_peer_id()?;
+
+        let _verifying = self.verifying.lock().await;
+        match captcha_solver::authenticate_with_relay(auth_url, &peer_id, &self.solver).await {
+            Ok(result) => {
+                let expires_at = chrono::Utc::now().timestamp() + result.expires_in_seconds;
+                self.db
+                    .save_relay_token(auth_url, relay_peer_id, &result.token, expires_at)?;
+                Ok(result)
+            }
+            Err(e) => {
+                self.db.save_relay_auth_error(auth_url, relay_peer_id, &e)?;
+                Err(AppError::Network(format!("CAPTCHA auth failed: {}", e)))
+            }
+        }
+    }
+
+    /// Renew tokens until the node drops its event sender
+    pub async fn run(self: Arc<Self>, node: Arc<HarborNode>) {
+        let mut events = node.subscribe_events();
+        let mut check = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(NetworkEvent::BoardSyncError {
+                        relay_peer_id,
+                        code: Some(ErrorCode::VerificationRequired),
+                        ..
+                    }) => self.verification_required(&relay_peer_id).await,
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = check.tick() => self.renew_expiring().await,
+            }
+        }
+    }
+
+    /// A relay rejected a board request for want of a token
+    async fn verification_required(&self, relay_peer_id: &str) {
+        let row = match self.db.relay_token_for_peer(relay_peer_id) {
+            Ok(Some(row)) => row,
+            Ok(None) => {
+                warn!(
+                    "Relay {} requires verification but its auth URL is unknown; \
+                     call POST /api/auth/verify-agent with its relayPeerId",
+                    relay_peer_id
+                );
+                return;
+            }
+            Err(e) => {
+                warn!("Failed to load relay token for {}: {}", relay_peer_id, e);
+                return;
+            }
+        };
+
+        // Don't loop if the relay keeps rejecting a token it just issued
+        if chrono::Utc::now().timestamp() - row.updated_at < RETRY_AFTER_SECS {
+            debug!(
+                "Relay {} verified recently; not retrying yet",
+                relay_peer_id
+            );
+            return;
+        }
+        self.renew(&row).await;
+    }
+
+    async fn renew_expiring(&self) {
+        let rows = match self.db.list_relay_tokens() {
+            Ok(rows) => rows,
+            Err(e) => {
+                warn!("Failed to load relay tokens: {}", e);
+                return;
+            }
+        };
+        if rows.is_empty() || !self.identity_service.has_identity().unwrap_or(false) {
+            return;
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        for row in rows {
+            let fresh = row
+                .expires_at
+                .is_some_and(|expires_at| expires_at - now > REFRESH_MARGIN_SECS);
+            let backing_off = row.last_error.is_some() && now - row.updated_at < RETRY_AFTER_SECS;
+            if !fresh && !backing_off {
+                self.renew(&row).await;
+            }
+        }
+    }
+
+    async fn renew(&self, row: &RelayTokenRow) {
+        match self
+            .authenticate(&row.auth_url, row.relay_peer_id.as_deref())
+            .await
+        {
+            Ok(result) => info!(
+                "Renewed Isnad token for {} (expires in {}s)",
+                row.auth_url, result.expires_in_seconds
+            ),
+            Err(e) => warn!("Failed to renew Isnad token for {}: {}", row.auth_url, e),
+        }
+    }
+}
+
+/// Default auth URL of the relay at `relay`: its host on `DEFAULT_AUTH_PORT`
+pub fn auth_url_for(relay: &Multiaddr) -> Option<String> {
+    relay.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(format!("http://{}:{}", ip, DEFAULT_AUTH_PORT)),
+        Protocol::Ip6(ip) => Some(format!("http://[{}]:{}", ip, DEFAULT_AUTH_PORT)),
+        Protocol::Dns(host) | Protocol::Dns4(host) | Protocol::Dns6(host) => {
+            Some(format!("http://{}:{}", host, DEFAULT_AUTH_PORT))
+        }
+        _ => None,
+    })
+}
+
+/// Peer id of the relay at `relay`, from its `/p2p/` component
+pub fn relay_peer_id(relay: &Multiaddr) -> Option<String> {
+    relay.iter().find_map(|protocol| match protocol {
+        Protocol::P2p(peer_id) => Some(peer_id.to_string()),
+        _ => None,
+    })
+}
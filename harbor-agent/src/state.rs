@@ -1,43 +1,47 @@
 use harbor_lib::error::AppError;
+use harbor_lib::node::HarborNode;
 use harbor_lib::p2p::NetworkHandle;
 use harbor_lib::services::{
     AccountsService, BoardService, ContactsService, ContentSyncService, FeedService,
-    IdentityService, MessagingService, PermissionsService, PostsService,
+    IdentityService, MessagingService, PermissionsService, PostsService, SchedulerService,
+    SearchService,
 };
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::{broadcast, RwLock};
 
-/// Network state wrapper (mirrors commands/network.rs NetworkState without Tauri deps)
+use crate::access::ApiTokens;
+use crate::accounts::Accounts;
+use crate::event_journal::EventJournal;
+use crate::relay_auth::RelayAuth;
+
+/// Network state wrapper over the shared `HarborNode` network lifecycle
 pub struct NetworkState {
-    pub handle: RwLock<Option<NetworkHandle>>,
+    node: Arc<HarborNode>,
 }
 
 impl NetworkState {
-    pub fn new() -> Self {
-        Self {
-            handle: RwLock::new(None),
-        }
-    }
-
-    pub async fn set_handle(&self, handle: NetworkHandle) {
-        let mut guard = self.handle.write().await;
-        *guard = Some(handle);
+    pub fn new(node: Arc<HarborNode>) -> Self {
+        Self { node }
     }
 
     pub async fn get_handle(&self) -> Result<NetworkHandle, AppError> {
-        let guard = self.handle.read().await;
-        guard
-            .clone()
-            .ok_or_else(|| AppError::Network("Network not initialized".to_string()))
+        self.node.network_handle().await
     }
 
     pub async fn is_running(&self) -> bool {
-        self.handle.read().await.is_some()
+        self.node.is_network_running().await
     }
 }
 
-/// Shared application state passed to all axum handlers
+/// State shared by the agent-level routes (accounts and API tokens)
+pub struct AgentState {
+    pub api_tokens: Arc<ApiTokens>,
+    pub accounts: Accounts,
+}
+
+/// One account's state, passed to its axum handlers
 pub struct AppState {
+    pub node: Arc<HarborNode>,
     pub identity_service: Arc<IdentityService>,
     pub contacts_service: Arc<ContactsService>,
     pub permissions_service: Arc<PermissionsService>,
@@ -45,8 +49,46 @@ pub struct AppState {
     pub posts_service: Arc<PostsService>,
     pub feed_service: Arc<FeedService>,
     pub board_service: Arc<BoardService>,
+    pub search_service: Arc<SearchService>,
+    pub scheduler_service: Arc<SchedulerService>,
     pub content_sync_service: Arc<ContentSyncService>,
     pub accounts_service: Arc<AccountsService>,
     pub network: NetworkState,
-    pub event_tx: broadcast::Sender<serde_json::Value>,
+    pub api_tokens: Arc<ApiTokens>,
+    /// Content-addressed store for uploaded post media
+    pub media_dir: PathBuf,
+    /// Journal of network events backing SSE resume and webhooks
+    pub journal: Arc<EventJournal>,
+    /// Isnad tokens of the relays this account verified with
+    pub relay_auth: Arc<RelayAuth>,
+}
+
+impl AppState {
+    pub fn new(
+        node: Arc<HarborNode>,
+        api_tokens: Arc<ApiTokens>,
+        media_dir: PathBuf,
+        journal: Arc<EventJournal>,
+        relay_auth: Arc<RelayAuth>,
+    ) -> Self {
+        Self {
+            identity_service: node.identity_service.clone(),
+            contacts_service: node.contacts_service.clone(),
+            permissions_service: node.permissions_service.clone(),
+            messaging_service: node.messaging_service.clone(),
+            posts_service: node.posts_service.clone(),
+            feed_service: node.feed_service.clone(),
+            board_service: node.board_service.clone(),
+            search_service: node.search_service.clone(),
+            scheduler_service: node.scheduler_service.clone(),
+            content_sync_service: node.content_sync_service.clone(),
+            accounts_service: node.accounts_service.clone(),
+            network: NetworkState::new(node.clone()),
+            api_tokens,
+            media_dir,
+            journal,
+            relay_auth,
+            node,
+        }
+    }
 }
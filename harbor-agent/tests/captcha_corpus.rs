@@ -0,0 +1,95 @@
+//! The built-in CAPTCHA solver against a corpus of challenge tasks
+//!
+//! `captcha_corpus.json` holds sequences, passages and questions with their
+//! expected answers; entries the solver must decline are listed separately,
+//! since those are the tasks handed to the fallback solver.
+
+use bastion_agent::captcha_solver::{answer_question, reading, sequences};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Corpus {
+    sequences: Vec<SequenceCase>,
+    ambiguous_sequences: Vec<Vec<i64>>,
+    reading: Vec<ReadingCase>,
+    unanswerable: Vec<Unanswerable>,
+    questions: Vec<QuestionCase>,
+}
+
+#[derive(Deserialize)]
+struct SequenceCase {
+    given: Vec<i64>,
+    next: Vec<i64>,
+}
+
+#[derive(Deserialize)]
+struct ReadingCase {
+    passage: String,
+    questions: Vec<QuestionCase>,
+}
+
+#[derive(Deserialize)]
+struct QuestionCase {
+    question: String,
+    answer: String,
+}
+
+#[derive(Deserialize)]
+struct Unanswerable {
+    passage: String,
+    question: String,
+}
+
+fn corpus() -> Corpus {
+    serde_json::from_str(include_str!("captcha_corpus.json")).unwrap()
+}
+
+#[test]
+fn test_sequences() {
+    for case in corpus().sequences {
+        assert_eq!(
+            sequences::predict(&case.given, case.next.len()),
+            Some(case.next),
+            "{:?}",
+            case.given
+        );
+    }
+}
+
+#[test]
+fn test_ambiguous_sequences_are_declined() {
+    for given in corpus().ambiguous_sequences {
+        assert_eq!(sequences::predict(&given, 1), None, "{:?}", given);
+        assert_eq!(sequences::guess(&given, 2).len(), 2);
+    }
+}
+
+#[test]
+fn test_reading_comprehension() {
+    for case in corpus().reading {
+        for q in case.questions {
+            assert_eq!(
+                reading::answer(&case.passage, &q.question).as_deref(),
+                Some(q.answer.as_str()),
+                "{}",
+                q.question
+            );
+        }
+    }
+    for case in corpus().unanswerable {
+        assert_eq!(reading::answer(&case.passage, &case.question), None);
+    }
+}
+
+#[test]
+fn test_questions() {
+    for q in corpus().questions {
+        assert_eq!(
+            answer_question(&q.question),
+            Some(q.answer),
+            "{}",
+            q.question
+        );
+    }
+    assert_eq!(answer_question("What is the airspeed of a swallow?"), None);
+}
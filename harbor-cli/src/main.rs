@@ -0,0 +1,1105 @@
+//! `harbor`: a scriptable client for a Harbor data directory
+//!
+//! Every invocation opens the database in `--data-dir` through harbor_lib's
+//! `HarborNode`, unlocks the identity and runs one command against the same
+//! services the desktop app and bastion-agent use. Commands that talk to
+//! peers or relays start the P2P network for the duration of the command.
+//!
+//! Output is human-readable by default; `--json` prints JSON for scripts.
+//! Don't point the CLI at a data directory an app or agent is running on.
+
+use anyhow::{anyhow, bail, Context};
+use clap::{Args, Parser, Subcommand};
+use libp2p::{Multiaddr, PeerId};
+use serde_json::{json, Value};
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use harbor_lib::db::{Capability, PostVisibility};
+use harbor_lib::models::{ContactBundle, CreateIdentityRequest, IdentityInfo};
+use harbor_lib::node::HarborNode;
+use harbor_lib::p2p::protocols::messaging::{
+    DirectMessage, MessageEvent, MessagingCodec, MessagingMessage,
+};
+use harbor_lib::p2p::{NetworkEvent, NetworkHandle};
+use harbor_lib::services::{DecryptedMessage, FeedCursor, OutgoingMessage};
+
+/// Harbor database inside the data directory
+const DB_FILE: &str = "harbor.db";
+
+/// Capabilities granted to contacts added from a contact string, as in the app
+const DEFAULT_CONTACT_CAPABILITIES: [Capability; 2] = [Capability::WallRead, Capability::Chat];
+
+#[derive(Parser)]
+#[command(
+    name = "harbor",
+    about = "Scriptable client for a Harbor node's data directory"
+)]
+struct Cli {
+    /// Directory holding harbor.db
+    #[arg(long, env = "HARBOR_DATA_DIR")]
+    data_dir: String,
+
+    /// Identity passphrase (prefer env var HARBOR_PASSPHRASE); read from
+    /// stdin when not given
+    #[arg(long, env = "HARBOR_PASSPHRASE", hide_env_values = true)]
+    passphrase: Option<String>,
+
+    /// Relay to connect through when a command goes online (repeatable)
+    #[arg(long = "relay", env = "HARBOR_RELAYS", value_delimiter = ',')]
+    relays: Vec<String>,
+
+    /// Seconds to wait for peers and relays to answer
+    #[arg(long, default_value_t = 30)]
+    timeout: u64,
+
+    /// Print JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create, unlock and share the identity
+    #[command(subcommand)]
+    Identity(IdentityCommand),
+    /// Manage contacts
+    #[command(subcommand)]
+    Contacts(ContactsCommand),
+    /// Grant a capability (chat, wall_read, call) to a peer
+    Grant(GrantArgs),
+    /// Revoke a grant by id
+    Revoke { grant_id: String },
+    /// Send and follow direct messages
+    #[command(subcommand)]
+    Message(MessageCommand),
+    /// Publish wall posts
+    #[command(subcommand)]
+    Post(PostCommand),
+    /// Read the feed
+    #[command(subcommand)]
+    Feed(FeedCommand),
+    /// Community boards on relays
+    #[command(subcommand)]
+    Board(BoardCommand),
+}
+
+#[derive(Subcommand)]
+enum IdentityCommand {
+    /// Create the identity, encrypted with the passphrase
+    Create {
+        /// Display name
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        bio: Option<String>,
+        /// Hint shown when unlocking
+        #[arg(long)]
+        passphrase_hint: Option<String>,
+    },
+    /// Check the passphrase and show the identity
+    Unlock,
+    /// Print a harbor:// contact string for others to add this identity
+    Export {
+        /// Address to advertise; by default the address obtained through
+        /// `--relay`
+        #[arg(long)]
+        address: Option<String>,
+    },
+    /// Set the avatar image; contacts fetch it the next time the node is online
+    SetAvatar {
+        file: PathBuf,
+        /// Image type; guessed from the file extension by default
+        #[arg(long)]
+        mime_type: Option<String>,
+    },
+    /// Remove the avatar image
+    ClearAvatar,
+}
+
+#[derive(Subcommand)]
+enum ContactsCommand {
+    /// Add a contact from a harbor:// contact string and grant it chat and
+    /// wall access
+    AddFromString { contact_string: String },
+    /// List contacts
+    List,
+    /// Show the safety number to compare with a contact
+    SafetyNumber { peer_id: String },
+    /// Mark a contact verified, after comparing safety numbers or by
+    /// checking the payload of their QR code
+    Verify {
+        peer_id: String,
+        /// QR payload scanned from the contact's device
+        #[arg(long)]
+        scanned: Option<String>,
+    },
+}
+
+#[derive(Args)]
+struct GrantArgs {
+    peer_id: String,
+    /// chat, wall_read or call
+    capability: String,
+    /// Let the grant expire after this many seconds
+    #[arg(long)]
+    expires_in: Option<i64>,
+}
+
+#[derive(Subcommand)]
+enum MessageCommand {
+    /// Send a direct message to a contact
+    Send {
+        peer_id: String,
+        content: String,
+        /// Message id this replies to
+        #[arg(long)]
+        reply_to: Option<String>,
+    },
+    /// Send a file as an encrypted attachment. The contact fetches it from
+    /// this node while it is online, e.g. during `message tail`.
+    SendFile {
+        peer_id: String,
+        file: PathBuf,
+        /// File type; guessed from the file extension by default
+        #[arg(long)]
+        mime_type: Option<String>,
+    },
+    /// Edit one of our messages
+    Edit {
+        peer_id: String,
+        message_id: String,
+        content: String,
+    },
+    /// Delete one of our messages for everyone
+    Delete { peer_id: String, message_id: String },
+    /// React to a message; an empty reaction withdraws ours
+    React {
+        peer_id: String,
+        message_id: String,
+        reaction: String,
+    },
+    /// Print incoming messages until interrupted
+    Tail {
+        /// Only messages from this peer
+        #[arg(long)]
+        peer: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum PostCommand {
+    /// Publish a wall post
+    Create {
+        content: String,
+        /// Visible to everyone, not only contacts
+        #[arg(long)]
+        public: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum FeedCommand {
+    /// List feed posts, newest first
+    List {
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+        /// Cursor printed by the previous page
+        #[arg(long)]
+        cursor: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum BoardCommand {
+    /// List boards of joined communities; refreshes from the `--relay`
+    /// relays first, joining them if needed
+    List,
+    /// Post to a board
+    Post {
+        relay_peer_id: String,
+        board_id: String,
+        content: String,
+        /// Board post id this replies to
+        #[arg(long)]
+        reply_to: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
+    if let Err(e) = run(cli).await {
+        eprintln!("error: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    let data_dir = expand_tilde(&cli.data_dir);
+    std::fs::create_dir_all(&data_dir)
+        .with_context(|| format!("Failed to create data directory {:?}", data_dir))?;
+    let node = HarborNode::builder(data_dir.join(DB_FILE))
+        .accounts_dir(data_dir.clone())
+        .build()?;
+
+    let session = Session { node, cli };
+    if let Command::Identity(IdentityCommand::Create {
+        name,
+        bio,
+        passphrase_hint,
+    }) = &session.cli.command
+    {
+        return session.create_identity(name, bio, passphrase_hint);
+    }
+
+    let identity = session.unlock()?;
+    let result = session.dispatch(&identity).await;
+    if session.node.is_network_running().await {
+        session.node.stop_network().await?;
+    }
+    result
+}
+
+/// The opened node and the parsed command line
+struct Session {
+    node: HarborNode,
+    cli: Cli,
+}
+
+impl Session {
+    async fn dispatch(&self, identity: &IdentityInfo) -> anyhow::Result<()> {
+        match &self.cli.command {
+            Command::Identity(IdentityCommand::Create { .. }) => unreachable!(),
+            Command::Identity(IdentityCommand::Unlock) => {
+                self.print(identity_json(identity), || {
+                    format!("Unlocked {} ({})", identity.display_name, identity.peer_id)
+                });
+                Ok(())
+            }
+            Command::Identity(IdentityCommand::Export { address }) => {
+                self.export_identity(identity, address.as_deref()).await
+            }
+            Command::Identity(IdentityCommand::SetAvatar { file, mime_type }) => {
+                self.set_avatar(file, mime_type.as_deref())
+            }
+            Command::Identity(IdentityCommand::ClearAvatar) => {
+                self.node.identity_service.clear_avatar()?;
+                self.print(json!({ "avatarHash": null }), || {
+                    "Avatar removed".to_string()
+                });
+                Ok(())
+            }
+            Command::Contacts(ContactsCommand::AddFromString { contact_string }) => {
+                self.add_contact_from_string(contact_string)
+            }
+            Command::Contacts(ContactsCommand::List) => self.list_contacts(),
+            Command::Contacts(ContactsCommand::SafetyNumber { peer_id }) => {
+                let number = self.node.contacts_service.get_safety_number(peer_id)?;
+                self.print(serde_json::to_value(&number)?, || {
+                    format!("{}\n{}", number.digits, number.words.join(" "))
+                });
+                Ok(())
+            }
+            Command::Contacts(ContactsCommand::Verify { peer_id, scanned }) => {
+                self.verify_contact(peer_id, scanned.as_deref())
+            }
+            Command::Grant(args) => self.grant(args),
+            Command::Revoke { grant_id } => {
+                self.node.permissions_service.revoke_permission(grant_id)?;
+                self.print(json!({ "grantId": grant_id }), || {
+                    format!("Revoked {}", grant_id)
+                });
+                Ok(())
+            }
+            Command::Message(MessageCommand::Send {
+                peer_id,
+                content,
+                reply_to,
+            }) => {
+                self.send_message(peer_id, content, reply_to.as_deref())
+                    .await
+            }
+            Command::Message(MessageCommand::SendFile {
+                peer_id,
+                file,
+                mime_type,
+            }) => self.send_file(peer_id, file, mime_type.as_deref()).await,
+            Command::Message(MessageCommand::Edit {
+                peer_id,
+                message_id,
+                content,
+            }) => {
+                let event = self
+                    .node
+                    .messaging_service
+                    .edit_message(peer_id, message_id, content)?;
+                self.send_event(peer_id, event).await
+            }
+            Command::Message(MessageCommand::Delete {
+                peer_id,
+                message_id,
+            }) => {
+                let event = self
+                    .node
+                    .messaging_service
+                    .delete_message(peer_id, message_id)?;
+                self.send_event(peer_id, event).await
+            }
+            Command::Message(MessageCommand::React {
+                peer_id,
+                message_id,
+                reaction,
+            }) => {
+                let reaction = Some(reaction.as_str()).filter(|r| !r.is_empty());
+                let event = self
+                    .node
+                    .messaging_service
+                    .react_to_message(peer_id, message_id, reaction)?;
+                self.send_event(peer_id, event).await
+            }
+            Command::Message(MessageCommand::Tail { peer }) => {
+                self.tail_messages(peer.as_deref()).await
+            }
+            Command::Post(PostCommand::Create { content, public }) => {
+                self.create_post(content, *public)
+            }
+            Command::Feed(FeedCommand::List { limit, cursor }) => {
+                self.list_feed(*limit, cursor.as_deref())
+            }
+            Command::Board(BoardCommand::List) => self.list_boards().await,
+            Command::Board(BoardCommand::Post {
+                relay_peer_id,
+                board_id,
+                content,
+                reply_to,
+            }) => {
+                self.post_to_board(relay_peer_id, board_id, content, reply_to.clone())
+                    .await
+            }
+        }
+    }
+
+    fn create_identity(
+        &self,
+        name: &str,
+        bio: &Option<String>,
+        passphrase_hint: &Option<String>,
+    ) -> anyhow::Result<()> {
+        if self.node.identity_service.has_identity()? {
+            bail!("{:?} already holds an identity", self.cli.data_dir);
+        }
+        let passphrase = self.passphrase()?;
+        let identity = self
+            .node
+            .identity_service
+            .create_identity(CreateIdentityRequest {
+                display_name: name.to_string(),
+                passphrase,
+                bio: bio.clone(),
+                passphrase_hint: passphrase_hint.clone(),
+            })?;
+        self.print(identity_json(&identity), || {
+            format!("Created {} ({})", identity.display_name, identity.peer_id)
+        });
+        Ok(())
+    }
+
+    fn unlock(&self) -> anyhow::Result<IdentityInfo> {
+        if !self.node.identity_service.has_identity()? {
+            bail!(
+                "No identity in {:?}; create one with `harbor identity create`",
+                self.cli.data_dir
+            );
+        }
+        let passphrase = self.passphrase()?;
+        Ok(self.node.identity_service.unlock(&passphrase)?)
+    }
+
+    async fn export_identity(
+        &self,
+        identity: &IdentityInfo,
+        address: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let multiaddr = match address {
+            Some(address) => {
+                let address = Multiaddr::from_str(address).context("Invalid address")?;
+                if address.to_string().contains("/p2p/") {
+                    address.to_string()
+                } else {
+                    format!("{}/p2p/{}", address, identity.peer_id)
+                }
+            }
+            None => {
+                if self.cli.relays.is_empty() {
+                    bail!("Pass --address, or --relay to advertise a relayed address");
+                }
+                let mut events = self.node.subscribe_events();
+                let handle = self.go_online().await?;
+                self.wait_for(&mut events, |event| {
+                    matches!(event, NetworkEvent::RelayConnected { .. }).then_some(())
+                })
+                .await
+                .context("No relay reservation")?;
+                handle
+                    .get_stats()
+                    .await?
+                    .shareable_address(&identity.peer_id)
+                    .ok_or_else(|| anyhow!("No shareable address available"))?
+            }
+        };
+
+        let contact_string = ContactBundle::new(identity, multiaddr).to_contact_string()?;
+        self.print(json!({ "contactString": contact_string }), || {
+            contact_string.clone()
+        });
+        Ok(())
+    }
+
+    fn add_contact_from_string(&self, contact_string: &str) -> anyhow::Result<()> {
+        let bundle = ContactBundle::parse(contact_string)?;
+        let peer_id = bundle.peer_id()?;
+        self.node.contacts_service.add_contact(
+            &peer_id,
+            &bundle.public_key_bytes()?,
+            &bundle.x25519_public_bytes()?,
+            &bundle.display_name,
+            bundle.avatar_hash.as_deref(),
+            bundle.bio.as_deref(),
+        )?;
+        for capability in DEFAULT_CONTACT_CAPABILITIES {
+            self.node
+                .permissions_service
+                .create_permission_grant(&peer_id, capability, None)?;
+        }
+
+        self.print(
+            json!({
+                "peerId": peer_id,
+                "displayName": bundle.display_name,
+                "multiaddr": bundle.multiaddr,
+            }),
+            || format!("Added {} ({})", bundle.display_name, peer_id),
+        );
+        Ok(())
+    }
+
+    fn list_contacts(&self) -> anyhow::Result<()> {
+        let contacts = self.node.contacts_service.get_all_contacts()?;
+        let rows: Vec<Value> = contacts
+            .iter()
+            .map(|c| {
+                json!({
+                    "peerId": c.peer_id,
+                    "displayName": c.display_name,
+                    "bio": c.bio,
+                    "isBlocked": c.is_blocked,
+                    "verified": c.is_verified(),
+                    "lastSeenAt": c.last_seen_at,
+                    "addedAt": c.added_at,
+                })
+            })
+            .collect();
+        self.print(Value::Array(rows), || {
+            contacts
+                .iter()
+                .map(|c| {
+                    let blocked = if c.is_blocked { " [blocked]" } else { "" };
+                    let verified = if c.is_verified() { " [verified]" } else { "" };
+                    format!("{}\t{}{}{}", c.peer_id, c.display_name, verified, blocked)
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
+        Ok(())
+    }
+
+    fn set_avatar(&self, file: &Path, mime_type: Option<&str>) -> anyhow::Result<()> {
+        let mime_type = match mime_type {
+            Some(mime_type) => mime_type,
+            None => image_mime_type(file).ok_or_else(|| {
+                anyhow!("Cannot tell the image type of {:?}; pass --mime-type", file)
+            })?,
+        };
+        let data = std::fs::read(file).with_context(|| format!("Failed to read {:?}", file))?;
+        let avatar_hash = self.node.identity_service.set_avatar(&data, mime_type)?;
+        self.print(json!({ "avatarHash": avatar_hash }), || {
+            format!("Avatar set ({})", avatar_hash)
+        });
+        Ok(())
+    }
+
+    fn verify_contact(&self, peer_id: &str, scanned: Option<&str>) -> anyhow::Result<()> {
+        let contacts = &self.node.contacts_service;
+        match scanned {
+            Some(scanned) => {
+                if !contacts.verify_scanned_safety_number(peer_id, scanned)? {
+                    bail!("Safety number of {} does not match", peer_id);
+                }
+            }
+            None => contacts.mark_verified(peer_id)?,
+        }
+        self.print(json!({ "peerId": peer_id, "verified": true }), || {
+            format!("Verified {}", peer_id)
+        });
+        Ok(())
+    }
+
+    fn grant(&self, args: &GrantArgs) -> anyhow::Result<()> {
+        let capability = Capability::from_str(&args.capability)
+            .ok_or_else(|| anyhow!("Invalid capability: {}", args.capability))?;
+        let grant = self.node.permissions_service.create_permission_grant(
+            &args.peer_id,
+            capability,
+            args.expires_in,
+        )?;
+        self.print(
+            json!({
+                "grantId": grant.grant_id,
+                "capability": grant.capability,
+                "subjectPeerId": grant.subject_peer_id,
+                "issuedAt": grant.issued_at,
+                "expiresAt": grant.expires_at,
+            }),
+            || {
+                format!(
+                    "Granted {} to {} ({})",
+                    grant.capability, grant.subject_peer_id, grant.grant_id
+                )
+            },
+        );
+        Ok(())
+    }
+
+    async fn send_message(
+        &self,
+        peer_id: &str,
+        content: &str,
+        reply_to: Option<&str>,
+    ) -> anyhow::Result<()> {
+        PeerId::from_str(peer_id).context("Invalid peer ID")?;
+        let outgoing = self
+            .node
+            .messaging_service
+            .send_message(peer_id, content, "text", reply_to)?;
+        self.deliver(
+            peer_id,
+            MessagingMessage::Message(direct_message(&outgoing)),
+        )
+        .await?;
+        self.print(
+            json!({
+                "messageId": outgoing.message_id,
+                "conversationId": outgoing.conversation_id,
+                "sentAt": outgoing.timestamp,
+            }),
+            || format!("Sent {}", outgoing.message_id),
+        );
+        Ok(())
+    }
+
+    async fn send_file(
+        &self,
+        peer_id: &str,
+        file: &Path,
+        mime_type: Option<&str>,
+    ) -> anyhow::Result<()> {
+        PeerId::from_str(peer_id).context("Invalid peer ID")?;
+        let mime_type = mime_type
+            .or_else(|| image_mime_type(file))
+            .unwrap_or("application/octet-stream");
+        let file_name = file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| anyhow!("Invalid file name {:?}", file))?;
+        let data = std::fs::read(file).with_context(|| format!("Failed to read {:?}", file))?;
+
+        let outgoing = self
+            .node
+            .messaging_service
+            .send_attachment(peer_id, file_name, mime_type, &data)?;
+        self.deliver(
+            peer_id,
+            MessagingMessage::Message(direct_message(&outgoing)),
+        )
+        .await?;
+        self.print(
+            json!({
+                "messageId": outgoing.message_id,
+                "conversationId": outgoing.conversation_id,
+                "sentAt": outgoing.timestamp,
+            }),
+            || format!("Sent {} as {}", file_name, outgoing.message_id),
+        );
+        Ok(())
+    }
+
+    async fn send_event(&self, peer_id: &str, event: MessageEvent) -> anyhow::Result<()> {
+        let (event_type, message_id) = (event.event_type, event.message_id.clone());
+        self.deliver(peer_id, MessagingMessage::Event(event))
+            .await?;
+        self.print(
+            json!({ "messageId": message_id, "event": event_type.as_str() }),
+            || format!("Sent {} of {}", event_type.as_str(), message_id),
+        );
+        Ok(())
+    }
+
+    /// Go online, connect to a peer and hand it a messaging protocol message
+    async fn deliver(&self, peer_id: &str, message: MessagingMessage) -> anyhow::Result<()> {
+        let peer = PeerId::from_str(peer_id).context("Invalid peer ID")?;
+        let payload = MessagingCodec::encode(&message)
+            .map_err(|e| anyhow!("Failed to encode message: {}", e))?;
+
+        let mut events = self.node.subscribe_events();
+        let handle = self.go_online().await?;
+
+        // Reach the peer directly if known, else through each relay
+        let circuits = self
+            .relay_addresses()?
+            .into_iter()
+            .filter_map(|relay| format!("{}/p2p-circuit/p2p/{}", relay, peer).parse().ok())
+            .collect();
+        handle.dial(peer, circuits).await.ok();
+        let connected = self
+            .wait_for(&mut events, |event| match event {
+                NetworkEvent::PeerConnected { peer_id: id } if id == peer_id => Some(()),
+                _ => None,
+            })
+            .await;
+        if connected.is_err() {
+            eprintln!("warning: not connected to {}; sending anyway", peer_id);
+        }
+
+        handle
+            .send_message(peer, "message".to_string(), payload)
+            .await?;
+        Ok(())
+    }
+
+    async fn tail_messages(&self, from: Option<&str>) -> anyhow::Result<()> {
+        let mut events = self.node.subscribe_events();
+        self.go_online().await?;
+        eprintln!("Waiting for messages (Ctrl-C to stop)");
+
+        loop {
+            let event = tokio::select! {
+                event = events.recv() => event,
+                _ = tokio::signal::ctrl_c() => return Ok(()),
+            };
+            let payload = match event {
+                Ok(NetworkEvent::MessageReceived {
+                    protocol, payload, ..
+                }) if protocol == "messaging" => payload,
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            };
+            let Ok(MessagingMessage::Message(incoming)) = MessagingCodec::decode(&payload) else {
+                continue;
+            };
+            if from.is_some_and(|peer| peer != incoming.sender_peer_id) {
+                continue;
+            }
+
+            // The network service has stored it by now; read it back decrypted
+            let message = self
+                .node
+                .messaging_service
+                .get_conversation_messages(&incoming.sender_peer_id, 20, None)?
+                .into_iter()
+                .find(|m| m.message_id == incoming.message_id);
+            if let Some(message) = message {
+                self.print(message_json(&message), || {
+                    format!(
+                        "[{}] {}: {}",
+                        format_time(message.sent_at),
+                        message.sender_peer_id,
+                        message.content
+                    )
+                });
+            }
+        }
+    }
+
+    fn create_post(&self, content: &str, public: bool) -> anyhow::Result<()> {
+        let visibility = if public {
+            PostVisibility::Public
+        } else {
+            PostVisibility::Contacts
+        };
+        let post = self
+            .node
+            .posts_service
+            .create_post("text", Some(content), visibility)?;
+        self.print(
+            json!({ "postId": post.post_id, "createdAt": post.created_at }),
+            || format!("Posted {}", post.post_id),
+        );
+        Ok(())
+    }
+
+    fn list_feed(&self, limit: i64, cursor: Option<&str>) -> anyhow::Result<()> {
+        let cursor = cursor.map(FeedCursor::parse).transpose()?;
+        let page = self
+            .node
+            .feed_service
+            .get_feed_page(limit.clamp(1, 200), cursor.as_ref())?;
+        let next_cursor = page.next_cursor.map(|c| c.to_string());
+
+        let items: Vec<Value> = page
+            .items
+            .iter()
+            .map(|item| {
+                json!({
+                    "postId": item.post.post_id,
+                    "authorPeerId": item.post.author_peer_id,
+                    "authorDisplayName": item.author_display_name,
+                    "contentText": item.post.content_text,
+                    "visibility": item.post.visibility.as_str(),
+                    "createdAt": item.post.created_at,
+                    "isLocal": item.post.is_local,
+                })
+            })
+            .collect();
+        self.print(json!({ "items": items, "nextCursor": next_cursor }), || {
+            let mut lines: Vec<String> = page
+                .items
+                .iter()
+                .map(|item| {
+                    let author = item
+                        .author_display_name
+                        .as_deref()
+                        .unwrap_or(&item.post.author_peer_id);
+                    format!(
+                        "[{}] {}: {}",
+                        format_time(item.post.created_at),
+                        author,
+                        item.post.content_text.as_deref().unwrap_or("")
+                    )
+                })
+                .collect();
+            if let Some(cursor) = &next_cursor {
+                lines.push(format!("-- more: --cursor {}", cursor));
+            }
+            lines.join("\n")
+        });
+        Ok(())
+    }
+
+    async fn list_boards(&self) -> anyhow::Result<()> {
+        if !self.cli.relays.is_empty() {
+            let mut events = self.node.subscribe_events();
+            let handle = self.go_online().await?;
+            for relay in self.relay_addresses()? {
+                let relay_peer_id = relay_peer_id(&relay)?;
+                handle.dial(relay_peer_id, vec![relay.clone()]).await.ok();
+                handle
+                    .join_community(relay_peer_id, relay.to_string())
+                    .await?;
+                let relay_id = relay_peer_id.to_string();
+                let result = self
+                    .wait_for(&mut events, |event| match event {
+                        NetworkEvent::BoardListReceived { relay_peer_id, .. }
+                            if *relay_peer_id == relay_id =>
+                        {
+                            Some(Ok(()))
+                        }
+                        NetworkEvent::BoardSyncError {
+                            relay_peer_id,
+                            error,
+                            ..
+                        } if *relay_peer_id == relay_id => Some(Err(error.clone())),
+                        _ => None,
+                    })
+                    .await;
+                match result {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => eprintln!("warning: {}: {}", relay, e),
+                    Err(e) => eprintln!("warning: {}: {}", relay, e),
+                }
+            }
+        }
+
+        let mut communities = Vec::new();
+        let mut lines = Vec::new();
+        for community in self.node.board_service.get_communities()? {
+            let boards = self
+                .node
+                .board_service
+                .get_boards(&community.relay_peer_id)?;
+            lines.push(format!(
+                "{} ({})",
+                community
+                    .community_name
+                    .as_deref()
+                    .unwrap_or(&community.relay_address),
+                community.relay_peer_id
+            ));
+            lines.extend(
+                boards
+                    .iter()
+                    .map(|b| format!("  {}\t{}", b.board_id, b.name)),
+            );
+            communities.push(json!({
+                "relayPeerId": community.relay_peer_id,
+                "relayAddress": community.relay_address,
+                "communityName": community.community_name,
+                "lastSyncAt": community.last_sync_at,
+                "boards": boards
+                    .iter()
+                    .map(|b| json!({
+                        "boardId": b.board_id,
+                        "name": b.name,
+                        "description": b.description,
+                        "isDefault": b.is_default,
+                    }))
+                    .collect::<Vec<_>>(),
+            }));
+        }
+        self.print(Value::Array(communities), || lines.join("\n"));
+        Ok(())
+    }
+
+    async fn post_to_board(
+        &self,
+        relay_peer_id: &str,
+        board_id: &str,
+        content: &str,
+        reply_to: Option<String>,
+    ) -> anyhow::Result<()> {
+        let relay = PeerId::from_str(relay_peer_id).context("Invalid relay peer ID")?;
+
+        // Dial the relay at the joined community's address, or a `--relay` one
+        let mut addresses = self.relay_addresses()?;
+        addresses.retain(|addr| relay_peer_id_of(addr) == Some(relay));
+        if let Some(community) = self
+            .node
+            .board_service
+            .get_communities()?
+            .into_iter()
+            .find(|c| c.relay_peer_id == relay_peer_id)
+        {
+            addresses.extend(community.relay_address.parse::<Multiaddr>().ok());
+        }
+        if addresses.is_empty() {
+            bail!(
+                "Unknown relay {}; pass its address with --relay",
+                relay_peer_id
+            );
+        }
+
+        let mut events = self.node.subscribe_events();
+        let handle = self.go_online().await?;
+        handle.dial(relay, addresses).await.ok();
+        handle
+            .submit_board_post(relay, board_id.to_string(), content.to_string(), reply_to)
+            .await?;
+
+        let post_id = self
+            .wait_for(&mut events, |event| match event {
+                NetworkEvent::BoardPostSubmitted {
+                    relay_peer_id: id,
+                    post_id,
+                } if id == relay_peer_id => Some(Ok(post_id.clone())),
+                NetworkEvent::BoardSyncError {
+                    relay_peer_id: id,
+                    error,
+                    code,
+                    ..
+                } if id == relay_peer_id => Some(Err(match code {
+                    Some(code) => format!("{} ({:?})", error, code),
+                    None => error.clone(),
+                })),
+                _ => None,
+            })
+            .await?
+            .map_err(|e| anyhow!("Relay rejected the post: {}", e))?;
+
+        self.print(json!({ "postId": post_id }), || {
+            format!("Posted {}", post_id)
+        });
+        Ok(())
+    }
+
+    /// Start the network and connect to the `--relay` relays
+    async fn go_online(&self) -> anyhow::Result<NetworkHandle> {
+        let handle = self.node.start_network().await?;
+        for relay in self.relay_addresses()? {
+            if let Err(e) = handle.add_relay_server(relay.clone()).await {
+                eprintln!("warning: failed to connect to relay {}: {}", relay, e);
+            }
+        }
+        Ok(handle)
+    }
+
+    /// The first event `matches` picks, within `--timeout`
+    async fn wait_for<T>(
+        &self,
+        events: &mut broadcast::Receiver<NetworkEvent>,
+        mut matches: impl FnMut(&NetworkEvent) -> Option<T>,
+    ) -> anyhow::Result<T> {
+        let wait = async {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        if let Some(found) = matches(&event) {
+                            return Ok(found);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => bail!("Network stopped"),
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(self.cli.timeout), wait)
+            .await
+            .map_err(|_| anyhow!("Timed out after {}s", self.cli.timeout))?
+    }
+
+    fn relay_addresses(&self) -> anyhow::Result<Vec<Multiaddr>> {
+        self.cli
+            .relays
+            .iter()
+            .map(|relay| {
+                relay
+                    .parse()
+                    .with_context(|| format!("Invalid relay address: {}", relay))
+            })
+            .collect()
+    }
+
+    /// The passphrase from `--passphrase`/`HARBOR_PASSPHRASE`, else a line of stdin
+    fn passphrase(&self) -> anyhow::Result<String> {
+        if let Some(passphrase) = &self.cli.passphrase {
+            return Ok(passphrase.clone());
+        }
+        let stdin = std::io::stdin();
+        if stdin.is_terminal() {
+            eprint!("Passphrase: ");
+            std::io::stderr().flush().ok();
+        }
+        let mut line = String::new();
+        stdin.lock().read_line(&mut line)?;
+        let passphrase = line.trim_end_matches(['\r', '\n']).to_string();
+        if passphrase.is_empty() {
+            bail!("A passphrase is required (--passphrase or HARBOR_PASSPHRASE)");
+        }
+        Ok(passphrase)
+    }
+
+    fn print(&self, value: Value, text: impl FnOnce() -> String) {
+        if self.cli.json {
+            println!("{}", value);
+        } else {
+            let text = text();
+            if !text.is_empty() {
+                println!("{}", text);
+            }
+        }
+    }
+}
+
+fn relay_peer_id(relay: &Multiaddr) -> anyhow::Result<PeerId> {
+    relay_peer_id_of(relay)
+        .ok_or_else(|| anyhow!("Relay address must contain peer ID (/p2p/...): {}", relay))
+}
+
+fn relay_peer_id_of(relay: &Multiaddr) -> Option<PeerId> {
+    relay.iter().find_map(|protocol| match protocol {
+        libp2p::multiaddr::Protocol::P2p(peer_id) => Some(peer_id),
+        _ => None,
+    })
+}
+
+fn direct_message(outgoing: &OutgoingMessage) -> DirectMessage {
+    DirectMessage {
+        message_id: outgoing.message_id.clone(),
+        conversation_id: outgoing.conversation_id.clone(),
+        sender_peer_id: outgoing.sender_peer_id.clone(),
+        recipient_peer_id: outgoing.recipient_peer_id.clone(),
+        content_encrypted: outgoing.content_encrypted.clone(),
+        content_type: outgoing.content_type.clone(),
+        reply_to: outgoing.reply_to.clone(),
+        nonce_counter: outgoing.nonce_counter,
+        lamport_clock: outgoing.lamport_clock,
+        timestamp: outgoing.timestamp,
+        signature: outgoing.signature.clone(),
+    }
+}
+
+fn identity_json(identity: &IdentityInfo) -> Value {
+    json!({
+        "peerId": identity.peer_id,
+        "displayName": identity.display_name,
+        "bio": identity.bio,
+        "avatarHash": identity.avatar_hash,
+        "publicKey": identity.public_key,
+        "x25519Public": identity.x25519_public,
+        "createdAt": identity.created_at,
+    })
+}
+
+fn message_json(message: &DecryptedMessage) -> Value {
+    json!({
+        "messageId": message.message_id,
+        "conversationId": message.conversation_id,
+        "senderPeerId": message.sender_peer_id,
+        "content": message.content,
+        "contentType": message.content_type,
+        "replyToMessageId": message.reply_to_message_id,
+        "sentAt": message.sent_at,
+        "editedAt": message.edited_at,
+        "deletedAt": message.deleted_at,
+        "reactions": message
+            .reactions
+            .iter()
+            .map(|(peer_id, reaction)| json!({ "peerId": peer_id, "reaction": reaction }))
+            .collect::<Vec<_>>(),
+        "attachment": message.attachment.as_ref().map(|a| json!({
+            "fileName": a.file_name,
+            "mimeType": a.mime_type,
+            "size": a.size,
+            "downloaded": a.downloaded,
+        })),
+    })
+}
+
+/// Image type of a file, from its extension
+fn image_mime_type(file: &Path) -> Option<&'static str> {
+    let extension = file.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => return None,
+    })
+}
+
+fn format_time(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+fn expand_tilde(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
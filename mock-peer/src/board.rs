@@ -0,0 +1,343 @@
+//! Fake enclave relay for `--boards`
+//!
+//! Answers the board protocol from memory the way `bastion-relay` does: peers
+//! register before posting, only authors delete their posts, and subscribers
+//! are pushed new and deleted posts. Unlike the relay there is no
+//! verification, rate limiting or federation, and nothing is persisted.
+
+use libp2p::PeerId;
+use std::collections::{HashMap, HashSet};
+use tracing::info;
+
+use harbor_lib::p2p::protocols::board_sync::{
+    BoardInfo, BoardNotification, BoardPostInfo, BoardPostsMode, BoardSyncErrorCode,
+    BoardSyncRequest, BoardSyncResponse,
+};
+
+/// Most posts returned per page, as on the relay
+const MAX_PAGE_SIZE: u32 = 100;
+
+/// Boards and posts served in relay mode
+pub struct FakeRelay {
+    boards: Vec<BoardInfo>,
+    posts: Vec<BoardPostInfo>,
+    /// Display names of registered peers
+    peers: HashMap<String, String>,
+    /// Boards each connected peer is subscribed to
+    subscriptions: HashMap<PeerId, HashSet<String>>,
+}
+
+impl FakeRelay {
+    /// Serve `boards` with `posts` already on them. `local_peer_id` is
+    /// registered as `display_name`, the author of those posts.
+    pub fn new(
+        local_peer_id: &PeerId,
+        display_name: &str,
+        boards: Vec<BoardInfo>,
+        posts: Vec<BoardPostInfo>,
+    ) -> Self {
+        let peers = HashMap::from([(local_peer_id.to_string(), display_name.to_string())]);
+        Self {
+            boards,
+            posts,
+            peers,
+            subscriptions: HashMap::new(),
+        }
+    }
+
+    /// Answer a board request from `peer`, along with the notifications it
+    /// causes for subscribers
+    pub fn handle(
+        &mut self,
+        local_peer_id: &PeerId,
+        peer: &PeerId,
+        request: BoardSyncRequest,
+    ) -> (BoardSyncResponse, Vec<BoardNotification>) {
+        let response = match request {
+            BoardSyncRequest::RegisterPeer {
+                peer_id,
+                display_name,
+                ..
+            } => {
+                if peer_id != peer.to_string() {
+                    return (denied("peer_id mismatch"), Vec::new());
+                }
+                info!("Registered board peer {} ({})", display_name, peer_id);
+                self.peers.insert(peer_id.clone(), display_name);
+                BoardSyncResponse::PeerRegistered { peer_id }
+            }
+            BoardSyncRequest::ListBoards { .. } => BoardSyncResponse::BoardList {
+                boards: self.boards.clone(),
+                relay_peer_id: local_peer_id.to_string(),
+            },
+            BoardSyncRequest::GetBoardPosts {
+                board_id,
+                after_timestamp,
+                limit,
+                mode,
+                ..
+            } => self.board_posts(board_id, after_timestamp, limit, mode),
+            BoardSyncRequest::GetThread { root_post_id, .. } => self.thread(&root_post_id),
+            BoardSyncRequest::SubmitPost {
+                post_id,
+                board_id,
+                author_peer_id,
+                content_type,
+                content_text,
+                lamport_clock,
+                created_at,
+                signature,
+                reply_to_post_id,
+            } => {
+                if author_peer_id != peer.to_string() {
+                    return (denied("author_peer_id mismatch"), Vec::new());
+                }
+                let Some(author_display_name) = self.peers.get(&author_peer_id).cloned() else {
+                    return (
+                        error("Peer not registered. Call RegisterPeer first."),
+                        Vec::new(),
+                    );
+                };
+                if !self.has_board(&board_id) {
+                    return (
+                        error(format!("Board {} does not exist", board_id)),
+                        Vec::new(),
+                    );
+                }
+                let thread_root_post_id = match reply_to_post_id.as_deref() {
+                    Some(parent_id) => match self.post(parent_id) {
+                        Some(parent) if parent.board_id == board_id => Some(
+                            parent
+                                .thread_root_post_id
+                                .clone()
+                                .unwrap_or_else(|| parent.post_id.clone()),
+                        ),
+                        Some(_) => {
+                            return (error("Parent post is on a different board"), Vec::new())
+                        }
+                        None => {
+                            return (
+                                error(format!("Parent post {} does not exist", parent_id)),
+                                Vec::new(),
+                            )
+                        }
+                    },
+                    None => None,
+                };
+                if self.post(&post_id).is_some() {
+                    return (BoardSyncResponse::PostAccepted { post_id }, Vec::new());
+                }
+
+                let post = BoardPostInfo {
+                    post_id: post_id.clone(),
+                    board_id: board_id.clone(),
+                    author_peer_id,
+                    author_display_name: Some(author_display_name),
+                    content_type,
+                    content_text,
+                    lamport_clock,
+                    created_at,
+                    deleted_at: None,
+                    signature,
+                    received_at: Some(chrono::Utc::now().timestamp()),
+                    reply_to_post_id,
+                    thread_root_post_id,
+                    reply_count: 0,
+                };
+                info!("Accepted board post {} on {}", post_id, board_id);
+                self.posts.push(post.clone());
+                let notification = BoardNotification::Posts {
+                    board_id,
+                    posts: vec![post],
+                };
+                return (
+                    BoardSyncResponse::PostAccepted { post_id },
+                    vec![notification],
+                );
+            }
+            BoardSyncRequest::DeletePost {
+                post_id,
+                author_peer_id,
+                ..
+            } => {
+                if author_peer_id != peer.to_string() {
+                    return (denied("author_peer_id mismatch"), Vec::new());
+                }
+                let now = chrono::Utc::now().timestamp();
+                let Some(post) = self.posts.iter_mut().find(|p| {
+                    p.post_id == post_id
+                        && p.author_peer_id == author_peer_id
+                        && p.deleted_at.is_none()
+                }) else {
+                    return (error("Post not found or not owned by you"), Vec::new());
+                };
+                post.deleted_at = Some(now);
+                info!("Deleted board post {}", post_id);
+                let notification = BoardNotification::PostDeleted {
+                    board_id: post.board_id.clone(),
+                    post_id: post_id.clone(),
+                    deleted_at: now,
+                };
+                return (
+                    BoardSyncResponse::PostDeleted { post_id },
+                    vec![notification],
+                );
+            }
+            BoardSyncRequest::Subscribe { board_ids, .. } => {
+                let board_ids: Vec<String> = board_ids
+                    .into_iter()
+                    .filter(|id| self.has_board(id))
+                    .collect();
+                let subscribed = self.subscriptions.entry(*peer).or_default();
+                subscribed.extend(board_ids);
+                BoardSyncResponse::Subscribed {
+                    board_ids: sorted(subscribed),
+                }
+            }
+            BoardSyncRequest::Unsubscribe { board_ids, .. } => {
+                let subscribed = self.subscriptions.entry(*peer).or_default();
+                if board_ids.is_empty() {
+                    subscribed.clear();
+                } else {
+                    for board_id in &board_ids {
+                        subscribed.remove(board_id);
+                    }
+                }
+                BoardSyncResponse::Subscribed {
+                    board_ids: sorted(subscribed),
+                }
+            }
+        };
+        (response, Vec::new())
+    }
+
+    /// Connected peers subscribed to the board a notification is about
+    pub fn subscribers(&self, notification: &BoardNotification) -> Vec<PeerId> {
+        let board_id = match notification {
+            BoardNotification::Posts { board_id, .. }
+            | BoardNotification::PostDeleted { board_id, .. } => board_id,
+        };
+        self.subscriptions
+            .iter()
+            .filter(|(_, boards)| boards.contains(board_id))
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+
+    /// Subscriptions last only as long as the connection
+    pub fn disconnected(&mut self, peer: &PeerId) {
+        self.subscriptions.remove(peer);
+    }
+
+    fn board_posts(
+        &self,
+        board_id: String,
+        after_timestamp: Option<i64>,
+        limit: u32,
+        mode: BoardPostsMode,
+    ) -> BoardSyncResponse {
+        let limit = limit.min(MAX_PAGE_SIZE) as usize;
+        let top_level_only = mode == BoardPostsMode::Threads;
+        let mut posts: Vec<BoardPostInfo> = self
+            .posts
+            .iter()
+            .filter(|p| p.board_id == board_id)
+            .filter(|p| after_timestamp.is_none() || p.received_at > after_timestamp)
+            .filter(|p| !top_level_only || p.thread_root_post_id.is_none())
+            .map(|p| self.with_reply_count(p))
+            .collect();
+
+        // Incremental syncs page forward from the cursor; first loads show the newest
+        posts.sort_by_key(|p| p.received_at);
+        if after_timestamp.is_none() || top_level_only {
+            posts.reverse();
+        }
+        let has_more = posts.len() > limit;
+        posts.truncate(limit);
+
+        BoardSyncResponse::BoardPosts {
+            board_id,
+            posts,
+            has_more,
+            mode,
+        }
+    }
+
+    fn thread(&self, post_id: &str) -> BoardSyncResponse {
+        let Some(post) = self.post(post_id) else {
+            return error(format!("Post {} does not exist", post_id));
+        };
+        let root_post_id = post
+            .thread_root_post_id
+            .clone()
+            .unwrap_or_else(|| post.post_id.clone());
+
+        let mut posts: Vec<BoardPostInfo> = self
+            .posts
+            .iter()
+            .filter(|p| {
+                p.post_id == root_post_id
+                    || p.thread_root_post_id.as_deref() == Some(root_post_id.as_str())
+            })
+            .map(|p| self.with_reply_count(p))
+            .collect();
+        posts.sort_by(|a, b| {
+            (a.thread_root_post_id.is_some(), a.created_at, &a.post_id).cmp(&(
+                b.thread_root_post_id.is_some(),
+                b.created_at,
+                &b.post_id,
+            ))
+        });
+
+        BoardSyncResponse::ThreadPosts {
+            board_id: post.board_id.clone(),
+            root_post_id,
+            posts,
+        }
+    }
+
+    fn with_reply_count(&self, post: &BoardPostInfo) -> BoardPostInfo {
+        let reply_count = self
+            .posts
+            .iter()
+            .filter(|r| {
+                r.thread_root_post_id.as_deref() == Some(post.post_id.as_str())
+                    && r.deleted_at.is_none()
+            })
+            .count() as u32;
+        BoardPostInfo {
+            reply_count,
+            ..post.clone()
+        }
+    }
+
+    fn has_board(&self, board_id: &str) -> bool {
+        self.boards.iter().any(|b| b.board_id == board_id)
+    }
+
+    fn post(&self, post_id: &str) -> Option<&BoardPostInfo> {
+        self.posts.iter().find(|p| p.post_id == post_id)
+    }
+}
+
+fn sorted(board_ids: &HashSet<String>) -> Vec<String> {
+    let mut board_ids: Vec<String> = board_ids.iter().cloned().collect();
+    board_ids.sort();
+    board_ids
+}
+
+fn error(error: impl Into<String>) -> BoardSyncResponse {
+    BoardSyncResponse::Error {
+        error: error.into(),
+        code: None,
+        retry_after_secs: None,
+    }
+}
+
+fn denied(error: impl Into<String>) -> BoardSyncResponse {
+    BoardSyncResponse::Error {
+        error: error.into(),
+        code: Some(BoardSyncErrorCode::PermissionDenied),
+        retry_after_secs: None,
+    }
+}
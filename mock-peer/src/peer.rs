@@ -0,0 +1,652 @@
+//! The mock peer's identity, content and protocol handlers
+//!
+//! Everything is backed by Harbor's own services on an in-memory database, so
+//! identities, posts, grants and messages are signed and checked exactly as a
+//! real peer would. Nothing survives a restart.
+
+use libp2p::PeerId;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use harbor_lib::db::{BlobsRepository, Capability, Database, PostVisibility};
+use harbor_lib::error::{AppError, Result};
+use harbor_lib::models::CreateIdentityRequest;
+use harbor_lib::p2p::behaviour::{
+    AttachmentRequest, AttachmentResponse, ContentSyncRequest, ContentSyncResponse,
+    IdentityExchangeRequest, IdentityExchangeResponse, MessagingRequest, MessagingResponse,
+    PostSummaryProto, IDENTITY_RESPONSE_VERSION,
+};
+use harbor_lib::p2p::protocols::board_sync::BoardPostInfo;
+use harbor_lib::p2p::protocols::{MessagingCodec, MessagingMessage};
+use harbor_lib::p2p::swarm::ed25519_to_libp2p_keypair;
+use harbor_lib::services::{
+    ContactsService, ContentSyncService, IdentityService, MessagingService, OutgoingMessage,
+    PermissionsService, PostsService, SignableBoardPost,
+};
+
+use crate::script::{attachment_mime_type, post_media_type, Script, ScriptedReply};
+
+/// Passphrase of the throwaway identity; it never leaves memory
+const IDENTITY_PASSPHRASE: &str = "mock-peer";
+
+/// Mock peer state
+pub struct MockPeer {
+    db: Arc<Database>,
+    identity_service: Arc<IdentityService>,
+    contacts_service: Arc<ContactsService>,
+    permissions_service: Arc<PermissionsService>,
+    messaging_service: Arc<MessagingService>,
+    content_sync_service: Arc<ContentSyncService>,
+    /// Display name
+    name: String,
+    /// Capabilities granted to every new contact
+    grants: Vec<Capability>,
+    /// Auto-replies, used in turn
+    replies: Vec<ScriptedReply>,
+    /// Replies sent so far
+    reply_counter: usize,
+}
+
+impl MockPeer {
+    /// Create a fresh identity and publish the script's wall posts
+    pub fn new(name: &str, bio: &str, grants: Vec<Capability>, script: &Script) -> Result<Self> {
+        let db = Arc::new(Database::in_memory()?);
+        let identity_service = Arc::new(IdentityService::new(db.clone()));
+        let contacts_service = Arc::new(ContactsService::new(db.clone(), identity_service.clone()));
+        let permissions_service = Arc::new(PermissionsService::new(
+            db.clone(),
+            identity_service.clone(),
+        ));
+        let posts_service = PostsService::new(
+            db.clone(),
+            identity_service.clone(),
+            contacts_service.clone(),
+            permissions_service.clone(),
+        );
+        let messaging_service = Arc::new(MessagingService::new(
+            db.clone(),
+            identity_service.clone(),
+            contacts_service.clone(),
+            permissions_service.clone(),
+        ));
+        let content_sync_service = Arc::new(ContentSyncService::new(
+            db.clone(),
+            identity_service.clone(),
+            contacts_service.clone(),
+            permissions_service.clone(),
+        ));
+
+        identity_service.create_identity(CreateIdentityRequest {
+            display_name: name.to_string(),
+            passphrase: IDENTITY_PASSPHRASE.to_string(),
+            bio: Some(bio.to_string()).filter(|b| !b.is_empty()),
+            passphrase_hint: None,
+        })?;
+
+        let peer = Self {
+            db,
+            identity_service,
+            contacts_service,
+            permissions_service,
+            messaging_service,
+            content_sync_service,
+            name: name.to_string(),
+            grants,
+            replies: script.replies.clone(),
+            reply_counter: 0,
+        };
+        peer.publish_posts(&posts_service, script)?;
+        Ok(peer)
+    }
+
+    /// Sign the script's wall posts and store their media
+    fn publish_posts(&self, posts_service: &PostsService, script: &Script) -> Result<()> {
+        for scripted in &script.posts {
+            let visibility = scripted
+                .visibility
+                .as_deref()
+                .and_then(PostVisibility::from_str)
+                .unwrap_or(PostVisibility::Contacts);
+            let post = posts_service.create_post("text", scripted.text.as_deref(), visibility)?;
+
+            for (sort_order, path) in scripted.media.iter().enumerate() {
+                let (media_type, mime_type) = post_media_type(path).ok_or_else(|| {
+                    AppError::Validation(format!("Unsupported media file {}", path.display()))
+                })?;
+                let data = std::fs::read(path)?;
+                let media_hash = BlobsRepository::put(&self.db, mime_type, &data)
+                    .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+                let file_name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                posts_service.add_media_to_post(
+                    &post.post_id,
+                    &media_hash,
+                    media_type,
+                    mime_type,
+                    &file_name,
+                    data.len() as i64,
+                    None,
+                    None,
+                    None,
+                    sort_order as i32,
+                )?;
+            }
+            info!(
+                "Published {} post {} with {} media file(s)",
+                post.visibility,
+                post.post_id,
+                scripted.media.len()
+            );
+        }
+        Ok(())
+    }
+
+    /// libp2p keypair of the identity, so the peer ID matches it
+    pub fn keypair(&self) -> Result<libp2p::identity::Keypair> {
+        let keys = self.identity_service.get_unlocked_keys()?;
+        ed25519_to_libp2p_keypair(&keys.ed25519_signing.to_bytes())
+    }
+
+    /// Ask a peer for its identity so it can be added as a contact
+    pub fn identity_request(&self) -> Result<IdentityExchangeRequest> {
+        let peer_id = self.identity_service.get_peer_id()?;
+        let timestamp = chrono::Utc::now().timestamp();
+        let signature = self
+            .identity_service
+            .sign_raw(format!("{}:{}", peer_id, timestamp).as_bytes())?;
+
+        Ok(IdentityExchangeRequest {
+            requester_peer_id: peer_id,
+            timestamp,
+            signature,
+        })
+    }
+
+    /// Our signed identity
+    pub fn identity_response(&self) -> Result<IdentityExchangeResponse> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+
+        let mut response = IdentityExchangeResponse {
+            version: IDENTITY_RESPONSE_VERSION,
+            peer_id: identity.peer_id,
+            public_key: identity.public_key,
+            x25519_public: identity.x25519_public,
+            display_name: identity.display_name,
+            avatar_hash: identity.avatar_hash,
+            bio: identity.bio,
+            profile_version: identity.profile_version,
+            timestamp: chrono::Utc::now().timestamp(),
+            signature: Vec::new(),
+        };
+        response.signature = self.identity_service.sign(&response.signable())?;
+        Ok(response)
+    }
+
+    /// Add or refresh the contact behind an identity response. New contacts
+    /// get the configured grants.
+    pub fn add_contact(&self, peer: PeerId, response: &IdentityExchangeResponse) -> Result<()> {
+        if response.peer_id != peer.to_string() {
+            return Err(AppError::Validation(
+                "Identity response peer ID mismatch".to_string(),
+            ));
+        }
+        response.verify()?;
+
+        let is_new = !self.contacts_service.is_contact(&response.peer_id)?;
+        self.contacts_service.add_contact(
+            &response.peer_id,
+            &response.public_key,
+            &response.x25519_public,
+            &response.display_name,
+            response.avatar_hash.as_deref(),
+            response.bio.as_deref(),
+        )?;
+        if !is_new {
+            return Ok(());
+        }
+
+        info!(
+            "Added contact {} ({})",
+            response.display_name, response.peer_id
+        );
+        for capability in &self.grants {
+            self.permissions_service.create_permission_grant(
+                &response.peer_id,
+                *capability,
+                None,
+            )?;
+            info!(
+                "Granted {} to {}",
+                capability.as_str(),
+                response.display_name
+            );
+        }
+        Ok(())
+    }
+
+    /// Store an incoming message and pick the auto-reply to send back, if any
+    pub fn handle_message(
+        &mut self,
+        peer: PeerId,
+        request: &MessagingRequest,
+    ) -> (MessagingResponse, Option<MessagingRequest>) {
+        let message = match MessagingCodec::decode(&request.payload) {
+            Ok(MessagingMessage::Message(message)) => message,
+            Ok(MessagingMessage::Ack(ack)) => {
+                return (Self::messaging_ok(Some(ack.message_id)), None);
+            }
+            Ok(MessagingMessage::Event(event)) => {
+                let response = match self.messaging_service.process_incoming_event(&event) {
+                    Ok(_) => Self::messaging_ok(Some(event.message_id)),
+                    Err(e) => Self::messaging_error(Some(event.message_id), e),
+                };
+                return (response, None);
+            }
+            Ok(MessagingMessage::Retention(update)) => {
+                let response = match self.messaging_service.process_incoming_retention(&update) {
+                    Ok(_) => Self::messaging_ok(None),
+                    Err(e) => Self::messaging_error(None, e),
+                };
+                return (response, None);
+            }
+            Err(e) => {
+                warn!("Failed to decode messaging payload from {}: {}", peer, e);
+                return (
+                    Self::messaging_error(None, AppError::Serialization(e.to_string())),
+                    None,
+                );
+            }
+        };
+
+        if message.sender_peer_id != peer.to_string() {
+            let error = AppError::Validation("Sender peer ID mismatch".to_string());
+            return (Self::messaging_error(Some(message.message_id), error), None);
+        }
+        if let Err(e) = self.messaging_service.process_incoming_message(
+            &message.message_id,
+            &message.conversation_id,
+            &message.sender_peer_id,
+            &message.recipient_peer_id,
+            &message.content_encrypted,
+            &message.content_type,
+            message.reply_to.as_deref(),
+            message.nonce_counter,
+            message.lamport_clock,
+            message.timestamp,
+            &message.signature,
+        ) {
+            warn!(
+                "Rejected message {} from {}: {}",
+                message.message_id, peer, e
+            );
+            return (Self::messaging_error(Some(message.message_id), e), None);
+        }
+        info!("Received message {} from {}", message.message_id, peer);
+
+        let reply = match self.next_reply(&message.sender_peer_id) {
+            Ok(outgoing) => {
+                MessagingCodec::encode(&MessagingMessage::Message(outgoing.to_direct_message()))
+                    .map(|payload| MessagingRequest {
+                        message_type: "message".to_string(),
+                        payload,
+                    })
+                    .map_err(|e| warn!("Failed to encode reply: {}", e))
+                    .ok()
+            }
+            Err(e) => {
+                warn!("Not replying to {}: {}", peer, e);
+                None
+            }
+        };
+        (Self::messaging_ok(Some(message.message_id)), reply)
+    }
+
+    /// Sign and store the next scripted reply to a sender
+    fn next_reply(&mut self, sender_peer_id: &str) -> Result<OutgoingMessage> {
+        let reply = self
+            .replies
+            .get(self.reply_counter % self.replies.len().max(1))
+            .cloned()
+            .ok_or_else(|| AppError::Validation("No replies scripted".to_string()))?;
+        self.reply_counter += 1;
+
+        match reply {
+            ScriptedReply::Text(text) => {
+                let sender_name = self
+                    .contacts_service
+                    .get_contact(sender_peer_id)?
+                    .map(|c| c.display_name)
+                    .unwrap_or_else(|| sender_peer_id.to_string());
+                let text = text
+                    .replace("{name}", &self.name)
+                    .replace("{sender}", &sender_name);
+                info!("Auto-reply to {}: {}", sender_name, text);
+                self.messaging_service
+                    .send_message(sender_peer_id, &text, "text", None)
+            }
+            ScriptedReply::File { file } => {
+                let data = std::fs::read(&file)?;
+                let file_name = file
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                info!("Auto-reply to {}: attachment {}", sender_peer_id, file_name);
+                self.messaging_service.send_attachment(
+                    sender_peer_id,
+                    &file_name,
+                    attachment_mime_type(&file),
+                    &data,
+                )
+            }
+        }
+    }
+
+    /// Serve our wall to a peer; the services check the signature and that
+    /// the peer was granted wall access
+    pub fn handle_content_sync(
+        &self,
+        peer: PeerId,
+        request: ContentSyncRequest,
+    ) -> ContentSyncResponse {
+        let result = match request {
+            ContentSyncRequest::Manifest {
+                requester_peer_id,
+                cursor,
+                limit,
+                timestamp,
+                signature,
+            } => self
+                .check_requester(peer, &requester_peer_id)
+                .and_then(|()| {
+                    self.content_sync_service.process_manifest_request(
+                        &requester_peer_id,
+                        &cursor,
+                        limit,
+                        timestamp,
+                        &signature,
+                    )
+                })
+                .map(|resp| {
+                    info!("Serving {} post(s) to {}", resp.posts.len(), peer);
+                    ContentSyncResponse::Manifest {
+                        responder_peer_id: resp.responder_peer_id,
+                        posts: resp
+                            .posts
+                            .into_iter()
+                            .map(|p| PostSummaryProto {
+                                post_id: p.post_id,
+                                author_peer_id: p.author_peer_id,
+                                lamport_clock: p.lamport_clock,
+                                content_type: p.content_type,
+                                has_media: p.has_media,
+                                media_hashes: p.media_hashes,
+                                created_at: p.created_at,
+                            })
+                            .collect(),
+                        has_more: resp.has_more,
+                        next_cursor: resp.next_cursor,
+                        timestamp: resp.timestamp,
+                        signature: resp.signature,
+                    }
+                }),
+            ContentSyncRequest::FetchPost {
+                post_id,
+                include_media,
+                requester_peer_id,
+                timestamp,
+                signature,
+            } => self
+                .check_requester(peer, &requester_peer_id)
+                .and_then(|()| {
+                    self.content_sync_service.process_fetch_request(
+                        &requester_peer_id,
+                        &post_id,
+                        include_media,
+                        timestamp,
+                        &signature,
+                    )
+                })
+                .map(|resp| ContentSyncResponse::Post {
+                    post_id: resp.post_id,
+                    author_peer_id: resp.author_peer_id,
+                    content_type: resp.content_type,
+                    content_text: resp.content_text,
+                    visibility: resp.visibility,
+                    lamport_clock: resp.lamport_clock,
+                    created_at: resp.created_at,
+                    signature: resp.signature,
+                }),
+        };
+
+        result.unwrap_or_else(|e| {
+            warn!("Refused content sync request from {}: {}", peer, e);
+            ContentSyncResponse::Error {
+                error: e.to_string(),
+            }
+        })
+    }
+
+    /// Serve a chunk of an attachment we sent to the peer
+    pub fn handle_attachment(
+        &self,
+        peer: PeerId,
+        request: AttachmentRequest,
+    ) -> AttachmentResponse {
+        match self.messaging_service.attachment_chunk(
+            &peer.to_string(),
+            &request.blob_hash,
+            request.chunk_index,
+        ) {
+            Ok(data) => AttachmentResponse::Chunk { data },
+            Err(e) => AttachmentResponse::Error {
+                error: e.to_string(),
+            },
+        }
+    }
+
+    /// Sign a board post authored by us, for boards served in relay mode
+    pub fn sign_board_post(&self, board_id: &str, text: &str) -> Result<BoardPostInfo> {
+        let peer_id = self.identity_service.get_peer_id()?;
+        let lamport_clock =
+            self.db
+                .next_lamport_clock(&peer_id)
+                .map_err(|e| AppError::DatabaseString(e.to_string()))? as u64;
+        let created_at = chrono::Utc::now().timestamp();
+
+        let signable = SignableBoardPost {
+            post_id: uuid::Uuid::new_v4().to_string(),
+            board_id: board_id.to_string(),
+            author_peer_id: peer_id,
+            content_type: "text".to_string(),
+            content_text: Some(text.to_string()),
+            lamport_clock,
+            created_at,
+            reply_to_post_id: None,
+        };
+        let signature = self.identity_service.sign(&signable)?;
+
+        Ok(BoardPostInfo {
+            post_id: signable.post_id,
+            board_id: signable.board_id,
+            author_peer_id: signable.author_peer_id,
+            author_display_name: Some(self.name.clone()),
+            content_type: signable.content_type,
+            content_text: signable.content_text,
+            lamport_clock,
+            created_at,
+            deleted_at: None,
+            signature,
+            received_at: Some(created_at),
+            reply_to_post_id: None,
+            thread_root_post_id: None,
+            reply_count: 0,
+        })
+    }
+
+    /// Our display name, shown on our board posts in relay mode
+    pub fn display_name(&self) -> &str {
+        &self.name
+    }
+
+    fn check_requester(&self, peer: PeerId, requester_peer_id: &str) -> Result<()> {
+        if requester_peer_id != peer.to_string() {
+            return Err(AppError::PermissionDenied(
+                "requester_peer_id mismatch".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn messaging_ok(message_id: Option<String>) -> MessagingResponse {
+        MessagingResponse {
+            success: true,
+            message_id,
+            error: None,
+        }
+    }
+
+    fn messaging_error(message_id: Option<String>, error: AppError) -> MessagingResponse {
+        MessagingResponse {
+            success: false,
+            message_id,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Parse a comma-separated capability list such as `chat,wall_read`
+pub fn parse_grants(list: &str) -> std::result::Result<Vec<Capability>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| Capability::from_str(s).ok_or_else(|| format!("Unknown capability: {}", s)))
+        .collect()
+}
+
+/// Stable board ID derived from a board name
+pub fn board_id(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer_id(peer: &MockPeer) -> PeerId {
+        peer.keypair().unwrap().public().to_peer_id()
+    }
+
+    /// Two mock peers that have exchanged identities and granted each other chat
+    fn connected_pair() -> (MockPeer, MockPeer) {
+        let silent = Script {
+            posts: Vec::new(),
+            replies: Vec::new(),
+            boards: Vec::new(),
+        };
+        let alice = MockPeer::new("Alice", "", vec![Capability::Chat], &silent).unwrap();
+        let bob = MockPeer::new("Bob", "", vec![Capability::Chat], &Script::builtin()).unwrap();
+        alice
+            .add_contact(peer_id(&bob), &bob.identity_response().unwrap())
+            .unwrap();
+        bob.add_contact(peer_id(&alice), &alice.identity_response().unwrap())
+            .unwrap();
+        (alice, bob)
+    }
+
+    #[test]
+    fn test_message_and_reply_round_trip_through_codec() {
+        let (mut alice, mut bob) = connected_pair();
+        let bob_peer_id = bob.identity_service.get_peer_id().unwrap();
+
+        let outgoing = alice
+            .messaging_service
+            .send_message(&bob_peer_id, "Hello Bob", "text", None)
+            .unwrap();
+        let request = MessagingRequest {
+            message_type: "message".to_string(),
+            payload: MessagingCodec::encode(&MessagingMessage::Message(
+                outgoing.to_direct_message(),
+            ))
+            .unwrap(),
+        };
+
+        let (response, reply) = bob.handle_message(peer_id(&alice), &request);
+        assert!(response.success, "{:?}", response.error);
+        assert_eq!(
+            response.message_id.as_deref(),
+            Some(outgoing.message_id.as_str())
+        );
+        let received = bob
+            .messaging_service
+            .get_conversation_messages(&outgoing.sender_peer_id, 10, None)
+            .unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].content, "Hello Bob");
+
+        // The scripted reply decodes with the same codec and Alice accepts it
+        let reply = reply.expect("Bob should reply");
+        let Ok(MessagingMessage::Message(direct)) = MessagingCodec::decode(&reply.payload) else {
+            panic!("Reply should decode to a message");
+        };
+        assert_eq!(direct.sender_peer_id, bob_peer_id);
+        assert_eq!(direct.recipient_peer_id, outgoing.sender_peer_id);
+
+        let (response, reply) = alice.handle_message(peer_id(&bob), &reply);
+        assert!(response.success, "{:?}", response.error);
+        assert_eq!(response.message_id, Some(direct.message_id));
+        assert!(reply.is_none());
+        let conversation = alice
+            .messaging_service
+            .get_conversation_messages(&bob_peer_id, 10, None)
+            .unwrap();
+        assert_eq!(conversation.len(), 2);
+        assert!(conversation
+            .iter()
+            .any(|m| m.sender_peer_id == bob_peer_id && m.content.contains("Bob")));
+    }
+
+    #[test]
+    fn test_message_from_another_peer_id_is_rejected() {
+        let (alice, mut bob) = connected_pair();
+        let outgoing = alice
+            .messaging_service
+            .send_message(
+                &bob.identity_service.get_peer_id().unwrap(),
+                "Hi",
+                "text",
+                None,
+            )
+            .unwrap();
+        let request = MessagingRequest {
+            message_type: "message".to_string(),
+            payload: MessagingCodec::encode(&MessagingMessage::Message(
+                outgoing.to_direct_message(),
+            ))
+            .unwrap(),
+        };
+
+        // Delivered over a connection from a different peer
+        let (response, reply) = bob.handle_message(PeerId::random(), &request);
+        assert!(!response.success);
+        assert!(reply.is_none());
+
+        let (response, reply) = bob.handle_message(
+            peer_id(&alice),
+            &MessagingRequest {
+                message_type: "message".to_string(),
+                payload: b"not a message".to_vec(),
+            },
+        );
+        assert!(!response.success);
+        assert!(reply.is_none());
+    }
+}
@@ -0,0 +1,167 @@
+//! Scripted content served by the mock peer
+//!
+//! A script is a JSON file with wall posts, auto-replies and boards. Paths in
+//! it are relative to the script file. Sections left out fall back to the
+//! built-in content, except posts: a script without posts serves an empty wall.
+//!
+//! ```json
+//! {
+//!   "posts": [{ "text": "Hello", "visibility": "public", "media": ["beach.jpg"] }],
+//!   "replies": ["Hi {sender}, this is {name}", { "file": "notes.pdf" }],
+//!   "boards": [{ "name": "General", "isDefault": true, "posts": ["Welcome!"] }]
+//! }
+//! ```
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Content the mock peer publishes and replies with
+#[derive(Debug, Deserialize)]
+pub struct Script {
+    #[serde(default)]
+    pub posts: Vec<ScriptedPost>,
+    #[serde(default)]
+    pub replies: Vec<ScriptedReply>,
+    #[serde(default)]
+    pub boards: Vec<ScriptedBoard>,
+}
+
+/// A wall post published at startup
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptedPost {
+    pub text: Option<String>,
+    /// "public" or "contacts" (the default)
+    pub visibility: Option<String>,
+    /// Image or video files attached to the post
+    #[serde(default)]
+    pub media: Vec<PathBuf>,
+}
+
+/// An auto-reply: text, or a file sent as an attachment
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ScriptedReply {
+    /// `{name}` is replaced by our name and `{sender}` by the sender's
+    Text(String),
+    File {
+        file: PathBuf,
+    },
+}
+
+/// A board served in fake relay mode
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptedBoard {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub is_default: bool,
+    /// Posts by the mock peer already on the board
+    #[serde(default)]
+    pub posts: Vec<String>,
+}
+
+impl Script {
+    /// Load a script file, resolving its paths against the file's directory
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read script {}: {}", path.display(), e))?;
+        let mut script: Script = serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid script {}: {}", path.display(), e))?;
+
+        let base = path.parent().unwrap_or(Path::new("."));
+        for post in &mut script.posts {
+            for media in &mut post.media {
+                *media = base.join(&*media);
+            }
+        }
+        for reply in &mut script.replies {
+            if let ScriptedReply::File { file } = reply {
+                *file = base.join(&*file);
+            }
+        }
+
+        let builtin = Self::builtin();
+        if script.replies.is_empty() {
+            script.replies = builtin.replies;
+        }
+        if script.boards.is_empty() {
+            script.boards = builtin.boards;
+        }
+        Ok(script)
+    }
+
+    /// Content served when no script is given
+    pub fn builtin() -> Self {
+        Self {
+            posts: vec![
+                ScriptedPost {
+                    text: Some("Hello from the mock peer! This post is public.".to_string()),
+                    visibility: Some("public".to_string()),
+                    media: Vec::new(),
+                },
+                ScriptedPost {
+                    text: Some("Only contacts with wall access can see this one.".to_string()),
+                    visibility: None,
+                    media: Vec::new(),
+                },
+            ],
+            replies: [
+                "Hey! This is {name} - a mock peer for testing. Your message was received successfully!",
+                "Thanks for testing Harbor's P2P messaging! Connection verified. - {name}",
+                "Message received loud and clear! The decentralized future is here. - {name}",
+                "Hello from the mock peer server! Everything is working as expected.",
+                "Great to connect with you, {sender}! Harbor's P2P is functioning properly.",
+            ]
+            .into_iter()
+            .map(|text| ScriptedReply::Text(text.to_string()))
+            .collect(),
+            boards: vec![
+                ScriptedBoard {
+                    name: "General".to_string(),
+                    description: Some("Anything goes".to_string()),
+                    is_default: true,
+                    posts: vec!["Welcome to the mock enclave!".to_string()],
+                },
+                ScriptedBoard {
+                    name: "Testing".to_string(),
+                    description: Some("Try posting and replying here".to_string()),
+                    is_default: false,
+                    posts: Vec::new(),
+                },
+            ],
+        }
+    }
+}
+
+/// Media type and MIME type of a post media file, by extension
+pub fn post_media_type(path: &Path) -> Option<(&'static str, &'static str)> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => Some(("image", "image/png")),
+        "jpg" | "jpeg" => Some(("image", "image/jpeg")),
+        "gif" => Some(("image", "image/gif")),
+        "webp" => Some(("image", "image/webp")),
+        "mp4" => Some(("video", "video/mp4")),
+        "webm" => Some(("video", "video/webm")),
+        _ => None,
+    }
+}
+
+/// MIME type of an attachment file, by extension
+pub fn attachment_mime_type(path: &Path) -> &'static str {
+    if let Some((_, mime_type)) = post_media_type(path) {
+        return mime_type;
+    }
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("pdf") => "application/pdf",
+        Some("txt") => "text/plain",
+        Some("json") => "application/json",
+        _ => "application/octet-stream",
+    }
+}
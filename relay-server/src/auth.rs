@@ -7,89 +7,116 @@
 //! 4. Relay verifies timing + correctness, issues a token
 //! 5. Agent includes this token when registering with the relay via libp2p
 //! 6. Relay checks token before granting relay reservation
+//!
+//! Challenges and tokens are persisted in SQLite and indexed by peer id.
+//! Token holders can revoke via /auth/revoke; operators holding the admin
+//! token can list live tokens at /auth/tokens and revoke them per peer.
 
-use axum::extract::State;
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
 use axum::Json;
 use chrono::Utc;
 use isnad::{CaptchaChallenge, CaptchaResponse, CaptchaVerifier, TaskAnswer};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
 use uuid::Uuid;
 
+use crate::db::RelayDatabase;
+
 /// How long a pending challenge stays valid (seconds)
 const CHALLENGE_TTL_SECS: i64 = 60;
 /// How long a verified token stays valid (seconds)
 const TOKEN_TTL_SECS: i64 = 3600;
+/// Number of token characters shown in operator listings
+const TOKEN_PREFIX_LEN: usize = 16;
 
 /// Shared auth state
+///
+/// Pending challenges and issued tokens are persisted in SQLite so a relay
+/// restart does not force every agent to redo the CAPTCHA.
 pub struct AuthState {
-    /// Pending challenges: challenge_id -> (expected_answers, peer_id, issued_at)
-    pending: RwLock<HashMap<Uuid, PendingChallenge>>,
-    /// Verified tokens: token -> (peer_id, verified_at)
-    verified: RwLock<HashMap<String, VerifiedAgent>>,
+    /// Challenge and token storage
+    db: RelayDatabase,
     /// The CAPTCHA verifier
     verifier: CaptchaVerifier,
+    /// Bearer token for operator endpoints (disabled when unset)
+    admin_token: Option<String>,
 }
 
-struct PendingChallenge {
-    expected_answers: Vec<TaskAnswer>,
-    challenge_json: serde_json::Value,
-    peer_id: String,
-    issued_at: chrono::DateTime<Utc>,
-}
-
-#[derive(Clone)]
-struct VerifiedAgent {
-    peer_id: String,
-    verified_at: chrono::DateTime<Utc>,
-}
+type AuthResult<T> = Result<T, (StatusCode, Json<AuthError>)>;
 
 impl AuthState {
-    pub fn new() -> Self {
+    pub fn new(db: RelayDatabase, admin_token: Option<String>) -> Self {
         Self {
-            pending: RwLock::new(HashMap::new()),
-            verified: RwLock::new(HashMap::new()),
+            db,
             verifier: CaptchaVerifier::new(),
+            admin_token,
         }
     }
 
     /// Check if a peer_id has a valid auth token
-    pub async fn is_peer_verified(&self, peer_id: &str) -> bool {
-        let verified = self.verified.read().await;
-        verified.values().any(|v| {
-            v.peer_id == peer_id
-                && Utc::now()
-                    .signed_duration_since(v.verified_at)
-                    .num_seconds()
-                    < TOKEN_TTL_SECS
-        })
+    pub fn is_peer_verified(&self, peer_id: &str) -> bool {
+        self.db
+            .has_valid_token(peer_id, Utc::now().timestamp())
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to check auth token for {}: {}", peer_id, e);
+                false
+            })
     }
 
     /// Clean up expired challenges and tokens
-    pub async fn cleanup(&self) {
-        let now = Utc::now();
-
-        {
-            let mut pending = self.pending.write().await;
-            pending.retain(|_, v| {
-                now.signed_duration_since(v.issued_at).num_seconds() < CHALLENGE_TTL_SECS
-            });
+    pub fn cleanup(&self) {
+        let now = Utc::now().timestamp();
+        if let Err(e) = self.db.prune_auth(now - CHALLENGE_TTL_SECS, now) {
+            tracing::warn!("Failed to prune auth store: {}", e);
         }
+    }
+
+    /// Reject the request unless it carries the operator bearer token
+    fn require_admin(&self, headers: &HeaderMap) -> AuthResult<()> {
+        let Some(ref admin_token) = self.admin_token else {
+            return Err(auth_error(
+                StatusCode::FORBIDDEN,
+                "Operator endpoints disabled. Start the relay with --admin-token.",
+            ));
+        };
+
+        let presented = headers
+            .get(axum::http::header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
 
-        {
-            let mut verified = self.verified.write().await;
-            verified.retain(|_, v| {
-                now.signed_duration_since(v.verified_at).num_seconds() < TOKEN_TTL_SECS
-            });
+        match presented {
+            Some(token) if constant_time_eq(token.as_bytes(), admin_token.as_bytes()) => Ok(()),
+            _ => Err(auth_error(StatusCode::UNAUTHORIZED, "Invalid operator token")),
         }
     }
 }
 
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn auth_error(status: StatusCode, error: impl Into<String>) -> (StatusCode, Json<AuthError>) {
+    (
+        status,
+        Json(AuthError {
+            error: error.into(),
+        }),
+    )
+}
+
+fn internal_error(e: impl std::fmt::Display) -> (StatusCode, Json<AuthError>) {
+    auth_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Internal error: {}", e),
+    )
+}
+
 // -- Request/Response types --
 
 #[derive(Deserialize)]
@@ -131,33 +158,33 @@ pub struct AuthError {
 pub async fn request_challenge(
     State(auth): State<Arc<AuthState>>,
     Json(req): Json<ChallengeRequest>,
-) -> impl IntoResponse {
+) -> AuthResult<Json<ChallengeResponse>> {
     // Clean up expired entries
-    auth.cleanup().await;
+    auth.cleanup();
 
     // Generate challenge
     let (challenge, expected_answers) = auth.verifier.generate_challenge();
 
-    let challenge_json = serde_json::to_value(&challenge).unwrap();
+    let challenge_json = serde_json::to_value(&challenge).map_err(internal_error)?;
+    let expected_json = serde_json::to_string(&expected_answers).map_err(internal_error)?;
     let challenge_id = challenge.challenge_id;
 
     // Store pending challenge
-    {
-        let mut pending = auth.pending.write().await;
-        pending.insert(
-            challenge_id,
-            PendingChallenge {
-                expected_answers,
-                challenge_json: challenge_json.clone(),
-                peer_id: req.peer_id,
-                issued_at: Utc::now(),
-            },
-        );
-    }
+    auth.db
+        .insert_challenge(
+            &challenge_id.to_string(),
+            &req.peer_id,
+            &challenge_json.to_string(),
+            &expected_json,
+            Utc::now().timestamp(),
+        )
+        .map_err(internal_error)?;
 
     tracing::info!("Issued CAPTCHA challenge {} for peer", challenge_id);
 
-    (StatusCode::OK, Json(ChallengeResponse { challenge: challenge_json }))
+    Ok(Json(ChallengeResponse {
+        challenge: challenge_json,
+    }))
 }
 
 /// POST /auth/verify - Submit CAPTCHA response and get a token
@@ -168,22 +195,14 @@ pub async fn verify_challenge(
     let challenge_id = req.response.challenge_id;
 
     // Look up the pending challenge
-    let pending_challenge = {
-        let mut pending = auth.pending.write().await;
-        pending.remove(&challenge_id)
-    };
-
-    let pending = match pending_challenge {
-        Some(p) => p,
-        None => {
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(AuthError {
-                    error: "Challenge not found or expired".to_string(),
-                }),
-            ));
-        }
-    };
+    let pending = auth
+        .db
+        .take_challenge(
+            &challenge_id.to_string(),
+            Utc::now().timestamp() - CHALLENGE_TTL_SECS,
+        )
+        .map_err(internal_error)?
+        .ok_or_else(|| auth_error(StatusCode::NOT_FOUND, "Challenge not found or expired"))?;
 
     // Verify peer_id matches
     if pending.peer_id != req.peer_id {
@@ -195,21 +214,16 @@ pub async fn verify_challenge(
         ));
     }
 
-    // Reconstruct the challenge from stored JSON
+    // Reconstruct the challenge and expected answers from stored JSON
     let challenge: CaptchaChallenge =
-        serde_json::from_value(pending.challenge_json).map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(AuthError {
-                    error: format!("Internal error: {}", e),
-                }),
-            )
-        })?;
+        serde_json::from_str(&pending.challenge_json).map_err(internal_error)?;
+    let expected_answers: Vec<TaskAnswer> =
+        serde_json::from_str(&pending.expected_answers_json).map_err(internal_error)?;
 
     // Verify the response
     match auth
         .verifier
-        .verify(&challenge, &req.response, &pending.expected_answers)
+        .verify(&challenge, &req.response, &expected_answers)
     {
         Ok(verification) => {
             tracing::info!(
@@ -224,16 +238,10 @@ pub async fn verify_challenge(
             let token = generate_token(&req.peer_id);
 
             // Store verified agent
-            {
-                let mut verified = auth.verified.write().await;
-                verified.insert(
-                    token.clone(),
-                    VerifiedAgent {
-                        peer_id: req.peer_id.clone(),
-                        verified_at: Utc::now(),
-                    },
-                );
-            }
+            let now = Utc::now().timestamp();
+            auth.db
+                .insert_token(&token, &req.peer_id, now, now + TOKEN_TTL_SECS)
+                .map_err(internal_error)?;
 
             Ok(Json(VerifyResponse {
                 token,
@@ -257,28 +265,17 @@ pub async fn verify_challenge(
 pub async fn check_token(
     State(auth): State<Arc<AuthState>>,
     Json(req): Json<CheckTokenRequest>,
-) -> Result<Json<CheckTokenResponse>, (StatusCode, Json<AuthError>)> {
-    let verified = auth.verified.read().await;
-    match verified.get(&req.token) {
-        Some(agent) => {
-            let elapsed = Utc::now()
-                .signed_duration_since(agent.verified_at)
-                .num_seconds();
-            if elapsed < TOKEN_TTL_SECS {
-                Ok(Json(CheckTokenResponse {
-                    valid: true,
-                    peer_id: Some(agent.peer_id.clone()),
-                    remaining_seconds: TOKEN_TTL_SECS - elapsed,
-                }))
-            } else {
-                Ok(Json(CheckTokenResponse {
-                    valid: false,
-                    peer_id: None,
-                    remaining_seconds: 0,
-                }))
-            }
-        }
-        None => Ok(Json(CheckTokenResponse {
+) -> AuthResult<Json<CheckTokenResponse>> {
+    let now = Utc::now().timestamp();
+    let token = auth.db.get_token(&req.token).map_err(internal_error)?;
+
+    match token {
+        Some(t) if t.revoked_at.is_none() && t.expires_at > now => Ok(Json(CheckTokenResponse {
+            valid: true,
+            peer_id: Some(t.peer_id),
+            remaining_seconds: t.expires_at - now,
+        })),
+        _ => Ok(Json(CheckTokenResponse {
             valid: false,
             peer_id: None,
             remaining_seconds: 0,
@@ -286,6 +283,72 @@ pub async fn check_token(
     }
 }
 
+/// POST /auth/revoke - Revoke a token, or every token held by a peer
+///
+/// Anyone holding a token may revoke it. Revoking by peer id is an operator
+/// action and requires the admin bearer token.
+pub async fn revoke_token(
+    State(auth): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Json(req): Json<RevokeTokenRequest>,
+) -> AuthResult<Json<RevokeTokenResponse>> {
+    let now = Utc::now().timestamp();
+
+    let revoked = match (req.token, req.peer_id) {
+        (Some(token), None) => {
+            usize::from(auth.db.revoke_token(&token, now).map_err(internal_error)?)
+        }
+        (None, Some(peer_id)) => {
+            auth.require_admin(&headers)?;
+            let count = auth
+                .db
+                .revoke_peer_tokens(&peer_id, now)
+                .map_err(internal_error)?;
+            tracing::info!("Operator revoked {} token(s) for peer {}", count, peer_id);
+            count
+        }
+        _ => {
+            return Err(auth_error(
+                StatusCode::BAD_REQUEST,
+                "Provide exactly one of token or peerId",
+            ));
+        }
+    };
+
+    Ok(Json(RevokeTokenResponse { revoked }))
+}
+
+/// GET /auth/tokens - List live tokens for operators (admin bearer token required)
+pub async fn list_tokens(
+    State(auth): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Query(query): Query<ListTokensQuery>,
+) -> AuthResult<Json<ListTokensResponse>> {
+    auth.require_admin(&headers)?;
+
+    let now = Utc::now().timestamp();
+    let tokens = auth
+        .db
+        .list_tokens(query.peer_id.as_deref(), now)
+        .map_err(internal_error)?
+        .into_iter()
+        .map(|t| TokenInfo {
+            token_prefix: t.token.chars().take(TOKEN_PREFIX_LEN).collect(),
+            peer_id: t.peer_id,
+            verified_at: t.verified_at,
+            expires_at: t.expires_at,
+            revoked_at: t.revoked_at,
+            remaining_seconds: if t.revoked_at.is_some() {
+                0
+            } else {
+                t.expires_at - now
+            },
+        })
+        .collect();
+
+    Ok(Json(ListTokensResponse { tokens }))
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckTokenRequest {
@@ -300,6 +363,42 @@ pub struct CheckTokenResponse {
     pub remaining_seconds: i64,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeTokenRequest {
+    pub token: Option<String>,
+    pub peer_id: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeTokenResponse {
+    pub revoked: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTokensQuery {
+    pub peer_id: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    pub token_prefix: String,
+    pub peer_id: String,
+    pub verified_at: i64,
+    pub expires_at: i64,
+    pub revoked_at: Option<i64>,
+    pub remaining_seconds: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTokensResponse {
+    pub tokens: Vec<TokenInfo>,
+}
+
 fn generate_token(peer_id: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(peer_id.as_bytes());
@@ -311,3 +410,96 @@ fn generate_token(peer_id: &str) -> String {
         hash.iter().map(|b| format!("{:02x}", b)).collect::<String>()
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth_state(admin_token: Option<&str>) -> Arc<AuthState> {
+        let db = RelayDatabase::open_auth_store(":memory:").unwrap();
+        Arc::new(AuthState::new(db, admin_token.map(str::to_string)))
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::AUTHORIZATION,
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    async fn list(
+        auth: &Arc<AuthState>,
+        headers: HeaderMap,
+        peer_id: Option<&str>,
+    ) -> Result<Vec<TokenInfo>, StatusCode> {
+        let query = ListTokensQuery {
+            peer_id: peer_id.map(str::to_string),
+        };
+        list_tokens(State(auth.clone()), headers, Query(query))
+            .await
+            .map(|Json(response)| response.tokens)
+            .map_err(|(status, _)| status)
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"s"));
+    }
+
+    #[tokio::test]
+    async fn test_list_tokens_requires_the_admin_token() {
+        let auth = auth_state(Some("operator-secret"));
+        assert_eq!(
+            list(&auth, HeaderMap::new(), None).await.err(),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            list(&auth, bearer("operator-secre"), None).await.err(),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+        assert!(list(&auth, bearer("operator-secret"), None).await.is_ok());
+
+        let disabled = auth_state(None);
+        assert_eq!(
+            list(&disabled, bearer("operator-secret"), None).await.err(),
+            Some(StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_tokens_shows_live_tokens() {
+        let auth = auth_state(Some("operator-secret"));
+        let now = Utc::now().timestamp();
+        let token = generate_token("alice");
+        auth.db
+            .insert_token(&token, "alice", now, now + 60)
+            .unwrap();
+        auth.db
+            .insert_token("bob-token", "bob", now, now + 60)
+            .unwrap();
+        auth.db
+            .insert_token("expired", "alice", now - 120, now - 60)
+            .unwrap();
+        assert!(auth.db.revoke_token("bob-token", now).unwrap());
+
+        let mut tokens = list(&auth, bearer("operator-secret"), None).await.unwrap();
+        tokens.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].peer_id, "alice");
+        assert_eq!(tokens[0].token_prefix, &token[..TOKEN_PREFIX_LEN]);
+        assert!(tokens[0].remaining_seconds > 0);
+        assert_eq!(tokens[1].peer_id, "bob");
+        assert_eq!(tokens[1].revoked_at, Some(now));
+        assert_eq!(tokens[1].remaining_seconds, 0);
+
+        let alice = list(&auth, bearer("operator-secret"), Some("alice"))
+            .await
+            .unwrap();
+        assert_eq!(alice.len(), 1);
+    }
+}
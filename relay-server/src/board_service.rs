@@ -1,8 +1,16 @@
 //! Server-side board logic for the relay server
 
 use crate::db::RelayDatabase;
+use crate::BoardPostsMode;
 use tracing::{info, warn};
 
+/// How far in the future a post's `created_at` may be. Federation cursors are
+/// keyed on `created_at`, so far-future posts would stall mirroring.
+pub const MAX_CLOCK_SKEW_SECS: i64 = 300;
+
+/// Maximum posts returned for one thread
+const MAX_THREAD_POSTS: u32 = 500;
+
 /// Service for processing board sync requests on the relay server
 pub struct BoardService {
     db: RelayDatabase,
@@ -18,6 +26,25 @@ impl BoardService {
         &self.community_name
     }
 
+    pub fn db(&self) -> &RelayDatabase {
+        &self.db
+    }
+
+    /// Check whether a peer is banned from this relay
+    pub fn is_peer_banned(&self, peer_id: &str) -> bool {
+        self.db.is_peer_banned(peer_id).unwrap_or(false)
+    }
+
+    /// Ban a peer (automatic moderation, e.g. repeated rate limit violations)
+    pub fn process_ban_peer(&self, peer_id: &str, reason: &str) -> Result<(), String> {
+        self.db
+            .ban_peer(peer_id, reason, "relay")
+            .map_err(|e| format!("Failed to ban peer: {}", e))?;
+
+        warn!("Banned peer {}: {}", peer_id, reason);
+        Ok(())
+    }
+
     /// Register a peer so they can post
     pub fn process_register_peer(
         &self,
@@ -48,6 +75,7 @@ impl BoardService {
         lamport_clock: u64,
         created_at: i64,
         signature: &[u8],
+        reply_to_post_id: Option<&str>,
     ) -> Result<(), String> {
         // Check peer is known
         if !self.db.is_peer_known(author_peer_id).unwrap_or(false) {
@@ -59,11 +87,34 @@ impl BoardService {
             return Err("Peer is banned".to_string());
         }
 
+        if created_at > chrono::Utc::now().timestamp() + MAX_CLOCK_SKEW_SECS {
+            return Err("Post timestamp is in the future".to_string());
+        }
+
         // Check board exists
         if !self.db.board_exists(board_id).unwrap_or(false) {
             return Err(format!("Board {} does not exist", board_id));
         }
 
+        // Replies join the parent's thread; the root is always a top-level post
+        let thread_root_post_id = match reply_to_post_id {
+            Some(parent_id) => {
+                let parent = self
+                    .db
+                    .get_post(parent_id)
+                    .map_err(|e| format!("Failed to look up parent post: {}", e))?
+                    .ok_or_else(|| format!("Parent post {} does not exist", parent_id))?;
+                if parent.board_id != board_id {
+                    return Err("Parent post is on a different board".to_string());
+                }
+                if parent.deleted_at.is_some() {
+                    return Err("Parent post has been deleted".to_string());
+                }
+                Some(parent.thread_root_post_id.unwrap_or(parent.post_id))
+            }
+            None => None,
+        };
+
         self.db
             .insert_post(
                 post_id,
@@ -74,6 +125,8 @@ impl BoardService {
                 lamport_clock,
                 created_at,
                 signature,
+                reply_to_post_id,
+                thread_root_post_id.as_deref(),
             )
             .map_err(|e| format!("Failed to insert post: {}", e))?;
 
@@ -88,17 +141,27 @@ impl BoardService {
             .map_err(|e| format!("Failed to list boards: {}", e))
     }
 
+    /// Keep only the board ids that exist on this relay
+    pub fn existing_boards(&self, board_ids: Vec<String>) -> Vec<String> {
+        board_ids
+            .into_iter()
+            .filter(|id| self.db.board_exists(id).unwrap_or(false))
+            .collect()
+    }
+
     /// Get paginated posts for a board
     pub fn process_get_board_posts(
         &self,
         board_id: &str,
         after_timestamp: Option<i64>,
         limit: u32,
+        mode: BoardPostsMode,
     ) -> Result<(Vec<crate::db::PostRow>, bool), String> {
         let clamped_limit = limit.min(100);
+        let top_level_only = mode == BoardPostsMode::Threads;
         let posts = self
             .db
-            .get_board_posts(board_id, after_timestamp, clamped_limit + 1)
+            .get_board_posts(board_id, after_timestamp, clamped_limit + 1, top_level_only)
             .map_err(|e| format!("Failed to get board posts: {}", e))?;
 
         let has_more = posts.len() > clamped_limit as usize;
@@ -111,6 +174,27 @@ impl BoardService {
         Ok((posts, has_more))
     }
 
+    /// Get a whole thread. A reply's id resolves to the thread it belongs to.
+    /// Returns (board_id, root_post_id, posts).
+    pub fn process_get_thread(
+        &self,
+        post_id: &str,
+    ) -> Result<(String, String, Vec<crate::db::PostRow>), String> {
+        let post = self
+            .db
+            .get_post(post_id)
+            .map_err(|e| format!("Failed to look up post: {}", e))?
+            .ok_or_else(|| format!("Post {} does not exist", post_id))?;
+        let root_post_id = post.thread_root_post_id.unwrap_or(post.post_id);
+
+        let posts = self
+            .db
+            .get_thread_posts(&root_post_id, MAX_THREAD_POSTS)
+            .map_err(|e| format!("Failed to get thread: {}", e))?;
+
+        Ok((post.board_id, root_post_id, posts))
+    }
+
     /// Delete a post (author-only)
     pub fn process_delete_post(
         &self,
@@ -131,3 +215,138 @@ impl BoardService {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUTHOR: &str = "12D3KooWAuthor";
+
+    fn service() -> (BoardService, String) {
+        let db = RelayDatabase::open(":memory:").unwrap();
+        let service = BoardService::new(db, "Test".to_string());
+        service
+            .process_register_peer(AUTHOR, &[1; 32], "Author")
+            .unwrap();
+        let board_id = service.process_list_boards().unwrap()[0].board_id.clone();
+        (service, board_id)
+    }
+
+    fn submit(
+        service: &BoardService,
+        post_id: &str,
+        board_id: &str,
+        reply_to: Option<&str>,
+    ) -> Result<(), String> {
+        let now = chrono::Utc::now().timestamp();
+        service.process_submit_post(
+            post_id,
+            board_id,
+            AUTHOR,
+            "text",
+            Some(post_id),
+            1,
+            now,
+            &[0],
+            reply_to,
+        )
+    }
+
+    fn reply_count(service: &BoardService, post_id: &str) -> u32 {
+        service.db().get_post(post_id).unwrap().unwrap().reply_count
+    }
+
+    #[test]
+    fn test_reply_count_follows_replies_and_deletes() {
+        let (service, board) = service();
+        submit(&service, "root", &board, None).unwrap();
+        submit(&service, "reply1", &board, Some("root")).unwrap();
+        submit(&service, "reply2", &board, Some("reply1")).unwrap();
+
+        // A reply to a reply joins the root's thread
+        let nested = service.db().get_post("reply2").unwrap().unwrap();
+        assert_eq!(nested.reply_to_post_id.as_deref(), Some("reply1"));
+        assert_eq!(nested.thread_root_post_id.as_deref(), Some("root"));
+        assert_eq!(reply_count(&service, "root"), 2);
+
+        service.process_delete_post("reply1", AUTHOR).unwrap();
+        assert_eq!(reply_count(&service, "root"), 1);
+    }
+
+    #[test]
+    fn test_reply_to_missing_parent_is_rejected() {
+        let (service, board) = service();
+        let err = submit(&service, "reply", &board, Some("nope")).unwrap_err();
+        assert!(err.contains("does not exist"));
+        assert!(service.db().get_post("reply").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_reply_across_boards_is_rejected() {
+        let (service, board) = service();
+        service
+            .db()
+            .insert_mirrored_board("other", "Other", None, "12D3KooWSibling")
+            .unwrap();
+        submit(&service, "root", &board, None).unwrap();
+
+        let err = submit(&service, "reply", "other", Some("root")).unwrap_err();
+        assert!(err.contains("different board"));
+        assert_eq!(reply_count(&service, "root"), 0);
+    }
+
+    #[test]
+    fn test_reply_to_deleted_parent_is_rejected() {
+        let (service, board) = service();
+        submit(&service, "root", &board, None).unwrap();
+        service.process_delete_post("root", AUTHOR).unwrap();
+
+        let err = submit(&service, "reply", &board, Some("root")).unwrap_err();
+        assert!(err.contains("deleted"));
+    }
+
+    #[test]
+    fn test_thread_mode_pages_top_level_posts() {
+        let (service, board) = service();
+        for root in ["a", "b", "c"] {
+            submit(&service, root, &board, None).unwrap();
+        }
+        submit(&service, "a1", &board, Some("a")).unwrap();
+
+        let (posts, has_more) = service
+            .process_get_board_posts(&board, None, 2, BoardPostsMode::Threads)
+            .unwrap();
+        assert_eq!(posts.len(), 2);
+        assert!(has_more);
+        assert!(posts.iter().all(|p| p.thread_root_post_id.is_none()));
+
+        let (posts, has_more) = service
+            .process_get_board_posts(&board, None, 3, BoardPostsMode::Threads)
+            .unwrap();
+        assert_eq!(posts.len(), 3);
+        assert!(!has_more);
+        let a = posts.iter().find(|p| p.post_id == "a").unwrap();
+        assert_eq!(a.reply_count, 1);
+
+        let (posts, _) = service
+            .process_get_board_posts(&board, None, 10, BoardPostsMode::Flat)
+            .unwrap();
+        assert_eq!(posts.len(), 4);
+    }
+
+    #[test]
+    fn test_get_thread_resolves_replies_to_their_root() {
+        let (service, board) = service();
+        submit(&service, "root", &board, None).unwrap();
+        submit(&service, "reply1", &board, Some("root")).unwrap();
+        submit(&service, "reply2", &board, Some("reply1")).unwrap();
+
+        let (board_id, root, posts) = service.process_get_thread("reply2").unwrap();
+        assert_eq!(board_id, board);
+        assert_eq!(root, "root");
+        assert_eq!(posts[0].post_id, "root");
+        assert_eq!(posts.len(), 3);
+
+        assert!(service.process_get_thread("nope").is_err());
+    }
+}
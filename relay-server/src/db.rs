@@ -1,6 +1,6 @@
 //! Relay server SQLite database for community board data
 
-use rusqlite::{params, Connection, Result as SqliteResult};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
 use std::sync::{Arc, Mutex};
 use tracing::info;
 
@@ -46,6 +46,80 @@ CREATE TABLE IF NOT EXISTS banned_peers (
 );
 "#;
 
+/// Auth tables are shared by enclave and relay-only mode. In relay-only mode
+/// they live in a standalone `auth.db` that carries no board tables.
+const AUTH_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS auth_challenges (
+    challenge_id TEXT PRIMARY KEY,
+    peer_id TEXT NOT NULL,
+    challenge_json TEXT NOT NULL,
+    expected_answers_json TEXT NOT NULL,
+    issued_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_auth_challenges_issued
+    ON auth_challenges(issued_at);
+
+CREATE TABLE IF NOT EXISTS auth_tokens (
+    token TEXT PRIMARY KEY,
+    peer_id TEXT NOT NULL,
+    verified_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL,
+    revoked_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_auth_tokens_peer
+    ON auth_tokens(peer_id, expires_at);
+
+CREATE INDEX IF NOT EXISTS idx_auth_tokens_expires
+    ON auth_tokens(expires_at);
+"#;
+
+/// Schema migrations applied after `SCHEMA`, tracked by `PRAGMA user_version`.
+/// Append new entries; never edit one that has shipped.
+const MIGRATIONS: &[&str] = &[
+    // 1: federation — receive time for mirrored posts, origin relay, cursors
+    r#"
+    ALTER TABLE board_posts ADD COLUMN received_at INTEGER;
+    ALTER TABLE board_posts ADD COLUMN origin_relay_peer_id TEXT;
+    UPDATE board_posts SET received_at = created_at WHERE received_at IS NULL;
+
+    CREATE INDEX IF NOT EXISTS idx_board_posts_board_received
+        ON board_posts(board_id, received_at DESC);
+    CREATE INDEX IF NOT EXISTS idx_board_posts_received_id
+        ON board_posts(received_at, post_id);
+    CREATE INDEX IF NOT EXISTS idx_board_posts_deleted_id
+        ON board_posts(deleted_at, post_id);
+
+    CREATE TABLE IF NOT EXISTS federation_cursors (
+        sibling_peer_id TEXT PRIMARY KEY,
+        post_received_at INTEGER NOT NULL DEFAULT 0,
+        post_id TEXT NOT NULL DEFAULT '',
+        tombstone_at INTEGER NOT NULL DEFAULT 0,
+        tombstone_post_id TEXT NOT NULL DEFAULT '',
+        last_sync_at INTEGER
+    );
+    "#,
+    // 2: threads — parent post and thread root for replies
+    r#"
+    ALTER TABLE board_posts ADD COLUMN reply_to_post_id TEXT;
+    ALTER TABLE board_posts ADD COLUMN thread_root_post_id TEXT;
+
+    CREATE INDEX IF NOT EXISTS idx_board_posts_thread
+        ON board_posts(thread_root_post_id, created_at);
+    "#,
+];
+
+/// Column list shared by every post query; indices match `row_to_post`.
+/// `reply_count` counts live replies in the thread rooted at the post.
+const POST_SELECT: &str = "SELECT bp.post_id, bp.board_id, bp.author_peer_id, bp.content_type, bp.content_text,
+        bp.lamport_clock, bp.created_at, bp.deleted_at, bp.signature,
+        kp.display_name, bp.received_at, bp.reply_to_post_id, bp.thread_root_post_id,
+        (SELECT COUNT(*) FROM board_posts r
+         WHERE r.thread_root_post_id = bp.post_id AND r.deleted_at IS NULL)
+ FROM board_posts bp
+ LEFT JOIN known_peers kp ON bp.author_peer_id = kp.peer_id";
+
 /// Relay server database
 #[derive(Clone)]
 pub struct RelayDatabase {
@@ -58,6 +132,8 @@ impl RelayDatabase {
         let conn = Connection::open(path)?;
         conn.execute_batch("PRAGMA foreign_keys = ON;")?;
         conn.execute_batch(SCHEMA)?;
+        conn.execute_batch(AUTH_SCHEMA)?;
+        Self::run_migrations(&conn)?;
 
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
@@ -70,6 +146,32 @@ impl RelayDatabase {
         Ok(db)
     }
 
+    /// Open or create a database holding only the auth tables (relay-only mode)
+    pub fn open_auth_store(path: &str) -> SqliteResult<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(AUTH_SCHEMA)?;
+
+        info!("Auth store initialized at {}", path);
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    fn run_migrations(conn: &Connection) -> SqliteResult<()> {
+        let version: usize =
+            conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))? as usize;
+
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let target = i + 1;
+            conn.execute_batch(&format!(
+                "BEGIN;\n{}\nPRAGMA user_version = {};\nCOMMIT;",
+                migration, target
+            ))?;
+            info!("Applied relay database migration {}", target);
+        }
+        Ok(())
+    }
+
     fn ensure_default_board(&self) -> SqliteResult<()> {
         let conn = self.conn.lock().unwrap();
         let count: i64 = conn.query_row(
@@ -127,55 +229,75 @@ impl RelayDatabase {
         lamport_clock: u64,
         created_at: i64,
         signature: &[u8],
+        reply_to_post_id: Option<&str>,
+        thread_root_post_id: Option<&str>,
     ) -> SqliteResult<()> {
         let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().timestamp();
         conn.execute(
-            "INSERT INTO board_posts (post_id, board_id, author_peer_id, content_type, content_text, lamport_clock, created_at, signature)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-            params![post_id, board_id, author_peer_id, content_type, content_text, lamport_clock as i64, created_at, signature],
+            "INSERT INTO board_posts (post_id, board_id, author_peer_id, content_type, content_text, lamport_clock, created_at, signature, received_at, reply_to_post_id, thread_root_post_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![post_id, board_id, author_peer_id, content_type, content_text, lamport_clock as i64, created_at, signature, now, reply_to_post_id, thread_root_post_id],
         )?;
         Ok(())
     }
 
+    pub fn get_post(&self, post_id: &str) -> SqliteResult<Option<PostRow>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            &format!("{} WHERE bp.post_id = ?", POST_SELECT),
+            [post_id],
+            Self::row_to_post,
+        )
+        .optional()
+    }
+
+    /// Posts for a board, optionally only thread roots. Newest first, except
+    /// that flat incremental reads (with `after_timestamp`) are oldest first.
     pub fn get_board_posts(
         &self,
         board_id: &str,
         after_timestamp: Option<i64>,
         limit: u32,
+        top_level_only: bool,
     ) -> SqliteResult<Vec<PostRow>> {
         let conn = self.conn.lock().unwrap();
+        let mut sql = format!(
+            "{} WHERE bp.board_id = ?1 AND (?2 IS NULL OR bp.received_at > ?2)",
+            POST_SELECT
+        );
+        if top_level_only {
+            sql.push_str(" AND bp.thread_root_post_id IS NULL");
+        }
+        // Incremental syncs page forward from the cursor; first loads show the newest
+        if after_timestamp.is_some() && !top_level_only {
+            sql.push_str(" ORDER BY bp.received_at ASC LIMIT ?3");
+        } else {
+            sql.push_str(" ORDER BY bp.received_at DESC LIMIT ?3");
+        }
+
+        let mut stmt = conn.prepare(&sql)?;
         let mut posts = Vec::new();
+        let mut rows = stmt.query(params![board_id, after_timestamp, limit])?;
+        while let Some(row) = rows.next()? {
+            posts.push(Self::row_to_post(row)?);
+        }
+        Ok(posts)
+    }
 
-        if let Some(after) = after_timestamp {
-            let mut stmt = conn.prepare(
-                "SELECT bp.post_id, bp.board_id, bp.author_peer_id, bp.content_type, bp.content_text,
-                        bp.lamport_clock, bp.created_at, bp.deleted_at, bp.signature,
-                        kp.display_name
-                 FROM board_posts bp
-                 LEFT JOIN known_peers kp ON bp.author_peer_id = kp.peer_id
-                 WHERE bp.board_id = ? AND bp.created_at > ?
-                 ORDER BY bp.created_at DESC
-                 LIMIT ?",
-            )?;
-            let mut rows = stmt.query(params![board_id, after, limit])?;
-            while let Some(row) = rows.next()? {
-                posts.push(Self::row_to_post(row)?);
-            }
-        } else {
-            let mut stmt = conn.prepare(
-                "SELECT bp.post_id, bp.board_id, bp.author_peer_id, bp.content_type, bp.content_text,
-                        bp.lamport_clock, bp.created_at, bp.deleted_at, bp.signature,
-                        kp.display_name
-                 FROM board_posts bp
-                 LEFT JOIN known_peers kp ON bp.author_peer_id = kp.peer_id
-                 WHERE bp.board_id = ?
-                 ORDER BY bp.created_at DESC
-                 LIMIT ?",
-            )?;
-            let mut rows = stmt.query(params![board_id, limit])?;
-            while let Some(row) = rows.next()? {
-                posts.push(Self::row_to_post(row)?);
-            }
+    /// A thread root followed by its replies, oldest first
+    pub fn get_thread_posts(&self, root_post_id: &str, limit: u32) -> SqliteResult<Vec<PostRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE bp.post_id = ?1 OR bp.thread_root_post_id = ?1
+             ORDER BY bp.thread_root_post_id IS NOT NULL, bp.created_at ASC, bp.post_id ASC
+             LIMIT ?2",
+            POST_SELECT
+        ))?;
+        let mut posts = Vec::new();
+        let mut rows = stmt.query(params![root_post_id, limit])?;
+        while let Some(row) = rows.next()? {
+            posts.push(Self::row_to_post(row)?);
         }
         Ok(posts)
     }
@@ -192,6 +314,10 @@ impl RelayDatabase {
             deleted_at: row.get(7)?,
             signature: row.get(8)?,
             author_display_name: row.get(9)?,
+            received_at: row.get(10)?,
+            reply_to_post_id: row.get(11)?,
+            thread_root_post_id: row.get(12)?,
+            reply_count: row.get::<_, i64>(13)? as u32,
         })
     }
 
@@ -246,6 +372,18 @@ impl RelayDatabase {
         Ok(count > 0)
     }
 
+    pub fn ban_peer(&self, peer_id: &str, reason: &str, banned_by: &str) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        conn.execute(
+            "INSERT INTO banned_peers (peer_id, reason, banned_at, banned_by)
+             VALUES (?, ?, ?, ?)
+             ON CONFLICT(peer_id) DO NOTHING",
+            params![peer_id, reason, now, banned_by],
+        )?;
+        Ok(())
+    }
+
     pub fn board_exists(&self, board_id: &str) -> SqliteResult<bool> {
         let conn = self.conn.lock().unwrap();
         let count: i64 = conn.query_row(
@@ -255,6 +393,438 @@ impl RelayDatabase {
         )?;
         Ok(count > 0)
     }
+
+    // ========== Federation Operations ==========
+
+    /// Posts after the (received_at, post_id) cursor in ascending order, skipping
+    /// banned authors. Only posts received before `before_received_at` are returned.
+    pub fn get_posts_since(
+        &self,
+        after_received_at: i64,
+        after_post_id: &str,
+        before_received_at: i64,
+        limit: u32,
+    ) -> SqliteResult<Vec<PostRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE (bp.received_at > ?1 OR (bp.received_at = ?1 AND bp.post_id > ?2))
+               AND bp.received_at < ?3
+               AND bp.author_peer_id NOT IN (SELECT peer_id FROM banned_peers)
+             ORDER BY bp.received_at ASC, bp.post_id ASC
+             LIMIT ?4",
+            POST_SELECT
+        ))?;
+        let mut posts = Vec::new();
+        let mut rows = stmt.query(params![
+            after_received_at,
+            after_post_id,
+            before_received_at,
+            limit
+        ])?;
+        while let Some(row) = rows.next()? {
+            posts.push(Self::row_to_post(row)?);
+        }
+        Ok(posts)
+    }
+
+    /// Tombstones (post_id, deleted_at) after the (deleted_at, post_id) cursor,
+    /// oldest first. Only tombstones from before `before_deleted_at` are returned.
+    pub fn get_tombstones_since(
+        &self,
+        after_deleted_at: i64,
+        after_post_id: &str,
+        before_deleted_at: i64,
+        limit: u32,
+    ) -> SqliteResult<Vec<(String, i64)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT post_id, deleted_at FROM board_posts
+             WHERE (deleted_at > ?1 OR (deleted_at = ?1 AND post_id > ?2))
+               AND deleted_at < ?3
+             ORDER BY deleted_at ASC, post_id ASC
+             LIMIT ?4",
+        )?;
+        let mut tombstones = Vec::new();
+        let mut rows = stmt.query(params![
+            after_deleted_at,
+            after_post_id,
+            before_deleted_at,
+            limit
+        ])?;
+        while let Some(row) = rows.next()? {
+            tombstones.push((row.get(0)?, row.get(1)?));
+        }
+        Ok(tombstones)
+    }
+
+    pub fn get_known_peer(&self, peer_id: &str) -> SqliteResult<Option<PeerRow>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT peer_id, public_key, display_name FROM known_peers WHERE peer_id = ?",
+            [peer_id],
+            |row| {
+                Ok(PeerRow {
+                    peer_id: row.get(0)?,
+                    public_key: row.get(1)?,
+                    display_name: row.get(2)?,
+                })
+            },
+        )
+        .optional()
+    }
+
+    pub fn list_bans(&self) -> SqliteResult<Vec<BanRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT peer_id, reason, banned_at FROM banned_peers ORDER BY banned_at")?;
+        let mut bans = Vec::new();
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            bans.push(BanRow {
+                peer_id: row.get(0)?,
+                reason: row.get(1)?,
+                banned_at: row.get(2)?,
+            });
+        }
+        Ok(bans)
+    }
+
+    /// Insert a board mirrored from a sibling relay. Mirrored boards are never the default.
+    pub fn insert_mirrored_board(
+        &self,
+        board_id: &str,
+        name: &str,
+        description: Option<&str>,
+        origin_relay_peer_id: &str,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        conn.execute(
+            "INSERT OR IGNORE INTO boards (board_id, name, description, created_by_peer_id, created_at, is_default)
+             VALUES (?, ?, ?, ?, ?, 0)",
+            params![board_id, name, description, origin_relay_peer_id, now],
+        )?;
+        Ok(())
+    }
+
+    /// Record a peer learned from a sibling relay without touching an existing entry
+    pub fn insert_known_peer_if_missing(
+        &self,
+        peer_id: &str,
+        public_key: &[u8],
+        display_name: &str,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        conn.execute(
+            "INSERT OR IGNORE INTO known_peers (peer_id, public_key, display_name, first_seen_at, last_seen_at)
+             VALUES (?, ?, ?, ?, ?)",
+            params![peer_id, public_key, display_name, now, now],
+        )?;
+        Ok(())
+    }
+
+    /// Insert a post mirrored from a sibling relay. Returns false if the post_id is already known.
+    pub fn insert_mirrored_post(
+        &self,
+        post: &PostRow,
+        origin_relay_peer_id: &str,
+    ) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let rows = conn.execute(
+            "INSERT OR IGNORE INTO board_posts
+                (post_id, board_id, author_peer_id, content_type, content_text, lamport_clock,
+                 created_at, deleted_at, signature, received_at, origin_relay_peer_id,
+                 reply_to_post_id, thread_root_post_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                post.post_id,
+                post.board_id,
+                post.author_peer_id,
+                post.content_type,
+                post.content_text,
+                post.lamport_clock as i64,
+                post.created_at,
+                post.deleted_at,
+                post.signature,
+                now,
+                origin_relay_peer_id,
+                post.reply_to_post_id,
+                post.thread_root_post_id,
+            ],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Apply a tombstone from a sibling relay. Returns false if the post is unknown or already deleted.
+    pub fn apply_tombstone(&self, post_id: &str, deleted_at: i64) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE board_posts SET deleted_at = ? WHERE post_id = ? AND deleted_at IS NULL",
+            params![deleted_at, post_id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    pub fn get_federation_cursor(&self, sibling_peer_id: &str) -> SqliteResult<FederationCursorRow> {
+        let conn = self.conn.lock().unwrap();
+        let cursor = conn
+            .query_row(
+                "SELECT post_received_at, post_id, tombstone_at, tombstone_post_id, last_sync_at
+                 FROM federation_cursors WHERE sibling_peer_id = ?",
+                [sibling_peer_id],
+                |row| {
+                    Ok(FederationCursorRow {
+                        post_received_at: row.get(0)?,
+                        post_id: row.get(1)?,
+                        tombstone_at: row.get(2)?,
+                        tombstone_post_id: row.get(3)?,
+                        last_sync_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(cursor.unwrap_or_default())
+    }
+
+    pub fn update_federation_cursor(
+        &self,
+        sibling_peer_id: &str,
+        post_received_at: i64,
+        post_id: &str,
+        tombstone_at: i64,
+        tombstone_post_id: &str,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        conn.execute(
+            "INSERT INTO federation_cursors (sibling_peer_id, post_received_at, post_id, tombstone_at, tombstone_post_id, last_sync_at)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT(sibling_peer_id) DO UPDATE SET
+                 post_received_at = excluded.post_received_at,
+                 post_id = excluded.post_id,
+                 tombstone_at = excluded.tombstone_at,
+                 tombstone_post_id = excluded.tombstone_post_id,
+                 last_sync_at = excluded.last_sync_at",
+            params![
+                sibling_peer_id,
+                post_received_at,
+                post_id,
+                tombstone_at,
+                tombstone_post_id,
+                now
+            ],
+        )?;
+        Ok(())
+    }
+
+    // ========== Auth Operations ==========
+
+    pub fn insert_challenge(
+        &self,
+        challenge_id: &str,
+        peer_id: &str,
+        challenge_json: &str,
+        expected_answers_json: &str,
+        issued_at: i64,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO auth_challenges (challenge_id, peer_id, challenge_json, expected_answers_json, issued_at)
+             VALUES (?, ?, ?, ?, ?)",
+            params![challenge_id, peer_id, challenge_json, expected_answers_json, issued_at],
+        )?;
+        Ok(())
+    }
+
+    /// Remove and return a pending challenge issued after `issued_after`.
+    /// Challenges are single-use, so the row is deleted whether or not it is still fresh.
+    pub fn take_challenge(
+        &self,
+        challenge_id: &str,
+        issued_after: i64,
+    ) -> SqliteResult<Option<ChallengeRow>> {
+        let conn = self.conn.lock().unwrap();
+        let row = conn
+            .query_row(
+                "SELECT peer_id, challenge_json, expected_answers_json, issued_at
+                 FROM auth_challenges WHERE challenge_id = ?",
+                [challenge_id],
+                |row| {
+                    Ok(ChallengeRow {
+                        peer_id: row.get(0)?,
+                        challenge_json: row.get(1)?,
+                        expected_answers_json: row.get(2)?,
+                        issued_at: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+
+        conn.execute(
+            "DELETE FROM auth_challenges WHERE challenge_id = ?",
+            [challenge_id],
+        )?;
+
+        Ok(row.filter(|c| c.issued_at > issued_after))
+    }
+
+    pub fn insert_token(
+        &self,
+        token: &str,
+        peer_id: &str,
+        verified_at: i64,
+        expires_at: i64,
+    ) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO auth_tokens (token, peer_id, verified_at, expires_at)
+             VALUES (?, ?, ?, ?)",
+            params![token, peer_id, verified_at, expires_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_token(&self, token: &str) -> SqliteResult<Option<TokenRow>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT token, peer_id, verified_at, expires_at, revoked_at
+             FROM auth_tokens WHERE token = ?",
+            [token],
+            Self::row_to_token,
+        )
+        .optional()
+    }
+
+    /// Check whether a peer holds at least one unexpired, unrevoked token
+    pub fn has_valid_token(&self, peer_id: &str, now: i64) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM auth_tokens
+             WHERE peer_id = ? AND expires_at > ? AND revoked_at IS NULL",
+            params![peer_id, now],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// List tokens that have not yet expired, optionally for a single peer
+    pub fn list_tokens(&self, peer_id: Option<&str>, now: i64) -> SqliteResult<Vec<TokenRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut tokens = Vec::new();
+
+        if let Some(peer_id) = peer_id {
+            let mut stmt = conn.prepare(
+                "SELECT token, peer_id, verified_at, expires_at, revoked_at
+                 FROM auth_tokens
+                 WHERE peer_id = ? AND expires_at > ?
+                 ORDER BY verified_at DESC",
+            )?;
+            let mut rows = stmt.query(params![peer_id, now])?;
+            while let Some(row) = rows.next()? {
+                tokens.push(Self::row_to_token(row)?);
+            }
+        } else {
+            let mut stmt = conn.prepare(
+                "SELECT token, peer_id, verified_at, expires_at, revoked_at
+                 FROM auth_tokens
+                 WHERE expires_at > ?
+                 ORDER BY verified_at DESC",
+            )?;
+            let mut rows = stmt.query([now])?;
+            while let Some(row) = rows.next()? {
+                tokens.push(Self::row_to_token(row)?);
+            }
+        }
+        Ok(tokens)
+    }
+
+    fn row_to_token(row: &rusqlite::Row) -> SqliteResult<TokenRow> {
+        Ok(TokenRow {
+            token: row.get(0)?,
+            peer_id: row.get(1)?,
+            verified_at: row.get(2)?,
+            expires_at: row.get(3)?,
+            revoked_at: row.get(4)?,
+        })
+    }
+
+    pub fn revoke_token(&self, token: &str, now: i64) -> SqliteResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE auth_tokens SET revoked_at = ? WHERE token = ? AND revoked_at IS NULL",
+            params![now, token],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Revoke every live token held by a peer. Returns the number revoked.
+    pub fn revoke_peer_tokens(&self, peer_id: &str, now: i64) -> SqliteResult<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE auth_tokens SET revoked_at = ?
+             WHERE peer_id = ? AND revoked_at IS NULL AND expires_at > ?",
+            params![now, peer_id, now],
+        )
+    }
+
+    /// Delete challenges issued at or before `challenges_before` and tokens
+    /// that expired at or before `now`
+    pub fn prune_auth(&self, challenges_before: i64, now: i64) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM auth_challenges WHERE issued_at <= ?",
+            [challenges_before],
+        )?;
+        conn.execute("DELETE FROM auth_tokens WHERE expires_at <= ?", [now])?;
+        Ok(())
+    }
+}
+
+/// A known peer row
+#[derive(Debug, Clone)]
+pub struct PeerRow {
+    pub peer_id: String,
+    pub public_key: Vec<u8>,
+    pub display_name: String,
+}
+
+/// A banned peer row
+#[derive(Debug, Clone)]
+pub struct BanRow {
+    pub peer_id: String,
+    pub reason: Option<String>,
+    pub banned_at: i64,
+}
+
+/// Federation progress against one sibling relay
+#[derive(Debug, Clone, Default)]
+pub struct FederationCursorRow {
+    pub post_received_at: i64,
+    pub post_id: String,
+    pub tombstone_at: i64,
+    pub tombstone_post_id: String,
+    pub last_sync_at: Option<i64>,
+}
+
+/// A pending CAPTCHA challenge row
+#[derive(Debug, Clone)]
+pub struct ChallengeRow {
+    pub peer_id: String,
+    pub challenge_json: String,
+    pub expected_answers_json: String,
+    pub issued_at: i64,
+}
+
+/// An issued auth token row
+#[derive(Debug, Clone)]
+pub struct TokenRow {
+    pub token: String,
+    pub peer_id: String,
+    pub verified_at: i64,
+    pub expires_at: i64,
+    pub revoked_at: Option<i64>,
 }
 
 /// A board row from the database
@@ -279,4 +849,174 @@ pub struct PostRow {
     pub deleted_at: Option<i64>,
     pub signature: Vec<u8>,
     pub author_display_name: Option<String>,
+    /// When this relay stored the post (differs from `created_at` for mirrored posts)
+    pub received_at: i64,
+    pub reply_to_post_id: Option<String>,
+    pub thread_root_post_id: Option<String>,
+    pub reply_count: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A database file that is removed when dropped
+    struct TempDb(std::path::PathBuf);
+
+    impl TempDb {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("relay-{}.db", uuid::Uuid::new_v4())))
+        }
+
+        fn open(&self) -> RelayDatabase {
+            RelayDatabase::open(self.0.to_str().unwrap()).unwrap()
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_auth_state_survives_reopen() {
+        let file = TempDb::new();
+        let now = chrono::Utc::now().timestamp();
+        {
+            let db = file.open();
+            db.insert_challenge("challenge-1", "alice", "{}", "[]", now)
+                .unwrap();
+            db.insert_token("token-1", "alice", now, now + 3600)
+                .unwrap();
+        }
+
+        let db = file.open();
+        assert!(db.has_valid_token("alice", now).unwrap());
+        let token = db.get_token("token-1").unwrap().unwrap();
+        assert_eq!(token.peer_id, "alice");
+        assert_eq!(token.expires_at, now + 3600);
+
+        let challenge = db.take_challenge("challenge-1", now - 60).unwrap().unwrap();
+        assert_eq!(challenge.peer_id, "alice");
+        // Single use
+        assert!(db
+            .take_challenge("challenge-1", now - 60)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_revoked_tokens_are_rejected() {
+        let db = RelayDatabase::open_auth_store(":memory:").unwrap();
+        let now = chrono::Utc::now().timestamp();
+        db.insert_token("token-1", "alice", now, now + 3600)
+            .unwrap();
+        db.insert_token("token-2", "alice", now, now + 3600)
+            .unwrap();
+        db.insert_token("token-3", "bob", now, now + 3600).unwrap();
+
+        assert!(db.revoke_token("token-1", now).unwrap());
+        assert!(!db.revoke_token("token-1", now).unwrap());
+        assert!(db.has_valid_token("alice", now).unwrap());
+        assert_eq!(
+            db.get_token("token-1").unwrap().unwrap().revoked_at,
+            Some(now)
+        );
+
+        assert_eq!(db.revoke_peer_tokens("alice", now).unwrap(), 1);
+        assert!(!db.has_valid_token("alice", now).unwrap());
+        assert!(db.has_valid_token("bob", now).unwrap());
+    }
+
+    #[test]
+    fn test_expired_challenges_and_tokens_are_purged() {
+        let db = RelayDatabase::open_auth_store(":memory:").unwrap();
+        let now = chrono::Utc::now().timestamp();
+        db.insert_challenge("stale", "alice", "{}", "[]", now - 120)
+            .unwrap();
+        db.insert_challenge("fresh", "alice", "{}", "[]", now)
+            .unwrap();
+        db.insert_token("expired", "alice", now - 7200, now - 3600)
+            .unwrap();
+        db.insert_token("live", "alice", now, now + 3600).unwrap();
+
+        // An expired challenge is consumed but not returned
+        assert!(db.take_challenge("stale", now - 60).unwrap().is_none());
+        db.insert_challenge("stale", "alice", "{}", "[]", now - 120)
+            .unwrap();
+
+        db.prune_auth(now - 60, now).unwrap();
+        assert!(db.get_token("expired").unwrap().is_none());
+        assert!(db.get_token("live").unwrap().is_some());
+        assert!(db.take_challenge("fresh", now - 60).unwrap().is_some());
+        let conn = db.conn.lock().unwrap();
+        let challenges: i64 = conn
+            .query_row("SELECT COUNT(*) FROM auth_challenges", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(challenges, 0);
+    }
+
+    #[test]
+    fn test_list_tokens_skips_expired() {
+        let db = RelayDatabase::open_auth_store(":memory:").unwrap();
+        let now = chrono::Utc::now().timestamp();
+        db.insert_token("old", "alice", now - 10, now + 60).unwrap();
+        db.insert_token("new", "alice", now, now + 60).unwrap();
+        db.insert_token("expired", "alice", now - 120, now - 60)
+            .unwrap();
+        db.insert_token("bob", "bob", now, now + 60).unwrap();
+        db.revoke_token("bob", now).unwrap();
+
+        let alice: Vec<String> = db
+            .list_tokens(Some("alice"), now)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.token)
+            .collect();
+        assert_eq!(alice, ["new", "old"]);
+
+        // Revoked tokens are listed until they expire
+        let all = db.list_tokens(None, now).unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(all
+            .iter()
+            .any(|t| t.token == "bob" && t.revoked_at.is_some()));
+    }
+
+    #[test]
+    fn test_migrations_apply_to_populated_database() {
+        let file = TempDb::new();
+        {
+            // A database from before any migration, with a post in it
+            let conn = Connection::open(&file.0).unwrap();
+            conn.execute_batch(SCHEMA).unwrap();
+            conn.execute_batch(AUTH_SCHEMA).unwrap();
+            conn.execute_batch(
+                "INSERT INTO boards (board_id, name, created_at, is_default)
+                 VALUES ('general', 'General', 100, 1);
+                 INSERT INTO board_posts (post_id, board_id, author_peer_id, content_type,
+                     content_text, lamport_clock, created_at, signature)
+                 VALUES ('post1', 'general', 'alice', 'text', 'hello', 1, 1000, X'00');",
+            )
+            .unwrap();
+        }
+
+        let db = file.open();
+        let version: i64 = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version as usize, MIGRATIONS.len());
+
+        let post = db.get_post("post1").unwrap().unwrap();
+        assert_eq!(post.content_text.as_deref(), Some("hello"));
+        assert_eq!(post.received_at, 1000);
+        assert_eq!(post.reply_to_post_id, None);
+        assert_eq!(post.thread_root_post_id, None);
+        assert_eq!(post.reply_count, 0);
+        assert_eq!(db.list_boards().unwrap().len(), 1);
+    }
 }
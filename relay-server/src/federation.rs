@@ -0,0 +1,828 @@
+//! Relay federation: mirror enclave boards between sibling bastion relays
+//!
+//! Each relay pulls from its configured siblings over `/bastion/federation/1.0.0`.
+//! A pull carries a cursor of (received_at, post_id) for posts and
+//! (deleted_at, post_id) for tombstones, so every exchange is incremental.
+//! `received_at` is when the serving relay stored the post, so backdated posts
+//! and posts mirrored in from other siblings still sort after the cursor. Only
+//! seconds that have fully passed are served, so nothing stored later in the
+//! same second can land behind a cursor. Mirrored posts keep their
+//! author signatures, are deduplicated on post_id, and are dropped if the
+//! author is banned or the signature does not verify against the author's
+//! registered key. Bans and tombstones propagate along with the posts.
+
+use crate::board_service::MAX_CLOCK_SKEW_SECS;
+use crate::db::{PostRow, RelayDatabase};
+use crate::{BoardInfoProto, BoardPostInfoProto};
+use axum::extract::State;
+use axum::Json;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+/// Federation protocol version
+pub const FEDERATION_PROTOCOL: &str = "/bastion/federation/1.0.0";
+
+/// Maximum posts or tombstones returned per pull
+const MAX_BATCH: u32 = 200;
+
+/// Incremental sync position against a sibling
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FederationCursor {
+    pub post_received_at: i64,
+    pub post_id: String,
+    pub tombstone_at: i64,
+    pub tombstone_post_id: String,
+}
+
+/// A peer referenced by mirrored posts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederatedPeer {
+    pub peer_id: String,
+    pub public_key: Vec<u8>,
+    pub display_name: String,
+}
+
+/// A ban propagated from a sibling
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederatedBan {
+    pub peer_id: String,
+    pub reason: Option<String>,
+    pub banned_at: i64,
+}
+
+/// Federation request (wire protocol)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FederationRequest {
+    /// Pull everything after the cursor
+    Pull {
+        relay_peer_id: String,
+        cursor: FederationCursor,
+        limit: u32,
+    },
+}
+
+/// Federation response (wire protocol)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FederationResponse {
+    Batch {
+        boards: Vec<BoardInfoProto>,
+        peers: Vec<FederatedPeer>,
+        posts: Vec<BoardPostInfoProto>,
+        tombstones: Vec<(String, i64)>,
+        bans: Vec<FederatedBan>,
+        next_cursor: FederationCursor,
+        has_more: bool,
+    },
+    Error {
+        error: String,
+    },
+}
+
+/// Signable board post — must match the client's `SignableBoardPost` field for field
+#[derive(Serialize)]
+struct SignableBoardPost<'a> {
+    post_id: &'a str,
+    board_id: &'a str,
+    author_peer_id: &'a str,
+    content_type: &'a str,
+    content_text: Option<&'a str>,
+    lamport_clock: u64,
+    created_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_to_post_id: Option<&'a str>,
+}
+
+fn verify_post_signature(public_key: &[u8], post: &BoardPostInfoProto) -> bool {
+    let Ok(key_bytes) = <[u8; 32]>::try_from(public_key) else {
+        return false;
+    };
+    let Ok(verifying_key) = VerifyingKey::from_bytes(&key_bytes) else {
+        return false;
+    };
+    let Ok(signature) = Signature::from_slice(&post.signature) else {
+        return false;
+    };
+
+    let signable = SignableBoardPost {
+        post_id: &post.post_id,
+        board_id: &post.board_id,
+        author_peer_id: &post.author_peer_id,
+        content_type: &post.content_type,
+        content_text: post.content_text.as_deref(),
+        lamport_clock: post.lamport_clock,
+        created_at: post.created_at,
+        reply_to_post_id: post.reply_to_post_id.as_deref(),
+    };
+    let mut bytes = Vec::new();
+    if ciborium::into_writer(&signable, &mut bytes).is_err() {
+        return false;
+    }
+    verifying_key.verify(&bytes, &signature).is_ok()
+}
+
+/// Sync status for one sibling relay
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiblingStatus {
+    pub peer_id: String,
+    pub address: String,
+    pub connected: bool,
+    pub last_sync_at: Option<i64>,
+    pub last_error: Option<String>,
+    pub posts_mirrored: u64,
+    pub tombstones_applied: u64,
+    pub cursor_received_at: i64,
+}
+
+/// Shared federation status, read by the HTTP API and written by the swarm loop
+#[derive(Default)]
+pub struct FederationStatus {
+    siblings: RwLock<HashMap<PeerId, SiblingStatus>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FederationStatusResponse {
+    pub enabled: bool,
+    pub siblings: Vec<SiblingStatus>,
+}
+
+/// GET /federation/status - Sync state for each sibling relay
+pub async fn federation_status(
+    State(status): State<Arc<FederationStatus>>,
+) -> Json<FederationStatusResponse> {
+    let siblings = status.siblings.read().await;
+    let mut list: Vec<SiblingStatus> = siblings.values().cloned().collect();
+    list.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+    Json(FederationStatusResponse {
+        enabled: !list.is_empty(),
+        siblings: list,
+    })
+}
+
+/// Server- and client-side federation logic
+pub struct FederationService {
+    db: RelayDatabase,
+    local_peer_id: PeerId,
+    siblings: HashMap<PeerId, Multiaddr>,
+    status: Arc<FederationStatus>,
+}
+
+impl FederationService {
+    /// Parse sibling addresses (each must end in /p2p/<peer_id>)
+    pub fn new(
+        db: RelayDatabase,
+        local_peer_id: PeerId,
+        sibling_addrs: &[String],
+        status: Arc<FederationStatus>,
+    ) -> Result<Self, String> {
+        let mut siblings = HashMap::new();
+        for addr in sibling_addrs {
+            let multiaddr: Multiaddr = addr
+                .parse()
+                .map_err(|e| format!("Invalid sibling address {}: {}", addr, e))?;
+            let peer_id = multiaddr
+                .iter()
+                .find_map(|p| match p {
+                    libp2p::multiaddr::Protocol::P2p(peer_id) => Some(peer_id),
+                    _ => None,
+                })
+                .ok_or_else(|| format!("Sibling address {} must include /p2p/<peer_id>", addr))?;
+            siblings.insert(peer_id, multiaddr);
+        }
+
+        Ok(Self {
+            db,
+            local_peer_id,
+            siblings,
+            status,
+        })
+    }
+
+    /// Seed the status table with every configured sibling
+    pub async fn init_status(&self) {
+        let mut status = self.status.siblings.write().await;
+        for (peer_id, addr) in &self.siblings {
+            let cursor = self
+                .db
+                .get_federation_cursor(&peer_id.to_string())
+                .unwrap_or_default();
+            status.insert(
+                *peer_id,
+                SiblingStatus {
+                    peer_id: peer_id.to_string(),
+                    address: addr.to_string(),
+                    connected: false,
+                    last_sync_at: cursor.last_sync_at,
+                    last_error: None,
+                    posts_mirrored: 0,
+                    tombstones_applied: 0,
+                    cursor_received_at: cursor.post_received_at,
+                },
+            );
+        }
+    }
+
+    pub fn siblings(&self) -> impl Iterator<Item = (&PeerId, &Multiaddr)> {
+        self.siblings.iter()
+    }
+
+    pub fn is_sibling(&self, peer: &PeerId) -> bool {
+        self.siblings.contains_key(peer)
+    }
+
+    pub async fn is_connected(&self, peer: &PeerId) -> bool {
+        self.status
+            .siblings
+            .read()
+            .await
+            .get(peer)
+            .is_some_and(|s| s.connected)
+    }
+
+    pub async fn set_connected(&self, peer: &PeerId, connected: bool) {
+        if let Some(status) = self.status.siblings.write().await.get_mut(peer) {
+            status.connected = connected;
+        }
+    }
+
+    pub async fn record_error(&self, peer: &PeerId, error: String) {
+        warn!("Federation with {} failed: {}", peer, error);
+        if let Some(status) = self.status.siblings.write().await.get_mut(peer) {
+            status.last_error = Some(error);
+        }
+    }
+
+    /// Build a pull request from the stored cursor for a sibling
+    pub fn create_pull_request(&self, sibling: &PeerId) -> FederationRequest {
+        let cursor = self
+            .db
+            .get_federation_cursor(&sibling.to_string())
+            .unwrap_or_default();
+        FederationRequest::Pull {
+            relay_peer_id: self.local_peer_id.to_string(),
+            cursor: FederationCursor {
+                post_received_at: cursor.post_received_at,
+                post_id: cursor.post_id,
+                tombstone_at: cursor.tombstone_at,
+                tombstone_post_id: cursor.tombstone_post_id,
+            },
+            limit: MAX_BATCH,
+        }
+    }
+
+    /// Serve a pull from a sibling
+    pub fn process_pull(&self, peer: &PeerId, request: FederationRequest) -> FederationResponse {
+        let FederationRequest::Pull {
+            relay_peer_id,
+            cursor,
+            limit,
+        } = request;
+
+        if !self.is_sibling(peer) || relay_peer_id != peer.to_string() {
+            warn!("Rejecting federation pull from non-sibling {}", peer);
+            return FederationResponse::Error {
+                error: "Not a federation sibling".to_string(),
+            };
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        match self.build_batch(&cursor, limit.min(MAX_BATCH), now) {
+            Ok(response) => response,
+            Err(e) => FederationResponse::Error { error: e },
+        }
+    }
+
+    /// Posts and tombstones after `cursor`, up to the second before `now`
+    fn build_batch(
+        &self,
+        cursor: &FederationCursor,
+        limit: u32,
+        now: i64,
+    ) -> Result<FederationResponse, String> {
+        let boards = self
+            .db
+            .list_boards()
+            .map_err(|e| format!("Failed to list boards: {}", e))?;
+
+        let posts = self
+            .db
+            .get_posts_since(cursor.post_received_at, &cursor.post_id, now, limit + 1)
+            .map_err(|e| format!("Failed to load posts: {}", e))?;
+        let tombstones = self
+            .db
+            .get_tombstones_since(
+                cursor.tombstone_at,
+                &cursor.tombstone_post_id,
+                now,
+                limit + 1,
+            )
+            .map_err(|e| format!("Failed to load tombstones: {}", e))?;
+
+        let has_more = posts.len() > limit as usize || tombstones.len() > limit as usize;
+        let posts: Vec<PostRow> = posts.into_iter().take(limit as usize).collect();
+        let tombstones: Vec<(String, i64)> = tombstones.into_iter().take(limit as usize).collect();
+
+        let mut peers: HashMap<String, FederatedPeer> = HashMap::new();
+        for post in &posts {
+            if peers.contains_key(&post.author_peer_id) {
+                continue;
+            }
+            if let Ok(Some(peer)) = self.db.get_known_peer(&post.author_peer_id) {
+                peers.insert(
+                    peer.peer_id.clone(),
+                    FederatedPeer {
+                        peer_id: peer.peer_id,
+                        public_key: peer.public_key,
+                        display_name: peer.display_name,
+                    },
+                );
+            }
+        }
+
+        let bans = self
+            .db
+            .list_bans()
+            .map_err(|e| format!("Failed to list bans: {}", e))?
+            .into_iter()
+            .map(|b| FederatedBan {
+                peer_id: b.peer_id,
+                reason: b.reason,
+                banned_at: b.banned_at,
+            })
+            .collect();
+
+        let (post_received_at, post_id) = posts
+            .last()
+            .map(|p| (p.received_at, p.post_id.clone()))
+            .unwrap_or_else(|| (cursor.post_received_at, cursor.post_id.clone()));
+        let (tombstone_post_id, tombstone_at) = tombstones
+            .last()
+            .cloned()
+            .unwrap_or_else(|| (cursor.tombstone_post_id.clone(), cursor.tombstone_at));
+        let next_cursor = FederationCursor {
+            post_received_at,
+            post_id,
+            tombstone_at,
+            tombstone_post_id,
+        };
+
+        Ok(FederationResponse::Batch {
+            boards: boards
+                .into_iter()
+                .map(|b| BoardInfoProto {
+                    board_id: b.board_id,
+                    name: b.name,
+                    description: b.description,
+                    is_default: b.is_default,
+                })
+                .collect(),
+            peers: peers.into_values().collect(),
+            posts: posts.into_iter().map(BoardPostInfoProto::from).collect(),
+            tombstones,
+            bans,
+            next_cursor,
+            has_more,
+        })
+    }
+
+    /// Apply a batch pulled from a sibling. Returns whether more is pending and
+    /// the ids of posts that were mirrored or tombstoned.
+    pub async fn apply_response(
+        &self,
+        sibling: &PeerId,
+        response: FederationResponse,
+    ) -> (bool, Vec<String>) {
+        match response {
+            FederationResponse::Batch {
+                boards,
+                peers,
+                posts,
+                tombstones,
+                bans,
+                next_cursor,
+                has_more,
+            } => match self.apply_batch(sibling, boards, peers, posts, tombstones, bans, &next_cursor)
+            {
+                Ok((mirrored, applied)) => {
+                    if !mirrored.is_empty() || !applied.is_empty() {
+                        info!(
+                            "Federation with {}: mirrored {} post(s), applied {} tombstone(s)",
+                            sibling,
+                            mirrored.len(),
+                            applied.len()
+                        );
+                    }
+                    if let Some(status) = self.status.siblings.write().await.get_mut(sibling) {
+                        status.last_sync_at = Some(chrono::Utc::now().timestamp());
+                        status.last_error = None;
+                        status.posts_mirrored += mirrored.len() as u64;
+                        status.tombstones_applied += applied.len() as u64;
+                        status.cursor_received_at = next_cursor.post_received_at;
+                    }
+                    let mut changed = mirrored;
+                    changed.extend(applied);
+                    (has_more, changed)
+                }
+                Err(e) => {
+                    self.record_error(sibling, e).await;
+                    (false, Vec::new())
+                }
+            },
+            FederationResponse::Error { error } => {
+                self.record_error(sibling, error).await;
+                (false, Vec::new())
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn apply_batch(
+        &self,
+        sibling: &PeerId,
+        boards: Vec<BoardInfoProto>,
+        peers: Vec<FederatedPeer>,
+        posts: Vec<BoardPostInfoProto>,
+        tombstones: Vec<(String, i64)>,
+        bans: Vec<FederatedBan>,
+        next_cursor: &FederationCursor,
+    ) -> Result<(Vec<String>, Vec<String>), String> {
+        let origin = sibling.to_string();
+
+        for ban in &bans {
+            let reason = ban.reason.as_deref().unwrap_or("Banned on sibling relay");
+            self.db
+                .ban_peer(&ban.peer_id, reason, &origin)
+                .map_err(|e| format!("Failed to apply ban: {}", e))?;
+        }
+
+        for board in &boards {
+            self.db
+                .insert_mirrored_board(
+                    &board.board_id,
+                    &board.name,
+                    board.description.as_deref(),
+                    &origin,
+                )
+                .map_err(|e| format!("Failed to mirror board: {}", e))?;
+        }
+
+        for peer in &peers {
+            self.db
+                .insert_known_peer_if_missing(&peer.peer_id, &peer.public_key, &peer.display_name)
+                .map_err(|e| format!("Failed to record peer: {}", e))?;
+        }
+
+        let max_created_at = chrono::Utc::now().timestamp() + MAX_CLOCK_SKEW_SECS;
+        let mut mirrored = Vec::new();
+        for post in posts {
+            if post.created_at > max_created_at
+                || self.db.is_peer_banned(&post.author_peer_id).unwrap_or(false)
+            {
+                continue;
+            }
+            let Ok(Some(author)) = self.db.get_known_peer(&post.author_peer_id) else {
+                warn!("Skipping mirrored post {}: unknown author", post.post_id);
+                continue;
+            };
+            if !verify_post_signature(&author.public_key, &post) {
+                warn!("Skipping mirrored post {}: bad signature", post.post_id);
+                continue;
+            }
+            let row = PostRow::from(post);
+            if self
+                .db
+                .insert_mirrored_post(&row, &origin)
+                .map_err(|e| format!("Failed to mirror post: {}", e))?
+            {
+                mirrored.push(row.post_id);
+            }
+        }
+
+        let mut applied = Vec::new();
+        for (post_id, deleted_at) in tombstones {
+            if self
+                .db
+                .apply_tombstone(&post_id, deleted_at)
+                .map_err(|e| format!("Failed to apply tombstone: {}", e))?
+            {
+                applied.push(post_id);
+            }
+        }
+
+        self.db
+            .update_federation_cursor(
+                &origin,
+                next_cursor.post_received_at,
+                &next_cursor.post_id,
+                next_cursor.tombstone_at,
+                &next_cursor.tombstone_post_id,
+            )
+            .map_err(|e| format!("Failed to store federation cursor: {}", e))?;
+
+        Ok((mirrored, applied))
+    }
+}
+
+impl From<PostRow> for BoardPostInfoProto {
+    fn from(p: PostRow) -> Self {
+        Self {
+            post_id: p.post_id,
+            board_id: p.board_id,
+            author_peer_id: p.author_peer_id,
+            author_display_name: p.author_display_name,
+            content_type: p.content_type,
+            content_text: p.content_text,
+            lamport_clock: p.lamport_clock,
+            created_at: p.created_at,
+            deleted_at: p.deleted_at,
+            signature: p.signature,
+            received_at: Some(p.received_at),
+            reply_to_post_id: p.reply_to_post_id,
+            thread_root_post_id: p.thread_root_post_id,
+            reply_count: p.reply_count,
+        }
+    }
+}
+
+impl From<BoardPostInfoProto> for PostRow {
+    fn from(p: BoardPostInfoProto) -> Self {
+        Self {
+            post_id: p.post_id,
+            board_id: p.board_id,
+            author_peer_id: p.author_peer_id,
+            content_type: p.content_type,
+            content_text: p.content_text,
+            lamport_clock: p.lamport_clock,
+            created_at: p.created_at,
+            deleted_at: p.deleted_at,
+            signature: p.signature,
+            author_display_name: p.author_display_name,
+            received_at: p.received_at.unwrap_or(p.created_at),
+            reply_to_post_id: p.reply_to_post_id,
+            thread_root_post_id: p.thread_root_post_id,
+            reply_count: p.reply_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    struct Relay {
+        peer_id: PeerId,
+        db: RelayDatabase,
+        service: FederationService,
+    }
+
+    /// Two relays configured as each other's siblings
+    async fn siblings() -> (Relay, Relay) {
+        let (a_id, b_id) = (PeerId::random(), PeerId::random());
+        let relay = |local: PeerId, sibling: PeerId| {
+            let db = RelayDatabase::open(":memory:").unwrap();
+            let addr = format!("/ip4/127.0.0.1/tcp/4001/p2p/{}", sibling);
+            let service =
+                FederationService::new(db.clone(), local, &[addr], Arc::default()).unwrap();
+            Relay {
+                peer_id: local,
+                db,
+                service,
+            }
+        };
+        let (a, b) = (relay(a_id, b_id), relay(b_id, a_id));
+        a.service.init_status().await;
+        b.service.init_status().await;
+        (a, b)
+    }
+
+    fn author_key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    fn default_board(db: &RelayDatabase) -> String {
+        db.list_boards().unwrap().remove(0).board_id
+    }
+
+    /// Store a post by `author` with a valid signature
+    fn add_post(db: &RelayDatabase, author: &str, post_id: &str, created_at: i64) {
+        let key = author_key();
+        db.register_peer(author, key.verifying_key().as_bytes(), author)
+            .unwrap();
+        let board_id = default_board(db);
+        let signable = SignableBoardPost {
+            post_id,
+            board_id: &board_id,
+            author_peer_id: author,
+            content_type: "text",
+            content_text: Some("hello"),
+            lamport_clock: 1,
+            created_at,
+            reply_to_post_id: None,
+        };
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&signable, &mut bytes).unwrap();
+        let signature = key.sign(&bytes).to_bytes();
+        db.insert_post(
+            post_id,
+            &board_id,
+            author,
+            "text",
+            Some("hello"),
+            1,
+            created_at,
+            &signature,
+            None,
+            None,
+        )
+        .unwrap();
+    }
+
+    /// One pull of at most `limit` items by `to` from `from`
+    async fn pull(to: &Relay, from: &Relay, limit: u32) -> (bool, Vec<String>) {
+        let FederationRequest::Pull { cursor, .. } = to.service.create_pull_request(&from.peer_id);
+        // Treat every second as settled so posts stored just now are served
+        let response = from.service.build_batch(&cursor, limit, i64::MAX).unwrap();
+        to.service.apply_response(&from.peer_id, response).await
+    }
+
+    /// Pull in batches of `limit` until the sibling has nothing more
+    async fn pull_all(to: &Relay, from: &Relay, limit: u32) -> Vec<String> {
+        let mut changed = Vec::new();
+        loop {
+            let (has_more, batch) = pull(to, from, limit).await;
+            changed.extend(batch);
+            if !has_more {
+                return changed;
+            }
+        }
+    }
+
+    fn is_deleted(db: &RelayDatabase, post_id: &str) -> bool {
+        db.get_post(post_id).unwrap().unwrap().deleted_at.is_some()
+    }
+
+    fn now() -> i64 {
+        chrono::Utc::now().timestamp()
+    }
+
+    #[tokio::test]
+    async fn test_pull_pages_with_cursor() {
+        let (a, b) = siblings().await;
+        for i in 0..5 {
+            add_post(&b.db, "alice", &format!("post-{}", i), now());
+        }
+
+        let (has_more, first) = pull(&a, &b, 2).await;
+        assert!(has_more);
+        assert_eq!(first.len(), 2);
+
+        let mut mirrored = first;
+        mirrored.extend(pull_all(&a, &b, 2).await);
+        mirrored.sort();
+        assert_eq!(mirrored, ["post-0", "post-1", "post-2", "post-3", "post-4"]);
+
+        // The stored cursor makes the next pull empty
+        assert_eq!(pull(&a, &b, 2).await, (false, Vec::new()));
+        assert!(a.db.get_post("post-4").unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_backdated_posts_are_mirrored() {
+        let (a, b) = siblings().await;
+        add_post(&b.db, "alice", "post-1", now());
+        pull_all(&a, &b, 10).await;
+
+        // Signed an hour ago but only now stored on the sibling
+        add_post(&b.db, "alice", "post-2", now() - 3600);
+        assert_eq!(pull_all(&a, &b, 10).await, ["post-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_posts_wait_for_their_second_to_pass() {
+        let (a, b) = siblings().await;
+        let stored_at = now();
+        add_post(&b.db, "alice", "post-1", stored_at);
+
+        let FederationRequest::Pull { cursor, .. } = a.service.create_pull_request(&b.peer_id);
+        let FederationResponse::Batch { posts, .. } =
+            b.service.build_batch(&cursor, 10, stored_at).unwrap()
+        else {
+            panic!("expected a batch");
+        };
+        assert!(posts.is_empty());
+
+        assert_eq!(pull_all(&a, &b, 10).await, ["post-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_in_the_same_second_page_across_batches() {
+        let (a, b) = siblings().await;
+        for i in 0..3 {
+            add_post(&b.db, "alice", &format!("post-{}", i), now());
+        }
+        pull_all(&a, &b, 10).await;
+        for i in 0..3 {
+            assert!(b.db.apply_tombstone(&format!("post-{}", i), 1_000).unwrap());
+        }
+
+        let mut applied = pull_all(&a, &b, 2).await;
+        applied.sort();
+        assert_eq!(applied, ["post-0", "post-1", "post-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_known_posts_are_not_mirrored_twice() {
+        let (a, b) = siblings().await;
+        add_post(&b.db, "alice", "post-1", now());
+        add_post(&b.db, "alice", "post-2", now());
+        let row = b.db.get_post("post-1").unwrap().unwrap();
+        a.db.insert_mirrored_board(&row.board_id, "General", None, "b")
+            .unwrap();
+        a.db.insert_mirrored_post(&row, "b").unwrap();
+
+        assert_eq!(pull_all(&a, &b, 10).await, ["post-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_posts_with_bad_signatures_are_dropped() {
+        let (a, b) = siblings().await;
+        add_post(&b.db, "alice", "post-1", now());
+        // Registered under a different key than the one that signed
+        b.db.register_peer("mallory", &[1u8; 32], "Mallory")
+            .unwrap();
+        let mut forged = b.db.get_post("post-1").unwrap().unwrap();
+        forged.post_id = "forged".to_string();
+        forged.author_peer_id = "mallory".to_string();
+        b.db.insert_mirrored_post(&forged, "elsewhere").unwrap();
+
+        assert_eq!(pull_all(&a, &b, 10).await, ["post-1"]);
+        assert!(a.db.get_post("forged").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_propagate() {
+        let (a, b) = siblings().await;
+        add_post(&b.db, "alice", "post-1", now());
+        add_post(&b.db, "alice", "post-2", now());
+        pull_all(&a, &b, 10).await;
+
+        assert!(b.db.delete_post("post-1", "alice").unwrap());
+        assert_eq!(pull_all(&a, &b, 10).await, ["post-1"]);
+        assert!(is_deleted(&a.db, "post-1"));
+        assert!(!is_deleted(&a.db, "post-2"));
+
+        // Already applied
+        assert!(pull_all(&a, &b, 10).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sibling_bans_are_applied() {
+        let (a, b) = siblings().await;
+        b.db.ban_peer("spammer", "Spam", "admin").unwrap();
+        add_post(&b.db, "spammer", "spam", now());
+        add_post(&b.db, "mallory", "post-1", now());
+        a.db.ban_peer("mallory", "Abuse", "admin").unwrap();
+
+        assert!(pull_all(&a, &b, 10).await.is_empty());
+        assert!(a.db.is_peer_banned("spammer").unwrap());
+        // Banned here, so not mirrored even though the sibling serves it
+        assert!(a.db.get_post("post-1").unwrap().is_none());
+        assert!(a.db.get_post("spam").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pull_from_non_sibling_is_rejected() {
+        let (a, b) = siblings().await;
+        let stranger = PeerId::random();
+        let request = FederationRequest::Pull {
+            relay_peer_id: stranger.to_string(),
+            cursor: FederationCursor::default(),
+            limit: 10,
+        };
+        assert!(matches!(
+            b.service.process_pull(&stranger, request),
+            FederationResponse::Error { .. }
+        ));
+
+        // A sibling must not claim to be another relay
+        let request = FederationRequest::Pull {
+            relay_peer_id: stranger.to_string(),
+            cursor: FederationCursor::default(),
+            limit: 10,
+        };
+        assert!(matches!(
+            b.service.process_pull(&a.peer_id, request),
+            FederationResponse::Error { .. }
+        ));
+    }
+}
@@ -13,13 +13,19 @@
 mod auth;
 mod board_service;
 mod db;
+mod federation;
+mod rate_limit;
+mod subscriptions;
 
 use auth::AuthState;
-use axum::routing::post;
+use axum::routing::{get, post};
 use axum::Router;
 use board_service::BoardService;
 use clap::Parser;
 use db::RelayDatabase;
+use federation::{FederationRequest, FederationResponse, FederationService, FederationStatus};
+use rate_limit::{RateLimitConfig, RateLimiter};
+use subscriptions::{BoardNotification, BoardNotificationAck, Subscriptions};
 use futures::StreamExt;
 use libp2p::{
     identify, noise, ping, relay,
@@ -34,7 +40,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use tower_http::cors::CorsLayer;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 use tracing_subscriber::EnvFilter;
 
 /// Board sync protocol version
@@ -56,6 +62,14 @@ pub enum BoardSyncRequest {
         limit: u32,
         timestamp: i64,
         signature: Vec<u8>,
+        #[serde(default)]
+        mode: BoardPostsMode,
+    },
+    GetThread {
+        requester_peer_id: String,
+        root_post_id: String,
+        timestamp: i64,
+        signature: Vec<u8>,
     },
     SubmitPost {
         post_id: String,
@@ -66,6 +80,8 @@ pub enum BoardSyncRequest {
         lamport_clock: u64,
         created_at: i64,
         signature: Vec<u8>,
+        #[serde(default)]
+        reply_to_post_id: Option<String>,
     },
     RegisterPeer {
         peer_id: String,
@@ -80,6 +96,29 @@ pub enum BoardSyncRequest {
         timestamp: i64,
         signature: Vec<u8>,
     },
+    Subscribe {
+        requester_peer_id: String,
+        board_ids: Vec<String>,
+        timestamp: i64,
+        signature: Vec<u8>,
+    },
+    Unsubscribe {
+        requester_peer_id: String,
+        board_ids: Vec<String>,
+        timestamp: i64,
+        signature: Vec<u8>,
+    },
+}
+
+/// Which posts `GetBoardPosts` returns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardPostsMode {
+    /// Every post, replies included (incremental sync)
+    #[default]
+    Flat,
+    /// Top-level posts only, with reply counts
+    Threads,
 }
 
 /// Board info in responses
@@ -104,6 +143,40 @@ pub struct BoardPostInfoProto {
     pub created_at: i64,
     pub deleted_at: Option<i64>,
     pub signature: Vec<u8>,
+    /// When the relay stored the post; mirrored posts arrive after `created_at`.
+    /// Clients should advance their sync cursor on this value.
+    #[serde(default)]
+    pub received_at: Option<i64>,
+    #[serde(default)]
+    pub reply_to_post_id: Option<String>,
+    #[serde(default)]
+    pub thread_root_post_id: Option<String>,
+    /// Live replies in the thread rooted at this post (0 for replies)
+    #[serde(default)]
+    pub reply_count: u32,
+}
+
+/// Machine-readable reason attached to `BoardSyncResponse::Error`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardSyncErrorCode {
+    /// Per-peer rate limit for this operation exceeded
+    RateLimited,
+    /// Posts-per-hour quota exceeded
+    QuotaExceeded,
+    /// Post content exceeds the relay's size limit
+    PayloadTooLarge,
+    /// The relay's global request budget is exhausted
+    Overloaded,
+    /// The peer is banned from this relay
+    Banned,
+    /// Isnad verification is required for this operation
+    VerificationRequired,
+    /// The request was signed for a different peer than the one sending it
+    PermissionDenied,
+    /// A code this build doesn't know about
+    #[serde(other)]
+    Unknown,
 }
 
 /// Board sync response (wire protocol)
@@ -118,11 +191,45 @@ pub enum BoardSyncResponse {
         board_id: String,
         posts: Vec<BoardPostInfoProto>,
         has_more: bool,
+        #[serde(default)]
+        mode: BoardPostsMode,
+    },
+    /// A thread root followed by its replies, oldest first
+    ThreadPosts {
+        board_id: String,
+        root_post_id: String,
+        posts: Vec<BoardPostInfoProto>,
     },
     PostAccepted { post_id: String },
     PeerRegistered { peer_id: String },
     PostDeleted { post_id: String },
-    Error { error: String },
+    /// The requester's full set of board subscriptions after the change
+    Subscribed { board_ids: Vec<String> },
+    Error {
+        error: String,
+        #[serde(default)]
+        code: Option<BoardSyncErrorCode>,
+        #[serde(default)]
+        retry_after_secs: Option<u64>,
+    },
+}
+
+impl BoardSyncResponse {
+    fn error(error: impl Into<String>) -> Self {
+        Self::Error {
+            error: error.into(),
+            code: None,
+            retry_after_secs: None,
+        }
+    }
+
+    fn denied(error: impl Into<String>) -> Self {
+        Self::Error {
+            error: error.into(),
+            code: Some(BoardSyncErrorCode::PermissionDenied),
+            retry_after_secs: None,
+        }
+    }
 }
 
 /// Bastion Relay - Isnad-verified relay for autonomous agents
@@ -165,7 +272,7 @@ struct Args {
     #[arg(long, default_value_t = false)]
     enclave: bool,
 
-    /// Directory for SQLite database storage (only used with --enclave)
+    /// Directory for SQLite database storage (relay.db in enclave mode, auth.db otherwise)
     #[arg(long)]
     data_dir: Option<String>,
 
@@ -176,6 +283,21 @@ struct Args {
     /// Disable Isnad CAPTCHA auth requirement (for testing)
     #[arg(long)]
     no_auth: bool,
+
+    /// Bearer token for operator endpoints (/auth/tokens, peer revocation)
+    #[arg(long)]
+    admin_token: Option<String>,
+
+    #[command(flatten)]
+    rate_limits: RateLimitConfig,
+
+    /// Sibling relay to mirror enclave boards with (repeatable, must include /p2p/<peer_id>)
+    #[arg(long = "federate")]
+    federate: Vec<String>,
+
+    /// Seconds between federation pulls from each sibling
+    #[arg(long, default_value_t = 30)]
+    federation_interval_secs: u64,
 }
 
 /// Combined behaviour for the relay server
@@ -185,6 +307,8 @@ struct RelayServerBehaviour {
     ping: ping::Behaviour,
     identify: identify::Behaviour,
     board_sync: Toggle<request_response::cbor::Behaviour<BoardSyncRequest, BoardSyncResponse>>,
+    federation: Toggle<request_response::cbor::Behaviour<FederationRequest, FederationResponse>>,
+    board_notify: Toggle<request_response::cbor::Behaviour<BoardNotification, BoardNotificationAck>>,
 }
 
 fn default_identity_path() -> String {
@@ -195,6 +319,17 @@ fn default_identity_path() -> String {
         .to_string()
 }
 
+fn resolve_data_dir(data_dir: Option<&str>) -> Result<PathBuf, std::io::Error> {
+    let dir = match data_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".config/bastion-relay"),
+    };
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
 fn load_or_generate_identity(path: &str) -> Result<Keypair, Box<dyn std::error::Error>> {
     let path = PathBuf::from(path);
 
@@ -227,12 +362,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Warn if enclave-only options are used without --enclave
     if !args.enclave {
-        if args.data_dir.is_some() {
-            warn!("--data-dir has no effect without --enclave");
-        }
         if args.enclave_name != "Bastion Enclave" {
             warn!("--enclave-name has no effect without --enclave");
         }
+        if !args.federate.is_empty() {
+            warn!("--federate has no effect without --enclave");
+        }
     }
 
     info!("Starting Bastion Relay...");
@@ -248,15 +383,41 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("Max reservations: {}", args.max_reservations);
     info!("Max circuits per peer: {}", args.max_circuits_per_peer);
 
+    // -- Open storage --
+    // Enclave mode keeps boards and auth state in relay.db; relay-only mode
+    // persists auth state alone in auth.db.
+    let data_dir = resolve_data_dir(args.data_dir.as_deref())?;
+    let (auth_db, board_service) = if args.enclave {
+        let db_path = data_dir.join("relay.db").display().to_string();
+        let relay_db = RelayDatabase::open(&db_path)?;
+        let service = BoardService::new(relay_db.clone(), args.enclave_name.clone());
+        info!("Database initialized at {}", db_path);
+        (relay_db, Some(service))
+    } else {
+        let db_path = data_dir.join("auth.db").display().to_string();
+        (RelayDatabase::open_auth_store(&db_path)?, None)
+    };
+
     // -- Start HTTP auth sidecar --
-    let auth_state = Arc::new(AuthState::new());
+    let auth_state = Arc::new(AuthState::new(auth_db, args.admin_token.clone()));
+    if args.admin_token.is_none() {
+        info!("Operator endpoints disabled (no --admin-token)");
+    }
+
+    let federation_status = Arc::new(FederationStatus::default());
+    let federation_router = Router::new()
+        .route("/federation/status", get(federation::federation_status))
+        .with_state(federation_status.clone());
 
     let auth_router = Router::new()
         .route("/auth/challenge", post(auth::request_challenge))
         .route("/auth/verify", post(auth::verify_challenge))
         .route("/auth/check", post(auth::check_token))
-        .layer(CorsLayer::permissive())
-        .with_state(auth_state.clone());
+        .route("/auth/revoke", post(auth::revoke_token))
+        .route("/auth/tokens", get(auth::list_tokens))
+        .with_state(auth_state.clone())
+        .merge(federation_router)
+        .layer(CorsLayer::permissive());
 
     let auth_addr = format!("{}:{}", args.auth_bind, args.auth_port);
     let auth_listener = tokio::net::TcpListener::bind(&auth_addr).await?;
@@ -266,30 +427,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         axum::serve(auth_listener, auth_router).await.ok();
     });
 
+    // Prune expired challenges and tokens in the background
+    let cleanup_state = auth_state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            cleanup_state.cleanup();
+        }
+    });
+
     let keypair = load_or_generate_identity(&args.identity_key_path)?;
     info!("Using identity key at {}", args.identity_key_path);
 
-    // Initialize database and board service only in enclave mode
-    let board_service: Option<BoardService> = if args.enclave {
-        let db_path = if let Some(ref data_dir) = args.data_dir {
-            fs::create_dir_all(data_dir)?;
-            format!("{}/relay.db", data_dir)
-        } else {
-            let default_dir = dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join(".config/bastion-relay");
-            fs::create_dir_all(&default_dir)?;
-            default_dir.join("relay.db").display().to_string()
-        };
-
-        let relay_db = RelayDatabase::open(&db_path)?;
-        let service = BoardService::new(relay_db, args.enclave_name.clone());
-        info!("Database initialized at {}", db_path);
-        Some(service)
-    } else {
-        None
-    };
-
     let enclave_mode = args.enclave;
 
     // Build the swarm
@@ -339,11 +489,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 Toggle::from(None)
             };
 
+            // Federation with sibling relays (only in enclave mode)
+            let federation = if enclave_mode {
+                Toggle::from(Some(request_response::cbor::Behaviour::new(
+                    [(
+                        StreamProtocol::new(federation::FEDERATION_PROTOCOL),
+                        ProtocolSupport::Full,
+                    )],
+                    request_response::Config::default(),
+                )))
+            } else {
+                Toggle::from(None)
+            };
+
+            // Push notifications to board subscribers (only in enclave mode)
+            let board_notify = if enclave_mode {
+                Toggle::from(Some(request_response::cbor::Behaviour::new(
+                    [(
+                        StreamProtocol::new(subscriptions::BOARD_NOTIFY_PROTOCOL),
+                        ProtocolSupport::Outbound,
+                    )],
+                    request_response::Config::default(),
+                )))
+            } else {
+                Toggle::from(None)
+            };
+
             RelayServerBehaviour {
                 relay,
                 ping,
                 identify,
                 board_sync,
+                federation,
+                board_notify,
             }
         })?
         .with_swarm_config(|cfg| cfg.with_idle_connection_timeout(Duration::from_secs(365 * 24 * 60 * 60)))
@@ -391,11 +569,55 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         info!("========================================");
     }
 
+    // -- Federation with sibling relays --
+    let federation_service = match (args.enclave, board_service.as_ref()) {
+        (true, Some(service)) if !args.federate.is_empty() => {
+            let federation = FederationService::new(
+                service.db().clone(),
+                local_peer_id,
+                &args.federate,
+                federation_status.clone(),
+            )?;
+            federation.init_status().await;
+            for (sibling, addr) in federation.siblings() {
+                info!("Federating with sibling relay {} at {}", sibling, addr);
+                if let Err(e) = swarm.dial(addr.clone()) {
+                    warn!("Failed to dial sibling relay {}: {}", sibling, e);
+                }
+            }
+            Some(federation)
+        }
+        _ => None,
+    };
+    let mut federation_tick =
+        tokio::time::interval(Duration::from_secs(args.federation_interval_secs.max(1)));
+
     let no_auth = args.no_auth;
+    let mut rate_limiter = RateLimiter::new(args.rate_limits.clone());
+    let mut subscriptions = Subscriptions::default();
 
     // Run the event loop
     loop {
-        match swarm.select_next_some().await {
+        let event = tokio::select! {
+            event = swarm.select_next_some() => event,
+            _ = federation_tick.tick() => {
+                if let Some(ref federation) = federation_service {
+                    for (sibling, addr) in federation.siblings() {
+                        if federation.is_connected(sibling).await {
+                            let request = federation.create_pull_request(sibling);
+                            if let Some(behaviour) = swarm.behaviour_mut().federation.as_mut() {
+                                behaviour.send_request(sibling, request);
+                            }
+                        } else if let Err(e) = swarm.dial(addr.clone()) {
+                            warn!("Failed to redial sibling relay {}: {}", sibling, e);
+                        }
+                    }
+                }
+                continue;
+            }
+        };
+
+        match event {
             SwarmEvent::NewListenAddr { address, .. } => {
                 info!("Listening on: {}/p2p/{}", address, local_peer_id);
             }
@@ -404,7 +626,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             )) => {
                 if no_auth {
                     info!("Relay reservation accepted for {} (auth disabled)", src_peer_id);
-                } else if auth_state.is_peer_verified(&src_peer_id.to_string()).await {
+                } else if auth_state.is_peer_verified(&src_peer_id.to_string()) {
                     info!("Relay reservation accepted for {} (Isnad verified)", src_peer_id);
                 } else {
                     info!(
@@ -430,15 +652,54 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     request, channel, ..
                 } => {
                     if let Some(ref service) = board_service {
-                        // Check auth for write operations (submit/delete)
-                        let response = if !no_auth && requires_auth(&request) && !auth_state.is_peer_verified(&peer.to_string()).await {
+                        let response = if service.is_peer_banned(&peer.to_string()) {
+                            BoardSyncResponse::Error {
+                                error: "Peer is banned".to_string(),
+                                code: Some(BoardSyncErrorCode::Banned),
+                                retry_after_secs: None,
+                            }
+                        } else if let Err(rejection) = rate_limiter.check(&peer, &request) {
+                            warn!("Rate limited board request from {}: {}", peer, rejection.message);
+                            if rejection.ban {
+                                warn!("Auto-banning repeat offender {}", peer);
+                                if let Err(e) = service.process_ban_peer(
+                                    &peer.to_string(),
+                                    "Repeated rate limit violations",
+                                ) {
+                                    warn!("Failed to ban {}: {}", peer, e);
+                                }
+                                rate_limiter.forget(&peer);
+                            }
+                            BoardSyncResponse::Error {
+                                error: rejection.message,
+                                code: Some(rejection.code),
+                                retry_after_secs: rejection.retry_after_secs,
+                            }
+                        } else if !no_auth && requires_auth(&request) && !auth_state.is_peer_verified(&peer.to_string()) {
+                            // Write operations require Isnad verification
                             warn!("Rejecting board request from unverified peer {}", peer);
                             BoardSyncResponse::Error {
                                 error: "Isnad CAPTCHA verification required. POST to /auth/challenge first.".to_string(),
+                                code: Some(BoardSyncErrorCode::VerificationRequired),
+                                retry_after_secs: None,
                             }
                         } else {
-                            handle_board_request(service, &local_peer_id, &peer, request)
+                            handle_board_request(
+                                service,
+                                &mut subscriptions,
+                                &local_peer_id,
+                                &peer,
+                                request,
+                            )
                         };
+                        let changed_post = match &response {
+                            BoardSyncResponse::PostAccepted { post_id }
+                            | BoardSyncResponse::PostDeleted { post_id } => Some(post_id.clone()),
+                            _ => None,
+                        };
+                        if matches!(response, BoardSyncResponse::PostAccepted { .. }) {
+                            rate_limiter.record_post(&peer);
+                        }
                         if let Err(e) = swarm
                             .behaviour_mut()
                             .board_sync
@@ -448,17 +709,80 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         {
                             warn!("Failed to send board sync response: {:?}", e);
                         }
+                        if let Some(post_id) = changed_post {
+                            notify_subscribers(&mut swarm, &subscriptions, service.db(), &[post_id]);
+                        }
                     }
                 }
                 request_response::Message::Response { .. } => {
                     // Relay server doesn't send requests, so we shouldn't get responses
                 }
             },
+            SwarmEvent::Behaviour(RelayServerBehaviourEvent::Federation(
+                request_response::Event::Message { peer, message, .. },
+            )) => {
+                let Some(ref federation) = federation_service else {
+                    continue;
+                };
+                match message {
+                    request_response::Message::Request {
+                        request, channel, ..
+                    } => {
+                        let response = federation.process_pull(&peer, request);
+                        if let Some(behaviour) = swarm.behaviour_mut().federation.as_mut() {
+                            if let Err(e) = behaviour.send_response(channel, response) {
+                                warn!("Failed to send federation response: {:?}", e);
+                            }
+                        }
+                    }
+                    request_response::Message::Response { response, .. } => {
+                        let (has_more, changed) = federation.apply_response(&peer, response).await;
+                        if let Some(ref service) = board_service {
+                            notify_subscribers(&mut swarm, &subscriptions, service.db(), &changed);
+                        }
+                        if has_more {
+                            let request = federation.create_pull_request(&peer);
+                            if let Some(behaviour) = swarm.behaviour_mut().federation.as_mut() {
+                                behaviour.send_request(&peer, request);
+                            }
+                        }
+                    }
+                }
+            }
+            SwarmEvent::Behaviour(RelayServerBehaviourEvent::Federation(
+                request_response::Event::OutboundFailure { peer, error, .. },
+            )) => {
+                if let Some(ref federation) = federation_service {
+                    federation.record_error(&peer, error.to_string()).await;
+                }
+            }
+            SwarmEvent::Behaviour(RelayServerBehaviourEvent::BoardNotify(
+                request_response::Event::OutboundFailure { peer, error, .. },
+            )) => {
+                debug!("Board notification to {} failed: {}", peer, error);
+            }
             SwarmEvent::ConnectionEstablished { peer_id, connection_id, endpoint, .. } => {
                 info!("Connection established with: {} via {:?} ({:?})", peer_id, connection_id, endpoint);
+                if let Some(ref federation) = federation_service {
+                    if federation.is_sibling(&peer_id) {
+                        federation.set_connected(&peer_id, true).await;
+                        let request = federation.create_pull_request(&peer_id);
+                        if let Some(behaviour) = swarm.behaviour_mut().federation.as_mut() {
+                            behaviour.send_request(&peer_id, request);
+                        }
+                    }
+                }
             }
-            SwarmEvent::ConnectionClosed { peer_id, connection_id, cause, endpoint, .. } => {
+            SwarmEvent::ConnectionClosed { peer_id, connection_id, cause, endpoint, num_established, .. } => {
                 info!("Connection closed with: {} via {:?} ({:?}), cause: {:?}", peer_id, connection_id, endpoint, cause);
+                if num_established == 0 {
+                    subscriptions.remove_peer(&peer_id);
+                }
+                if let Some(ref federation) = federation_service {
+                    if num_established == 0 && federation.is_sibling(&peer_id) {
+                        federation.set_connected(&peer_id, false).await;
+                    }
+                }
             }
             _ => {}
         }
@@ -475,8 +799,29 @@ fn requires_auth(request: &BoardSyncRequest) -> bool {
     )
 }
 
+/// Push changed posts to every subscriber of their boards
+fn notify_subscribers(
+    swarm: &mut libp2p::Swarm<RelayServerBehaviour>,
+    subscriptions: &Subscriptions,
+    db: &RelayDatabase,
+    post_ids: &[String],
+) {
+    if post_ids.is_empty() || subscriptions.is_empty() {
+        return;
+    }
+    let Some(behaviour) = swarm.behaviour_mut().board_notify.as_mut() else {
+        return;
+    };
+    for notification in subscriptions::notifications_for(db, post_ids) {
+        for subscriber in subscriptions.subscribers(notification.board_id()) {
+            behaviour.send_request(&subscriber, notification.clone());
+        }
+    }
+}
+
 fn handle_board_request(
     service: &BoardService,
+    subscriptions: &mut Subscriptions,
     local_peer_id: &PeerId,
     peer: &PeerId,
     request: BoardSyncRequest,
@@ -489,13 +834,11 @@ fn handle_board_request(
             ..
         } => {
             if peer_id != peer.to_string() {
-                return BoardSyncResponse::Error {
-                    error: "peer_id mismatch".to_string(),
-                };
+                return BoardSyncResponse::denied("peer_id mismatch");
             }
             match service.process_register_peer(&peer_id, &public_key, &display_name) {
                 Ok(()) => BoardSyncResponse::PeerRegistered { peer_id },
-                Err(e) => BoardSyncResponse::Error { error: e },
+                Err(e) => BoardSyncResponse::error(e),
             }
         }
         BoardSyncRequest::ListBoards { .. } => match service.process_list_boards() {
@@ -514,35 +857,33 @@ fn handle_board_request(
                     relay_peer_id: local_peer_id.to_string(),
                 }
             },
-            Err(e) => BoardSyncResponse::Error { error: e },
+            Err(e) => BoardSyncResponse::error(e),
         },
         BoardSyncRequest::GetBoardPosts {
             board_id,
             after_timestamp,
             limit,
+            mode,
             ..
-        } => match service.process_get_board_posts(&board_id, after_timestamp, limit) {
+        } => match service.process_get_board_posts(&board_id, after_timestamp, limit, mode) {
             Ok((posts, has_more)) => BoardSyncResponse::BoardPosts {
                 board_id,
-                posts: posts
-                    .into_iter()
-                    .map(|p| BoardPostInfoProto {
-                        post_id: p.post_id,
-                        board_id: p.board_id,
-                        author_peer_id: p.author_peer_id,
-                        author_display_name: p.author_display_name,
-                        content_type: p.content_type,
-                        content_text: p.content_text,
-                        lamport_clock: p.lamport_clock,
-                        created_at: p.created_at,
-                        deleted_at: p.deleted_at,
-                        signature: p.signature,
-                    })
-                    .collect(),
+                posts: posts.into_iter().map(BoardPostInfoProto::from).collect(),
                 has_more,
+                mode,
             },
-            Err(e) => BoardSyncResponse::Error { error: e },
+            Err(e) => BoardSyncResponse::error(e),
         },
+        BoardSyncRequest::GetThread { root_post_id, .. } => {
+            match service.process_get_thread(&root_post_id) {
+                Ok((board_id, root_post_id, posts)) => BoardSyncResponse::ThreadPosts {
+                    board_id,
+                    root_post_id,
+                    posts: posts.into_iter().map(BoardPostInfoProto::from).collect(),
+                },
+                Err(e) => BoardSyncResponse::error(e),
+            }
+        }
         BoardSyncRequest::SubmitPost {
             post_id,
             board_id,
@@ -552,11 +893,10 @@ fn handle_board_request(
             lamport_clock,
             created_at,
             signature,
+            reply_to_post_id,
         } => {
             if author_peer_id != peer.to_string() {
-                return BoardSyncResponse::Error {
-                    error: "author_peer_id mismatch".to_string(),
-                };
+                return BoardSyncResponse::denied("author_peer_id mismatch");
             }
             match service.process_submit_post(
                 &post_id,
@@ -567,9 +907,10 @@ fn handle_board_request(
                 lamport_clock,
                 created_at,
                 &signature,
+                reply_to_post_id.as_deref(),
             ) {
                 Ok(()) => BoardSyncResponse::PostAccepted { post_id },
-                Err(e) => BoardSyncResponse::Error { error: e },
+                Err(e) => BoardSyncResponse::error(e),
             }
         }
         BoardSyncRequest::DeletePost {
@@ -578,14 +919,25 @@ fn handle_board_request(
             ..
         } => {
             if author_peer_id != peer.to_string() {
-                return BoardSyncResponse::Error {
-                    error: "author_peer_id mismatch".to_string(),
-                };
+                return BoardSyncResponse::denied("author_peer_id mismatch");
             }
             match service.process_delete_post(&post_id, &author_peer_id) {
                 Ok(()) => BoardSyncResponse::PostDeleted { post_id },
-                Err(e) => BoardSyncResponse::Error { error: e },
+                Err(e) => BoardSyncResponse::error(e),
             }
         }
+        BoardSyncRequest::Subscribe { board_ids, .. } => {
+            let board_ids = service.existing_boards(board_ids);
+            match subscriptions.subscribe(*peer, board_ids) {
+                Ok(board_ids) => {
+                    info!("Peer {} subscribed to {} board(s)", peer, board_ids.len());
+                    BoardSyncResponse::Subscribed { board_ids }
+                }
+                Err(e) => BoardSyncResponse::error(e),
+            }
+        }
+        BoardSyncRequest::Unsubscribe { board_ids, .. } => BoardSyncResponse::Subscribed {
+            board_ids: subscriptions.unsubscribe(peer, &board_ids),
+        },
     }
 }
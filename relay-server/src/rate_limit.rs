@@ -0,0 +1,489 @@
+//! Per-peer rate limiting and quotas for the board protocol
+//!
+//! Every board request passes through `RateLimiter::check` before it reaches
+//! the board service. Limits are token buckets keyed by (peer, operation),
+//! plus a relay-wide bucket, an hourly post quota per peer and a maximum post
+//! size. Per-peer limits are checked first, so one peer's rejected requests
+//! don't drain the relay-wide budget. Only posts the board service accepts
+//! count against the quota, via `RateLimiter::record_post`. Peers that keep
+//! tripping limits are reported as repeat offenders so the caller can ban them.
+
+use crate::{BoardSyncErrorCode, BoardSyncRequest};
+use libp2p::PeerId;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// How often idle buckets and stale offence records are pruned
+const PRUNE_INTERVAL: Duration = Duration::from_secs(300);
+/// Sliding window for the posts-per-hour quota
+const QUOTA_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Rate limit configuration (exposed as relay CLI flags)
+#[derive(clap::Args, Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Board read requests (ListBoards, GetBoardPosts, GetThread) per peer per minute
+    #[arg(long, default_value_t = 120)]
+    pub rate_read_per_min: u32,
+
+    /// SubmitPost requests per peer per minute
+    #[arg(long, default_value_t = 10)]
+    pub rate_submit_per_min: u32,
+
+    /// RegisterPeer requests per peer per minute
+    #[arg(long, default_value_t = 5)]
+    pub rate_register_per_min: u32,
+
+    /// DeletePost requests per peer per minute
+    #[arg(long, default_value_t = 20)]
+    pub rate_delete_per_min: u32,
+
+    /// Board requests per second across all peers
+    #[arg(long, default_value_t = 200)]
+    pub global_requests_per_sec: u32,
+
+    /// Maximum size of a post's content_text in bytes
+    #[arg(long, default_value_t = 8192)]
+    pub max_post_bytes: usize,
+
+    /// Maximum accepted posts per peer per hour
+    #[arg(long, default_value_t = 60)]
+    pub max_posts_per_hour: u32,
+
+    /// Ban a peer after this many rejected requests within the violation window (0 = never)
+    #[arg(long, default_value_t = 50)]
+    pub ban_after_violations: u32,
+
+    /// Window in seconds over which violations are counted
+    #[arg(long, default_value_t = 600)]
+    pub violation_window_secs: u64,
+}
+
+/// Board operations that are limited independently
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoardOp {
+    Read,
+    Submit,
+    Register,
+    Delete,
+}
+
+impl BoardOp {
+    pub fn of(request: &BoardSyncRequest) -> Self {
+        match request {
+            BoardSyncRequest::ListBoards { .. }
+            | BoardSyncRequest::GetBoardPosts { .. }
+            | BoardSyncRequest::GetThread { .. }
+            | BoardSyncRequest::Subscribe { .. }
+            | BoardSyncRequest::Unsubscribe { .. } => Self::Read,
+            BoardSyncRequest::SubmitPost { .. } => Self::Submit,
+            BoardSyncRequest::RegisterPeer { .. } => Self::Register,
+            BoardSyncRequest::DeletePost { .. } => Self::Delete,
+        }
+    }
+}
+
+/// Why a request was rejected
+#[derive(Debug, Clone)]
+pub struct Rejection {
+    pub code: BoardSyncErrorCode,
+    pub message: String,
+    pub retry_after_secs: Option<u64>,
+    /// Set once the peer crosses the violation threshold and should be banned
+    pub ban: bool,
+}
+
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u32, refill_per_sec: f64, now: Instant) -> Self {
+        Self {
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            refill_per_sec,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Take one token, or return the seconds until one is available
+    fn try_take(&mut self, now: Instant) -> Result<(), u64> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else if self.refill_per_sec > 0.0 {
+            Err(((1.0 - self.tokens) / self.refill_per_sec).ceil() as u64)
+        } else {
+            Err(u64::MAX)
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.tokens >= self.capacity
+    }
+}
+
+/// Rate limiter state for the board protocol
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: HashMap<(PeerId, BoardOp), TokenBucket>,
+    global: TokenBucket,
+    /// Accepted post times per peer, for the hourly quota
+    post_history: HashMap<PeerId, VecDeque<Instant>>,
+    /// Rejection times per peer, for auto-ban
+    violations: HashMap<PeerId, VecDeque<Instant>>,
+    last_prune: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let now = Instant::now();
+        let global = TokenBucket::new(
+            config.global_requests_per_sec,
+            config.global_requests_per_sec as f64,
+            now,
+        );
+        Self {
+            config,
+            buckets: HashMap::new(),
+            global,
+            post_history: HashMap::new(),
+            violations: HashMap::new(),
+            last_prune: now,
+        }
+    }
+
+    fn per_minute(&self, op: BoardOp) -> u32 {
+        match op {
+            BoardOp::Read => self.config.rate_read_per_min,
+            BoardOp::Submit => self.config.rate_submit_per_min,
+            BoardOp::Register => self.config.rate_register_per_min,
+            BoardOp::Delete => self.config.rate_delete_per_min,
+        }
+    }
+
+    /// Check a request against all limits. Posts only count against the
+    /// hourly quota once accepted; see `record_post`.
+    pub fn check(&mut self, peer: &PeerId, request: &BoardSyncRequest) -> Result<(), Rejection> {
+        self.check_at(peer, request, Instant::now())
+    }
+
+    fn check_at(
+        &mut self,
+        peer: &PeerId,
+        request: &BoardSyncRequest,
+        now: Instant,
+    ) -> Result<(), Rejection> {
+        if now.duration_since(self.last_prune) >= PRUNE_INTERVAL {
+            self.prune(now);
+        }
+
+        let result = self.evaluate(peer, request, now);
+        if let Err(mut rejection) = result {
+            // An overloaded relay isn't the peer's fault
+            if rejection.code != BoardSyncErrorCode::Overloaded {
+                rejection.ban = self.record_violation(peer, now);
+            }
+            return Err(rejection);
+        }
+        Ok(())
+    }
+
+    /// Count a post the board service accepted against the peer's hourly quota
+    pub fn record_post(&mut self, peer: &PeerId) {
+        self.record_post_at(peer, Instant::now());
+    }
+
+    fn record_post_at(&mut self, peer: &PeerId, now: Instant) {
+        self.post_history.entry(*peer).or_default().push_back(now);
+    }
+
+    fn evaluate(
+        &mut self,
+        peer: &PeerId,
+        request: &BoardSyncRequest,
+        now: Instant,
+    ) -> Result<(), Rejection> {
+        if let BoardSyncRequest::SubmitPost {
+            content_text: Some(text),
+            ..
+        } = request
+        {
+            if text.len() > self.config.max_post_bytes {
+                return Err(Rejection {
+                    code: BoardSyncErrorCode::PayloadTooLarge,
+                    message: format!(
+                        "Post is {} bytes, maximum is {}",
+                        text.len(),
+                        self.config.max_post_bytes
+                    ),
+                    retry_after_secs: None,
+                    ban: false,
+                });
+            }
+        }
+
+        let op = BoardOp::of(request);
+        let per_minute = self.per_minute(op);
+        let bucket = self
+            .buckets
+            .entry((*peer, op))
+            .or_insert_with(|| TokenBucket::new(per_minute, per_minute as f64 / 60.0, now));
+        if let Err(retry) = bucket.try_take(now) {
+            return Err(Rejection {
+                code: BoardSyncErrorCode::RateLimited,
+                message: format!("Rate limit exceeded ({} per minute)", per_minute),
+                retry_after_secs: Some(retry),
+                ban: false,
+            });
+        }
+
+        if op == BoardOp::Submit {
+            let max_posts = self.config.max_posts_per_hour as usize;
+            let history = self.post_history.entry(*peer).or_default();
+            while history
+                .front()
+                .is_some_and(|t| now.duration_since(*t) >= QUOTA_WINDOW)
+            {
+                history.pop_front();
+            }
+            if history.len() >= max_posts {
+                let retry = history
+                    .front()
+                    .map(|t| QUOTA_WINDOW.saturating_sub(now.duration_since(*t)).as_secs())
+                    .unwrap_or(QUOTA_WINDOW.as_secs());
+                return Err(Rejection {
+                    code: BoardSyncErrorCode::QuotaExceeded,
+                    message: format!("Post quota exceeded ({} per hour)", max_posts),
+                    retry_after_secs: Some(retry),
+                    ban: false,
+                });
+            }
+        }
+
+        if let Err(retry) = self.global.try_take(now) {
+            return Err(Rejection {
+                code: BoardSyncErrorCode::Overloaded,
+                message: "Relay is over its request budget, try again shortly".to_string(),
+                retry_after_secs: Some(retry),
+                ban: false,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Record a rejection and report whether the peer should now be banned
+    fn record_violation(&mut self, peer: &PeerId, now: Instant) -> bool {
+        if self.config.ban_after_violations == 0 {
+            return false;
+        }
+        let window = Duration::from_secs(self.config.violation_window_secs);
+        let history = self.violations.entry(*peer).or_default();
+        while history
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= window)
+        {
+            history.pop_front();
+        }
+        history.push_back(now);
+        history.len() >= self.config.ban_after_violations as usize
+    }
+
+    /// Forget all state for a peer (after banning)
+    pub fn forget(&mut self, peer: &PeerId) {
+        self.buckets.retain(|(p, _), _| p != peer);
+        self.post_history.remove(peer);
+        self.violations.remove(peer);
+    }
+
+    fn prune(&mut self, now: Instant) {
+        let window = Duration::from_secs(self.config.violation_window_secs);
+        for bucket in self.buckets.values_mut() {
+            bucket.refill(now);
+        }
+        self.buckets.retain(|_, b| !b.is_full());
+        self.post_history.retain(|_, h| {
+            h.back()
+                .is_some_and(|t| now.duration_since(*t) < QUOTA_WINDOW)
+        });
+        self.violations
+            .retain(|_, h| h.back().is_some_and(|t| now.duration_since(*t) < window));
+        self.last_prune = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> RateLimitConfig {
+        RateLimitConfig {
+            rate_read_per_min: 120,
+            rate_submit_per_min: 100,
+            rate_register_per_min: 5,
+            rate_delete_per_min: 20,
+            global_requests_per_sec: 200,
+            max_post_bytes: 16,
+            max_posts_per_hour: 60,
+            ban_after_violations: 0,
+            violation_window_secs: 600,
+        }
+    }
+
+    fn read() -> BoardSyncRequest {
+        BoardSyncRequest::ListBoards {
+            requester_peer_id: String::new(),
+            timestamp: 0,
+            signature: Vec::new(),
+        }
+    }
+
+    fn submit(text: &str) -> BoardSyncRequest {
+        BoardSyncRequest::SubmitPost {
+            post_id: "post".to_string(),
+            board_id: "board".to_string(),
+            author_peer_id: String::new(),
+            content_type: "text".to_string(),
+            content_text: Some(text.to_string()),
+            lamport_clock: 1,
+            created_at: 0,
+            signature: Vec::new(),
+            reply_to_post_id: None,
+        }
+    }
+
+    fn code(result: Result<(), Rejection>) -> Option<BoardSyncErrorCode> {
+        result.err().map(|r| r.code)
+    }
+
+    #[test]
+    fn test_token_bucket_refills_up_to_capacity() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2, 0.5, start);
+        assert!(bucket.try_take(start).is_ok());
+        assert!(bucket.try_take(start).is_ok());
+        assert_eq!(bucket.try_take(start), Err(2));
+
+        assert!(bucket.try_take(start + Duration::from_secs(2)).is_ok());
+        assert!(bucket.try_take(start + Duration::from_secs(2)).is_err());
+
+        let later = start + Duration::from_secs(3600);
+        assert!(bucket.try_take(later).is_ok());
+        assert!(bucket.try_take(later).is_ok());
+        assert!(bucket.try_take(later).is_err());
+    }
+
+    #[test]
+    fn test_per_peer_limits_do_not_drain_the_global_bucket() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            rate_read_per_min: 2,
+            global_requests_per_sec: 3,
+            ..config()
+        });
+        let now = Instant::now();
+        let (noisy, other, third) = (PeerId::random(), PeerId::random(), PeerId::random());
+
+        assert!(limiter.check_at(&noisy, &read(), now).is_ok());
+        assert!(limiter.check_at(&noisy, &read(), now).is_ok());
+        for _ in 0..5 {
+            assert_eq!(
+                code(limiter.check_at(&noisy, &read(), now)),
+                Some(BoardSyncErrorCode::RateLimited)
+            );
+        }
+
+        assert!(limiter.check_at(&other, &read(), now).is_ok());
+        assert_eq!(
+            code(limiter.check_at(&third, &read(), now)),
+            Some(BoardSyncErrorCode::Overloaded)
+        );
+    }
+
+    #[test]
+    fn test_quota_counts_only_accepted_posts() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            max_posts_per_hour: 2,
+            ..config()
+        });
+        let start = Instant::now();
+        let peer = PeerId::random();
+
+        // Checked but never accepted
+        for _ in 0..5 {
+            assert!(limiter.check_at(&peer, &submit("hi"), start).is_ok());
+        }
+        assert_eq!(
+            code(limiter.check_at(&peer, &submit("far too long for a post"), start)),
+            Some(BoardSyncErrorCode::PayloadTooLarge)
+        );
+
+        limiter.record_post_at(&peer, start);
+        limiter.record_post_at(&peer, start + Duration::from_secs(60));
+        let rejection = limiter
+            .check_at(&peer, &submit("hi"), start + Duration::from_secs(600))
+            .unwrap_err();
+        assert_eq!(rejection.code, BoardSyncErrorCode::QuotaExceeded);
+        assert_eq!(rejection.retry_after_secs, Some(3000));
+
+        // The first post has left the window
+        assert!(limiter
+            .check_at(&peer, &submit("hi"), start + QUOTA_WINDOW)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_repeat_offenders_are_banned() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            ban_after_violations: 3,
+            ..config()
+        });
+        let start = Instant::now();
+        let peer = PeerId::random();
+        let mut banned_at = |secs| {
+            let now = start + Duration::from_secs(secs);
+            let rejection = limiter
+                .check_at(&peer, &submit("far too long for a post"), now)
+                .unwrap_err();
+            rejection.ban
+        };
+
+        assert!(!banned_at(0));
+        assert!(!banned_at(1));
+        // Both earlier violations have left the window
+        assert!(!banned_at(700));
+        assert!(!banned_at(701));
+        assert!(banned_at(702));
+
+        // Forgetting a peer clears its violations
+        limiter.forget(&peer);
+        let now = start + Duration::from_secs(703);
+        let rejection = limiter
+            .check_at(&peer, &submit("far too long for a post"), now)
+            .unwrap_err();
+        assert!(!rejection.ban);
+    }
+
+    #[test]
+    fn test_no_bans_when_disabled() {
+        let mut limiter = RateLimiter::new(config());
+        let start = Instant::now();
+        let peer = PeerId::random();
+        for _ in 0..100 {
+            let rejection = limiter
+                .check_at(&peer, &submit("far too long for a post"), start)
+                .unwrap_err();
+            assert!(!rejection.ban);
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! Board subscriptions: push new posts to subscribed peers
+//!
+//! Peers subscribe to boards with `BoardSyncRequest::Subscribe`. Whenever the
+//! relay accepts, mirrors or deletes a post it sends a `BoardNotification` to
+//! every connected subscriber of that board over `/harbor/board-notify/1.0.0`.
+//! Subscriptions live only as long as the connection; clients resubscribe and
+//! catch up from their sync cursor after reconnecting.
+
+use crate::db::RelayDatabase;
+use crate::BoardPostInfoProto;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Board notification protocol version
+pub const BOARD_NOTIFY_PROTOCOL: &str = "/harbor/board-notify/1.0.0";
+
+/// Maximum boards a single peer may subscribe to
+pub const MAX_SUBSCRIPTIONS_PER_PEER: usize = 64;
+
+/// Relay-initiated board notification (wire protocol)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BoardNotification {
+    /// New posts on a board
+    Posts {
+        board_id: String,
+        posts: Vec<BoardPostInfoProto>,
+    },
+    /// A post on a board was deleted
+    PostDeleted {
+        board_id: String,
+        post_id: String,
+        deleted_at: i64,
+    },
+}
+
+/// Subscriber acknowledgement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardNotificationAck {
+    pub accepted: bool,
+}
+
+/// Active subscriptions, keyed by peer
+#[derive(Default)]
+pub struct Subscriptions {
+    by_peer: HashMap<PeerId, HashSet<String>>,
+}
+
+impl Subscriptions {
+    /// Add boards to a peer's subscriptions. Returns the peer's full set.
+    pub fn subscribe(&mut self, peer: PeerId, board_ids: Vec<String>) -> Result<Vec<String>, String> {
+        let boards = self.by_peer.entry(peer).or_default();
+        let new: HashSet<String> = board_ids
+            .into_iter()
+            .filter(|b| !boards.contains(b))
+            .collect();
+        if boards.len() + new.len() > MAX_SUBSCRIPTIONS_PER_PEER {
+            return Err(format!(
+                "Too many subscriptions (maximum {} boards)",
+                MAX_SUBSCRIPTIONS_PER_PEER
+            ));
+        }
+        boards.extend(new);
+        Ok(Self::sorted(boards))
+    }
+
+    /// Remove boards from a peer's subscriptions (all of them if `board_ids` is empty).
+    /// Returns the peer's remaining set.
+    pub fn unsubscribe(&mut self, peer: &PeerId, board_ids: &[String]) -> Vec<String> {
+        let Some(boards) = self.by_peer.get_mut(peer) else {
+            return Vec::new();
+        };
+        if board_ids.is_empty() {
+            boards.clear();
+        } else {
+            for board_id in board_ids {
+                boards.remove(board_id);
+            }
+        }
+        let remaining = Self::sorted(boards);
+        if remaining.is_empty() {
+            self.by_peer.remove(peer);
+        }
+        remaining
+    }
+
+    /// Drop everything for a peer (on disconnect)
+    pub fn remove_peer(&mut self, peer: &PeerId) {
+        self.by_peer.remove(peer);
+    }
+
+    /// Peers subscribed to a board
+    pub fn subscribers(&self, board_id: &str) -> Vec<PeerId> {
+        self.by_peer
+            .iter()
+            .filter(|(_, boards)| boards.contains(board_id))
+            .map(|(peer, _)| *peer)
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_peer.is_empty()
+    }
+
+    fn sorted(boards: &HashSet<String>) -> Vec<String> {
+        let mut list: Vec<String> = boards.iter().cloned().collect();
+        list.sort();
+        list
+    }
+}
+
+/// Build notifications for a set of changed posts, grouping new posts by board
+pub fn notifications_for(db: &RelayDatabase, post_ids: &[String]) -> Vec<BoardNotification> {
+    let mut posts_by_board: HashMap<String, Vec<BoardPostInfoProto>> = HashMap::new();
+    let mut notifications = Vec::new();
+
+    for post_id in post_ids {
+        let Ok(Some(post)) = db.get_post(post_id) else {
+            continue;
+        };
+        match post.deleted_at {
+            Some(deleted_at) => notifications.push(BoardNotification::PostDeleted {
+                board_id: post.board_id,
+                post_id: post.post_id,
+                deleted_at,
+            }),
+            None => posts_by_board
+                .entry(post.board_id.clone())
+                .or_default()
+                .push(BoardPostInfoProto::from(post)),
+        }
+    }
+
+    notifications.extend(
+        posts_by_board
+            .into_iter()
+            .map(|(board_id, posts)| BoardNotification::Posts { board_id, posts }),
+    );
+    notifications
+}
+
+impl BoardNotification {
+    pub fn board_id(&self) -> &str {
+        match self {
+            Self::Posts { board_id, .. } | Self::PostDeleted { board_id, .. } => board_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boards(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_subscribe_merges_and_sorts() {
+        let mut subscriptions = Subscriptions::default();
+        let peer = PeerId::random();
+        assert_eq!(
+            subscriptions
+                .subscribe(peer, boards(&["b", "a", "b"]))
+                .unwrap(),
+            ["a", "b"]
+        );
+        assert_eq!(
+            subscriptions.subscribe(peer, boards(&["c", "a"])).unwrap(),
+            ["a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn test_subscription_limit() {
+        let mut subscriptions = Subscriptions::default();
+        let peer = PeerId::random();
+        let full: Vec<String> = (0..MAX_SUBSCRIPTIONS_PER_PEER)
+            .map(|i| format!("board-{:02}", i))
+            .collect();
+        assert_eq!(
+            subscriptions.subscribe(peer, full.clone()).unwrap().len(),
+            MAX_SUBSCRIPTIONS_PER_PEER
+        );
+
+        // Boards already held don't count again; a new one is over the limit
+        assert!(subscriptions.subscribe(peer, full[..3].to_vec()).is_ok());
+        assert!(subscriptions.subscribe(peer, boards(&["extra"])).is_err());
+        assert!(subscriptions.subscribers("extra").is_empty());
+
+        // The limit is per peer
+        assert!(subscriptions
+            .subscribe(PeerId::random(), boards(&["extra"]))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_unsubscribe_and_disconnect() {
+        let mut subscriptions = Subscriptions::default();
+        let (alice, bob) = (PeerId::random(), PeerId::random());
+        subscriptions
+            .subscribe(alice, boards(&["a", "b", "c"]))
+            .unwrap();
+        subscriptions.subscribe(bob, boards(&["a"])).unwrap();
+
+        assert_eq!(
+            subscriptions.unsubscribe(&alice, &boards(&["b"])),
+            ["a", "c"]
+        );
+        assert!(subscriptions.subscribers("b").is_empty());
+
+        // An empty list drops every board
+        assert!(subscriptions.unsubscribe(&alice, &[]).is_empty());
+        assert_eq!(subscriptions.subscribers("a"), [bob]);
+        assert!(subscriptions
+            .unsubscribe(&alice, &boards(&["a"]))
+            .is_empty());
+
+        // Subscriptions end with the connection
+        subscriptions.remove_peer(&bob);
+        assert!(subscriptions.subscribers("a").is_empty());
+        assert!(subscriptions.is_empty());
+    }
+
+    #[test]
+    fn test_notifications_group_posts_by_board() {
+        let db = RelayDatabase::open(":memory:").unwrap();
+        let general = db.list_boards().unwrap().remove(0).board_id;
+        db.insert_mirrored_board("other", "Other", None, "sibling")
+            .unwrap();
+        let add = |post_id: &str, board_id: &str| {
+            db.insert_post(
+                post_id,
+                board_id,
+                "alice",
+                "text",
+                None,
+                1,
+                0,
+                &[],
+                None,
+                None,
+            )
+            .unwrap();
+        };
+        add("p1", &general);
+        add("p2", &general);
+        add("p3", "other");
+        add("p4", "other");
+        assert!(db.delete_post("p4", "alice").unwrap());
+
+        let ids = boards(&["p1", "p2", "p3", "p4", "missing"]);
+        let mut notifications = notifications_for(&db, &ids);
+        assert_eq!(notifications.len(), 3);
+        notifications.sort_by_key(|n| matches!(n, BoardNotification::PostDeleted { .. }));
+
+        let mut posted: Vec<(String, Vec<String>)> = notifications[..2]
+            .iter()
+            .map(|n| match n {
+                BoardNotification::Posts { board_id, posts } => (
+                    board_id.clone(),
+                    posts.iter().map(|p| p.post_id.clone()).collect(),
+                ),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        posted.sort();
+        let mut expected = vec![
+            (general.clone(), boards(&["p1", "p2"])),
+            ("other".to_string(), boards(&["p3"])),
+        ];
+        expected.sort();
+        assert_eq!(posted, expected);
+
+        assert!(matches!(
+            &notifications[2],
+            BoardNotification::PostDeleted { board_id, post_id, .. }
+                if board_id == "other" && post_id == "p4"
+        ));
+    }
+}
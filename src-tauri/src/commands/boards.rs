@@ -43,6 +43,28 @@ pub struct BoardPostInfoFe {
     pub content_text: Option<String>,
     pub lamport_clock: i64,
     pub created_at: i64,
+    pub reply_to_post_id: Option<String>,
+    pub thread_root_post_id: Option<String>,
+    pub reply_count: i64,
+}
+
+impl From<crate::db::BoardPost> for BoardPostInfoFe {
+    fn from(p: crate::db::BoardPost) -> Self {
+        Self {
+            post_id: p.post_id,
+            board_id: p.board_id,
+            relay_peer_id: p.relay_peer_id,
+            author_peer_id: p.author_peer_id,
+            author_display_name: p.author_display_name,
+            content_type: p.content_type,
+            content_text: p.content_text,
+            lamport_clock: p.lamport_clock,
+            created_at: p.created_at,
+            reply_to_post_id: p.reply_to_post_id,
+            thread_root_post_id: p.thread_root_post_id,
+            reply_count: p.reply_count,
+        }
+    }
 }
 
 /// Get all joined communities
@@ -74,7 +96,7 @@ pub async fn join_community(
     // Parse the multiaddress to extract peer ID
     let addr: libp2p::Multiaddr = relay_address
         .parse()
-        .map_err(|e| AppError::Network(format!("Invalid address: {}", e)))?;
+        .map_err(|e| AppError::Validation(format!("Invalid address: {}", e)))?;
 
     let relay_peer_id = addr
         .iter()
@@ -85,7 +107,9 @@ pub async fn join_community(
                 None
             }
         })
-        .ok_or_else(|| AppError::Network("Address must contain peer ID (/p2p/...)".to_string()))?;
+        .ok_or_else(|| {
+            AppError::Validation("Address must contain peer ID (/p2p/...)".to_string())
+        })?;
 
     // Dial the relay first
     handle.dial(relay_peer_id, vec![addr.clone()]).await.ok();
@@ -134,38 +158,68 @@ pub async fn get_board_posts(
     let limit = limit.unwrap_or(50);
     let posts =
         board_service.get_board_posts(&relay_peer_id, &board_id, limit, before_timestamp)?;
-    Ok(posts
-        .into_iter()
-        .map(|p| BoardPostInfoFe {
-            post_id: p.post_id,
-            board_id: p.board_id,
-            relay_peer_id: p.relay_peer_id,
-            author_peer_id: p.author_peer_id,
-            author_display_name: p.author_display_name,
-            content_type: p.content_type,
-            content_text: p.content_text,
-            lamport_clock: p.lamport_clock,
-            created_at: p.created_at,
-        })
-        .collect())
+    Ok(posts.into_iter().map(BoardPostInfoFe::from).collect())
+}
+
+/// Get top-level board posts (thread roots) from local cache
+#[tauri::command]
+pub async fn get_board_threads(
+    board_service: State<'_, Arc<BoardService>>,
+    relay_peer_id: String,
+    board_id: String,
+    limit: Option<i64>,
+    before_timestamp: Option<i64>,
+) -> Result<Vec<BoardPostInfoFe>, AppError> {
+    let limit = limit.unwrap_or(50);
+    let posts =
+        board_service.get_board_threads(&relay_peer_id, &board_id, limit, before_timestamp)?;
+    Ok(posts.into_iter().map(BoardPostInfoFe::from).collect())
 }
 
-/// Submit a post to a board on a relay
+/// Get a thread (root post and replies, oldest first) from local cache
+#[tauri::command]
+pub async fn get_board_thread(
+    board_service: State<'_, Arc<BoardService>>,
+    relay_peer_id: String,
+    root_post_id: String,
+) -> Result<Vec<BoardPostInfoFe>, AppError> {
+    let posts = board_service.get_thread(&relay_peer_id, &root_post_id)?;
+    Ok(posts.into_iter().map(BoardPostInfoFe::from).collect())
+}
+
+/// Fetch a thread from the relay
+#[tauri::command]
+pub async fn sync_board_thread(
+    network_state: State<'_, NetworkState>,
+    relay_peer_id: String,
+    root_post_id: String,
+) -> Result<(), AppError> {
+    let handle = network_state.get_handle().await?;
+
+    let peer_id: libp2p::PeerId = relay_peer_id
+        .parse()
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
+
+    handle.get_board_thread(peer_id, root_post_id).await
+}
+
+/// Submit a post (or a reply, with `reply_to_post_id`) to a board on a relay
 #[tauri::command]
 pub async fn submit_board_post(
     network_state: State<'_, NetworkState>,
     relay_peer_id: String,
     board_id: String,
     content_text: String,
+    reply_to_post_id: Option<String>,
 ) -> Result<(), AppError> {
     let handle = network_state.get_handle().await?;
 
     let peer_id: libp2p::PeerId = relay_peer_id
         .parse()
-        .map_err(|e| AppError::Network(format!("Invalid peer ID: {}", e)))?;
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
 
     handle
-        .submit_board_post(peer_id, board_id, content_text)
+        .submit_board_post(peer_id, board_id, content_text, reply_to_post_id)
         .await
 }
 
@@ -180,7 +234,7 @@ pub async fn delete_board_post(
 
     let peer_id: libp2p::PeerId = relay_peer_id
         .parse()
-        .map_err(|e| AppError::Network(format!("Invalid peer ID: {}", e)))?;
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
 
     handle.delete_board_post(peer_id, post_id).await
 }
@@ -196,8 +250,40 @@ pub async fn sync_board(
 
     let peer_id: libp2p::PeerId = relay_peer_id
         .parse()
-        .map_err(|e| AppError::Network(format!("Invalid peer ID: {}", e)))?;
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
 
     // Use list_boards as a simple way to trigger sync — actually use get_board_posts
     handle.get_board_posts(peer_id, board_id, None, 50).await
 }
+
+/// Subscribe to pushed updates for boards on a relay
+#[tauri::command]
+pub async fn subscribe_boards(
+    network_state: State<'_, NetworkState>,
+    relay_peer_id: String,
+    board_ids: Vec<String>,
+) -> Result<(), AppError> {
+    let handle = network_state.get_handle().await?;
+
+    let peer_id: libp2p::PeerId = relay_peer_id
+        .parse()
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
+
+    handle.subscribe_boards(peer_id, board_ids).await
+}
+
+/// Stop pushed updates for boards on a relay (all boards if `board_ids` is empty)
+#[tauri::command]
+pub async fn unsubscribe_boards(
+    network_state: State<'_, NetworkState>,
+    relay_peer_id: String,
+    board_ids: Vec<String>,
+) -> Result<(), AppError> {
+    let handle = network_state.get_handle().await?;
+
+    let peer_id: libp2p::PeerId = relay_peer_id
+        .parse()
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
+
+    handle.unsubscribe_boards(peer_id, board_ids).await
+}
@@ -7,9 +7,10 @@ use std::sync::Arc;
 use tauri::State;
 use tracing::info;
 
+use crate::commands::identity::avatar_data_url;
 use crate::commands::network::NetworkState;
 use crate::error::AppError;
-use crate::services::ContactsService;
+use crate::services::{ContactsService, SafetyNumber};
 
 /// Contact info for the frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -156,6 +157,55 @@ pub async fn is_contact_blocked(
     contacts_service.is_blocked(&peer_id)
 }
 
+/// Get a contact's avatar image as a data URL, if it has been fetched
+#[tauri::command]
+pub async fn get_contact_avatar(
+    contacts_service: State<'_, Arc<ContactsService>>,
+    peer_id: String,
+) -> Result<Option<String>, AppError> {
+    Ok(contacts_service
+        .get_avatar(&peer_id)?
+        .as_ref()
+        .map(avatar_data_url))
+}
+
+/// Get the safety number for verifying a contact's keys
+#[tauri::command]
+pub async fn get_safety_number(
+    contacts_service: State<'_, Arc<ContactsService>>,
+    peer_id: String,
+) -> Result<SafetyNumber, AppError> {
+    contacts_service.get_safety_number(&peer_id)
+}
+
+/// Mark a contact verified after comparing safety numbers
+#[tauri::command]
+pub async fn mark_contact_verified(
+    contacts_service: State<'_, Arc<ContactsService>>,
+    peer_id: String,
+) -> Result<(), AppError> {
+    contacts_service.mark_verified(&peer_id)
+}
+
+/// Verify a contact from their scanned QR payload; returns false on mismatch
+#[tauri::command]
+pub async fn verify_contact_safety_number(
+    contacts_service: State<'_, Arc<ContactsService>>,
+    peer_id: String,
+    scanned: String,
+) -> Result<bool, AppError> {
+    contacts_service.verify_scanned_safety_number(&peer_id, &scanned)
+}
+
+/// Drop a contact back to unverified
+#[tauri::command]
+pub async fn clear_contact_verification(
+    contacts_service: State<'_, Arc<ContactsService>>,
+    peer_id: String,
+) -> Result<(), AppError> {
+    contacts_service.clear_verification(&peer_id)
+}
+
 /// Request identity exchange with a peer (adds them as a contact)
 #[tauri::command]
 pub async fn request_peer_identity(
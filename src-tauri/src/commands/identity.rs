@@ -1,3 +1,4 @@
+use crate::db::Blob;
 use crate::error::AppError;
 use crate::models::{CreateIdentityRequest, IdentityInfo};
 use crate::services::{AccountsService, IdentityService};
@@ -96,6 +97,42 @@ pub async fn update_bio(
     identity_service.update_bio(bio.as_deref())
 }
 
+/// Set the avatar image; returns its content hash
+#[tauri::command]
+pub async fn set_avatar(
+    identity_service: State<'_, Arc<IdentityService>>,
+    data: Vec<u8>,
+    mime_type: String,
+) -> Result<String, AppError> {
+    identity_service.set_avatar(&data, &mime_type)
+}
+
+/// Remove the avatar image
+#[tauri::command]
+pub async fn clear_avatar(
+    identity_service: State<'_, Arc<IdentityService>>,
+) -> Result<(), AppError> {
+    identity_service.clear_avatar()
+}
+
+/// Get the avatar image as a data URL
+#[tauri::command]
+pub async fn get_avatar(
+    identity_service: State<'_, Arc<IdentityService>>,
+) -> Result<Option<String>, AppError> {
+    Ok(identity_service.get_avatar()?.as_ref().map(avatar_data_url))
+}
+
+/// Encode an avatar blob as a data URL the webview can display
+pub(crate) fn avatar_data_url(blob: &Blob) -> String {
+    use base64::Engine;
+    format!(
+        "data:{};base64,{}",
+        blob.mime_type,
+        base64::engine::general_purpose::STANDARD.encode(&blob.data)
+    )
+}
+
 /// Update passphrase hint
 #[tauri::command]
 pub async fn update_passphrase_hint(
@@ -7,11 +7,14 @@ use std::sync::Arc;
 use tauri::State;
 use tracing::info;
 
+use crate::commands::files::save_to_downloads;
 use crate::commands::network::NetworkState;
 use crate::db::repositories::Conversation;
 use crate::error::AppError;
-use crate::p2p::protocols::messaging::{DirectMessage, MessagingCodec, MessagingMessage};
-use crate::services::{DecryptedMessage, MessagingService, OutgoingMessage};
+use crate::p2p::protocols::messaging::{MessagingCodec, MessagingMessage};
+use crate::services::{
+    AttachmentInfo, DecryptedMessage, ExportFormat, MessageCursor, MessagingService,
+};
 
 /// Message info for the frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,11 +27,55 @@ pub struct MessageInfo {
     pub content: String,
     pub content_type: String,
     pub reply_to_message_id: Option<String>,
+    pub lamport_clock: i64,
     pub sent_at: i64,
     pub delivered_at: Option<i64>,
     pub read_at: Option<i64>,
     pub status: String,
     pub is_outgoing: bool,
+    pub edited_at: Option<i64>,
+    pub deleted_at: Option<i64>,
+    pub reactions: Vec<ReactionInfo>,
+    pub attachment: Option<AttachmentMeta>,
+}
+
+/// A peer's reaction to a message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReactionInfo {
+    pub peer_id: String,
+    pub reaction: String,
+}
+
+/// Attachment metadata for the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentMeta {
+    pub file_name: String,
+    pub mime_type: String,
+    pub size: u64,
+    pub downloaded: bool,
+}
+
+impl From<AttachmentInfo> for AttachmentMeta {
+    fn from(info: AttachmentInfo) -> Self {
+        Self {
+            file_name: info.file_name,
+            mime_type: info.mime_type,
+            size: info.size,
+            downloaded: info.downloaded,
+        }
+    }
+}
+
+/// A decrypted attachment for the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentFile {
+    pub file_name: String,
+    pub mime_type: String,
+    /// The file as a data URL
+    pub data_url: String,
 }
 
 impl From<DecryptedMessage> for MessageInfo {
@@ -41,15 +88,33 @@ impl From<DecryptedMessage> for MessageInfo {
             content: msg.content,
             content_type: msg.content_type,
             reply_to_message_id: msg.reply_to_message_id,
+            lamport_clock: msg.lamport_clock,
             sent_at: msg.sent_at,
             delivered_at: msg.delivered_at,
             read_at: msg.read_at,
             status: msg.status,
             is_outgoing: msg.is_outgoing,
+            edited_at: msg.edited_at,
+            deleted_at: msg.deleted_at,
+            reactions: msg
+                .reactions
+                .into_iter()
+                .map(|(peer_id, reaction)| ReactionInfo { peer_id, reaction })
+                .collect(),
+            attachment: msg.attachment.map(AttachmentMeta::from),
         }
     }
 }
 
+/// A page of a conversation for the frontend, oldest message first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagePageInfo {
+    pub messages: Vec<MessageInfo>,
+    /// Pass as `cursor` to get the page before this one; absent on the first page
+    pub next_cursor: Option<String>,
+}
+
 /// Conversation info for the frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -80,21 +145,21 @@ pub struct SendMessageResult {
     pub sent_at: i64,
 }
 
-/// Convert OutgoingMessage to DirectMessage for network transmission
-fn outgoing_to_direct_message(outgoing: &OutgoingMessage) -> DirectMessage {
-    DirectMessage {
-        message_id: outgoing.message_id.clone(),
-        conversation_id: outgoing.conversation_id.clone(),
-        sender_peer_id: outgoing.sender_peer_id.clone(),
-        recipient_peer_id: outgoing.recipient_peer_id.clone(),
-        content_encrypted: outgoing.content_encrypted.clone(),
-        content_type: outgoing.content_type.clone(),
-        reply_to: outgoing.reply_to.clone(),
-        nonce_counter: outgoing.nonce_counter,
-        lamport_clock: outgoing.lamport_clock,
-        timestamp: outgoing.timestamp,
-        signature: outgoing.signature.clone(),
-    }
+/// Encode a messaging protocol message and send it to a peer
+async fn send_to_peer(
+    network: &NetworkState,
+    peer_id: &str,
+    message: &MessagingMessage,
+) -> Result<(), AppError> {
+    let payload = MessagingCodec::encode(message)
+        .map_err(|e| AppError::Internal(format!("Failed to encode message: {}", e)))?;
+    let libp2p_peer_id = PeerId::from_str(peer_id)
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
+
+    let handle = network.get_handle().await?;
+    handle
+        .send_message(libp2p_peer_id, "message".to_string(), payload)
+        .await
 }
 
 /// Send a message to a peer
@@ -114,7 +179,7 @@ pub async fn send_message(
         messaging_service.send_message(&peer_id, &content, &content_type, reply_to.as_deref())?;
 
     // Convert to DirectMessage and encode for network transmission
-    let direct_msg = outgoing_to_direct_message(&outgoing);
+    let direct_msg = outgoing.to_direct_message();
     let msg_wrapper = MessagingMessage::Message(direct_msg);
     let payload = MessagingCodec::encode(&msg_wrapper)
         .map_err(|e| AppError::Internal(format!("Failed to encode message: {}", e)))?;
@@ -154,6 +219,38 @@ pub async fn get_messages(
     Ok(messages.into_iter().map(MessageInfo::from).collect())
 }
 
+/// Get one page of a conversation, paging back from the newest message
+#[tauri::command]
+pub async fn get_message_page(
+    messaging_service: State<'_, Arc<MessagingService>>,
+    peer_id: String,
+    limit: Option<i64>,
+    cursor: Option<String>,
+) -> Result<MessagePageInfo, AppError> {
+    let limit = limit.unwrap_or(50).clamp(1, 200);
+    let cursor = cursor.as_deref().map(MessageCursor::parse).transpose()?;
+
+    let page = messaging_service.get_conversation_page(&peer_id, limit, cursor.as_ref())?;
+    Ok(MessagePageInfo {
+        messages: page.messages.into_iter().map(MessageInfo::from).collect(),
+        next_cursor: page.next_cursor.map(|c| c.to_string()),
+    })
+}
+
+/// Export a conversation (json, markdown or mbox) to the downloads folder.
+/// Returns the path of the written file.
+#[tauri::command]
+pub async fn export_conversation(
+    app: tauri::AppHandle,
+    messaging_service: State<'_, Arc<MessagingService>>,
+    peer_id: String,
+    format: String,
+) -> Result<String, AppError> {
+    let format = ExportFormat::parse(&format)?;
+    let export = messaging_service.export_conversation(&peer_id, format)?;
+    save_to_downloads(app, export.file_name, export.content).map_err(AppError::Internal)
+}
+
 /// Get all conversations
 #[tauri::command]
 pub async fn get_conversations(
@@ -193,3 +290,117 @@ pub async fn get_total_unread_count(
     let total: i64 = conversations.iter().map(|c| c.unread_count).sum();
     Ok(total)
 }
+
+/// Send a file to a peer as an encrypted attachment
+#[tauri::command]
+pub async fn send_attachment(
+    messaging_service: State<'_, Arc<MessagingService>>,
+    network: State<'_, NetworkState>,
+    peer_id: String,
+    file_name: String,
+    mime_type: String,
+    data: Vec<u8>,
+) -> Result<SendMessageResult, AppError> {
+    let outgoing = messaging_service.send_attachment(&peer_id, &file_name, &mime_type, &data)?;
+
+    let msg_wrapper = MessagingMessage::Message(outgoing.to_direct_message());
+    send_to_peer(&network, &peer_id, &msg_wrapper).await?;
+
+    info!(
+        "Attachment {} sent to peer {}",
+        outgoing.message_id, peer_id
+    );
+
+    Ok(SendMessageResult {
+        message_id: outgoing.message_id,
+        conversation_id: outgoing.conversation_id,
+        sent_at: outgoing.timestamp,
+    })
+}
+
+/// Get the file of an attachment; `None` until it has been fetched
+#[tauri::command]
+pub async fn get_attachment(
+    messaging_service: State<'_, Arc<MessagingService>>,
+    peer_id: String,
+    message_id: String,
+) -> Result<Option<AttachmentFile>, AppError> {
+    use base64::Engine;
+
+    Ok(messaging_service
+        .get_attachment(&peer_id, &message_id)?
+        .map(|attachment| AttachmentFile {
+            data_url: format!(
+                "data:{};base64,{}",
+                attachment.mime_type,
+                base64::engine::general_purpose::STANDARD.encode(&attachment.data)
+            ),
+            file_name: attachment.file_name,
+            mime_type: attachment.mime_type,
+        }))
+}
+
+/// Edit one of our messages
+#[tauri::command]
+pub async fn edit_message(
+    messaging_service: State<'_, Arc<MessagingService>>,
+    network: State<'_, NetworkState>,
+    peer_id: String,
+    message_id: String,
+    content: String,
+) -> Result<(), AppError> {
+    let event = messaging_service.edit_message(&peer_id, &message_id, &content)?;
+    send_to_peer(&network, &peer_id, &MessagingMessage::Event(event)).await
+}
+
+/// Delete one of our messages for everyone
+#[tauri::command]
+pub async fn delete_message(
+    messaging_service: State<'_, Arc<MessagingService>>,
+    network: State<'_, NetworkState>,
+    peer_id: String,
+    message_id: String,
+) -> Result<(), AppError> {
+    let event = messaging_service.delete_message(&peer_id, &message_id)?;
+    send_to_peer(&network, &peer_id, &MessagingMessage::Event(event)).await
+}
+
+/// React to a message; no reaction withdraws ours
+#[tauri::command]
+pub async fn react_to_message(
+    messaging_service: State<'_, Arc<MessagingService>>,
+    network: State<'_, NetworkState>,
+    peer_id: String,
+    message_id: String,
+    reaction: Option<String>,
+) -> Result<(), AppError> {
+    let event = messaging_service.react_to_message(&peer_id, &message_id, reaction.as_deref())?;
+    send_to_peer(&network, &peer_id, &MessagingMessage::Event(event)).await
+}
+
+/// Set how long messages of the conversation with a peer are kept, in
+/// seconds; no retention keeps them forever
+#[tauri::command]
+pub async fn set_conversation_retention(
+    messaging_service: State<'_, Arc<MessagingService>>,
+    network: State<'_, NetworkState>,
+    peer_id: String,
+    retention_secs: Option<u64>,
+) -> Result<(), AppError> {
+    let update = messaging_service.set_retention(&peer_id, retention_secs)?;
+    // Sent again when the peer next connects
+    if let Err(e) = send_to_peer(&network, &peer_id, &MessagingMessage::Retention(update)).await {
+        tracing::warn!("Failed to send retention update to {}: {}", peer_id, e);
+    }
+    Ok(())
+}
+
+/// Get how long messages of the conversation with a peer are kept, in
+/// seconds; null keeps them forever
+#[tauri::command]
+pub async fn get_conversation_retention(
+    messaging_service: State<'_, Arc<MessagingService>>,
+    peer_id: String,
+) -> Result<Option<u64>, AppError> {
+    messaging_service.get_retention(&peer_id)
+}
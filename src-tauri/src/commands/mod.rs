@@ -14,6 +14,8 @@ pub mod network;
 pub mod permissions;
 pub mod posts;
 pub mod rss;
+pub mod scheduled;
+pub mod search;
 
 pub use accounts::*;
 pub use boards::*;
@@ -31,3 +33,5 @@ pub use network::*;
 pub use permissions::*;
 pub use posts::*;
 pub use rss::*;
+pub use scheduled::*;
+pub use search::*;
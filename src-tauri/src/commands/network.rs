@@ -1,43 +1,30 @@
 use crate::error::AppError;
-use crate::p2p::{NetworkConfig, NetworkHandle, NetworkService, NetworkStats, PeerInfo};
-use crate::services::{
-    ContactsService, ContentSyncService, IdentityService, MessagingService, PermissionsService,
-    PostsService,
-};
+use crate::models::ContactBundle;
+use crate::node::HarborNode;
+use crate::p2p::{NetworkHandle, NetworkStats, PeerInfo, PeerPresence, PresenceStatus};
+use crate::services::IdentityService;
+use libp2p::PeerId;
+use std::str::FromStr;
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter, State};
-use tokio::sync::RwLock;
+use tauri::State;
 use tracing::info;
 
-/// Wrapper for NetworkHandle to make it Tauri state compatible
+/// Tauri state wrapper around the shared `HarborNode` network lifecycle
 pub struct NetworkState {
-    pub handle: RwLock<Option<NetworkHandle>>,
+    node: Arc<HarborNode>,
 }
 
 impl NetworkState {
-    pub fn new() -> Self {
-        Self {
-            handle: RwLock::new(None),
-        }
-    }
-
-    pub async fn set_handle(&self, handle: NetworkHandle) {
-        let mut guard = self.handle.write().await;
-        *guard = Some(handle);
+    pub fn new(node: Arc<HarborNode>) -> Self {
+        Self { node }
     }
 
     pub async fn get_handle(&self) -> Result<NetworkHandle, AppError> {
-        let guard: tokio::sync::RwLockReadGuard<'_, Option<NetworkHandle>> =
-            self.handle.read().await;
-        guard
-            .clone()
-            .ok_or_else(|| AppError::Network("Network not initialized".to_string()))
+        self.node.network_handle().await
     }
-}
 
-impl Default for NetworkState {
-    fn default() -> Self {
-        Self::new()
+    pub async fn is_running(&self) -> bool {
+        self.node.is_network_running().await
     }
 }
 
@@ -60,9 +47,7 @@ pub async fn get_network_stats(network: State<'_, NetworkState>) -> Result<Netwo
 /// Check if the network is running
 #[tauri::command]
 pub async fn is_network_running(network: State<'_, NetworkState>) -> Result<bool, AppError> {
-    let guard: tokio::sync::RwLockReadGuard<'_, Option<NetworkHandle>> =
-        network.handle.read().await;
-    Ok(guard.is_some())
+    Ok(network.is_running().await)
 }
 
 /// Bootstrap the DHT (connect to bootstrap nodes)
@@ -73,111 +58,22 @@ pub async fn bootstrap_network(network: State<'_, NetworkState>) -> Result<(), A
 }
 
 /// Start the P2P network (called after identity is unlocked)
-#[allow(clippy::too_many_arguments)]
 #[tauri::command]
-pub async fn start_network(
-    app: AppHandle,
-    network: State<'_, NetworkState>,
-    identity_service: State<'_, Arc<IdentityService>>,
-    messaging_service: State<'_, Arc<MessagingService>>,
-    contacts_service: State<'_, Arc<ContactsService>>,
-    permissions_service: State<'_, Arc<PermissionsService>>,
-    posts_service: State<'_, Arc<PostsService>>,
-    content_sync_service: State<'_, Arc<ContentSyncService>>,
-) -> Result<(), AppError> {
-    // Check if identity is unlocked
-    if !identity_service.is_unlocked() {
-        return Err(AppError::PermissionDenied(
-            "Identity must be unlocked to start network".to_string(),
-        ));
-    }
-
-    // Check if network is already running
-    {
-        let guard: tokio::sync::RwLockReadGuard<'_, Option<NetworkHandle>> =
-            network.handle.read().await;
-        if guard.is_some() {
-            return Ok(()); // Already running
-        }
-    }
-
-    // Get the unlocked keys to create a libp2p keypair
-    let unlocked_keys = identity_service.get_unlocked_keys()?;
-    let ed25519_bytes = unlocked_keys.ed25519_signing.to_bytes();
-
-    // Convert to libp2p keypair
-    let keypair = crate::p2p::swarm::ed25519_to_libp2p_keypair(&ed25519_bytes)?;
-    let network_peer_id = libp2p::PeerId::from(keypair.public());
-
-    // Compare with stored identity peer ID to verify they match
-    if let Ok(Some(identity_info)) = identity_service.get_identity_info() {
-        info!(
-            "PEER ID CHECK - Stored: {} (len={}) vs Network: {} (len={})",
-            identity_info.peer_id,
-            identity_info.peer_id.len(),
-            network_peer_id,
-            network_peer_id.to_string().len()
-        );
-        if identity_info.peer_id != network_peer_id.to_string() {
-            tracing::error!(
-                "PEER ID MISMATCH! Stored peer ID does not match network peer ID. This will cause messaging to fail."
-            );
-        }
-    }
-
-    // Create network config
-    let config = NetworkConfig::default();
-
-    // Create network service - clone the Arc to pass to the service
-    let identity_arc: Arc<IdentityService> = (*identity_service).clone();
-    let (mut service, handle, mut event_rx) = NetworkService::new(config, identity_arc, keypair)?;
-
-    // Inject services for message processing, contact storage, permissions, and content sync
-    service.set_messaging_service((*messaging_service).clone());
-    service.set_contacts_service((*contacts_service).clone());
-    service.set_permissions_service((*permissions_service).clone());
-    service.set_posts_service((*posts_service).clone());
-    service.set_content_sync_service((*content_sync_service).clone());
-
-    // Store the handle
-    network.set_handle(handle).await;
-
-    // Spawn the network service in a background task
-    tokio::spawn(async move {
-        info!("Network service starting in background task");
-        service.run().await;
-        info!("Network service stopped");
-    });
-
-    // Spawn a task to process network events and forward to frontend
-    let app_clone = app.clone();
-    tokio::spawn(async move {
-        while let Some(event) = event_rx.recv().await {
-            info!("Network event: {:?}", event);
-            // Emit event to frontend
-            if let Err(e) = app_clone.emit("harbor:network", &event) {
-                tracing::warn!("Failed to emit network event: {}", e);
-            }
-        }
-    });
-
-    info!("Network started successfully");
+pub async fn start_network(network: State<'_, NetworkState>) -> Result<(), AppError> {
+    network.node.start_network().await?;
     Ok(())
 }
 
 /// Stop the P2P network
 #[tauri::command]
 pub async fn stop_network(network: State<'_, NetworkState>) -> Result<(), AppError> {
-    let maybe_handle: Option<NetworkHandle> = {
-        let mut guard = network.handle.write().await;
-        guard.take()
-    };
-
-    if let Some(handle) = maybe_handle {
-        handle.shutdown().await?;
-        info!("Network stopped");
-    }
+    network.node.stop_network().await
+}
 
+/// Restart the P2P network (e.g. after changing relays or bootstrap nodes)
+#[tauri::command]
+pub async fn restart_network(network: State<'_, NetworkState>) -> Result<(), AppError> {
+    network.node.restart_network().await?;
     Ok(())
 }
 
@@ -306,22 +202,38 @@ pub async fn sync_feed(
     handle.sync_feed(limit.unwrap_or(50)).await
 }
 
-/// Contact bundle for sharing - contains everything needed to add someone as a contact
-#[derive(serde::Serialize, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ContactBundle {
-    /// Multiaddress for connection
-    pub multiaddr: String,
-    /// Display name
-    pub display_name: String,
-    /// Ed25519 public key (base64)
-    pub public_key: String,
-    /// X25519 public key for encryption (base64)
-    pub x25519_public: String,
-    /// Optional bio
-    pub bio: Option<String>,
-    /// Optional avatar hash
-    pub avatar_hash: Option<String>,
+/// Tell a contact we started or stopped typing to it. Repeat `typing: true`
+/// every few seconds while typing continues.
+#[tauri::command]
+pub async fn set_typing(
+    network: State<'_, NetworkState>,
+    peer_id: String,
+    typing: bool,
+) -> Result<(), AppError> {
+    let peer_id = PeerId::from_str(&peer_id)
+        .map_err(|e| AppError::Validation(format!("Invalid peer ID: {}", e)))?;
+    let handle: NetworkHandle = network.get_handle().await?;
+    handle.set_typing(peer_id, typing).await
+}
+
+/// Set our presence and share it with peers that may read our wall
+#[tauri::command]
+pub async fn set_presence(
+    network: State<'_, NetworkState>,
+    status: PresenceStatus,
+    status_text: Option<String>,
+) -> Result<(), AppError> {
+    let handle: NetworkHandle = network.get_handle().await?;
+    handle.set_presence(status, status_text).await
+}
+
+/// Get the presence of connected peers that share it with us
+#[tauri::command]
+pub async fn get_presence(
+    network: State<'_, NetworkState>,
+) -> Result<Vec<PeerPresence>, AppError> {
+    let handle: NetworkHandle = network.get_handle().await?;
+    handle.get_presence().await
 }
 
 /// Generate a shareable contact string that includes all info needed to add as contact
@@ -331,53 +243,22 @@ pub async fn get_shareable_contact_string(
     network: State<'_, NetworkState>,
     identity_service: State<'_, Arc<IdentityService>>,
 ) -> Result<String, AppError> {
-    use base64::Engine;
-
     let handle: NetworkHandle = network.get_handle().await?;
     let stats = handle.get_stats().await?;
 
     // Get our identity with keys
-    let identity = identity_service
-        .get_identity()?
-        .ok_or_else(|| AppError::NotFound("Identity not found".to_string()))?;
-
     let keys = identity_service
         .get_identity_info()?
         .ok_or_else(|| AppError::NotFound("Identity keys not found".to_string()))?;
 
     // Get the best address to share
-    let multiaddr = if !stats.relay_addresses.is_empty() {
-        // Prefer relay addresses as they work through NAT
-        stats.relay_addresses[0].clone()
-    } else if !stats.external_addresses.is_empty() {
-        // Use external address if available
-        let addr = &stats.external_addresses[0];
-        if addr.contains("/p2p/") {
-            addr.clone()
-        } else {
-            format!("{}/p2p/{}", addr, identity.peer_id)
-        }
-    } else {
-        return Err(AppError::Network(
+    let multiaddr = stats.shareable_address(&keys.peer_id).ok_or_else(|| {
+        AppError::Network(
             "No shareable address available. Please connect to a relay first.".to_string(),
-        ));
-    };
+        )
+    })?;
 
-    let bundle = ContactBundle {
-        multiaddr,
-        display_name: identity.display_name,
-        public_key: base64::engine::general_purpose::STANDARD.encode(&keys.public_key),
-        x25519_public: base64::engine::general_purpose::STANDARD.encode(&keys.x25519_public),
-        bio: identity.bio,
-        avatar_hash: identity.avatar_hash,
-    };
-
-    let json = serde_json::to_string(&bundle)
-        .map_err(|e| AppError::Serialization(format!("Failed to serialize contact: {}", e)))?;
-
-    let encoded = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json.as_bytes());
-
-    Ok(format!("harbor://{}", encoded))
+    ContactBundle::new(&keys, multiaddr).to_contact_string()
 }
 
 /// Add a contact from a shareable contact string and connect to them
@@ -390,44 +271,11 @@ pub async fn add_contact_from_string(
     contact_string: String,
 ) -> Result<String, AppError> {
     use crate::db::Capability;
-    use base64::Engine;
-
-    // Parse the contact string
-    let encoded = contact_string
-        .strip_prefix("harbor://")
-        .ok_or_else(|| AppError::Validation("Invalid contact string format".to_string()))?;
-
-    let json_bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
-        .decode(encoded)
-        .map_err(|e| AppError::Validation(format!("Invalid contact encoding: {}", e)))?;
-
-    let bundle: ContactBundle = serde_json::from_slice(&json_bytes)
-        .map_err(|e| AppError::Validation(format!("Invalid contact data: {}", e)))?;
-
-    // Decode the keys — contact strings have double-base64-encoded keys:
-    // base64(base64(32 raw bytes)). Decode both layers, fall back to single decode
-    // for correctly-encoded keys.
-    let pk_once = base64::engine::general_purpose::STANDARD
-        .decode(&bundle.public_key)
-        .map_err(|e| AppError::Validation(format!("Invalid public key: {}", e)))?;
-    let public_key = base64::engine::general_purpose::STANDARD
-        .decode(&pk_once)
-        .unwrap_or(pk_once);
-
-    let x25519_once = base64::engine::general_purpose::STANDARD
-        .decode(&bundle.x25519_public)
-        .map_err(|e| AppError::Validation(format!("Invalid x25519 key: {}", e)))?;
-    let x25519_public = base64::engine::general_purpose::STANDARD
-        .decode(&x25519_once)
-        .unwrap_or(x25519_once);
-
-    // Extract peer ID from multiaddr
-    let peer_id = bundle
-        .multiaddr
-        .split("/p2p/")
-        .last()
-        .ok_or_else(|| AppError::Validation("No peer ID in multiaddr".to_string()))?
-        .to_string();
+
+    let bundle = ContactBundle::parse(&contact_string)?;
+    let public_key = bundle.public_key_bytes()?;
+    let x25519_public = bundle.x25519_public_bytes()?;
+    let peer_id = bundle.peer_id()?;
 
     // Add as contact
     contacts_service.add_contact(
@@ -0,0 +1,112 @@
+//! Tauri commands for scheduled posts and messages
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
+
+use crate::db::PostVisibility;
+use crate::error::AppError;
+use crate::services::{ScheduledEntry, SchedulerService};
+
+/// Scheduled item for the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledItemInfo {
+    pub schedule_id: String,
+    /// post or message
+    pub kind: String,
+    pub recipient_peer_id: Option<String>,
+    pub content_type: String,
+    /// Cleared once the item is published or canceled
+    pub content: Option<String>,
+    pub visibility: Option<String>,
+    pub reply_to_message_id: Option<String>,
+    pub publish_at: i64,
+    /// pending, publishing, published, failed or canceled
+    pub status: String,
+    pub created_at: i64,
+    pub published_at: Option<i64>,
+    pub published_id: Option<String>,
+    pub error: Option<String>,
+}
+
+impl From<ScheduledEntry> for ScheduledItemInfo {
+    fn from(entry: ScheduledEntry) -> Self {
+        Self {
+            schedule_id: entry.schedule_id,
+            kind: entry.kind.as_str().to_string(),
+            recipient_peer_id: entry.recipient_peer_id,
+            content_type: entry.content_type,
+            content: entry.content,
+            visibility: entry.visibility,
+            reply_to_message_id: entry.reply_to_message_id,
+            publish_at: entry.publish_at,
+            status: entry.status.as_str().to_string(),
+            created_at: entry.created_at,
+            published_at: entry.published_at,
+            published_id: entry.published_id,
+            error: entry.error,
+        }
+    }
+}
+
+/// Schedule a post to be published at `publish_at` (unix seconds)
+#[tauri::command]
+pub async fn schedule_post(
+    scheduler_service: State<'_, Arc<SchedulerService>>,
+    content_type: String,
+    content_text: Option<String>,
+    visibility: Option<String>,
+    publish_at: i64,
+) -> Result<ScheduledItemInfo, AppError> {
+    let vis = match visibility.as_deref() {
+        Some("public") => PostVisibility::Public,
+        _ => PostVisibility::Contacts,
+    };
+
+    let entry =
+        scheduler_service.schedule_post(&content_type, content_text.as_deref(), vis, publish_at)?;
+    Ok(entry.into())
+}
+
+/// Schedule a message to a peer to be sent at `publish_at` (unix seconds)
+#[tauri::command]
+pub async fn schedule_message(
+    scheduler_service: State<'_, Arc<SchedulerService>>,
+    peer_id: String,
+    content: String,
+    content_type: Option<String>,
+    reply_to: Option<String>,
+    publish_at: i64,
+) -> Result<ScheduledItemInfo, AppError> {
+    let content_type = content_type.unwrap_or_else(|| "text".to_string());
+
+    let entry = scheduler_service.schedule_message(
+        &peer_id,
+        &content,
+        &content_type,
+        reply_to.as_deref(),
+        publish_at,
+    )?;
+    Ok(entry.into())
+}
+
+/// Get scheduled items, soonest first; only pending ones unless
+/// `include_finished`
+#[tauri::command]
+pub async fn get_scheduled_items(
+    scheduler_service: State<'_, Arc<SchedulerService>>,
+    include_finished: Option<bool>,
+) -> Result<Vec<ScheduledItemInfo>, AppError> {
+    let entries = scheduler_service.list(include_finished.unwrap_or(false))?;
+    Ok(entries.into_iter().map(ScheduledItemInfo::from).collect())
+}
+
+/// Cancel a pending scheduled item
+#[tauri::command]
+pub async fn cancel_scheduled_item(
+    scheduler_service: State<'_, Arc<SchedulerService>>,
+    schedule_id: String,
+) -> Result<(), AppError> {
+    scheduler_service.cancel(&schedule_id)
+}
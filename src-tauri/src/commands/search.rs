@@ -0,0 +1,72 @@
+//! Tauri commands for full-text search
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::State;
+
+use crate::db::{SearchFilters, SearchHit, SearchKind};
+use crate::error::AppError;
+use crate::services::SearchService;
+
+/// Search hit for the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHitInfo {
+    /// message, wall_post, feed_post, board_post or contact
+    pub kind: String,
+    pub item_id: String,
+    pub peer_id: String,
+    pub relay_peer_id: Option<String>,
+    pub board_id: Option<String>,
+    pub created_at: i64,
+    /// Matched terms are wrapped in `<mark>` tags; everything else is plain text
+    pub snippet: String,
+    pub rank: f64,
+}
+
+impl From<SearchHit> for SearchHitInfo {
+    fn from(hit: SearchHit) -> Self {
+        Self {
+            kind: hit.kind.as_str().to_string(),
+            item_id: hit.item_id,
+            peer_id: hit.peer_id,
+            relay_peer_id: hit.relay_peer_id,
+            board_id: hit.board_id,
+            created_at: hit.created_at,
+            snippet: hit.snippet,
+            rank: hit.rank,
+        }
+    }
+}
+
+/// Parse search kind names into filters
+fn parse_search_kinds(kinds: &[String]) -> Result<Vec<SearchKind>, AppError> {
+    kinds
+        .iter()
+        .map(|kind| {
+            SearchKind::from_str(kind)
+                .ok_or_else(|| AppError::Validation(format!("Unknown search kind: {}", kind)))
+        })
+        .collect()
+}
+
+/// Search messages, posts, board posts and contacts
+#[tauri::command]
+pub async fn search(
+    search_service: State<'_, Arc<SearchService>>,
+    query: String,
+    kinds: Option<Vec<String>>,
+    peer_id: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<SearchHitInfo>, AppError> {
+    let filters = SearchFilters {
+        kinds: parse_search_kinds(&kinds.unwrap_or_default())?,
+        peer_id,
+        since,
+        until,
+    };
+    let hits = search_service.search(&query, &filters, limit.unwrap_or(50))?;
+    Ok(hits.into_iter().map(SearchHitInfo::from).collect())
+}
@@ -11,6 +11,19 @@ const MIGRATION_005: &str = include_str!("migrations/005_post_likes.sql");
 const MIGRATION_006: &str = include_str!("migrations/006_bootstrap_nodes.sql");
 const MIGRATION_007: &str = include_str!("migrations/007_passphrase_hint.sql");
 const MIGRATION_008: &str = include_str!("migrations/008_boards.sql");
+const MIGRATION_009: &str = include_str!("migrations/009_board_threads.sql");
+const MIGRATION_010: &str = include_str!("migrations/010_profiles.sql");
+const MIGRATION_011: &str = include_str!("migrations/011_message_events.sql");
+const MIGRATION_012: &str = include_str!("migrations/012_search.sql");
+const MIGRATION_013: &str = include_str!("migrations/013_message_paging.sql");
+const MIGRATION_014: &str = include_str!("migrations/014_retention.sql");
+const MIGRATION_015: &str = include_str!("migrations/015_scheduled_items.sql");
+const MIGRATION_016: &str = include_str!("migrations/016_board_subscriptions.sql");
+const MIGRATION_017: &str = include_str!("migrations/017_contact_identity_version.sql");
+const MIGRATION_018: &str = include_str!("migrations/018_incremental_vacuum.sql");
+
+/// Free pages released per lock of the connection when vacuuming
+const VACUUM_BATCH_PAGES: u32 = 256;
 
 /// Database wrapper for SQLite connection management
 pub struct Database {
@@ -29,8 +42,9 @@ impl Database {
 
         let conn = Connection::open(&path)?;
 
-        // Enable foreign keys
-        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        // Enable foreign keys; keep TEMP tables (the decrypted message search
+        // index) in memory
+        conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA temp_store = MEMORY;")?;
 
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
@@ -47,7 +61,7 @@ impl Database {
     /// Create an in-memory database (for testing)
     pub fn in_memory() -> SqliteResult<Self> {
         let conn = Connection::open_in_memory()?;
-        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA temp_store = MEMORY;")?;
 
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
@@ -119,6 +133,66 @@ impl Database {
             info!("Migration 008 complete");
         }
 
+        if version < 9 {
+            info!("Running migration 009...");
+            conn.execute_batch(MIGRATION_009)?;
+            info!("Migration 009 complete");
+        }
+
+        if version < 10 {
+            info!("Running migration 010...");
+            conn.execute_batch(MIGRATION_010)?;
+            info!("Migration 010 complete");
+        }
+
+        if version < 11 {
+            info!("Running migration 011...");
+            conn.execute_batch(MIGRATION_011)?;
+            info!("Migration 011 complete");
+        }
+
+        if version < 12 {
+            info!("Running migration 012...");
+            conn.execute_batch(MIGRATION_012)?;
+            info!("Migration 012 complete");
+        }
+
+        if version < 13 {
+            info!("Running migration 013...");
+            conn.execute_batch(MIGRATION_013)?;
+            info!("Migration 013 complete");
+        }
+
+        if version < 14 {
+            info!("Running migration 014...");
+            conn.execute_batch(MIGRATION_014)?;
+            info!("Migration 014 complete");
+        }
+
+        if version < 15 {
+            info!("Running migration 015...");
+            conn.execute_batch(MIGRATION_015)?;
+            info!("Migration 015 complete");
+        }
+
+        if version < 16 {
+            info!("Running migration 016...");
+            conn.execute_batch(MIGRATION_016)?;
+            info!("Migration 016 complete");
+        }
+
+        if version < 17 {
+            info!("Running migration 017...");
+            conn.execute_batch(MIGRATION_017)?;
+            info!("Migration 017 complete");
+        }
+
+        if version < 18 {
+            info!("Running migration 018...");
+            conn.execute_batch(MIGRATION_018)?;
+            info!("Migration 018 complete");
+        }
+
         Ok(())
     }
 
@@ -380,6 +454,37 @@ impl Database {
             .or(Ok(None))
         })
     }
+
+    /// Reclaim the space left by deleted rows.
+    ///
+    /// The database uses incremental auto-vacuum, so free pages are released
+    /// `VACUUM_BATCH_PAGES` at a time and the connection is unlocked between
+    /// batches; other callers wait for one batch at most. Returns the number
+    /// of pages released.
+    pub fn vacuum(&self) -> SqliteResult<u64> {
+        let mut released = 0;
+        loop {
+            let freed = self.with_connection(|conn| {
+                let before: u64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+                if before == 0 {
+                    return Ok(0);
+                }
+                // Each step of the pragma frees a page, so step it to the end
+                let mut stmt = conn.prepare(&format!(
+                    "PRAGMA incremental_vacuum({})",
+                    VACUUM_BATCH_PAGES
+                ))?;
+                let mut rows = stmt.query([])?;
+                while rows.next()?.is_some() {}
+                let after: u64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+                Ok(before.saturating_sub(after))
+            })?;
+            if freed == 0 {
+                return Ok(released);
+            }
+            released += freed;
+        }
+    }
 }
 
 impl Clone for Database {
@@ -574,4 +679,91 @@ mod tests {
         assert_eq!(cursor.get("12D3KooWAuthor2"), Some(&20));
         assert_eq!(cursor.get("12D3KooWAuthor3"), Some(&30));
     }
+
+    #[test]
+    fn test_board_threads_migration_on_populated_database() {
+        let conn = Connection::open_in_memory().unwrap();
+        for migration in [
+            MIGRATION_001,
+            MIGRATION_002,
+            MIGRATION_003,
+            MIGRATION_004,
+            MIGRATION_005,
+            MIGRATION_006,
+            MIGRATION_007,
+            MIGRATION_008,
+        ] {
+            conn.execute_batch(migration).unwrap();
+        }
+        conn.execute(
+            "INSERT INTO board_posts (post_id, board_id, relay_peer_id, author_peer_id,
+                content_type, content_text, lamport_clock, created_at, signature, cached_at)
+             VALUES ('post1', 'general', 'relay1', 'author1', 'text', 'hello', 1, 1000, X'00', 1000)",
+            [],
+        )
+        .unwrap();
+
+        let db = Database {
+            conn: Arc::new(Mutex::new(conn)),
+            path: PathBuf::from(":memory:"),
+        };
+        db.migrate().unwrap();
+
+        db.with_connection(|conn| {
+            let version: i32 = conn.query_row(
+                "SELECT version FROM schema_version WHERE id = 1",
+                [],
+                |row| row.get(0),
+            )?;
+            assert!(version >= 9);
+
+            let (content, reply_to, root, reply_count): (
+                String,
+                Option<String>,
+                Option<String>,
+                i64,
+            ) = conn.query_row(
+                "SELECT content_text, reply_to_post_id, thread_root_post_id, reply_count
+                     FROM board_posts WHERE post_id = 'post1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )?;
+            assert_eq!(content, "hello");
+            assert_eq!(reply_to, None);
+            assert_eq!(root, None);
+            assert_eq!(reply_count, 0);
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_vacuum_releases_free_pages_in_batches() {
+        let db = Database::in_memory().unwrap();
+        let free_pages = |db: &Database| -> u64 {
+            db.with_connection(|conn| conn.query_row("PRAGMA freelist_count", [], |row| row.get(0)))
+                .unwrap()
+        };
+
+        db.with_connection(|conn| {
+            let auto_vacuum: i32 = conn.query_row("PRAGMA auto_vacuum", [], |row| row.get(0))?;
+            assert_eq!(
+                auto_vacuum, 2,
+                "Database should use incremental auto-vacuum"
+            );
+
+            conn.execute_batch("CREATE TABLE filler (data BLOB)")?;
+            for _ in 0..1000 {
+                conn.execute("INSERT INTO filler (data) VALUES (zeroblob(4000))", [])?;
+            }
+            conn.execute_batch("DELETE FROM filler")
+        })
+        .unwrap();
+        let before = free_pages(&db);
+        assert!(before > VACUUM_BATCH_PAGES as u64);
+
+        assert_eq!(db.vacuum().unwrap(), before);
+        assert_eq!(free_pages(&db), 0);
+        assert_eq!(db.vacuum().unwrap(), 0);
+    }
 }
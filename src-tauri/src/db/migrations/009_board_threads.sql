@@ -0,0 +1,12 @@
+-- Migration 009: Board threads
+-- Replies carry their parent post and the thread's root post
+
+ALTER TABLE board_posts ADD COLUMN reply_to_post_id TEXT;
+ALTER TABLE board_posts ADD COLUMN thread_root_post_id TEXT;
+ALTER TABLE board_posts ADD COLUMN reply_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_board_posts_thread
+    ON board_posts(relay_peer_id, thread_root_post_id, created_at);
+
+-- Update schema version
+UPDATE schema_version SET version = 9 WHERE id = 1;
@@ -0,0 +1,18 @@
+-- Migration 010: Profile versions and content-addressed blobs
+-- profile_version counts changes to name, bio and avatar so peers can tell
+-- a newer profile from a replayed one. Blobs are keyed by the hex SHA-256
+-- of their bytes (avatars for now).
+
+ALTER TABLE local_identity ADD COLUMN profile_version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE contacts ADD COLUMN profile_version INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS blobs (
+    hash TEXT PRIMARY KEY,
+    mime_type TEXT NOT NULL,
+    data BLOB NOT NULL,
+    size INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+-- Update schema version
+UPDATE schema_version SET version = 10 WHERE id = 1;
@@ -0,0 +1,32 @@
+-- Migration 011: Message edits, deletes, reactions and attachments
+-- Signed message events (recorded in message_events) are materialized here.
+-- Edits replace the ciphertext of the message; the newest edit by Lamport
+-- clock wins. Deleted messages keep their row with the content cleared.
+
+ALTER TABLE messages ADD COLUMN edited_at INTEGER;
+ALTER TABLE messages ADD COLUMN edit_lamport_clock INTEGER;
+ALTER TABLE messages ADD COLUMN deleted_at INTEGER;
+
+-- One reaction per peer per message, encrypted like message content. An
+-- empty reaction is kept as a tombstone so older events can't revive it.
+CREATE TABLE IF NOT EXISTS message_reactions (
+    message_id TEXT NOT NULL,
+    reactor_peer_id TEXT NOT NULL,
+    reaction_encrypted BLOB NOT NULL,
+    nonce_counter INTEGER NOT NULL,
+    lamport_clock INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (message_id, reactor_peer_id)
+);
+
+-- Encrypted file of an attachment message, stored in blobs once transferred
+CREATE TABLE IF NOT EXISTS message_attachments (
+    message_id TEXT PRIMARY KEY,
+    blob_hash TEXT NOT NULL,
+    size INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_message_attachments_blob ON message_attachments(blob_hash);
+
+-- Update schema version
+UPDATE schema_version SET version = 11 WHERE id = 1;
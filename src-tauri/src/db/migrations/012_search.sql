@@ -0,0 +1,40 @@
+-- Migration 012: Full-text search
+-- FTS5 indexes over plaintext we already store: wall and feed posts, board
+-- posts and contacts. Each index row shares the rowid of its source row and
+-- is kept current by the repositories that write the source table.
+-- board_posts has no integer primary key, so its index follows the implicit
+-- rowid; a VACUUM would renumber it and require rebuilding board_post_search.
+-- Decrypted message text is never written here; it is indexed in a TEMP
+-- table that lives only while the identity is unlocked.
+
+CREATE VIRTUAL TABLE IF NOT EXISTS post_search USING fts5(
+    content_text,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS board_post_search USING fts5(
+    author_display_name,
+    content_text,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS contact_search USING fts5(
+    display_name,
+    bio,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+-- Index what is already stored
+INSERT INTO post_search (rowid, content_text)
+    SELECT id, content_text FROM posts
+    WHERE deleted_at IS NULL AND content_text IS NOT NULL;
+
+INSERT INTO board_post_search (rowid, author_display_name, content_text)
+    SELECT rowid, author_display_name, content_text FROM board_posts
+    WHERE deleted_at IS NULL;
+
+INSERT INTO contact_search (rowid, display_name, bio)
+    SELECT id, display_name, bio FROM contacts;
+
+-- Update schema version
+UPDATE schema_version SET version = 12 WHERE id = 1;
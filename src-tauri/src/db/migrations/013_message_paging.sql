@@ -0,0 +1,9 @@
+-- Migration 013: Conversation paging by Lamport clock
+-- Conversation history is paged on (lamport_clock, message_id), which is
+-- stable when messages arrive while a client is scrolling back.
+
+CREATE INDEX IF NOT EXISTS idx_messages_conv_lamport
+    ON messages(conversation_id, lamport_clock, message_id);
+
+-- Update schema version
+UPDATE schema_version SET version = 13 WHERE id = 1;
@@ -0,0 +1,20 @@
+-- Migration 014: Disappearing messages
+-- One signed retention setting per conversation, agreed on by both peers
+-- through a control message. The newest setting by (lamport_clock,
+-- set_by_peer_id) wins. A NULL retention_secs keeps messages forever.
+-- Messages older than the retention period are hidden by the repository and
+-- hard-deleted, together with their events, by a background sweeper.
+
+CREATE TABLE IF NOT EXISTS conversation_retention (
+    conversation_id TEXT PRIMARY KEY,
+    peer_id TEXT NOT NULL,
+    retention_secs INTEGER,
+    set_by_peer_id TEXT NOT NULL,
+    lamport_clock INTEGER NOT NULL,
+    timestamp INTEGER NOT NULL,
+    signature BLOB NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+-- Update schema version
+UPDATE schema_version SET version = 14 WHERE id = 1;
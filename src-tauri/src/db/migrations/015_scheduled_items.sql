@@ -0,0 +1,30 @@
+-- Migration 015: Scheduled posts and messages
+-- Items queued for a future time. Nothing is signed when an item is queued:
+-- the post or message is created, signed and given its Lamport clock when it
+-- is published, so it orders after everything authored before then.
+-- Content is encrypted with a key derived from our X25519 secret, so queued
+-- messages are not kept in plaintext and can only be published while unlocked.
+-- An item moves pending -> publishing -> published or failed, or pending ->
+-- canceled. Items left publishing by a crash are failed on the next start
+-- rather than published twice.
+
+CREATE TABLE IF NOT EXISTS scheduled_items (
+    schedule_id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,                 -- post or message
+    recipient_peer_id TEXT,             -- messages only
+    content_type TEXT NOT NULL,
+    content_encrypted BLOB,
+    visibility TEXT,                    -- posts only
+    reply_to_message_id TEXT,           -- messages only
+    publish_at INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_at INTEGER NOT NULL,
+    published_at INTEGER,
+    published_id TEXT,                  -- post or message ID once published
+    error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_items_due ON scheduled_items(status, publish_at);
+
+-- Update schema version
+UPDATE schema_version SET version = 15 WHERE id = 1;
@@ -0,0 +1,14 @@
+-- Migration 016: Board subscriptions
+-- Boards we want pushed updates for, per relay. Relays only hold
+-- subscriptions for the life of a connection, so these are replayed to the
+-- relay on every (re)connect, including after a restart.
+
+CREATE TABLE IF NOT EXISTS board_subscriptions (
+    relay_peer_id TEXT NOT NULL,
+    board_id TEXT NOT NULL,
+    subscribed_at INTEGER NOT NULL,
+    PRIMARY KEY (relay_peer_id, board_id)
+);
+
+-- Update schema version
+UPDATE schema_version SET version = 16 WHERE id = 1;
@@ -0,0 +1,9 @@
+-- Migration 017: Contact identity response version
+-- The highest identity response version a contact has sent. Once a contact
+-- has sent a fully signed response, unsigned (legacy) ones from it are
+-- rejected, so a downgraded response can't rewrite its profile.
+
+ALTER TABLE contacts ADD COLUMN identity_version INTEGER NOT NULL DEFAULT 1;
+
+-- Update schema version
+UPDATE schema_version SET version = 17 WHERE id = 1;
@@ -0,0 +1,18 @@
+-- Migration 018: Incremental auto-vacuum
+-- Lets the retention sweep reclaim free pages a few at a time instead of
+-- running a full VACUUM, which would hold the shared connection for as long
+-- as it takes to rewrite the whole file. Changing auto_vacuum on an existing
+-- database takes one last full VACUUM, run here before the network starts.
+-- That VACUUM may renumber the implicit rowids of board_posts, so the board
+-- post search index is rebuilt; incremental vacuums leave rowids alone.
+
+PRAGMA auto_vacuum = INCREMENTAL;
+VACUUM;
+
+DELETE FROM board_post_search;
+INSERT INTO board_post_search (rowid, author_display_name, content_text)
+    SELECT rowid, author_display_name, content_text FROM board_posts
+    WHERE deleted_at IS NULL;
+
+-- Update schema version
+UPDATE schema_version SET version = 18 WHERE id = 1;
@@ -3,8 +3,11 @@ pub mod repositories;
 
 pub use connection::Database;
 pub use repositories::{
-    Board, BoardPost, BoardsRepository, Capability, Contact, ContactData, ContactsRepository,
-    Conversation, GrantData, Message, MessageData, MessageStatus, MessagesRepository, Permission,
-    PermissionEvent, PermissionsRepository, Post, PostData, PostMedia, PostMediaData,
-    PostVisibility, PostsRepository, RelayCommunity,
+    Blob, BlobsRepository, Board, BoardPost, BoardsRepository, Capability, Contact, ContactData,
+    ContactsRepository, Conversation, ConversationRetention, ExpiredConversation, ExpiredMessages,
+    GrantData, Message, MessageAttachment, MessageData, MessageReaction, MessageSearchEntry,
+    MessageStatus, MessagesRepository, Permission, PermissionEvent, PermissionsRepository, Post,
+    PostData, PostMedia, PostMediaData, PostVisibility, PostsRepository, RelayCommunity,
+    RetentionRepository, ScheduleStatus, ScheduledItem, ScheduledKind, ScheduledRepository,
+    SearchFilters, SearchHit, SearchKind, SearchRepository, StoredMessageEvent, TrustLevel,
 };
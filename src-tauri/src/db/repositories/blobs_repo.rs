@@ -0,0 +1,138 @@
+//! Content-addressed blob storage
+//!
+//! Blobs are keyed by the hex SHA-256 of their bytes, so a hash received from
+//! a peer both names the blob and checks the bytes fetched for it.
+
+use crate::db::Database;
+use rusqlite::{params, OptionalExtension, Result as SqliteResult};
+use sha2::{Digest, Sha256};
+
+/// A stored blob
+#[derive(Debug, Clone)]
+pub struct Blob {
+    pub hash: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+    pub size: i64,
+    pub created_at: i64,
+}
+
+/// Repository for blob operations
+pub struct BlobsRepository;
+
+impl BlobsRepository {
+    /// Hex SHA-256 of `data`, the key it is stored under
+    pub fn hash(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    /// Store a blob and return its hash. Storing the same bytes again is a no-op.
+    pub fn put(db: &Database, mime_type: &str, data: &[u8]) -> SqliteResult<String> {
+        let hash = Self::hash(data);
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT OR IGNORE INTO blobs (hash, mime_type, data, size, created_at)
+                 VALUES (?, ?, ?, ?, ?)",
+                params![
+                    hash,
+                    mime_type,
+                    data,
+                    data.len() as i64,
+                    chrono::Utc::now().timestamp()
+                ],
+            )?;
+            Ok(())
+        })?;
+        Ok(hash)
+    }
+
+    /// Get a blob by hash
+    pub fn get(db: &Database, hash: &str) -> SqliteResult<Option<Blob>> {
+        db.with_connection(|conn| {
+            conn.query_row(
+                "SELECT hash, mime_type, data, size, created_at FROM blobs WHERE hash = ?",
+                [hash],
+                |row| {
+                    Ok(Blob {
+                        hash: row.get(0)?,
+                        mime_type: row.get(1)?,
+                        data: row.get(2)?,
+                        size: row.get(3)?,
+                        created_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()
+        })
+    }
+
+    /// Check if a blob is stored
+    pub fn exists(db: &Database, hash: &str) -> SqliteResult<bool> {
+        db.with_connection(|conn| {
+            let count: i32 =
+                conn.query_row("SELECT COUNT(*) FROM blobs WHERE hash = ?", [hash], |row| {
+                    row.get(0)
+                })?;
+            Ok(count > 0)
+        })
+    }
+
+    /// Delete a blob unless an avatar or a message attachment still uses it
+    pub fn delete_if_unreferenced(db: &Database, hash: &str) -> SqliteResult<bool> {
+        db.with_connection(|conn| {
+            let rows = conn.execute(
+                "DELETE FROM blobs WHERE hash = ?1
+                   AND NOT EXISTS (SELECT 1 FROM local_identity WHERE avatar_hash = ?1)
+                   AND NOT EXISTS (SELECT 1 FROM contacts WHERE avatar_hash = ?1)
+                   AND NOT EXISTS (SELECT 1 FROM message_attachments WHERE blob_hash = ?1)",
+                [hash],
+            )?;
+            Ok(rows > 0)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_put_is_content_addressed() {
+        let db = Database::in_memory().unwrap();
+
+        let hash = BlobsRepository::put(&db, "image/png", b"avatar bytes").unwrap();
+        assert_eq!(hash, BlobsRepository::hash(b"avatar bytes"));
+        assert_eq!(hash.len(), 64);
+
+        // Same bytes, same key
+        let again = BlobsRepository::put(&db, "image/png", b"avatar bytes").unwrap();
+        assert_eq!(again, hash);
+
+        let blob = BlobsRepository::get(&db, &hash).unwrap().unwrap();
+        assert_eq!(blob.data, b"avatar bytes");
+        assert_eq!(blob.mime_type, "image/png");
+        assert_eq!(blob.size, 12);
+    }
+
+    #[test]
+    fn test_delete_if_unreferenced() {
+        let db = Database::in_memory().unwrap();
+        let hash = BlobsRepository::put(&db, "image/png", b"old avatar").unwrap();
+
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO contacts (peer_id, public_key, x25519_public, display_name,
+                                       avatar_hash, added_at, updated_at)
+                 VALUES ('12D3KooWPeer', x'01', x'02', 'Peer', ?, 0, 0)",
+                [&hash],
+            )
+        })
+        .unwrap();
+        assert!(!BlobsRepository::delete_if_unreferenced(&db, &hash).unwrap());
+
+        db.with_connection(|conn| conn.execute("UPDATE contacts SET avatar_hash = NULL", []))
+            .unwrap();
+        assert!(BlobsRepository::delete_if_unreferenced(&db, &hash).unwrap());
+        assert!(!BlobsRepository::exists(&db, &hash).unwrap());
+    }
+}
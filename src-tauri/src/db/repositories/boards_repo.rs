@@ -1,8 +1,21 @@
 //! Board repository for storing and retrieving community board data
 
-use crate::db::Database;
+use crate::db::{Database, SearchRepository};
 use rusqlite::{params, Result as SqliteResult};
 
+/// Column list for board post queries; indices match `row_to_board_post`.
+/// The relay's reply count can lag behind replies synced since, so the larger
+/// of it and the locally cached count wins.
+const BOARD_POST_SELECT: &str = "SELECT bp.post_id, bp.board_id, bp.relay_peer_id, bp.author_peer_id,
+        bp.author_display_name, bp.content_type, bp.content_text, bp.lamport_clock,
+        bp.created_at, bp.deleted_at, bp.signature, bp.cached_at,
+        bp.reply_to_post_id, bp.thread_root_post_id,
+        MAX(bp.reply_count, (SELECT COUNT(*) FROM board_posts r
+                             WHERE r.relay_peer_id = bp.relay_peer_id
+                               AND r.thread_root_post_id = bp.post_id
+                               AND r.deleted_at IS NULL))
+ FROM board_posts bp";
+
 /// A cached relay community
 #[derive(Debug, Clone)]
 pub struct RelayCommunity {
@@ -39,6 +52,9 @@ pub struct BoardPost {
     pub deleted_at: Option<i64>,
     pub signature: Vec<u8>,
     pub cached_at: i64,
+    pub reply_to_post_id: Option<String>,
+    pub thread_root_post_id: Option<String>,
+    pub reply_count: i64,
 }
 
 /// Repository for board operations
@@ -91,6 +107,10 @@ impl BoardsRepository {
     /// Remove a relay community (cascade deletes boards and posts)
     pub fn delete_relay_community(db: &Database, relay_peer_id: &str) -> SqliteResult<bool> {
         db.with_connection(|conn| {
+            conn.execute(
+                "DELETE FROM board_subscriptions WHERE relay_peer_id = ?",
+                [relay_peer_id],
+            )?;
             let rows = conn.execute(
                 "DELETE FROM relay_communities WHERE relay_peer_id = ?",
                 [relay_peer_id],
@@ -163,17 +183,22 @@ impl BoardsRepository {
         created_at: i64,
         deleted_at: Option<i64>,
         signature: &[u8],
+        reply_to_post_id: Option<&str>,
+        thread_root_post_id: Option<&str>,
+        reply_count: i64,
     ) -> SqliteResult<()> {
         let now = chrono::Utc::now().timestamp();
         db.with_connection(|conn| {
             conn.execute(
                 "INSERT INTO board_posts (post_id, board_id, relay_peer_id, author_peer_id,
                     author_display_name, content_type, content_text, lamport_clock,
-                    created_at, deleted_at, signature, cached_at)
-                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                    created_at, deleted_at, signature, cached_at,
+                    reply_to_post_id, thread_root_post_id, reply_count)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
                  ON CONFLICT(post_id, relay_peer_id) DO UPDATE SET
                      deleted_at = excluded.deleted_at,
-                     cached_at = excluded.cached_at",
+                     cached_at = excluded.cached_at,
+                     reply_count = excluded.reply_count",
                 params![
                     post_id,
                     board_id,
@@ -186,9 +211,13 @@ impl BoardsRepository {
                     created_at,
                     deleted_at,
                     signature,
-                    now
+                    now,
+                    reply_to_post_id,
+                    thread_root_post_id,
+                    reply_count
                 ],
             )?;
+            SearchRepository::index_board_post(conn, post_id, relay_peer_id)?;
             Ok(())
         })
     }
@@ -201,34 +230,67 @@ impl BoardsRepository {
         limit: i64,
         before_timestamp: Option<i64>,
     ) -> SqliteResult<Vec<BoardPost>> {
+        Self::query_board_posts(db, board_id, relay_peer_id, limit, before_timestamp, false)
+    }
+
+    /// Get top-level posts (thread roots) for a board (paginated)
+    pub fn get_board_threads(
+        db: &Database,
+        board_id: &str,
+        relay_peer_id: &str,
+        limit: i64,
+        before_timestamp: Option<i64>,
+    ) -> SqliteResult<Vec<BoardPost>> {
+        Self::query_board_posts(db, board_id, relay_peer_id, limit, before_timestamp, true)
+    }
+
+    fn query_board_posts(
+        db: &Database,
+        board_id: &str,
+        relay_peer_id: &str,
+        limit: i64,
+        before_timestamp: Option<i64>,
+        top_level_only: bool,
+    ) -> SqliteResult<Vec<BoardPost>> {
+        let mut sql = format!(
+            "{} WHERE bp.board_id = ?1 AND bp.relay_peer_id = ?2
+                AND (?3 IS NULL OR bp.created_at < ?3) AND bp.deleted_at IS NULL",
+            BOARD_POST_SELECT
+        );
+        if top_level_only {
+            sql.push_str(" AND bp.thread_root_post_id IS NULL");
+        }
+        sql.push_str(" ORDER BY bp.created_at DESC LIMIT ?4");
+
         db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&sql)?;
             let mut posts = Vec::new();
-            if let Some(before) = before_timestamp {
-                let mut stmt = conn.prepare(
-                    "SELECT post_id, board_id, relay_peer_id, author_peer_id,
-                            author_display_name, content_type, content_text, lamport_clock,
-                            created_at, deleted_at, signature, cached_at
-                     FROM board_posts
-                     WHERE board_id = ? AND relay_peer_id = ? AND created_at < ? AND deleted_at IS NULL
-                     ORDER BY created_at DESC LIMIT ?",
-                )?;
-                let mut rows = stmt.query(params![board_id, relay_peer_id, before, limit])?;
-                while let Some(row) = rows.next()? {
-                    posts.push(Self::row_to_board_post(row)?);
-                }
-            } else {
-                let mut stmt = conn.prepare(
-                    "SELECT post_id, board_id, relay_peer_id, author_peer_id,
-                            author_display_name, content_type, content_text, lamport_clock,
-                            created_at, deleted_at, signature, cached_at
-                     FROM board_posts
-                     WHERE board_id = ? AND relay_peer_id = ? AND deleted_at IS NULL
-                     ORDER BY created_at DESC LIMIT ?",
-                )?;
-                let mut rows = stmt.query(params![board_id, relay_peer_id, limit])?;
-                while let Some(row) = rows.next()? {
-                    posts.push(Self::row_to_board_post(row)?);
-                }
+            let mut rows = stmt.query(params![board_id, relay_peer_id, before_timestamp, limit])?;
+            while let Some(row) = rows.next()? {
+                posts.push(Self::row_to_board_post(row)?);
+            }
+            Ok(posts)
+        })
+    }
+
+    /// Get a thread root and its replies, oldest first
+    pub fn get_thread_posts(
+        db: &Database,
+        relay_peer_id: &str,
+        root_post_id: &str,
+    ) -> SqliteResult<Vec<BoardPost>> {
+        db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "{} WHERE bp.relay_peer_id = ?1
+                    AND (bp.post_id = ?2 OR bp.thread_root_post_id = ?2)
+                    AND bp.deleted_at IS NULL
+                 ORDER BY bp.thread_root_post_id IS NOT NULL, bp.created_at ASC",
+                BOARD_POST_SELECT
+            ))?;
+            let mut posts = Vec::new();
+            let mut rows = stmt.query(params![relay_peer_id, root_post_id])?;
+            while let Some(row) = rows.next()? {
+                posts.push(Self::row_to_board_post(row)?);
             }
             Ok(posts)
         })
@@ -248,6 +310,9 @@ impl BoardsRepository {
             deleted_at: row.get(9)?,
             signature: row.get(10)?,
             cached_at: row.get(11)?,
+            reply_to_post_id: row.get(12)?,
+            thread_root_post_id: row.get(13)?,
+            reply_count: row.get(14)?,
         })
     }
 
@@ -299,6 +364,60 @@ impl BoardsRepository {
         })
     }
 
+    /// Record board subscriptions on a relay
+    pub fn add_board_subscriptions(
+        db: &Database,
+        relay_peer_id: &str,
+        board_ids: &[String],
+    ) -> SqliteResult<()> {
+        let now = chrono::Utc::now().timestamp();
+        db.with_connection(|conn| {
+            for board_id in board_ids {
+                conn.execute(
+                    "INSERT OR IGNORE INTO board_subscriptions (relay_peer_id, board_id, subscribed_at)
+                     VALUES (?, ?, ?)",
+                    params![relay_peer_id, board_id, now],
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Remove board subscriptions on a relay (all of them if `board_ids` is empty)
+    pub fn remove_board_subscriptions(
+        db: &Database,
+        relay_peer_id: &str,
+        board_ids: &[String],
+    ) -> SqliteResult<()> {
+        db.with_connection(|conn| {
+            if board_ids.is_empty() {
+                conn.execute(
+                    "DELETE FROM board_subscriptions WHERE relay_peer_id = ?",
+                    [relay_peer_id],
+                )?;
+            }
+            for board_id in board_ids {
+                conn.execute(
+                    "DELETE FROM board_subscriptions WHERE relay_peer_id = ? AND board_id = ?",
+                    params![relay_peer_id, board_id],
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    /// All board subscriptions as (relay_peer_id, board_id)
+    pub fn get_board_subscriptions(db: &Database) -> SqliteResult<Vec<(String, String)>> {
+        db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT relay_peer_id, board_id FROM board_subscriptions
+                 ORDER BY relay_peer_id, board_id",
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect()
+        })
+    }
+
     /// Delete a board post locally
     pub fn delete_board_post(
         db: &Database,
@@ -311,7 +430,166 @@ impl BoardsRepository {
                 "UPDATE board_posts SET deleted_at = ? WHERE post_id = ? AND relay_peer_id = ? AND deleted_at IS NULL",
                 params![now, post_id, relay_peer_id],
             )?;
+            SearchRepository::index_board_post(conn, post_id, relay_peer_id)?;
             Ok(rows > 0)
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RELAY: &str = "12D3KooWRelay";
+    const BOARD: &str = "general";
+
+    fn insert_post(
+        db: &Database,
+        post_id: &str,
+        created_at: i64,
+        parent: Option<&str>,
+        root: Option<&str>,
+    ) {
+        BoardsRepository::upsert_board_post(
+            db,
+            post_id,
+            BOARD,
+            RELAY,
+            "12D3KooWAuthor",
+            None,
+            "text",
+            Some(post_id),
+            created_at,
+            created_at,
+            None,
+            &[0],
+            parent,
+            root,
+            0,
+        )
+        .unwrap();
+    }
+
+    fn reply_count(db: &Database, post_id: &str) -> i64 {
+        BoardsRepository::get_thread_posts(db, RELAY, post_id).unwrap()[0].reply_count
+    }
+
+    #[test]
+    fn test_reply_count_follows_replies_and_deletes() {
+        let db = Database::in_memory().unwrap();
+        insert_post(&db, "root", 100, None, None);
+        assert_eq!(reply_count(&db, "root"), 0);
+
+        insert_post(&db, "reply1", 110, Some("root"), Some("root"));
+        insert_post(&db, "reply2", 120, Some("reply1"), Some("root"));
+        assert_eq!(reply_count(&db, "root"), 2);
+
+        assert!(BoardsRepository::delete_board_post(&db, "reply1", RELAY).unwrap());
+        assert_eq!(reply_count(&db, "root"), 1);
+    }
+
+    #[test]
+    fn test_relay_reply_count_wins_when_larger() {
+        let db = Database::in_memory().unwrap();
+        BoardsRepository::upsert_board_post(
+            &db,
+            "root",
+            BOARD,
+            RELAY,
+            "12D3KooWAuthor",
+            None,
+            "text",
+            None,
+            1,
+            100,
+            None,
+            &[0],
+            None,
+            None,
+            5,
+        )
+        .unwrap();
+        insert_post(&db, "reply1", 110, Some("root"), Some("root"));
+
+        assert_eq!(reply_count(&db, "root"), 5);
+    }
+
+    #[test]
+    fn test_thread_paging() {
+        let db = Database::in_memory().unwrap();
+        insert_post(&db, "a", 100, None, None);
+        insert_post(&db, "b", 200, None, None);
+        insert_post(&db, "c", 300, None, None);
+        insert_post(&db, "c1", 310, Some("c"), Some("c"));
+        insert_post(&db, "c2", 320, Some("c1"), Some("c"));
+
+        let page = BoardsRepository::get_board_threads(&db, BOARD, RELAY, 2, None).unwrap();
+        let ids: Vec<_> = page.iter().map(|p| p.post_id.as_str()).collect();
+        assert_eq!(ids, ["c", "b"]);
+        assert_eq!(page[0].reply_count, 2);
+
+        let next =
+            BoardsRepository::get_board_threads(&db, BOARD, RELAY, 2, Some(page[1].created_at))
+                .unwrap();
+        let ids: Vec<_> = next.iter().map(|p| p.post_id.as_str()).collect();
+        assert_eq!(ids, ["a"]);
+
+        // The flat listing still includes replies
+        let flat = BoardsRepository::get_board_posts(&db, BOARD, RELAY, 10, None).unwrap();
+        assert_eq!(flat.len(), 5);
+    }
+
+    #[test]
+    fn test_board_subscriptions() {
+        let db = Database::in_memory().unwrap();
+        let boards = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        BoardsRepository::add_board_subscriptions(&db, RELAY, &boards(&["a", "b", "c"])).unwrap();
+        BoardsRepository::add_board_subscriptions(&db, "12D3KooWOther", &boards(&["a"])).unwrap();
+        // Subscribing twice is a no-op
+        BoardsRepository::add_board_subscriptions(&db, RELAY, &boards(&["a"])).unwrap();
+        assert_eq!(
+            BoardsRepository::get_board_subscriptions(&db)
+                .unwrap()
+                .len(),
+            4
+        );
+
+        BoardsRepository::remove_board_subscriptions(&db, RELAY, &boards(&["b"])).unwrap();
+        let subs = BoardsRepository::get_board_subscriptions(&db).unwrap();
+        assert!(!subs.contains(&(RELAY.to_string(), "b".to_string())));
+        assert_eq!(subs.len(), 3);
+
+        BoardsRepository::remove_board_subscriptions(&db, RELAY, &[]).unwrap();
+        let subs = BoardsRepository::get_board_subscriptions(&db).unwrap();
+        assert_eq!(subs, vec![("12D3KooWOther".to_string(), "a".to_string())]);
+    }
+
+    #[test]
+    fn test_leaving_a_community_drops_its_subscriptions() {
+        let db = Database::in_memory().unwrap();
+        BoardsRepository::upsert_relay_community(&db, RELAY, "/ip4/127.0.0.1/tcp/4001", None, 100)
+            .unwrap();
+        BoardsRepository::add_board_subscriptions(&db, RELAY, &[BOARD.to_string()]).unwrap();
+
+        assert!(BoardsRepository::delete_relay_community(&db, RELAY).unwrap());
+        assert!(BoardsRepository::get_board_subscriptions(&db)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_thread_posts_root_first_then_oldest_reply() {
+        let db = Database::in_memory().unwrap();
+        insert_post(&db, "root", 100, None, None);
+        insert_post(&db, "late", 130, Some("root"), Some("root"));
+        insert_post(&db, "early", 110, Some("root"), Some("root"));
+        insert_post(&db, "other", 120, None, None);
+
+        let thread = BoardsRepository::get_thread_posts(&db, RELAY, "root").unwrap();
+        let ids: Vec<_> = thread.iter().map(|p| p.post_id.as_str()).collect();
+        assert_eq!(ids, ["root", "early", "late"]);
+        assert_eq!(thread[1].reply_to_post_id.as_deref(), Some("root"));
+        assert_eq!(thread[1].thread_root_post_id.as_deref(), Some("root"));
+    }
+}
@@ -1,6 +1,6 @@
 //! Contact repository for managing peer contacts
 
-use crate::db::Database;
+use crate::db::{Database, SearchRepository};
 use rusqlite::{params, OptionalExtension, Result as SqliteResult};
 
 /// Represents a contact in the database
@@ -16,10 +16,45 @@ pub struct Contact {
     pub is_blocked: bool,
     pub trust_level: i32,
     pub last_seen_at: Option<i64>,
+    /// Profile version of the last profile applied (0 if never announced)
+    pub profile_version: i64,
     pub added_at: i64,
     pub updated_at: i64,
 }
 
+impl Contact {
+    /// Whether the contact's safety number has been verified
+    pub fn is_verified(&self) -> bool {
+        TrustLevel::from_i32(self.trust_level) >= TrustLevel::Verified
+    }
+}
+
+/// Values of `contacts.trust_level`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TrustLevel {
+    /// Keys taken on trust from a contact string or identity exchange
+    Unverified,
+    /// Safety number compared out of band
+    Verified,
+}
+
+impl TrustLevel {
+    pub fn as_i32(&self) -> i32 {
+        match self {
+            TrustLevel::Unverified => 0,
+            TrustLevel::Verified => 1,
+        }
+    }
+
+    pub fn from_i32(level: i32) -> Self {
+        if level >= 1 {
+            TrustLevel::Verified
+        } else {
+            TrustLevel::Unverified
+        }
+    }
+}
+
 /// Contact data for creating or updating contacts
 #[derive(Debug, Clone)]
 pub struct ContactData {
@@ -29,6 +64,8 @@ pub struct ContactData {
     pub display_name: String,
     pub avatar_hash: Option<String>,
     pub bio: Option<String>,
+    /// Version the peer announced for this profile; 0 if unknown
+    pub profile_version: i64,
 }
 
 /// Repository for contact operations
@@ -40,8 +77,8 @@ impl ContactsRepository {
         db.with_connection(|conn| {
             let now = chrono::Utc::now().timestamp();
             conn.execute(
-                "INSERT INTO contacts (peer_id, public_key, x25519_public, display_name, avatar_hash, bio, added_at, updated_at)
-                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                "INSERT INTO contacts (peer_id, public_key, x25519_public, display_name, avatar_hash, bio, profile_version, added_at, updated_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
                 params![
                     contact.peer_id,
                     contact.public_key,
@@ -49,11 +86,14 @@ impl ContactsRepository {
                     contact.display_name,
                     contact.avatar_hash,
                     contact.bio,
+                    contact.profile_version,
                     now,
                     now
                 ],
             )?;
-            Ok(conn.last_insert_rowid())
+            let id = conn.last_insert_rowid();
+            SearchRepository::index_contact(conn, &contact.peer_id)?;
+            Ok(id)
         })
     }
 
@@ -62,7 +102,7 @@ impl ContactsRepository {
         db.with_connection(|conn| {
             conn.query_row(
                 "SELECT id, peer_id, public_key, x25519_public, display_name, avatar_hash, bio,
-                        is_blocked, trust_level, last_seen_at, added_at, updated_at
+                        is_blocked, trust_level, last_seen_at, profile_version, added_at, updated_at
                  FROM contacts WHERE peer_id = ?",
                 [peer_id],
                 |row| {
@@ -77,8 +117,9 @@ impl ContactsRepository {
                         is_blocked: row.get::<_, i32>(7)? != 0,
                         trust_level: row.get(8)?,
                         last_seen_at: row.get(9)?,
-                        added_at: row.get(10)?,
-                        updated_at: row.get(11)?,
+                        profile_version: row.get(10)?,
+                        added_at: row.get(11)?,
+                        updated_at: row.get(12)?,
                     })
                 },
             )
@@ -91,7 +132,7 @@ impl ContactsRepository {
         db.with_connection(|conn| {
             let mut stmt = conn.prepare(
                 "SELECT id, peer_id, public_key, x25519_public, display_name, avatar_hash, bio,
-                        is_blocked, trust_level, last_seen_at, added_at, updated_at
+                        is_blocked, trust_level, last_seen_at, profile_version, added_at, updated_at
                  FROM contacts
                  ORDER BY display_name ASC",
             )?;
@@ -108,8 +149,9 @@ impl ContactsRepository {
                     is_blocked: row.get::<_, i32>(7)? != 0,
                     trust_level: row.get(8)?,
                     last_seen_at: row.get(9)?,
-                    added_at: row.get(10)?,
-                    updated_at: row.get(11)?,
+                    profile_version: row.get(10)?,
+                    added_at: row.get(11)?,
+                    updated_at: row.get(12)?,
                 })
             })?;
 
@@ -122,7 +164,7 @@ impl ContactsRepository {
         db.with_connection(|conn| {
             let mut stmt = conn.prepare(
                 "SELECT id, peer_id, public_key, x25519_public, display_name, avatar_hash, bio,
-                        is_blocked, trust_level, last_seen_at, added_at, updated_at
+                        is_blocked, trust_level, last_seen_at, profile_version, added_at, updated_at
                  FROM contacts
                  WHERE is_blocked = 0
                  ORDER BY display_name ASC",
@@ -140,8 +182,9 @@ impl ContactsRepository {
                     is_blocked: row.get::<_, i32>(7)? != 0,
                     trust_level: row.get(8)?,
                     last_seen_at: row.get(9)?,
-                    added_at: row.get(10)?,
-                    updated_at: row.get(11)?,
+                    profile_version: row.get(10)?,
+                    added_at: row.get(11)?,
+                    updated_at: row.get(12)?,
                 })
             })?;
 
@@ -156,13 +199,75 @@ impl ContactsRepository {
         display_name: &str,
         avatar_hash: Option<&str>,
         bio: Option<&str>,
+        profile_version: i64,
+    ) -> SqliteResult<bool> {
+        db.with_connection(|conn| {
+            let now = chrono::Utc::now().timestamp();
+            let rows = conn.execute(
+                "UPDATE contacts SET display_name = ?, avatar_hash = ?, bio = ?,
+                                     profile_version = MAX(profile_version, ?), updated_at = ?
+                 WHERE peer_id = ?",
+                params![
+                    display_name,
+                    avatar_hash,
+                    bio,
+                    profile_version,
+                    now,
+                    peer_id
+                ],
+            )?;
+            SearchRepository::index_contact(conn, peer_id)?;
+            Ok(rows > 0)
+        })
+    }
+
+    /// Replace a contact's keys. Verification is for the old keys, so the
+    /// trust level drops back to unverified.
+    pub fn update_keys(
+        db: &Database,
+        peer_id: &str,
+        public_key: &[u8],
+        x25519_public: &[u8],
     ) -> SqliteResult<bool> {
         db.with_connection(|conn| {
             let now = chrono::Utc::now().timestamp();
             let rows = conn.execute(
-                "UPDATE contacts SET display_name = ?, avatar_hash = ?, bio = ?, updated_at = ?
+                "UPDATE contacts SET public_key = ?, x25519_public = ?, trust_level = ?, updated_at = ?
                  WHERE peer_id = ?",
-                params![display_name, avatar_hash, bio, now, peer_id],
+                params![
+                    public_key,
+                    x25519_public,
+                    TrustLevel::Unverified.as_i32(),
+                    now,
+                    peer_id
+                ],
+            )?;
+            Ok(rows > 0)
+        })
+    }
+
+    /// Highest identity response version a contact has sent (None if not a contact)
+    pub fn get_identity_version(db: &Database, peer_id: &str) -> SqliteResult<Option<u32>> {
+        db.with_connection(|conn| {
+            conn.query_row(
+                "SELECT identity_version FROM contacts WHERE peer_id = ?",
+                [peer_id],
+                |row| row.get(0),
+            )
+            .optional()
+        })
+    }
+
+    /// Record an identity response version from a contact; never lowers it
+    pub fn record_identity_version(
+        db: &Database,
+        peer_id: &str,
+        version: u32,
+    ) -> SqliteResult<bool> {
+        db.with_connection(|conn| {
+            let rows = conn.execute(
+                "UPDATE contacts SET identity_version = MAX(identity_version, ?) WHERE peer_id = ?",
+                params![version, peer_id],
             )?;
             Ok(rows > 0)
         })
@@ -219,6 +324,7 @@ impl ContactsRepository {
     /// Remove a contact
     pub fn remove_contact(db: &Database, peer_id: &str) -> SqliteResult<bool> {
         db.with_connection(|conn| {
+            SearchRepository::unindex_contact(conn, peer_id)?;
             let rows = conn.execute("DELETE FROM contacts WHERE peer_id = ?", [peer_id])?;
             Ok(rows > 0)
         })
@@ -266,6 +372,7 @@ mod tests {
             display_name: "Test User".to_string(),
             avatar_hash: None,
             bio: Some("Hello!".to_string()),
+            profile_version: 0,
         };
 
         let id = ContactsRepository::add_contact(&db, &contact_data).unwrap();
@@ -292,6 +399,7 @@ mod tests {
             display_name: "Test User".to_string(),
             avatar_hash: None,
             bio: None,
+            profile_version: 0,
         };
 
         ContactsRepository::add_contact(&db, &contact_data).unwrap();
@@ -322,6 +430,7 @@ mod tests {
                 display_name: "Active".to_string(),
                 avatar_hash: None,
                 bio: None,
+                profile_version: 0,
             },
         )
         .unwrap();
@@ -335,6 +444,7 @@ mod tests {
                 display_name: "Blocked".to_string(),
                 avatar_hash: None,
                 bio: None,
+                profile_version: 0,
             },
         )
         .unwrap();
@@ -365,6 +475,7 @@ mod tests {
                 display_name: "Test".to_string(),
                 avatar_hash: None,
                 bio: None,
+                profile_version: 0,
             },
         )
         .unwrap();
@@ -375,4 +486,41 @@ mod tests {
 
         assert!(!ContactsRepository::is_contact(&db, "12D3KooWTest").unwrap());
     }
+
+    #[test]
+    fn test_identity_version_only_increases() {
+        let db = Database::in_memory().unwrap();
+        assert_eq!(
+            ContactsRepository::get_identity_version(&db, "12D3KooWTest").unwrap(),
+            None
+        );
+
+        ContactsRepository::add_contact(
+            &db,
+            &ContactData {
+                peer_id: "12D3KooWTest".to_string(),
+                public_key: vec![1],
+                x25519_public: vec![2],
+                display_name: "Test User".to_string(),
+                avatar_hash: None,
+                bio: None,
+                profile_version: 0,
+            },
+        )
+        .unwrap();
+        // Contacts start out as legacy until they send a signed response
+        assert_eq!(
+            ContactsRepository::get_identity_version(&db, "12D3KooWTest").unwrap(),
+            Some(1)
+        );
+
+        assert!(ContactsRepository::record_identity_version(&db, "12D3KooWTest", 2).unwrap());
+        ContactsRepository::record_identity_version(&db, "12D3KooWTest", 1).unwrap();
+        assert_eq!(
+            ContactsRepository::get_identity_version(&db, "12D3KooWTest").unwrap(),
+            Some(2)
+        );
+
+        assert!(!ContactsRepository::record_identity_version(&db, "12D3KooWOther", 2).unwrap());
+    }
 }
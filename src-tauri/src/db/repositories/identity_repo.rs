@@ -30,7 +30,8 @@ impl<'a> IdentityRepository<'a> {
         self.db.with_connection(|conn| {
             let mut stmt = conn.prepare(
                 "SELECT peer_id, public_key, x25519_public, private_key_encrypted,
-                        display_name, avatar_hash, bio, passphrase_hint, created_at, updated_at
+                        display_name, avatar_hash, bio, passphrase_hint, profile_version,
+                        created_at, updated_at
                  FROM local_identity WHERE id = 1",
             )?;
 
@@ -44,8 +45,9 @@ impl<'a> IdentityRepository<'a> {
                     avatar_hash: row.get(5)?,
                     bio: row.get(6)?,
                     passphrase_hint: row.get(7)?,
-                    created_at: row.get(8)?,
-                    updated_at: row.get(9)?,
+                    profile_version: row.get(8)?,
+                    created_at: row.get(9)?,
+                    updated_at: row.get(10)?,
                 })
             });
 
@@ -63,8 +65,9 @@ impl<'a> IdentityRepository<'a> {
             conn.execute(
                 "INSERT INTO local_identity
                  (id, peer_id, public_key, x25519_public, private_key_encrypted,
-                  display_name, avatar_hash, bio, passphrase_hint, created_at, updated_at)
-                 VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                  display_name, avatar_hash, bio, passphrase_hint, profile_version,
+                  created_at, updated_at)
+                 VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
                 params![
                     identity.peer_id,
                     identity.public_key,
@@ -74,6 +77,7 @@ impl<'a> IdentityRepository<'a> {
                     identity.avatar_hash,
                     identity.bio,
                     identity.passphrase_hint,
+                    identity.profile_version,
                     identity.created_at,
                     identity.updated_at,
                 ],
@@ -83,36 +87,42 @@ impl<'a> IdentityRepository<'a> {
         })
     }
 
-    /// Update display name
+    /// Update display name (bumps the profile version)
     pub fn update_display_name(&self, display_name: &str) -> SqliteResult<()> {
         let now = chrono::Utc::now().timestamp();
         self.db.with_connection(|conn| {
             conn.execute(
-                "UPDATE local_identity SET display_name = ?1, updated_at = ?2 WHERE id = 1",
+                "UPDATE local_identity
+                 SET display_name = ?1, profile_version = profile_version + 1, updated_at = ?2
+                 WHERE id = 1",
                 params![display_name, now],
             )?;
             Ok(())
         })
     }
 
-    /// Update bio
+    /// Update bio (bumps the profile version)
     pub fn update_bio(&self, bio: Option<&str>) -> SqliteResult<()> {
         let now = chrono::Utc::now().timestamp();
         self.db.with_connection(|conn| {
             conn.execute(
-                "UPDATE local_identity SET bio = ?1, updated_at = ?2 WHERE id = 1",
+                "UPDATE local_identity
+                 SET bio = ?1, profile_version = profile_version + 1, updated_at = ?2
+                 WHERE id = 1",
                 params![bio, now],
             )?;
             Ok(())
         })
     }
 
-    /// Update avatar hash
+    /// Update avatar hash (bumps the profile version)
     pub fn update_avatar(&self, avatar_hash: Option<&str>) -> SqliteResult<()> {
         let now = chrono::Utc::now().timestamp();
         self.db.with_connection(|conn| {
             conn.execute(
-                "UPDATE local_identity SET avatar_hash = ?1, updated_at = ?2 WHERE id = 1",
+                "UPDATE local_identity
+                 SET avatar_hash = ?1, profile_version = profile_version + 1, updated_at = ?2
+                 WHERE id = 1",
                 params![avatar_hash, now],
             )?;
             Ok(())
@@ -146,6 +156,7 @@ mod tests {
             avatar_hash: None,
             bio: Some("Test bio".to_string()),
             passphrase_hint: Some("My hint".to_string()),
+            profile_version: 0,
             created_at: 1000,
             updated_at: 1000,
         }
@@ -183,5 +194,10 @@ mod tests {
 
         let identity = repo.get().unwrap().unwrap();
         assert_eq!(identity.display_name, "New Name");
+        assert_eq!(identity.profile_version, 1);
+
+        // The passphrase hint isn't part of the profile
+        repo.update_passphrase_hint(Some("Other hint")).unwrap();
+        assert_eq!(repo.get().unwrap().unwrap().profile_version, 1);
     }
 }
@@ -1,7 +1,8 @@
 //! Messages repository for storing and retrieving direct messages
 
+use crate::db::repositories::retention_repo::expired_condition;
 use crate::db::Database;
-use rusqlite::{params, Connection, Result as SqliteResult};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
 
 /// Message status
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -56,6 +57,8 @@ pub struct Message {
     pub delivered_at: Option<i64>,
     pub read_at: Option<i64>,
     pub status: String,
+    pub edited_at: Option<i64>,
+    pub deleted_at: Option<i64>,
 }
 
 /// Data for inserting a new message
@@ -75,6 +78,41 @@ pub struct MessageData {
     pub status: MessageStatus,
 }
 
+/// A signed message event as recorded, with the exact bytes that were signed
+#[derive(Debug, Clone)]
+pub struct StoredMessageEvent {
+    pub event_id: String,
+    /// sent, received, edit, delete or reaction
+    pub event_type: String,
+    pub message_id: String,
+    pub sender_peer_id: String,
+    pub lamport_clock: i64,
+    pub payload_cbor: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// A peer's reaction to a message, encrypted like message content
+#[derive(Debug, Clone)]
+pub struct MessageReaction {
+    pub message_id: String,
+    pub reactor_peer_id: String,
+    /// Empty plaintext once the reaction was withdrawn
+    pub reaction_encrypted: Vec<u8>,
+    pub nonce_counter: u64,
+    pub lamport_clock: i64,
+    pub updated_at: i64,
+}
+
+/// The encrypted file behind an attachment message
+#[derive(Debug, Clone)]
+pub struct MessageAttachment {
+    pub message_id: String,
+    /// Hash of the encrypted file in the blob store
+    pub blob_hash: String,
+    /// Size of the encrypted file in bytes
+    pub size: i64,
+}
+
 /// A conversation summary
 #[derive(Debug, Clone)]
 pub struct Conversation {
@@ -129,7 +167,8 @@ impl MessagesRepository {
         let mut stmt = conn.prepare(
             "SELECT id, message_id, conversation_id, sender_peer_id, recipient_peer_id,
                     content_encrypted, content_type, reply_to_message_id, nonce_counter,
-                    lamport_clock, sent_at, received_at, delivered_at, read_at, status
+                    lamport_clock, sent_at, received_at, delivered_at, read_at, status,
+                    edited_at, deleted_at
              FROM messages WHERE message_id = ?",
         )?;
 
@@ -152,13 +191,18 @@ impl MessagesRepository {
                 delivered_at: row.get(12)?,
                 read_at: row.get(13)?,
                 status: row.get(14)?,
+                edited_at: row.get(15)?,
+                deleted_at: row.get(16)?,
             }))
         } else {
             Ok(None)
         }
     }
 
-    /// Get messages for a conversation
+    /// Get messages for a conversation.
+    ///
+    /// Messages past the conversation's retention period are never returned,
+    /// even before the sweeper has deleted them.
     pub fn get_conversation_messages(
         db: &Database,
         conversation_id: &str,
@@ -168,44 +212,74 @@ impl MessagesRepository {
         db.with_connection(|conn| {
             // For pagination, we need to get the N most recent messages, then sort them ASC for display
             // When paginating (before_timestamp provided), get messages before that time
-            let query = if before_timestamp.is_some() {
-                "SELECT id, message_id, conversation_id, sender_peer_id, recipient_peer_id,
-                        content_encrypted, content_type, reply_to_message_id, nonce_counter,
-                        lamport_clock, sent_at, received_at, delivered_at, read_at, status
-                 FROM (
-                   SELECT * FROM messages
-                   WHERE conversation_id = ? AND sent_at < ?
-                   ORDER BY sent_at DESC
-                   LIMIT ?
-                 ) ORDER BY sent_at ASC"
-            } else {
+            let mut stmt = conn.prepare(&format!(
                 "SELECT id, message_id, conversation_id, sender_peer_id, recipient_peer_id,
                         content_encrypted, content_type, reply_to_message_id, nonce_counter,
-                        lamport_clock, sent_at, received_at, delivered_at, read_at, status
+                        lamport_clock, sent_at, received_at, delivered_at, read_at, status,
+                        edited_at, deleted_at
                  FROM (
                    SELECT * FROM messages
-                   WHERE conversation_id = ?
+                   WHERE conversation_id = ?1 AND (?2 IS NULL OR sent_at < ?2)
+                     AND NOT {}
                    ORDER BY sent_at DESC
-                   LIMIT ?
-                 ) ORDER BY sent_at ASC"
-            };
+                   LIMIT ?3
+                 ) ORDER BY sent_at ASC",
+                expired_condition(4)
+            ))?;
 
-            let mut stmt = conn.prepare(query)?;
+            let now = chrono::Utc::now().timestamp();
+            let rows = stmt.query_map(
+                params![conversation_id, before_timestamp, limit, now],
+                Self::row_to_message,
+            )?;
+            rows.collect()
+        })
+    }
 
-            let rows = if let Some(before) = before_timestamp {
-                stmt.query_map(
-                    params![conversation_id, before, limit],
-                    Self::row_to_message,
-                )?
-            } else {
-                stmt.query_map(params![conversation_id, limit], Self::row_to_message)?
+    /// Get one page of a conversation, oldest first.
+    ///
+    /// Returns the `limit` newest messages ordered before `before`, a
+    /// `(lamport_clock, message_id)` key. Keying on the Lamport clock instead
+    /// of the sender's wall clock keeps pages stable while new messages
+    /// arrive and when peers' clocks disagree. Expired messages are skipped.
+    pub fn get_conversation_page(
+        db: &Database,
+        conversation_id: &str,
+        limit: i64,
+        before: Option<(i64, &str)>,
+    ) -> SqliteResult<Vec<Message>> {
+        db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT id, message_id, conversation_id, sender_peer_id, recipient_peer_id,
+                        content_encrypted, content_type, reply_to_message_id, nonce_counter,
+                        lamport_clock, sent_at, received_at, delivered_at, read_at, status,
+                        edited_at, deleted_at
+                 FROM messages
+                 WHERE conversation_id = ?1
+                   AND (?2 IS NULL OR lamport_clock < ?2
+                        OR (lamport_clock = ?2 AND message_id < ?3))
+                   AND NOT {}
+                 ORDER BY lamport_clock DESC, message_id DESC
+                 LIMIT ?4",
+                expired_condition(5)
+            ))?;
+            let (before_clock, before_id) = match before {
+                Some((clock, id)) => (Some(clock), Some(id)),
+                None => (None, None),
             };
+            let now = chrono::Utc::now().timestamp();
+            let rows = stmt.query_map(
+                params![conversation_id, before_clock, before_id, limit, now],
+                Self::row_to_message,
+            )?;
 
-            rows.collect()
+            let mut messages = rows.collect::<SqliteResult<Vec<_>>>()?;
+            messages.reverse();
+            Ok(messages)
         })
     }
 
-    fn row_to_message(row: &rusqlite::Row) -> SqliteResult<Message> {
+    pub(crate) fn row_to_message(row: &rusqlite::Row) -> SqliteResult<Message> {
         Ok(Message {
             id: row.get(0)?,
             message_id: row.get(1)?,
@@ -222,6 +296,8 @@ impl MessagesRepository {
             delivered_at: row.get(12)?,
             read_at: row.get(13)?,
             status: row.get(14)?,
+            edited_at: row.get(15)?,
+            deleted_at: row.get(16)?,
         })
     }
 
@@ -346,7 +422,8 @@ impl MessagesRepository {
             let mut stmt = conn.prepare(
                 "SELECT id, message_id, conversation_id, sender_peer_id, recipient_peer_id,
                         content_encrypted, content_type, reply_to_message_id, nonce_counter,
-                        lamport_clock, sent_at, received_at, delivered_at, read_at, status
+                        lamport_clock, sent_at, received_at, delivered_at, read_at, status,
+                        edited_at, deleted_at
                  FROM messages
                  WHERE recipient_peer_id = ? AND status = 'pending'
                  ORDER BY sent_at ASC",
@@ -410,6 +487,34 @@ impl MessagesRepository {
         })
     }
 
+    /// Get the signed events of a conversation, oldest first
+    pub fn get_conversation_events(
+        db: &Database,
+        conversation_id: &str,
+    ) -> SqliteResult<Vec<StoredMessageEvent>> {
+        db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT event_id, event_type, message_id, sender_peer_id, lamport_clock,
+                        payload_cbor, signature
+                 FROM message_events
+                 WHERE conversation_id = ?
+                 ORDER BY lamport_clock ASC, id ASC",
+            )?;
+            let rows = stmt.query_map([conversation_id], |row| {
+                Ok(StoredMessageEvent {
+                    event_id: row.get(0)?,
+                    event_type: row.get(1)?,
+                    message_id: row.get(2)?,
+                    sender_peer_id: row.get(3)?,
+                    lamport_clock: row.get(4)?,
+                    payload_cbor: row.get::<_, Option<Vec<u8>>>(5)?.unwrap_or_default(),
+                    signature: row.get(6)?,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+
     /// Check if a message event exists (for deduplication)
     pub fn event_exists(db: &Database, event_id: &str) -> SqliteResult<bool> {
         db.with_connection(|conn| {
@@ -421,6 +526,186 @@ impl MessagesRepository {
             Ok(count > 0)
         })
     }
+
+    /// Replace the content of a message with an edit, unless it was deleted
+    /// or a newer edit was applied already
+    pub fn apply_edit(
+        db: &Database,
+        message_id: &str,
+        content_encrypted: &[u8],
+        nonce_counter: u64,
+        lamport_clock: i64,
+        edited_at: i64,
+    ) -> SqliteResult<bool> {
+        db.with_connection(|conn| {
+            let rows = conn.execute(
+                "UPDATE messages
+                 SET content_encrypted = ?, nonce_counter = ?, edit_lamport_clock = ?,
+                     edited_at = ?
+                 WHERE message_id = ? AND deleted_at IS NULL
+                   AND (edit_lamport_clock IS NULL OR edit_lamport_clock < ?)",
+                params![
+                    content_encrypted,
+                    nonce_counter as i64,
+                    lamport_clock,
+                    edited_at,
+                    message_id,
+                    lamport_clock
+                ],
+            )?;
+            Ok(rows > 0)
+        })
+    }
+
+    /// Delete a message for everyone: clear its content and drop its
+    /// reactions and attachment
+    pub fn apply_delete(db: &Database, message_id: &str, deleted_at: i64) -> SqliteResult<bool> {
+        db.with_connection(|conn| {
+            let rows = conn.execute(
+                "UPDATE messages SET content_encrypted = x'', deleted_at = ?
+                 WHERE message_id = ? AND deleted_at IS NULL",
+                params![deleted_at, message_id],
+            )?;
+            conn.execute(
+                "DELETE FROM message_reactions WHERE message_id = ?",
+                [message_id],
+            )?;
+            conn.execute(
+                "DELETE FROM message_attachments WHERE message_id = ?",
+                [message_id],
+            )?;
+            Ok(rows > 0)
+        })
+    }
+
+    /// Store a reaction unless a newer one from the same peer is stored
+    pub fn set_reaction(db: &Database, reaction: &MessageReaction) -> SqliteResult<bool> {
+        db.with_connection(|conn| {
+            let rows = conn.execute(
+                "INSERT INTO message_reactions (
+                    message_id, reactor_peer_id, reaction_encrypted, nonce_counter,
+                    lamport_clock, updated_at
+                 ) VALUES (?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(message_id, reactor_peer_id) DO UPDATE SET
+                    reaction_encrypted = excluded.reaction_encrypted,
+                    nonce_counter = excluded.nonce_counter,
+                    lamport_clock = excluded.lamport_clock,
+                    updated_at = excluded.updated_at
+                 WHERE excluded.lamport_clock > message_reactions.lamport_clock",
+                params![
+                    reaction.message_id,
+                    reaction.reactor_peer_id,
+                    reaction.reaction_encrypted,
+                    reaction.nonce_counter as i64,
+                    reaction.lamport_clock,
+                    reaction.updated_at,
+                ],
+            )?;
+            Ok(rows > 0)
+        })
+    }
+
+    /// Get the reactions to messages of a conversation
+    pub fn get_conversation_reactions(
+        db: &Database,
+        conversation_id: &str,
+    ) -> SqliteResult<Vec<MessageReaction>> {
+        db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT r.message_id, r.reactor_peer_id, r.reaction_encrypted, r.nonce_counter,
+                        r.lamport_clock, r.updated_at
+                 FROM message_reactions r
+                 JOIN messages m ON m.message_id = r.message_id
+                 WHERE m.conversation_id = ?
+                 ORDER BY r.updated_at ASC",
+            )?;
+            let rows = stmt.query_map([conversation_id], |row| {
+                Ok(MessageReaction {
+                    message_id: row.get(0)?,
+                    reactor_peer_id: row.get(1)?,
+                    reaction_encrypted: row.get(2)?,
+                    nonce_counter: row.get::<_, i64>(3)? as u64,
+                    lamport_clock: row.get(4)?,
+                    updated_at: row.get(5)?,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+
+    /// Record the encrypted file of an attachment message
+    pub fn add_attachment(db: &Database, attachment: &MessageAttachment) -> SqliteResult<()> {
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT OR IGNORE INTO message_attachments (message_id, blob_hash, size)
+                 VALUES (?, ?, ?)",
+                params![attachment.message_id, attachment.blob_hash, attachment.size],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Get the attachment of a message
+    pub fn get_attachment(
+        db: &Database,
+        message_id: &str,
+    ) -> SqliteResult<Option<MessageAttachment>> {
+        db.with_connection(|conn| {
+            conn.query_row(
+                "SELECT message_id, blob_hash, size FROM message_attachments
+                 WHERE message_id = ?",
+                [message_id],
+                Self::row_to_attachment,
+            )
+            .optional()
+        })
+    }
+
+    /// Attachments received from `sender_peer_id` whose file hasn't been
+    /// transferred yet
+    pub fn get_missing_attachments(
+        db: &Database,
+        sender_peer_id: &str,
+    ) -> SqliteResult<Vec<MessageAttachment>> {
+        db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT a.message_id, a.blob_hash, a.size
+                 FROM message_attachments a
+                 JOIN messages m ON m.message_id = a.message_id
+                 WHERE m.sender_peer_id = ?
+                   AND NOT EXISTS (SELECT 1 FROM blobs b WHERE b.hash = a.blob_hash)",
+            )?;
+            let rows = stmt.query_map([sender_peer_id], Self::row_to_attachment)?;
+            rows.collect()
+        })
+    }
+
+    /// Check that a file was sent to `peer_id` as an attachment, so it may
+    /// fetch it
+    pub fn is_attachment_sent_to(
+        db: &Database,
+        blob_hash: &str,
+        peer_id: &str,
+    ) -> SqliteResult<bool> {
+        db.with_connection(|conn| {
+            let count: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM message_attachments a
+                 JOIN messages m ON m.message_id = a.message_id
+                 WHERE a.blob_hash = ? AND m.recipient_peer_id = ?",
+                params![blob_hash, peer_id],
+                |row| row.get(0),
+            )?;
+            Ok(count > 0)
+        })
+    }
+
+    fn row_to_attachment(row: &rusqlite::Row) -> SqliteResult<MessageAttachment> {
+        Ok(MessageAttachment {
+            message_id: row.get(0)?,
+            blob_hash: row.get(1)?,
+            size: row.get(2)?,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -552,4 +837,142 @@ mod tests {
         assert_eq!(conversations[1].conversation_id, "conv-1");
         assert_eq!(conversations[1].peer_id, "peer-b");
     }
+
+    fn insert_text(db: &Database, message_id: &str) {
+        MessagesRepository::insert_message(
+            db,
+            &MessageData {
+                message_id: message_id.to_string(),
+                conversation_id: "conv".to_string(),
+                sender_peer_id: "peer-a".to_string(),
+                recipient_peer_id: "peer-b".to_string(),
+                content_encrypted: vec![1],
+                content_type: "text".to_string(),
+                reply_to_message_id: None,
+                nonce_counter: 1,
+                lamport_clock: 1,
+                sent_at: 1000,
+                received_at: None,
+                status: MessageStatus::Sent,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_conversation_pages_are_stable() {
+        let db = create_test_db();
+        let insert = |message_id: &str, lamport_clock: i64, sent_at: i64| {
+            MessagesRepository::insert_message(
+                &db,
+                &MessageData {
+                    message_id: message_id.to_string(),
+                    conversation_id: "conv-1".to_string(),
+                    sender_peer_id: "peer-a".to_string(),
+                    recipient_peer_id: "peer-b".to_string(),
+                    content_encrypted: vec![1],
+                    content_type: "text".to_string(),
+                    reply_to_message_id: None,
+                    nonce_counter: lamport_clock as u64,
+                    lamport_clock,
+                    sent_at,
+                    received_at: None,
+                    status: MessageStatus::Sent,
+                },
+            )
+            .unwrap();
+        };
+        // Wall clocks disagree with causal order; two messages share a clock
+        insert("msg-a", 1, 3000);
+        insert("msg-b", 2, 1000);
+        insert("msg-c", 2, 2000);
+        insert("msg-d", 3, 500);
+
+        let ids = |page: &[Message]| {
+            page.iter()
+                .map(|m| m.message_id.clone())
+                .collect::<Vec<_>>()
+        };
+        let newest = MessagesRepository::get_conversation_page(&db, "conv-1", 2, None).unwrap();
+        assert_eq!(ids(&newest), vec!["msg-c", "msg-d"]);
+
+        // A message arriving meanwhile doesn't shift the next page
+        insert("msg-e", 4, 4000);
+        let first = &newest[0];
+        let older = MessagesRepository::get_conversation_page(
+            &db,
+            "conv-1",
+            2,
+            Some((first.lamport_clock, &first.message_id)),
+        )
+        .unwrap();
+        assert_eq!(ids(&older), vec!["msg-a", "msg-b"]);
+    }
+
+    #[test]
+    fn test_newest_edit_wins() {
+        let db = create_test_db();
+        insert_text(&db, "msg-1");
+
+        assert!(MessagesRepository::apply_edit(&db, "msg-1", &[3], 3, 5, 1005).unwrap());
+        // An older edit arriving late is ignored
+        assert!(!MessagesRepository::apply_edit(&db, "msg-1", &[2], 2, 4, 1004).unwrap());
+
+        let stored = MessagesRepository::get_by_message_id(&db, "msg-1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.content_encrypted, vec![3]);
+        assert_eq!(stored.nonce_counter, 3);
+        assert_eq!(stored.edited_at, Some(1005));
+    }
+
+    #[test]
+    fn test_delete_clears_content_and_blocks_edits() {
+        let db = create_test_db();
+        insert_text(&db, "msg-1");
+        MessagesRepository::add_attachment(
+            &db,
+            &MessageAttachment {
+                message_id: "msg-1".to_string(),
+                blob_hash: "abc".to_string(),
+                size: 10,
+            },
+        )
+        .unwrap();
+
+        assert!(MessagesRepository::apply_delete(&db, "msg-1", 1010).unwrap());
+        assert!(!MessagesRepository::apply_delete(&db, "msg-1", 1011).unwrap());
+        assert!(!MessagesRepository::apply_edit(&db, "msg-1", &[3], 3, 5, 1005).unwrap());
+
+        let stored = MessagesRepository::get_by_message_id(&db, "msg-1")
+            .unwrap()
+            .unwrap();
+        assert!(stored.content_encrypted.is_empty());
+        assert_eq!(stored.deleted_at, Some(1010));
+        assert!(MessagesRepository::get_attachment(&db, "msg-1")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_reactions_are_last_writer_wins() {
+        let db = create_test_db();
+        insert_text(&db, "msg-1");
+        let reaction = |ciphertext: u8, lamport_clock: i64| MessageReaction {
+            message_id: "msg-1".to_string(),
+            reactor_peer_id: "peer-b".to_string(),
+            reaction_encrypted: vec![ciphertext],
+            nonce_counter: lamport_clock as u64,
+            lamport_clock,
+            updated_at: 1000 + lamport_clock,
+        };
+
+        assert!(MessagesRepository::set_reaction(&db, &reaction(1, 2)).unwrap());
+        assert!(MessagesRepository::set_reaction(&db, &reaction(2, 4)).unwrap());
+        assert!(!MessagesRepository::set_reaction(&db, &reaction(3, 3)).unwrap());
+
+        let reactions = MessagesRepository::get_conversation_reactions(&db, "conv").unwrap();
+        assert_eq!(reactions.len(), 1);
+        assert_eq!(reactions[0].reaction_encrypted, vec![2]);
+    }
 }
@@ -1,3 +1,4 @@
+pub mod blobs_repo;
 pub mod boards_repo;
 pub mod bootstrap_repo;
 pub mod contacts_repo;
@@ -6,14 +7,29 @@ pub mod likes_repo;
 pub mod messages_repo;
 pub mod permissions_repo;
 pub mod posts_repo;
+pub mod retention_repo;
+pub mod scheduled_repo;
+pub mod search_repo;
 
+pub use blobs_repo::{Blob, BlobsRepository};
 pub use boards_repo::{Board, BoardPost, BoardsRepository, RelayCommunity};
 pub use bootstrap_repo::{AddBootstrapNodeInput, BootstrapNodeConfig, BootstrapNodesRepo};
-pub use contacts_repo::{Contact, ContactData, ContactsRepository};
+pub use contacts_repo::{Contact, ContactData, ContactsRepository, TrustLevel};
 pub use identity_repo::IdentityRepository;
 pub use likes_repo::{LikeData, LikeSummary, LikesRepository, PostLike};
-pub use messages_repo::{Conversation, Message, MessageData, MessageStatus, MessagesRepository};
+pub use messages_repo::{
+    Conversation, Message, MessageAttachment, MessageData, MessageReaction, MessageStatus,
+    MessagesRepository, StoredMessageEvent,
+};
 pub use permissions_repo::{
     Capability, GrantData, Permission, PermissionEvent, PermissionsRepository,
 };
 pub use posts_repo::{Post, PostData, PostMedia, PostMediaData, PostVisibility, PostsRepository};
+pub use retention_repo::{
+    ConversationRetention, ExpiredConversation, ExpiredMessages, RetentionRepository,
+};
+pub use scheduled_repo::{ScheduleStatus, ScheduledItem, ScheduledKind, ScheduledRepository};
+pub use search_repo::{
+    MessageSearchEntry, SearchFilters, SearchHit, SearchKind, SearchRepository,
+    SNIPPET_MATCH_END, SNIPPET_MATCH_START,
+};
@@ -1,6 +1,6 @@
 //! Posts repository for storing and retrieving wall/blog posts
 
-use crate::db::Database;
+use crate::db::{Database, SearchRepository};
 use rusqlite::{params, Connection, Result as SqliteResult};
 
 /// Post visibility
@@ -123,7 +123,9 @@ impl PostsRepository {
                     post.signature,
                 ],
             )?;
-            Ok(conn.last_insert_rowid())
+            let id = conn.last_insert_rowid();
+            SearchRepository::index_post(conn, &post.post_id)?;
+            Ok(id)
         })
     }
 
@@ -149,7 +151,9 @@ impl PostsRepository {
                     post.signature,
                 ],
             )?;
-            Ok(conn.last_insert_rowid())
+            let id = conn.last_insert_rowid();
+            SearchRepository::index_post(conn, &post.post_id)?;
+            Ok(id)
         })
     }
 
@@ -240,6 +244,39 @@ impl PostsRepository {
         })
     }
 
+    /// Get a page of an author's posts, newest first, strictly before the
+    /// `(created_at, post_id)` position when one is given
+    pub fn get_by_author_page(
+        db: &Database,
+        author_peer_id: &str,
+        limit: i64,
+        before: Option<(i64, &str)>,
+    ) -> SqliteResult<Vec<Post>> {
+        db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, post_id, author_peer_id, content_type, content_text,
+                        visibility, lamport_clock, created_at, updated_at,
+                        deleted_at, is_local, signature
+                 FROM posts
+                 WHERE author_peer_id = ?1 AND deleted_at IS NULL
+                   AND (?2 IS NULL OR created_at < ?2 OR (created_at = ?2 AND post_id < ?3))
+                 ORDER BY created_at DESC, post_id DESC
+                 LIMIT ?4",
+            )?;
+            let (before_ts, before_id) = match before {
+                Some((ts, id)) => (Some(ts), Some(id)),
+                None => (None, None),
+            };
+            let mut rows = stmt.query(params![author_peer_id, before_ts, before_id, limit])?;
+
+            let mut posts = Vec::new();
+            while let Some(row) = rows.next()? {
+                posts.push(Self::row_to_post(row)?);
+            }
+            Ok(posts)
+        })
+    }
+
     /// Get local posts (for own wall)
     pub fn get_local_posts(
         db: &Database,
@@ -297,6 +334,7 @@ impl PostsRepository {
                  WHERE post_id = ?",
                 params![content_text, updated_at, lamport_clock, post_id],
             )?;
+            SearchRepository::index_post(conn, post_id)?;
             Ok(rows > 0)
         })
     }
@@ -309,6 +347,7 @@ impl PostsRepository {
                  WHERE post_id = ? AND deleted_at IS NULL",
                 params![deleted_at, post_id],
             )?;
+            SearchRepository::index_post(conn, post_id)?;
             Ok(rows > 0)
         })
     }
@@ -583,4 +622,33 @@ mod tests {
         let hashes = PostsRepository::get_media_hashes(&db, "post-media").unwrap();
         assert_eq!(hashes, vec!["abc123"]);
     }
+
+    #[test]
+    fn test_get_by_author_page_breaks_timestamp_ties() {
+        let db = create_test_db();
+
+        for post_id in ["post-a", "post-b", "post-c"] {
+            let post = PostData {
+                post_id: post_id.to_string(),
+                author_peer_id: "peer-a".to_string(),
+                content_type: "text".to_string(),
+                content_text: Some(post_id.to_string()),
+                visibility: PostVisibility::Contacts,
+                lamport_clock: 1,
+                created_at: 1234567890,
+                signature: vec![1, 2, 3, 4],
+            };
+            PostsRepository::insert_post(&db, &post).unwrap();
+        }
+
+        let first = PostsRepository::get_by_author_page(&db, "peer-a", 2, None).unwrap();
+        let ids: Vec<_> = first.iter().map(|p| p.post_id.as_str()).collect();
+        assert_eq!(ids, vec!["post-c", "post-b"]);
+
+        let second =
+            PostsRepository::get_by_author_page(&db, "peer-a", 2, Some((1234567890, "post-b")))
+                .unwrap();
+        let ids: Vec<_> = second.iter().map(|p| p.post_id.as_str()).collect();
+        assert_eq!(ids, vec!["post-a"]);
+    }
 }
@@ -0,0 +1,300 @@
+//! Per-conversation retention settings and the expiry of old messages
+//!
+//! A message expires once `retention_secs` have passed since it was received,
+//! or since it was sent for our own messages. Expired messages are filtered
+//! out of conversation reads by `MessagesRepository` and hard-deleted, with
+//! their events, reactions and attachments, by `delete_expired`.
+
+use crate::db::Database;
+use rusqlite::{params, OptionalExtension, Result as SqliteResult};
+use std::collections::BTreeMap;
+
+/// SQL condition that is true for a row of `messages` that has expired at
+/// the instant bound to `?{now}`
+pub(crate) fn expired_condition(now_param: usize) -> String {
+    format!(
+        "EXISTS (SELECT 1 FROM conversation_retention r
+                 WHERE r.conversation_id = messages.conversation_id
+                   AND r.retention_secs IS NOT NULL
+                   AND COALESCE(messages.received_at, messages.sent_at) + r.retention_secs
+                       <= ?{now_param})"
+    )
+}
+
+/// The signed retention setting of a conversation
+#[derive(Debug, Clone)]
+pub struct ConversationRetention {
+    pub conversation_id: String,
+    /// The other peer of the conversation
+    pub peer_id: String,
+    /// None keeps messages forever
+    pub retention_secs: Option<u64>,
+    pub set_by_peer_id: String,
+    pub lamport_clock: i64,
+    pub timestamp: i64,
+    pub signature: Vec<u8>,
+    pub updated_at: i64,
+}
+
+/// Messages removed from one conversation by a sweep
+#[derive(Debug, Clone)]
+pub struct ExpiredConversation {
+    pub conversation_id: String,
+    pub peer_id: String,
+    pub message_ids: Vec<String>,
+}
+
+/// Everything a sweep removed
+#[derive(Debug, Clone, Default)]
+pub struct ExpiredMessages {
+    pub conversations: Vec<ExpiredConversation>,
+    /// Attachment blobs that may no longer be referenced
+    pub blob_hashes: Vec<String>,
+}
+
+impl ExpiredMessages {
+    pub fn is_empty(&self) -> bool {
+        self.conversations.is_empty()
+    }
+}
+
+/// Repository for retention operations
+pub struct RetentionRepository;
+
+impl RetentionRepository {
+    /// Store a retention setting if it is newer than the stored one.
+    ///
+    /// Settings are ordered by (lamport_clock, set_by_peer_id), so both peers
+    /// converge on the same setting whatever order they see updates in.
+    /// Returns true if the setting was stored.
+    pub fn upsert(db: &Database, retention: &ConversationRetention) -> SqliteResult<bool> {
+        db.with_connection(|conn| {
+            let rows = conn.execute(
+                "INSERT INTO conversation_retention
+                    (conversation_id, peer_id, retention_secs, set_by_peer_id,
+                     lamport_clock, timestamp, signature, updated_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(conversation_id) DO UPDATE SET
+                    retention_secs = excluded.retention_secs,
+                    set_by_peer_id = excluded.set_by_peer_id,
+                    lamport_clock = excluded.lamport_clock,
+                    timestamp = excluded.timestamp,
+                    signature = excluded.signature,
+                    updated_at = excluded.updated_at
+                 WHERE (excluded.lamport_clock, excluded.set_by_peer_id)
+                     > (conversation_retention.lamport_clock,
+                        conversation_retention.set_by_peer_id)",
+                params![
+                    retention.conversation_id,
+                    retention.peer_id,
+                    retention.retention_secs.map(|secs| secs as i64),
+                    retention.set_by_peer_id,
+                    retention.lamport_clock,
+                    retention.timestamp,
+                    retention.signature,
+                    retention.updated_at,
+                ],
+            )?;
+            Ok(rows > 0)
+        })
+    }
+
+    /// Get the retention setting of a conversation
+    pub fn get(
+        db: &Database,
+        conversation_id: &str,
+    ) -> SqliteResult<Option<ConversationRetention>> {
+        db.with_connection(|conn| {
+            conn.query_row(
+                "SELECT conversation_id, peer_id, retention_secs, set_by_peer_id,
+                        lamport_clock, timestamp, signature, updated_at
+                 FROM conversation_retention WHERE conversation_id = ?",
+                [conversation_id],
+                |row| {
+                    Ok(ConversationRetention {
+                        conversation_id: row.get(0)?,
+                        peer_id: row.get(1)?,
+                        retention_secs: row.get::<_, Option<i64>>(2)?.map(|secs| secs as u64),
+                        set_by_peer_id: row.get(3)?,
+                        lamport_clock: row.get(4)?,
+                        timestamp: row.get(5)?,
+                        signature: row.get(6)?,
+                        updated_at: row.get(7)?,
+                    })
+                },
+            )
+            .optional()
+        })
+    }
+
+    /// Hard-delete every message that has expired at `now`, with its events,
+    /// reactions and attachment records, in one transaction
+    pub fn delete_expired(db: &Database, now: i64) -> SqliteResult<ExpiredMessages> {
+        let expired = expired_condition(1);
+        db.with_connection_mut(|conn| {
+            let tx = conn.transaction()?;
+
+            let mut conversations: BTreeMap<String, ExpiredConversation> = BTreeMap::new();
+            {
+                let mut stmt = tx.prepare(&format!(
+                    "SELECT messages.message_id, messages.conversation_id, r.peer_id
+                     FROM messages
+                     JOIN conversation_retention r
+                       ON r.conversation_id = messages.conversation_id
+                     WHERE {expired}
+                     ORDER BY messages.lamport_clock, messages.message_id"
+                ))?;
+                let rows = stmt.query_map([now], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                })?;
+                for row in rows {
+                    let (message_id, conversation_id, peer_id) = row?;
+                    conversations
+                        .entry(conversation_id.clone())
+                        .or_insert_with(|| ExpiredConversation {
+                            conversation_id,
+                            peer_id,
+                            message_ids: Vec::new(),
+                        })
+                        .message_ids
+                        .push(message_id);
+                }
+            }
+            if conversations.is_empty() {
+                return Ok(ExpiredMessages::default());
+            }
+
+            let expired_ids = format!("SELECT message_id FROM messages WHERE {expired}");
+            let blob_hashes = {
+                let mut stmt = tx.prepare(&format!(
+                    "SELECT DISTINCT blob_hash FROM message_attachments
+                     WHERE message_id IN ({expired_ids})"
+                ))?;
+                let rows = stmt.query_map([now], |row| row.get(0))?;
+                rows.collect::<SqliteResult<Vec<String>>>()?
+            };
+            for table in ["message_events", "message_reactions", "message_attachments"] {
+                tx.execute(
+                    &format!("DELETE FROM {table} WHERE message_id IN ({expired_ids})"),
+                    [now],
+                )?;
+            }
+            tx.execute(&format!("DELETE FROM messages WHERE {expired}"), [now])?;
+
+            tx.commit()?;
+            Ok(ExpiredMessages {
+                conversations: conversations.into_values().collect(),
+                blob_hashes,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{MessageAttachment, MessageData, MessageStatus, MessagesRepository};
+
+    fn retention(secs: Option<u64>, lamport_clock: i64, set_by: &str) -> ConversationRetention {
+        ConversationRetention {
+            conversation_id: "conv".to_string(),
+            peer_id: "peer-b".to_string(),
+            retention_secs: secs,
+            set_by_peer_id: set_by.to_string(),
+            lamport_clock,
+            timestamp: 1000,
+            signature: vec![0; 64],
+            updated_at: 1000,
+        }
+    }
+
+    fn insert(db: &Database, message_id: &str, sent_at: i64, received_at: Option<i64>) {
+        MessagesRepository::insert_message(
+            db,
+            &MessageData {
+                message_id: message_id.to_string(),
+                conversation_id: "conv".to_string(),
+                sender_peer_id: "peer-a".to_string(),
+                recipient_peer_id: "peer-b".to_string(),
+                content_encrypted: vec![1, 2, 3],
+                content_type: "text".to_string(),
+                reply_to_message_id: None,
+                nonce_counter: 1,
+                lamport_clock: sent_at,
+                sent_at,
+                received_at,
+                status: MessageStatus::Sent,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_newest_setting_wins() {
+        let db = Database::in_memory().unwrap();
+
+        assert!(RetentionRepository::upsert(&db, &retention(Some(3600), 5, "peer-a")).unwrap());
+        // An older update is ignored
+        assert!(!RetentionRepository::upsert(&db, &retention(None, 4, "peer-b")).unwrap());
+        // Equal clocks are broken by peer ID
+        assert!(RetentionRepository::upsert(&db, &retention(Some(86_400), 5, "peer-b")).unwrap());
+        assert!(!RetentionRepository::upsert(&db, &retention(None, 5, "peer-a")).unwrap());
+
+        let stored = RetentionRepository::get(&db, "conv").unwrap().unwrap();
+        assert_eq!(stored.retention_secs, Some(86_400));
+        assert_eq!(stored.set_by_peer_id, "peer-b");
+    }
+
+    #[test]
+    fn test_expired_messages_are_hidden_and_deleted() {
+        let db = Database::in_memory().unwrap();
+        let now = chrono::Utc::now().timestamp();
+
+        // Sent long ago but received just now: ages from receipt
+        insert(&db, "old", now - 7200, None);
+        insert(&db, "late", now - 7200, Some(now - 60));
+        insert(&db, "new", now - 60, None);
+        MessagesRepository::add_attachment(
+            &db,
+            &MessageAttachment {
+                message_id: "old".to_string(),
+                blob_hash: "hash-old".to_string(),
+                size: 3,
+            },
+        )
+        .unwrap();
+
+        // No setting keeps everything
+        assert!(RetentionRepository::delete_expired(&db, now)
+            .unwrap()
+            .is_empty());
+
+        RetentionRepository::upsert(&db, &retention(Some(3600), 1, "peer-a")).unwrap();
+        let visible = MessagesRepository::get_conversation_messages(&db, "conv", 10, None).unwrap();
+        let ids: Vec<_> = visible.iter().map(|m| m.message_id.as_str()).collect();
+        assert_eq!(ids, vec!["late", "new"]);
+
+        let expired = RetentionRepository::delete_expired(&db, now).unwrap();
+        assert_eq!(expired.conversations.len(), 1);
+        assert_eq!(expired.conversations[0].peer_id, "peer-b");
+        assert_eq!(
+            expired.conversations[0].message_ids,
+            vec!["old".to_string()]
+        );
+        assert_eq!(expired.blob_hashes, vec!["hash-old".to_string()]);
+        assert!(!MessagesRepository::message_exists(&db, "old").unwrap());
+        assert!(MessagesRepository::get_attachment(&db, "old")
+            .unwrap()
+            .is_none());
+
+        // Turning retention off keeps the rest
+        RetentionRepository::upsert(&db, &retention(None, 2, "peer-a")).unwrap();
+        assert!(RetentionRepository::delete_expired(&db, now + 86_400)
+            .unwrap()
+            .is_empty());
+    }
+}
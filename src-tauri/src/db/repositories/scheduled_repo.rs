@@ -0,0 +1,335 @@
+//! Posts and messages queued to be published at a later time
+
+use crate::db::Database;
+use rusqlite::{params, OptionalExtension, Result as SqliteResult};
+
+/// What a scheduled item publishes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduledKind {
+    Post,
+    Message,
+}
+
+impl ScheduledKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScheduledKind::Post => "post",
+            ScheduledKind::Message => "message",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "post" => Some(ScheduledKind::Post),
+            "message" => Some(ScheduledKind::Message),
+            _ => None,
+        }
+    }
+}
+
+/// Where a scheduled item is in its life
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleStatus {
+    /// Waiting for its publish time
+    Pending,
+    /// Claimed by the scheduler and being published
+    Publishing,
+    Published,
+    Failed,
+    Canceled,
+}
+
+impl ScheduleStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScheduleStatus::Pending => "pending",
+            ScheduleStatus::Publishing => "publishing",
+            ScheduleStatus::Published => "published",
+            ScheduleStatus::Failed => "failed",
+            ScheduleStatus::Canceled => "canceled",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(ScheduleStatus::Pending),
+            "publishing" => Some(ScheduleStatus::Publishing),
+            "published" => Some(ScheduleStatus::Published),
+            "failed" => Some(ScheduleStatus::Failed),
+            "canceled" => Some(ScheduleStatus::Canceled),
+            _ => None,
+        }
+    }
+}
+
+/// A stored scheduled item
+#[derive(Debug, Clone)]
+pub struct ScheduledItem {
+    pub schedule_id: String,
+    pub kind: ScheduledKind,
+    /// Messages only
+    pub recipient_peer_id: Option<String>,
+    pub content_type: String,
+    /// Content encrypted with the local scheduling key; None for a post
+    /// without text
+    pub content_encrypted: Option<Vec<u8>>,
+    /// Posts only
+    pub visibility: Option<String>,
+    /// Messages only
+    pub reply_to_message_id: Option<String>,
+    pub publish_at: i64,
+    pub status: ScheduleStatus,
+    pub created_at: i64,
+    pub published_at: Option<i64>,
+    /// ID of the post or message once published
+    pub published_id: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Repository for scheduled items
+pub struct ScheduledRepository;
+
+impl ScheduledRepository {
+    /// Queue an item
+    pub fn insert(db: &Database, item: &ScheduledItem) -> SqliteResult<()> {
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO scheduled_items
+                    (schedule_id, kind, recipient_peer_id, content_type, content_encrypted,
+                     visibility, reply_to_message_id, publish_at, status, created_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    item.schedule_id,
+                    item.kind.as_str(),
+                    item.recipient_peer_id,
+                    item.content_type,
+                    item.content_encrypted,
+                    item.visibility,
+                    item.reply_to_message_id,
+                    item.publish_at,
+                    item.status.as_str(),
+                    item.created_at,
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Get an item by ID
+    pub fn get(db: &Database, schedule_id: &str) -> SqliteResult<Option<ScheduledItem>> {
+        db.with_connection(|conn| {
+            conn.query_row(
+                "SELECT schedule_id, kind, recipient_peer_id, content_type, content_encrypted,
+                        visibility, reply_to_message_id, publish_at, status, created_at,
+                        published_at, published_id, error
+                 FROM scheduled_items WHERE schedule_id = ?",
+                [schedule_id],
+                Self::row_to_item,
+            )
+            .optional()
+        })
+    }
+
+    /// List items, soonest first; only pending ones unless `include_finished`
+    pub fn list(db: &Database, include_finished: bool) -> SqliteResult<Vec<ScheduledItem>> {
+        db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT schedule_id, kind, recipient_peer_id, content_type, content_encrypted,
+                        visibility, reply_to_message_id, publish_at, status, created_at,
+                        published_at, published_id, error
+                 FROM scheduled_items
+                 WHERE ?1 OR status = 'pending'
+                 ORDER BY publish_at, created_at",
+            )?;
+            let rows = stmt.query_map([include_finished], Self::row_to_item)?;
+            rows.collect()
+        })
+    }
+
+    /// Pending items whose publish time has come, oldest first
+    pub fn due(db: &Database, now: i64, limit: i64) -> SqliteResult<Vec<ScheduledItem>> {
+        db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT schedule_id, kind, recipient_peer_id, content_type, content_encrypted,
+                        visibility, reply_to_message_id, publish_at, status, created_at,
+                        published_at, published_id, error
+                 FROM scheduled_items
+                 WHERE status = 'pending' AND publish_at <= ?
+                 ORDER BY publish_at, created_at
+                 LIMIT ?",
+            )?;
+            let rows = stmt.query_map(params![now, limit], Self::row_to_item)?;
+            rows.collect()
+        })
+    }
+
+    /// Move a pending item to publishing. Returns false if it was canceled or
+    /// claimed in the meantime.
+    pub fn claim(db: &Database, schedule_id: &str) -> SqliteResult<bool> {
+        db.with_connection(|conn| {
+            let rows = conn.execute(
+                "UPDATE scheduled_items SET status = 'publishing'
+                 WHERE schedule_id = ? AND status = 'pending'",
+                [schedule_id],
+            )?;
+            Ok(rows > 0)
+        })
+    }
+
+    /// Record that a claimed item was published as `published_id`
+    pub fn mark_published(
+        db: &Database,
+        schedule_id: &str,
+        published_id: &str,
+        published_at: i64,
+    ) -> SqliteResult<()> {
+        db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE scheduled_items
+                 SET status = 'published', published_id = ?, published_at = ?,
+                     content_encrypted = NULL
+                 WHERE schedule_id = ? AND status = 'publishing'",
+                params![published_id, published_at, schedule_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Record that a claimed item could not be published
+    pub fn mark_failed(db: &Database, schedule_id: &str, error: &str) -> SqliteResult<()> {
+        db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE scheduled_items SET status = 'failed', error = ?
+                 WHERE schedule_id = ? AND status = 'publishing'",
+                params![error, schedule_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Cancel a pending item and drop its content. Returns false if it is no
+    /// longer pending.
+    pub fn cancel(db: &Database, schedule_id: &str) -> SqliteResult<bool> {
+        db.with_connection(|conn| {
+            let rows = conn.execute(
+                "UPDATE scheduled_items SET status = 'canceled', content_encrypted = NULL
+                 WHERE schedule_id = ? AND status = 'pending'",
+                [schedule_id],
+            )?;
+            Ok(rows > 0)
+        })
+    }
+
+    /// Fail items left publishing by a crash; they may or may not have been
+    /// published, and publishing them again could duplicate them
+    pub fn fail_interrupted(db: &Database) -> SqliteResult<usize> {
+        db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE scheduled_items
+                 SET status = 'failed', error = 'Interrupted while publishing'
+                 WHERE status = 'publishing'",
+                [],
+            )
+        })
+    }
+
+    fn row_to_item(row: &rusqlite::Row) -> SqliteResult<ScheduledItem> {
+        let kind: String = row.get(1)?;
+        let status: String = row.get(8)?;
+        Ok(ScheduledItem {
+            schedule_id: row.get(0)?,
+            kind: ScheduledKind::from_str(&kind).unwrap_or(ScheduledKind::Post),
+            recipient_peer_id: row.get(2)?,
+            content_type: row.get(3)?,
+            content_encrypted: row.get(4)?,
+            visibility: row.get(5)?,
+            reply_to_message_id: row.get(6)?,
+            publish_at: row.get(7)?,
+            status: ScheduleStatus::from_str(&status).unwrap_or(ScheduleStatus::Failed),
+            created_at: row.get(9)?,
+            published_at: row.get(10)?,
+            published_id: row.get(11)?,
+            error: row.get(12)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(schedule_id: &str, publish_at: i64) -> ScheduledItem {
+        ScheduledItem {
+            schedule_id: schedule_id.to_string(),
+            kind: ScheduledKind::Message,
+            recipient_peer_id: Some("peer-b".to_string()),
+            content_type: "text".to_string(),
+            content_encrypted: Some(vec![1, 2, 3]),
+            visibility: None,
+            reply_to_message_id: None,
+            publish_at,
+            status: ScheduleStatus::Pending,
+            created_at: 100,
+            published_at: None,
+            published_id: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_due_items_are_claimed_once() {
+        let db = Database::in_memory().unwrap();
+        ScheduledRepository::insert(&db, &item("later", 2000)).unwrap();
+        ScheduledRepository::insert(&db, &item("soon", 1000)).unwrap();
+
+        assert!(ScheduledRepository::due(&db, 999, 10).unwrap().is_empty());
+        let due = ScheduledRepository::due(&db, 1500, 10).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].schedule_id, "soon");
+
+        assert!(ScheduledRepository::claim(&db, "soon").unwrap());
+        assert!(!ScheduledRepository::claim(&db, "soon").unwrap());
+        assert!(ScheduledRepository::due(&db, 1500, 10).unwrap().is_empty());
+
+        ScheduledRepository::mark_published(&db, "soon", "msg-1", 1500).unwrap();
+        let published = ScheduledRepository::get(&db, "soon").unwrap().unwrap();
+        assert_eq!(published.status, ScheduleStatus::Published);
+        assert_eq!(published.published_id.as_deref(), Some("msg-1"));
+        assert!(published.content_encrypted.is_none());
+    }
+
+    #[test]
+    fn test_cancel_and_list() {
+        let db = Database::in_memory().unwrap();
+        ScheduledRepository::insert(&db, &item("a", 1000)).unwrap();
+        ScheduledRepository::insert(&db, &item("b", 2000)).unwrap();
+
+        assert!(ScheduledRepository::cancel(&db, "a").unwrap());
+        assert!(!ScheduledRepository::cancel(&db, "a").unwrap());
+
+        let pending = ScheduledRepository::list(&db, false).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].schedule_id, "b");
+        let all = ScheduledRepository::list(&db, true).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].status, ScheduleStatus::Canceled);
+
+        // A claimed item can no longer be canceled
+        assert!(ScheduledRepository::claim(&db, "b").unwrap());
+        assert!(!ScheduledRepository::cancel(&db, "b").unwrap());
+    }
+
+    #[test]
+    fn test_interrupted_items_fail() {
+        let db = Database::in_memory().unwrap();
+        ScheduledRepository::insert(&db, &item("a", 1000)).unwrap();
+        ScheduledRepository::claim(&db, "a").unwrap();
+
+        assert_eq!(ScheduledRepository::fail_interrupted(&db).unwrap(), 1);
+        let failed = ScheduledRepository::get(&db, "a").unwrap().unwrap();
+        assert_eq!(failed.status, ScheduleStatus::Failed);
+        assert!(failed.error.is_some());
+    }
+}
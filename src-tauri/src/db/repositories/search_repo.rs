@@ -0,0 +1,580 @@
+//! Search repository: FTS5 indexes over posts, board posts, contacts and messages
+//!
+//! Each index row shares the rowid of its source row. The post, board post
+//! and contact indexes are persistent and refreshed by the repositories that
+//! write those tables. Messages are stored encrypted, so their decrypted text
+//! is indexed in TEMP tables that only exist in memory while the identity is
+//! unlocked; the messaging service fills them and locking drops them.
+
+use crate::db::repositories::MessagesRepository;
+use crate::db::{Database, Message};
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, Result as SqliteResult};
+
+/// Marks the start of a matched term in a snippet
+pub const SNIPPET_MATCH_START: &str = "<mark>";
+
+/// Marks the end of a matched term in a snippet
+pub const SNIPPET_MATCH_END: &str = "</mark>";
+
+/// Tokens of context around the best match in a snippet
+const SNIPPET_TOKENS: i64 = 16;
+
+const MESSAGE_INDEX_SCHEMA: &str = "
+    CREATE VIRTUAL TABLE IF NOT EXISTS temp.message_search USING fts5(
+        body,
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+    CREATE TABLE IF NOT EXISTS temp.message_search_state (
+        id INTEGER PRIMARY KEY,
+        peer_id TEXT NOT NULL,
+        nonce_counter INTEGER NOT NULL
+    );";
+
+/// What a search hit is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SearchKind {
+    /// A direct message
+    Message,
+    /// One of our own posts
+    WallPost,
+    /// A post by a contact
+    FeedPost,
+    /// A post on a community board
+    BoardPost,
+    /// A contact, by name or bio
+    Contact,
+}
+
+impl SearchKind {
+    pub const ALL: [SearchKind; 5] = [
+        SearchKind::Message,
+        SearchKind::WallPost,
+        SearchKind::FeedPost,
+        SearchKind::BoardPost,
+        SearchKind::Contact,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchKind::Message => "message",
+            SearchKind::WallPost => "wall_post",
+            SearchKind::FeedPost => "feed_post",
+            SearchKind::BoardPost => "board_post",
+            SearchKind::Contact => "contact",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "message" => Some(SearchKind::Message),
+            "wall_post" => Some(SearchKind::WallPost),
+            "feed_post" => Some(SearchKind::FeedPost),
+            "board_post" => Some(SearchKind::BoardPost),
+            "contact" => Some(SearchKind::Contact),
+            _ => None,
+        }
+    }
+}
+
+/// Narrows a search
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilters {
+    /// Kinds of hits to return; empty means all
+    pub kinds: Vec<SearchKind>,
+    /// Only hits written by this peer, in a conversation with it, or the contact itself
+    pub peer_id: Option<String>,
+    /// Only hits created at or after this timestamp
+    pub since: Option<i64>,
+    /// Only hits created before this timestamp
+    pub until: Option<i64>,
+}
+
+impl SearchFilters {
+    fn includes(&self, kind: SearchKind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&kind)
+    }
+}
+
+/// A ranked search hit
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    /// Message, post or board post ID, or the contact's peer ID
+    pub item_id: String,
+    /// Author of the post, other party of the message, or the contact
+    pub peer_id: String,
+    /// Relay and board of a board post
+    pub relay_peer_id: Option<String>,
+    pub board_id: Option<String>,
+    pub created_at: i64,
+    /// Best matching fragment, matches wrapped in `SNIPPET_MATCH_START`/`END`
+    pub snippet: String,
+    /// BM25 rank; lower is better
+    pub rank: f64,
+}
+
+/// Decrypted text of a message, ready to be indexed
+#[derive(Debug, Clone)]
+pub struct MessageSearchEntry {
+    /// Row ID of the message
+    pub id: i64,
+    /// The other party of the conversation
+    pub peer_id: String,
+    /// Nonce counter of the indexed ciphertext; changes when the message is edited
+    pub nonce_counter: u64,
+    pub text: String,
+}
+
+/// Repository for search operations
+pub struct SearchRepository;
+
+impl SearchRepository {
+    /// Turn user input into an FTS5 query matching every word as a prefix.
+    /// Returns `None` if the input has no words.
+    pub fn match_expression(input: &str) -> Option<String> {
+        let terms: Vec<String> = input
+            .split_whitespace()
+            .map(|word| word.replace('"', ""))
+            .filter(|word| !word.is_empty())
+            .map(|word| format!("\"{}\"*", word))
+            .collect();
+        (!terms.is_empty()).then(|| terms.join(" "))
+    }
+
+    // ============================================================
+    // Persistent indexes, refreshed by the owning repositories
+    // ============================================================
+
+    /// Re-index a post from its row; deleted posts drop out
+    pub fn index_post(conn: &Connection, post_id: &str) -> SqliteResult<()> {
+        conn.execute(
+            "DELETE FROM post_search WHERE rowid = (SELECT id FROM posts WHERE post_id = ?)",
+            [post_id],
+        )?;
+        conn.execute(
+            "INSERT INTO post_search (rowid, content_text)
+             SELECT id, content_text FROM posts
+             WHERE post_id = ? AND deleted_at IS NULL AND content_text IS NOT NULL",
+            [post_id],
+        )?;
+        Ok(())
+    }
+
+    /// Re-index a board post from its row; deleted posts drop out
+    pub fn index_board_post(
+        conn: &Connection,
+        post_id: &str,
+        relay_peer_id: &str,
+    ) -> SqliteResult<()> {
+        conn.execute(
+            "DELETE FROM board_post_search WHERE rowid =
+                 (SELECT rowid FROM board_posts WHERE post_id = ? AND relay_peer_id = ?)",
+            params![post_id, relay_peer_id],
+        )?;
+        conn.execute(
+            "INSERT INTO board_post_search (rowid, author_display_name, content_text)
+             SELECT rowid, author_display_name, content_text FROM board_posts
+             WHERE post_id = ? AND relay_peer_id = ? AND deleted_at IS NULL",
+            params![post_id, relay_peer_id],
+        )?;
+        Ok(())
+    }
+
+    /// Re-index a contact from its row
+    pub fn index_contact(conn: &Connection, peer_id: &str) -> SqliteResult<()> {
+        Self::unindex_contact(conn, peer_id)?;
+        conn.execute(
+            "INSERT INTO contact_search (rowid, display_name, bio)
+             SELECT id, display_name, bio FROM contacts WHERE peer_id = ?",
+            [peer_id],
+        )?;
+        Ok(())
+    }
+
+    /// Drop a contact from the index; call before deleting its row
+    pub fn unindex_contact(conn: &Connection, peer_id: &str) -> SqliteResult<()> {
+        conn.execute(
+            "DELETE FROM contact_search WHERE rowid = (SELECT id FROM contacts WHERE peer_id = ?)",
+            [peer_id],
+        )?;
+        Ok(())
+    }
+
+    // ============================================================
+    // Decrypted message index, kept only in memory
+    // ============================================================
+
+    /// Whether the message index exists, i.e. was filled since the last lock
+    pub fn has_message_index(db: &Database) -> SqliteResult<bool> {
+        db.with_connection(Self::has_message_index_inner)
+    }
+
+    fn has_message_index_inner(conn: &Connection) -> SqliteResult<bool> {
+        conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_temp_master WHERE name = 'message_search'",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .map(|count| count > 0)
+    }
+
+    /// Create the message index if needed, drop deleted messages from it and
+    /// return the messages that are missing or were edited since indexing
+    pub fn stale_messages(db: &Database) -> SqliteResult<Vec<Message>> {
+        db.with_connection(|conn| {
+            conn.execute_batch(MESSAGE_INDEX_SCHEMA)?;
+            conn.execute(
+                "DELETE FROM temp.message_search WHERE rowid IN (
+                     SELECT s.id FROM temp.message_search_state s
+                     LEFT JOIN messages m ON m.id = s.id
+                     WHERE m.id IS NULL OR m.deleted_at IS NOT NULL)",
+                [],
+            )?;
+            conn.execute(
+                "DELETE FROM temp.message_search_state
+                 WHERE id NOT IN (SELECT id FROM messages WHERE deleted_at IS NULL)",
+                [],
+            )?;
+
+            let mut stmt = conn.prepare(
+                "SELECT m.id, m.message_id, m.conversation_id, m.sender_peer_id,
+                        m.recipient_peer_id, m.content_encrypted, m.content_type,
+                        m.reply_to_message_id, m.nonce_counter, m.lamport_clock, m.sent_at,
+                        m.received_at, m.delivered_at, m.read_at, m.status, m.edited_at,
+                        m.deleted_at
+                 FROM messages m
+                 LEFT JOIN temp.message_search_state s ON s.id = m.id
+                 WHERE m.deleted_at IS NULL
+                   AND (s.id IS NULL OR s.nonce_counter != m.nonce_counter)",
+            )?;
+            let rows = stmt.query_map([], MessagesRepository::row_to_message)?;
+            rows.collect()
+        })
+    }
+
+    /// Index decrypted messages, replacing earlier text of the same messages
+    pub fn index_messages(db: &Database, entries: &[MessageSearchEntry]) -> SqliteResult<()> {
+        db.with_connection_mut(|conn| {
+            let tx = conn.transaction()?;
+            tx.execute_batch(MESSAGE_INDEX_SCHEMA)?;
+            for entry in entries {
+                tx.execute("DELETE FROM temp.message_search WHERE rowid = ?", [entry.id])?;
+                tx.execute(
+                    "INSERT INTO temp.message_search (rowid, body) VALUES (?, ?)",
+                    params![entry.id, entry.text],
+                )?;
+                tx.execute(
+                    "INSERT INTO temp.message_search_state (id, peer_id, nonce_counter)
+                     VALUES (?, ?, ?)
+                     ON CONFLICT(id) DO UPDATE SET nonce_counter = excluded.nonce_counter",
+                    params![entry.id, entry.peer_id, entry.nonce_counter as i64],
+                )?;
+            }
+            tx.commit()
+        })
+    }
+
+    /// Forget all decrypted message text
+    pub fn drop_message_index(db: &Database) -> SqliteResult<()> {
+        db.with_connection(|conn| {
+            conn.execute_batch(
+                "DROP TABLE IF EXISTS temp.message_search;
+                 DROP TABLE IF EXISTS temp.message_search_state;",
+            )
+        })
+    }
+
+    // ============================================================
+    // Queries
+    // ============================================================
+
+    /// Search every requested index for an FTS5 match expression (see
+    /// [`Self::match_expression`]), best hits first. Messages are only
+    /// searched while their index exists.
+    pub fn search(
+        db: &Database,
+        expression: &str,
+        filters: &SearchFilters,
+        limit: i64,
+    ) -> SqliteResult<Vec<SearchHit>> {
+        db.with_connection(|conn| {
+            let mut selects: Vec<String> = Vec::new();
+            let mut values: Vec<Value> = Vec::new();
+
+            // Each select yields: kind, item_id, peer_id, relay_peer_id,
+            // board_id, created_at, snippet, rank
+            let mut add = |sql: String, peer_column: &str, time_column: &str| {
+                values.push(Value::Text(SNIPPET_MATCH_START.to_string()));
+                values.push(Value::Text(SNIPPET_MATCH_END.to_string()));
+                values.push(Value::Integer(SNIPPET_TOKENS));
+                values.push(Value::Text(expression.to_string()));
+                let mut sql = sql;
+                if let Some(ref peer_id) = filters.peer_id {
+                    sql.push_str(&format!(" AND {} = ?", peer_column));
+                    values.push(Value::Text(peer_id.clone()));
+                }
+                if let Some(since) = filters.since {
+                    sql.push_str(&format!(" AND {} >= ?", time_column));
+                    values.push(Value::Integer(since));
+                }
+                if let Some(until) = filters.until {
+                    sql.push_str(&format!(" AND {} < ?", time_column));
+                    values.push(Value::Integer(until));
+                }
+                selects.push(sql);
+            };
+
+            let wall = filters.includes(SearchKind::WallPost);
+            let feed = filters.includes(SearchKind::FeedPost);
+            if wall || feed {
+                let mut sql = "SELECT CASE WHEN p.is_local = 1 THEN 'wall_post' ELSE 'feed_post' END,
+                        p.post_id, p.author_peer_id, NULL, NULL, p.created_at,
+                        snippet(post_search, 0, ?, ?, '…', ?), bm25(post_search)
+                 FROM post_search JOIN posts p ON p.id = post_search.rowid
+                 WHERE post_search MATCH ? AND p.deleted_at IS NULL"
+                    .to_string();
+                if wall != feed {
+                    sql.push_str(if wall {
+                        " AND p.is_local = 1"
+                    } else {
+                        " AND p.is_local = 0"
+                    });
+                }
+                add(sql, "p.author_peer_id", "p.created_at");
+            }
+            if filters.includes(SearchKind::BoardPost) {
+                add(
+                    "SELECT 'board_post', bp.post_id, bp.author_peer_id, bp.relay_peer_id,
+                            bp.board_id, bp.created_at,
+                            snippet(board_post_search, -1, ?, ?, '…', ?), bm25(board_post_search)
+                     FROM board_post_search JOIN board_posts bp ON bp.rowid = board_post_search.rowid
+                     WHERE board_post_search MATCH ? AND bp.deleted_at IS NULL"
+                        .to_string(),
+                    "bp.author_peer_id",
+                    "bp.created_at",
+                );
+            }
+            if filters.includes(SearchKind::Contact) {
+                add(
+                    "SELECT 'contact', c.peer_id, c.peer_id, NULL, NULL, c.added_at,
+                            snippet(contact_search, -1, ?, ?, '…', ?), bm25(contact_search)
+                     FROM contact_search JOIN contacts c ON c.id = contact_search.rowid
+                     WHERE contact_search MATCH ?"
+                        .to_string(),
+                    "c.peer_id",
+                    "c.added_at",
+                );
+            }
+            if filters.includes(SearchKind::Message) && Self::has_message_index_inner(conn)? {
+                add(
+                    "SELECT 'message', m.message_id, s.peer_id, NULL, NULL, m.sent_at,
+                            snippet(message_search, 0, ?, ?, '…', ?), bm25(message_search)
+                     FROM temp.message_search
+                     JOIN temp.message_search_state s ON s.id = message_search.rowid
+                     JOIN messages m ON m.id = message_search.rowid
+                     WHERE message_search MATCH ? AND m.deleted_at IS NULL"
+                        .to_string(),
+                    "s.peer_id",
+                    "m.sent_at",
+                );
+            }
+
+            if selects.is_empty() {
+                return Ok(Vec::new());
+            }
+            let sql = format!(
+                "{} ORDER BY 8, 6 DESC LIMIT ?",
+                selects.join(" UNION ALL ")
+            );
+            values.push(Value::Integer(limit));
+
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+                let kind: String = row.get(0)?;
+                Ok(SearchHit {
+                    kind: SearchKind::from_str(&kind).unwrap_or(SearchKind::Message),
+                    item_id: row.get(1)?,
+                    peer_id: row.get(2)?,
+                    relay_peer_id: row.get(3)?,
+                    board_id: row.get(4)?,
+                    created_at: row.get(5)?,
+                    snippet: row.get(6)?,
+                    rank: row.get(7)?,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{
+        BoardsRepository, ContactData, ContactsRepository, MessageData, MessageStatus, PostData,
+        PostVisibility, PostsRepository,
+    };
+
+    fn search(db: &Database, input: &str, filters: &SearchFilters) -> Vec<SearchHit> {
+        let expression = SearchRepository::match_expression(input).unwrap();
+        SearchRepository::search(db, &expression, filters, 20).unwrap()
+    }
+
+    fn insert_post(db: &Database, post_id: &str, text: &str, local: bool) {
+        let post = PostData {
+            post_id: post_id.to_string(),
+            author_peer_id: if local { "me" } else { "alice" }.to_string(),
+            content_type: "text".to_string(),
+            content_text: Some(text.to_string()),
+            visibility: PostVisibility::Contacts,
+            lamport_clock: 1,
+            created_at: 100,
+            signature: vec![0],
+        };
+        if local {
+            PostsRepository::insert_post(db, &post).unwrap();
+        } else {
+            PostsRepository::insert_remote_post(db, &post).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_match_expression() {
+        assert_eq!(
+            SearchRepository::match_expression("  sail \"boat\" ").as_deref(),
+            Some("\"sail\"* \"boat\"*")
+        );
+        assert_eq!(SearchRepository::match_expression(" \"\" "), None);
+    }
+
+    #[test]
+    fn test_posts_follow_edits_and_deletes() {
+        let db = Database::in_memory().unwrap();
+        insert_post(&db, "wall-1", "Sailing the harbour at dawn", true);
+        insert_post(&db, "feed-1", "Sailboats everywhere", false);
+
+        let hits = search(&db, "sail", &SearchFilters::default());
+        assert_eq!(hits.len(), 2);
+        assert!(hits[0].snippet.contains("<mark>"));
+
+        let filters = SearchFilters {
+            kinds: vec![SearchKind::FeedPost],
+            ..Default::default()
+        };
+        let hits = search(&db, "sail", &filters);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].item_id, "feed-1");
+        assert_eq!(hits[0].peer_id, "alice");
+
+        PostsRepository::update_post(&db, "wall-1", Some("Rowing instead"), 200, 2).unwrap();
+        assert!(search(&db, "harbour", &SearchFilters::default()).is_empty());
+        assert_eq!(search(&db, "rowing", &SearchFilters::default()).len(), 1);
+
+        PostsRepository::delete_post(&db, "feed-1", 300).unwrap();
+        assert!(search(&db, "sailboats", &SearchFilters::default()).is_empty());
+    }
+
+    #[test]
+    fn test_board_posts_and_contacts() {
+        let db = Database::in_memory().unwrap();
+        BoardsRepository::upsert_board_post(
+            &db,
+            "bp-1",
+            "general",
+            "relay",
+            "bob",
+            Some("Bob"),
+            "text",
+            Some("Anyone up for chess?"),
+            1,
+            100,
+            None,
+            &[0],
+            None,
+            None,
+            0,
+        )
+        .unwrap();
+        ContactsRepository::add_contact(
+            &db,
+            &ContactData {
+                peer_id: "carol".to_string(),
+                public_key: vec![1; 32],
+                x25519_public: vec![2; 32],
+                display_name: "Carol Chessington".to_string(),
+                avatar_hash: None,
+                bio: None,
+                profile_version: 0,
+            },
+        )
+        .unwrap();
+
+        let hits = search(&db, "chess", &SearchFilters::default());
+        assert_eq!(hits.len(), 2);
+        let board = hits.iter().find(|h| h.kind == SearchKind::BoardPost).unwrap();
+        assert_eq!(board.board_id.as_deref(), Some("general"));
+        assert_eq!(board.relay_peer_id.as_deref(), Some("relay"));
+
+        // Author names are searchable too
+        assert_eq!(search(&db, "bob", &SearchFilters::default()).len(), 1);
+
+        BoardsRepository::delete_board_post(&db, "bp-1", "relay").unwrap();
+        ContactsRepository::remove_contact(&db, "carol").unwrap();
+        assert!(search(&db, "chess", &SearchFilters::default()).is_empty());
+    }
+
+    #[test]
+    fn test_message_index_is_temporary() {
+        let db = Database::in_memory().unwrap();
+        let message_id = MessagesRepository::insert_message(
+            &db,
+            &MessageData {
+                message_id: "msg-1".to_string(),
+                conversation_id: "conv".to_string(),
+                sender_peer_id: "alice".to_string(),
+                recipient_peer_id: "me".to_string(),
+                content_encrypted: vec![9; 16],
+                content_type: "text".to_string(),
+                reply_to_message_id: None,
+                nonce_counter: 1,
+                lamport_clock: 1,
+                sent_at: 100,
+                received_at: None,
+                status: MessageStatus::Delivered,
+            },
+        )
+        .unwrap();
+
+        // Nothing is searched before the index is filled
+        assert!(!SearchRepository::has_message_index(&db).unwrap());
+        assert!(search(&db, "lunch", &SearchFilters::default()).is_empty());
+
+        let stale = SearchRepository::stale_messages(&db).unwrap();
+        assert_eq!(stale.len(), 1);
+        SearchRepository::index_messages(
+            &db,
+            &[MessageSearchEntry {
+                id: message_id,
+                peer_id: "alice".to_string(),
+                nonce_counter: 1,
+                text: "Lunch at noon?".to_string(),
+            }],
+        )
+        .unwrap();
+        assert!(SearchRepository::stale_messages(&db).unwrap().is_empty());
+
+        let hits = search(&db, "lunch", &SearchFilters::default());
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].kind, SearchKind::Message);
+        assert_eq!(hits[0].peer_id, "alice");
+
+        // Edits re-encrypt with a new nonce and need re-indexing
+        MessagesRepository::apply_edit(&db, "msg-1", &[8; 16], 2, 2, 200).unwrap();
+        assert_eq!(SearchRepository::stale_messages(&db).unwrap().len(), 1);
+
+        SearchRepository::drop_message_index(&db).unwrap();
+        assert!(search(&db, "lunch", &SearchFilters::default()).is_empty());
+    }
+}
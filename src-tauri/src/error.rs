@@ -3,6 +3,7 @@ use thiserror::Error;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum ErrorCode {
     DatabaseError,
     DatabaseConnection,
@@ -27,6 +28,13 @@ pub enum ErrorCode {
     NetworkConnectionFailed,
     NetworkPeerUnreachable,
     NetworkTimeout,
+    NetworkNotRunning,
+    RateLimited,
+    QuotaExceeded,
+    PayloadTooLarge,
+    RelayOverloaded,
+    Banned,
+    VerificationRequired,
     InternalError,
 }
 
@@ -56,6 +64,13 @@ impl ErrorCode {
             ErrorCode::NetworkConnectionFailed => "Failed to connect to the network",
             ErrorCode::NetworkPeerUnreachable => "Could not reach the peer",
             ErrorCode::NetworkTimeout => "The connection timed out",
+            ErrorCode::NetworkNotRunning => "The network is not running",
+            ErrorCode::RateLimited => "Too many requests",
+            ErrorCode::QuotaExceeded => "Posting quota exceeded",
+            ErrorCode::PayloadTooLarge => "The content is too large",
+            ErrorCode::RelayOverloaded => "The relay is overloaded",
+            ErrorCode::Banned => "You are banned from this relay",
+            ErrorCode::VerificationRequired => "Verification is required for this action",
             ErrorCode::InternalError => "An unexpected error occurred",
         }
     }
@@ -70,12 +85,50 @@ impl ErrorCode {
             }
             ErrorCode::NetworkPeerUnreachable => Some("The peer may be offline. Try again later"),
             ErrorCode::NetworkTimeout => Some("Try again or check your connection"),
+            ErrorCode::NetworkNotRunning => Some("Unlock your identity to start the network"),
+            ErrorCode::RateLimited | ErrorCode::QuotaExceeded | ErrorCode::RelayOverloaded => {
+                Some("Wait a moment and try again")
+            }
+            ErrorCode::VerificationRequired => Some("Verify with the relay and try again"),
             _ => None,
         }
     }
+
+    /// HTTP status used when this error is returned over an HTTP API
+    pub fn http_status(&self) -> u16 {
+        match self {
+            ErrorCode::InvalidData | ErrorCode::ValidationError => 400,
+            ErrorCode::Unauthorized | ErrorCode::IdentityInvalidPassphrase => 401,
+            ErrorCode::PermissionDenied
+            | ErrorCode::IdentityLocked
+            | ErrorCode::Banned
+            | ErrorCode::VerificationRequired => 403,
+            ErrorCode::NotFound | ErrorCode::IdentityNotFound => 404,
+            ErrorCode::AlreadyExists => 409,
+            ErrorCode::PayloadTooLarge => 413,
+            ErrorCode::RateLimited | ErrorCode::QuotaExceeded => 429,
+            ErrorCode::NetworkError
+            | ErrorCode::NetworkConnectionFailed
+            | ErrorCode::NetworkPeerUnreachable => 502,
+            ErrorCode::NetworkNotRunning | ErrorCode::RelayOverloaded => 503,
+            ErrorCode::NetworkTimeout => 504,
+            ErrorCode::DatabaseError
+            | ErrorCode::DatabaseConnection
+            | ErrorCode::DatabaseMigration
+            | ErrorCode::CryptoError
+            | ErrorCode::CryptoKeyGeneration
+            | ErrorCode::CryptoEncryption
+            | ErrorCode::CryptoDecryption
+            | ErrorCode::IdentityError
+            | ErrorCode::SerializationError
+            | ErrorCode::IoError
+            | ErrorCode::InternalError => 500,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ErrorResponse {
     pub code: ErrorCode,
     pub message: String,
@@ -115,6 +168,15 @@ pub enum AppError {
     #[error("Identity error: {0}")]
     Identity(String),
 
+    #[error("No identity found")]
+    IdentityNotFound,
+
+    #[error("Identity is locked")]
+    IdentityLocked,
+
+    #[error("Invalid passphrase")]
+    InvalidPassphrase,
+
     #[error("Serialization error: {0}")]
     Serialization(String),
 
@@ -142,27 +204,34 @@ pub enum AppError {
     #[error("Network error: {0}")]
     Network(String),
 
+    #[error("Network is not running")]
+    NetworkNotRunning,
+
+    #[error("Connection failed: {0}")]
+    ConnectionFailed(String),
+
+    #[error("Peer unreachable: {0}")]
+    PeerUnreachable(String),
+
+    #[error("Network timeout: {0}")]
+    NetworkTimeout(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
 
 impl AppError {
+    /// Stable machine-readable code for this error, shared by Tauri commands,
+    /// the agent's HTTP API and relay board-sync errors
     pub fn error_code(&self) -> ErrorCode {
         match self {
             AppError::Database(_) => ErrorCode::DatabaseError,
             AppError::DatabaseString(_) => ErrorCode::DatabaseError,
             AppError::Crypto(_) => ErrorCode::CryptoError,
-            AppError::Identity(msg) => {
-                if msg.contains("locked") {
-                    ErrorCode::IdentityLocked
-                } else if msg.contains("not found") || msg.contains("No identity") {
-                    ErrorCode::IdentityNotFound
-                } else if msg.contains("passphrase") || msg.contains("Passphrase") {
-                    ErrorCode::IdentityInvalidPassphrase
-                } else {
-                    ErrorCode::IdentityError
-                }
-            }
+            AppError::Identity(_) => ErrorCode::IdentityError,
+            AppError::IdentityNotFound => ErrorCode::IdentityNotFound,
+            AppError::IdentityLocked => ErrorCode::IdentityLocked,
+            AppError::InvalidPassphrase => ErrorCode::IdentityInvalidPassphrase,
             AppError::Serialization(_) => ErrorCode::SerializationError,
             AppError::Io(_) => ErrorCode::IoError,
             AppError::InvalidData(_) => ErrorCode::InvalidData,
@@ -171,17 +240,11 @@ impl AppError {
             AppError::PermissionDenied(_) => ErrorCode::PermissionDenied,
             AppError::Unauthorized(_) => ErrorCode::Unauthorized,
             AppError::Validation(_) => ErrorCode::ValidationError,
-            AppError::Network(msg) => {
-                if msg.contains("timeout") || msg.contains("Timeout") {
-                    ErrorCode::NetworkTimeout
-                } else if msg.contains("unreachable") {
-                    ErrorCode::NetworkPeerUnreachable
-                } else if msg.contains("connection") || msg.contains("Connection") {
-                    ErrorCode::NetworkConnectionFailed
-                } else {
-                    ErrorCode::NetworkError
-                }
-            }
+            AppError::Network(_) => ErrorCode::NetworkError,
+            AppError::NetworkNotRunning => ErrorCode::NetworkNotRunning,
+            AppError::ConnectionFailed(_) => ErrorCode::NetworkConnectionFailed,
+            AppError::PeerUnreachable(_) => ErrorCode::NetworkPeerUnreachable,
+            AppError::NetworkTimeout(_) => ErrorCode::NetworkTimeout,
             AppError::Internal(_) => ErrorCode::InternalError,
         }
     }
@@ -206,13 +269,60 @@ pub type Result<T> = std::result::Result<T, AppError>;
 // Implement From for libp2p transport error
 impl From<libp2p::TransportError<std::io::Error>> for AppError {
     fn from(err: libp2p::TransportError<std::io::Error>) -> Self {
-        AppError::Network(err.to_string())
+        AppError::ConnectionFailed(err.to_string())
     }
 }
 
 // Implement From for libp2p dial error
 impl From<libp2p::swarm::DialError> for AppError {
     fn from(err: libp2p::swarm::DialError) -> Self {
-        AppError::Network(err.to_string())
+        AppError::PeerUnreachable(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subcases_have_distinct_codes() {
+        assert_eq!(
+            AppError::IdentityLocked.error_code(),
+            ErrorCode::IdentityLocked
+        );
+        assert_eq!(
+            AppError::InvalidPassphrase.error_code(),
+            ErrorCode::IdentityInvalidPassphrase
+        );
+        assert_eq!(
+            AppError::PeerUnreachable("offline".to_string()).error_code(),
+            ErrorCode::NetworkPeerUnreachable
+        );
+        // Codes no longer depend on the wording of the message
+        assert_eq!(
+            AppError::Network("Connection timeout".to_string()).error_code(),
+            ErrorCode::NetworkError
+        );
+    }
+
+    #[test]
+    fn test_response_serializes_code() {
+        let json = serde_json::to_value(AppError::IdentityLocked).unwrap();
+        assert_eq!(json["code"], "IDENTITY_LOCKED");
+        assert_eq!(json["details"], "Identity is locked");
+        assert!(json["recovery"].is_string());
+
+        let json = serde_json::to_value(AppError::NetworkNotRunning).unwrap();
+        assert_eq!(json["code"], "NETWORK_NOT_RUNNING");
+    }
+
+    #[test]
+    fn test_http_status() {
+        assert_eq!(ErrorCode::IdentityLocked.http_status(), 403);
+        assert_eq!(ErrorCode::IdentityInvalidPassphrase.http_status(), 401);
+        assert_eq!(ErrorCode::IdentityNotFound.http_status(), 404);
+        assert_eq!(ErrorCode::RateLimited.http_status(), 429);
+        assert_eq!(ErrorCode::NetworkPeerUnreachable.http_status(), 502);
+        assert_eq!(ErrorCode::InternalError.http_status(), 500);
     }
 }
@@ -4,26 +4,22 @@ pub mod db;
 pub mod error;
 pub mod logging;
 pub mod models;
+pub mod node;
 pub mod p2p;
 pub mod services;
 
 #[cfg(feature = "tauri-app")]
 use commands::NetworkState;
 #[cfg(feature = "tauri-app")]
-use db::Database;
-#[cfg(feature = "tauri-app")]
 use logging::{get_log_directory, LogConfig};
 #[cfg(feature = "tauri-app")]
-use services::{
-    AccountsService, BoardService, CallingService, ContactsService, ContentSyncService,
-    FeedService, IdentityService, MessagingService, PermissionsService, PostsService,
-};
+use node::HarborNode;
 #[cfg(feature = "tauri-app")]
 use std::path::PathBuf;
 #[cfg(feature = "tauri-app")]
 use std::sync::Arc;
 #[cfg(feature = "tauri-app")]
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 #[cfg(feature = "tauri-app")]
 use tracing::info;
 
@@ -123,76 +119,52 @@ pub fn run() {
 
             app.manage(LogDirectory(log_dir));
 
-            // Initialize accounts service (manages multi-account registry)
-            let accounts_service = Arc::new(AccountsService::new(app_data_dir.clone()));
-
-            // Initialize database
+            // Initialize database and services
             let db_path = get_db_path(app.handle());
             info!("Database path: {:?}", db_path);
 
-            // Migrate legacy single-account setup if needed
-            if let Ok(Some(account)) = accounts_service.migrate_legacy_account(&db_path) {
-                info!("Migrated legacy account: {}", account.display_name);
-            }
-
-            let db = Arc::new(Database::new(db_path).expect("Failed to initialize database"));
-
-            // Initialize services
-            let identity_service = Arc::new(IdentityService::new(db.clone()));
-            let contacts_service =
-                Arc::new(ContactsService::new(db.clone(), identity_service.clone()));
-            let permissions_service = Arc::new(PermissionsService::new(
-                db.clone(),
-                identity_service.clone(),
-            ));
-            let messaging_service = Arc::new(MessagingService::new(
-                db.clone(),
-                identity_service.clone(),
-                contacts_service.clone(),
-                permissions_service.clone(),
-            ));
-            let posts_service = Arc::new(PostsService::new(
-                db.clone(),
-                identity_service.clone(),
-                contacts_service.clone(),
-                permissions_service.clone(),
-            ));
-            let feed_service = Arc::new(FeedService::new(
-                db.clone(),
-                identity_service.clone(),
-                permissions_service.clone(),
-                contacts_service.clone(),
-            ));
-            let calling_service = Arc::new(CallingService::new(
-                db.clone(),
-                identity_service.clone(),
-                contacts_service.clone(),
-                permissions_service.clone(),
-            ));
-            let content_sync_service = Arc::new(ContentSyncService::new(
-                db.clone(),
-                identity_service.clone(),
-                contacts_service.clone(),
-                permissions_service.clone(),
-            ));
-            let board_service = Arc::new(BoardService::new(db.clone(), identity_service.clone()));
+            let node = Arc::new(
+                HarborNode::builder(db_path)
+                    .accounts_dir(app_data_dir.clone())
+                    .migrate_legacy_account(true)
+                    .build()
+                    .expect("Failed to initialize Harbor node"),
+            );
 
-            // Initialize network state (will be populated when identity is unlocked)
-            let network_state = NetworkState::new();
+            // Forward network events to the frontend (survives network restarts)
+            let mut events = node.subscribe_events();
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match events.recv().await {
+                        Ok(event) => {
+                            if let Err(e) = app_handle.emit("harbor:network", &event) {
+                                tracing::warn!("Failed to emit network event: {}", e);
+                            }
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                            tracing::warn!("Dropped {} network events", n);
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
 
             // Register state
-            app.manage(db);
-            app.manage(accounts_service);
-            app.manage(identity_service);
-            app.manage(contacts_service);
-            app.manage(permissions_service);
-            app.manage(messaging_service);
-            app.manage(posts_service);
-            app.manage(content_sync_service);
-            app.manage(feed_service);
-            app.manage(calling_service);
-            app.manage(board_service);
-            app.manage(network_state);
+            app.manage(node.db.clone());
+            app.manage(node.accounts_service.clone());
+            app.manage(node.identity_service.clone());
+            app.manage(node.contacts_service.clone());
+            app.manage(node.permissions_service.clone());
+            app.manage(node.messaging_service.clone());
+            app.manage(node.posts_service.clone());
+            app.manage(node.content_sync_service.clone());
+            app.manage(node.feed_service.clone());
+            app.manage(node.calling_service.clone());
+            app.manage(node.board_service.clone());
+            app.manage(node.search_service.clone());
+            app.manage(node.scheduler_service.clone());
+            app.manage(NetworkState::new(node));
 
             info!("Application setup complete");
             Ok(())
@@ -215,6 +187,9 @@ pub fn run() {
             commands::lock_identity,
             commands::update_display_name,
             commands::update_bio,
+            commands::set_avatar,
+            commands::clear_avatar,
+            commands::get_avatar,
             commands::update_passphrase_hint,
             commands::get_peer_id,
             // Network commands
@@ -224,6 +199,7 @@ pub fn run() {
             commands::bootstrap_network,
             commands::start_network,
             commands::stop_network,
+            commands::restart_network,
             commands::get_listening_addresses,
             commands::connect_to_peer,
             commands::sync_feed,
@@ -234,6 +210,9 @@ pub fn run() {
             commands::add_relay_server,
             commands::connect_to_public_relays,
             commands::get_nat_status,
+            commands::set_typing,
+            commands::set_presence,
+            commands::get_presence,
             // Bootstrap configuration commands
             commands::get_bootstrap_nodes,
             commands::add_bootstrap_node_config,
@@ -250,6 +229,11 @@ pub fn run() {
             commands::remove_contact,
             commands::is_contact,
             commands::is_contact_blocked,
+            commands::get_contact_avatar,
+            commands::get_safety_number,
+            commands::mark_contact_verified,
+            commands::verify_contact_safety_number,
+            commands::clear_contact_verification,
             commands::request_peer_identity,
             // Permission commands
             commands::grant_permission,
@@ -263,10 +247,19 @@ pub fn run() {
             // Messaging commands
             commands::send_message,
             commands::get_messages,
+            commands::get_message_page,
+            commands::export_conversation,
             commands::get_conversations,
             commands::mark_conversation_read,
             commands::get_unread_count,
             commands::get_total_unread_count,
+            commands::edit_message,
+            commands::delete_message,
+            commands::react_to_message,
+            commands::set_conversation_retention,
+            commands::get_conversation_retention,
+            commands::send_attachment,
+            commands::get_attachment,
             // Post commands
             commands::create_post,
             commands::update_post,
@@ -276,11 +269,18 @@ pub fn run() {
             commands::get_posts_by_author,
             commands::add_post_media,
             commands::get_post_media,
+            // Scheduled post and message commands
+            commands::schedule_post,
+            commands::schedule_message,
+            commands::get_scheduled_items,
+            commands::cancel_scheduled_item,
             // Feed commands
             commands::get_feed,
             commands::get_wall,
             commands::get_wall_preview,
             commands::get_wall_visibility_stats,
+            // Search commands
+            commands::search,
             // RSS commands
             commands::generate_rss_feed,
             commands::get_peer_rss_feed,
@@ -316,9 +316,14 @@ pub fn run() {
             commands::leave_community,
             commands::get_boards,
             commands::get_board_posts,
+            commands::get_board_threads,
+            commands::get_board_thread,
+            commands::sync_board_thread,
             commands::submit_board_post,
             commands::delete_board_post,
             commands::sync_board,
+            commands::subscribe_boards,
+            commands::unsubscribe_boards,
             // File commands
             commands::save_to_downloads,
         ])
@@ -0,0 +1,138 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, Result};
+use crate::models::IdentityInfo;
+
+/// Scheme prefix of shareable contact strings
+pub const CONTACT_STRING_PREFIX: &str = "harbor://";
+
+/// Contact bundle for sharing - contains everything needed to add someone as a contact
+///
+/// Shared as `harbor://<base64url(json)>`. The keys are the base64 strings of
+/// [`IdentityInfo`] encoded once more, so decoding accepts both one and two
+/// layers of base64.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactBundle {
+    /// Multiaddress for connection
+    pub multiaddr: String,
+    /// Display name
+    pub display_name: String,
+    /// Ed25519 public key (base64)
+    pub public_key: String,
+    /// X25519 public key for encryption (base64)
+    pub x25519_public: String,
+    /// Optional bio
+    pub bio: Option<String>,
+    /// Optional avatar hash
+    pub avatar_hash: Option<String>,
+}
+
+impl ContactBundle {
+    /// Bundle for `identity`, reachable at `multiaddr`
+    pub fn new(identity: &IdentityInfo, multiaddr: String) -> Self {
+        let b64 = base64::engine::general_purpose::STANDARD;
+        Self {
+            multiaddr,
+            display_name: identity.display_name.clone(),
+            public_key: b64.encode(&identity.public_key),
+            x25519_public: b64.encode(&identity.x25519_public),
+            bio: identity.bio.clone(),
+            avatar_hash: identity.avatar_hash.clone(),
+        }
+    }
+
+    /// Parse a `harbor://...` contact string
+    pub fn parse(contact_string: &str) -> Result<Self> {
+        let encoded = contact_string
+            .trim()
+            .strip_prefix(CONTACT_STRING_PREFIX)
+            .ok_or_else(|| AppError::Validation("Invalid contact string format".to_string()))?;
+
+        let json_bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|e| AppError::Validation(format!("Invalid contact encoding: {}", e)))?;
+
+        serde_json::from_slice(&json_bytes)
+            .map_err(|e| AppError::Validation(format!("Invalid contact data: {}", e)))
+    }
+
+    /// Encode as a `harbor://...` contact string
+    pub fn to_contact_string(&self) -> Result<String> {
+        let json = serde_json::to_string(self)
+            .map_err(|e| AppError::Serialization(format!("Failed to serialize contact: {}", e)))?;
+        let encoded = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json.as_bytes());
+        Ok(format!("{}{}", CONTACT_STRING_PREFIX, encoded))
+    }
+
+    /// Peer ID from the multiaddress's `/p2p/` component
+    pub fn peer_id(&self) -> Result<String> {
+        self.multiaddr
+            .rsplit_once("/p2p/")
+            .map(|(_, peer_id)| peer_id.to_string())
+            .filter(|peer_id| !peer_id.is_empty())
+            .ok_or_else(|| AppError::Validation("No peer ID in multiaddr".to_string()))
+    }
+
+    /// Raw Ed25519 public key
+    pub fn public_key_bytes(&self) -> Result<Vec<u8>> {
+        decode_key(&self.public_key)
+            .map_err(|e| AppError::Validation(format!("Invalid public key: {}", e)))
+    }
+
+    /// Raw X25519 public key
+    pub fn x25519_public_bytes(&self) -> Result<Vec<u8>> {
+        decode_key(&self.x25519_public)
+            .map_err(|e| AppError::Validation(format!("Invalid x25519 key: {}", e)))
+    }
+}
+
+/// Decode a key that may be base64-encoded once or twice
+fn decode_key(encoded: &str) -> std::result::Result<Vec<u8>, base64::DecodeError> {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let once = b64.decode(encoded)?;
+    Ok(b64.decode(&once).unwrap_or(once))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity() -> IdentityInfo {
+        let b64 = base64::engine::general_purpose::STANDARD;
+        IdentityInfo {
+            peer_id: "12D3KooWTest".to_string(),
+            public_key: b64.encode([1u8; 32]),
+            x25519_public: b64.encode([2u8; 32]),
+            display_name: "Alice".to_string(),
+            avatar_hash: None,
+            bio: Some("Hello".to_string()),
+            passphrase_hint: None,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_contact_string_roundtrip() {
+        let bundle =
+            ContactBundle::new(&identity(), "/ip4/1.2.3.4/tcp/9000/p2p/12D3KooWTest".into());
+        let parsed = ContactBundle::parse(&bundle.to_contact_string().unwrap()).unwrap();
+
+        assert_eq!(parsed.display_name, "Alice");
+        assert_eq!(parsed.bio.as_deref(), Some("Hello"));
+        assert_eq!(parsed.peer_id().unwrap(), "12D3KooWTest");
+        assert_eq!(parsed.public_key_bytes().unwrap(), vec![1u8; 32]);
+        assert_eq!(parsed.x25519_public_bytes().unwrap(), vec![2u8; 32]);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_strings() {
+        assert!(ContactBundle::parse("https://example.com").is_err());
+        assert!(ContactBundle::parse("harbor://not json").is_err());
+
+        let bundle = ContactBundle::new(&identity(), "/ip4/1.2.3.4/tcp/9000".into());
+        assert!(bundle.peer_id().is_err());
+    }
+}
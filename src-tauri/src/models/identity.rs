@@ -11,6 +11,8 @@ pub struct LocalIdentity {
     pub avatar_hash: Option<String>,
     pub bio: Option<String>,
     pub passphrase_hint: Option<String>,
+    /// Bumped on every change to name, bio or avatar
+    pub profile_version: i64,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -18,6 +20,7 @@ pub struct LocalIdentity {
 /// Identity info sent to frontend (no private keys)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct IdentityInfo {
     pub peer_id: String,
     pub public_key: String,    // base64 encoded
@@ -52,6 +55,7 @@ impl From<LocalIdentity> for IdentityInfo {
 /// Request to create a new identity
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CreateIdentityRequest {
     pub display_name: String,
     pub passphrase: String,
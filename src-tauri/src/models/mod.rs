@@ -1,3 +1,5 @@
+pub mod contact;
 pub mod identity;
 
+pub use contact::*;
 pub use identity::*;
@@ -0,0 +1,395 @@
+//! Shared Harbor runtime used by every frontend
+//!
+//! `HarborNode` owns the database, all services and the lifecycle of the
+//! `NetworkService`. The Tauri app and bastion-agent are thin adapters over it:
+//! they build a node once at startup, hand its services to their handlers and
+//! subscribe to its network events.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::db::Database;
+use crate::error::{AppError, Result};
+use crate::p2p::{NetworkConfig, NetworkEvent, NetworkHandle, NetworkService};
+use crate::services::{
+    AccountsService, BoardService, CallingService, ContactsService, ContentSyncService,
+    FeedService, IdentityService, MessagingService, PermissionsService, PostsService,
+    SchedulerService, SearchService,
+};
+
+/// Default capacity of the network event fan-out channel
+const DEFAULT_EVENT_CAPACITY: usize = 256;
+
+/// Builder for a [`HarborNode`]
+pub struct HarborNodeBuilder {
+    db_path: PathBuf,
+    accounts_dir: Option<PathBuf>,
+    network_config: NetworkConfig,
+    event_capacity: usize,
+    migrate_legacy_account: bool,
+}
+
+impl HarborNodeBuilder {
+    /// Directory holding the account registry (defaults to the database's directory)
+    pub fn accounts_dir(mut self, dir: PathBuf) -> Self {
+        self.accounts_dir = Some(dir);
+        self
+    }
+
+    /// Network configuration used every time the network is started
+    pub fn network_config(mut self, config: NetworkConfig) -> Self {
+        self.network_config = config;
+        self
+    }
+
+    /// Number of network events buffered per subscriber
+    pub fn event_capacity(mut self, capacity: usize) -> Self {
+        self.event_capacity = capacity.max(1);
+        self
+    }
+
+    /// Register a pre-multi-account database in the account registry on build
+    pub fn migrate_legacy_account(mut self, migrate: bool) -> Self {
+        self.migrate_legacy_account = migrate;
+        self
+    }
+
+    /// Open the database and wire up all services
+    pub fn build(self) -> Result<HarborNode> {
+        let accounts_dir = match self.accounts_dir {
+            Some(dir) => dir,
+            None => self
+                .db_path
+                .parent()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(".")),
+        };
+        let accounts_service = Arc::new(AccountsService::new(accounts_dir));
+
+        if self.migrate_legacy_account {
+            if let Ok(Some(account)) = accounts_service.migrate_legacy_account(&self.db_path) {
+                info!("Migrated legacy account: {}", account.display_name);
+            }
+        }
+
+        let db = Arc::new(Database::new(self.db_path)?);
+
+        let identity_service = Arc::new(IdentityService::new(db.clone()));
+        let contacts_service = Arc::new(ContactsService::new(db.clone(), identity_service.clone()));
+        let permissions_service = Arc::new(PermissionsService::new(
+            db.clone(),
+            identity_service.clone(),
+        ));
+        let messaging_service = Arc::new(MessagingService::new(
+            db.clone(),
+            identity_service.clone(),
+            contacts_service.clone(),
+            permissions_service.clone(),
+        ));
+        let posts_service = Arc::new(PostsService::new(
+            db.clone(),
+            identity_service.clone(),
+            contacts_service.clone(),
+            permissions_service.clone(),
+        ));
+        let feed_service = Arc::new(FeedService::new(
+            db.clone(),
+            identity_service.clone(),
+            permissions_service.clone(),
+            contacts_service.clone(),
+        ));
+        let calling_service = Arc::new(CallingService::new(
+            db.clone(),
+            identity_service.clone(),
+            contacts_service.clone(),
+            permissions_service.clone(),
+        ));
+        let content_sync_service = Arc::new(ContentSyncService::new(
+            db.clone(),
+            identity_service.clone(),
+            contacts_service.clone(),
+            permissions_service.clone(),
+        ));
+        let board_service = Arc::new(BoardService::new(db.clone(), identity_service.clone()));
+        let search_service = Arc::new(SearchService::new(db.clone(), messaging_service.clone()));
+        let scheduler_service = Arc::new(SchedulerService::new(
+            db.clone(),
+            identity_service.clone(),
+            permissions_service.clone(),
+            posts_service.clone(),
+            messaging_service.clone(),
+        ));
+
+        // Items a previous run stopped in the middle of publishing may have
+        // gone out already, so they are failed rather than retried
+        match scheduler_service.recover_interrupted() {
+            Ok(0) => {}
+            Ok(count) => warn!(
+                "Failed {} scheduled items interrupted while publishing",
+                count
+            ),
+            Err(e) => warn!("Failed to recover interrupted scheduled items: {}", e),
+        }
+
+        let (event_tx, _) = broadcast::channel(self.event_capacity);
+
+        Ok(HarborNode {
+            db,
+            accounts_service,
+            identity_service,
+            contacts_service,
+            permissions_service,
+            messaging_service,
+            posts_service,
+            feed_service,
+            calling_service,
+            content_sync_service,
+            board_service,
+            search_service,
+            scheduler_service,
+            network_config: self.network_config,
+            network: RwLock::new(None),
+            event_tx,
+        })
+    }
+}
+
+/// A running network service and the task driving it
+struct RunningNetwork {
+    handle: NetworkHandle,
+    task: JoinHandle<()>,
+}
+
+/// The Harbor runtime: database, services and network lifecycle
+pub struct HarborNode {
+    pub db: Arc<Database>,
+    pub accounts_service: Arc<AccountsService>,
+    pub identity_service: Arc<IdentityService>,
+    pub contacts_service: Arc<ContactsService>,
+    pub permissions_service: Arc<PermissionsService>,
+    pub messaging_service: Arc<MessagingService>,
+    pub posts_service: Arc<PostsService>,
+    pub feed_service: Arc<FeedService>,
+    pub calling_service: Arc<CallingService>,
+    pub content_sync_service: Arc<ContentSyncService>,
+    pub board_service: Arc<BoardService>,
+    pub search_service: Arc<SearchService>,
+    pub scheduler_service: Arc<SchedulerService>,
+    network_config: NetworkConfig,
+    network: RwLock<Option<RunningNetwork>>,
+    event_tx: broadcast::Sender<NetworkEvent>,
+}
+
+impl HarborNode {
+    /// Start building a node backed by the database at `db_path`
+    pub fn builder(db_path: PathBuf) -> HarborNodeBuilder {
+        HarborNodeBuilder {
+            db_path,
+            accounts_dir: None,
+            network_config: NetworkConfig::default(),
+            event_capacity: DEFAULT_EVENT_CAPACITY,
+            migrate_legacy_account: false,
+        }
+    }
+
+    /// Subscribe to network events. Subscriptions survive network restarts.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<NetworkEvent> {
+        self.event_tx.subscribe()
+    }
+
+    /// Handle to the running network
+    pub async fn network_handle(&self) -> Result<NetworkHandle> {
+        self.network
+            .read()
+            .await
+            .as_ref()
+            .map(|running| running.handle.clone())
+            .ok_or(AppError::NetworkNotRunning)
+    }
+
+    /// Whether the network is running
+    pub async fn is_network_running(&self) -> bool {
+        self.network.read().await.is_some()
+    }
+
+    /// Start the P2P network (requires an unlocked identity). No-op if already running.
+    pub async fn start_network(&self) -> Result<NetworkHandle> {
+        if !self.identity_service.is_unlocked() {
+            return Err(AppError::PermissionDenied(
+                "Identity must be unlocked to start network".to_string(),
+            ));
+        }
+
+        let mut guard = self.network.write().await;
+        if let Some(running) = guard.as_ref() {
+            return Ok(running.handle.clone());
+        }
+
+        // Derive the libp2p keypair from the unlocked identity
+        let unlocked_keys = self.identity_service.get_unlocked_keys()?;
+        let ed25519_bytes = unlocked_keys.ed25519_signing.to_bytes();
+        let keypair = crate::p2p::swarm::ed25519_to_libp2p_keypair(&ed25519_bytes)?;
+        let network_peer_id = libp2p::PeerId::from(keypair.public());
+
+        if let Ok(Some(identity_info)) = self.identity_service.get_identity_info() {
+            if identity_info.peer_id != network_peer_id.to_string() {
+                error!(
+                    "PEER ID MISMATCH! Stored {} does not match network {}. Messaging will fail.",
+                    identity_info.peer_id, network_peer_id
+                );
+            }
+        }
+
+        let (mut service, handle, mut event_rx) = NetworkService::new(
+            self.network_config.clone(),
+            self.identity_service.clone(),
+            keypair,
+        )?;
+
+        service.set_messaging_service(self.messaging_service.clone());
+        service.set_contacts_service(self.contacts_service.clone());
+        service.set_permissions_service(self.permissions_service.clone());
+        service.set_posts_service(self.posts_service.clone());
+        service.set_content_sync_service(self.content_sync_service.clone());
+        service.set_board_service(self.board_service.clone());
+        service.set_scheduler_service(self.scheduler_service.clone());
+
+        let task = tokio::spawn(async move {
+            info!("Network service starting in background task");
+            service.run().await;
+            info!("Network service stopped");
+        });
+
+        // Fan events out to every subscriber; ends when the service drops its sender
+        let event_tx = self.event_tx.clone();
+        tokio::spawn(async move {
+            while let Some(event) = event_rx.recv().await {
+                info!("Network event: {:?}", event);
+                // No subscribers is not an error
+                let _ = event_tx.send(event);
+            }
+        });
+
+        *guard = Some(RunningNetwork {
+            handle: handle.clone(),
+            task,
+        });
+
+        info!("Network started successfully");
+        Ok(handle)
+    }
+
+    /// Stop the P2P network and wait for the service to exit. No-op if not running.
+    pub async fn stop_network(&self) -> Result<()> {
+        let running = self.network.write().await.take();
+
+        if let Some(running) = running {
+            if let Err(e) = running.handle.shutdown().await {
+                warn!("Network service already gone: {}", e);
+            }
+            if let Err(e) = running.task.await {
+                warn!("Network task ended abnormally: {}", e);
+            }
+            info!("Network stopped");
+        }
+
+        Ok(())
+    }
+
+    /// Stop the network (if running) and start it again
+    pub async fn restart_network(&self) -> Result<NetworkHandle> {
+        self.stop_network().await?;
+        self.start_network().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CreateIdentityRequest;
+    use std::time::Duration;
+
+    fn create_temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("harbor_node_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn build_node(dir: &std::path::Path) -> HarborNode {
+        let config = NetworkConfig {
+            enable_mdns: false,
+            enable_dht: false,
+            enable_relay_client: false,
+            enable_dcutr: false,
+            enable_autonat: false,
+            ..Default::default()
+        };
+        HarborNode::builder(dir.join("harbor.db"))
+            .network_config(config)
+            .build()
+            .unwrap()
+    }
+
+    /// Wait for the running network to report a listen address
+    async fn wait_listening(events: &mut broadcast::Receiver<NetworkEvent>) {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                match events.recv().await {
+                    Ok(NetworkEvent::ListeningOn { .. }) => return,
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => panic!("event channel closed"),
+                }
+            }
+        })
+        .await
+        .expect("network never started listening");
+    }
+
+    #[tokio::test]
+    async fn test_start_stop_restart() {
+        let dir = create_temp_dir();
+        let node = build_node(&dir);
+        let mut events = node.subscribe_events();
+
+        // The network needs an unlocked identity
+        assert!(node.start_network().await.is_err());
+        node.identity_service
+            .create_identity(CreateIdentityRequest {
+                display_name: "Node Test".to_string(),
+                passphrase: "test-passphrase".to_string(),
+                bio: None,
+                passphrase_hint: None,
+            })
+            .unwrap();
+        assert!(node.identity_service.is_unlocked());
+
+        let handle = node.start_network().await.unwrap();
+        assert!(node.is_network_running().await);
+        wait_listening(&mut events).await;
+        assert!(handle.get_stats().await.is_ok());
+
+        // Starting again is a no-op
+        node.start_network().await.unwrap();
+
+        node.stop_network().await.unwrap();
+        assert!(!node.is_network_running().await);
+        assert!(matches!(
+            node.network_handle().await,
+            Err(AppError::NetworkNotRunning)
+        ));
+        assert!(handle.get_stats().await.is_err());
+
+        // The same event subscription keeps working across the restart
+        let handle = node.restart_network().await.unwrap();
+        assert!(node.is_network_running().await);
+        wait_listening(&mut events).await;
+        assert!(handle.get_stats().await.is_ok());
+        assert!(node.network_handle().await.is_ok());
+
+        node.stop_network().await.unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -7,10 +7,17 @@ use libp2p::{
 use std::collections::HashMap;
 use std::time::Duration;
 
-use super::protocols::board_sync::{BoardSyncRequest, BoardSyncResponse};
+use super::protocols::board_sync::{
+    BoardNotification, BoardNotificationAck, BoardSyncRequest, BoardSyncResponse,
+};
 use super::protocols::{
-    BOARD_SYNC_PROTOCOL, CONTENT_SYNC_PROTOCOL, IDENTITY_PROTOCOL, MESSAGING_PROTOCOL,
+    ATTACHMENT_PROTOCOL, BOARD_NOTIFY_PROTOCOL, BOARD_SYNC_PROTOCOL, CONTENT_SYNC_PROTOCOL,
+    IDENTITY_PROTOCOL, IDENTITY_PROTOCOL_V1, MESSAGING_PROTOCOL, PROFILE_PROTOCOL, SIGNAL_PROTOCOL,
 };
+use super::types::PresenceStatus;
+use crate::error::{AppError, Result};
+use crate::services::signing::{verify, SignableIdentityResponse};
+use crate::services::CryptoService;
 
 // Duration is used in ping configuration
 
@@ -35,12 +42,21 @@ pub struct ChatBehaviour {
     /// Request-response for identity exchange
     pub identity_exchange:
         request_response::cbor::Behaviour<IdentityExchangeRequest, IdentityExchangeResponse>,
+    /// Request-response for profile pushes and avatar fetches
+    pub profile: request_response::cbor::Behaviour<ProfileRequest, ProfileResponse>,
     /// Request-response for messaging
     pub messaging: request_response::cbor::Behaviour<MessagingRequest, MessagingResponse>,
+    /// Request-response for fetching message attachments chunk by chunk
+    pub attachments: request_response::cbor::Behaviour<AttachmentRequest, AttachmentResponse>,
+    /// Request-response for typing indicators and presence
+    pub signals: request_response::cbor::Behaviour<SignalRequest, SignalAck>,
     /// Request-response for content sync (feed/wall)
     pub content_sync: request_response::cbor::Behaviour<ContentSyncRequest, ContentSyncResponse>,
     /// Request-response for board sync (community boards)
     pub board_sync: request_response::cbor::Behaviour<BoardSyncRequest, BoardSyncResponse>,
+    /// Inbound-only notifications pushed by relays for subscribed boards
+    pub board_notify:
+        request_response::cbor::Behaviour<BoardNotification, BoardNotificationAck>,
 }
 
 /// Identity exchange request (simplified for request-response)
@@ -51,19 +67,157 @@ pub struct IdentityExchangeRequest {
     pub signature: Vec<u8>,
 }
 
+/// Version of identity responses whose signature covers every field
+pub const IDENTITY_RESPONSE_VERSION: u32 = 2;
+
 /// Identity exchange response
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct IdentityExchangeResponse {
+    /// [`IDENTITY_RESPONSE_VERSION`], or 1 from peers that predate it and
+    /// sign in formats of their own
+    #[serde(default = "legacy_identity_response_version")]
+    pub version: u32,
     pub peer_id: String,
     pub public_key: Vec<u8>,
     pub x25519_public: Vec<u8>,
     pub display_name: String,
     pub avatar_hash: Option<String>,
     pub bio: Option<String>,
+    /// Bumped on every profile change; 0 from peers that predate it
+    #[serde(default)]
+    pub profile_version: i64,
     pub timestamp: i64,
+    /// Signature over [`IdentityExchangeResponse::signable`]
     pub signature: Vec<u8>,
 }
 
+fn legacy_identity_response_version() -> u32 {
+    1
+}
+
+impl IdentityExchangeResponse {
+    /// The signed payload: every field except the signature. The version is
+    /// covered so a signed response can't be relabelled as a legacy one.
+    pub fn signable(&self) -> SignableIdentityResponse {
+        SignableIdentityResponse {
+            version: self.version,
+            peer_id: self.peer_id.clone(),
+            public_key: self.public_key.clone(),
+            x25519_public: self.x25519_public.clone(),
+            display_name: self.display_name.clone(),
+            avatar_hash: self.avatar_hash.clone(),
+            bio: self.bio.clone(),
+            profile_version: self.profile_version,
+            timestamp: self.timestamp,
+        }
+    }
+
+    /// Check that `public_key` is the key behind `peer_id` and, for current
+    /// responses, that the signature covers the fields as received. Legacy
+    /// responses can't be checked beyond the key.
+    pub fn verify(&self) -> Result<()> {
+        if CryptoService::peer_id_from_public_key(&self.public_key)? != self.peer_id {
+            return Err(AppError::Crypto(
+                "Public key does not match peer ID".to_string(),
+            ));
+        }
+        if !self.is_signed() {
+            return Ok(());
+        }
+
+        let verifying_key = ed25519_dalek::VerifyingKey::from_bytes(
+            self.public_key
+                .as_slice()
+                .try_into()
+                .map_err(|_| AppError::Crypto("Invalid public key length".to_string()))?,
+        )
+        .map_err(|e| AppError::Crypto(format!("Invalid public key: {}", e)))?;
+        if !verify(&verifying_key, &self.signable(), &self.signature)? {
+            return Err(AppError::Crypto(
+                "Invalid identity response signature".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the signature covers every field
+    pub fn is_signed(&self) -> bool {
+        self.version >= IDENTITY_RESPONSE_VERSION
+    }
+}
+
+/// Profile protocol request
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProfileRequest {
+    /// The sender's profile changed; same payload as an identity response
+    Update { profile: IdentityExchangeResponse },
+    /// Fetch the image behind the responder's avatar hash
+    FetchAvatar { avatar_hash: String },
+}
+
+/// Profile protocol response
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProfileResponse {
+    /// Update received; not accepted if stale or not from a contact
+    Ack { accepted: bool },
+    /// Avatar image; the receiver checks it against the hash
+    Avatar {
+        avatar_hash: String,
+        mime_type: String,
+        data: Vec<u8>,
+    },
+    /// Error response
+    Error { error: String },
+}
+
+/// Attachment protocol request: one chunk of an encrypted attachment
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AttachmentRequest {
+    pub blob_hash: String,
+    pub chunk_index: u32,
+}
+
+/// Attachment protocol response
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AttachmentResponse {
+    /// The requested chunk; shorter than the chunk size only for the last one
+    Chunk { data: Vec<u8> },
+    /// Error response
+    Error { error: String },
+}
+
+/// How often a peer that keeps typing repeats its typing signal
+pub const TYPING_REFRESH_SECS: u64 = 3;
+
+/// How long a typing signal lasts unless it is repeated
+pub const TYPING_TIMEOUT_SECS: u64 = 6;
+
+/// Longest custom status text, in characters
+pub const MAX_STATUS_TEXT_CHARS: usize = 80;
+
+/// Signal protocol request. Signals are ephemeral and never stored.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SignalRequest {
+    /// The sender started or stopped typing to the receiver
+    Typing { typing: bool },
+    /// The sender's presence
+    Presence {
+        status: PresenceStatus,
+        status_text: Option<String>,
+    },
+}
+
+/// Signal protocol response
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SignalAck {
+    /// False if the sender lacks the capability or is rate limited
+    pub accepted: bool,
+}
+
 /// Messaging request
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct MessagingRequest {
@@ -178,10 +332,22 @@ impl ChatBehaviour {
 
         // Identity exchange protocol
         let identity_exchange = request_response::cbor::Behaviour::new(
-            [(
-                StreamProtocol::new(IDENTITY_PROTOCOL),
-                ProtocolSupport::Full,
-            )],
+            [
+                (
+                    StreamProtocol::new(IDENTITY_PROTOCOL),
+                    ProtocolSupport::Full,
+                ),
+                (
+                    StreamProtocol::new(IDENTITY_PROTOCOL_V1),
+                    ProtocolSupport::Full,
+                ),
+            ],
+            request_response::Config::default(),
+        );
+
+        // Profile protocol
+        let profile = request_response::cbor::Behaviour::new(
+            [(StreamProtocol::new(PROFILE_PROTOCOL), ProtocolSupport::Full)],
             request_response::Config::default(),
         );
 
@@ -194,6 +360,21 @@ impl ChatBehaviour {
             request_response::Config::default(),
         );
 
+        // Attachment protocol
+        let attachments = request_response::cbor::Behaviour::new(
+            [(
+                StreamProtocol::new(ATTACHMENT_PROTOCOL),
+                ProtocolSupport::Full,
+            )],
+            request_response::Config::default(),
+        );
+
+        // Signal protocol
+        let signals = request_response::cbor::Behaviour::new(
+            [(StreamProtocol::new(SIGNAL_PROTOCOL), ProtocolSupport::Full)],
+            request_response::Config::default(),
+        );
+
         // Content sync protocol
         let content_sync = request_response::cbor::Behaviour::new(
             [(
@@ -212,6 +393,15 @@ impl ChatBehaviour {
             request_response::Config::default(),
         );
 
+        // Board notifications (relays push, we only receive)
+        let board_notify = request_response::cbor::Behaviour::new(
+            [(
+                StreamProtocol::new(BOARD_NOTIFY_PROTOCOL),
+                ProtocolSupport::Inbound,
+            )],
+            request_response::Config::default(),
+        );
+
         Self {
             ping,
             identify,
@@ -221,9 +411,116 @@ impl ChatBehaviour {
             dcutr,
             autonat,
             identity_exchange,
+            profile,
             messaging,
+            attachments,
+            signals,
             content_sync,
             board_sync,
+            board_notify,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::signing::sign;
+
+    fn signed_response() -> IdentityExchangeResponse {
+        let (signing_key, verifying_key) = CryptoService::generate_ed25519_keypair();
+        let mut response = IdentityExchangeResponse {
+            version: IDENTITY_RESPONSE_VERSION,
+            peer_id: CryptoService::derive_peer_id_from_signing_key(&signing_key),
+            public_key: verifying_key.to_bytes().to_vec(),
+            x25519_public: vec![7; 32],
+            display_name: "Alice".to_string(),
+            avatar_hash: None,
+            bio: Some("Hello".to_string()),
+            profile_version: 3,
+            timestamp: 1234567890,
+            signature: Vec::new(),
+        };
+        response.signature = sign(&signing_key, &response.signable()).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_signed_response_covers_every_field() {
+        let response = signed_response();
+        response.verify().unwrap();
+
+        let mut tampered = response.clone();
+        tampered.bio = Some("Swapped".to_string());
+        assert!(tampered.verify().is_err());
+
+        let mut tampered = response.clone();
+        tampered.x25519_public = vec![8; 32];
+        assert!(tampered.verify().is_err());
+
+        let mut tampered = response.clone();
+        tampered.profile_version = 4;
+        assert!(tampered.verify().is_err());
+
+        let mut tampered = response;
+        tampered.version = IDENTITY_RESPONSE_VERSION + 1;
+        assert!(tampered.verify().is_err());
+    }
+
+    #[test]
+    fn test_public_key_must_derive_to_peer_id() {
+        let mut response = signed_response();
+        response.peer_id = signed_response().peer_id;
+        assert!(response.verify().is_err());
+    }
+
+    #[test]
+    fn test_legacy_response_is_checked_by_key_only() {
+        let mut response = signed_response();
+        response.version = 1;
+        response.signature = vec![1, 2, 3];
+        assert!(!response.is_signed());
+        response.verify().unwrap();
+
+        // Fields from before the version field decode as legacy
+        let mut bytes = Vec::new();
+        ciborium::into_writer(
+            &serde_json::json!({
+                "peer_id": response.peer_id,
+                "public_key": response.public_key,
+                "x25519_public": response.x25519_public,
+                "display_name": "Mock Peer",
+                "avatar_hash": null,
+                "bio": null,
+                "timestamp": 0,
+                "signature": [],
+            }),
+            &mut bytes,
+        )
+        .unwrap();
+        let decoded: IdentityExchangeResponse = ciborium::from_reader(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.version, 1);
+        assert_eq!(decoded.profile_version, 0);
+    }
+
+    #[test]
+    fn test_signal_wire_format() {
+        let presence = SignalRequest::Presence {
+            status: PresenceStatus::DoNotDisturb,
+            status_text: Some("Heads down".to_string()),
+        };
+        assert_eq!(
+            serde_json::to_value(&presence).unwrap(),
+            serde_json::json!({
+                "type": "presence",
+                "status": "do_not_disturb",
+                "status_text": "Heads down",
+            })
+        );
+
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&SignalRequest::Typing { typing: true }, &mut bytes).unwrap();
+        let decoded: SignalRequest = ciborium::from_reader(bytes.as_slice()).unwrap();
+        assert!(matches!(decoded, SignalRequest::Typing { typing: true }));
+    }
+}
@@ -1,4 +1,3 @@
-use base64::Engine;
 use futures::StreamExt;
 use libp2p::{
     autonat, dcutr, identify, kad, mdns, ping, relay,
@@ -6,9 +5,9 @@ use libp2p::{
     swarm::SwarmEvent,
     Multiaddr, PeerId, Swarm,
 };
-use std::collections::HashMap;
-use std::time::Instant;
-use tokio::sync::{mpsc, oneshot};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot, watch};
 use tracing::{debug, error, info, warn};
 
 /// Public relay servers that support libp2p relay v2
@@ -23,24 +22,42 @@ const FALLBACK_RELAYS: &[&str] = &[
     // Users can deploy their own using the AWS CloudFormation template
 ];
 
+/// Signals accepted from one peer per `SIGNAL_WINDOW`; further signals are refused
+const SIGNALS_PER_WINDOW: u32 = 20;
+
+/// Window over which incoming signals are counted
+const SIGNAL_WINDOW: Duration = Duration::from_secs(30);
+
+/// How often messages past their conversation's retention period are deleted
+const RETENTION_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Least time between vacuums, which only follow sweeps that deleted messages
+const VACUUM_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often scheduled posts and messages are checked for being due
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
 use super::behaviour::{
-    ChatBehaviour, ChatBehaviourEvent, ContentSyncRequest, ContentSyncResponse,
-    IdentityExchangeRequest, IdentityExchangeResponse, MessagingRequest, MessagingResponse,
-    PostSummaryProto,
+    AttachmentRequest, AttachmentResponse, ChatBehaviour, ChatBehaviourEvent, ContentSyncRequest,
+    ContentSyncResponse, IdentityExchangeRequest, IdentityExchangeResponse, MessagingRequest,
+    MessagingResponse, PostSummaryProto, ProfileRequest, ProfileResponse, SignalAck, SignalRequest,
+    IDENTITY_RESPONSE_VERSION, MAX_STATUS_TEXT_CHARS,
 };
 use super::config::NetworkConfig;
 use super::protocols::board_sync::{
+    BoardNotification, BoardNotificationAck, BoardPostInfo, BoardPostsMode,
     BoardSyncRequest as WireBoardSyncRequest, BoardSyncResponse as WireBoardSyncResponse,
 };
-use super::protocols::messaging::{MessagingCodec, MessagingMessage};
+use super::protocols::messaging::{MessagingCodec, MessagingMessage, ATTACHMENT_CONTENT_TYPE};
 use super::swarm::build_swarm;
 use super::types::*;
-use crate::db::Capability;
-use crate::error::{AppError, Result};
+use crate::db::{Capability, ContactData};
+use crate::error::{AppError, ErrorCode, Result};
 use crate::services::board_service::StorableBoardPost;
 use crate::services::{
-    BoardService, ContactsService, ContentSyncService, IdentityService, MessagingService,
-    PermissionsService, PostsService,
+    is_valid_attachment_chunk, BoardService, ContactUpdate, ContactsService, ContentSyncService,
+    IdentityService, MessagingService, OutgoingMessage, PermissionsService, PostsService,
+    Publication, SchedulerService, ATTACHMENT_CHUNK_SIZE,
 };
 use std::sync::Arc;
 
@@ -61,7 +78,7 @@ impl NetworkHandle {
 
         match rx.await {
             Ok(NetworkResponse::Ok) => Ok(()),
-            Ok(NetworkResponse::Error(e)) => Err(AppError::Network(e)),
+            Ok(NetworkResponse::Error(e)) => Err(AppError::PeerUnreachable(e)),
             _ => Err(AppError::Internal("Unexpected response".into())),
         }
     }
@@ -286,6 +303,58 @@ impl NetworkHandle {
                     board_id,
                     after_timestamp,
                     limit,
+                    mode: BoardPostsMode::Flat,
+                },
+                Some(tx),
+            ))
+            .await
+            .map_err(|_| AppError::Internal("Network service unavailable".into()))?;
+
+        match rx.await {
+            Ok(NetworkResponse::Ok) => Ok(()),
+            Ok(NetworkResponse::Error(e)) => Err(AppError::Network(e)),
+            _ => Err(AppError::Internal("Unexpected response".into())),
+        }
+    }
+
+    /// Get top-level posts (thread roots with reply counts) from a relay
+    pub async fn get_board_threads(
+        &self,
+        relay_peer_id: PeerId,
+        board_id: String,
+        after_timestamp: Option<i64>,
+        limit: u32,
+    ) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.command_tx
+            .send((
+                NetworkCommand::GetBoardPosts {
+                    relay_peer_id,
+                    board_id,
+                    after_timestamp,
+                    limit,
+                    mode: BoardPostsMode::Threads,
+                },
+                Some(tx),
+            ))
+            .await
+            .map_err(|_| AppError::Internal("Network service unavailable".into()))?;
+
+        match rx.await {
+            Ok(NetworkResponse::Ok) => Ok(()),
+            Ok(NetworkResponse::Error(e)) => Err(AppError::Network(e)),
+            _ => Err(AppError::Internal("Unexpected response".into())),
+        }
+    }
+
+    /// Get a whole thread (root post and replies) from a relay
+    pub async fn get_board_thread(&self, relay_peer_id: PeerId, root_post_id: String) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.command_tx
+            .send((
+                NetworkCommand::GetBoardThread {
+                    relay_peer_id,
+                    root_post_id,
                 },
                 Some(tx),
             ))
@@ -299,12 +368,13 @@ impl NetworkHandle {
         }
     }
 
-    /// Submit a board post to a relay
+    /// Submit a board post (or a reply, with `reply_to_post_id`) to a relay
     pub async fn submit_board_post(
         &self,
         relay_peer_id: PeerId,
         board_id: String,
         content_text: String,
+        reply_to_post_id: Option<String>,
     ) -> Result<()> {
         let (tx, rx) = oneshot::channel();
         self.command_tx
@@ -313,6 +383,53 @@ impl NetworkHandle {
                     relay_peer_id,
                     board_id,
                     content_text,
+                    reply_to_post_id,
+                },
+                Some(tx),
+            ))
+            .await
+            .map_err(|_| AppError::Internal("Network service unavailable".into()))?;
+
+        match rx.await {
+            Ok(NetworkResponse::Ok) => Ok(()),
+            Ok(NetworkResponse::Error(e)) => Err(AppError::Network(e)),
+            _ => Err(AppError::Internal("Unexpected response".into())),
+        }
+    }
+
+    /// Subscribe to pushed updates for boards on a relay
+    pub async fn subscribe_boards(&self, relay_peer_id: PeerId, board_ids: Vec<String>) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.command_tx
+            .send((
+                NetworkCommand::SubscribeBoards {
+                    relay_peer_id,
+                    board_ids,
+                },
+                Some(tx),
+            ))
+            .await
+            .map_err(|_| AppError::Internal("Network service unavailable".into()))?;
+
+        match rx.await {
+            Ok(NetworkResponse::Ok) => Ok(()),
+            Ok(NetworkResponse::Error(e)) => Err(AppError::Network(e)),
+            _ => Err(AppError::Internal("Unexpected response".into())),
+        }
+    }
+
+    /// Stop pushed updates for boards on a relay (all boards if empty)
+    pub async fn unsubscribe_boards(
+        &self,
+        relay_peer_id: PeerId,
+        board_ids: Vec<String>,
+    ) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.command_tx
+            .send((
+                NetworkCommand::UnsubscribeBoards {
+                    relay_peer_id,
+                    board_ids,
                 },
                 Some(tx),
             ))
@@ -403,10 +520,86 @@ impl NetworkHandle {
             _ => Err(AppError::Internal("Unexpected response".into())),
         }
     }
+
+    /// Tell a contact we started or stopped typing to it
+    pub async fn set_typing(&self, peer_id: PeerId, typing: bool) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.command_tx
+            .send((NetworkCommand::SetTyping { peer_id, typing }, Some(tx)))
+            .await
+            .map_err(|_| AppError::Internal("Network service unavailable".into()))?;
+
+        match rx.await {
+            Ok(NetworkResponse::Ok) => Ok(()),
+            Ok(NetworkResponse::Error(e)) => Err(AppError::Network(e)),
+            _ => Err(AppError::Internal("Unexpected response".into())),
+        }
+    }
+
+    /// Set our presence and share it with connected peers that may read our wall
+    pub async fn set_presence(
+        &self,
+        status: PresenceStatus,
+        status_text: Option<String>,
+    ) -> Result<()> {
+        if status == PresenceStatus::Offline {
+            return Err(AppError::Validation(
+                "Offline is not a presence that can be set".to_string(),
+            ));
+        }
+        let status_text = normalize_status_text(status_text).ok_or_else(|| {
+            AppError::Validation(format!(
+                "Status text is longer than {} characters",
+                MAX_STATUS_TEXT_CHARS
+            ))
+        })?;
+
+        let (tx, rx) = oneshot::channel();
+        self.command_tx
+            .send((
+                NetworkCommand::SetPresence {
+                    status,
+                    status_text,
+                },
+                Some(tx),
+            ))
+            .await
+            .map_err(|_| AppError::Internal("Network service unavailable".into()))?;
+
+        match rx.await {
+            Ok(NetworkResponse::Ok) => Ok(()),
+            Ok(NetworkResponse::Error(e)) => Err(AppError::Network(e)),
+            _ => Err(AppError::Internal("Unexpected response".into())),
+        }
+    }
+
+    /// Get the presence of connected peers that shared it with us
+    pub async fn get_presence(&self) -> Result<Vec<PeerPresence>> {
+        let (tx, rx) = oneshot::channel();
+        self.command_tx
+            .send((NetworkCommand::GetPresence, Some(tx)))
+            .await
+            .map_err(|_| AppError::Internal("Network service unavailable".into()))?;
+
+        match rx.await {
+            Ok(NetworkResponse::Presence(presence)) => Ok(presence),
+            Ok(NetworkResponse::Error(e)) => Err(AppError::Network(e)),
+            _ => Err(AppError::Internal("Unexpected response".into())),
+        }
+    }
 }
 
 use super::types::NatStatus;
 
+/// An attachment being fetched from its sender chunk by chunk
+struct AttachmentDownload {
+    message_id: String,
+    blob_hash: String,
+    /// Size of the encrypted file
+    size: usize,
+    data: Vec<u8>,
+}
+
 /// The network service manages the libp2p swarm
 pub struct NetworkService {
     swarm: Swarm<ChatBehaviour>,
@@ -418,6 +611,7 @@ pub struct NetworkService {
     posts_service: Option<Arc<PostsService>>,
     content_sync_service: Option<Arc<ContentSyncService>>,
     board_service: Option<Arc<BoardService>>,
+    scheduler_service: Option<Arc<SchedulerService>>,
     command_rx: mpsc::Receiver<(NetworkCommand, Option<oneshot::Sender<NetworkResponse>>)>,
     event_tx: mpsc::Sender<NetworkEvent>,
     connected_peers: HashMap<PeerId, PeerInfo>,
@@ -437,6 +631,30 @@ pub struct NetworkService {
     /// Key: relay peer ID, Value: full relay multiaddr (transport + /p2p/<id>).
     /// Reservation is requested in Identify::Received after the connection is fully negotiated.
     pending_relay_reservations: HashMap<PeerId, Multiaddr>,
+    /// Boards we want pushed updates for, per relay, mirrored in the
+    /// database so they resume after a restart. The flag records whether
+    /// the board has caught up from its sync cursor since the last (re)connect;
+    /// pushed posts only advance the cursor once it has.
+    board_subscriptions: HashMap<PeerId, HashMap<String, bool>>,
+    /// Our profile version; changes when the profile is edited
+    profile_rx: watch::Receiver<i64>,
+    /// Profile pushes in flight, with the peer and version pushed
+    profile_pushes: HashMap<request_response::OutboundRequestId, (PeerId, i64)>,
+    /// Highest profile version each contact acknowledged this session
+    profile_pushed: HashMap<PeerId, i64>,
+    /// Attachments being fetched, by the request for their next chunk
+    attachment_downloads: HashMap<request_response::OutboundRequestId, AttachmentDownload>,
+    /// Our presence, shared with peers that may read our wall
+    presence_status: PresenceStatus,
+    presence_text: Option<String>,
+    /// Presence connected peers shared with us
+    peer_presence: HashMap<PeerId, PeerPresence>,
+    /// Peers whose last typing signal said they were typing
+    typing_peers: HashSet<PeerId>,
+    /// Start of each peer's signal window and the signals received in it
+    signal_windows: HashMap<PeerId, (Instant, u32)>,
+    /// When the database was last vacuumed this session
+    last_vacuum: Option<Instant>,
 }
 
 impl NetworkService {
@@ -452,6 +670,7 @@ impl NetworkService {
         let (event_tx, event_rx) = mpsc::channel(256);
 
         let handle = NetworkHandle { command_tx };
+        let profile_rx = identity_service.subscribe_profile_changes();
 
         let service = Self {
             swarm,
@@ -463,6 +682,7 @@ impl NetworkService {
             posts_service: None,
             content_sync_service: None,
             board_service: None,
+            scheduler_service: None,
             command_rx,
             event_tx,
             connected_peers: HashMap::new(),
@@ -475,6 +695,17 @@ impl NetworkService {
             external_addresses: Vec::new(),
             relay_connection_attempted: false,
             pending_relay_reservations: HashMap::new(),
+            board_subscriptions: HashMap::new(),
+            profile_rx,
+            profile_pushes: HashMap::new(),
+            profile_pushed: HashMap::new(),
+            attachment_downloads: HashMap::new(),
+            presence_status: PresenceStatus::default(),
+            presence_text: None,
+            peer_presence: HashMap::new(),
+            typing_peers: HashSet::new(),
+            signal_windows: HashMap::new(),
+            last_vacuum: None,
         };
 
         Ok((service, handle, event_rx))
@@ -505,11 +736,36 @@ impl NetworkService {
         self.content_sync_service = Some(service);
     }
 
-    /// Set board service for community board operations
+    /// Set board service for community board operations. Persisted board
+    /// subscriptions are restored and resumed when their relay connects.
     pub fn set_board_service(&mut self, service: Arc<BoardService>) {
+        match service.get_subscriptions() {
+            Ok(subscriptions) => {
+                for (relay_peer_id, board_id) in subscriptions {
+                    match relay_peer_id.parse::<PeerId>() {
+                        Ok(relay) => {
+                            self.board_subscriptions
+                                .entry(relay)
+                                .or_default()
+                                .insert(board_id, false);
+                        }
+                        Err(e) => warn!(
+                            "Skipping subscription for invalid relay {}: {}",
+                            relay_peer_id, e
+                        ),
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to load board subscriptions: {}", e),
+        }
         self.board_service = Some(service);
     }
 
+    /// Set scheduler service for publishing scheduled posts and messages
+    pub fn set_scheduler_service(&mut self, service: Arc<SchedulerService>) {
+        self.scheduler_service = Some(service);
+    }
+
     /// Get the local peer ID
     pub fn local_peer_id(&self) -> &PeerId {
         self.swarm.local_peer_id()
@@ -520,7 +776,7 @@ impl NetworkService {
         let info = self
             .identity_service
             .get_identity_info()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let timestamp = chrono::Utc::now().timestamp();
         let signature = self
@@ -566,6 +822,11 @@ impl NetworkService {
         info!("Auto-connecting to Harbor relay...");
         self.connect_to_relays().await;
 
+        let mut retention_sweep = tokio::time::interval(RETENTION_SWEEP_INTERVAL);
+        retention_sweep.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut schedule_check = tokio::time::interval(SCHEDULE_CHECK_INTERVAL);
+        schedule_check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
         loop {
             tokio::select! {
                 // Handle swarm events
@@ -573,6 +834,21 @@ impl NetworkService {
                     self.handle_swarm_event(event).await;
                 }
 
+                // Push profile edits to connected contacts
+                Ok(()) = self.profile_rx.changed() => {
+                    self.push_profile_to_contacts();
+                }
+
+                // Delete messages past their retention period
+                _ = retention_sweep.tick() => {
+                    self.sweep_expired_messages().await;
+                }
+
+                // Publish scheduled posts and messages that are due
+                _ = schedule_check.tick() => {
+                    self.publish_scheduled().await;
+                }
+
                 // Handle commands from the application
                 Some((command, response_tx)) = self.command_rx.recv() => {
                     let should_shutdown = matches!(command, NetworkCommand::Shutdown);
@@ -603,9 +879,15 @@ impl NetworkService {
             }
 
             SwarmEvent::ConnectionEstablished {
-                peer_id, endpoint, ..
+                peer_id,
+                endpoint,
+                num_established,
+                ..
             } => {
                 info!("Connected to peer: {} at {:?}", peer_id, endpoint);
+                if num_established.get() == 1 && self.board_subscriptions.contains_key(&peer_id) {
+                    self.resume_board_subscriptions(peer_id);
+                }
                 let peer_info = PeerInfo {
                     peer_id: peer_id.to_string(),
                     addresses: vec![endpoint.get_remote_address().to_string()],
@@ -616,6 +898,13 @@ impl NetworkService {
                 };
                 self.connected_peers.insert(peer_id, peer_info);
                 self.stats.connected_peers = self.connected_peers.len();
+                if num_established.get() == 1 {
+                    self.push_profile(peer_id);
+                    self.fetch_missing_avatar(peer_id);
+                    self.fetch_missing_attachments(peer_id);
+                    self.send_presence(peer_id);
+                    self.send_retention(peer_id);
+                }
 
                 let _ = self
                     .event_tx
@@ -625,8 +914,19 @@ impl NetworkService {
                     .await;
             }
 
-            SwarmEvent::ConnectionClosed { peer_id, cause, .. } => {
+            SwarmEvent::ConnectionClosed {
+                peer_id,
+                cause,
+                num_established,
+                ..
+            } => {
                 info!("Disconnected from peer: {} (cause: {:?})", peer_id, cause);
+                if num_established == 0 {
+                    if let Some(boards) = self.board_subscriptions.get_mut(&peer_id) {
+                        boards.values_mut().for_each(|caught_up| *caught_up = false);
+                    }
+                    self.clear_signals(peer_id).await;
+                }
                 self.connected_peers.remove(&peer_id);
                 self.stats.connected_peers = self.connected_peers.len();
 
@@ -1079,6 +1379,37 @@ impl NetworkService {
                 }
             },
 
+            ChatBehaviourEvent::Profile(request_response::Event::Message {
+                peer, message, ..
+            }) => match message {
+                request_response::Message::Request {
+                    request, channel, ..
+                } => {
+                    let response = self.handle_profile_request(peer, request).await;
+                    let _ = self
+                        .swarm
+                        .behaviour_mut()
+                        .profile
+                        .send_response(channel, response);
+                }
+                request_response::Message::Response {
+                    request_id,
+                    response,
+                } => {
+                    self.handle_profile_response(peer, request_id, response)
+                        .await;
+                }
+            },
+            ChatBehaviourEvent::Profile(request_response::Event::OutboundFailure {
+                peer,
+                request_id,
+                error,
+                ..
+            }) => {
+                debug!("Profile request to {} failed: {}", peer, error);
+                self.profile_pushes.remove(&request_id);
+            }
+
             ChatBehaviourEvent::Messaging(request_response::Event::Message {
                 peer,
                 message,
@@ -1103,6 +1434,70 @@ impl NetworkService {
                     }
                 }
             }
+            ChatBehaviourEvent::Attachments(request_response::Event::Message {
+                peer,
+                message,
+                ..
+            }) => match message {
+                request_response::Message::Request {
+                    request, channel, ..
+                } => {
+                    let response = self.handle_attachment_request(peer, request);
+                    let _ = self
+                        .swarm
+                        .behaviour_mut()
+                        .attachments
+                        .send_response(channel, response);
+                }
+                request_response::Message::Response {
+                    request_id,
+                    response,
+                } => {
+                    self.handle_attachment_response(peer, request_id, response)
+                        .await;
+                }
+            },
+            ChatBehaviourEvent::Attachments(request_response::Event::OutboundFailure {
+                peer,
+                request_id,
+                error,
+                ..
+            }) => {
+                if let Some(download) = self.attachment_downloads.remove(&request_id) {
+                    debug!(
+                        "Fetching attachment of {} from {} failed: {}",
+                        download.message_id, peer, error
+                    );
+                }
+            }
+
+            ChatBehaviourEvent::Signals(request_response::Event::Message {
+                peer, message, ..
+            }) => match message {
+                request_response::Message::Request {
+                    request, channel, ..
+                } => {
+                    let accepted = self.handle_signal_request(peer, request).await;
+                    let _ = self
+                        .swarm
+                        .behaviour_mut()
+                        .signals
+                        .send_response(channel, SignalAck { accepted });
+                }
+                request_response::Message::Response { response, .. } => {
+                    if !response.accepted {
+                        debug!("{} refused our signal", peer);
+                    }
+                }
+            },
+            ChatBehaviourEvent::Signals(request_response::Event::OutboundFailure {
+                peer,
+                error,
+                ..
+            }) => {
+                debug!("Signal to {} failed: {}", peer, error);
+            }
+
             ChatBehaviourEvent::ContentSync(request_response::Event::Message {
                 peer,
                 message,
@@ -1139,6 +1534,8 @@ impl NetworkService {
                         channel,
                         WireBoardSyncResponse::Error {
                             error: "Not a relay server".to_string(),
+                            code: None,
+                            retry_after_secs: None,
                         },
                     );
                 }
@@ -1147,6 +1544,22 @@ impl NetworkService {
                 }
             },
 
+            // Board notifications pushed by relays
+            ChatBehaviourEvent::BoardNotify(request_response::Event::Message {
+                peer,
+                message: request_response::Message::Request {
+                    request, channel, ..
+                },
+                ..
+            }) => {
+                let accepted = self.handle_board_notification(peer, request).await;
+                let _ = self
+                    .swarm
+                    .behaviour_mut()
+                    .board_notify
+                    .send_response(channel, BoardNotificationAck { accepted });
+            }
+
             // Relay client events for NAT traversal
             ChatBehaviourEvent::RelayClient(event) => {
                 self.handle_relay_client_event(event).await;
@@ -1437,25 +1850,36 @@ impl NetworkService {
                 }
             }
             WireBoardSyncResponse::BoardPosts {
-                board_id, posts, ..
+                board_id,
+                posts,
+                has_more,
+                mode,
             } => {
-                let storable: Vec<StorableBoardPost> = posts
-                    .iter()
-                    .map(|p| StorableBoardPost {
-                        post_id: p.post_id.clone(),
-                        board_id: p.board_id.clone(),
-                        author_peer_id: p.author_peer_id.clone(),
-                        author_display_name: p.author_display_name.clone(),
-                        content_type: p.content_type.clone(),
-                        content_text: p.content_text.clone(),
-                        lamport_clock: p.lamport_clock as i64,
-                        created_at: p.created_at,
-                        deleted_at: p.deleted_at,
-                        signature: p.signature.clone(),
-                    })
-                    .collect();
+                let storable: Vec<StorableBoardPost> = posts.iter().map(storable_board_post).collect();
                 let post_count = storable.len();
-                match board_service.store_board_posts(&relay_peer_id, &storable) {
+                let advance_cursor = mode == BoardPostsMode::Flat;
+                let stored = board_service.store_board_posts(&relay_peer_id, &storable, advance_cursor);
+
+                // Subscribed boards page through the backlog until caught up
+                if advance_cursor && stored.is_ok() {
+                    let subscribed = self
+                        .board_subscriptions
+                        .get(&peer)
+                        .is_some_and(|boards| boards.contains_key(&board_id));
+                    if subscribed && has_more && post_count > 0 {
+                        if let Err(e) = self.send_board_sync(peer, &board_id) {
+                            warn!("Failed to continue board catch-up with {}: {}", peer, e);
+                        }
+                    } else if let Some(caught_up) = self
+                        .board_subscriptions
+                        .get_mut(&peer)
+                        .and_then(|boards| boards.get_mut(&board_id))
+                    {
+                        *caught_up = true;
+                    }
+                }
+
+                match stored {
                     Ok(()) => {
                         let _ = self
                             .event_tx
@@ -1471,6 +1895,30 @@ impl NetworkService {
                     }
                 }
             }
+            WireBoardSyncResponse::ThreadPosts {
+                board_id,
+                root_post_id,
+                posts,
+            } => {
+                let storable: Vec<StorableBoardPost> = posts.iter().map(storable_board_post).collect();
+                let post_count = storable.len();
+                match board_service.store_board_posts(&relay_peer_id, &storable, false) {
+                    Ok(()) => {
+                        let _ = self
+                            .event_tx
+                            .send(NetworkEvent::BoardThreadReceived {
+                                relay_peer_id,
+                                board_id,
+                                root_post_id,
+                                post_count,
+                            })
+                            .await;
+                    }
+                    Err(e) => {
+                        warn!("Failed to store board thread from {}: {}", peer, e);
+                    }
+                }
+            }
             WireBoardSyncResponse::PostAccepted { post_id } => {
                 info!("Board post {} accepted by relay {}", post_id, peer);
                 let _ = self
@@ -1487,158 +1935,877 @@ impl NetworkService {
             WireBoardSyncResponse::PostDeleted { post_id } => {
                 info!("Board post {} deleted on relay {}", post_id, peer);
             }
-            WireBoardSyncResponse::Error { error } => {
-                warn!("Board sync error from {}: {}", peer, error);
+            WireBoardSyncResponse::Subscribed { board_ids } => {
+                info!("Subscribed to {} board(s) on relay {}", board_ids.len(), peer);
                 let _ = self
                     .event_tx
-                    .send(NetworkEvent::BoardSyncError {
+                    .send(NetworkEvent::BoardsSubscribed {
                         relay_peer_id,
-                        error,
+                        board_ids,
                     })
                     .await;
             }
-        }
-    }
-
-    async fn handle_identity_request(
-        &mut self,
+            WireBoardSyncResponse::Error {
+                error,
+                code,
+                retry_after_secs,
+            } => {
+                match retry_after_secs {
+                    Some(secs) => warn!(
+                        "Board sync error from {} ({:?}, retry in {}s): {}",
+                        peer, code, secs, error
+                    ),
+                    None => warn!("Board sync error from {} ({:?}): {}", peer, code, error),
+                }
+                let _ = self
+                    .event_tx
+                    .send(NetworkEvent::BoardSyncError {
+                        relay_peer_id,
+                        error,
+                        code: code.map(ErrorCode::from),
+                        retry_after_secs,
+                    })
+                    .await;
+            }
+        }
+    }
+
+    async fn handle_identity_request(
+        &mut self,
         _peer: PeerId,
         _request_id: request_response::InboundRequestId,
         _request: IdentityExchangeRequest,
         channel: ResponseChannel<IdentityExchangeResponse>,
     ) {
+        match self.build_identity_response() {
+            Ok(response) => {
+                if let Err(e) = self
+                    .swarm
+                    .behaviour_mut()
+                    .identity_exchange
+                    .send_response(channel, response)
+                {
+                    warn!("Failed to send identity response: {:?}", e);
+                }
+            }
+            Err(AppError::IdentityNotFound) => {
+                warn!("No identity configured, cannot respond to identity request");
+            }
+            Err(e) => {
+                warn!("Failed to build identity response: {}", e);
+            }
+        }
+    }
+
+    /// Our identity as sent in identity responses and profile updates
+    fn build_identity_response(&self) -> Result<IdentityExchangeResponse> {
         // Get our libp2p peer ID (this is what other peers see us as)
         let local_peer_id = *self.swarm.local_peer_id();
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+
+        let mut response = IdentityExchangeResponse {
+            version: IDENTITY_RESPONSE_VERSION,
+            // Use the libp2p peer ID, not the stored Harbor peer_id
+            peer_id: local_peer_id.to_string(),
+            public_key: identity.public_key,
+            x25519_public: identity.x25519_public,
+            display_name: identity.display_name,
+            avatar_hash: identity.avatar_hash,
+            bio: identity.bio,
+            profile_version: identity.profile_version,
+            timestamp: chrono::Utc::now().timestamp(),
+            signature: Vec::new(),
+        };
+        response.signature = self.identity_service.sign(&response.signable())?;
+        Ok(response)
+    }
 
-        // Get our identity info to respond with
-        match self.identity_service.get_identity_info() {
-            Ok(Some(info)) => {
-                // Sign the response using the libp2p peer ID
-                let timestamp = chrono::Utc::now().timestamp();
-                let signature = match self.identity_service.sign_raw(
-                    format!("{}:{}:{}", local_peer_id, info.display_name, timestamp).as_bytes(),
-                ) {
-                    Ok(sig) => sig,
-                    Err(e) => {
-                        warn!("Failed to sign identity response: {}", e);
-                        return;
-                    }
-                };
+    async fn handle_identity_response(
+        &mut self,
+        peer: PeerId,
+        _request_id: request_response::OutboundRequestId,
+        response: IdentityExchangeResponse,
+    ) {
+        info!(
+            "Got identity from {}: {} ({})",
+            peer, response.display_name, response.peer_id
+        );
 
-                // Decode base64 public keys to bytes for the network protocol
-                let engine = base64::engine::general_purpose::STANDARD;
-                let public_key = match engine.decode(&info.public_key) {
-                    Ok(bytes) => bytes,
-                    Err(e) => {
-                        warn!("Failed to decode public key: {}", e);
-                        return;
+        // Store in contacts database if we have the contacts service
+        let Some(contacts_service) = self.contacts_service.clone() else {
+            warn!("No contacts service configured, cannot store identity");
+            return;
+        };
+
+        // Verify the response peer ID matches the peer we received from
+        if response.peer_id != peer.to_string() {
+            warn!(
+                "Identity response peer ID mismatch: expected {}, got {}",
+                peer, response.peer_id
+            );
+            return;
+        }
+
+        if let Err(e) = response.verify() {
+            warn!("Rejected identity response from {}: {}", peer, e);
+            return;
+        }
+
+        // Unsigned responses from older peers may introduce a contact but
+        // not replace the keys we have for one. A contact that has sent a
+        // signed response has upgraded, so unsigned ones from it are forged.
+        if !response.is_signed() {
+            if let Ok(Some(version)) = contacts_service.identity_version(&response.peer_id) {
+                if version >= IDENTITY_RESPONSE_VERSION {
+                    warn!(
+                        "Rejected unsigned identity response from {}, which has sent signed ones",
+                        peer
+                    );
+                    return;
+                }
+            }
+            if let Ok(Some(contact)) = contacts_service.get_contact(&response.peer_id) {
+                if contact.public_key != response.public_key
+                    || contact.x25519_public != response.x25519_public
+                {
+                    warn!(
+                        "Ignoring unsigned key change for contact {} ({})",
+                        contact.display_name, response.peer_id
+                    );
+                    return;
+                }
+            }
+        }
+
+        // Known contacts are refreshed; others are added
+        let added = match self.apply_contact_profile(peer, &response).await {
+            Ok(ContactUpdate::NotFound) => contacts_service
+                .add_contact(
+                    &response.peer_id,
+                    &response.public_key,
+                    &response.x25519_public,
+                    &response.display_name,
+                    response.avatar_hash.as_deref(),
+                    response.bio.as_deref(),
+                )
+                .map(|contact_id| {
+                    info!(
+                        "Added contact {} with ID {}",
+                        response.display_name, contact_id
+                    );
+                    true
+                }),
+            Ok(_) => Ok(false),
+            Err(e) => Err(e),
+        };
+
+        if added.is_ok() && response.is_signed() {
+            if let Err(e) =
+                contacts_service.record_identity_version(&response.peer_id, response.version)
+            {
+                warn!("Failed to record identity version of {}: {}", peer, e);
+            }
+        }
+
+        match added {
+            Ok(false) => {}
+            Ok(true) => {
+                // Grant chat permission to the new contact
+                if let Some(ref permissions_service) = self.permissions_service {
+                    match permissions_service.create_permission_grant(
+                        &response.peer_id,
+                        Capability::Chat,
+                        None, // No expiration
+                    ) {
+                        Ok(_) => {
+                            info!("Granted chat permission to {}", response.peer_id);
+                        }
+                        Err(e) => {
+                            warn!("Failed to grant chat permission: {}", e);
+                        }
                     }
+                }
+
+                self.fetch_missing_avatar(peer);
+
+                // Emit event to notify frontend
+                let _ = self
+                    .event_tx
+                    .send(NetworkEvent::ContactAdded {
+                        peer_id: response.peer_id.clone(),
+                        display_name: response.display_name.clone(),
+                    })
+                    .await;
+            }
+            Err(e) => {
+                warn!("Failed to add contact: {}", e);
+            }
+        }
+    }
+
+    /// Apply a profile a peer sent about itself to its contact entry. Warns
+    /// about new keys of verified contacts and fetches a changed avatar.
+    async fn apply_contact_profile(
+        &mut self,
+        peer: PeerId,
+        profile: &IdentityExchangeResponse,
+    ) -> Result<ContactUpdate> {
+        let Some(contacts_service) = self.contacts_service.clone() else {
+            return Err(AppError::Internal(
+                "Contacts service unavailable".to_string(),
+            ));
+        };
+
+        let update = contacts_service.apply_profile(&ContactData {
+            peer_id: profile.peer_id.clone(),
+            public_key: profile.public_key.clone(),
+            x25519_public: profile.x25519_public.clone(),
+            display_name: profile.display_name.clone(),
+            avatar_hash: profile.avatar_hash.clone(),
+            bio: profile.bio.clone(),
+            profile_version: profile.profile_version,
+        })?;
+
+        match update {
+            ContactUpdate::NotFound | ContactUpdate::Stale => return Ok(update),
+            ContactUpdate::Updated => {}
+            ContactUpdate::KeysChanged { was_verified } => {
+                warn!(
+                    "Contact {} ({}) presented new keys",
+                    profile.display_name, profile.peer_id
+                );
+                if was_verified {
+                    let _ = self
+                        .event_tx
+                        .send(NetworkEvent::ContactKeysChanged {
+                            peer_id: profile.peer_id.clone(),
+                            display_name: profile.display_name.clone(),
+                        })
+                        .await;
+                }
+            }
+        }
+
+        self.fetch_missing_avatar(peer);
+        let _ = self
+            .event_tx
+            .send(NetworkEvent::ContactProfileUpdated {
+                peer_id: profile.peer_id.clone(),
+                display_name: profile.display_name.clone(),
+                avatar_hash: profile.avatar_hash.clone(),
+            })
+            .await;
+        Ok(update)
+    }
+
+    /// Push our profile to every connected contact
+    fn push_profile_to_contacts(&mut self) {
+        let peers: Vec<PeerId> = self.connected_peers.keys().copied().collect();
+        for peer in peers {
+            self.push_profile(peer);
+        }
+    }
+
+    /// Push our profile to `peer` if it is a contact that hasn't acknowledged
+    /// this version yet
+    fn push_profile(&mut self, peer: PeerId) {
+        let Some(ref contacts_service) = self.contacts_service else {
+            return;
+        };
+        match contacts_service.get_contact(&peer.to_string()) {
+            Ok(Some(contact)) if !contact.is_blocked => {}
+            _ => return,
+        }
+
+        let profile = match self.build_identity_response() {
+            Ok(profile) => profile,
+            Err(e) => {
+                warn!("Failed to build profile update: {}", e);
+                return;
+            }
+        };
+        let version = profile.profile_version;
+        if self
+            .profile_pushed
+            .get(&peer)
+            .is_some_and(|&pushed| pushed >= version)
+        {
+            return;
+        }
+
+        debug!("Pushing profile version {} to {}", version, peer);
+        let request_id = self
+            .swarm
+            .behaviour_mut()
+            .profile
+            .send_request(&peer, ProfileRequest::Update { profile });
+        self.profile_pushes.insert(request_id, (peer, version));
+    }
+
+    /// Fetch a contact's avatar image if we don't have it yet
+    fn fetch_missing_avatar(&mut self, peer: PeerId) {
+        let Some(ref contacts_service) = self.contacts_service else {
+            return;
+        };
+        match contacts_service.missing_avatar(&peer.to_string()) {
+            Ok(Some(avatar_hash)) => {
+                debug!("Fetching avatar {} from {}", avatar_hash, peer);
+                self.swarm
+                    .behaviour_mut()
+                    .profile
+                    .send_request(&peer, ProfileRequest::FetchAvatar { avatar_hash });
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to check avatar of {}: {}", peer, e),
+        }
+    }
+
+    async fn handle_profile_request(
+        &mut self,
+        peer: PeerId,
+        request: ProfileRequest,
+    ) -> ProfileResponse {
+        let blocked = self
+            .contacts_service
+            .as_ref()
+            .is_some_and(|c| c.is_blocked(&peer.to_string()).unwrap_or(false));
+
+        match request {
+            ProfileRequest::Update { profile } => {
+                if blocked || profile.peer_id != peer.to_string() {
+                    warn!(
+                        "Ignoring profile update for {} from {}",
+                        profile.peer_id, peer
+                    );
+                    return ProfileResponse::Ack { accepted: false };
+                }
+                // Profile updates postdate signed responses, so they must be signed
+                let verified = if profile.is_signed() {
+                    profile.verify()
+                } else {
+                    Err(AppError::Crypto("Unsigned profile update".to_string()))
                 };
-                let x25519_public = match engine.decode(&info.x25519_public) {
-                    Ok(bytes) => bytes,
+                if let Err(e) = verified {
+                    warn!("Rejected profile update from {}: {}", peer, e);
+                    return ProfileResponse::Ack { accepted: false };
+                }
+                if let Some(ref contacts_service) = self.contacts_service {
+                    if let Err(e) =
+                        contacts_service.record_identity_version(&profile.peer_id, profile.version)
+                    {
+                        warn!("Failed to record identity version of {}: {}", peer, e);
+                    }
+                }
+                let accepted = match self.apply_contact_profile(peer, &profile).await {
+                    Ok(update) => matches!(
+                        update,
+                        ContactUpdate::Updated | ContactUpdate::KeysChanged { .. }
+                    ),
                     Err(e) => {
-                        warn!("Failed to decode x25519 public key: {}", e);
-                        return;
+                        warn!("Failed to apply profile update from {}: {}", peer, e);
+                        false
                     }
                 };
+                ProfileResponse::Ack { accepted }
+            }
+            ProfileRequest::FetchAvatar { avatar_hash } => {
+                if blocked {
+                    return ProfileResponse::Error {
+                        error: "Avatar not found".to_string(),
+                    };
+                }
+                match self.identity_service.get_avatar() {
+                    Ok(Some(blob)) if blob.hash == avatar_hash => ProfileResponse::Avatar {
+                        avatar_hash: blob.hash,
+                        mime_type: blob.mime_type,
+                        data: blob.data,
+                    },
+                    Ok(_) => ProfileResponse::Error {
+                        error: "Avatar not found".to_string(),
+                    },
+                    Err(e) => ProfileResponse::Error {
+                        error: e.to_string(),
+                    },
+                }
+            }
+        }
+    }
 
-                let response = IdentityExchangeResponse {
-                    // Use the libp2p peer ID, not the stored Harbor peer_id
-                    peer_id: local_peer_id.to_string(),
-                    public_key,
-                    x25519_public,
-                    display_name: info.display_name,
-                    avatar_hash: info.avatar_hash,
-                    bio: info.bio,
-                    timestamp,
-                    signature,
+    async fn handle_profile_response(
+        &mut self,
+        peer: PeerId,
+        request_id: request_response::OutboundRequestId,
+        response: ProfileResponse,
+    ) {
+        match response {
+            ProfileResponse::Ack { accepted } => {
+                if let Some((_, version)) = self.profile_pushes.remove(&request_id) {
+                    debug!(
+                        "{} acknowledged profile version {} (accepted: {})",
+                        peer, version, accepted
+                    );
+                    let pushed = self.profile_pushed.entry(peer).or_default();
+                    *pushed = (*pushed).max(version);
+                }
+            }
+            ProfileResponse::Avatar {
+                avatar_hash,
+                mime_type,
+                data,
+            } => {
+                let Some(ref contacts_service) = self.contacts_service else {
+                    return;
                 };
+                match contacts_service.store_avatar(
+                    &peer.to_string(),
+                    &avatar_hash,
+                    &mime_type,
+                    &data,
+                ) {
+                    Ok(()) => {
+                        info!("Stored avatar {} of {}", avatar_hash, peer);
+                        let _ = self
+                            .event_tx
+                            .send(NetworkEvent::ContactAvatarUpdated {
+                                peer_id: peer.to_string(),
+                                avatar_hash,
+                            })
+                            .await;
+                    }
+                    Err(e) => warn!("Rejected avatar from {}: {}", peer, e),
+                }
+            }
+            ProfileResponse::Error { error } => {
+                debug!("Profile request to {} failed: {}", peer, error);
+            }
+        }
+    }
 
-                if let Err(e) = self
-                    .swarm
-                    .behaviour_mut()
-                    .identity_exchange
-                    .send_response(channel, response)
+    /// Start fetching the attachments a peer sent us that we don't have yet
+    fn fetch_missing_attachments(&mut self, peer: PeerId) {
+        let Some(ref messaging_service) = self.messaging_service else {
+            return;
+        };
+        let missing = match messaging_service.missing_attachments(&peer.to_string()) {
+            Ok(missing) => missing,
+            Err(e) => {
+                warn!("Failed to check attachments of {}: {}", peer, e);
+                return;
+            }
+        };
+
+        for attachment in missing {
+            if self
+                .attachment_downloads
+                .values()
+                .any(|d| d.message_id == attachment.message_id)
+            {
+                continue;
+            }
+            debug!(
+                "Fetching attachment of {} from {}",
+                attachment.message_id, peer
+            );
+            let download = AttachmentDownload {
+                message_id: attachment.message_id,
+                blob_hash: attachment.blob_hash,
+                size: attachment.size as usize,
+                data: Vec::with_capacity(attachment.size as usize),
+            };
+            self.request_attachment_chunk(peer, download);
+        }
+    }
+
+    /// Request the chunk following what a download has received so far
+    fn request_attachment_chunk(&mut self, peer: PeerId, download: AttachmentDownload) {
+        let request = AttachmentRequest {
+            blob_hash: download.blob_hash.clone(),
+            chunk_index: (download.data.len() / ATTACHMENT_CHUNK_SIZE) as u32,
+        };
+        let request_id = self
+            .swarm
+            .behaviour_mut()
+            .attachments
+            .send_request(&peer, request);
+        self.attachment_downloads.insert(request_id, download);
+    }
+
+    fn handle_attachment_request(
+        &self,
+        peer: PeerId,
+        request: AttachmentRequest,
+    ) -> AttachmentResponse {
+        let Some(ref messaging_service) = self.messaging_service else {
+            return AttachmentResponse::Error {
+                error: "Messaging service not available".to_string(),
+            };
+        };
+        let blocked = self
+            .contacts_service
+            .as_ref()
+            .is_some_and(|c| c.is_blocked(&peer.to_string()).unwrap_or(false));
+        if blocked {
+            return AttachmentResponse::Error {
+                error: "Attachment not found".to_string(),
+            };
+        }
+
+        match messaging_service.attachment_chunk(
+            &peer.to_string(),
+            &request.blob_hash,
+            request.chunk_index,
+        ) {
+            Ok(data) => AttachmentResponse::Chunk { data },
+            Err(e) => AttachmentResponse::Error {
+                error: e.to_string(),
+            },
+        }
+    }
+
+    async fn handle_attachment_response(
+        &mut self,
+        peer: PeerId,
+        request_id: request_response::OutboundRequestId,
+        response: AttachmentResponse,
+    ) {
+        let Some(mut download) = self.attachment_downloads.remove(&request_id) else {
+            return;
+        };
+
+        let data = match response {
+            AttachmentResponse::Chunk { data } => data,
+            AttachmentResponse::Error { error } => {
+                warn!(
+                    "Fetching attachment of {} from {} failed: {}",
+                    download.message_id, peer, error
+                );
+                return;
+            }
+        };
+        if !is_valid_attachment_chunk(download.size, download.data.len(), data.len()) {
+            warn!(
+                "Dropping attachment of {} from {}: bad chunk size",
+                download.message_id, peer
+            );
+            return;
+        }
+        download.data.extend_from_slice(&data);
+
+        if download.data.len() < download.size {
+            self.request_attachment_chunk(peer, download);
+            return;
+        }
+
+        let Some(ref messaging_service) = self.messaging_service else {
+            return;
+        };
+        match messaging_service.store_attachment(&download.message_id, &download.data) {
+            Ok(()) => {
+                info!("Stored attachment of {} from {}", download.message_id, peer);
+                let _ = self
+                    .event_tx
+                    .send(NetworkEvent::AttachmentReceived {
+                        peer_id: peer.to_string(),
+                        message_id: download.message_id,
+                    })
+                    .await;
+            }
+            Err(e) => warn!(
+                "Rejected attachment of {} from {}: {}",
+                download.message_id, peer, e
+            ),
+        }
+    }
+
+    /// Count a signal from `peer` against its window. Returns false once the
+    /// peer has used up the window.
+    fn allow_signal(&mut self, peer: PeerId) -> bool {
+        let now = Instant::now();
+        let (started, count) = self.signal_windows.entry(peer).or_insert((now, 0));
+        if now.duration_since(*started) >= SIGNAL_WINDOW {
+            *started = now;
+            *count = 0;
+        }
+        *count += 1;
+        *count <= SIGNALS_PER_WINDOW
+    }
+
+    async fn handle_signal_request(&mut self, peer: PeerId, request: SignalRequest) -> bool {
+        if !self.allow_signal(peer) {
+            debug!("Rate limited signal from {}", peer);
+            return false;
+        }
+        let (Some(contacts_service), Some(permissions_service)) =
+            (&self.contacts_service, &self.permissions_service)
+        else {
+            return false;
+        };
+        let peer_id = peer.to_string();
+        if contacts_service.is_blocked(&peer_id).unwrap_or(true) {
+            return false;
+        }
+
+        let event = match request {
+            SignalRequest::Typing { typing } => {
+                if !permissions_service
+                    .peer_has_capability(&peer_id, Capability::Chat)
+                    .unwrap_or(false)
                 {
-                    warn!("Failed to send identity response: {:?}", e);
+                    return false;
                 }
+                // Every repeat is forwarded so the UI can refresh its timeout;
+                // `typing_peers` only tracks who to clear on disconnect
+                let forward = if typing {
+                    self.typing_peers.insert(peer);
+                    true
+                } else {
+                    self.typing_peers.remove(&peer)
+                };
+                forward.then_some(NetworkEvent::TypingChanged { peer_id, typing })
             }
-            Ok(None) => {
-                warn!("No identity configured, cannot respond to identity request");
+            SignalRequest::Presence {
+                status,
+                status_text,
+            } => {
+                // Presence is only shared with peers that may read the sender's wall
+                if !permissions_service
+                    .we_have_capability(&peer_id, Capability::WallRead)
+                    .unwrap_or(false)
+                {
+                    return false;
+                }
+                let Some(status_text) = normalize_status_text(status_text) else {
+                    return false;
+                };
+                if status == PresenceStatus::Offline {
+                    return false;
+                }
+                let presence = PeerPresence {
+                    peer_id: peer_id.clone(),
+                    status,
+                    status_text: status_text.clone(),
+                    updated_at: chrono::Utc::now().timestamp(),
+                };
+                let changed = !matches!(
+                    self.peer_presence.insert(peer, presence),
+                    Some(old) if old.status == status && old.status_text == status_text
+                );
+                changed.then_some(NetworkEvent::PresenceChanged {
+                    peer_id,
+                    status,
+                    status_text,
+                })
             }
+        };
+
+        if let Err(e) = contacts_service.update_last_seen(&peer.to_string()) {
+            debug!("Failed to update last seen of {}: {}", peer, e);
+        }
+        if let Some(event) = event {
+            let _ = self.event_tx.send(event).await;
+        }
+        true
+    }
+
+    /// Send our presence to `peer` if it is a contact that may read our wall
+    fn send_presence(&mut self, peer: PeerId) {
+        let (Some(contacts_service), Some(permissions_service)) =
+            (&self.contacts_service, &self.permissions_service)
+        else {
+            return;
+        };
+        let peer_id = peer.to_string();
+        if contacts_service.is_blocked(&peer_id).unwrap_or(true)
+            || !permissions_service
+                .peer_has_capability(&peer_id, Capability::WallRead)
+                .unwrap_or(false)
+        {
+            return;
+        }
+
+        let request = SignalRequest::Presence {
+            status: self.presence_status,
+            status_text: self.presence_text.clone(),
+        };
+        self.swarm
+            .behaviour_mut()
+            .signals
+            .send_request(&peer, request);
+    }
+
+    /// Send `peer` our retention setting for the conversation again, in case
+    /// it missed the update while offline
+    fn send_retention(&mut self, peer: PeerId) {
+        let Some(ref messaging_service) = self.messaging_service else {
+            return;
+        };
+        let update = match messaging_service.own_retention_update(&peer.to_string()) {
+            Ok(Some(update)) => update,
+            Ok(None) => return,
             Err(e) => {
-                warn!("Failed to get identity info: {}", e);
+                debug!("No retention setting to send {}: {}", peer, e);
+                return;
+            }
+        };
+        match MessagingCodec::encode(&MessagingMessage::Retention(update)) {
+            Ok(payload) => {
+                let request = MessagingRequest {
+                    message_type: "message".to_string(),
+                    payload,
+                };
+                self.swarm
+                    .behaviour_mut()
+                    .messaging
+                    .send_request(&peer, request);
             }
+            Err(e) => warn!("Failed to encode retention update: {}", e),
         }
     }
 
-    async fn handle_identity_response(
-        &mut self,
-        peer: PeerId,
-        _request_id: request_response::OutboundRequestId,
-        response: IdentityExchangeResponse,
-    ) {
-        info!(
-            "Got identity from {}: {} ({})",
-            peer, response.display_name, response.peer_id
-        );
-
-        // Store in contacts database if we have the contacts service
-        if let Some(ref contacts_service) = self.contacts_service {
-            // Verify the response peer ID matches the peer we received from
-            if response.peer_id != peer.to_string() {
-                warn!(
-                    "Identity response peer ID mismatch: expected {}, got {}",
-                    peer, response.peer_id
-                );
+    /// Delete expired messages, report them per conversation, and vacuum the
+    /// database if it has not been for `VACUUM_INTERVAL`. The vacuum runs off
+    /// the event loop and releases free pages in batches, so database calls
+    /// made here meanwhile wait for one batch at most.
+    async fn sweep_expired_messages(&mut self) {
+        let Some(messaging_service) = self.messaging_service.clone() else {
+            return;
+        };
+        let expired = match messaging_service.sweep_expired(chrono::Utc::now().timestamp()) {
+            Ok(expired) => expired,
+            Err(e) => {
+                warn!("Failed to delete expired messages: {}", e);
                 return;
             }
+        };
+        if expired.is_empty() {
+            return;
+        }
+
+        for conversation in expired.conversations {
+            info!(
+                "Deleted {} expired messages with {}",
+                conversation.message_ids.len(),
+                conversation.peer_id
+            );
+            let _ = self
+                .event_tx
+                .send(NetworkEvent::MessagesExpired {
+                    peer_id: conversation.peer_id,
+                    message_ids: conversation.message_ids,
+                })
+                .await;
+        }
 
-            // TODO: Verify signature on the response
-            // For now, we trust the identity since we're getting it from a direct connection
+        if self
+            .last_vacuum
+            .is_some_and(|at| at.elapsed() < VACUUM_INTERVAL)
+        {
+            return;
+        }
+        self.last_vacuum = Some(Instant::now());
+        tokio::task::spawn_blocking(move || match messaging_service.vacuum() {
+            Ok(pages) => debug!("Vacuum released {} database pages", pages),
+            Err(e) => warn!("Failed to vacuum the database: {}", e),
+        });
+    }
 
-            match contacts_service.add_contact(
-                &response.peer_id,
-                &response.public_key,
-                &response.x25519_public,
-                &response.display_name,
-                response.avatar_hash.as_deref(),
-                response.bio.as_deref(),
-            ) {
-                Ok(contact_id) => {
-                    info!(
-                        "Added contact {} with ID {}",
-                        response.display_name, contact_id
-                    );
+    /// Publish due scheduled items and send the messages among them.
+    /// Published posts reach contacts through their next content sync.
+    async fn publish_scheduled(&mut self) {
+        let Some(ref scheduler_service) = self.scheduler_service else {
+            return;
+        };
+        let outcomes = match scheduler_service.publish_due(chrono::Utc::now().timestamp()) {
+            Ok(outcomes) => outcomes,
+            Err(e) => {
+                warn!("Failed to publish scheduled items: {}", e);
+                return;
+            }
+        };
 
-                    // Grant chat permission to the new contact
-                    if let Some(ref permissions_service) = self.permissions_service {
-                        match permissions_service.create_permission_grant(
-                            &response.peer_id,
-                            Capability::Chat,
-                            None, // No expiration
-                        ) {
-                            Ok(_) => {
-                                info!("Granted chat permission to {}", response.peer_id);
-                            }
-                            Err(e) => {
-                                warn!("Failed to grant chat permission: {}", e);
-                            }
+        for outcome in outcomes {
+            let kind = outcome.kind.as_str().to_string();
+            let event = match outcome.result {
+                Ok(publication) => {
+                    let published_id = match publication {
+                        Publication::Post(post) => post.post_id,
+                        Publication::Message(message) => {
+                            self.send_scheduled_message(&message);
+                            message.message_id
                         }
+                    };
+                    info!("Published scheduled {} {}", kind, outcome.schedule_id);
+                    NetworkEvent::ScheduledItemPublished {
+                        schedule_id: outcome.schedule_id,
+                        kind,
+                        published_id,
                     }
-
-                    // Emit event to notify frontend
-                    drop(self.event_tx.send(NetworkEvent::ContactAdded {
-                        peer_id: response.peer_id.clone(),
-                        display_name: response.display_name.clone(),
-                    }));
                 }
                 Err(e) => {
-                    warn!("Failed to add contact: {}", e);
+                    warn!(
+                        "Failed to publish scheduled {} {}: {}",
+                        kind, outcome.schedule_id, e
+                    );
+                    NetworkEvent::ScheduledItemFailed {
+                        schedule_id: outcome.schedule_id,
+                        kind,
+                        error: e.to_string(),
+                    }
                 }
+            };
+            let _ = self.event_tx.send(event).await;
+        }
+    }
+
+    /// Send a published scheduled message to its recipient
+    fn send_scheduled_message(&mut self, message: &OutgoingMessage) {
+        let peer = match message.recipient_peer_id.parse::<PeerId>() {
+            Ok(peer) => peer,
+            Err(e) => {
+                warn!("Invalid recipient of scheduled message: {}", e);
+                return;
             }
-        } else {
-            warn!("No contacts service configured, cannot store identity");
+        };
+        match MessagingCodec::encode(&MessagingMessage::Message(message.to_direct_message())) {
+            Ok(payload) => {
+                let request = MessagingRequest {
+                    message_type: "message".to_string(),
+                    payload,
+                };
+                self.swarm
+                    .behaviour_mut()
+                    .messaging
+                    .send_request(&peer, request);
+            }
+            Err(e) => warn!("Failed to encode scheduled message: {}", e),
+        }
+    }
+
+    /// Forget a disconnected peer's signals, reporting it as no longer typing
+    /// and offline
+    async fn clear_signals(&mut self, peer: PeerId) {
+        self.signal_windows.remove(&peer);
+        if self.typing_peers.remove(&peer) {
+            let _ = self
+                .event_tx
+                .send(NetworkEvent::TypingChanged {
+                    peer_id: peer.to_string(),
+                    typing: false,
+                })
+                .await;
+        }
+        if self.peer_presence.remove(&peer).is_some() {
+            let _ = self
+                .event_tx
+                .send(NetworkEvent::PresenceChanged {
+                    peer_id: peer.to_string(),
+                    status: PresenceStatus::Offline,
+                    status_text: None,
+                })
+                .await;
         }
     }
 
@@ -1652,6 +2819,9 @@ impl NetworkService {
         // Decode the message payload
         let msg_result = MessagingCodec::decode(&request.payload);
 
+        let mut fetch_attachments = false;
+        let mut updated_message_id = None;
+        let mut retention_changed = None;
         let (success, message_id, error) = match msg_result {
             Ok(MessagingMessage::Message(direct_msg)) => {
                 info!(
@@ -1676,6 +2846,7 @@ impl NetworkService {
                     ) {
                         Ok(_) => {
                             info!("Message {} processed successfully", direct_msg.message_id);
+                            fetch_attachments = direct_msg.content_type == ATTACHMENT_CONTENT_TYPE;
                             (true, Some(direct_msg.message_id.clone()), None)
                         }
                         Err(e) => {
@@ -1701,6 +2872,59 @@ impl NetworkService {
                 // TODO: Process acknowledgment (update message status)
                 (true, Some(ack.message_id), None)
             }
+            Ok(MessagingMessage::Event(event)) => {
+                info!(
+                    "Received {} event for {} from {}",
+                    event.event_type.as_str(),
+                    event.message_id,
+                    peer
+                );
+                match self.messaging_service {
+                    Some(ref messaging_service) => {
+                        match messaging_service.process_incoming_event(&event) {
+                            Ok(changed) => {
+                                if changed {
+                                    updated_message_id = Some(event.message_id.clone());
+                                }
+                                (true, Some(event.message_id), None)
+                            }
+                            Err(e) => {
+                                warn!("Failed to process event {}: {}", event.event_id, e);
+                                (false, Some(event.message_id), Some(e.to_string()))
+                            }
+                        }
+                    }
+                    None => (
+                        false,
+                        Some(event.message_id),
+                        Some("Messaging service not available".to_string()),
+                    ),
+                }
+            }
+            Ok(MessagingMessage::Retention(update)) => {
+                info!("Received retention update from {}", peer);
+                match self.messaging_service {
+                    Some(ref messaging_service) => {
+                        match messaging_service.process_incoming_retention(&update) {
+                            Ok(changed) => {
+                                if changed {
+                                    retention_changed = Some(update.retention_secs);
+                                }
+                                (true, None, None)
+                            }
+                            Err(e) => {
+                                warn!("Failed to process retention update: {}", e);
+                                (false, None, Some(e.to_string()))
+                            }
+                        }
+                    }
+                    None => (
+                        false,
+                        None,
+                        Some("Messaging service not available".to_string()),
+                    ),
+                }
+            }
             Err(e) => {
                 warn!("Failed to decode messaging payload: {}", e);
                 (false, None, Some(format!("Failed to decode: {}", e)))
@@ -1723,6 +2947,28 @@ impl NetworkService {
             warn!("Failed to send messaging response: {:?}", e);
         }
 
+        if fetch_attachments {
+            self.fetch_missing_attachments(peer);
+        }
+        if let Some(message_id) = updated_message_id {
+            let _ = self
+                .event_tx
+                .send(NetworkEvent::MessageUpdated {
+                    peer_id: peer.to_string(),
+                    message_id,
+                })
+                .await;
+        }
+        if let Some(retention_secs) = retention_changed {
+            let _ = self
+                .event_tx
+                .send(NetworkEvent::RetentionChanged {
+                    peer_id: peer.to_string(),
+                    retention_secs,
+                })
+                .await;
+        }
+
         // Emit event for the application layer (for UI updates)
         let _ = self
             .event_tx
@@ -2147,6 +3393,7 @@ impl NetworkService {
                 board_id,
                 after_timestamp,
                 limit,
+                mode,
             } => {
                 let Some(ref board_service) = self.board_service else {
                     return NetworkResponse::Error("Board service unavailable".to_string());
@@ -2165,6 +3412,7 @@ impl NetworkService {
                             limit: req.limit,
                             timestamp: req.timestamp,
                             signature: req.signature,
+                            mode,
                         };
                         self.swarm
                             .behaviour_mut()
@@ -2179,16 +3427,49 @@ impl NetworkService {
                 }
             }
 
+            NetworkCommand::GetBoardThread {
+                relay_peer_id,
+                root_post_id,
+            } => {
+                let Some(ref board_service) = self.board_service else {
+                    return NetworkResponse::Error("Board service unavailable".to_string());
+                };
+
+                match board_service.create_get_thread_request(&root_post_id) {
+                    Ok(req) => {
+                        let request = WireBoardSyncRequest::GetThread {
+                            requester_peer_id: req.requester_peer_id,
+                            root_post_id: req.root_post_id,
+                            timestamp: req.timestamp,
+                            signature: req.signature,
+                        };
+                        self.swarm
+                            .behaviour_mut()
+                            .board_sync
+                            .send_request(&relay_peer_id, request);
+                        NetworkResponse::Ok
+                    }
+                    Err(e) => {
+                        NetworkResponse::Error(format!("Failed to create thread request: {}", e))
+                    }
+                }
+            }
+
             NetworkCommand::SubmitBoardPost {
                 relay_peer_id,
                 board_id,
                 content_text,
+                reply_to_post_id,
             } => {
                 let Some(ref board_service) = self.board_service else {
                     return NetworkResponse::Error("Board service unavailable".to_string());
                 };
 
-                match board_service.create_board_post(&board_id, &content_text) {
+                match board_service.create_board_post(
+                    &board_id,
+                    &content_text,
+                    reply_to_post_id.as_deref(),
+                ) {
                     Ok(post) => {
                         let request = WireBoardSyncRequest::SubmitPost {
                             post_id: post.post_id,
@@ -2199,6 +3480,7 @@ impl NetworkService {
                             lamport_clock: post.lamport_clock,
                             created_at: post.created_at,
                             signature: post.signature,
+                            reply_to_post_id: post.reply_to_post_id,
                         };
                         self.swarm
                             .behaviour_mut()
@@ -2241,41 +3523,275 @@ impl NetworkService {
             NetworkCommand::SyncBoard {
                 relay_peer_id,
                 board_id,
+            } => match self.send_board_sync(relay_peer_id, &board_id) {
+                Ok(()) => NetworkResponse::Ok,
+                Err(e) => NetworkResponse::Error(format!("Failed to create sync request: {}", e)),
+            },
+
+            NetworkCommand::SubscribeBoards {
+                relay_peer_id,
+                board_ids,
             } => {
-                let Some(ref board_service) = self.board_service else {
-                    return NetworkResponse::Error("Board service unavailable".to_string());
-                };
+                if let Some(ref board_service) = self.board_service {
+                    if let Err(e) =
+                        board_service.save_subscriptions(&relay_peer_id.to_string(), &board_ids)
+                    {
+                        return NetworkResponse::Error(format!(
+                            "Failed to save subscriptions: {}",
+                            e
+                        ));
+                    }
+                }
+                let boards = self.board_subscriptions.entry(relay_peer_id).or_default();
+                for board_id in &board_ids {
+                    boards.entry(board_id.clone()).or_insert(false);
+                }
+                if !self.swarm.is_connected(&relay_peer_id) {
+                    // Subscribed on the next connection to the relay
+                    return NetworkResponse::Ok;
+                }
+                if let Err(e) = self.send_board_subscription(relay_peer_id, board_ids.clone(), true) {
+                    return NetworkResponse::Error(format!(
+                        "Failed to create subscribe request: {}",
+                        e
+                    ));
+                }
+                for board_id in &board_ids {
+                    if let Err(e) = self.send_board_sync(relay_peer_id, board_id) {
+                        warn!("Failed to start board catch-up with {}: {}", relay_peer_id, e);
+                    }
+                }
+                NetworkResponse::Ok
+            }
 
-                let after_timestamp = board_service
-                    .get_sync_cursor(&relay_peer_id.to_string(), &board_id)
-                    .unwrap_or(None);
+            NetworkCommand::UnsubscribeBoards {
+                relay_peer_id,
+                board_ids,
+            } => {
+                if let Some(ref board_service) = self.board_service {
+                    if let Err(e) =
+                        board_service.forget_subscriptions(&relay_peer_id.to_string(), &board_ids)
+                    {
+                        return NetworkResponse::Error(format!(
+                            "Failed to remove subscriptions: {}",
+                            e
+                        ));
+                    }
+                }
+                if board_ids.is_empty() {
+                    self.board_subscriptions.remove(&relay_peer_id);
+                } else if let Some(boards) = self.board_subscriptions.get_mut(&relay_peer_id) {
+                    for board_id in &board_ids {
+                        boards.remove(board_id);
+                    }
+                    if boards.is_empty() {
+                        self.board_subscriptions.remove(&relay_peer_id);
+                    }
+                }
+                if !self.swarm.is_connected(&relay_peer_id) {
+                    return NetworkResponse::Ok;
+                }
+                match self.send_board_subscription(relay_peer_id, board_ids, false) {
+                    Ok(()) => NetworkResponse::Ok,
+                    Err(e) => NetworkResponse::Error(format!(
+                        "Failed to create unsubscribe request: {}",
+                        e
+                    )),
+                }
+            }
 
-                match board_service.create_get_board_posts_request(&board_id, after_timestamp, 50) {
-                    Ok(req) => {
-                        let request = WireBoardSyncRequest::GetBoardPosts {
-                            requester_peer_id: req.requester_peer_id,
-                            board_id: req.board_id,
-                            after_timestamp: req.after_timestamp,
-                            limit: req.limit,
-                            timestamp: req.timestamp,
-                            signature: req.signature,
-                        };
-                        self.swarm
-                            .behaviour_mut()
-                            .board_sync
-                            .send_request(&relay_peer_id, request);
-                        NetworkResponse::Ok
+            NetworkCommand::SetTyping { peer_id, typing } => {
+                if !self.swarm.is_connected(&peer_id) {
+                    // Nothing to stop, and typing isn't worth a dial
+                    return NetworkResponse::Ok;
+                }
+                let Some(ref permissions_service) = self.permissions_service else {
+                    return NetworkResponse::Error("Permissions service unavailable".to_string());
+                };
+                match permissions_service
+                    .peer_has_capability(&peer_id.to_string(), Capability::Chat)
+                {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        return NetworkResponse::Error(
+                            "No chat permission with this peer".to_string(),
+                        )
                     }
-                    Err(e) => {
-                        NetworkResponse::Error(format!("Failed to create sync request: {}", e))
+                    Err(e) => return NetworkResponse::Error(e.to_string()),
+                }
+                self.swarm
+                    .behaviour_mut()
+                    .signals
+                    .send_request(&peer_id, SignalRequest::Typing { typing });
+                NetworkResponse::Ok
+            }
+
+            NetworkCommand::SetPresence {
+                status,
+                status_text,
+            } => {
+                if self.presence_status != status || self.presence_text != status_text {
+                    self.presence_status = status;
+                    self.presence_text = status_text;
+                    let peers: Vec<PeerId> = self.connected_peers.keys().copied().collect();
+                    for peer in peers {
+                        self.send_presence(peer);
                     }
                 }
+                NetworkResponse::Ok
+            }
+
+            NetworkCommand::GetPresence => {
+                NetworkResponse::Presence(self.peer_presence.values().cloned().collect())
             }
 
             NetworkCommand::Shutdown => NetworkResponse::Ok,
         }
     }
 
+    /// Request posts for a board after its stored sync cursor
+    fn send_board_sync(&mut self, relay_peer_id: PeerId, board_id: &str) -> Result<()> {
+        let Some(ref board_service) = self.board_service else {
+            return Err(AppError::Internal("Board service unavailable".to_string()));
+        };
+
+        let after_timestamp = board_service
+            .get_sync_cursor(&relay_peer_id.to_string(), board_id)
+            .unwrap_or(None);
+
+        let req = board_service.create_get_board_posts_request(board_id, after_timestamp, 50)?;
+        let request = WireBoardSyncRequest::GetBoardPosts {
+            requester_peer_id: req.requester_peer_id,
+            board_id: req.board_id,
+            after_timestamp: req.after_timestamp,
+            limit: req.limit,
+            timestamp: req.timestamp,
+            signature: req.signature,
+            mode: BoardPostsMode::Flat,
+        };
+        self.swarm
+            .behaviour_mut()
+            .board_sync
+            .send_request(&relay_peer_id, request);
+        Ok(())
+    }
+
+    /// Send a Subscribe or Unsubscribe request for boards on a relay
+    fn send_board_subscription(
+        &mut self,
+        relay_peer_id: PeerId,
+        board_ids: Vec<String>,
+        subscribe: bool,
+    ) -> Result<()> {
+        let Some(ref board_service) = self.board_service else {
+            return Err(AppError::Internal("Board service unavailable".to_string()));
+        };
+
+        let req = board_service.create_subscription_request(&board_ids)?;
+        let request = if subscribe {
+            WireBoardSyncRequest::Subscribe {
+                requester_peer_id: req.requester_peer_id,
+                board_ids: req.board_ids,
+                timestamp: req.timestamp,
+                signature: req.signature,
+            }
+        } else {
+            WireBoardSyncRequest::Unsubscribe {
+                requester_peer_id: req.requester_peer_id,
+                board_ids: req.board_ids,
+                timestamp: req.timestamp,
+                signature: req.signature,
+            }
+        };
+        self.swarm
+            .behaviour_mut()
+            .board_sync
+            .send_request(&relay_peer_id, request);
+        Ok(())
+    }
+
+    /// Resubscribe after reconnecting to a relay and catch up from the stored cursors
+    fn resume_board_subscriptions(&mut self, relay_peer_id: PeerId) {
+        let Some(boards) = self.board_subscriptions.get(&relay_peer_id) else {
+            return;
+        };
+        let board_ids: Vec<String> = boards.keys().cloned().collect();
+        info!(
+            "Resuming {} board subscription(s) on relay {}",
+            board_ids.len(),
+            relay_peer_id
+        );
+
+        if let Err(e) = self.send_board_subscription(relay_peer_id, board_ids.clone(), true) {
+            warn!("Failed to resubscribe to boards on {}: {}", relay_peer_id, e);
+            return;
+        }
+        for board_id in &board_ids {
+            if let Err(e) = self.send_board_sync(relay_peer_id, board_id) {
+                warn!("Failed to start board catch-up with {}: {}", relay_peer_id, e);
+            }
+        }
+    }
+
+    /// Store a notification pushed by a relay. Returns whether it was accepted.
+    async fn handle_board_notification(
+        &mut self,
+        peer: PeerId,
+        notification: BoardNotification,
+    ) -> bool {
+        let Some(ref board_service) = self.board_service else {
+            return false;
+        };
+        let Some(boards) = self.board_subscriptions.get(&peer) else {
+            debug!("Ignoring board notification from unsubscribed peer {}", peer);
+            return false;
+        };
+        let relay_peer_id = peer.to_string();
+
+        match notification {
+            BoardNotification::Posts { board_id, posts } => {
+                let Some(&caught_up) = boards.get(&board_id) else {
+                    return false;
+                };
+                let storable: Vec<StorableBoardPost> = posts.iter().map(storable_board_post).collect();
+                let post_count = storable.len();
+                if let Err(e) = board_service.store_board_posts(&relay_peer_id, &storable, caught_up) {
+                    warn!("Failed to store pushed board posts from {}: {}", peer, e);
+                    return false;
+                }
+                let _ = self
+                    .event_tx
+                    .send(NetworkEvent::BoardPostsReceived {
+                        relay_peer_id,
+                        board_id,
+                        post_count,
+                    })
+                    .await;
+                true
+            }
+            BoardNotification::PostDeleted {
+                board_id, post_id, ..
+            } => {
+                if !boards.contains_key(&board_id) {
+                    return false;
+                }
+                if let Err(e) = board_service.apply_board_post_deletion(&relay_peer_id, &post_id) {
+                    warn!("Failed to apply pushed deletion from {}: {}", peer, e);
+                    return false;
+                }
+                let _ = self
+                    .event_tx
+                    .send(NetworkEvent::BoardPostDeleted {
+                        relay_peer_id,
+                        board_id,
+                        post_id,
+                    })
+                    .await;
+                true
+            }
+        }
+    }
+
     /// Attempt to connect to public relay servers
     /// This is called when we detect we're behind NAT or when manually requested
     pub async fn try_connect_to_relays(&mut self) {
@@ -2286,3 +3802,32 @@ impl NetworkService {
         self.connect_to_relays().await;
     }
 }
+
+fn storable_board_post(p: &BoardPostInfo) -> StorableBoardPost {
+    StorableBoardPost {
+        post_id: p.post_id.clone(),
+        board_id: p.board_id.clone(),
+        author_peer_id: p.author_peer_id.clone(),
+        author_display_name: p.author_display_name.clone(),
+        content_type: p.content_type.clone(),
+        content_text: p.content_text.clone(),
+        lamport_clock: p.lamport_clock as i64,
+        created_at: p.created_at,
+        deleted_at: p.deleted_at,
+        signature: p.signature.clone(),
+        received_at: p.received_at,
+        reply_to_post_id: p.reply_to_post_id.clone(),
+        thread_root_post_id: p.thread_root_post_id.clone(),
+        reply_count: p.reply_count as i64,
+    }
+}
+
+/// Trim custom status text, dropping it when empty. Returns `None` if the
+/// text is too long.
+fn normalize_status_text(text: Option<String>) -> Option<Option<String>> {
+    match text.as_deref().map(str::trim) {
+        Some(t) if t.chars().count() > MAX_STATUS_TEXT_CHARS => None,
+        Some("") | None => Some(None),
+        Some(t) => Some(Some(t.to_string())),
+    }
+}
@@ -4,6 +4,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::error::ErrorCode;
+
 /// Board sync request (wire protocol)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -22,6 +24,16 @@ pub enum BoardSyncRequest {
         limit: u32,
         timestamp: i64,
         signature: Vec<u8>,
+        /// Absent from older clients, which always get the flat stream
+        #[serde(default)]
+        mode: BoardPostsMode,
+    },
+    /// Get a thread root and all of its replies
+    GetThread {
+        requester_peer_id: String,
+        root_post_id: String,
+        timestamp: i64,
+        signature: Vec<u8>,
     },
     /// Submit a new post to a board
     SubmitPost {
@@ -33,6 +45,9 @@ pub enum BoardSyncRequest {
         lamport_clock: u64,
         created_at: i64,
         signature: Vec<u8>,
+        /// Parent post when this post is a reply
+        #[serde(default)]
+        reply_to_post_id: Option<String>,
     },
     /// Register a peer with the relay (required before posting)
     RegisterPeer {
@@ -49,6 +64,31 @@ pub enum BoardSyncRequest {
         timestamp: i64,
         signature: Vec<u8>,
     },
+    /// Receive pushed notifications for these boards while connected
+    Subscribe {
+        requester_peer_id: String,
+        board_ids: Vec<String>,
+        timestamp: i64,
+        signature: Vec<u8>,
+    },
+    /// Stop notifications for these boards (all boards if empty)
+    Unsubscribe {
+        requester_peer_id: String,
+        board_ids: Vec<String>,
+        timestamp: i64,
+        signature: Vec<u8>,
+    },
+}
+
+/// Which posts `GetBoardPosts` returns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardPostsMode {
+    /// Every post, replies included (incremental sync)
+    #[default]
+    Flat,
+    /// Top-level posts only, with reply counts
+    Threads,
 }
 
 /// Board info in responses
@@ -73,6 +113,58 @@ pub struct BoardPostInfo {
     pub created_at: i64,
     pub deleted_at: Option<i64>,
     pub signature: Vec<u8>,
+    /// When the serving relay stored the post (differs from created_at for
+    /// posts mirrored from a sibling relay)
+    #[serde(default)]
+    pub received_at: Option<i64>,
+    #[serde(default)]
+    pub reply_to_post_id: Option<String>,
+    #[serde(default)]
+    pub thread_root_post_id: Option<String>,
+    /// Live replies in the thread rooted at this post (0 for replies)
+    #[serde(default)]
+    pub reply_count: u32,
+}
+
+/// Machine-readable reason attached to `BoardSyncResponse::Error`
+///
+/// This is the relay wire encoding; clients surface it as the matching
+/// [`ErrorCode`] so relay rejections share codes with local errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardSyncErrorCode {
+    /// Per-peer rate limit for this operation exceeded
+    RateLimited,
+    /// Posts-per-hour quota exceeded
+    QuotaExceeded,
+    /// Post content exceeds the relay's size limit
+    PayloadTooLarge,
+    /// The relay's global request budget is exhausted
+    Overloaded,
+    /// The peer is banned from this relay
+    Banned,
+    /// Isnad verification is required for this operation
+    VerificationRequired,
+    /// The request was signed for a different peer than the one sending it
+    PermissionDenied,
+    /// A code this build doesn't know about
+    #[serde(other)]
+    Unknown,
+}
+
+impl From<BoardSyncErrorCode> for ErrorCode {
+    fn from(code: BoardSyncErrorCode) -> Self {
+        match code {
+            BoardSyncErrorCode::RateLimited => ErrorCode::RateLimited,
+            BoardSyncErrorCode::QuotaExceeded => ErrorCode::QuotaExceeded,
+            BoardSyncErrorCode::PayloadTooLarge => ErrorCode::PayloadTooLarge,
+            BoardSyncErrorCode::Overloaded => ErrorCode::RelayOverloaded,
+            BoardSyncErrorCode::Banned => ErrorCode::Banned,
+            BoardSyncErrorCode::VerificationRequired => ErrorCode::VerificationRequired,
+            BoardSyncErrorCode::PermissionDenied => ErrorCode::PermissionDenied,
+            BoardSyncErrorCode::Unknown => ErrorCode::NetworkError,
+        }
+    }
 }
 
 /// Board sync response (wire protocol)
@@ -89,6 +181,14 @@ pub enum BoardSyncResponse {
         board_id: String,
         posts: Vec<BoardPostInfo>,
         has_more: bool,
+        #[serde(default)]
+        mode: BoardPostsMode,
+    },
+    /// A thread root followed by its replies, oldest first
+    ThreadPosts {
+        board_id: String,
+        root_post_id: String,
+        posts: Vec<BoardPostInfo>,
     },
     /// Post was accepted
     PostAccepted { post_id: String },
@@ -96,6 +196,37 @@ pub enum BoardSyncResponse {
     PeerRegistered { peer_id: String },
     /// Post was deleted
     PostDeleted { post_id: String },
-    /// Error response
-    Error { error: String },
+    /// The requester's full set of board subscriptions after the change
+    Subscribed { board_ids: Vec<String> },
+    /// Error response. `code` and `retry_after_secs` are absent from older relays.
+    Error {
+        error: String,
+        #[serde(default)]
+        code: Option<BoardSyncErrorCode>,
+        #[serde(default)]
+        retry_after_secs: Option<u64>,
+    },
+}
+
+/// Relay-initiated notification for subscribed boards (board-notify protocol)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BoardNotification {
+    /// New posts on a board
+    Posts {
+        board_id: String,
+        posts: Vec<BoardPostInfo>,
+    },
+    /// A post on a board was deleted
+    PostDeleted {
+        board_id: String,
+        post_id: String,
+        deleted_at: i64,
+    },
+}
+
+/// Acknowledgement for a board notification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoardNotificationAck {
+    pub accepted: bool,
 }
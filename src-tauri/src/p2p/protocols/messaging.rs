@@ -73,6 +73,95 @@ pub enum AckStatus {
     Read,
 }
 
+/// A change to an earlier message of the conversation
+///
+/// Encrypted and nonce-counted like a direct message. Only the author of a
+/// message may edit or delete it; either peer may react to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageEvent {
+    /// Unique event ID (UUID v4)
+    pub event_id: String,
+    pub event_type: MessageEventType,
+    /// ID of the message the event applies to
+    pub message_id: String,
+    pub conversation_id: String,
+    pub sender_peer_id: String,
+    pub recipient_peer_id: String,
+    /// New content of an edit, or the reaction (empty to withdraw it);
+    /// empty plaintext for a delete
+    pub payload_encrypted: Vec<u8>,
+    /// Counter used for AES-GCM nonce generation (for replay protection)
+    pub nonce_counter: u64,
+    /// Lamport timestamp; the latest edit or reaction wins
+    pub lamport_clock: u64,
+    pub timestamp: i64,
+    /// Signature over all fields above (excluding signature itself)
+    pub signature: Vec<u8>,
+}
+
+/// Kind of change a [`MessageEvent`] makes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageEventType {
+    Edit,
+    /// Delete for everyone
+    Delete,
+    Reaction,
+}
+
+impl MessageEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageEventType::Edit => "edit",
+            MessageEventType::Delete => "delete",
+            MessageEventType::Reaction => "reaction",
+        }
+    }
+}
+
+/// Retention periods a conversation may be set to, in seconds:
+/// one hour, one day, one week and thirty days
+pub const RETENTION_PERIODS_SECS: [u64; 4] = [3_600, 86_400, 604_800, 2_592_000];
+
+/// How long messages of the conversation are kept, set by either peer
+///
+/// The newest update by (lamport_clock, sender_peer_id) wins on both sides.
+/// Each peer deletes messages once the period has passed since it received
+/// them, or since it sent them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionUpdate {
+    pub conversation_id: String,
+    pub sender_peer_id: String,
+    pub recipient_peer_id: String,
+    /// One of [`RETENTION_PERIODS_SECS`], or None to keep messages forever
+    pub retention_secs: Option<u64>,
+    pub lamport_clock: u64,
+    pub timestamp: i64,
+    /// Signature over all fields above (excluding signature itself)
+    pub signature: Vec<u8>,
+}
+
+/// Content type of messages whose content is an [`AttachmentManifest`]
+pub const ATTACHMENT_CONTENT_TYPE: &str = "attachment";
+
+/// Content of an attachment message (JSON, encrypted like any content)
+///
+/// The file itself is encrypted with `key` and fetched from the sender over
+/// the attachment protocol by the hash of the encrypted bytes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentManifest {
+    /// Hex SHA-256 of the encrypted file
+    pub blob_hash: String,
+    pub file_name: String,
+    pub mime_type: String,
+    /// Size of the file before encryption
+    pub size: u64,
+    /// Size of the encrypted file as transferred
+    pub encrypted_size: u64,
+    /// AES-256-GCM key of the file
+    pub key: Vec<u8>,
+}
+
 /// Request/response wrapper for messaging protocol
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -81,6 +170,10 @@ pub enum MessagingMessage {
     Message(DirectMessage),
     /// An acknowledgment
     Ack(MessageAck),
+    /// An edit, delete or reaction
+    Event(MessageEvent),
+    /// A change to the conversation's retention period
+    Retention(RetentionUpdate),
 }
 
 /// Codec for messaging protocol
@@ -186,4 +279,52 @@ mod tests {
             "Conversation ID should be the same regardless of order"
         );
     }
+
+    #[test]
+    fn test_message_event_roundtrip() {
+        let event = MessageEvent {
+            event_id: "evt-1".to_string(),
+            event_type: MessageEventType::Reaction,
+            message_id: "msg-123".to_string(),
+            conversation_id: "conv-456".to_string(),
+            sender_peer_id: "peer-a".to_string(),
+            recipient_peer_id: "peer-b".to_string(),
+            payload_encrypted: vec![1, 2, 3],
+            nonce_counter: 2,
+            lamport_clock: 3,
+            timestamp: 1234567890,
+            signature: vec![4, 5, 6],
+        };
+
+        let encoded = MessagingCodec::encode(&MessagingMessage::Event(event)).unwrap();
+        match MessagingCodec::decode(&encoded).unwrap() {
+            MessagingMessage::Event(decoded) => {
+                assert_eq!(decoded.event_type, MessageEventType::Reaction);
+                assert_eq!(decoded.payload_encrypted, vec![1, 2, 3]);
+            }
+            _ => panic!("Expected Event variant"),
+        }
+    }
+
+    #[test]
+    fn test_retention_update_roundtrip() {
+        let update = RetentionUpdate {
+            conversation_id: "conv-456".to_string(),
+            sender_peer_id: "peer-a".to_string(),
+            recipient_peer_id: "peer-b".to_string(),
+            retention_secs: None,
+            lamport_clock: 4,
+            timestamp: 1234567890,
+            signature: vec![7, 8, 9],
+        };
+
+        let encoded = MessagingCodec::encode(&MessagingMessage::Retention(update)).unwrap();
+        match MessagingCodec::decode(&encoded).unwrap() {
+            MessagingMessage::Retention(decoded) => {
+                assert_eq!(decoded.retention_secs, None);
+                assert_eq!(decoded.lamport_clock, 4);
+            }
+            _ => panic!("Expected Retention variant"),
+        }
+    }
 }
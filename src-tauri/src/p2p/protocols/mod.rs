@@ -9,11 +9,18 @@ pub use identity_exchange::*;
 pub use messaging::*;
 
 /// Protocol version string for identity exchange
-pub const IDENTITY_PROTOCOL: &str = "/harbor/identity/1.0.0";
+pub const IDENTITY_PROTOCOL: &str = "/harbor/identity/2.0.0";
+
+/// Identity exchange as spoken by peers that predate signed responses.
+/// Still served and requested; the response format is the same.
+pub const IDENTITY_PROTOCOL_V1: &str = "/harbor/identity/1.0.0";
 
 /// Protocol version string for direct messaging
 pub const MESSAGING_PROTOCOL: &str = "/harbor/messaging/1.0.0";
 
+/// Protocol version string for chunked transfer of message attachments
+pub const ATTACHMENT_PROTOCOL: &str = "/harbor/attachment/1.0.0";
+
 /// Protocol version string for content sync
 pub const CONTENT_SYNC_PROTOCOL: &str = "/harbor/content/1.0.0";
 
@@ -22,3 +29,12 @@ pub const SIGNALING_PROTOCOL: &str = "/harbor/signaling/1.0.0";
 
 /// Protocol version string for board sync (community boards)
 pub const BOARD_SYNC_PROTOCOL: &str = "/harbor/board/1.0.0";
+
+/// Protocol version string for profile updates and avatar transfer
+pub const PROFILE_PROTOCOL: &str = "/harbor/profile/1.0.0";
+
+/// Protocol version string for ephemeral signals (typing and presence)
+pub const SIGNAL_PROTOCOL: &str = "/harbor/signal/1.0.0";
+
+/// Protocol version string for relay-pushed board notifications
+pub const BOARD_NOTIFY_PROTOCOL: &str = "/harbor/board-notify/1.0.0";
@@ -2,9 +2,13 @@ use libp2p::{Multiaddr, PeerId};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::protocols::board_sync::BoardPostsMode;
+use crate::error::ErrorCode;
+
 /// Network connection status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum ConnectionStatus {
     Disconnected,
     Connecting,
@@ -14,6 +18,7 @@ pub enum ConnectionStatus {
 /// NAT status detected by AutoNAT
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum NatStatus {
     /// NAT status not yet determined
     #[default]
@@ -26,9 +31,34 @@ pub enum NatStatus {
     BehindNat,
 }
 
+/// Presence a peer shares with the peers it lets read its wall
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum PresenceStatus {
+    #[default]
+    Online,
+    Away,
+    DoNotDisturb,
+    /// Disconnected; never sent by peers
+    Offline,
+}
+
+/// Last presence received from a connected peer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PeerPresence {
+    pub peer_id: String,
+    pub status: PresenceStatus,
+    pub status_text: Option<String>,
+    pub updated_at: i64,
+}
+
 /// Information about a discovered or connected peer
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct PeerInfo {
     pub peer_id: String,
     pub addresses: Vec<String>,
@@ -41,6 +71,7 @@ pub struct PeerInfo {
 /// Network statistics
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct NetworkStats {
     pub connected_peers: usize,
     pub total_bytes_in: u64,
@@ -54,9 +85,27 @@ pub struct NetworkStats {
     pub external_addresses: Vec<String>,
 }
 
+impl NetworkStats {
+    /// Best address to share with contacts: a relay address (works through
+    /// NAT), else an external address with `/p2p/<peer_id>` appended
+    pub fn shareable_address(&self, peer_id: &str) -> Option<String> {
+        if let Some(addr) = self.relay_addresses.first() {
+            return Some(addr.clone());
+        }
+        self.external_addresses.first().map(|addr| {
+            if addr.contains("/p2p/") {
+                addr.clone()
+            } else {
+                format!("{}/p2p/{}", addr, peer_id)
+            }
+        })
+    }
+}
+
 /// Events emitted by the network layer to the application
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum NetworkEvent {
     /// A new peer was discovered (e.g., via mDNS)
     PeerDiscovered { peer_id: String },
@@ -76,6 +125,45 @@ pub enum NetworkEvent {
         protocol: String,
         payload: Vec<u8>,
     },
+    /// An edit, delete or reaction was applied to a message
+    MessageUpdated { peer_id: String, message_id: String },
+    /// All chunks of an incoming attachment were fetched and stored
+    AttachmentReceived { peer_id: String, message_id: String },
+    /// A contact changed how long our conversation's messages are kept;
+    /// `None` keeps them forever
+    RetentionChanged {
+        peer_id: String,
+        retention_secs: Option<u64>,
+    },
+    /// Messages passed their conversation's retention period and were deleted
+    MessagesExpired {
+        peer_id: String,
+        message_ids: Vec<String>,
+    },
+    /// A scheduled post or message was published; `published_id` is the
+    /// new post or message ID
+    ScheduledItemPublished {
+        schedule_id: String,
+        kind: String,
+        published_id: String,
+    },
+    /// A scheduled post or message could not be published
+    ScheduledItemFailed {
+        schedule_id: String,
+        kind: String,
+        error: String,
+    },
+    /// A contact started or stopped typing to us. `typing: true` repeats
+    /// while the contact keeps typing; typing that isn't refreshed within
+    /// `TYPING_TIMEOUT_SECS` should be shown as stopped.
+    TypingChanged { peer_id: String, typing: bool },
+    /// A peer that lets us read its wall changed its presence, or went
+    /// offline by disconnecting
+    PresenceChanged {
+        peer_id: String,
+        status: PresenceStatus,
+        status_text: Option<String>,
+    },
     /// Network status changed
     StatusChanged { status: ConnectionStatus },
     /// A contact was added via identity exchange
@@ -83,6 +171,23 @@ pub enum NetworkEvent {
         peer_id: String,
         display_name: String,
     },
+    /// A verified contact presented different keys. The new keys were stored
+    /// and the contact is unverified until the safety number is compared again.
+    ContactKeysChanged {
+        peer_id: String,
+        display_name: String,
+    },
+    /// A contact sent a newer profile
+    ContactProfileUpdated {
+        peer_id: String,
+        display_name: String,
+        avatar_hash: Option<String>,
+    },
+    /// A contact's avatar image was fetched and stored
+    ContactAvatarUpdated {
+        peer_id: String,
+        avatar_hash: String,
+    },
     /// NAT status changed
     NatStatusChanged { status: NatStatus },
     /// Successfully connected to a relay and have a relay address
@@ -110,6 +215,24 @@ pub enum NetworkEvent {
         board_id: String,
         post_count: usize,
     },
+    /// A board thread (root post and replies) received from a relay
+    BoardThreadReceived {
+        relay_peer_id: String,
+        board_id: String,
+        root_post_id: String,
+        post_count: usize,
+    },
+    /// A board post was deleted (pushed by a relay)
+    BoardPostDeleted {
+        relay_peer_id: String,
+        board_id: String,
+        post_id: String,
+    },
+    /// A relay confirmed our board subscriptions
+    BoardsSubscribed {
+        relay_peer_id: String,
+        board_ids: Vec<String>,
+    },
     /// Board post submitted successfully
     BoardPostSubmitted {
         relay_peer_id: String,
@@ -119,6 +242,8 @@ pub enum NetworkEvent {
     BoardSyncError {
         relay_peer_id: String,
         error: String,
+        code: Option<ErrorCode>,
+        retry_after_secs: Option<u64>,
     },
 }
 
@@ -181,12 +306,19 @@ pub enum NetworkCommand {
         board_id: String,
         after_timestamp: Option<i64>,
         limit: u32,
+        mode: BoardPostsMode,
+    },
+    /// Get a thread (root post and replies) from a relay
+    GetBoardThread {
+        relay_peer_id: PeerId,
+        root_post_id: String,
     },
-    /// Submit a board post to a relay
+    /// Submit a board post (or reply) to a relay
     SubmitBoardPost {
         relay_peer_id: PeerId,
         board_id: String,
         content_text: String,
+        reply_to_post_id: Option<String>,
     },
     /// Delete a board post on a relay
     DeleteBoardPost {
@@ -198,6 +330,25 @@ pub enum NetworkCommand {
         relay_peer_id: PeerId,
         board_id: String,
     },
+    /// Subscribe to pushed updates for boards on a relay (kept across reconnects)
+    SubscribeBoards {
+        relay_peer_id: PeerId,
+        board_ids: Vec<String>,
+    },
+    /// Stop pushed updates for boards on a relay (all boards if empty)
+    UnsubscribeBoards {
+        relay_peer_id: PeerId,
+        board_ids: Vec<String>,
+    },
+    /// Tell a contact we started or stopped typing to it
+    SetTyping { peer_id: PeerId, typing: bool },
+    /// Set our presence and share it with connected peers that may read our wall
+    SetPresence {
+        status: PresenceStatus,
+        status_text: Option<String>,
+    },
+    /// Get the presence of connected peers
+    GetPresence,
     /// Shutdown the network
     Shutdown,
 }
@@ -209,5 +360,6 @@ pub enum NetworkResponse {
     Stats(NetworkStats),
     Peers(Vec<PeerInfo>),
     Addresses(Vec<String>),
+    Presence(Vec<PeerPresence>),
     Error(String),
 }
@@ -7,7 +7,8 @@ use crate::db::{BoardsRepository, Database};
 use crate::error::{AppError, Result};
 use crate::services::{
     IdentityService, SignableBoardListRequest, SignableBoardPost, SignableBoardPostDelete,
-    SignableBoardPostsRequest, SignablePeerRegistration,
+    SignableBoardPostsRequest, SignableBoardSubscription, SignableBoardThreadRequest,
+    SignablePeerRegistration,
 };
 
 /// Service for managing community board operations
@@ -27,6 +28,7 @@ pub struct OutgoingBoardPost {
     pub lamport_clock: u64,
     pub created_at: i64,
     pub signature: Vec<u8>,
+    pub reply_to_post_id: Option<String>,
 }
 
 /// A peer registration request ready to be sent to the relay
@@ -58,6 +60,24 @@ pub struct OutgoingBoardPostsRequest {
     pub signature: Vec<u8>,
 }
 
+/// A board thread request ready to be sent
+#[derive(Debug, Clone)]
+pub struct OutgoingBoardThreadRequest {
+    pub requester_peer_id: String,
+    pub root_post_id: String,
+    pub timestamp: i64,
+    pub signature: Vec<u8>,
+}
+
+/// A board subscribe/unsubscribe request ready to be sent
+#[derive(Debug, Clone)]
+pub struct OutgoingBoardSubscription {
+    pub requester_peer_id: String,
+    pub board_ids: Vec<String>,
+    pub timestamp: i64,
+    pub signature: Vec<u8>,
+}
+
 /// A board post delete request
 #[derive(Debug, Clone)]
 pub struct OutgoingBoardPostDelete {
@@ -75,16 +95,17 @@ impl BoardService {
         }
     }
 
-    /// Create a signed board post for submission to a relay
+    /// Create a signed board post (or reply) for submission to a relay
     pub fn create_board_post(
         &self,
         board_id: &str,
         content_text: &str,
+        reply_to_post_id: Option<&str>,
     ) -> Result<OutgoingBoardPost> {
         let info = self
             .identity_service
             .get_identity_info()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let post_id = Uuid::new_v4().to_string();
         let now = chrono::Utc::now().timestamp();
@@ -98,6 +119,7 @@ impl BoardService {
             content_text: Some(content_text.to_string()),
             lamport_clock,
             created_at: now,
+            reply_to_post_id: reply_to_post_id.map(str::to_string),
         };
 
         let signature = self.identity_service.sign(&signable)?;
@@ -111,6 +133,7 @@ impl BoardService {
             lamport_clock,
             created_at: now,
             signature,
+            reply_to_post_id: reply_to_post_id.map(str::to_string),
         })
     }
 
@@ -119,7 +142,7 @@ impl BoardService {
         let info = self
             .identity_service
             .get_identity_info()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let now = chrono::Utc::now().timestamp();
 
@@ -150,7 +173,7 @@ impl BoardService {
         let info = self
             .identity_service
             .get_identity_info()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let now = chrono::Utc::now().timestamp();
         let signable = SignableBoardListRequest {
@@ -176,7 +199,7 @@ impl BoardService {
         let info = self
             .identity_service
             .get_identity_info()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let now = chrono::Utc::now().timestamp();
         let signable = SignableBoardPostsRequest {
@@ -196,12 +219,61 @@ impl BoardService {
         })
     }
 
+    /// Create a signed board thread request
+    pub fn create_get_thread_request(&self, root_post_id: &str) -> Result<OutgoingBoardThreadRequest> {
+        let info = self
+            .identity_service
+            .get_identity_info()?
+            .ok_or(AppError::IdentityNotFound)?;
+
+        let now = chrono::Utc::now().timestamp();
+        let signable = SignableBoardThreadRequest {
+            requester_peer_id: info.peer_id.clone(),
+            root_post_id: root_post_id.to_string(),
+            timestamp: now,
+        };
+        let signature = self.identity_service.sign(&signable)?;
+
+        Ok(OutgoingBoardThreadRequest {
+            requester_peer_id: info.peer_id,
+            root_post_id: root_post_id.to_string(),
+            timestamp: now,
+            signature,
+        })
+    }
+
+    /// Create a signed board subscribe/unsubscribe request
+    pub fn create_subscription_request(
+        &self,
+        board_ids: &[String],
+    ) -> Result<OutgoingBoardSubscription> {
+        let info = self
+            .identity_service
+            .get_identity_info()?
+            .ok_or(AppError::IdentityNotFound)?;
+
+        let now = chrono::Utc::now().timestamp();
+        let signable = SignableBoardSubscription {
+            requester_peer_id: info.peer_id.clone(),
+            board_ids: board_ids.to_vec(),
+            timestamp: now,
+        };
+        let signature = self.identity_service.sign(&signable)?;
+
+        Ok(OutgoingBoardSubscription {
+            requester_peer_id: info.peer_id,
+            board_ids: board_ids.to_vec(),
+            timestamp: now,
+            signature,
+        })
+    }
+
     /// Create a signed board post delete request
     pub fn create_delete_post_request(&self, post_id: &str) -> Result<OutgoingBoardPostDelete> {
         let info = self
             .identity_service
             .get_identity_info()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let now = chrono::Utc::now().timestamp();
         let signable = SignableBoardPostDelete {
@@ -274,6 +346,34 @@ impl BoardService {
         .map_err(AppError::Database)
     }
 
+    /// Get top-level posts for a board from local cache
+    pub fn get_board_threads(
+        &self,
+        relay_peer_id: &str,
+        board_id: &str,
+        limit: i64,
+        before_timestamp: Option<i64>,
+    ) -> Result<Vec<crate::db::BoardPost>> {
+        BoardsRepository::get_board_threads(
+            &self.db,
+            board_id,
+            relay_peer_id,
+            limit,
+            before_timestamp,
+        )
+        .map_err(AppError::Database)
+    }
+
+    /// Get a thread (root post and replies) from local cache
+    pub fn get_thread(
+        &self,
+        relay_peer_id: &str,
+        root_post_id: &str,
+    ) -> Result<Vec<crate::db::BoardPost>> {
+        BoardsRepository::get_thread_posts(&self.db, relay_peer_id, root_post_id)
+            .map_err(AppError::Database)
+    }
+
     /// Store boards received from a relay
     pub fn store_boards(
         &self,
@@ -294,11 +394,13 @@ impl BoardService {
         Ok(())
     }
 
-    /// Store board posts received from a relay
+    /// Store board posts received from a relay. The sync cursor only moves
+    /// for complete (flat) post streams, not for thread or top-level views.
     pub fn store_board_posts(
         &self,
         relay_peer_id: &str,
         posts: &[StorableBoardPost],
+        advance_cursor: bool,
     ) -> Result<()> {
         for post in posts {
             BoardsRepository::upsert_board_post(
@@ -314,15 +416,22 @@ impl BoardService {
                 post.created_at,
                 post.deleted_at,
                 &post.signature,
+                post.reply_to_post_id.as_deref(),
+                post.thread_root_post_id.as_deref(),
+                post.reply_count,
             )
             .map_err(AppError::Database)?;
 
-            // Update sync cursor
+            if !advance_cursor {
+                continue;
+            }
+
+            // Update sync cursor (relays filter on their own receive time)
             BoardsRepository::update_board_sync_cursor(
                 &self.db,
                 relay_peer_id,
                 &post.board_id,
-                post.created_at,
+                post.received_at.unwrap_or(post.created_at),
             )
             .map_err(AppError::Database)?;
         }
@@ -334,6 +443,29 @@ impl BoardService {
         Ok(())
     }
 
+    /// Apply a deletion pushed by a relay
+    pub fn apply_board_post_deletion(&self, relay_peer_id: &str, post_id: &str) -> Result<bool> {
+        BoardsRepository::delete_board_post(&self.db, post_id, relay_peer_id)
+            .map_err(AppError::Database)
+    }
+
+    /// Persist board subscriptions on a relay so they survive a restart
+    pub fn save_subscriptions(&self, relay_peer_id: &str, board_ids: &[String]) -> Result<()> {
+        BoardsRepository::add_board_subscriptions(&self.db, relay_peer_id, board_ids)
+            .map_err(AppError::Database)
+    }
+
+    /// Forget board subscriptions on a relay (all of them if `board_ids` is empty)
+    pub fn forget_subscriptions(&self, relay_peer_id: &str, board_ids: &[String]) -> Result<()> {
+        BoardsRepository::remove_board_subscriptions(&self.db, relay_peer_id, board_ids)
+            .map_err(AppError::Database)
+    }
+
+    /// Persisted board subscriptions as (relay_peer_id, board_id)
+    pub fn get_subscriptions(&self) -> Result<Vec<(String, String)>> {
+        BoardsRepository::get_board_subscriptions(&self.db).map_err(AppError::Database)
+    }
+
     /// Get sync cursor for a board
     pub fn get_sync_cursor(&self, relay_peer_id: &str, board_id: &str) -> Result<Option<i64>> {
         BoardsRepository::get_board_sync_cursor(&self.db, relay_peer_id, board_id)
@@ -354,4 +486,8 @@ pub struct StorableBoardPost {
     pub created_at: i64,
     pub deleted_at: Option<i64>,
     pub signature: Vec<u8>,
+    pub received_at: Option<i64>,
+    pub reply_to_post_id: Option<String>,
+    pub thread_root_post_id: Option<String>,
+    pub reply_count: i64,
 }
@@ -121,7 +121,7 @@ impl CallingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         // Check we have call permission with this peer
         if !self
@@ -169,7 +169,7 @@ impl CallingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         // Verify we are the callee
         if callee_peer_id != identity.peer_id {
@@ -225,7 +225,7 @@ impl CallingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let timestamp = chrono::Utc::now().timestamp();
 
@@ -262,7 +262,7 @@ impl CallingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         // Verify we are the caller
         if caller_peer_id != identity.peer_id {
@@ -309,7 +309,7 @@ impl CallingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let timestamp = chrono::Utc::now().timestamp();
 
@@ -384,7 +384,7 @@ impl CallingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let timestamp = chrono::Utc::now().timestamp();
 
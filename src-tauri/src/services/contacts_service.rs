@@ -1,9 +1,26 @@
 //! Contacts service for managing peer relationships
 
-use crate::db::{Contact, ContactData, ContactsRepository, Database};
+use crate::db::{
+    Blob, BlobsRepository, Contact, ContactData, ContactsRepository, Database, TrustLevel,
+};
 use crate::error::{AppError, Result};
-use crate::services::IdentityService;
+use crate::services::{IdentityService, SafetyNumber, SafetyNumberParty, MAX_AVATAR_BYTES};
 use std::sync::Arc;
+use tracing::warn;
+
+/// Outcome of [`ContactsService::update_contact_info`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactUpdate {
+    /// The peer is not a contact
+    NotFound,
+    /// The profile is not newer than the one stored; nothing changed
+    Stale,
+    /// Profile updated, keys unchanged
+    Updated,
+    /// The contact presented different keys; they replaced the old ones and
+    /// the contact is unverified now
+    KeysChanged { was_verified: bool },
+}
 
 /// Service for managing contacts
 pub struct ContactsService {
@@ -44,18 +61,24 @@ impl ContactsService {
             .map_err(|e| AppError::DatabaseString(e.to_string()))?
         {
             // Update existing contact info instead
-            ContactsRepository::update_contact_info(
-                &self.db,
+            let update = self.update_contact_info(
                 peer_id,
+                public_key,
+                x25519_public,
                 display_name,
                 avatar_hash,
                 bio,
-            )
-            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+            )?;
+            if update == (ContactUpdate::KeysChanged { was_verified: true }) {
+                warn!(
+                    "Keys of verified contact {} changed; verification dropped",
+                    peer_id
+                );
+            }
 
             // Return existing contact's ID
-            let contact = ContactsRepository::get_by_peer_id(&self.db, peer_id)
-                .map_err(|e| AppError::DatabaseString(e.to_string()))?
+            let contact = self
+                .get_contact(peer_id)?
                 .ok_or_else(|| AppError::NotFound("Contact not found".to_string()))?;
             return Ok(contact.id);
         }
@@ -67,6 +90,7 @@ impl ContactsService {
             display_name: display_name.to_string(),
             avatar_hash: avatar_hash.map(String::from),
             bio: bio.map(String::from),
+            profile_version: 0,
         };
 
         ContactsRepository::add_contact(&self.db, &contact_data)
@@ -90,15 +114,130 @@ impl ContactsService {
             .map_err(|e| AppError::DatabaseString(e.to_string()))
     }
 
-    /// Update contact info (from network)
+    /// Update contact info (from network). New keys replace the stored ones
+    /// and drop the contact's verification.
     pub fn update_contact_info(
         &self,
         peer_id: &str,
+        public_key: &[u8],
+        x25519_public: &[u8],
         display_name: &str,
         avatar_hash: Option<&str>,
         bio: Option<&str>,
-    ) -> Result<bool> {
-        ContactsRepository::update_contact_info(&self.db, peer_id, display_name, avatar_hash, bio)
+    ) -> Result<ContactUpdate> {
+        self.apply_profile(&ContactData {
+            peer_id: peer_id.to_string(),
+            public_key: public_key.to_vec(),
+            x25519_public: x25519_public.to_vec(),
+            display_name: display_name.to_string(),
+            avatar_hash: avatar_hash.map(String::from),
+            bio: bio.map(String::from),
+            profile_version: 0,
+        })
+    }
+
+    /// Apply a profile announced by a contact. A versioned profile that isn't
+    /// newer than the stored one is ignored; version 0 always applies.
+    pub fn apply_profile(&self, profile: &ContactData) -> Result<ContactUpdate> {
+        let Some(contact) = self.get_contact(&profile.peer_id)? else {
+            return Ok(ContactUpdate::NotFound);
+        };
+        if profile.profile_version > 0 && profile.profile_version <= contact.profile_version {
+            return Ok(ContactUpdate::Stale);
+        }
+
+        ContactsRepository::update_contact_info(
+            &self.db,
+            &profile.peer_id,
+            &profile.display_name,
+            profile.avatar_hash.as_deref(),
+            profile.bio.as_deref(),
+            profile.profile_version,
+        )
+        .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+
+        if let Some(old_hash) = contact.avatar_hash.as_deref() {
+            if profile.avatar_hash.as_deref() != Some(old_hash) {
+                BlobsRepository::delete_if_unreferenced(&self.db, old_hash)
+                    .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+            }
+        }
+
+        if contact.public_key == profile.public_key
+            && contact.x25519_public == profile.x25519_public
+        {
+            return Ok(ContactUpdate::Updated);
+        }
+        ContactsRepository::update_keys(
+            &self.db,
+            &profile.peer_id,
+            &profile.public_key,
+            &profile.x25519_public,
+        )
+        .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+        Ok(ContactUpdate::KeysChanged {
+            was_verified: contact.is_verified(),
+        })
+    }
+
+    /// Hash of the contact's avatar if its image hasn't been fetched yet
+    pub fn missing_avatar(&self, peer_id: &str) -> Result<Option<String>> {
+        let Some(hash) = self.get_contact(peer_id)?.and_then(|c| c.avatar_hash) else {
+            return Ok(None);
+        };
+        let stored = BlobsRepository::exists(&self.db, &hash)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+        Ok((!stored).then_some(hash))
+    }
+
+    /// Store an avatar image fetched from a contact. The bytes must hash to
+    /// the contact's current avatar hash.
+    pub fn store_avatar(
+        &self,
+        peer_id: &str,
+        avatar_hash: &str,
+        mime_type: &str,
+        data: &[u8],
+    ) -> Result<()> {
+        let contact = self
+            .get_contact(peer_id)?
+            .ok_or_else(|| AppError::NotFound("Contact not found".to_string()))?;
+        if contact.avatar_hash.as_deref() != Some(avatar_hash) {
+            return Err(AppError::Validation(
+                "Not the contact's current avatar".to_string(),
+            ));
+        }
+        if data.len() > MAX_AVATAR_BYTES || !mime_type.starts_with("image/") {
+            return Err(AppError::Validation("Invalid avatar image".to_string()));
+        }
+        if BlobsRepository::hash(data) != avatar_hash {
+            return Err(AppError::Validation(
+                "Avatar does not match its hash".to_string(),
+            ));
+        }
+
+        BlobsRepository::put(&self.db, mime_type, data)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Get a contact's avatar image, if it has one and it has been fetched
+    pub fn get_avatar(&self, peer_id: &str) -> Result<Option<Blob>> {
+        let Some(hash) = self.get_contact(peer_id)?.and_then(|c| c.avatar_hash) else {
+            return Ok(None);
+        };
+        BlobsRepository::get(&self.db, &hash).map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+
+    /// Highest identity response version a contact has sent (None if not a contact)
+    pub fn identity_version(&self, peer_id: &str) -> Result<Option<u32>> {
+        ContactsRepository::get_identity_version(&self.db, peer_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+
+    /// Record the version of an identity response or profile a contact sent
+    pub fn record_identity_version(&self, peer_id: &str, version: u32) -> Result<bool> {
+        ContactsRepository::record_identity_version(&self.db, peer_id, version)
             .map_err(|e| AppError::DatabaseString(e.to_string()))
     }
 
@@ -138,6 +277,59 @@ impl ContactsService {
             .map_err(|e| AppError::DatabaseString(e.to_string()))
     }
 
+    /// Safety number of our conversation with a contact
+    pub fn get_safety_number(&self, peer_id: &str) -> Result<SafetyNumber> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+        let contact = self
+            .get_contact(peer_id)?
+            .ok_or_else(|| AppError::NotFound("Contact not found".to_string()))?;
+
+        Ok(SafetyNumber::compute(
+            &SafetyNumberParty {
+                peer_id: &identity.peer_id,
+                public_key: &identity.public_key,
+                x25519_public: &identity.x25519_public,
+            },
+            &SafetyNumberParty {
+                peer_id: &contact.peer_id,
+                public_key: &contact.public_key,
+                x25519_public: &contact.x25519_public,
+            },
+        ))
+    }
+
+    /// Mark a contact verified after comparing safety numbers
+    pub fn mark_verified(&self, peer_id: &str) -> Result<()> {
+        self.set_trust_level(peer_id, TrustLevel::Verified)
+    }
+
+    /// Verify a contact with the QR payload scanned from their device.
+    /// Returns whether it matched; the contact is marked verified if so.
+    pub fn verify_scanned_safety_number(&self, peer_id: &str, scanned: &str) -> Result<bool> {
+        if !self.get_safety_number(peer_id)?.matches_qr(scanned) {
+            return Ok(false);
+        }
+        self.mark_verified(peer_id)?;
+        Ok(true)
+    }
+
+    /// Drop a contact's verification
+    pub fn clear_verification(&self, peer_id: &str) -> Result<()> {
+        self.set_trust_level(peer_id, TrustLevel::Unverified)
+    }
+
+    fn set_trust_level(&self, peer_id: &str, level: TrustLevel) -> Result<()> {
+        let updated = ContactsRepository::set_trust_level(&self.db, peer_id, level.as_i32())
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+        if !updated {
+            return Err(AppError::NotFound("Contact not found".to_string()));
+        }
+        Ok(())
+    }
+
     /// Get X25519 public key for a contact (needed for encryption)
     pub fn get_x25519_public(&self, peer_id: &str) -> Result<Option<Vec<u8>>> {
         let contact = self.get_contact(peer_id)?;
@@ -163,6 +355,10 @@ mod tests {
         (db, identity_service, contacts_service)
     }
 
+    fn is_verified(service: &ContactsService, peer_id: &str) -> bool {
+        service.get_contact(peer_id).unwrap().unwrap().is_verified()
+    }
+
     #[test]
     fn test_add_and_get_contact() {
         let (_, _, service) = create_test_services();
@@ -209,4 +405,134 @@ mod tests {
         let active = service.get_active_contacts().unwrap();
         assert!(active.is_empty());
     }
+
+    #[test]
+    fn test_changed_keys_drop_verification() {
+        let (_, _, service) = create_test_services();
+        service
+            .add_contact("12D3KooWTest", &[1; 32], &[2; 32], "Test User", None, None)
+            .unwrap();
+        service.mark_verified("12D3KooWTest").unwrap();
+
+        let update = service
+            .update_contact_info("12D3KooWTest", &[1; 32], &[2; 32], "Renamed", None, None)
+            .unwrap();
+        assert_eq!(update, ContactUpdate::Updated);
+        assert!(is_verified(&service, "12D3KooWTest"));
+
+        let update = service
+            .update_contact_info("12D3KooWTest", &[1; 32], &[9; 32], "Renamed", None, None)
+            .unwrap();
+        assert_eq!(update, ContactUpdate::KeysChanged { was_verified: true });
+
+        let contact = service.get_contact("12D3KooWTest").unwrap().unwrap();
+        assert!(!contact.is_verified());
+        assert_eq!(contact.x25519_public, vec![9; 32]);
+        assert_eq!(
+            service
+                .update_contact_info("12D3KooWOther", &[1; 32], &[2; 32], "Nobody", None, None)
+                .unwrap(),
+            ContactUpdate::NotFound
+        );
+    }
+
+    #[test]
+    fn test_verify_scanned_safety_number() {
+        let (_, identity_service, service) = create_test_services();
+        identity_service
+            .create_identity(crate::models::CreateIdentityRequest {
+                display_name: "Me".to_string(),
+                passphrase: "correct horse battery staple".to_string(),
+                bio: None,
+                passphrase_hint: None,
+            })
+            .unwrap();
+        service
+            .add_contact("12D3KooWTest", &[1; 32], &[2; 32], "Test User", None, None)
+            .unwrap();
+
+        let number = service.get_safety_number("12D3KooWTest").unwrap();
+        assert!(!service
+            .verify_scanned_safety_number("12D3KooWTest", "HARBORSN1:0000")
+            .unwrap());
+        assert!(!is_verified(&service, "12D3KooWTest"));
+
+        assert!(service
+            .verify_scanned_safety_number("12D3KooWTest", &number.qr_payload)
+            .unwrap());
+        assert!(is_verified(&service, "12D3KooWTest"));
+
+        service.clear_verification("12D3KooWTest").unwrap();
+        assert!(!is_verified(&service, "12D3KooWTest"));
+        assert!(service.mark_verified("12D3KooWOther").is_err());
+    }
+
+    fn profile(version: i64, display_name: &str, avatar_hash: Option<&str>) -> ContactData {
+        ContactData {
+            peer_id: "12D3KooWTest".to_string(),
+            public_key: vec![1; 32],
+            x25519_public: vec![2; 32],
+            display_name: display_name.to_string(),
+            avatar_hash: avatar_hash.map(String::from),
+            bio: None,
+            profile_version: version,
+        }
+    }
+
+    #[test]
+    fn test_stale_profiles_are_ignored() {
+        let (_, _, service) = create_test_services();
+        service
+            .add_contact("12D3KooWTest", &[1; 32], &[2; 32], "Test User", None, None)
+            .unwrap();
+
+        let update = service.apply_profile(&profile(3, "Third", None)).unwrap();
+        assert_eq!(update, ContactUpdate::Updated);
+
+        // A replayed older profile doesn't undo the newer one
+        let update = service.apply_profile(&profile(2, "Second", None)).unwrap();
+        assert_eq!(update, ContactUpdate::Stale);
+        let contact = service.get_contact("12D3KooWTest").unwrap().unwrap();
+        assert_eq!(contact.display_name, "Third");
+        assert_eq!(contact.profile_version, 3);
+
+        // Unversioned updates still apply and keep the version
+        service
+            .update_contact_info("12D3KooWTest", &[1; 32], &[2; 32], "Manual", None, None)
+            .unwrap();
+        let contact = service.get_contact("12D3KooWTest").unwrap().unwrap();
+        assert_eq!(contact.display_name, "Manual");
+        assert_eq!(contact.profile_version, 3);
+    }
+
+    #[test]
+    fn test_store_avatar_checks_hash() {
+        let (_, _, service) = create_test_services();
+        let image = b"contact avatar";
+        let hash = BlobsRepository::hash(image);
+        service
+            .add_contact("12D3KooWTest", &[1; 32], &[2; 32], "Test User", None, None)
+            .unwrap();
+        service
+            .apply_profile(&profile(1, "Test User", Some(&hash)))
+            .unwrap();
+        assert_eq!(
+            service.missing_avatar("12D3KooWTest").unwrap(),
+            Some(hash.clone())
+        );
+
+        assert!(service
+            .store_avatar("12D3KooWTest", &hash, "image/png", b"tampered")
+            .is_err());
+        assert!(service.get_avatar("12D3KooWTest").unwrap().is_none());
+
+        service
+            .store_avatar("12D3KooWTest", &hash, "image/png", image)
+            .unwrap();
+        assert_eq!(service.missing_avatar("12D3KooWTest").unwrap(), None);
+        assert_eq!(
+            service.get_avatar("12D3KooWTest").unwrap().unwrap().data,
+            image
+        );
+    }
 }
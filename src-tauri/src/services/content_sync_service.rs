@@ -89,7 +89,7 @@ impl ContentSyncService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let timestamp = chrono::Utc::now().timestamp();
 
@@ -120,7 +120,7 @@ impl ContentSyncService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let timestamp = chrono::Utc::now().timestamp();
 
@@ -152,7 +152,7 @@ impl ContentSyncService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         // Validate timestamp is within acceptable window (5 minutes)
         let now = chrono::Utc::now().timestamp();
@@ -243,7 +243,7 @@ impl ContentSyncService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         // Verify the requester's signature
         let requester_public_key = self
@@ -508,7 +508,7 @@ impl ContentSyncService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         // We are syncing *from* peer_id, so the cursor is keyed by (source_peer_id=peer_id)
         // for our local identity.
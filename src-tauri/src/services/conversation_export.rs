@@ -0,0 +1,391 @@
+//! Conversation export to JSON, Markdown and mbox
+//!
+//! Exports carry the decrypted history along with whether each message's
+//! signed events still verify against the sender's key, so a reader can tell
+//! an intact record from one that was tampered with or arrived unsigned.
+
+use chrono::{TimeZone, Utc};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::p2p::protocols::messaging::ATTACHMENT_CONTENT_TYPE;
+
+/// Format of a conversation export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Markdown,
+    /// One RFC 5322 message per chat message, mboxrd-quoted
+    Mbox,
+}
+
+impl ExportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "markdown",
+            ExportFormat::Mbox => "mbox",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "json" => Some(ExportFormat::Json),
+            "markdown" | "md" => Some(ExportFormat::Markdown),
+            "mbox" => Some(ExportFormat::Mbox),
+            _ => None,
+        }
+    }
+
+    /// Parse a format name, as a validation error if unknown
+    pub fn parse(s: &str) -> Result<Self> {
+        Self::from_str(s)
+            .ok_or_else(|| AppError::Validation(format!("Unknown export format: {}", s)))
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Mbox => "mbox",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+            ExportFormat::Mbox => "application/mbox",
+        }
+    }
+}
+
+/// Whether a message's signed events verify
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// The message and every edit, delete and reaction on it verify
+    Verified,
+    /// At least one event doesn't verify against its sender's key
+    Invalid,
+    /// No signed record of the message was kept, or the sender's key is unknown
+    Unverified,
+}
+
+impl SignatureStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureStatus::Verified => "verified",
+            SignatureStatus::Invalid => "invalid",
+            SignatureStatus::Unverified => "unverified",
+        }
+    }
+}
+
+/// One message of an export
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedMessage {
+    pub message_id: String,
+    pub sender_peer_id: String,
+    pub sender_name: String,
+    pub lamport_clock: i64,
+    pub sent_at: i64,
+    pub edited_at: Option<i64>,
+    pub deleted_at: Option<i64>,
+    pub content_type: String,
+    /// Message text, or the file name of an attachment; empty once deleted
+    pub content: String,
+    pub reply_to_message_id: Option<String>,
+    /// (peer ID, reaction) of every peer that reacted
+    pub reactions: Vec<(String, String)>,
+    pub signature: SignatureStatus,
+}
+
+/// A conversation rendered for saving
+#[derive(Debug, Clone)]
+pub struct ConversationExport {
+    pub file_name: String,
+    pub mime_type: &'static str,
+    pub content: String,
+}
+
+/// A conversation to export, oldest message first
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationTranscript {
+    pub conversation_id: String,
+    pub our_peer_id: String,
+    pub our_name: String,
+    pub peer_id: String,
+    pub peer_name: String,
+    pub exported_at: i64,
+    pub messages: Vec<ExportedMessage>,
+}
+
+impl ConversationTranscript {
+    /// Render the transcript in `format`
+    pub fn render(&self, format: ExportFormat) -> Result<ConversationExport> {
+        let content = match format {
+            ExportFormat::Json => serde_json::to_string_pretty(self)
+                .map_err(|e| AppError::Serialization(e.to_string()))?,
+            ExportFormat::Markdown => self.to_markdown(),
+            ExportFormat::Mbox => self.to_mbox(),
+        };
+        Ok(ConversationExport {
+            file_name: format!(
+                "harbor-conversation-{}-{}.{}",
+                file_name_part(&self.peer_name),
+                format_time(self.exported_at, "%Y%m%d-%H%M%S"),
+                format.extension()
+            ),
+            mime_type: format.mime_type(),
+            content,
+        })
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "# Conversation with {}\n\n- Peer: `{}`\n- Exported: {}\n- Messages: {}\n",
+            self.peer_name,
+            self.peer_id,
+            format_time(self.exported_at, "%Y-%m-%d %H:%M:%S UTC"),
+            self.messages.len()
+        );
+        for message in &self.messages {
+            out.push_str(&format!(
+                "\n## {} — {}\n\n",
+                message.sender_name,
+                format_time(message.sent_at, "%Y-%m-%d %H:%M:%S UTC")
+            ));
+
+            let mut notes = vec![format!("signature {}", message.signature.as_str())];
+            if let Some(reply_to) = &message.reply_to_message_id {
+                notes.push(format!("reply to `{}`", reply_to));
+            }
+            if message.edited_at.is_some() {
+                notes.push("edited".to_string());
+            }
+            out.push_str(&format!("*{}*\n\n", notes.join(", ")));
+
+            if message.deleted_at.is_some() {
+                out.push_str("*This message was deleted.*\n");
+            } else if message.content_type == ATTACHMENT_CONTENT_TYPE {
+                out.push_str(&format!("Attachment: `{}`\n", message.content));
+            } else {
+                for line in message.content.lines() {
+                    out.push_str(&format!("> {}\n", line));
+                }
+            }
+
+            if !message.reactions.is_empty() {
+                let reactions: Vec<String> = message
+                    .reactions
+                    .iter()
+                    .map(|(peer_id, reaction)| {
+                        format!("{} ({})", reaction, self.name_of(peer_id))
+                    })
+                    .collect();
+                out.push_str(&format!("\nReactions: {}\n", reactions.join(", ")));
+            }
+        }
+        out
+    }
+
+    fn to_mbox(&self) -> String {
+        let mut out = String::new();
+        for message in &self.messages {
+            let recipient = if message.sender_peer_id == self.our_peer_id {
+                (&self.peer_name, &self.peer_id)
+            } else {
+                (&self.our_name, &self.our_peer_id)
+            };
+            out.push_str(&format!(
+                "From {}@harbor {}\n",
+                message.sender_peer_id,
+                format_time(message.sent_at, "%a %b %e %H:%M:%S %Y")
+            ));
+            out.push_str(&format!(
+                "From: {} <{}@harbor>\n",
+                header_value(&message.sender_name),
+                message.sender_peer_id
+            ));
+            out.push_str(&format!(
+                "To: {} <{}@harbor>\n",
+                header_value(recipient.0),
+                recipient.1
+            ));
+            out.push_str(&format!(
+                "Date: {}\n",
+                Utc.timestamp_opt(message.sent_at, 0)
+                    .single()
+                    .unwrap_or_default()
+                    .to_rfc2822()
+            ));
+            out.push_str(&format!("Message-ID: <{}@harbor>\n", message.message_id));
+            if let Some(reply_to) = &message.reply_to_message_id {
+                out.push_str(&format!("In-Reply-To: <{}@harbor>\n", reply_to));
+            }
+            out.push_str(&format!(
+                "X-Harbor-Conversation: {}\nX-Harbor-Lamport-Clock: {}\nX-Harbor-Signature: {}\n",
+                self.conversation_id,
+                message.lamport_clock,
+                message.signature.as_str()
+            ));
+            if message.edited_at.is_some() {
+                out.push_str("X-Harbor-Edited: yes\n");
+            }
+            out.push_str("Content-Type: text/plain; charset=utf-8\n\n");
+
+            let body = if message.deleted_at.is_some() {
+                "[deleted]".to_string()
+            } else if message.content_type == ATTACHMENT_CONTENT_TYPE {
+                format!("[attachment: {}]", message.content)
+            } else {
+                message.content.clone()
+            };
+            for line in body.lines() {
+                // mboxrd: quote every "From " line, quoted or not, with one more '>'
+                if line.trim_start_matches('>').starts_with("From ") {
+                    out.push('>');
+                }
+                out.push_str(line);
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn name_of<'a>(&'a self, peer_id: &'a str) -> &'a str {
+        if peer_id == self.our_peer_id {
+            &self.our_name
+        } else if peer_id == self.peer_id {
+            &self.peer_name
+        } else {
+            peer_id
+        }
+    }
+}
+
+fn format_time(timestamp: i64, format: &str) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .unwrap_or_default()
+        .format(format)
+        .to_string()
+}
+
+/// Keep header values on one line
+fn header_value(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+/// Reduce a display name to characters safe in a file name
+fn file_name_part(name: &str) -> String {
+    let mut part = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            part.push(c);
+        } else if !part.is_empty() && !part.ends_with('-') {
+            part.push('-');
+        }
+    }
+    let part: String = part.chars().take(40).collect();
+    let part = part.trim_end_matches('-');
+    if part.is_empty() {
+        "peer".to_string()
+    } else {
+        part.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript() -> ConversationTranscript {
+        let message = |id: &str, sender: &str, name: &str, content: &str| ExportedMessage {
+            message_id: id.to_string(),
+            sender_peer_id: sender.to_string(),
+            sender_name: name.to_string(),
+            lamport_clock: 1,
+            sent_at: 1_700_000_000,
+            edited_at: None,
+            deleted_at: None,
+            content_type: "text".to_string(),
+            content: content.to_string(),
+            reply_to_message_id: None,
+            reactions: Vec::new(),
+            signature: SignatureStatus::Verified,
+        };
+        let mut deleted = message("m3", "me", "Me", "");
+        deleted.deleted_at = Some(1_700_000_100);
+        let mut tampered = message("m2", "alice", "Alice", "From here on\n>From there");
+        tampered.signature = SignatureStatus::Invalid;
+        tampered.reactions = vec![("me".to_string(), "👍".to_string())];
+
+        ConversationTranscript {
+            conversation_id: "conv".to_string(),
+            our_peer_id: "me".to_string(),
+            our_name: "Me".to_string(),
+            peer_id: "alice".to_string(),
+            peer_name: "Alice / Work".to_string(),
+            exported_at: 1_700_000_200,
+            messages: vec![message("m1", "me", "Me", "hi"), tampered, deleted],
+        }
+    }
+
+    #[test]
+    fn test_format_names() {
+        for format in [ExportFormat::Json, ExportFormat::Markdown, ExportFormat::Mbox] {
+            assert_eq!(ExportFormat::from_str(format.as_str()), Some(format));
+        }
+        assert!(ExportFormat::parse("pdf").is_err());
+    }
+
+    #[test]
+    fn test_json_carries_signature_status() {
+        let export = transcript().render(ExportFormat::Json).unwrap();
+        assert!(export.file_name.starts_with("harbor-conversation-Alice-Work-"));
+        assert!(export.file_name.ends_with(".json"));
+
+        let value: serde_json::Value = serde_json::from_str(&export.content).unwrap();
+        assert_eq!(value["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(value["messages"][1]["signature"], "invalid");
+        assert_eq!(value["messages"][0]["signature"], "verified");
+    }
+
+    #[test]
+    fn test_markdown() {
+        let export = transcript().render(ExportFormat::Markdown).unwrap();
+        assert!(export.content.starts_with("# Conversation with Alice / Work"));
+        assert!(export.content.contains("*signature invalid*"));
+        assert!(export.content.contains("> From here on\n> >From there\n"));
+        assert!(export.content.contains("Reactions: 👍 (Me)"));
+        assert!(export.content.contains("*This message was deleted.*"));
+    }
+
+    #[test]
+    fn test_mbox_quotes_from_lines() {
+        let export = transcript().render(ExportFormat::Mbox).unwrap();
+        let separators = export
+            .content
+            .lines()
+            .filter(|line| line.starts_with("From "))
+            .count();
+        assert_eq!(separators, 3);
+        assert!(export.content.contains("\n>From here on\n>>From there\n"));
+        assert!(export.content.contains("X-Harbor-Signature: invalid\n"));
+        assert!(export.content.contains("To: Alice / Work <alice@harbor>\n"));
+        assert!(export.content.contains("\n[deleted]\n"));
+    }
+
+    #[test]
+    fn test_file_name_part() {
+        assert_eq!(file_name_part("Alice / Work"), "Alice-Work");
+        assert_eq!(file_name_part("  --Bob--  "), "Bob");
+        assert_eq!(file_name_part("!!!"), "peer");
+    }
+}
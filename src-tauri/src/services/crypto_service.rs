@@ -42,6 +42,15 @@ impl CryptoService {
         peer_id.to_string()
     }
 
+    /// Derive the libp2p peer ID of a raw 32-byte Ed25519 public key, as received
+    /// from a peer
+    pub fn peer_id_from_public_key(public_key: &[u8]) -> Result<String> {
+        let public_key = libp2p::identity::ed25519::PublicKey::try_from_bytes(public_key)
+            .map_err(|e| AppError::Crypto(format!("Invalid public key: {}", e)))?;
+        let public_key = libp2p::identity::PublicKey::from(public_key);
+        Ok(libp2p::PeerId::from(public_key).to_string())
+    }
+
     /// Derive a peer ID from an Ed25519 public key (DEPRECATED - use derive_peer_id_from_signing_key)
     /// This uses a simplified hash-based approach that is NOT compatible with libp2p
     #[deprecated(note = "Use derive_peer_id_from_signing_key instead for libp2p compatibility")]
@@ -159,7 +168,7 @@ impl CryptoService {
 
         let plaintext = cipher
             .decrypt(nonce, ciphertext)
-            .map_err(|_| AppError::Crypto("Decryption failed - wrong passphrase?".to_string()))?;
+            .map_err(|_| AppError::InvalidPassphrase)?;
 
         let keys: EncryptedKeys = serde_json::from_slice(&plaintext)
             .map_err(|e| AppError::Serialization(format!("Failed to deserialize keys: {}", e)))?;
@@ -229,6 +238,26 @@ impl CryptoService {
         key
     }
 
+    /// Derive a key for data only we read, such as content queued on this
+    /// device, from our X25519 secret. `purpose` separates keys per use.
+    pub fn derive_local_key(secret: &X25519Secret, purpose: &str) -> [u8; 32] {
+        use hkdf::Hkdf;
+
+        let salt = format!("harbor:v1:local:{}", purpose);
+        let hk = Hkdf::<Sha256>::new(Some(salt.as_bytes()), secret.as_bytes());
+        let mut key = [0u8; 32];
+        hk.expand(b"local-key", &mut key)
+            .expect("HKDF expand failed");
+        key
+    }
+
+    /// Generate a random AES-256-GCM key
+    pub fn generate_symmetric_key() -> [u8; 32] {
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        key
+    }
+
     /// Encrypt a message using AES-256-GCM
     pub fn encrypt_message(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>> {
         let cipher = Aes256Gcm::new_from_slice(key)
@@ -453,6 +482,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_peer_id_from_public_key() {
+        let (signing_key, verifying_key) = CryptoService::generate_ed25519_keypair();
+
+        assert_eq!(
+            CryptoService::peer_id_from_public_key(verifying_key.as_bytes()).unwrap(),
+            CryptoService::derive_peer_id_from_signing_key(&signing_key)
+        );
+        assert!(CryptoService::peer_id_from_public_key(&[1, 2, 3]).is_err());
+    }
+
     #[test]
     #[allow(deprecated)]
     fn test_peer_id_derivation_legacy() {
@@ -22,6 +22,43 @@ pub struct FeedItem {
     pub author_display_name: Option<String>,
 }
 
+/// Position in the feed: the last item of the previous page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedCursor {
+    pub created_at: i64,
+    pub post_id: String,
+}
+
+impl FeedCursor {
+    /// Parse a cursor in its `<created_at>:<post_id>` string form
+    pub fn parse(cursor: &str) -> Result<Self> {
+        cursor
+            .split_once(':')
+            .and_then(|(created_at, post_id)| {
+                Some(Self {
+                    created_at: created_at.parse().ok()?,
+                    post_id: post_id.to_string(),
+                })
+            })
+            .filter(|c| !c.post_id.is_empty())
+            .ok_or_else(|| AppError::Validation(format!("Invalid feed cursor: {}", cursor)))
+    }
+}
+
+impl std::fmt::Display for FeedCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.created_at, self.post_id)
+    }
+}
+
+/// A page of the feed
+#[derive(Debug, Clone)]
+pub struct FeedPage {
+    pub items: Vec<FeedItem>,
+    /// Cursor for the next page, if there may be more
+    pub next_cursor: Option<FeedCursor>,
+}
+
 impl FeedService {
     /// Create a new feed service
     pub fn new(
@@ -46,10 +83,43 @@ impl FeedService {
     /// - Only non-deleted posts
     /// - Sorted by creation time, newest first
     pub fn get_feed(&self, limit: i64, before_timestamp: Option<i64>) -> Result<Vec<FeedItem>> {
+        self.build_feed(limit, |author| {
+            PostsRepository::get_by_author(&self.db, author, limit, before_timestamp)
+        })
+    }
+
+    /// Get one page of the feed, newest first.
+    ///
+    /// Pages are keyed on `(created_at, post_id)` so posts sharing a timestamp
+    /// are neither skipped nor repeated across page boundaries. Pass the
+    /// returned `next_cursor` to get the following page.
+    pub fn get_feed_page(&self, limit: i64, cursor: Option<&FeedCursor>) -> Result<FeedPage> {
+        let before = cursor.map(|c| (c.created_at, c.post_id.as_str()));
+        let items = self.build_feed(limit, |author| {
+            PostsRepository::get_by_author_page(&self.db, author, limit, before)
+        })?;
+
+        let next_cursor = if items.len() as i64 >= limit {
+            items.last().map(|item| FeedCursor {
+                created_at: item.post.created_at,
+                post_id: item.post.post_id.clone(),
+            })
+        } else {
+            None
+        };
+
+        Ok(FeedPage { items, next_cursor })
+    }
+
+    /// Merge posts from every author visible to us, newest first
+    fn build_feed<F>(&self, limit: i64, fetch: F) -> Result<Vec<FeedItem>>
+    where
+        F: Fn(&str) -> rusqlite::Result<Vec<Post>>,
+    {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         // Get all peer IDs who granted us WallRead
         let permissions = self.permissions_service.get_received_permissions()?;
@@ -69,8 +139,7 @@ impl FeedService {
         // Get posts from all allowed authors
         let mut all_posts = Vec::new();
         for author in &allowed_authors {
-            let posts = PostsRepository::get_by_author(&self.db, author, limit, before_timestamp)
-                .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+            let posts = fetch(author).map_err(|e| AppError::DatabaseString(e.to_string()))?;
 
             // Filter: if it's not our own post and visibility is "contacts",
             // make sure we have permission (we already checked above)
@@ -88,8 +157,12 @@ impl FeedService {
             }
         }
 
-        // Sort by created_at descending
-        all_posts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        // Sort by created_at descending (post_id breaks ties so paging is stable)
+        all_posts.sort_by(|a, b| {
+            b.created_at
+                .cmp(&a.created_at)
+                .then_with(|| b.post_id.cmp(&a.post_id))
+        });
 
         // Apply limit
         all_posts.truncate(limit as usize);
@@ -140,7 +213,7 @@ impl FeedService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         // Check permission if not our own wall
         if author_peer_id != identity.peer_id
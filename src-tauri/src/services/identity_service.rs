@@ -1,19 +1,25 @@
 use crate::db::repositories::IdentityRepository;
-use crate::db::Database;
+use crate::db::{Blob, BlobsRepository, Database, SearchRepository};
 use crate::error::{AppError, Result};
 use crate::models::{CreateIdentityRequest, IdentityInfo, LocalIdentity};
 use crate::services::{sign as signing_sign, CryptoService, Signable};
 
 use ed25519_dalek::SigningKey;
 use std::sync::{Arc, RwLock};
-use tracing::info;
+use tokio::sync::watch;
+use tracing::{info, warn};
 use x25519_dalek::StaticSecret as X25519Secret;
 
+/// Largest avatar image accepted, in bytes
+pub const MAX_AVATAR_BYTES: usize = 512 * 1024;
+
 /// Service for managing the local user's identity
 pub struct IdentityService {
     db: Arc<Database>,
     /// Cached unlocked keys (only available after unlock)
     unlocked_keys: Arc<RwLock<Option<UnlockedKeys>>>,
+    /// Latest profile version, for pushing profile changes to contacts
+    profile_tx: Arc<watch::Sender<i64>>,
 }
 
 /// Keys that are available after unlocking with passphrase
@@ -25,9 +31,11 @@ pub struct UnlockedKeys {
 
 impl IdentityService {
     pub fn new(db: Arc<Database>) -> Self {
+        let (profile_tx, _) = watch::channel(0);
         Self {
             db,
             unlocked_keys: Arc::new(RwLock::new(None)),
+            profile_tx: Arc::new(profile_tx),
         }
     }
 
@@ -94,6 +102,7 @@ impl IdentityService {
             avatar_hash: None,
             bio: request.bio,
             passphrase_hint: request.passphrase_hint,
+            profile_version: 0,
             created_at: now,
             updated_at: now,
         };
@@ -117,9 +126,7 @@ impl IdentityService {
     pub fn unlock(&self, passphrase: &str) -> Result<IdentityInfo> {
         let repo = IdentityRepository::new(&self.db);
 
-        let identity = repo
-            .get()?
-            .ok_or_else(|| AppError::NotFound("No identity found".to_string()))?;
+        let identity = repo.get()?.ok_or(AppError::IdentityNotFound)?;
 
         // Decrypt private keys
         let keys = CryptoService::decrypt_keys(&identity.private_key_encrypted, passphrase)?;
@@ -155,15 +162,17 @@ impl IdentityService {
     pub fn lock(&self) {
         let mut unlocked = self.unlocked_keys.write().unwrap();
         *unlocked = None;
+        // Decrypted message text must not outlive the keys
+        if let Err(e) = SearchRepository::drop_message_index(&self.db) {
+            warn!("Failed to drop message search index: {}", e);
+        }
         info!("Identity locked");
     }
 
     /// Get the unlocked keys (for signing/encryption operations)
     pub fn get_unlocked_keys(&self) -> Result<UnlockedKeys> {
         let unlocked = self.unlocked_keys.read().unwrap();
-        unlocked
-            .clone()
-            .ok_or_else(|| AppError::PermissionDenied("Identity is locked".to_string()))
+        unlocked.clone().ok_or(AppError::IdentityLocked)
     }
 
     /// Sign raw data using the unlocked Ed25519 key
@@ -189,13 +198,73 @@ impl IdentityService {
     pub fn update_display_name(&self, display_name: &str) -> Result<()> {
         let repo = IdentityRepository::new(&self.db);
         repo.update_display_name(display_name)?;
-        Ok(())
+        self.notify_profile_changed()
     }
 
     /// Update bio
     pub fn update_bio(&self, bio: Option<&str>) -> Result<()> {
         let repo = IdentityRepository::new(&self.db);
         repo.update_bio(bio)?;
+        self.notify_profile_changed()
+    }
+
+    /// Set the avatar image; returns its hash
+    pub fn set_avatar(&self, data: &[u8], mime_type: &str) -> Result<String> {
+        if data.is_empty() || data.len() > MAX_AVATAR_BYTES {
+            return Err(AppError::Validation(format!(
+                "Avatar must be between 1 and {} bytes",
+                MAX_AVATAR_BYTES
+            )));
+        }
+        if !mime_type.starts_with("image/") {
+            return Err(AppError::Validation(format!(
+                "Avatar must be an image, got {}",
+                mime_type
+            )));
+        }
+
+        let hash = BlobsRepository::put(&self.db, mime_type, data)?;
+        self.replace_avatar(Some(&hash))?;
+        Ok(hash)
+    }
+
+    /// Remove the avatar
+    pub fn clear_avatar(&self) -> Result<()> {
+        self.replace_avatar(None)
+    }
+
+    /// Get the avatar image, if one is set
+    pub fn get_avatar(&self) -> Result<Option<Blob>> {
+        match self.get_identity()?.and_then(|i| i.avatar_hash) {
+            Some(hash) => Ok(BlobsRepository::get(&self.db, &hash)?),
+            None => Ok(None),
+        }
+    }
+
+    /// Watch the profile version; changes whenever name, bio or avatar do
+    pub fn subscribe_profile_changes(&self) -> watch::Receiver<i64> {
+        self.profile_tx.subscribe()
+    }
+
+    fn replace_avatar(&self, avatar_hash: Option<&str>) -> Result<()> {
+        let repo = IdentityRepository::new(&self.db);
+        let old_hash = repo.get()?.ok_or(AppError::IdentityNotFound)?.avatar_hash;
+        if old_hash.as_deref() == avatar_hash {
+            return Ok(());
+        }
+
+        repo.update_avatar(avatar_hash)?;
+        if let Some(old_hash) = old_hash {
+            BlobsRepository::delete_if_unreferenced(&self.db, &old_hash)?;
+        }
+        self.notify_profile_changed()
+    }
+
+    fn notify_profile_changed(&self) -> Result<()> {
+        let repo = IdentityRepository::new(&self.db);
+        if let Some(identity) = repo.get()? {
+            self.profile_tx.send_replace(identity.profile_version);
+        }
         Ok(())
     }
 
@@ -209,9 +278,7 @@ impl IdentityService {
     /// Get the local peer ID
     pub fn get_peer_id(&self) -> Result<String> {
         let repo = IdentityRepository::new(&self.db);
-        let identity = repo
-            .get()?
-            .ok_or_else(|| AppError::NotFound("No identity found".to_string()))?;
+        let identity = repo.get()?.ok_or(AppError::IdentityNotFound)?;
         Ok(identity.peer_id)
     }
 }
@@ -221,6 +288,7 @@ impl Clone for IdentityService {
         Self {
             db: Arc::clone(&self.db),
             unlocked_keys: Arc::clone(&self.unlocked_keys),
+            profile_tx: Arc::clone(&self.profile_tx),
         }
     }
 }
@@ -330,4 +398,40 @@ mod tests {
         let result = service.sign_raw(b"test data");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_avatar_bumps_profile_version() {
+        let service = create_test_service();
+
+        let request = CreateIdentityRequest {
+            display_name: "Test User".to_string(),
+            passphrase: "test-passphrase".to_string(),
+            bio: None,
+            passphrase_hint: None,
+        };
+
+        service.create_identity(request).unwrap();
+        let changes = service.subscribe_profile_changes();
+
+        let hash = service.set_avatar(b"png bytes", "image/png").unwrap();
+        let identity = service.get_identity().unwrap().unwrap();
+        assert_eq!(identity.avatar_hash.as_deref(), Some(hash.as_str()));
+        assert_eq!(identity.profile_version, 1);
+        assert_eq!(*changes.borrow(), 1);
+        assert_eq!(service.get_avatar().unwrap().unwrap().data, b"png bytes");
+
+        // Setting the same image again changes nothing
+        service.set_avatar(b"png bytes", "image/png").unwrap();
+        assert_eq!(service.get_identity().unwrap().unwrap().profile_version, 1);
+
+        assert!(service.set_avatar(b"text", "text/plain").is_err());
+        assert!(service
+            .set_avatar(&vec![0; MAX_AVATAR_BYTES + 1], "image/png")
+            .is_err());
+
+        service.clear_avatar().unwrap();
+        assert!(service.get_avatar().unwrap().is_none());
+        assert!(!BlobsRepository::exists(&service.db, &hash).unwrap());
+        assert_eq!(*changes.borrow(), 2);
+    }
 }
@@ -1,20 +1,54 @@
 //! Messaging service for sending and receiving direct messages
 
-use ed25519_dalek::VerifyingKey;
+use ed25519_dalek::{Signature, VerifyingKey};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use uuid::Uuid;
 use x25519_dalek::PublicKey as X25519Public;
 
 use crate::db::{
-    Capability, Conversation, Database, MessageData, MessageStatus, MessagesRepository,
+    BlobsRepository, Capability, Conversation, ConversationRetention, Database, ExpiredMessages,
+    Message, MessageAttachment, MessageData, MessageReaction, MessageSearchEntry, MessageStatus,
+    MessagesRepository, RetentionRepository, SearchRepository,
 };
 use crate::error::{AppError, Result};
-use crate::p2p::protocols::messaging::derive_conversation_id;
+use crate::p2p::protocols::messaging::{
+    derive_conversation_id, AttachmentManifest, DirectMessage, MessageEvent, MessageEventType,
+    RetentionUpdate, ATTACHMENT_CONTENT_TYPE, RETENTION_PERIODS_SECS,
+};
+use crate::services::conversation_export::{
+    ConversationExport, ConversationTranscript, ExportFormat, ExportedMessage, SignatureStatus,
+};
 use crate::services::{
     verify, ContactsService, CryptoService, IdentityService, PermissionsService, Signable,
-    SignableDirectMessage, SignableMessageAck,
+    SignableDirectMessage, SignableMessageAck, SignableMessageEvent, SignableRetentionUpdate,
 };
 
+/// Largest file that can be sent as an attachment
+pub const MAX_ATTACHMENT_BYTES: usize = 16 * 1024 * 1024;
+
+/// Size of the chunks attachments are transferred in
+pub const ATTACHMENT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Longest reaction accepted, in bytes
+const MAX_REACTION_BYTES: usize = 32;
+
+/// Messages read per query while exporting a conversation
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Blob MIME type of encrypted attachment files
+const ENCRYPTED_BLOB_MIME_TYPE: &str = "application/octet-stream";
+
+/// Whether a fetched chunk of `chunk_len` bytes fits an attachment download
+/// of `size` bytes that has received `received` bytes so far. Every chunk but
+/// the last is full size.
+pub fn is_valid_attachment_chunk(size: usize, received: usize, chunk_len: usize) -> bool {
+    let remaining = size.saturating_sub(received);
+    chunk_len > 0
+        && chunk_len <= remaining
+        && (chunk_len == ATTACHMENT_CHUNK_SIZE || chunk_len == remaining)
+}
+
 /// Service for managing direct messages
 pub struct MessagingService {
     db: Arc<Database>,
@@ -33,11 +67,72 @@ pub struct DecryptedMessage {
     pub content: String,
     pub content_type: String,
     pub reply_to_message_id: Option<String>,
+    pub lamport_clock: i64,
     pub sent_at: i64,
     pub delivered_at: Option<i64>,
     pub read_at: Option<i64>,
     pub status: String,
     pub is_outgoing: bool,
+    pub edited_at: Option<i64>,
+    pub deleted_at: Option<i64>,
+    /// (peer ID, reaction) of every peer that reacted
+    pub reactions: Vec<(String, String)>,
+    pub attachment: Option<AttachmentInfo>,
+}
+
+/// Position in a conversation: the oldest message of the previous page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageCursor {
+    pub lamport_clock: i64,
+    pub message_id: String,
+}
+
+impl MessageCursor {
+    /// Parse a cursor in its `<lamport_clock>:<message_id>` string form
+    pub fn parse(cursor: &str) -> Result<Self> {
+        cursor
+            .split_once(':')
+            .and_then(|(lamport_clock, message_id)| {
+                Some(Self {
+                    lamport_clock: lamport_clock.parse().ok()?,
+                    message_id: message_id.to_string(),
+                })
+            })
+            .filter(|c| !c.message_id.is_empty())
+            .ok_or_else(|| AppError::Validation(format!("Invalid message cursor: {}", cursor)))
+    }
+}
+
+impl std::fmt::Display for MessageCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.lamport_clock, self.message_id)
+    }
+}
+
+/// A page of a conversation, oldest message first
+#[derive(Debug, Clone)]
+pub struct MessagePage {
+    pub messages: Vec<DecryptedMessage>,
+    /// Cursor for the page before this one, if there may be more
+    pub next_cursor: Option<MessageCursor>,
+}
+
+/// Metadata of a message attachment
+#[derive(Debug, Clone)]
+pub struct AttachmentInfo {
+    pub file_name: String,
+    pub mime_type: String,
+    pub size: u64,
+    /// Whether the file has been transferred and can be opened
+    pub downloaded: bool,
+}
+
+/// A decrypted attachment file
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub file_name: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
 }
 
 /// A message ready to be sent over the network
@@ -56,6 +151,25 @@ pub struct OutgoingMessage {
     pub signature: Vec<u8>,
 }
 
+impl OutgoingMessage {
+    /// The message as sent over the messaging protocol
+    pub fn to_direct_message(&self) -> DirectMessage {
+        DirectMessage {
+            message_id: self.message_id.clone(),
+            conversation_id: self.conversation_id.clone(),
+            sender_peer_id: self.sender_peer_id.clone(),
+            recipient_peer_id: self.recipient_peer_id.clone(),
+            content_encrypted: self.content_encrypted.clone(),
+            content_type: self.content_type.clone(),
+            reply_to: self.reply_to.clone(),
+            nonce_counter: self.nonce_counter,
+            lamport_clock: self.lamport_clock,
+            timestamp: self.timestamp,
+            signature: self.signature.clone(),
+        }
+    }
+}
+
 impl MessagingService {
     /// Create a new messaging service
     pub fn new(
@@ -84,7 +198,7 @@ impl MessagingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         // Check we have chat permission with this peer
         if !self
@@ -96,28 +210,10 @@ impl MessagingService {
             ));
         }
 
-        // Get recipient's X25519 public key for encryption
-        let x25519_public = self
-            .contacts_service
-            .get_x25519_public(recipient_peer_id)?
-            .ok_or_else(|| AppError::NotFound("Contact not found".to_string()))?;
-
-        // Get our X25519 keys
-        let our_keys = self.identity_service.get_unlocked_keys()?;
-
         // Derive conversation ID and encryption key
         let conversation_id = derive_conversation_id(&identity.peer_id, recipient_peer_id);
-        let their_public = X25519Public::from(
-            <[u8; 32]>::try_from(x25519_public.as_slice())
-                .map_err(|_| AppError::Crypto("Invalid X25519 key".to_string()))?,
-        );
-        let shared_secret = CryptoService::x25519_dh(&our_keys.x25519_secret, &their_public);
-        let conv_key = CryptoService::derive_conversation_key(
-            &shared_secret,
-            &conversation_id,
-            &identity.peer_id,
-            recipient_peer_id,
-        );
+        let conv_key =
+            self.conversation_key(&identity.peer_id, recipient_peer_id, &conversation_id)?;
 
         // Get next nonce counter
         let nonce_counter = self
@@ -235,7 +331,7 @@ impl MessagingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         tracing::info!(
             "MESSAGE RECEIVE - recipient in msg: {} (len={}) vs our identity: {} (len={})",
@@ -254,15 +350,6 @@ impl MessagingService {
             return Err(AppError::Validation("Message not for us".to_string()));
         }
 
-        // Check for replay (BEFORE decryption)
-        if !self
-            .db
-            .check_and_record_nonce(conversation_id, sender_peer_id, nonce_counter)
-            .map_err(|e| AppError::DatabaseString(e.to_string()))?
-        {
-            return Err(AppError::Crypto("Replay attack detected".to_string()));
-        }
-
         // Get sender's public key for verification
         tracing::info!("Looking up sender {} in contacts", sender_peer_id);
         let sender_public_key = self
@@ -309,6 +396,16 @@ impl MessagingService {
             return Ok(()); // Already processed
         }
 
+        // Check for replay (BEFORE decryption). Only signed messages count, so
+        // a forged one can't use up the nonce of the real one.
+        if !self
+            .db
+            .check_and_record_nonce(conversation_id, sender_peer_id, nonce_counter)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+        {
+            return Err(AppError::Crypto("Replay attack detected".to_string()));
+        }
+
         // Update lamport clock
         self.db
             .update_lamport_clock(sender_peer_id, lamport_clock as i64)
@@ -334,6 +431,10 @@ impl MessagingService {
         MessagesRepository::insert_message(&self.db, &msg_data)
             .map_err(|e| AppError::DatabaseString(e.to_string()))?;
 
+        if content_type == ATTACHMENT_CONTENT_TYPE {
+            self.record_incoming_attachment(&msg_data)?;
+        }
+
         // Record event
         let event_id = format!("received:{}", message_id);
         let payload_cbor = signable.signable_bytes()?;
@@ -360,7 +461,7 @@ impl MessagingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let message = MessagesRepository::get_by_message_id(&self.db, message_id)
             .map_err(|e| AppError::DatabaseString(e.to_string()))?
@@ -386,7 +487,7 @@ impl MessagingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let message = MessagesRepository::get_by_message_id(&self.db, message_id)
             .map_err(|e| AppError::DatabaseString(e.to_string()))?
@@ -479,7 +580,7 @@ impl MessagingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let conversation_id = derive_conversation_id(&identity.peer_id, peer_id);
 
@@ -492,39 +593,258 @@ impl MessagingService {
         )
         .map_err(|e| AppError::DatabaseString(e.to_string()))?;
 
-        // Get peer's X25519 key for decryption
-        let x25519_public = self
+        self.decrypt_messages(&identity.peer_id, peer_id, &conversation_id, messages)
+    }
+
+    /// Get one page of a conversation, decrypted and oldest first.
+    ///
+    /// Pages are keyed on `(lamport_clock, message_id)`, so messages arriving
+    /// while scrolling back neither shift nor repeat older pages. Pass the
+    /// returned `next_cursor` to get the page before this one.
+    pub fn get_conversation_page(
+        &self,
+        peer_id: &str,
+        limit: i64,
+        cursor: Option<&MessageCursor>,
+    ) -> Result<MessagePage> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+
+        let conversation_id = derive_conversation_id(&identity.peer_id, peer_id);
+        let before = cursor.map(|c| (c.lamport_clock, c.message_id.as_str()));
+        let messages =
+            MessagesRepository::get_conversation_page(&self.db, &conversation_id, limit, before)
+                .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+
+        let next_cursor = if messages.len() as i64 >= limit {
+            messages.first().map(|m| MessageCursor {
+                lamport_clock: m.lamport_clock,
+                message_id: m.message_id.clone(),
+            })
+        } else {
+            None
+        };
+
+        let messages =
+            self.decrypt_messages(&identity.peer_id, peer_id, &conversation_id, messages)?;
+        Ok(MessagePage {
+            messages,
+            next_cursor,
+        })
+    }
+
+    /// Export our whole conversation with a peer, noting for every message
+    /// whether its signed events still verify
+    pub fn export_conversation(
+        &self,
+        peer_id: &str,
+        format: ExportFormat,
+    ) -> Result<ConversationExport> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+        let contact = self
             .contacts_service
-            .get_x25519_public(peer_id)?
+            .get_contact(peer_id)?
             .ok_or_else(|| AppError::NotFound("Contact not found".to_string()))?;
+        let conversation_id = derive_conversation_id(&identity.peer_id, peer_id);
 
-        let our_keys = self.identity_service.get_unlocked_keys()?;
+        // Walk the history back page by page
+        let mut pages = Vec::new();
+        let mut before: Option<(i64, String)> = None;
+        loop {
+            let page = MessagesRepository::get_conversation_page(
+                &self.db,
+                &conversation_id,
+                EXPORT_PAGE_SIZE,
+                before.as_ref().map(|(clock, id)| (*clock, id.as_str())),
+            )
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+            let Some(first) = page.first() else {
+                break;
+            };
+            before = Some((first.lamport_clock, first.message_id.clone()));
+            let full = page.len() as i64 >= EXPORT_PAGE_SIZE;
+            pages.push(page);
+            if !full {
+                break;
+            }
+        }
+        let messages: Vec<Message> = pages.into_iter().rev().flatten().collect();
+        let messages =
+            self.decrypt_messages(&identity.peer_id, peer_id, &conversation_id, messages)?;
 
-        // Derive conversation key
-        let their_public = X25519Public::from(
-            <[u8; 32]>::try_from(x25519_public.as_slice())
-                .map_err(|_| AppError::Crypto("Invalid X25519 key".to_string()))?,
-        );
-        let shared_secret = CryptoService::x25519_dh(&our_keys.x25519_secret, &their_public);
-        let conv_key = CryptoService::derive_conversation_key(
-            &shared_secret,
+        let signatures = self.verify_conversation_events(
             &conversation_id,
             &identity.peer_id,
+            &identity.public_key,
             peer_id,
-        );
+            &contact.public_key,
+        )?;
+
+        let name_of = |sender: &str| {
+            if sender == identity.peer_id {
+                identity.display_name.clone()
+            } else {
+                contact.display_name.clone()
+            }
+        };
+        let transcript = ConversationTranscript {
+            conversation_id,
+            our_peer_id: identity.peer_id.clone(),
+            our_name: identity.display_name.clone(),
+            peer_id: peer_id.to_string(),
+            peer_name: contact.display_name.clone(),
+            exported_at: chrono::Utc::now().timestamp(),
+            messages: messages
+                .into_iter()
+                .map(|m| ExportedMessage {
+                    signature: signatures
+                        .get(&m.message_id)
+                        .copied()
+                        .unwrap_or(SignatureStatus::Unverified),
+                    sender_name: name_of(&m.sender_peer_id),
+                    message_id: m.message_id,
+                    sender_peer_id: m.sender_peer_id,
+                    lamport_clock: m.lamport_clock,
+                    sent_at: m.sent_at,
+                    edited_at: m.edited_at,
+                    deleted_at: m.deleted_at,
+                    content_type: m.content_type,
+                    content: m.content,
+                    reply_to_message_id: m.reply_to_message_id,
+                    reactions: m.reactions,
+                })
+                .collect(),
+        };
+        transcript.render(format)
+    }
+
+    /// Check the recorded events of a conversation against the keys of the
+    /// two parties. A message verifies if its own signed record and every
+    /// edit, delete and reaction on it do; one bad signature makes it invalid.
+    fn verify_conversation_events(
+        &self,
+        conversation_id: &str,
+        our_peer_id: &str,
+        our_public_key: &[u8],
+        peer_id: &str,
+        peer_public_key: &[u8],
+    ) -> Result<HashMap<String, SignatureStatus>> {
+        let key = |bytes: &[u8]| {
+            <[u8; 32]>::try_from(bytes)
+                .ok()
+                .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+        };
+        let our_key = key(our_public_key);
+        let peer_key = key(peer_public_key);
+
+        let events = MessagesRepository::get_conversation_events(&self.db, conversation_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+
+        let mut statuses: HashMap<String, SignatureStatus> = HashMap::new();
+        let mut signed: HashSet<String> = HashSet::new();
+        for event in events {
+            let verifying_key = if event.sender_peer_id == our_peer_id {
+                our_key.as_ref()
+            } else if event.sender_peer_id == peer_id {
+                peer_key.as_ref()
+            } else {
+                None
+            };
+            let valid = verifying_key.is_some_and(|key| {
+                Signature::from_slice(&event.signature)
+                    .is_ok_and(|sig| CryptoService::verify(key, &event.payload_cbor, &sig))
+            });
+
+            if !valid {
+                statuses.insert(event.message_id, SignatureStatus::Invalid);
+                continue;
+            }
+            if event.event_type == "sent" || event.event_type == "received" {
+                signed.insert(event.message_id.clone());
+            }
+            statuses
+                .entry(event.message_id)
+                .or_insert(SignatureStatus::Verified);
+        }
+
+        // Messages with only valid follow-up events but no record of their own
+        for (message_id, status) in statuses.iter_mut() {
+            if *status == SignatureStatus::Verified && !signed.contains(message_id) {
+                *status = SignatureStatus::Unverified;
+            }
+        }
+        Ok(statuses)
+    }
+
+    /// Decrypt messages of our conversation with a peer, with their reactions
+    fn decrypt_messages(
+        &self,
+        our_peer_id: &str,
+        peer_id: &str,
+        conversation_id: &str,
+        messages: Vec<Message>,
+    ) -> Result<Vec<DecryptedMessage>> {
+        // Derive conversation key
+        let conv_key = self.conversation_key(our_peer_id, peer_id, conversation_id)?;
+
+        // Decrypt reactions; an empty reaction is a withdrawn one
+        let mut reactions: HashMap<String, Vec<(String, String)>> = HashMap::new();
+        for reaction in MessagesRepository::get_conversation_reactions(&self.db, conversation_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+        {
+            let Ok(bytes) = CryptoService::decrypt_message_with_counter(
+                &conv_key,
+                &reaction.reaction_encrypted,
+                reaction.nonce_counter,
+            ) else {
+                continue;
+            };
+            if !bytes.is_empty() {
+                reactions.entry(reaction.message_id).or_default().push((
+                    reaction.reactor_peer_id,
+                    String::from_utf8_lossy(&bytes).to_string(),
+                ));
+            }
+        }
 
         // Decrypt messages
         let mut decrypted = Vec::new();
         for msg in messages {
-            let content = match CryptoService::decrypt_message_with_counter(
-                &conv_key,
-                &msg.content_encrypted,
-                msg.nonce_counter,
-            ) {
-                Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
-                Err(_) => "[Decryption failed]".to_string(),
+            let mut content = if msg.deleted_at.is_some() {
+                String::new()
+            } else {
+                match CryptoService::decrypt_message_with_counter(
+                    &conv_key,
+                    &msg.content_encrypted,
+                    msg.nonce_counter,
+                ) {
+                    Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+                    Err(_) => "[Decryption failed]".to_string(),
+                }
             };
 
+            // Attachments show their file name as content
+            let mut attachment = None;
+            if msg.content_type == ATTACHMENT_CONTENT_TYPE && msg.deleted_at.is_none() {
+                if let Ok(manifest) = serde_json::from_str::<AttachmentManifest>(&content) {
+                    let downloaded = BlobsRepository::exists(&self.db, &manifest.blob_hash)
+                        .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+                    content = manifest.file_name.clone();
+                    attachment = Some(AttachmentInfo {
+                        file_name: manifest.file_name,
+                        mime_type: manifest.mime_type,
+                        size: manifest.size,
+                        downloaded,
+                    });
+                }
+            }
+
+            let reactions = reactions.remove(&msg.message_id).unwrap_or_default();
             decrypted.push(DecryptedMessage {
                 message_id: msg.message_id,
                 conversation_id: msg.conversation_id,
@@ -533,11 +853,16 @@ impl MessagingService {
                 content,
                 content_type: msg.content_type,
                 reply_to_message_id: msg.reply_to_message_id,
+                lamport_clock: msg.lamport_clock,
                 sent_at: msg.sent_at,
                 delivered_at: msg.delivered_at,
                 read_at: msg.read_at,
                 status: msg.status,
-                is_outgoing: msg.sender_peer_id == identity.peer_id,
+                is_outgoing: msg.sender_peer_id == our_peer_id,
+                edited_at: msg.edited_at,
+                deleted_at: msg.deleted_at,
+                reactions,
+                attachment,
             });
         }
 
@@ -549,7 +874,7 @@ impl MessagingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         MessagesRepository::get_conversations(&self.db, &identity.peer_id)
             .map_err(|e| AppError::DatabaseString(e.to_string()))
@@ -560,7 +885,7 @@ impl MessagingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let conversation_id = derive_conversation_id(&identity.peer_id, peer_id);
         let timestamp = chrono::Utc::now().timestamp();
@@ -579,7 +904,7 @@ impl MessagingService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let conversation_id = derive_conversation_id(&identity.peer_id, peer_id);
 
@@ -592,4 +917,1203 @@ impl MessagingService {
         MessagesRepository::update_status(&self.db, message_id, status)
             .map_err(|e| AppError::DatabaseString(e.to_string()))
     }
+
+    /// Edit one of our messages; returns the event to send to the peer
+    pub fn edit_message(
+        &self,
+        peer_id: &str,
+        message_id: &str,
+        content: &str,
+    ) -> Result<MessageEvent> {
+        let message = self.own_message(peer_id, message_id)?;
+        if message.content_type == ATTACHMENT_CONTENT_TYPE {
+            return Err(AppError::Validation(
+                "Attachments cannot be edited".to_string(),
+            ));
+        }
+        self.create_event(
+            peer_id,
+            &message,
+            MessageEventType::Edit,
+            content.as_bytes(),
+        )
+    }
+
+    /// Delete one of our messages for everyone; returns the event to send to
+    /// the peer
+    pub fn delete_message(&self, peer_id: &str, message_id: &str) -> Result<MessageEvent> {
+        let message = self.own_message(peer_id, message_id)?;
+        self.create_event(peer_id, &message, MessageEventType::Delete, &[])
+    }
+
+    /// React to a message of the conversation, or withdraw our reaction with
+    /// `None`; returns the event to send to the peer
+    pub fn react_to_message(
+        &self,
+        peer_id: &str,
+        message_id: &str,
+        reaction: Option<&str>,
+    ) -> Result<MessageEvent> {
+        let reaction = reaction.unwrap_or_default();
+        if reaction.len() > MAX_REACTION_BYTES {
+            return Err(AppError::Validation(format!(
+                "Reaction must be at most {} bytes",
+                MAX_REACTION_BYTES
+            )));
+        }
+
+        let message = self.conversation_message(peer_id, message_id)?;
+        self.create_event(
+            peer_id,
+            &message,
+            MessageEventType::Reaction,
+            reaction.as_bytes(),
+        )
+    }
+
+    /// Process an incoming edit, delete or reaction. Returns whether the
+    /// message changed.
+    pub fn process_incoming_event(&self, event: &MessageEvent) -> Result<bool> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+
+        if event.recipient_peer_id != identity.peer_id {
+            return Err(AppError::Validation("Event not for us".to_string()));
+        }
+        if event.conversation_id != derive_conversation_id(&identity.peer_id, &event.sender_peer_id)
+        {
+            return Err(AppError::Validation(
+                "Event for another conversation".to_string(),
+            ));
+        }
+
+        // Verify signature
+        let sender_public_key = self
+            .contacts_service
+            .get_public_key(&event.sender_peer_id)?
+            .ok_or_else(|| AppError::NotFound("Sender not in contacts".to_string()))?;
+        let verifying_key = VerifyingKey::from_bytes(
+            sender_public_key
+                .as_slice()
+                .try_into()
+                .map_err(|_| AppError::Crypto("Invalid public key length".to_string()))?,
+        )
+        .map_err(|e| AppError::Crypto(format!("Invalid public key: {}", e)))?;
+
+        let signable = SignableMessageEvent {
+            event_id: event.event_id.clone(),
+            event_type: event.event_type.as_str().to_string(),
+            message_id: event.message_id.clone(),
+            conversation_id: event.conversation_id.clone(),
+            sender_peer_id: event.sender_peer_id.clone(),
+            recipient_peer_id: event.recipient_peer_id.clone(),
+            payload_encrypted: event.payload_encrypted.clone(),
+            nonce_counter: event.nonce_counter,
+            lamport_clock: event.lamport_clock,
+            timestamp: event.timestamp,
+        };
+        if !verify(&verifying_key, &signable, &event.signature)? {
+            return Err(AppError::Crypto("Invalid event signature".to_string()));
+        }
+
+        // Check for deduplication
+        if MessagesRepository::event_exists(&self.db, &event.event_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+        {
+            return Ok(false);
+        }
+
+        let message = MessagesRepository::get_by_message_id(&self.db, &event.message_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+            .filter(|m| m.conversation_id == event.conversation_id)
+            .ok_or_else(|| AppError::NotFound("Message not found".to_string()))?;
+        if event.event_type != MessageEventType::Reaction
+            && message.sender_peer_id != event.sender_peer_id
+        {
+            return Err(AppError::PermissionDenied(
+                "Only the sender can edit or delete a message".to_string(),
+            ));
+        }
+
+        // Check for replay only once the event can be applied, so an event
+        // that arrives ahead of its message can be delivered again later
+        if !self
+            .db
+            .check_and_record_nonce(
+                &event.conversation_id,
+                &event.sender_peer_id,
+                event.nonce_counter,
+            )
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+        {
+            return Err(AppError::Crypto("Replay attack detected".to_string()));
+        }
+
+        self.db
+            .update_lamport_clock(&event.sender_peer_id, event.lamport_clock as i64)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+
+        let changed = self.apply_event(&message, event.event_type, &signable)?;
+        self.record_event(event.event_type, &signable, &event.signature)?;
+        Ok(changed)
+    }
+
+    /// Send a file as an encrypted attachment
+    ///
+    /// The file is encrypted with a fresh key and kept until the peer has
+    /// fetched it; the message itself only carries the manifest.
+    pub fn send_attachment(
+        &self,
+        recipient_peer_id: &str,
+        file_name: &str,
+        mime_type: &str,
+        data: &[u8],
+    ) -> Result<OutgoingMessage> {
+        if data.is_empty() || data.len() > MAX_ATTACHMENT_BYTES {
+            return Err(AppError::Validation(format!(
+                "Attachment must be between 1 and {} bytes",
+                MAX_ATTACHMENT_BYTES
+            )));
+        }
+        let file_name = file_name.trim();
+        if file_name.is_empty() || file_name.contains(['/', '\\']) {
+            return Err(AppError::Validation("Invalid file name".to_string()));
+        }
+        if !mime_type.contains('/') {
+            return Err(AppError::Validation(format!(
+                "Invalid MIME type: {}",
+                mime_type
+            )));
+        }
+
+        let key = CryptoService::generate_symmetric_key();
+        let encrypted = CryptoService::encrypt_message(&key, data)?;
+        let blob_hash = BlobsRepository::put(&self.db, ENCRYPTED_BLOB_MIME_TYPE, &encrypted)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+
+        let manifest = AttachmentManifest {
+            blob_hash: blob_hash.clone(),
+            file_name: file_name.to_string(),
+            mime_type: mime_type.to_string(),
+            size: data.len() as u64,
+            encrypted_size: encrypted.len() as u64,
+            key: key.to_vec(),
+        };
+        let content =
+            serde_json::to_string(&manifest).map_err(|e| AppError::Serialization(e.to_string()))?;
+
+        let outgoing =
+            match self.send_message(recipient_peer_id, &content, ATTACHMENT_CONTENT_TYPE, None) {
+                Ok(outgoing) => outgoing,
+                Err(e) => {
+                    BlobsRepository::delete_if_unreferenced(&self.db, &blob_hash)
+                        .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+                    return Err(e);
+                }
+            };
+
+        MessagesRepository::add_attachment(
+            &self.db,
+            &MessageAttachment {
+                message_id: outgoing.message_id.clone(),
+                blob_hash,
+                size: encrypted.len() as i64,
+            },
+        )
+        .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+
+        Ok(outgoing)
+    }
+
+    /// Attachments from a peer whose file still has to be fetched
+    pub fn missing_attachments(&self, peer_id: &str) -> Result<Vec<MessageAttachment>> {
+        MessagesRepository::get_missing_attachments(&self.db, peer_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+
+    /// Serve a chunk of an encrypted attachment file to the peer it was sent to
+    pub fn attachment_chunk(
+        &self,
+        requester_peer_id: &str,
+        blob_hash: &str,
+        chunk_index: u32,
+    ) -> Result<Vec<u8>> {
+        if !MessagesRepository::is_attachment_sent_to(&self.db, blob_hash, requester_peer_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+        {
+            return Err(AppError::PermissionDenied(
+                "Attachment was not sent to this peer".to_string(),
+            ));
+        }
+
+        let blob = BlobsRepository::get(&self.db, blob_hash)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound("Attachment not found".to_string()))?;
+
+        let start = chunk_index as usize * ATTACHMENT_CHUNK_SIZE;
+        if start >= blob.data.len() {
+            return Err(AppError::Validation("Chunk out of range".to_string()));
+        }
+        let end = (start + ATTACHMENT_CHUNK_SIZE).min(blob.data.len());
+        Ok(blob.data[start..end].to_vec())
+    }
+
+    /// Store the fetched encrypted file of an incoming attachment
+    pub fn store_attachment(&self, message_id: &str, data: &[u8]) -> Result<()> {
+        let attachment = MessagesRepository::get_attachment(&self.db, message_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound("Attachment not found".to_string()))?;
+
+        if data.len() as i64 != attachment.size
+            || BlobsRepository::hash(data) != attachment.blob_hash
+        {
+            return Err(AppError::Crypto(
+                "Attachment does not match its hash".to_string(),
+            ));
+        }
+
+        BlobsRepository::put(&self.db, ENCRYPTED_BLOB_MIME_TYPE, data)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Get the decrypted file of an attachment in our conversation with a
+    /// peer; `None` until it has been fetched
+    pub fn get_attachment(&self, peer_id: &str, message_id: &str) -> Result<Option<Attachment>> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+        let message = self.conversation_message(peer_id, message_id)?;
+        let conv_key =
+            self.conversation_key(&identity.peer_id, peer_id, &message.conversation_id)?;
+        let manifest = Self::decrypt_manifest(&conv_key, &message)
+            .ok_or_else(|| AppError::NotFound("Message has no attachment".to_string()))?;
+
+        let Some(blob) = BlobsRepository::get(&self.db, &manifest.blob_hash)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+
+        let key = <[u8; 32]>::try_from(manifest.key.as_slice())
+            .map_err(|_| AppError::Crypto("Invalid attachment key".to_string()))?;
+        let data = CryptoService::decrypt_message(&key, &blob.data)?;
+
+        Ok(Some(Attachment {
+            file_name: manifest.file_name,
+            mime_type: manifest.mime_type,
+            data,
+        }))
+    }
+
+    /// Bring the in-memory search index of decrypted messages up to date.
+    /// Does nothing while locked. Messages that can't be decrypted are
+    /// indexed without text so they aren't retried on every search.
+    pub fn refresh_search_index(&self) -> Result<()> {
+        if !self.identity_service.is_unlocked() {
+            return Ok(());
+        }
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+
+        let stale = SearchRepository::stale_messages(&self.db)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+        if stale.is_empty() {
+            return Ok(());
+        }
+
+        let mut keys: HashMap<String, Option<[u8; 32]>> = HashMap::new();
+        let mut entries = Vec::with_capacity(stale.len());
+        for message in stale {
+            let peer_id = if message.sender_peer_id == identity.peer_id {
+                message.recipient_peer_id.clone()
+            } else {
+                message.sender_peer_id.clone()
+            };
+            let conv_key = *keys.entry(peer_id.clone()).or_insert_with(|| {
+                self.conversation_key(&identity.peer_id, &peer_id, &message.conversation_id)
+                    .ok()
+            });
+
+            // Attachments are found by their file name
+            let text = match conv_key {
+                Some(key) if message.content_type == ATTACHMENT_CONTENT_TYPE => {
+                    Self::decrypt_manifest(&key, &message)
+                        .map(|manifest| manifest.file_name)
+                        .unwrap_or_default()
+                }
+                Some(key) => CryptoService::decrypt_message_with_counter(
+                    &key,
+                    &message.content_encrypted,
+                    message.nonce_counter,
+                )
+                .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+                .unwrap_or_default(),
+                None => String::new(),
+            };
+
+            entries.push(MessageSearchEntry {
+                id: message.id,
+                peer_id,
+                nonce_counter: message.nonce_counter,
+                text,
+            });
+        }
+
+        SearchRepository::index_messages(&self.db, &entries)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+
+    /// Set how long messages of our conversation with a peer are kept, or
+    /// keep them forever with `None`; returns the update to send to the peer
+    pub fn set_retention(
+        &self,
+        peer_id: &str,
+        retention_secs: Option<u64>,
+    ) -> Result<RetentionUpdate> {
+        if let Some(secs) = retention_secs {
+            if !RETENTION_PERIODS_SECS.contains(&secs) {
+                return Err(AppError::Validation(format!(
+                    "Retention must be one of {:?} seconds",
+                    RETENTION_PERIODS_SECS
+                )));
+            }
+        }
+
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+        if !self
+            .permissions_service
+            .peer_has_capability(peer_id, Capability::Chat)?
+        {
+            return Err(AppError::PermissionDenied(
+                "No chat permission with this peer".to_string(),
+            ));
+        }
+
+        let lamport_clock =
+            self.db
+                .next_lamport_clock(&identity.peer_id)
+                .map_err(|e| AppError::DatabaseString(e.to_string()))? as u64;
+        let signable = SignableRetentionUpdate {
+            conversation_id: derive_conversation_id(&identity.peer_id, peer_id),
+            sender_peer_id: identity.peer_id.clone(),
+            recipient_peer_id: peer_id.to_string(),
+            retention_secs,
+            lamport_clock,
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        let signature = self.identity_service.sign(&signable)?;
+        self.store_retention(peer_id, &signable, &signature)?;
+
+        Ok(RetentionUpdate {
+            conversation_id: signable.conversation_id,
+            sender_peer_id: signable.sender_peer_id,
+            recipient_peer_id: signable.recipient_peer_id,
+            retention_secs: signable.retention_secs,
+            lamport_clock: signable.lamport_clock,
+            timestamp: signable.timestamp,
+            signature,
+        })
+    }
+
+    /// Process an incoming retention update. Returns whether the setting
+    /// changed; older updates are ignored.
+    pub fn process_incoming_retention(&self, update: &RetentionUpdate) -> Result<bool> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+
+        if update.recipient_peer_id != identity.peer_id {
+            return Err(AppError::Validation(
+                "Retention update not for us".to_string(),
+            ));
+        }
+        if update.conversation_id
+            != derive_conversation_id(&identity.peer_id, &update.sender_peer_id)
+        {
+            return Err(AppError::Validation(
+                "Retention update for another conversation".to_string(),
+            ));
+        }
+        if let Some(secs) = update.retention_secs {
+            if !RETENTION_PERIODS_SECS.contains(&secs) {
+                return Err(AppError::Validation(
+                    "Unsupported retention period".to_string(),
+                ));
+            }
+        }
+
+        // Verify signature
+        let sender_public_key = self
+            .contacts_service
+            .get_public_key(&update.sender_peer_id)?
+            .ok_or_else(|| AppError::NotFound("Sender not in contacts".to_string()))?;
+        let verifying_key = VerifyingKey::from_bytes(
+            sender_public_key
+                .as_slice()
+                .try_into()
+                .map_err(|_| AppError::Crypto("Invalid public key length".to_string()))?,
+        )
+        .map_err(|e| AppError::Crypto(format!("Invalid public key: {}", e)))?;
+
+        let signable = SignableRetentionUpdate {
+            conversation_id: update.conversation_id.clone(),
+            sender_peer_id: update.sender_peer_id.clone(),
+            recipient_peer_id: update.recipient_peer_id.clone(),
+            retention_secs: update.retention_secs,
+            lamport_clock: update.lamport_clock,
+            timestamp: update.timestamp,
+        };
+        if !verify(&verifying_key, &signable, &update.signature)? {
+            return Err(AppError::Crypto(
+                "Invalid retention update signature".to_string(),
+            ));
+        }
+
+        self.db
+            .update_lamport_clock(&update.sender_peer_id, update.lamport_clock as i64)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+
+        self.store_retention(&update.sender_peer_id, &signable, &update.signature)
+    }
+
+    /// Get the retention period of our conversation with a peer, in seconds;
+    /// `None` keeps messages forever
+    pub fn get_retention(&self, peer_id: &str) -> Result<Option<u64>> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+        let conversation_id = derive_conversation_id(&identity.peer_id, peer_id);
+
+        Ok(RetentionRepository::get(&self.db, &conversation_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+            .and_then(|retention| retention.retention_secs))
+    }
+
+    /// The retention update we last sent a peer, if ours is the current
+    /// setting, so it can be sent again when the peer may have missed it
+    pub fn own_retention_update(&self, peer_id: &str) -> Result<Option<RetentionUpdate>> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+        let conversation_id = derive_conversation_id(&identity.peer_id, peer_id);
+
+        Ok(RetentionRepository::get(&self.db, &conversation_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+            .filter(|retention| retention.set_by_peer_id == identity.peer_id)
+            .map(|retention| RetentionUpdate {
+                conversation_id: retention.conversation_id,
+                sender_peer_id: retention.set_by_peer_id,
+                recipient_peer_id: retention.peer_id,
+                retention_secs: retention.retention_secs,
+                lamport_clock: retention.lamport_clock as u64,
+                timestamp: retention.timestamp,
+                signature: retention.signature,
+            }))
+    }
+
+    /// Hard-delete every message that has expired at `now`, then the
+    /// attachment files no other message refers to
+    pub fn sweep_expired(&self, now: i64) -> Result<ExpiredMessages> {
+        let expired = RetentionRepository::delete_expired(&self.db, now)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+        for hash in &expired.blob_hashes {
+            BlobsRepository::delete_if_unreferenced(&self.db, hash)
+                .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+        }
+        Ok(expired)
+    }
+
+    /// Reclaim the space left by deleted messages, returning the pages released
+    pub fn vacuum(&self) -> Result<u64> {
+        self.db
+            .vacuum()
+            .map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+
+    /// Derive the key of our conversation with a contact
+    fn conversation_key(
+        &self,
+        our_peer_id: &str,
+        peer_id: &str,
+        conversation_id: &str,
+    ) -> Result<[u8; 32]> {
+        let x25519_public = self
+            .contacts_service
+            .get_x25519_public(peer_id)?
+            .ok_or_else(|| AppError::NotFound("Contact not found".to_string()))?;
+        let our_keys = self.identity_service.get_unlocked_keys()?;
+
+        let their_public = X25519Public::from(
+            <[u8; 32]>::try_from(x25519_public.as_slice())
+                .map_err(|_| AppError::Crypto("Invalid X25519 key".to_string()))?,
+        );
+        let shared_secret = CryptoService::x25519_dh(&our_keys.x25519_secret, &their_public);
+        Ok(CryptoService::derive_conversation_key(
+            &shared_secret,
+            conversation_id,
+            our_peer_id,
+            peer_id,
+        ))
+    }
+
+    /// Get a message of our conversation with a peer that hasn't been deleted
+    fn conversation_message(&self, peer_id: &str, message_id: &str) -> Result<Message> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+        let conversation_id = derive_conversation_id(&identity.peer_id, peer_id);
+
+        MessagesRepository::get_by_message_id(&self.db, message_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+            .filter(|m| m.conversation_id == conversation_id && m.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound("Message not found".to_string()))
+    }
+
+    /// Get a message we sent to a peer that hasn't been deleted
+    fn own_message(&self, peer_id: &str, message_id: &str) -> Result<Message> {
+        let message = self.conversation_message(peer_id, message_id)?;
+        if message.sender_peer_id == peer_id {
+            return Err(AppError::PermissionDenied(
+                "Only the sender can edit or delete a message".to_string(),
+            ));
+        }
+        Ok(message)
+    }
+
+    /// Store a signed retention setting if it is newer than ours
+    fn store_retention(
+        &self,
+        peer_id: &str,
+        signable: &SignableRetentionUpdate,
+        signature: &[u8],
+    ) -> Result<bool> {
+        RetentionRepository::upsert(
+            &self.db,
+            &ConversationRetention {
+                conversation_id: signable.conversation_id.clone(),
+                peer_id: peer_id.to_string(),
+                retention_secs: signable.retention_secs,
+                set_by_peer_id: signable.sender_peer_id.clone(),
+                lamport_clock: signable.lamport_clock as i64,
+                timestamp: signable.timestamp,
+                signature: signature.to_vec(),
+                updated_at: chrono::Utc::now().timestamp(),
+            },
+        )
+        .map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+
+    /// Sign an event on `message`, apply it locally and record it
+    fn create_event(
+        &self,
+        peer_id: &str,
+        message: &Message,
+        event_type: MessageEventType,
+        payload: &[u8],
+    ) -> Result<MessageEvent> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+
+        if !self
+            .permissions_service
+            .peer_has_capability(peer_id, Capability::Chat)?
+        {
+            return Err(AppError::PermissionDenied(
+                "No chat permission with this peer".to_string(),
+            ));
+        }
+
+        let conv_key =
+            self.conversation_key(&identity.peer_id, peer_id, &message.conversation_id)?;
+        let nonce_counter = self
+            .db
+            .next_send_counter(&message.conversation_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+        let payload_encrypted =
+            CryptoService::encrypt_message_with_counter(&conv_key, payload, nonce_counter)?;
+        let lamport_clock =
+            self.db
+                .next_lamport_clock(&identity.peer_id)
+                .map_err(|e| AppError::DatabaseString(e.to_string()))? as u64;
+
+        let signable = SignableMessageEvent {
+            event_id: Uuid::new_v4().to_string(),
+            event_type: event_type.as_str().to_string(),
+            message_id: message.message_id.clone(),
+            conversation_id: message.conversation_id.clone(),
+            sender_peer_id: identity.peer_id.clone(),
+            recipient_peer_id: peer_id.to_string(),
+            payload_encrypted,
+            nonce_counter,
+            lamport_clock,
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        let signature = self.identity_service.sign(&signable)?;
+
+        self.apply_event(message, event_type, &signable)?;
+        self.record_event(event_type, &signable, &signature)?;
+
+        Ok(MessageEvent {
+            event_id: signable.event_id,
+            event_type,
+            message_id: signable.message_id,
+            conversation_id: signable.conversation_id,
+            sender_peer_id: signable.sender_peer_id,
+            recipient_peer_id: signable.recipient_peer_id,
+            payload_encrypted: signable.payload_encrypted,
+            nonce_counter,
+            lamport_clock,
+            timestamp: signable.timestamp,
+            signature,
+        })
+    }
+
+    /// Materialize an event into the message it applies to
+    fn apply_event(
+        &self,
+        message: &Message,
+        event_type: MessageEventType,
+        event: &SignableMessageEvent,
+    ) -> Result<bool> {
+        let changed = match event_type {
+            MessageEventType::Edit => MessagesRepository::apply_edit(
+                &self.db,
+                &event.message_id,
+                &event.payload_encrypted,
+                event.nonce_counter,
+                event.lamport_clock as i64,
+                event.timestamp,
+            ),
+            MessageEventType::Delete => {
+                let attachment = MessagesRepository::get_attachment(&self.db, &event.message_id)
+                    .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+                let deleted =
+                    MessagesRepository::apply_delete(&self.db, &event.message_id, event.timestamp)
+                        .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+                if let Some(attachment) = attachment {
+                    BlobsRepository::delete_if_unreferenced(&self.db, &attachment.blob_hash)
+                        .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+                }
+                Ok(deleted)
+            }
+            MessageEventType::Reaction if message.deleted_at.is_some() => Ok(false),
+            MessageEventType::Reaction => MessagesRepository::set_reaction(
+                &self.db,
+                &MessageReaction {
+                    message_id: event.message_id.clone(),
+                    reactor_peer_id: event.sender_peer_id.clone(),
+                    reaction_encrypted: event.payload_encrypted.clone(),
+                    nonce_counter: event.nonce_counter,
+                    lamport_clock: event.lamport_clock as i64,
+                    updated_at: event.timestamp,
+                },
+            ),
+        };
+        changed.map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+
+    /// Record a signed event in the message event log
+    fn record_event(
+        &self,
+        event_type: MessageEventType,
+        event: &SignableMessageEvent,
+        signature: &[u8],
+    ) -> Result<()> {
+        let payload_cbor = event.signable_bytes()?;
+        MessagesRepository::record_message_event(
+            &self.db,
+            &event.event_id,
+            event_type.as_str(),
+            &event.message_id,
+            &event.conversation_id,
+            &event.sender_peer_id,
+            &event.recipient_peer_id,
+            event.lamport_clock as i64,
+            event.timestamp,
+            &payload_cbor,
+            signature,
+        )
+        .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Remember the file of an incoming attachment message so it gets fetched
+    fn record_incoming_attachment(&self, message: &MessageData) -> Result<()> {
+        let conv_key = self.conversation_key(
+            &message.recipient_peer_id,
+            &message.sender_peer_id,
+            &message.conversation_id,
+        )?;
+        let manifest = CryptoService::decrypt_message_with_counter(
+            &conv_key,
+            &message.content_encrypted,
+            message.nonce_counter,
+        )
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<AttachmentManifest>(&bytes).ok());
+
+        // Nonce and tag added by AES-GCM
+        let max_encrypted_size = (MAX_ATTACHMENT_BYTES + 28) as u64;
+        match manifest {
+            Some(m) if m.encrypted_size > 0 && m.encrypted_size <= max_encrypted_size => {
+                MessagesRepository::add_attachment(
+                    &self.db,
+                    &MessageAttachment {
+                        message_id: message.message_id.clone(),
+                        blob_hash: m.blob_hash,
+                        size: m.encrypted_size as i64,
+                    },
+                )
+                .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+            }
+            _ => tracing::warn!(
+                "Ignoring invalid attachment in message {}",
+                message.message_id
+            ),
+        }
+        Ok(())
+    }
+
+    /// Decrypt the manifest of an attachment message
+    fn decrypt_manifest(conv_key: &[u8; 32], message: &Message) -> Option<AttachmentManifest> {
+        if message.content_type != ATTACHMENT_CONTENT_TYPE {
+            return None;
+        }
+        let bytes = CryptoService::decrypt_message_with_counter(
+            conv_key,
+            &message.content_encrypted,
+            message.nonce_counter,
+        )
+        .ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CreateIdentityRequest;
+
+    /// One side of a conversation, with its own database
+    struct Peer {
+        peer_id: String,
+        identity_service: Arc<IdentityService>,
+        messaging: MessagingService,
+    }
+
+    fn create_peer(name: &str) -> Peer {
+        let db = Arc::new(Database::in_memory().unwrap());
+        let identity_service = Arc::new(IdentityService::new(db.clone()));
+        let info = identity_service
+            .create_identity(CreateIdentityRequest {
+                display_name: name.to_string(),
+                passphrase: "test-passphrase".to_string(),
+                bio: None,
+                passphrase_hint: None,
+            })
+            .unwrap();
+        let contacts_service = Arc::new(ContactsService::new(db.clone(), identity_service.clone()));
+        let permissions_service = Arc::new(PermissionsService::new(
+            db.clone(),
+            identity_service.clone(),
+        ));
+        let messaging = MessagingService::new(
+            db,
+            identity_service.clone(),
+            contacts_service,
+            permissions_service,
+        );
+        Peer {
+            peer_id: info.peer_id,
+            identity_service,
+            messaging,
+        }
+    }
+
+    /// Two peers that are each other's contacts with chat permission
+    fn create_pair() -> (Peer, Peer) {
+        let alice = create_peer("Alice");
+        let bob = create_peer("Bob");
+        for (peer, other) in [(&alice, &bob), (&bob, &alice)] {
+            let identity = other.identity_service.get_identity().unwrap().unwrap();
+            peer.messaging
+                .contacts_service
+                .add_contact(
+                    &identity.peer_id,
+                    &identity.public_key,
+                    &identity.x25519_public,
+                    &identity.display_name,
+                    None,
+                    None,
+                )
+                .unwrap();
+            peer.messaging
+                .permissions_service
+                .create_permission_grant(&other.peer_id, Capability::Chat, None)
+                .unwrap();
+        }
+        (alice, bob)
+    }
+
+    fn deliver(to: &Peer, message: &OutgoingMessage) -> Result<()> {
+        to.messaging.process_incoming_message(
+            &message.message_id,
+            &message.conversation_id,
+            &message.sender_peer_id,
+            &message.recipient_peer_id,
+            &message.content_encrypted,
+            &message.content_type,
+            message.reply_to.as_deref(),
+            message.nonce_counter,
+            message.lamport_clock,
+            message.timestamp,
+            &message.signature,
+        )
+    }
+
+    fn message(peer: &Peer, other: &Peer, message_id: &str) -> DecryptedMessage {
+        peer.messaging
+            .get_conversation_messages(&other.peer_id, 50, None)
+            .unwrap()
+            .into_iter()
+            .find(|m| m.message_id == message_id)
+            .unwrap()
+    }
+
+    /// Re-sign an event with `signer`'s key after changing it
+    fn resign(signer: &Peer, event: &mut MessageEvent) {
+        let signable = SignableMessageEvent {
+            event_id: event.event_id.clone(),
+            event_type: event.event_type.as_str().to_string(),
+            message_id: event.message_id.clone(),
+            conversation_id: event.conversation_id.clone(),
+            sender_peer_id: event.sender_peer_id.clone(),
+            recipient_peer_id: event.recipient_peer_id.clone(),
+            payload_encrypted: event.payload_encrypted.clone(),
+            nonce_counter: event.nonce_counter,
+            lamport_clock: event.lamport_clock,
+            timestamp: event.timestamp,
+        };
+        event.signature = signer.identity_service.sign(&signable).unwrap();
+    }
+
+    #[test]
+    fn test_only_the_author_can_edit_or_delete() {
+        let (alice, bob) = create_pair();
+        let sent = alice
+            .messaging
+            .send_message(&bob.peer_id, "hello", "text", None)
+            .unwrap();
+        deliver(&bob, &sent).unwrap();
+
+        // Bob can't edit or delete Alice's message locally
+        assert!(matches!(
+            bob.messaging
+                .edit_message(&alice.peer_id, &sent.message_id, "forged"),
+            Err(AppError::PermissionDenied(_))
+        ));
+        assert!(matches!(
+            bob.messaging
+                .delete_message(&alice.peer_id, &sent.message_id),
+            Err(AppError::PermissionDenied(_))
+        ));
+
+        // Nor by sending Alice a validly signed edit or delete of it
+        let reaction = bob
+            .messaging
+            .react_to_message(&alice.peer_id, &sent.message_id, Some("x"))
+            .unwrap();
+        for event_type in [MessageEventType::Edit, MessageEventType::Delete] {
+            let mut forged = reaction.clone();
+            forged.event_id = Uuid::new_v4().to_string();
+            forged.event_type = event_type;
+            resign(&bob, &mut forged);
+            assert!(matches!(
+                alice.messaging.process_incoming_event(&forged),
+                Err(AppError::PermissionDenied(_))
+            ));
+        }
+        assert_eq!(message(&alice, &bob, &sent.message_id).content, "hello");
+        assert!(message(&alice, &bob, &sent.message_id).deleted_at.is_none());
+
+        // The author can
+        let edit = alice
+            .messaging
+            .edit_message(&bob.peer_id, &sent.message_id, "hello again")
+            .unwrap();
+        assert!(bob.messaging.process_incoming_event(&edit).unwrap());
+        assert_eq!(
+            message(&bob, &alice, &sent.message_id).content,
+            "hello again"
+        );
+    }
+
+    #[test]
+    fn test_bad_signatures_are_rejected() {
+        let (alice, bob) = create_pair();
+
+        let sent = alice
+            .messaging
+            .send_message(&bob.peer_id, "hello", "text", None)
+            .unwrap();
+        let mut forged = sent.clone();
+        forged.signature[0] ^= 1;
+        assert!(matches!(deliver(&bob, &forged), Err(AppError::Crypto(_))));
+        assert!(bob
+            .messaging
+            .get_conversation_messages(&alice.peer_id, 50, None)
+            .unwrap()
+            .is_empty());
+
+        // The forgery didn't use up the real message's nonce
+        deliver(&bob, &sent).unwrap();
+        assert_eq!(message(&bob, &alice, &sent.message_id).content, "hello");
+
+        let edit = alice
+            .messaging
+            .edit_message(&bob.peer_id, &sent.message_id, "edited")
+            .unwrap();
+        let mut forged = edit.clone();
+        forged.payload_encrypted[0] ^= 1;
+        assert!(matches!(
+            bob.messaging.process_incoming_event(&forged),
+            Err(AppError::Crypto(_))
+        ));
+
+        // An event signed by someone other than its sender
+        let mut forged = edit.clone();
+        resign(&bob, &mut forged);
+        assert!(matches!(
+            bob.messaging.process_incoming_event(&forged),
+            Err(AppError::Crypto(_))
+        ));
+        assert_eq!(message(&bob, &alice, &sent.message_id).content, "hello");
+
+        assert!(bob.messaging.process_incoming_event(&edit).unwrap());
+        assert_eq!(message(&bob, &alice, &sent.message_id).content, "edited");
+    }
+
+    #[test]
+    fn test_reactions_are_idempotent() {
+        let (alice, bob) = create_pair();
+        let sent = bob
+            .messaging
+            .send_message(&alice.peer_id, "hello", "text", None)
+            .unwrap();
+        deliver(&alice, &sent).unwrap();
+
+        let add = alice
+            .messaging
+            .react_to_message(&bob.peer_id, &sent.message_id, Some("👍"))
+            .unwrap();
+        assert!(bob.messaging.process_incoming_event(&add).unwrap());
+        assert!(!bob.messaging.process_incoming_event(&add).unwrap());
+        assert_eq!(
+            message(&bob, &alice, &sent.message_id).reactions,
+            vec![(alice.peer_id.clone(), "👍".to_string())]
+        );
+
+        let remove = alice
+            .messaging
+            .react_to_message(&bob.peer_id, &sent.message_id, None)
+            .unwrap();
+        assert!(bob.messaging.process_incoming_event(&remove).unwrap());
+        assert!(!bob.messaging.process_incoming_event(&remove).unwrap());
+        assert!(message(&bob, &alice, &sent.message_id).reactions.is_empty());
+
+        // Removing again leaves no reaction behind
+        let remove_again = alice
+            .messaging
+            .react_to_message(&bob.peer_id, &sent.message_id, None)
+            .unwrap();
+        bob.messaging.process_incoming_event(&remove_again).unwrap();
+        assert!(message(&bob, &alice, &sent.message_id).reactions.is_empty());
+        assert!(message(&alice, &bob, &sent.message_id).reactions.is_empty());
+    }
+
+    #[test]
+    fn test_out_of_order_edits_and_deletes() {
+        let (alice, bob) = create_pair();
+        let sent = alice
+            .messaging
+            .send_message(&bob.peer_id, "v1", "text", None)
+            .unwrap();
+        let id = &sent.message_id;
+        let edit1 = alice
+            .messaging
+            .edit_message(&bob.peer_id, id, "v2")
+            .unwrap();
+        let edit2 = alice
+            .messaging
+            .edit_message(&bob.peer_id, id, "v3")
+            .unwrap();
+        let edit3 = alice
+            .messaging
+            .edit_message(&bob.peer_id, id, "v4")
+            .unwrap();
+        let delete = alice.messaging.delete_message(&bob.peer_id, id).unwrap();
+
+        // An event ahead of its message fails but can be delivered again
+        assert!(matches!(
+            bob.messaging.process_incoming_event(&edit2),
+            Err(AppError::NotFound(_))
+        ));
+        deliver(&bob, &sent).unwrap();
+        assert!(bob.messaging.process_incoming_event(&edit2).unwrap());
+        assert_eq!(message(&bob, &alice, id).content, "v3");
+
+        // An older edit arriving late doesn't win
+        assert!(!bob.messaging.process_incoming_event(&edit1).unwrap());
+        assert_eq!(message(&bob, &alice, id).content, "v3");
+
+        // Nor does an edit arriving after the delete
+        assert!(bob.messaging.process_incoming_event(&delete).unwrap());
+        assert!(!bob.messaging.process_incoming_event(&edit3).unwrap());
+        let deleted = message(&bob, &alice, id);
+        assert!(deleted.deleted_at.is_some());
+        assert_eq!(deleted.content, "");
+    }
+
+    #[test]
+    fn test_attachment_chunks_reassemble() {
+        let (alice, bob) = create_pair();
+        let data: Vec<u8> = (0..ATTACHMENT_CHUNK_SIZE * 2 + 1000)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let sent = alice
+            .messaging
+            .send_attachment(&bob.peer_id, "notes.bin", "application/octet-stream", &data)
+            .unwrap();
+        deliver(&bob, &sent).unwrap();
+
+        let missing = bob.messaging.missing_attachments(&alice.peer_id).unwrap();
+        assert_eq!(missing.len(), 1);
+        let attachment = &missing[0];
+        let size = attachment.size as usize;
+        assert!(bob
+            .messaging
+            .get_attachment(&alice.peer_id, &sent.message_id)
+            .unwrap()
+            .is_none());
+
+        // Fetch it the way the network does, chunk by chunk
+        let mut received = Vec::new();
+        let mut chunk_index = 0;
+        while received.len() < size {
+            let chunk = alice
+                .messaging
+                .attachment_chunk(&bob.peer_id, &attachment.blob_hash, chunk_index)
+                .unwrap();
+            assert!(is_valid_attachment_chunk(size, received.len(), chunk.len()));
+            received.extend_from_slice(&chunk);
+            chunk_index += 1;
+        }
+        assert_eq!(chunk_index, 3);
+        assert!(alice
+            .messaging
+            .attachment_chunk(&bob.peer_id, &attachment.blob_hash, chunk_index)
+            .is_err());
+        assert!(matches!(
+            alice
+                .messaging
+                .attachment_chunk("12D3KooWStranger", &attachment.blob_hash, 0),
+            Err(AppError::PermissionDenied(_))
+        ));
+
+        bob.messaging
+            .store_attachment(&sent.message_id, &received)
+            .unwrap();
+        let file = bob
+            .messaging
+            .get_attachment(&alice.peer_id, &sent.message_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(file.file_name, "notes.bin");
+        assert_eq!(file.data, data);
+        assert!(bob
+            .messaging
+            .missing_attachments(&alice.peer_id)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_bad_attachment_data_is_rejected() {
+        let (alice, bob) = create_pair();
+        let data = vec![7u8; 1000];
+        let sent = alice
+            .messaging
+            .send_attachment(&bob.peer_id, "notes.bin", "application/octet-stream", &data)
+            .unwrap();
+        deliver(&bob, &sent).unwrap();
+        let attachment = &bob.messaging.missing_attachments(&alice.peer_id).unwrap()[0];
+        let encrypted = alice
+            .messaging
+            .attachment_chunk(&bob.peer_id, &attachment.blob_hash, 0)
+            .unwrap();
+
+        let mut corrupted = encrypted.clone();
+        corrupted[0] ^= 1;
+        assert!(matches!(
+            bob.messaging.store_attachment(&sent.message_id, &corrupted),
+            Err(AppError::Crypto(_))
+        ));
+
+        let mut oversized = encrypted.clone();
+        oversized.push(0);
+        assert!(matches!(
+            bob.messaging.store_attachment(&sent.message_id, &oversized),
+            Err(AppError::Crypto(_))
+        ));
+        assert!(bob
+            .messaging
+            .get_attachment(&alice.peer_id, &sent.message_id)
+            .unwrap()
+            .is_none());
+
+        bob.messaging
+            .store_attachment(&sent.message_id, &encrypted)
+            .unwrap();
+        assert_eq!(
+            bob.messaging
+                .get_attachment(&alice.peer_id, &sent.message_id)
+                .unwrap()
+                .unwrap()
+                .data,
+            data
+        );
+    }
+
+    #[test]
+    fn test_attachment_chunk_sizes() {
+        let size = ATTACHMENT_CHUNK_SIZE + 100;
+        assert!(is_valid_attachment_chunk(size, 0, ATTACHMENT_CHUNK_SIZE));
+        assert!(is_valid_attachment_chunk(size, ATTACHMENT_CHUNK_SIZE, 100));
+
+        // Oversized, past the end, short before the last chunk, or empty
+        assert!(!is_valid_attachment_chunk(
+            size,
+            0,
+            ATTACHMENT_CHUNK_SIZE + 1
+        ));
+        assert!(!is_valid_attachment_chunk(size, ATTACHMENT_CHUNK_SIZE, 101));
+        assert!(!is_valid_attachment_chunk(size, 0, 100));
+        assert!(!is_valid_attachment_chunk(size, 0, 0));
+        assert!(!is_valid_attachment_chunk(size, size, 1));
+    }
 }
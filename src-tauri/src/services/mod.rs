@@ -3,12 +3,16 @@ pub mod board_service;
 pub mod calling_service;
 pub mod contacts_service;
 pub mod content_sync_service;
+pub mod conversation_export;
 pub mod crypto_service;
 pub mod feed_service;
 pub mod identity_service;
 pub mod messaging_service;
 pub mod permissions_service;
 pub mod posts_service;
+pub mod safety_number;
+pub mod scheduler_service;
+pub mod search_service;
 pub mod signing;
 
 pub use accounts_service::AccountsService;
@@ -16,18 +20,29 @@ pub use board_service::BoardService;
 pub use calling_service::{
     Call, CallState, CallingService, OutgoingAnswer, OutgoingHangup, OutgoingIce, OutgoingOffer,
 };
-pub use contacts_service::ContactsService;
+pub use contacts_service::{ContactUpdate, ContactsService};
 pub use content_sync_service::{
     ContentSyncService, OutgoingManifestRequest, OutgoingManifestResponse,
 };
+pub use conversation_export::{
+    ConversationExport, ConversationTranscript, ExportFormat, ExportedMessage, SignatureStatus,
+};
 pub use crypto_service::CryptoService;
-pub use feed_service::{FeedItem, FeedService};
-pub use identity_service::IdentityService;
-pub use messaging_service::{DecryptedMessage, MessagingService, OutgoingMessage};
+pub use feed_service::{FeedCursor, FeedItem, FeedPage, FeedService};
+pub use identity_service::{IdentityService, MAX_AVATAR_BYTES};
+pub use messaging_service::{
+    is_valid_attachment_chunk, Attachment, AttachmentInfo, DecryptedMessage, MessageCursor,
+    MessagePage, MessagingService, OutgoingMessage, ATTACHMENT_CHUNK_SIZE, MAX_ATTACHMENT_BYTES,
+};
 pub use permissions_service::{
     PermissionGrantMessage, PermissionRequestMessage, PermissionRevokeMessage, PermissionsService,
 };
 pub use posts_service::{OutgoingPost, OutgoingPostDelete, OutgoingPostUpdate, PostsService};
+pub use safety_number::{SafetyNumber, SafetyNumberParty};
+pub use scheduler_service::{
+    Publication, PublishOutcome, ScheduledEntry, SchedulerService, MAX_SCHEDULE_AHEAD_SECS,
+};
+pub use search_service::{SearchService, MAX_SEARCH_RESULTS};
 pub use signing::{
     sign,
     verify,
@@ -39,6 +54,8 @@ pub use signing::{
     SignableBoardPost,
     SignableBoardPostDelete,
     SignableBoardPostsRequest,
+    SignableBoardSubscription,
+    SignableBoardThreadRequest,
     // Content sync
     SignableContentManifestRequest,
     SignableContentManifestResponse,
@@ -48,6 +65,7 @@ pub use signing::{
     SignableIdentityRequest,
     SignableIdentityResponse,
     SignableMessageAck,
+    SignableMessageEvent,
     SignablePeerRegistration,
     SignablePermissionGrant,
     // Permission messages
@@ -57,6 +75,7 @@ pub use signing::{
     SignablePost,
     SignablePostDelete,
     SignablePostUpdate,
+    SignableRetentionUpdate,
     SignableSignalingAnswer,
     SignableSignalingHangup,
     SignableSignalingIce,
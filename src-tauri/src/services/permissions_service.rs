@@ -76,7 +76,7 @@ impl PermissionsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let request_id = Uuid::new_v4().to_string();
         let lamport_clock =
@@ -117,7 +117,7 @@ impl PermissionsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let grant_id = Uuid::new_v4().to_string();
         let lamport_clock =
@@ -198,7 +198,7 @@ impl PermissionsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         // Verify we issued this grant
         let grant = PermissionsRepository::get_by_grant_id(&self.db, grant_id)
@@ -429,7 +429,7 @@ impl PermissionsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         PermissionsRepository::has_capability(
             &self.db,
@@ -445,7 +445,7 @@ impl PermissionsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         PermissionsRepository::has_capability(
             &self.db,
@@ -461,7 +461,7 @@ impl PermissionsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         PermissionsRepository::get_permissions_by_issuer(&self.db, &identity.peer_id)
             .map_err(|e| AppError::DatabaseString(e.to_string()))
@@ -472,7 +472,7 @@ impl PermissionsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         PermissionsRepository::get_permissions_for_subject(&self.db, &identity.peer_id)
             .map_err(|e| AppError::DatabaseString(e.to_string()))
@@ -483,7 +483,7 @@ impl PermissionsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         PermissionsRepository::get_chat_contacts(&self.db, &identity.peer_id)
             .map_err(|e| AppError::DatabaseString(e.to_string()))
@@ -498,7 +498,7 @@ impl PermissionsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         PermissionsRepository::get_capability_grant(
             &self.db,
@@ -4,10 +4,12 @@ use ed25519_dalek::VerifyingKey;
 use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::db::repositories::{LikeData, LikeSummary, LikesRepository};
 use crate::db::{
     Capability, Database, Post, PostData, PostMedia, PostMediaData, PostVisibility, PostsRepository,
 };
 use crate::error::{AppError, Result};
+use crate::services::signing::SignablePostLike;
 use crate::services::{
     verify, ContactsService, IdentityService, PermissionsService, Signable, SignablePost,
     SignablePostDelete, SignablePostUpdate,
@@ -82,7 +84,7 @@ impl PostsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         let post_id = Uuid::new_v4().to_string();
         let lamport_clock =
@@ -158,7 +160,7 @@ impl PostsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         // Verify we own the post
         let post = PostsRepository::get_by_post_id(&self.db, post_id)
@@ -229,7 +231,7 @@ impl PostsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         // Verify we own the post
         let post = PostsRepository::get_by_post_id(&self.db, post_id)
@@ -305,7 +307,7 @@ impl PostsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         // Verify we own the post
         let post = PostsRepository::get_by_post_id(&self.db, post_id)
@@ -353,7 +355,7 @@ impl PostsService {
         let _identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         PostsRepository::get_local_posts(&self.db, limit, before_timestamp)
             .map_err(|e| AppError::DatabaseString(e.to_string()))
@@ -370,7 +372,7 @@ impl PostsService {
         let identity = self
             .identity_service
             .get_identity()?
-            .ok_or_else(|| AppError::NotFound("No identity".to_string()))?;
+            .ok_or(AppError::IdentityNotFound)?;
 
         if author_peer_id != identity.peer_id {
             // Check if they've granted us WallRead permission
@@ -388,6 +390,85 @@ impl PostsService {
             .map_err(|e| AppError::DatabaseString(e.to_string()))
     }
 
+    /// Like a post as the local identity. Returns the updated like summary.
+    pub fn like_post(&self, post_id: &str) -> Result<LikeSummary> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+
+        let timestamp = chrono::Utc::now().timestamp();
+        let signable = SignablePostLike {
+            post_id: post_id.to_string(),
+            liker_peer_id: identity.peer_id.clone(),
+            reaction_type: "like".to_string(),
+            timestamp,
+        };
+        let signature = self.identity_service.sign(&signable)?;
+
+        let data = LikeData {
+            post_id: post_id.to_string(),
+            liker_peer_id: identity.peer_id.clone(),
+            reaction_type: "like".to_string(),
+            timestamp,
+            signature,
+        };
+        LikesRepository::add_like(&self.db, &data)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+
+        LikesRepository::get_like_summary(&self.db, post_id, &identity.peer_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+
+    /// Remove the local identity's like from a post. Returns the updated like summary.
+    pub fn unlike_post(&self, post_id: &str) -> Result<LikeSummary> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+
+        LikesRepository::remove_like(&self.db, post_id, &identity.peer_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+
+        LikesRepository::get_like_summary(&self.db, post_id, &identity.peer_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+
+    /// Like summaries for posts, from the local identity's point of view
+    pub fn get_like_summaries(&self, post_ids: &[String]) -> Result<Vec<LikeSummary>> {
+        let current_peer_id = self
+            .identity_service
+            .get_identity()?
+            .map(|i| i.peer_id)
+            .unwrap_or_default();
+
+        LikesRepository::get_like_summaries_batch(&self.db, post_ids, &current_peer_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+
+    /// Like summary for a single post
+    pub fn get_like_summary(&self, post_id: &str) -> Result<LikeSummary> {
+        let current_peer_id = self
+            .identity_service
+            .get_identity()?
+            .map(|i| i.peer_id)
+            .unwrap_or_default();
+
+        LikesRepository::get_like_summary(&self.db, post_id, &current_peer_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+
+    /// Post IDs the local identity has liked
+    pub fn get_my_liked_posts(&self) -> Result<Vec<String>> {
+        let identity = self
+            .identity_service
+            .get_identity()?
+            .ok_or(AppError::IdentityNotFound)?;
+
+        LikesRepository::get_liked_posts(&self.db, &identity.peer_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+
     /// Process an incoming post from the network
     #[allow(clippy::too_many_arguments)]
     pub fn process_incoming_post(
@@ -0,0 +1,196 @@
+//! Safety numbers for verifying contacts' keys out of band
+//!
+//! Both sides of a conversation derive the same number from the two peer IDs
+//! and their Ed25519 and X25519 public keys. Users compare it in person or
+//! over another channel (read out as digits or words, or by scanning a QR
+//! code), then mark the contact verified. If a verified contact's keys
+//! change, the number changes too and verification is dropped.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+
+/// Bump when the derivation changes; part of the hash input and the QR payload
+const VERSION: u8 = 1;
+
+/// Hash iterations per fingerprint, to slow down searches for colliding keys
+const ITERATIONS: usize = 5200;
+
+/// Digit groups per party; each group encodes five fingerprint bytes
+const GROUPS_PER_PARTY: usize = 6;
+
+/// Number of words in the word form
+const WORD_COUNT: usize = 12;
+
+/// Prefix of the QR payload. Uppercase, digits and `:` keep the payload in
+/// QR alphanumeric mode.
+const QR_PREFIX: &str = "HARBORSN";
+
+/// One side of the conversation
+pub struct SafetyNumberParty<'a> {
+    pub peer_id: &'a str,
+    pub public_key: &'a [u8],
+    pub x25519_public: &'a [u8],
+}
+
+/// A safety number in its display forms
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SafetyNumber {
+    /// Sixty digits in twelve groups of five, separated by spaces
+    pub digits: String,
+    /// A digest of the number as words, for reading aloud
+    pub words: Vec<String>,
+    /// Payload to show as a QR code; scanning the other side's code and
+    /// passing it to [`SafetyNumber::matches_qr`] verifies the contact
+    pub qr_payload: String,
+}
+
+impl SafetyNumber {
+    /// Safety number of the conversation between `a` and `b`, in either order
+    pub fn compute(a: &SafetyNumberParty, b: &SafetyNumberParty) -> Self {
+        let (first, second) = if a.peer_id <= b.peer_id {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let fingerprints = [fingerprint(first), fingerprint(second)];
+
+        let groups: Vec<String> = fingerprints
+            .iter()
+            .flat_map(|fp| fp.chunks(5).take(GROUPS_PER_PARTY))
+            .map(|chunk| {
+                let value = chunk.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+                format!("{:05}", value % 100_000)
+            })
+            .collect();
+        let digits = groups.join(" ");
+
+        let mut hasher = Sha256::new();
+        hasher.update(fingerprints[0]);
+        hasher.update(fingerprints[1]);
+        let words = hasher.finalize()[..WORD_COUNT]
+            .iter()
+            .map(|&b| WORDS[b as usize].to_string())
+            .collect();
+
+        Self {
+            qr_payload: format!("{}{}:{}", QR_PREFIX, VERSION, groups.concat()),
+            digits,
+            words,
+        }
+    }
+
+    /// Whether a scanned QR payload shows this same number
+    pub fn matches_qr(&self, scanned: &str) -> bool {
+        scanned.trim().eq_ignore_ascii_case(&self.qr_payload)
+    }
+}
+
+/// Iterated hash over one party's identity; the first 30 bytes are shown
+fn fingerprint(party: &SafetyNumberParty) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    hasher.update([VERSION]);
+    hasher.update(party.public_key);
+    hasher.update(party.x25519_public);
+    hasher.update(party.peer_id.as_bytes());
+    let mut hash = hasher.finalize();
+
+    for _ in 0..ITERATIONS {
+        let mut hasher = Sha512::new();
+        hasher.update(hash);
+        hasher.update(party.public_key);
+        hasher.update(party.x25519_public);
+        hash = hasher.finalize();
+    }
+    hash.into()
+}
+
+/// One word per byte value
+const WORDS: [&str; 256] = [
+    "acid", "acorn", "actor", "adobe", "agent", "alarm", "album", "alley", "amber", "angle",
+    "ankle", "apple", "apron", "arena", "arrow", "atlas", "attic", "audio", "award", "bacon",
+    "badge", "bagel", "baker", "bamboo", "banjo", "barn", "basil", "basin", "beach", "beard",
+    "beaver", "bench", "berry", "bison", "blade", "blanket", "bloom", "board", "bonus", "boots",
+    "bottle", "brain", "brass", "bread", "brick", "bridge", "broom", "brush", "bucket", "buffalo",
+    "bugle", "butter", "cabin", "cactus", "camel", "canal", "candle", "canoe", "canyon", "carpet",
+    "carrot", "castle", "cedar", "chalk", "cherry", "chess", "chimney", "cider", "cinema",
+    "circus", "clock", "cloud", "clover", "coast", "cobra", "comet", "coral", "cotton", "cougar",
+    "crane", "crater", "crayon", "cricket", "crown", "crystal", "cube", "curtain", "daisy",
+    "dancer", "delta", "desert", "diamond", "dinner", "dolphin", "donkey", "dragon", "drum",
+    "eagle", "easel", "echo", "elbow", "ember", "engine", "falcon", "feather", "fence", "ferry",
+    "fiddle", "finch", "flag", "flute", "forest", "fossil", "fountain", "fox", "galaxy", "garden",
+    "garlic", "geyser", "ginger", "glacier", "globe", "goose", "granite", "grape", "gravel",
+    "guitar", "hammer", "harbor", "harp", "hawk", "hazel", "helmet", "hermit", "heron", "hinge",
+    "honey", "horizon", "hornet", "igloo", "island", "ivory", "jacket", "jaguar", "jasmine",
+    "jelly", "jewel", "jigsaw", "jungle", "kayak", "kettle", "kitten", "koala", "ladder", "lagoon",
+    "lantern", "lemon", "leopard", "lily", "lizard", "lobster", "locket", "magnet", "mango",
+    "maple", "marble", "meadow", "melon", "meteor", "mirror", "mitten", "moose", "mosaic",
+    "muffin", "nectar", "needle", "nest", "nickel", "noodle", "oasis", "ocean", "olive", "onion",
+    "orbit", "orchid", "otter", "oyster", "paddle", "panda", "parrot", "peach", "pebble", "pepper",
+    "piano", "pickle", "pigeon", "pillow", "pine", "planet", "plum", "pocket", "pony", "poppy",
+    "prism", "pumpkin", "puzzle", "quartz", "quill", "rabbit", "radar", "radish", "raven", "reef",
+    "ribbon", "river", "robin", "rocket", "saddle", "salmon", "sandal", "satin", "scarf", "shadow",
+    "shell", "silver", "sketch", "sled", "snail", "spider", "sponge", "spruce", "squid", "stable",
+    "statue", "summit", "sunset", "swan", "table", "tango", "temple", "thistle", "thunder",
+    "tiger", "timber", "tomato", "topaz", "tortoise", "trumpet", "tulip", "tunnel", "turtle",
+    "umbrella", "valley", "velvet", "violin", "walnut",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alice() -> SafetyNumberParty<'static> {
+        SafetyNumberParty {
+            peer_id: "12D3KooWAlice",
+            public_key: &[1; 32],
+            x25519_public: &[2; 32],
+        }
+    }
+
+    fn bob() -> SafetyNumberParty<'static> {
+        SafetyNumberParty {
+            peer_id: "12D3KooWBob",
+            public_key: &[3; 32],
+            x25519_public: &[4; 32],
+        }
+    }
+
+    #[test]
+    fn test_both_sides_agree() {
+        let ours = SafetyNumber::compute(&alice(), &bob());
+        let theirs = SafetyNumber::compute(&bob(), &alice());
+        assert_eq!(ours, theirs);
+
+        let groups: Vec<&str> = ours.digits.split(' ').collect();
+        assert_eq!(groups.len(), 12);
+        assert!(groups
+            .iter()
+            .all(|g| g.len() == 5 && g.chars().all(|c| c.is_ascii_digit())));
+        assert_eq!(ours.words.len(), WORD_COUNT);
+        assert!(ours.matches_qr(&theirs.qr_payload));
+    }
+
+    #[test]
+    fn test_changed_key_changes_number() {
+        let before = SafetyNumber::compute(&alice(), &bob());
+        let swapped = SafetyNumberParty {
+            x25519_public: &[5; 32],
+            ..bob()
+        };
+        let after = SafetyNumber::compute(&alice(), &swapped);
+
+        assert_ne!(before.digits, after.digits);
+        assert_ne!(before.words, after.words);
+        assert!(!before.matches_qr(&after.qr_payload));
+    }
+
+    #[test]
+    fn test_words_are_distinct() {
+        let mut words = WORDS.to_vec();
+        words.sort_unstable();
+        words.dedup();
+        assert_eq!(words.len(), 256);
+    }
+}
@@ -0,0 +1,636 @@
+//! Scheduler service: posts and messages published at a later time
+//!
+//! Queued items hold only their content. The post or message is created and
+//! signed when it is published, so its Lamport clock orders it after
+//! everything authored before then.
+
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::db::{
+    Capability, Database, PostVisibility, ScheduleStatus, ScheduledItem, ScheduledKind,
+    ScheduledRepository,
+};
+use crate::error::{AppError, Result};
+use crate::services::{
+    CryptoService, IdentityService, MessagingService, OutgoingMessage, OutgoingPost,
+    PermissionsService, PostsService,
+};
+
+/// Furthest ahead an item can be scheduled
+pub const MAX_SCHEDULE_AHEAD_SECS: i64 = 365 * 24 * 60 * 60;
+
+/// Most items published per check
+const DUE_BATCH_SIZE: i64 = 50;
+
+/// Purpose of the local key queued content is encrypted with
+const SCHEDULE_KEY_PURPOSE: &str = "scheduled-items";
+
+/// A scheduled item with its content decrypted, for the UI and API
+#[derive(Debug, Clone)]
+pub struct ScheduledEntry {
+    pub schedule_id: String,
+    pub kind: ScheduledKind,
+    pub recipient_peer_id: Option<String>,
+    pub content_type: String,
+    /// None once published or canceled, or for a post without text
+    pub content: Option<String>,
+    pub visibility: Option<String>,
+    pub reply_to_message_id: Option<String>,
+    pub publish_at: i64,
+    pub status: ScheduleStatus,
+    pub created_at: i64,
+    pub published_at: Option<i64>,
+    pub published_id: Option<String>,
+    pub error: Option<String>,
+}
+
+/// What publishing a scheduled item produced
+#[derive(Debug, Clone)]
+pub enum Publication {
+    /// Stored locally; contacts fetch it on their next sync
+    Post(OutgoingPost),
+    /// Stored locally and ready to be sent to the recipient
+    Message(OutgoingMessage),
+}
+
+/// Outcome of publishing one due item
+#[derive(Debug)]
+pub struct PublishOutcome {
+    pub schedule_id: String,
+    pub kind: ScheduledKind,
+    pub result: Result<Publication>,
+}
+
+/// Service for queueing posts and messages
+pub struct SchedulerService {
+    db: Arc<Database>,
+    identity_service: Arc<IdentityService>,
+    permissions_service: Arc<PermissionsService>,
+    posts_service: Arc<PostsService>,
+    messaging_service: Arc<MessagingService>,
+}
+
+impl SchedulerService {
+    /// Create a new scheduler service
+    pub fn new(
+        db: Arc<Database>,
+        identity_service: Arc<IdentityService>,
+        permissions_service: Arc<PermissionsService>,
+        posts_service: Arc<PostsService>,
+        messaging_service: Arc<MessagingService>,
+    ) -> Self {
+        Self {
+            db,
+            identity_service,
+            permissions_service,
+            posts_service,
+            messaging_service,
+        }
+    }
+
+    /// Queue a post to be published at `publish_at`
+    pub fn schedule_post(
+        &self,
+        content_type: &str,
+        content_text: Option<&str>,
+        visibility: PostVisibility,
+        publish_at: i64,
+    ) -> Result<ScheduledEntry> {
+        Self::validate_publish_at(publish_at)?;
+
+        self.queue(ScheduledItem {
+            schedule_id: Uuid::new_v4().to_string(),
+            kind: ScheduledKind::Post,
+            recipient_peer_id: None,
+            content_type: content_type.to_string(),
+            content_encrypted: content_text
+                .map(|text| self.encrypt_content(text))
+                .transpose()?,
+            visibility: Some(visibility.to_string()),
+            reply_to_message_id: None,
+            publish_at,
+            status: ScheduleStatus::Pending,
+            created_at: chrono::Utc::now().timestamp(),
+            published_at: None,
+            published_id: None,
+            error: None,
+        })
+    }
+
+    /// Queue a message to a peer to be sent at `publish_at`
+    pub fn schedule_message(
+        &self,
+        recipient_peer_id: &str,
+        content: &str,
+        content_type: &str,
+        reply_to: Option<&str>,
+        publish_at: i64,
+    ) -> Result<ScheduledEntry> {
+        Self::validate_publish_at(publish_at)?;
+
+        // Checked again when sent, in case the permission is revoked meanwhile
+        if !self
+            .permissions_service
+            .peer_has_capability(recipient_peer_id, Capability::Chat)?
+        {
+            return Err(AppError::PermissionDenied(
+                "No chat permission with this peer".to_string(),
+            ));
+        }
+
+        self.queue(ScheduledItem {
+            schedule_id: Uuid::new_v4().to_string(),
+            kind: ScheduledKind::Message,
+            recipient_peer_id: Some(recipient_peer_id.to_string()),
+            content_type: content_type.to_string(),
+            content_encrypted: Some(self.encrypt_content(content)?),
+            visibility: None,
+            reply_to_message_id: reply_to.map(String::from),
+            publish_at,
+            status: ScheduleStatus::Pending,
+            created_at: chrono::Utc::now().timestamp(),
+            published_at: None,
+            published_id: None,
+            error: None,
+        })
+    }
+
+    /// List scheduled items, soonest first; only pending ones unless
+    /// `include_finished`
+    pub fn list(&self, include_finished: bool) -> Result<Vec<ScheduledEntry>> {
+        let key = self.local_key()?;
+        ScheduledRepository::list(&self.db, include_finished)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+            .into_iter()
+            .map(|item| Self::to_entry(&key, item))
+            .collect()
+    }
+
+    /// Cancel a pending item
+    pub fn cancel(&self, schedule_id: &str) -> Result<()> {
+        let item = ScheduledRepository::get(&self.db, schedule_id)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound("Scheduled item not found".to_string()))?;
+        if item.status != ScheduleStatus::Pending
+            || !ScheduledRepository::cancel(&self.db, schedule_id)
+                .map_err(|e| AppError::DatabaseString(e.to_string()))?
+        {
+            return Err(AppError::Validation(format!(
+                "Scheduled item is already {}",
+                item.status.as_str()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Fail items a previous run stopped in the middle of publishing.
+    /// Returns how many there were.
+    pub fn recover_interrupted(&self) -> Result<usize> {
+        ScheduledRepository::fail_interrupted(&self.db)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+
+    /// Publish every item due at `now`. Each item is claimed before it is
+    /// published, so it is published at most once. Does nothing while locked.
+    pub fn publish_due(&self, now: i64) -> Result<Vec<PublishOutcome>> {
+        if !self.identity_service.is_unlocked() {
+            return Ok(Vec::new());
+        }
+        let due = ScheduledRepository::due(&self.db, now, DUE_BATCH_SIZE)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+
+        let mut outcomes = Vec::with_capacity(due.len());
+        for item in due {
+            if !ScheduledRepository::claim(&self.db, &item.schedule_id)
+                .map_err(|e| AppError::DatabaseString(e.to_string()))?
+            {
+                continue;
+            }
+
+            let result = self.publish(&item);
+            match &result {
+                Ok(publication) => {
+                    let (published_id, published_at) = match publication {
+                        Publication::Post(post) => (&post.post_id, post.created_at),
+                        Publication::Message(message) => (&message.message_id, message.timestamp),
+                    };
+                    ScheduledRepository::mark_published(
+                        &self.db,
+                        &item.schedule_id,
+                        published_id,
+                        published_at,
+                    )
+                }
+                Err(e) => {
+                    ScheduledRepository::mark_failed(&self.db, &item.schedule_id, &e.to_string())
+                }
+            }
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+
+            outcomes.push(PublishOutcome {
+                schedule_id: item.schedule_id,
+                kind: item.kind,
+                result,
+            });
+        }
+        Ok(outcomes)
+    }
+
+    /// Create and sign the post or message of a claimed item
+    fn publish(&self, item: &ScheduledItem) -> Result<Publication> {
+        let key = self.local_key()?;
+        let content = Self::decrypt_content(&key, item.content_encrypted.as_deref())?;
+
+        match item.kind {
+            ScheduledKind::Post => {
+                let visibility = item
+                    .visibility
+                    .as_deref()
+                    .and_then(PostVisibility::from_str)
+                    .unwrap_or(PostVisibility::Contacts);
+                self.posts_service
+                    .create_post(&item.content_type, content.as_deref(), visibility)
+                    .map(Publication::Post)
+            }
+            ScheduledKind::Message => {
+                let recipient_peer_id = item
+                    .recipient_peer_id
+                    .as_deref()
+                    .ok_or_else(|| AppError::Validation("Message has no recipient".to_string()))?;
+                self.messaging_service
+                    .send_message(
+                        recipient_peer_id,
+                        content.as_deref().unwrap_or_default(),
+                        &item.content_type,
+                        item.reply_to_message_id.as_deref(),
+                    )
+                    .map(Publication::Message)
+            }
+        }
+    }
+
+    fn queue(&self, item: ScheduledItem) -> Result<ScheduledEntry> {
+        ScheduledRepository::insert(&self.db, &item)
+            .map_err(|e| AppError::DatabaseString(e.to_string()))?;
+        Self::to_entry(&self.local_key()?, item)
+    }
+
+    fn validate_publish_at(publish_at: i64) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        if publish_at <= now {
+            return Err(AppError::Validation(
+                "Publish time must be in the future".to_string(),
+            ));
+        }
+        if publish_at - now > MAX_SCHEDULE_AHEAD_SECS {
+            return Err(AppError::Validation(
+                "Publish time is more than a year ahead".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Key queued content is encrypted with; needs the unlocked identity
+    fn local_key(&self) -> Result<[u8; 32]> {
+        let keys = self.identity_service.get_unlocked_keys()?;
+        Ok(CryptoService::derive_local_key(
+            &keys.x25519_secret,
+            SCHEDULE_KEY_PURPOSE,
+        ))
+    }
+
+    fn encrypt_content(&self, content: &str) -> Result<Vec<u8>> {
+        CryptoService::encrypt_message(&self.local_key()?, content.as_bytes())
+    }
+
+    fn decrypt_content(key: &[u8; 32], encrypted: Option<&[u8]>) -> Result<Option<String>> {
+        encrypted
+            .map(|bytes| {
+                let plaintext = CryptoService::decrypt_message(key, bytes)?;
+                String::from_utf8(plaintext)
+                    .map_err(|_| AppError::Crypto("Scheduled content is not UTF-8".to_string()))
+            })
+            .transpose()
+    }
+
+    fn to_entry(key: &[u8; 32], item: ScheduledItem) -> Result<ScheduledEntry> {
+        let content = Self::decrypt_content(key, item.content_encrypted.as_deref())?;
+        Ok(ScheduledEntry {
+            schedule_id: item.schedule_id,
+            kind: item.kind,
+            recipient_peer_id: item.recipient_peer_id,
+            content_type: item.content_type,
+            content,
+            visibility: item.visibility,
+            reply_to_message_id: item.reply_to_message_id,
+            publish_at: item.publish_at,
+            status: item.status,
+            created_at: item.created_at,
+            published_at: item.published_at,
+            published_id: item.published_id,
+            error: item.error,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CreateIdentityRequest;
+    use crate::services::ContactsService;
+
+    struct Scheduler {
+        db: Arc<Database>,
+        identity_service: Arc<IdentityService>,
+        contacts_service: Arc<ContactsService>,
+        permissions_service: Arc<PermissionsService>,
+        scheduler: SchedulerService,
+    }
+
+    fn create_identity(db: &Arc<Database>, name: &str) -> Arc<IdentityService> {
+        let identity_service = Arc::new(IdentityService::new(db.clone()));
+        identity_service
+            .create_identity(CreateIdentityRequest {
+                display_name: name.to_string(),
+                passphrase: "test-passphrase".to_string(),
+                bio: None,
+                passphrase_hint: None,
+            })
+            .unwrap();
+        identity_service
+    }
+
+    fn create_scheduler() -> Scheduler {
+        let db = Arc::new(Database::in_memory().unwrap());
+        let identity_service = create_identity(&db, "Alice");
+        let contacts_service = Arc::new(ContactsService::new(db.clone(), identity_service.clone()));
+        let permissions_service = Arc::new(PermissionsService::new(
+            db.clone(),
+            identity_service.clone(),
+        ));
+        let posts_service = Arc::new(PostsService::new(
+            db.clone(),
+            identity_service.clone(),
+            contacts_service.clone(),
+            permissions_service.clone(),
+        ));
+        let messaging_service = Arc::new(MessagingService::new(
+            db.clone(),
+            identity_service.clone(),
+            contacts_service.clone(),
+            permissions_service.clone(),
+        ));
+        let scheduler = SchedulerService::new(
+            db.clone(),
+            identity_service.clone(),
+            permissions_service.clone(),
+            posts_service,
+            messaging_service,
+        );
+        Scheduler {
+            db,
+            identity_service,
+            contacts_service,
+            permissions_service,
+            scheduler,
+        }
+    }
+
+    /// Add a contact with its own identity; chat permission if `chat`
+    fn add_contact(s: &Scheduler, chat: bool) -> String {
+        let other = create_identity(&Arc::new(Database::in_memory().unwrap()), "Bob")
+            .get_identity()
+            .unwrap()
+            .unwrap();
+        s.contacts_service
+            .add_contact(
+                &other.peer_id,
+                &other.public_key,
+                &other.x25519_public,
+                &other.display_name,
+                None,
+                None,
+            )
+            .unwrap();
+        if chat {
+            s.permissions_service
+                .create_permission_grant(&other.peer_id, Capability::Chat, None)
+                .unwrap();
+        }
+        other.peer_id
+    }
+
+    fn now() -> i64 {
+        chrono::Utc::now().timestamp()
+    }
+
+    fn stored(s: &Scheduler, schedule_id: &str) -> ScheduledItem {
+        ScheduledRepository::get(&s.db, schedule_id)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_post_is_published_when_due() {
+        let s = create_scheduler();
+        let publish_at = now() + 60;
+        let entry = s
+            .scheduler
+            .schedule_post("text", Some("Later"), PostVisibility::Public, publish_at)
+            .unwrap();
+        assert_eq!(entry.content.as_deref(), Some("Later"));
+        assert_eq!(entry.status, ScheduleStatus::Pending);
+
+        // Not yet due
+        assert!(s.scheduler.publish_due(publish_at - 1).unwrap().is_empty());
+        assert_eq!(
+            stored(&s, &entry.schedule_id).status,
+            ScheduleStatus::Pending
+        );
+
+        let outcomes = s.scheduler.publish_due(publish_at).unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].schedule_id, entry.schedule_id);
+        let Ok(Publication::Post(post)) = &outcomes[0].result else {
+            panic!("Expected a published post");
+        };
+        assert_eq!(post.content_text.as_deref(), Some("Later"));
+        assert_eq!(post.visibility, PostVisibility::Public.to_string());
+
+        let item = stored(&s, &entry.schedule_id);
+        assert_eq!(item.status, ScheduleStatus::Published);
+        assert_eq!(item.published_id.as_deref(), Some(post.post_id.as_str()));
+
+        // Published once only
+        assert!(s.scheduler.publish_due(publish_at + 60).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_message_is_sent_when_due() {
+        let s = create_scheduler();
+        let bob = add_contact(&s, true);
+        let publish_at = now() + 60;
+        let entry = s
+            .scheduler
+            .schedule_message(&bob, "Hello later", "text", None, publish_at)
+            .unwrap();
+
+        let outcomes = s.scheduler.publish_due(publish_at).unwrap();
+        assert_eq!(outcomes.len(), 1);
+        let Ok(Publication::Message(message)) = &outcomes[0].result else {
+            panic!("Expected a sent message");
+        };
+        assert_eq!(message.recipient_peer_id, bob);
+        assert_eq!(
+            stored(&s, &entry.schedule_id).published_id.as_deref(),
+            Some(message.message_id.as_str())
+        );
+    }
+
+    #[test]
+    fn test_message_fails_when_permission_is_revoked() {
+        let s = create_scheduler();
+        // No chat permission: refused when queued
+        let stranger = add_contact(&s, false);
+        assert!(matches!(
+            s.scheduler
+                .schedule_message(&stranger, "Hi", "text", None, now() + 60),
+            Err(AppError::PermissionDenied(_))
+        ));
+
+        let bob = add_contact(&s, false);
+        let grant = s
+            .permissions_service
+            .create_permission_grant(&bob, Capability::Chat, None)
+            .unwrap();
+        let publish_at = now() + 60;
+        let entry = s
+            .scheduler
+            .schedule_message(&bob, "Hi", "text", None, publish_at)
+            .unwrap();
+        s.permissions_service
+            .revoke_permission(&grant.grant_id)
+            .unwrap();
+
+        let outcomes = s.scheduler.publish_due(publish_at).unwrap();
+        assert!(matches!(
+            outcomes[0].result,
+            Err(AppError::PermissionDenied(_))
+        ));
+        let item = stored(&s, &entry.schedule_id);
+        assert_eq!(item.status, ScheduleStatus::Failed);
+        assert!(item.error.is_some());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let s = create_scheduler();
+        let publish_at = now() + 60;
+        let entry = s
+            .scheduler
+            .schedule_post("text", Some("Never"), PostVisibility::Contacts, publish_at)
+            .unwrap();
+
+        s.scheduler.cancel(&entry.schedule_id).unwrap();
+        assert!(s.scheduler.publish_due(publish_at).unwrap().is_empty());
+        assert!(s.scheduler.list(false).unwrap().is_empty());
+        let all = s.scheduler.list(true).unwrap();
+        assert_eq!(all[0].status, ScheduleStatus::Canceled);
+
+        // Only pending items can be canceled
+        assert!(matches!(
+            s.scheduler.cancel(&entry.schedule_id),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            s.scheduler.cancel("missing"),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_overdue_items_are_recovered_on_startup() {
+        let s = create_scheduler();
+        let overdue = s
+            .scheduler
+            .schedule_post(
+                "text",
+                Some("Overdue"),
+                PostVisibility::Contacts,
+                now() + 60,
+            )
+            .unwrap();
+        let interrupted = s
+            .scheduler
+            .schedule_post(
+                "text",
+                Some("Interrupted"),
+                PostVisibility::Contacts,
+                now() + 60,
+            )
+            .unwrap();
+
+        // The app was closed past both publish times, in the middle of
+        // publishing the second
+        s.db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE scheduled_items SET publish_at = ?",
+                rusqlite::params![now() - 3600],
+            )
+        })
+        .unwrap();
+        assert!(ScheduledRepository::claim(&s.db, &interrupted.schedule_id).unwrap());
+
+        // Startup fails the interrupted item rather than risk publishing it twice
+        assert_eq!(s.scheduler.recover_interrupted().unwrap(), 1);
+        assert_eq!(
+            stored(&s, &interrupted.schedule_id).status,
+            ScheduleStatus::Failed
+        );
+
+        // The first check publishes the overdue item
+        let outcomes = s.scheduler.publish_due(now()).unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].schedule_id, overdue.schedule_id);
+        assert!(outcomes[0].result.is_ok());
+        assert_eq!(
+            stored(&s, &overdue.schedule_id).status,
+            ScheduleStatus::Published
+        );
+    }
+
+    #[test]
+    fn test_nothing_is_published_while_locked() {
+        let s = create_scheduler();
+        let publish_at = now() + 60;
+        let entry = s
+            .scheduler
+            .schedule_post("text", Some("Later"), PostVisibility::Contacts, publish_at)
+            .unwrap();
+
+        s.identity_service.lock();
+        assert!(s.scheduler.publish_due(publish_at).unwrap().is_empty());
+        assert_eq!(
+            stored(&s, &entry.schedule_id).status,
+            ScheduleStatus::Pending
+        );
+
+        s.identity_service.unlock("test-passphrase").unwrap();
+        assert_eq!(s.scheduler.publish_due(publish_at).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_past_and_far_future_times_are_rejected() {
+        let s = create_scheduler();
+        let now = now();
+        for publish_at in [now - 60, now, now + MAX_SCHEDULE_AHEAD_SECS + 60] {
+            assert!(matches!(
+                s.scheduler
+                    .schedule_post("text", Some("Hi"), PostVisibility::Contacts, publish_at),
+                Err(AppError::Validation(_))
+            ));
+        }
+        assert!(s.scheduler.list(true).unwrap().is_empty());
+    }
+}
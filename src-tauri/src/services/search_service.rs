@@ -0,0 +1,55 @@
+//! Search service: ranked full-text search over local content
+
+use std::sync::Arc;
+
+use crate::db::{Database, SearchFilters, SearchHit, SearchRepository};
+use crate::error::{AppError, Result};
+use crate::services::MessagingService;
+
+/// Most hits returned by one search
+pub const MAX_SEARCH_RESULTS: i64 = 100;
+
+/// Service for searching messages, posts, board posts and contacts
+pub struct SearchService {
+    db: Arc<Database>,
+    messaging_service: Arc<MessagingService>,
+}
+
+impl SearchService {
+    /// Create a new search service
+    pub fn new(db: Arc<Database>, messaging_service: Arc<MessagingService>) -> Self {
+        Self {
+            db,
+            messaging_service,
+        }
+    }
+
+    /// Search for every word of `query` as a prefix, best hits first.
+    /// Messages are only found while the identity is unlocked.
+    pub fn search(
+        &self,
+        query: &str,
+        filters: &SearchFilters,
+        limit: i64,
+    ) -> Result<Vec<SearchHit>> {
+        let expression = SearchRepository::match_expression(query)
+            .ok_or_else(|| AppError::Validation("Search query is empty".to_string()))?;
+        if let (Some(since), Some(until)) = (filters.since, filters.until) {
+            if since >= until {
+                return Err(AppError::Validation(
+                    "Search range must end after it starts".to_string(),
+                ));
+            }
+        }
+
+        self.messaging_service.refresh_search_index()?;
+
+        SearchRepository::search(
+            &self.db,
+            &expression,
+            filters,
+            limit.clamp(1, MAX_SEARCH_RESULTS),
+        )
+        .map_err(|e| AppError::DatabaseString(e.to_string()))
+    }
+}
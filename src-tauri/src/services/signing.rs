@@ -80,13 +80,14 @@ impl Signable for SignableIdentityRequest {}
 /// Signable version of IdentityResponse (excludes signature)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignableIdentityResponse {
+    pub version: u32,
     pub peer_id: String,
     pub public_key: Vec<u8>,
     pub x25519_public: Vec<u8>,
     pub display_name: String,
     pub avatar_hash: Option<String>,
     pub bio: Option<String>,
-    pub identity_version: String, // hash for caching
+    pub profile_version: i64,
     pub timestamp: i64,
 }
 
@@ -175,6 +176,39 @@ pub struct SignableMessageAck {
 
 impl Signable for SignableMessageAck {}
 
+/// Signable version of MessageEvent (excludes signature)
+///
+/// The payload is encrypted and nonce-counted like message content, so the
+/// same replay protection applies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignableMessageEvent {
+    pub event_id: String,
+    pub event_type: String, // "edit", "delete" or "reaction"
+    pub message_id: String,
+    pub conversation_id: String,
+    pub sender_peer_id: String,
+    pub recipient_peer_id: String,
+    pub payload_encrypted: Vec<u8>,
+    pub nonce_counter: u64,
+    pub lamport_clock: u64,
+    pub timestamp: i64,
+}
+
+impl Signable for SignableMessageEvent {}
+
+/// Signable version of a conversation retention update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignableRetentionUpdate {
+    pub conversation_id: String,
+    pub sender_peer_id: String,
+    pub recipient_peer_id: String,
+    pub retention_secs: Option<u64>,
+    pub lamport_clock: u64,
+    pub timestamp: i64,
+}
+
+impl Signable for SignableRetentionUpdate {}
+
 // ============================================================
 // POST MESSAGES
 // ============================================================
@@ -242,6 +276,9 @@ pub struct SignableBoardPost {
     pub content_text: Option<String>,
     pub lamport_clock: u64,
     pub created_at: i64,
+    /// Omitted when absent so top-level posts keep their original encoding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to_post_id: Option<String>,
 }
 
 impl Signable for SignableBoardPost {}
@@ -285,6 +322,26 @@ pub struct SignableBoardPostsRequest {
 
 impl Signable for SignableBoardPostsRequest {}
 
+/// Signable version of a board thread request (excludes signature)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignableBoardThreadRequest {
+    pub requester_peer_id: String,
+    pub root_post_id: String,
+    pub timestamp: i64,
+}
+
+impl Signable for SignableBoardThreadRequest {}
+
+/// Signable version of a board subscribe/unsubscribe request (excludes signature)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignableBoardSubscription {
+    pub requester_peer_id: String,
+    pub board_ids: Vec<String>,
+    pub timestamp: i64,
+}
+
+impl Signable for SignableBoardSubscription {}
+
 // ============================================================
 // SIGNALING (Voice Calls)
 // ============================================================
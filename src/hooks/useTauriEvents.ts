@@ -74,6 +74,48 @@ export function useTauriEvents() {
           refreshContacts();
           break;
 
+        case 'message_updated':
+        case 'attachment_received':
+        case 'retention_changed':
+          if (useMessagingStore.getState().activeConversation === event.peerId) {
+            useMessagingStore.getState().loadMessages(event.peerId);
+          }
+          break;
+
+        case 'messages_expired':
+          useMessagingStore.getState().loadConversations();
+          if (useMessagingStore.getState().activeConversation === event.peerId) {
+            useMessagingStore.getState().loadMessages(event.peerId);
+          }
+          break;
+
+        case 'scheduled_item_published':
+          console.log(`[Network] Published scheduled ${event.kind} ${event.scheduleId}`);
+          if (event.kind === 'message') {
+            useMessagingStore.getState().loadConversations();
+          }
+          break;
+
+        case 'scheduled_item_failed':
+          console.warn(
+            `[Network] Scheduled ${event.kind} ${event.scheduleId} failed: ${event.error}`,
+          );
+          toast.error(`A scheduled ${event.kind} could not be published: ${event.error}`);
+          break;
+
+        case 'typing_changed':
+          useMessagingStore.getState().setPeerTyping(event.peerId, event.typing);
+          break;
+
+        case 'presence_changed':
+          useNetworkStore.getState().setPeerPresence({
+            peerId: event.peerId,
+            status: event.status,
+            statusText: event.statusText,
+            updatedAt: Math.floor(Date.now() / 1000),
+          });
+          break;
+
         case 'listening_on':
           console.log(`[Network] Listening on: ${event.address}`);
           break;
@@ -92,6 +134,19 @@ export function useTauriEvents() {
           toast.success(`Added ${event.displayName} to contacts!`);
           break;
 
+        case 'contact_keys_changed':
+          console.warn(`[Network] Keys changed for verified contact ${event.peerId}`);
+          refreshContacts();
+          toast.error(
+            `${event.displayName}'s safety number changed. Verify it again before trusting them.`
+          );
+          break;
+
+        case 'contact_profile_updated':
+        case 'contact_avatar_updated':
+          refreshContacts();
+          break;
+
         case 'nat_status_changed':
           console.log(`[Network] NAT status changed: ${event.status}`);
           // Update NAT status in store
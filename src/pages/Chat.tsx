@@ -1,4 +1,4 @@
-import { useState, useRef, useEffect, KeyboardEvent } from 'react';
+import { useState, useRef, useEffect, useCallback, KeyboardEvent } from 'react';
 import { useNavigate } from 'react-router-dom';
 import toast from 'react-hot-toast';
 import {
@@ -13,6 +13,8 @@ import {
   XIcon,
 } from '../components/icons';
 import { useContactsStore, useMessagingStore } from '../stores';
+import { TYPING_REFRESH_MS } from '../stores/messaging';
+import * as networkService from '../services/network';
 
 // Conversation menu component
 function ConversationMenu({
@@ -168,6 +170,7 @@ export function ChatPage() {
     setActiveConversation,
     selectedConversationId,
     setSelectedConversation,
+    typingUntil,
   } = useMessagingStore();
 
   // Use store's selectedConversationId
@@ -193,6 +196,9 @@ export function ChatPage() {
   const messagesEndRef = useRef<HTMLDivElement>(null);
   const inputRef = useRef<HTMLTextAreaElement>(null);
   const searchInputRef = useRef<HTMLInputElement>(null);
+  // The contact we last told we are typing, and when
+  const typingSentRef = useRef<{ peerId: string; at: number } | null>(null);
+  const [now, setNow] = useState(() => Date.now());
 
   // Load real contacts and conversations on mount
   useEffect(() => {
@@ -242,6 +248,41 @@ export function ChatPage() {
   // Get messages for current conversation
   const currentMessages = selectedConv ? realMessages[selectedConv.peerId] || [] : [];
 
+  // Typing indicator for the selected contact, re-rendered when it expires
+  const typingExpiresAt = selectedConv ? typingUntil[selectedConv.peerId] : undefined;
+  const isPeerTyping = typingExpiresAt !== undefined && typingExpiresAt > now;
+  useEffect(() => {
+    if (typingExpiresAt === undefined) return;
+    const timer = setTimeout(() => setNow(Date.now()), Math.max(0, typingExpiresAt - Date.now()));
+    return () => clearTimeout(timer);
+  }, [typingExpiresAt]);
+
+  // Tell the contact we stopped typing, if we told them we started
+  const stopTyping = useCallback(() => {
+    const sent = typingSentRef.current;
+    if (!sent) return;
+    typingSentRef.current = null;
+    networkService.setTyping(sent.peerId, false).catch(() => {});
+  }, []);
+
+  // Leaving a conversation stops typing to that contact
+  useEffect(() => stopTyping, [selectedConv?.peerId, stopTyping]);
+
+  // Send a typing signal, repeated while the user keeps typing
+  const handleInputChange = (value: string) => {
+    setMessageInput(value);
+    if (!selectedConv?.isReal) return;
+    if (!value.trim()) {
+      stopTyping();
+      return;
+    }
+    const sent = typingSentRef.current;
+    const at = Date.now();
+    if (sent?.peerId === selectedConv.peerId && at - sent.at < TYPING_REFRESH_MS) return;
+    typingSentRef.current = { peerId: selectedConv.peerId, at };
+    networkService.setTyping(selectedConv.peerId, true).catch(() => {});
+  };
+
   // Calculate search results
   const searchResults = messageSearchQuery.trim()
     ? currentMessages
@@ -307,6 +348,7 @@ export function ChatPage() {
 
     const content = messageInput.trim();
     setMessageInput('');
+    stopTyping();
     inputRef.current?.focus();
 
     try {
@@ -594,9 +636,11 @@ export function ChatPage() {
               }}
             >
               {selectedConv!.isReal
-                ? selectedConv!.online
-                  ? 'Online'
-                  : 'Offline'
+                ? isPeerTyping
+                  ? 'typing…'
+                  : selectedConv!.online
+                    ? 'Online'
+                    : 'Offline'
                 : selectedConv!.online
                   ? 'Online - will reply automatically'
                   : 'Offline'}
@@ -781,7 +825,7 @@ export function ChatPage() {
             ref={inputRef}
             placeholder="Type a message..."
             value={messageInput}
-            onChange={(e) => setMessageInput(e.target.value)}
+            onChange={(e) => handleInputChange(e.target.value)}
             onKeyDown={handleKeyDown}
             rows={1}
             className="flex-1 px-4 py-3 rounded-lg text-sm resize-none max-h-32"
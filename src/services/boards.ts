@@ -38,12 +38,43 @@ export const boardsService = {
     });
   },
 
-  /** Submit a post to a board */
-  async submitBoardPost(relayPeerId: string, boardId: string, contentText: string): Promise<void> {
+  /** Get top-level posts (thread roots) for a board */
+  async getBoardThreads(
+    relayPeerId: string,
+    boardId: string,
+    limit?: number,
+    beforeTimestamp?: number,
+  ): Promise<BoardPost[]> {
+    return invoke<BoardPost[]>('get_board_threads', {
+      relayPeerId,
+      boardId,
+      limit,
+      beforeTimestamp,
+    });
+  },
+
+  /** Get a thread (root post and replies, oldest first) */
+  async getBoardThread(relayPeerId: string, rootPostId: string): Promise<BoardPost[]> {
+    return invoke<BoardPost[]>('get_board_thread', { relayPeerId, rootPostId });
+  },
+
+  /** Fetch a thread from the relay */
+  async syncBoardThread(relayPeerId: string, rootPostId: string): Promise<void> {
+    return invoke<void>('sync_board_thread', { relayPeerId, rootPostId });
+  },
+
+  /** Submit a post to a board, or a reply when replyToPostId is given */
+  async submitBoardPost(
+    relayPeerId: string,
+    boardId: string,
+    contentText: string,
+    replyToPostId?: string,
+  ): Promise<void> {
     return invoke<void>('submit_board_post', {
       relayPeerId,
       boardId,
       contentText,
+      replyToPostId,
     });
   },
 
@@ -56,4 +87,14 @@ export const boardsService = {
   async syncBoard(relayPeerId: string, boardId: string): Promise<void> {
     return invoke<void>('sync_board', { relayPeerId, boardId });
   },
+
+  /** Subscribe to pushed updates for boards on a relay */
+  async subscribeBoards(relayPeerId: string, boardIds: string[]): Promise<void> {
+    return invoke<void>('subscribe_boards', { relayPeerId, boardIds });
+  },
+
+  /** Stop pushed updates for boards on a relay (all boards if boardIds is empty) */
+  async unsubscribeBoards(relayPeerId: string, boardIds: string[]): Promise<void> {
+    return invoke<void>('unsubscribe_boards', { relayPeerId, boardIds });
+  },
 };
@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { Contact, ContactData } from '../types';
+import type { Contact, ContactData, SafetyNumber } from '../types';
 
 /** Contacts service - wraps Tauri commands */
 export const contactsService = {
@@ -58,6 +58,31 @@ export const contactsService = {
     return invoke<boolean>('is_contact_blocked', { peerId });
   },
 
+  /** Get a contact's avatar image as a data URL, once fetched from them */
+  async getAvatar(peerId: string): Promise<string | null> {
+    return invoke<string | null>('get_contact_avatar', { peerId });
+  },
+
+  /** Get the safety number for verifying a contact */
+  async getSafetyNumber(peerId: string): Promise<SafetyNumber> {
+    return invoke<SafetyNumber>('get_safety_number', { peerId });
+  },
+
+  /** Mark a contact verified after comparing safety numbers */
+  async markVerified(peerId: string): Promise<void> {
+    return invoke<void>('mark_contact_verified', { peerId });
+  },
+
+  /** Verify a contact from their scanned QR payload; false on mismatch */
+  async verifyScanned(peerId: string, scanned: string): Promise<boolean> {
+    return invoke<boolean>('verify_contact_safety_number', { peerId, scanned });
+  },
+
+  /** Drop a contact back to unverified */
+  async clearVerification(peerId: string): Promise<void> {
+    return invoke<void>('clear_contact_verification', { peerId });
+  },
+
   /** Request identity exchange with a peer (adds them as a contact) */
   async requestPeerIdentity(peerId: string): Promise<void> {
     return invoke<void>('request_peer_identity', { peerId });
@@ -43,6 +43,21 @@ export const identityService = {
     return invoke('update_bio', { bio });
   },
 
+  /** Set the avatar image; returns its content hash */
+  async setAvatar(data: Uint8Array, mimeType: string): Promise<string> {
+    return invoke<string>('set_avatar', { data: Array.from(data), mimeType });
+  },
+
+  /** Remove the avatar image */
+  async clearAvatar(): Promise<void> {
+    return invoke('clear_avatar');
+  },
+
+  /** Get the avatar image as a data URL */
+  async getAvatar(): Promise<string | null> {
+    return invoke<string | null>('get_avatar');
+  },
+
   /** Update passphrase hint */
   async updatePassphraseHint(hint: string | null): Promise<void> {
     return invoke('update_passphrase_hint', { hint });
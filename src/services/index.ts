@@ -6,5 +6,7 @@ export { permissionsService } from './permissions';
 export { messagingService } from './messaging';
 export { postsService } from './posts';
 export { feedService } from './feed';
+export { searchService } from './search';
+export { scheduledService } from './scheduled';
 export { callingService } from './calling';
 export * as loggingService from './logging';
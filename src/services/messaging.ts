@@ -1,5 +1,13 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { Message, Conversation, SendMessageResult } from '../types';
+import type {
+  AttachmentFile,
+  Message,
+  MessagePage,
+  Conversation,
+  ExportFormat,
+  RetentionPeriod,
+  SendMessageResult,
+} from '../types';
 
 /** Messaging service - wraps Tauri commands */
 export const messagingService = {
@@ -18,6 +26,41 @@ export const messagingService = {
     });
   },
 
+  /** Send a file as an encrypted attachment */
+  async sendAttachment(
+    peerId: string,
+    fileName: string,
+    mimeType: string,
+    data: Uint8Array,
+  ): Promise<SendMessageResult> {
+    return invoke<SendMessageResult>('send_attachment', {
+      peerId,
+      fileName,
+      mimeType,
+      data: Array.from(data),
+    });
+  },
+
+  /** Get an attachment; null until it has been fetched from the sender */
+  async getAttachment(peerId: string, messageId: string): Promise<AttachmentFile | null> {
+    return invoke<AttachmentFile | null>('get_attachment', { peerId, messageId });
+  },
+
+  /** Edit one of our messages */
+  async editMessage(peerId: string, messageId: string, content: string): Promise<void> {
+    return invoke('edit_message', { peerId, messageId, content });
+  },
+
+  /** Delete one of our messages for everyone */
+  async deleteMessage(peerId: string, messageId: string): Promise<void> {
+    return invoke('delete_message', { peerId, messageId });
+  },
+
+  /** React to a message; null withdraws our reaction */
+  async reactToMessage(peerId: string, messageId: string, reaction: string | null): Promise<void> {
+    return invoke('react_to_message', { peerId, messageId, reaction });
+  },
+
   /** Get messages for a conversation */
   async getMessages(peerId: string, limit?: number, beforeTimestamp?: number): Promise<Message[]> {
     return invoke<Message[]>('get_messages', {
@@ -27,6 +70,26 @@ export const messagingService = {
     });
   },
 
+  /** Get one page of a conversation, paging back from the newest message */
+  async getMessagePage(peerId: string, limit?: number, cursor?: string): Promise<MessagePage> {
+    return invoke<MessagePage>('get_message_page', { peerId, limit, cursor });
+  },
+
+  /** Export a conversation to the downloads folder; returns the saved file's path */
+  async exportConversation(peerId: string, format: ExportFormat): Promise<string> {
+    return invoke<string>('export_conversation', { peerId, format });
+  },
+
+  /** Set how long both peers keep the conversation's messages */
+  async setConversationRetention(peerId: string, retentionSecs: RetentionPeriod): Promise<void> {
+    return invoke('set_conversation_retention', { peerId, retentionSecs });
+  },
+
+  /** Get how long the conversation's messages are kept */
+  async getConversationRetention(peerId: string): Promise<RetentionPeriod> {
+    return invoke<RetentionPeriod>('get_conversation_retention', { peerId });
+  },
+
   /** Get all conversations */
   async getConversations(): Promise<Conversation[]> {
     return invoke<Conversation[]>('get_conversations');
@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { PeerInfo, NetworkStats } from '../types';
+import type { PeerInfo, NetworkStats, PeerPresence, PresenceStatus } from '../types';
 
 /** Start the P2P network (requires unlocked identity) */
 export async function startNetwork(): Promise<void> {
@@ -11,6 +11,11 @@ export async function stopNetwork(): Promise<void> {
   return invoke('stop_network');
 }
 
+/** Restart the P2P network */
+export async function restartNetwork(): Promise<void> {
+  return invoke('restart_network');
+}
+
 /** Check if the network is running */
 export async function isNetworkRunning(): Promise<boolean> {
   return invoke('is_network_running');
@@ -66,6 +71,21 @@ export async function getShareableAddresses(): Promise<string[]> {
   return invoke<string[]>('get_shareable_addresses');
 }
 
+/** Tell a contact we started or stopped typing (repeat while typing continues) */
+export async function setTyping(peerId: string, typing: boolean): Promise<void> {
+  return invoke<void>('set_typing', { peerId, typing });
+}
+
+/** Set our presence, shared with peers that may read our wall */
+export async function setPresence(status: PresenceStatus, statusText?: string): Promise<void> {
+  return invoke<void>('set_presence', { status, statusText: statusText ?? null });
+}
+
+/** Get the presence of connected peers that share it with us */
+export async function getPresence(): Promise<PeerPresence[]> {
+  return invoke<PeerPresence[]>('get_presence');
+}
+
 /** Get a shareable contact string that includes everything needed to add as contact */
 export async function getShareableContactString(): Promise<string> {
   return invoke<string>('get_shareable_contact_string');
@@ -0,0 +1,47 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { PostVisibility, ScheduledItem } from '../types';
+
+/** Scheduled service - wraps the Tauri commands for scheduled posts and messages */
+export const scheduledService = {
+  /** Publish a post at `publishAt` (unix seconds); it is signed when published */
+  async schedulePost(
+    contentType: string,
+    contentText: string | undefined,
+    visibility: PostVisibility,
+    publishAt: number,
+  ): Promise<ScheduledItem> {
+    return invoke<ScheduledItem>('schedule_post', {
+      contentType,
+      contentText,
+      visibility,
+      publishAt,
+    });
+  },
+
+  /** Send a message at `publishAt` (unix seconds); it is signed when sent */
+  async scheduleMessage(
+    peerId: string,
+    content: string,
+    publishAt: number,
+    contentType?: string,
+    replyTo?: string,
+  ): Promise<ScheduledItem> {
+    return invoke<ScheduledItem>('schedule_message', {
+      peerId,
+      content,
+      contentType,
+      replyTo,
+      publishAt,
+    });
+  },
+
+  /** Scheduled items, soonest first; only pending ones unless `includeFinished` */
+  async getScheduledItems(includeFinished = false): Promise<ScheduledItem[]> {
+    return invoke<ScheduledItem[]>('get_scheduled_items', { includeFinished });
+  },
+
+  /** Cancel a pending item */
+  async cancelScheduledItem(scheduleId: string): Promise<void> {
+    return invoke('cancel_scheduled_item', { scheduleId });
+  },
+};
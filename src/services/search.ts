@@ -0,0 +1,10 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { SearchFilters, SearchHit } from '../types';
+
+/** Search service - wraps the Tauri full-text search command */
+export const searchService = {
+  /** Search messages, posts, board posts and contacts, best hits first */
+  async search(query: string, filters: SearchFilters = {}, limit?: number): Promise<SearchHit[]> {
+    return invoke<SearchHit[]>('search', { query, ...filters, limit });
+  },
+};
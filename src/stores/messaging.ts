@@ -2,12 +2,19 @@ import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
 import type { Message, Conversation, SendMessageResult } from '../types';
 
+/** Typing indicators not refreshed within this long are shown as stopped */
+export const TYPING_TIMEOUT_MS = 6000;
+
+/** How often we repeat our typing signal while the user keeps typing */
+export const TYPING_REFRESH_MS = 3000;
+
 interface MessagingState {
   // State
   conversations: Conversation[];
   messages: Record<string, Message[]>; // keyed by peerId
   activeConversation: string | null;
   selectedConversationId: string | null; // UI state for selected conversation (includes mock)
+  typingUntil: Record<string, number>; // peerId -> ms timestamp the typing indicator expires
   isLoading: boolean;
   error: string | null;
 
@@ -24,6 +31,7 @@ interface MessagingState {
   clearConversationSelection: () => void;
   handleIncomingMessage: (message: Message) => void;
   markConversationRead: (peerId: string) => Promise<void>;
+  setPeerTyping: (peerId: string, typing: boolean) => void;
 }
 
 export const useMessagingStore = create<MessagingState>((set, get) => ({
@@ -32,6 +40,7 @@ export const useMessagingStore = create<MessagingState>((set, get) => ({
   messages: {},
   activeConversation: null,
   selectedConversationId: null,
+  typingUntil: {},
   isLoading: false,
   error: null,
 
@@ -88,6 +97,10 @@ export const useMessagingStore = create<MessagingState>((set, get) => ({
         readAt: null,
         status: 'sent',
         isOutgoing: true,
+        editedAt: null,
+        deletedAt: null,
+        reactions: [],
+        attachment: null,
       };
 
       set((state) => ({
@@ -150,4 +163,17 @@ export const useMessagingStore = create<MessagingState>((set, get) => ({
       console.error('Failed to mark conversation read:', error);
     }
   },
+
+  // Show or clear a contact's typing indicator (called by event handler)
+  setPeerTyping: (peerId: string, typing: boolean) => {
+    set((state) => {
+      const next = { ...state.typingUntil };
+      if (typing) {
+        next[peerId] = Date.now() + TYPING_TIMEOUT_MS;
+      } else {
+        delete next[peerId];
+      }
+      return { typingUntil: next };
+    });
+  },
 }));
@@ -1,5 +1,5 @@
 import { create } from 'zustand';
-import type { PeerInfo, NetworkStats, ConnectionStatus, NatStatus } from '../types';
+import type { PeerInfo, NetworkStats, ConnectionStatus, NatStatus, PeerPresence } from '../types';
 import * as networkService from '../services/network';
 
 export type RelayStatus = 'disconnected' | 'connecting' | 'connected';
@@ -13,6 +13,8 @@ interface NetworkState {
   listeningAddresses: string[];
   shareableAddresses: string[];
   relayStatus: RelayStatus;
+  /** Presence of connected peers that share it with us, keyed by peerId */
+  presence: Record<string, PeerPresence>;
   error: string | null;
   isLoading: boolean;
 
@@ -32,6 +34,8 @@ interface NetworkState {
   // NAT status update (called by event handler)
   setNatStatus: (status: NatStatus) => void;
   addRelayAddress: (address: string) => void;
+  // Presence update (called by event handler)
+  setPeerPresence: (presence: PeerPresence) => void;
 }
 
 const initialStats: NetworkStats = {
@@ -53,6 +57,7 @@ export const useNetworkStore = create<NetworkState>((set, get) => ({
   listeningAddresses: [],
   shareableAddresses: [],
   relayStatus: 'disconnected',
+  presence: {},
   error: null,
   isLoading: false,
 
@@ -89,6 +94,7 @@ export const useNetworkStore = create<NetworkState>((set, get) => ({
         listeningAddresses: [],
         shareableAddresses: [],
         relayStatus: 'disconnected',
+        presence: {},
         isLoading: false,
       });
     } catch (error) {
@@ -243,4 +249,17 @@ export const useNetworkStore = create<NetworkState>((set, get) => ({
       };
     });
   },
+
+  // Record a peer's presence, forgetting peers that went offline (called by event handler)
+  setPeerPresence: (presence: PeerPresence) => {
+    set((state) => {
+      const next = { ...state.presence };
+      if (presence.status === 'offline') {
+        delete next[presence.peerId];
+      } else {
+        next[presence.peerId] = presence;
+      }
+      return { presence: next };
+    });
+  },
 }));
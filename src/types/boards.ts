@@ -27,4 +27,7 @@ export interface BoardPost {
   contentText: string | null;
   lamportClock: number;
   createdAt: number;
+  replyToPostId: string | null;
+  threadRootPostId: string | null;
+  replyCount: number;
 }
@@ -8,7 +8,7 @@ export interface Contact {
   avatarHash: string | null;
   bio: string | null;
   isBlocked: boolean;
-  trustLevel: number;
+  trustLevel: number; // 0 = unverified, 1 = verified
   lastSeenAt: number | null;
   addedAt: number;
   updatedAt: number;
@@ -23,3 +23,10 @@ export interface ContactData {
   avatarHash?: string | null;
   bio?: string | null;
 }
+
+/** Safety number for verifying a contact's keys out of band */
+export interface SafetyNumber {
+  digits: string; // twelve groups of five digits
+  words: string[];
+  qrPayload: string;
+}
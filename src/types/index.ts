@@ -8,3 +8,5 @@ export * from './messaging';
 export * from './posts';
 export * from './feed';
 export * from './calling';
+export * from './search';
+export * from './scheduled';
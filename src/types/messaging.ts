@@ -12,6 +12,46 @@ export interface Message {
   readAt: number | null;
   status: MessageStatus;
   isOutgoing: boolean;
+  editedAt: number | null;
+  /** Set when the sender deleted the message for everyone; content is empty */
+  deletedAt: number | null;
+  reactions: Reaction[];
+  attachment: AttachmentMeta | null;
+}
+
+/** A page of a conversation, oldest message first */
+export interface MessagePage {
+  messages: Message[];
+  /** Pass as `cursor` to get the page before this one; null on the first page */
+  nextCursor: string | null;
+}
+
+/** Format of a conversation export */
+export type ExportFormat = 'json' | 'markdown' | 'mbox';
+
+/** Seconds a conversation's messages are kept; null keeps them forever */
+export type RetentionPeriod = 3600 | 86400 | 604800 | 2592000 | null;
+
+/** A peer's reaction to a message */
+export interface Reaction {
+  peerId: string;
+  reaction: string;
+}
+
+/** Metadata of a message attachment; the message content is its file name */
+export interface AttachmentMeta {
+  fileName: string;
+  mimeType: string;
+  size: number;
+  /** Whether the file has been fetched from the sender */
+  downloaded: boolean;
+}
+
+/** A decrypted attachment */
+export interface AttachmentFile {
+  fileName: string;
+  mimeType: string;
+  dataUrl: string;
 }
 
 /** Message delivery status */
@@ -1,3 +1,6 @@
+import type { RetentionPeriod } from './messaging';
+import type { ScheduledKind } from './scheduled';
+
 /** Network connection status */
 export type ConnectionStatus = 'disconnected' | 'connecting' | 'connected';
 
@@ -28,6 +31,17 @@ export interface NetworkStats {
   externalAddresses: string[];
 }
 
+/** Presence a peer shares with the peers it lets read its wall */
+export type PresenceStatus = 'online' | 'away' | 'do_not_disturb' | 'offline';
+
+/** Last presence received from a connected peer */
+export interface PeerPresence {
+  peerId: string;
+  status: PresenceStatus;
+  statusText: string | null;
+  updatedAt: number;
+}
+
 /** Network events emitted by the backend */
 export type NetworkEvent =
   | { type: 'peer_discovered'; peerId: string }
@@ -37,8 +51,19 @@ export type NetworkEvent =
   | { type: 'external_address_discovered'; address: string }
   | { type: 'listening_on'; address: string }
   | { type: 'message_received'; peerId: string; protocol: string; payload: number[] }
+  | { type: 'message_updated'; peerId: string; messageId: string }
+  | { type: 'attachment_received'; peerId: string; messageId: string }
+  | { type: 'retention_changed'; peerId: string; retentionSecs: RetentionPeriod }
+  | { type: 'messages_expired'; peerId: string; messageIds: string[] }
+  | { type: 'scheduled_item_published'; scheduleId: string; kind: ScheduledKind; publishedId: string }
+  | { type: 'scheduled_item_failed'; scheduleId: string; kind: ScheduledKind; error: string }
+  | { type: 'typing_changed'; peerId: string; typing: boolean }
+  | { type: 'presence_changed'; peerId: string; status: PresenceStatus; statusText: string | null }
   | { type: 'status_changed'; status: ConnectionStatus }
   | { type: 'contact_added'; peerId: string; displayName: string }
+  | { type: 'contact_keys_changed'; peerId: string; displayName: string }
+  | { type: 'contact_profile_updated'; peerId: string; displayName: string; avatarHash: string | null }
+  | { type: 'contact_avatar_updated'; peerId: string; avatarHash: string }
   | { type: 'nat_status_changed'; status: NatStatus }
   | { type: 'relay_connected'; relayAddress: string }
   | { type: 'hole_punch_succeeded'; peerId: string }
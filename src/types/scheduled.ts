@@ -0,0 +1,27 @@
+/** What a scheduled item publishes */
+export type ScheduledKind = 'post' | 'message';
+
+/** Where a scheduled item is in its life */
+export type ScheduleStatus = 'pending' | 'publishing' | 'published' | 'failed' | 'canceled';
+
+/** A post or message queued to be published later */
+export interface ScheduledItem {
+  scheduleId: string;
+  kind: ScheduledKind;
+  /** Messages only */
+  recipientPeerId: string | null;
+  contentType: string;
+  /** Cleared once the item is published or canceled */
+  content: string | null;
+  /** Posts only */
+  visibility: string | null;
+  replyToMessageId: string | null;
+  /** Unix seconds */
+  publishAt: number;
+  status: ScheduleStatus;
+  createdAt: number;
+  publishedAt: number | null;
+  /** ID of the post or message once published */
+  publishedId: string | null;
+  error: string | null;
+}
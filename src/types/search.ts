@@ -0,0 +1,27 @@
+/** What a search hit is */
+export type SearchKind = 'message' | 'wall_post' | 'feed_post' | 'board_post' | 'contact';
+
+/** Narrows a search; every field is optional */
+export interface SearchFilters {
+  /** Kinds to search; all if empty or absent */
+  kinds?: SearchKind[];
+  /** Only hits by, with, or about this peer */
+  peerId?: string;
+  since?: number;
+  until?: number;
+}
+
+/** A ranked search hit */
+export interface SearchHit {
+  kind: SearchKind;
+  /** Message, post or board post ID, or the contact's peer ID */
+  itemId: string;
+  peerId: string;
+  relayPeerId: string | null;
+  boardId: string | null;
+  createdAt: number;
+  /** Matched terms are wrapped in <mark> tags; everything else is plain text */
+  snippet: string;
+  /** BM25 rank; lower is better */
+  rank: number;
+}
@@ -22,6 +22,13 @@ export type ErrorCode =
   | 'NETWORK_CONNECTION_FAILED'
   | 'NETWORK_PEER_UNREACHABLE'
   | 'NETWORK_TIMEOUT'
+  | 'NETWORK_NOT_RUNNING'
+  | 'RATE_LIMITED'
+  | 'QUOTA_EXCEEDED'
+  | 'PAYLOAD_TOO_LARGE'
+  | 'RELAY_OVERLOADED'
+  | 'BANNED'
+  | 'VERIFICATION_REQUIRED'
   | 'INTERNAL_ERROR';
 
 export interface ErrorResponse {
@@ -75,6 +82,9 @@ export class HarborError extends Error {
       'NETWORK_TIMEOUT',
       'NETWORK_CONNECTION_FAILED',
       'NETWORK_PEER_UNREACHABLE',
+      'NETWORK_NOT_RUNNING',
+      'RATE_LIMITED',
+      'RELAY_OVERLOADED',
       'IDENTITY_LOCKED',
       'IDENTITY_INVALID_PASSPHRASE',
       'VALIDATION_ERROR',